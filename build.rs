@@ -0,0 +1,66 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    grpc::generate();
+}
+
+/// Generates the `zeroclaw.v1.Messaging` service glue. Message types are
+/// hand-written in `src/channels/grpc.rs`, so no `protoc` is required.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    const TYPES: &str = "crate::channels::grpc::proto";
+
+    fn method(
+        name: &str,
+        route: &str,
+        input: &str,
+        output: &str,
+    ) -> tonic_build::manual::MethodBuilder {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("{TYPES}::{input}"))
+            .output_type(format!("{TYPES}::{output}"))
+            .codec_path("tonic_prost::ProstCodec")
+    }
+
+    pub fn generate() {
+        let service = Service::builder()
+            .name("Messaging")
+            .package("zeroclaw.v1")
+            .method(
+                method(
+                    "send_message",
+                    "SendMessage",
+                    "SendMessageRequest",
+                    "SendMessageResponse",
+                )
+                .build(),
+            )
+            .method(
+                method(
+                    "stream_inbound",
+                    "StreamInbound",
+                    "StreamInboundRequest",
+                    "InboundMessage",
+                )
+                .server_streaming()
+                .build(),
+            )
+            .method(
+                method(
+                    "list_channels",
+                    "ListChannels",
+                    "ListChannelsRequest",
+                    "ListChannelsResponse",
+                )
+                .build(),
+            )
+            .build();
+
+        Builder::new().build_client(false).compile(&[service]);
+    }
+}
@@ -1,12 +1,14 @@
 use crate::config::Config;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::observability::{self, Observer, ObserverEvent};
+use crate::providers::traits::StreamOptions;
 use crate::providers::{self, ChatMessage, Provider, ToolCall};
 use crate::runtime;
 use crate::security::SecurityPolicy;
 use crate::tools::{self, Tool};
 use crate::util::truncate_with_ellipsis;
 use anyhow::Result;
+use futures_util::StreamExt;
 use regex::{Regex, RegexSet};
 use std::fmt::Write;
 use std::io::Write as _;
@@ -529,8 +531,48 @@ pub(crate) async fn run_tool_call_loop(
     temperature: f64,
     silent: bool,
 ) -> Result<String> {
+    run_tool_call_loop_streaming(
+        provider,
+        history,
+        tools_registry,
+        observer,
+        provider_name,
+        model,
+        temperature,
+        silent,
+        None,
+    )
+    .await
+}
+
+/// Text of a reply while it is generated, see [`run_tool_call_loop_streaming`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ReplyDelta {
+    /// More text of the current model response
+    Text(String),
+    /// The response called tools; the text so far is replaced by the next one
+    Restart,
+}
+
+/// [`run_tool_call_loop`], sending the text of each model response to
+/// `deltas` as it streams in when the provider supports streaming. Tools
+/// are then called through prompt tags, which are never sent.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_tool_call_loop_streaming(
+    provider: &dyn Provider,
+    history: &mut Vec<ChatMessage>,
+    tools_registry: &[Box<dyn Tool>],
+    observer: &dyn Observer,
+    provider_name: &str,
+    model: &str,
+    temperature: f64,
+    silent: bool,
+    deltas: Option<&tokio::sync::mpsc::UnboundedSender<ReplyDelta>>,
+) -> Result<String> {
+    let deltas = deltas.filter(|_| provider.supports_streaming());
     // Build native tool definitions once if the provider supports them.
-    let use_native_tools = provider.supports_native_tools() && !tools_registry.is_empty();
+    let use_native_tools =
+        deltas.is_none() && provider.supports_native_tools() && !tools_registry.is_empty();
     let tool_definitions = if use_native_tools {
         tools_to_openai_format(tools_registry)
     } else {
@@ -598,10 +640,7 @@ pub(crate) async fn run_tool_call_loop(
                     }
                 }
             } else {
-                match provider
-                    .chat_with_history(history, model, temperature)
-                    .await
-                {
+                match chat_or_stream(provider, history, model, temperature, deltas).await {
                     Ok(resp) => {
                         observer.record_event(&ObserverEvent::LlmResponse {
                             provider: provider_name.to_string(),
@@ -642,6 +681,10 @@ pub(crate) async fn run_tool_call_loop(
             return Ok(display_text);
         }
 
+        if let Some(deltas) = deltas {
+            let _ = deltas.send(ReplyDelta::Restart);
+        }
+
         // Print any text the LLM produced alongside tool calls (unless silent)
         if !silent && !display_text.is_empty() {
             print!("{display_text}");
@@ -697,6 +740,65 @@ pub(crate) async fn run_tool_call_loop(
     anyhow::bail!("Agent exceeded maximum tool iterations ({MAX_TOOL_ITERATIONS})")
 }
 
+/// One model response, streamed to `deltas` when given.
+async fn chat_or_stream(
+    provider: &dyn Provider,
+    history: &[ChatMessage],
+    model: &str,
+    temperature: f64,
+    deltas: Option<&tokio::sync::mpsc::UnboundedSender<ReplyDelta>>,
+) -> Result<String> {
+    let Some(deltas) = deltas else {
+        return provider
+            .chat_with_history(history, model, temperature)
+            .await;
+    };
+    let mut stream =
+        provider.stream_chat_with_history(history, model, temperature, StreamOptions::new(true));
+    let mut text = String::new();
+    let mut sent = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            // Nothing was shown yet, so the response can still come whole
+            Err(e) if sent == 0 => {
+                tracing::warn!("Streaming failed, retrying without: {e}");
+                return provider
+                    .chat_with_history(history, model, temperature)
+                    .await;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        text.push_str(&chunk.delta);
+        let visible = streamable_len(&text);
+        if visible > sent {
+            let _ = deltas.send(ReplyDelta::Text(text[sent..visible].to_string()));
+            sent = visible;
+        }
+        if chunk.is_final {
+            break;
+        }
+    }
+    Ok(text)
+}
+
+/// Length of the start of a streaming response that can be shown: the text
+/// before any tool call, holding back what may be the start of a tag.
+fn streamable_len(text: &str) -> usize {
+    const TAG: &str = "<tool_call>";
+    // A response that is all JSON may be a native-style tool call
+    if text.trim_start().starts_with(['{', '[']) {
+        return 0;
+    }
+    if let Some(start) = text.find(TAG) {
+        return start;
+    }
+    (1..TAG.len())
+        .rev()
+        .find(|&n| text.ends_with(&TAG[..n]))
+        .map_or(text.len(), |n| text.len() - n)
+}
+
 /// Build the tool instruction block for the system prompt so the LLM knows
 /// how to invoke tools.
 pub(crate) fn build_tool_instructions(tools_registry: &[Box<dyn Tool>]) -> String {
@@ -1732,6 +1834,15 @@ Done."#;
         assert_eq!(result.len(), 2);
     }
 
+    #[test]
+    fn streamable_len_holds_back_tool_calls() {
+        assert_eq!(streamable_len("Hello there"), 11);
+        assert_eq!(streamable_len("Let me check <tool"), 13);
+        assert_eq!(streamable_len("Let me check <tool_call>{\"name\""), 13);
+        assert_eq!(streamable_len("a < b"), 5);
+        assert_eq!(streamable_len(r#"{"tool_calls": ["#), 0);
+    }
+
     // ═══════════════════════════════════════════════════════════════════════
     // Recovery Tests - Constants Validation
     // ═══════════════════════════════════════════════════════════════════════
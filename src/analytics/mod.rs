@@ -0,0 +1,683 @@
+//! Usage analytics (`[analytics]`).
+//!
+//! The channel runtime reports every accepted message with
+//! [`Analytics::record_message`] and every answered one with
+//! [`Analytics::record_reply`]. Reports
+//! are counted in memory and folded into hourly and daily aggregates in
+//! `<workspace>/state/analytics.db` once a minute by [`run_analytics`],
+//! which also prunes aggregates past `retention_days` and sends the weekly
+//! digest to the `[ops]` target. `GET /admin/analytics` serves the same
+//! report as JSON.
+//!
+//! Users are stored as a hash of `<channel>:<user id>`: enough to count
+//! daily actives, nothing to erase when someone asks to be forgotten.
+
+use crate::channels::control::ChannelControl;
+use crate::channels::manager::BroadcastTarget;
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::{AnalyticsConfig, ModelPricing, OpsConfig};
+use crate::cost::quota::priced_usage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc, Weekday};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often counted activity is written to the store.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Commands listed in a report.
+const TOP_COMMANDS: usize = 10;
+
+/// Longest word still counted as a command.
+const MAX_COMMAND_CHARS: usize = 32;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct HourTotals {
+    messages: u64,
+    replies: u64,
+    latency_ms: u64,
+    cost_usd: f64,
+}
+
+/// Activity counted since the last flush.
+#[derive(Debug, Default)]
+struct Pending {
+    /// Keyed by (day, hour of day, channel)
+    hours: HashMap<(NaiveDate, u32, String), HourTotals>,
+    /// (day, channel, user hash)
+    users: HashSet<(NaiveDate, String, String)>,
+    /// Keyed by (day, command)
+    commands: HashMap<(NaiveDate, String), u64>,
+}
+
+/// One channel's activity over a report's range.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChannelActivity {
+    pub channel: String,
+    pub messages: u64,
+    pub replies: u64,
+    pub avg_latency_ms: Option<u64>,
+    pub cost_usd: f64,
+    /// Distinct users over the whole range
+    pub active_users: u64,
+    pub daily_active_users: BTreeMap<NaiveDate, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CommandUse {
+    pub command: String,
+    pub uses: u64,
+}
+
+/// Activity between `from` and `to`, both inclusive.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub messages: u64,
+    pub replies: u64,
+    pub avg_latency_ms: Option<u64>,
+    pub cost_usd: f64,
+    /// Busiest channels first
+    pub channels: Vec<ChannelActivity>,
+    /// Messages by hour of day (UTC), index 0 = 00:00–00:59
+    pub messages_per_hour: Vec<u64>,
+    pub top_commands: Vec<CommandUse>,
+}
+
+pub struct Analytics {
+    conn: Mutex<Connection>,
+    prices: HashMap<String, ModelPricing>,
+    pending: Mutex<Pending>,
+}
+
+/// The `/command` a message starts with, lowercased and without a
+/// `@botname` suffix. Paths and long words aren't commands.
+fn command_of(content: &str) -> Option<String> {
+    let word = content.split_whitespace().next()?;
+    let name = word.strip_prefix('/')?.split('@').next()?;
+    let valid = !name.is_empty()
+        && name.len() <= MAX_COMMAND_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| format!("/{}", name.to_ascii_lowercase()))
+}
+
+fn user_hash(channel: &str, user: &str) -> String {
+    let digest = Sha256::digest(format!("{channel}:{user}").as_bytes());
+    hex::encode(&digest[..16])
+}
+
+fn count(value: i64) -> u64 {
+    u64::try_from(value).unwrap_or(0)
+}
+
+fn average(total_ms: u64, replies: u64) -> Option<u64> {
+    (replies > 0).then(|| total_ms / replies)
+}
+
+impl Analytics {
+    /// Open (or create) the store under `<workspace>/state/`.
+    pub fn open(workspace_dir: &Path, prices: &HashMap<String, ModelPricing>) -> Result<Self> {
+        let dir = workspace_dir.join("state");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create state directory: {}", dir.display()))?;
+        let db_path = dir.join("analytics.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open analytics DB: {}", db_path.display()))?;
+        Self::with_connection(conn, prices)
+    }
+
+    fn with_connection(conn: Connection, prices: &HashMap<String, ModelPricing>) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS analytics_hourly (
+                day         TEXT NOT NULL,
+                hour        INTEGER NOT NULL,
+                channel     TEXT NOT NULL,
+                messages    INTEGER NOT NULL DEFAULT 0,
+                replies     INTEGER NOT NULL DEFAULT 0,
+                latency_ms  INTEGER NOT NULL DEFAULT 0,
+                cost_usd    REAL NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, hour, channel)
+            );
+            CREATE TABLE IF NOT EXISTS analytics_users (
+                day      TEXT NOT NULL,
+                channel  TEXT NOT NULL,
+                user     TEXT NOT NULL,
+                PRIMARY KEY (day, channel, user)
+            );
+            CREATE TABLE IF NOT EXISTS analytics_commands (
+                day      TEXT NOT NULL,
+                command  TEXT NOT NULL,
+                uses     INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, command)
+            );
+            CREATE TABLE IF NOT EXISTS analytics_meta (
+                key    TEXT PRIMARY KEY,
+                value  TEXT NOT NULL
+            );",
+        )
+        .context("Failed to initialize analytics schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            prices: prices.clone(),
+            pending: Mutex::new(Pending::default()),
+        })
+    }
+
+    /// Count an accepted inbound message.
+    pub fn record_message(&self, msg: &ChannelMessage, at: DateTime<Utc>) {
+        let day = at.date_naive();
+        let mut pending = self.pending.lock();
+        pending
+            .hours
+            .entry((day, at.hour(), msg.channel.clone()))
+            .or_default()
+            .messages += 1;
+        pending.users.insert((
+            day,
+            msg.channel.clone(),
+            user_hash(&msg.channel, msg.author_id()),
+        ));
+        if let Some(command) = command_of(&msg.content) {
+            *pending.commands.entry((day, command)).or_default() += 1;
+        }
+    }
+
+    /// Count an answered message: how long the reply took and what the
+    /// estimated tokens cost at `[cost.prices]`.
+    pub fn record_reply(
+        &self,
+        channel: &str,
+        latency: Duration,
+        model: &str,
+        input_tokens: u64,
+        output_tokens: u64,
+        at: DateTime<Utc>,
+    ) {
+        let usage = priced_usage(&self.prices, model, input_tokens, output_tokens);
+        let mut pending = self.pending.lock();
+        let totals = pending
+            .hours
+            .entry((at.date_naive(), at.hour(), channel.to_string()))
+            .or_default();
+        totals.replies += 1;
+        totals.latency_ms = totals
+            .latency_ms
+            .saturating_add(u64::try_from(latency.as_millis()).unwrap_or(u64::MAX));
+        totals.cost_usd += usage.cost_usd;
+    }
+
+    /// Write counted activity to the store.
+    pub fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        for ((day, hour, channel), totals) in &pending.hours {
+            tx.execute(
+                "INSERT INTO analytics_hourly (day, hour, channel, messages, replies, latency_ms, cost_usd)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT (day, hour, channel) DO UPDATE SET
+                    messages = messages + excluded.messages,
+                    replies = replies + excluded.replies,
+                    latency_ms = latency_ms + excluded.latency_ms,
+                    cost_usd = cost_usd + excluded.cost_usd",
+                params![
+                    day.to_string(),
+                    hour,
+                    channel,
+                    i64::try_from(totals.messages).unwrap_or(i64::MAX),
+                    i64::try_from(totals.replies).unwrap_or(i64::MAX),
+                    i64::try_from(totals.latency_ms).unwrap_or(i64::MAX),
+                    totals.cost_usd
+                ],
+            )?;
+        }
+        for (day, channel, user) in &pending.users {
+            tx.execute(
+                "INSERT OR IGNORE INTO analytics_users (day, channel, user) VALUES (?1, ?2, ?3)",
+                params![day.to_string(), channel, user],
+            )?;
+        }
+        for ((day, command), uses) in &pending.commands {
+            tx.execute(
+                "INSERT INTO analytics_commands (day, command, uses) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (day, command) DO UPDATE SET uses = uses + excluded.uses",
+                params![
+                    day.to_string(),
+                    command,
+                    i64::try_from(*uses).unwrap_or(i64::MAX)
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete aggregates of days before `before`. Returns the rows deleted.
+    pub fn prune(&self, before: NaiveDate) -> Result<usize> {
+        let conn = self.conn.lock();
+        let before = before.to_string();
+        let mut removed = 0;
+        for table in ["analytics_hourly", "analytics_users", "analytics_commands"] {
+            removed += conn.execute(
+                &format!("DELETE FROM {table} WHERE day < ?1"),
+                params![before],
+            )?;
+        }
+        Ok(removed)
+    }
+
+    /// Stored activity from `from` to `to`, both inclusive. Activity not
+    /// flushed yet isn't included.
+    pub fn report(&self, from: NaiveDate, to: NaiveDate) -> Result<AnalyticsReport> {
+        let conn = self.conn.lock();
+        let (first_day, last_day) = (from.to_string(), to.to_string());
+        let range = params![first_day, last_day];
+
+        let mut channels: BTreeMap<String, ChannelActivity> = BTreeMap::new();
+        let mut latency_ms = 0;
+        let mut stmt = conn.prepare(
+            "SELECT channel, SUM(messages), SUM(replies), SUM(latency_ms), SUM(cost_usd)
+             FROM analytics_hourly WHERE day BETWEEN ?1 AND ?2 GROUP BY channel",
+        )?;
+        let mut rows = stmt.query(range)?;
+        while let Some(row) = rows.next()? {
+            let channel: String = row.get(0)?;
+            let replies = count(row.get(2)?);
+            let channel_latency = count(row.get(3)?);
+            latency_ms += channel_latency;
+            channels.insert(
+                channel.clone(),
+                ChannelActivity {
+                    channel,
+                    messages: count(row.get(1)?),
+                    replies,
+                    avg_latency_ms: average(channel_latency, replies),
+                    cost_usd: row.get(4)?,
+                    ..ChannelActivity::default()
+                },
+            );
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT channel, day, COUNT(*) FROM analytics_users
+             WHERE day BETWEEN ?1 AND ?2 GROUP BY channel, day",
+        )?;
+        let mut rows = stmt.query(range)?;
+        while let Some(row) = rows.next()? {
+            let channel: String = row.get(0)?;
+            let day: String = row.get(1)?;
+            let Ok(day) = day.parse::<NaiveDate>() else {
+                continue;
+            };
+            let activity = channels
+                .entry(channel.clone())
+                .or_insert_with(|| ChannelActivity {
+                    channel,
+                    ..ChannelActivity::default()
+                });
+            activity.daily_active_users.insert(day, count(row.get(2)?));
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT channel, COUNT(DISTINCT user) FROM analytics_users
+             WHERE day BETWEEN ?1 AND ?2 GROUP BY channel",
+        )?;
+        let mut rows = stmt.query(range)?;
+        while let Some(row) = rows.next()? {
+            let channel: String = row.get(0)?;
+            if let Some(activity) = channels.get_mut(&channel) {
+                activity.active_users = count(row.get(1)?);
+            }
+        }
+
+        let mut messages_per_hour = vec![0; 24];
+        let mut stmt = conn.prepare(
+            "SELECT hour, SUM(messages) FROM analytics_hourly
+             WHERE day BETWEEN ?1 AND ?2 GROUP BY hour",
+        )?;
+        let mut rows = stmt.query(range)?;
+        while let Some(row) = rows.next()? {
+            let hour: i64 = row.get(0)?;
+            if let Some(slot) = usize::try_from(hour)
+                .ok()
+                .and_then(|h| messages_per_hour.get_mut(h))
+            {
+                *slot = count(row.get(1)?);
+            }
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT command, SUM(uses) AS total FROM analytics_commands
+             WHERE day BETWEEN ?1 AND ?2 GROUP BY command
+             ORDER BY total DESC, command LIMIT ?3",
+        )?;
+        let top_commands = stmt
+            .query_map(
+                params![
+                    first_day,
+                    last_day,
+                    i64::try_from(TOP_COMMANDS).unwrap_or(i64::MAX)
+                ],
+                |row| {
+                    Ok(CommandUse {
+                        command: row.get(0)?,
+                        uses: count(row.get(1)?),
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut channels: Vec<ChannelActivity> = channels.into_values().collect();
+        channels.sort_by(|a, b| b.messages.cmp(&a.messages).then(a.channel.cmp(&b.channel)));
+        let messages = channels.iter().map(|c| c.messages).sum();
+        let replies = channels.iter().map(|c| c.replies).sum();
+        Ok(AnalyticsReport {
+            from,
+            to,
+            messages,
+            replies,
+            avg_latency_ms: average(latency_ms, replies),
+            cost_usd: channels.iter().map(|c| c.cost_usd).sum(),
+            channels,
+            messages_per_hour,
+            top_commands,
+        })
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .lock()
+            .query_row(
+                "SELECT value FROM analytics_meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.lock().execute(
+            "INSERT INTO analytics_meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
+
+fn seconds(ms: u64) -> String {
+    let secs = ms as f64 / 1000.0;
+    format!("{secs:.1}s")
+}
+
+/// The weekly digest text for `report`.
+pub fn render_digest(report: &AnalyticsReport) -> String {
+    let mut text = format!("📊 Usage {} – {}", report.from, report.to);
+    if report.messages == 0 {
+        text.push_str("\nNo messages.");
+        return text;
+    }
+    let _ = write!(
+        text,
+        "\nMessages: {} ({} answered)",
+        report.messages, report.replies
+    );
+    if let Some(ms) = report.avg_latency_ms {
+        let _ = write!(text, ", avg reply {}", seconds(ms));
+    }
+    let _ = write!(text, ", LLM cost ${:.2}", report.cost_usd);
+
+    let days = u64::try_from((report.to - report.from).num_days() + 1)
+        .unwrap_or(1)
+        .max(1);
+    for channel in &report.channels {
+        let daily: u64 = channel.daily_active_users.values().sum();
+        let _ = write!(
+            text,
+            "\n• {}: {} messages, {} users (avg {}/day)",
+            channel.channel,
+            channel.messages,
+            channel.active_users,
+            daily / days
+        );
+        if let Some(ms) = channel.avg_latency_ms {
+            let _ = write!(text, ", avg reply {}", seconds(ms));
+        }
+    }
+
+    if let Some((hour, busiest)) = report
+        .messages_per_hour
+        .iter()
+        .enumerate()
+        .max_by_key(|(hour, messages)| (**messages, std::cmp::Reverse(*hour)))
+    {
+        let _ = write!(
+            text,
+            "\nBusiest hour (UTC): {hour:02}:00 ({busiest} messages)"
+        );
+    }
+    if !report.top_commands.is_empty() {
+        let commands: Vec<String> = report
+            .top_commands
+            .iter()
+            .map(|c| format!("{} {}", c.command, c.uses))
+            .collect();
+        let _ = write!(text, "\nTop commands: {}", commands.join(", "));
+    }
+    text
+}
+
+/// When this week's digest is due: `weekday` at `hour` UTC of `now`'s ISO
+/// week.
+fn digest_time(now: DateTime<Utc>, weekday: Weekday, hour: u32) -> DateTime<Utc> {
+    let monday =
+        now.date_naive() - chrono::Duration::days(i64::from(now.weekday().num_days_from_monday()));
+    let day = monday + chrono::Duration::days(i64::from(weekday.num_days_from_monday()));
+    day.and_hms_opt(hour.min(23), 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+}
+
+async fn send_digest(ops: &OpsConfig, control: &ChannelControl, text: &str) -> bool {
+    let Some(manager) = control.manager() else {
+        return false;
+    };
+    let target = BroadcastTarget::new(&ops.channel, &ops.recipient);
+    let mut sent = false;
+    for target in manager.expand(&target) {
+        match manager.send(&target.channel, text, &target.recipient).await {
+            Ok(()) => sent = true,
+            Err(e) => tracing::warn!(
+                "Failed to deliver analytics digest to {}: {e}",
+                target.channel
+            ),
+        }
+    }
+    sent
+}
+
+/// Flush counted activity, prune old aggregates and send the weekly
+/// digest until the process stops.
+pub async fn run_analytics(
+    config: AnalyticsConfig,
+    ops: OpsConfig,
+    analytics: Arc<Analytics>,
+    control: Arc<ChannelControl>,
+) {
+    let weekday = config
+        .digest_weekday
+        .parse::<Weekday>()
+        .unwrap_or_else(|_| {
+            tracing::warn!(
+                "[analytics] digest_weekday {:?} is not a weekday; using Monday",
+                config.digest_weekday
+            );
+            Weekday::Mon
+        });
+    let digest = config.weekly_digest && !ops.channel.is_empty();
+    if config.weekly_digest && !digest {
+        tracing::warn!("[analytics] weekly_digest needs an [ops] channel; not sending digests");
+    }
+
+    let mut pruned: Option<NaiveDate> = None;
+    let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = analytics.flush() {
+            tracing::warn!("Failed to store analytics: {e}");
+        }
+
+        let now = Utc::now();
+        let today = now.date_naive();
+        if pruned != Some(today) {
+            let before = today - chrono::Duration::days(i64::from(config.retention_days.max(1)));
+            match analytics.prune(before) {
+                Ok(removed) if removed > 0 => {
+                    tracing::debug!("Pruned {removed} analytics rows before {before}");
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to prune analytics: {e}"),
+            }
+            pruned = Some(today);
+        }
+
+        let due = digest_time(now, weekday, config.digest_hour);
+        if !digest || now < due {
+            continue;
+        }
+        let week = due.date_naive().to_string();
+        match analytics.meta("last_digest") {
+            Ok(Some(last)) if last == week => continue,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Failed to read analytics digest state: {e}");
+                continue;
+            }
+        }
+        let to = due.date_naive() - chrono::Duration::days(1);
+        let report = match analytics.report(to - chrono::Duration::days(6), to) {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!("Failed to build analytics digest: {e}");
+                continue;
+            }
+        };
+        if send_digest(&ops, &control, &render_digest(&report)).await {
+            if let Err(e) = analytics.set_meta("last_digest", &week) {
+                tracing::warn!("Failed to record analytics digest: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn analytics() -> Analytics {
+        let prices = HashMap::from([(
+            "openai/gpt-4o".to_string(),
+            ModelPricing {
+                input: 2.5,
+                output: 10.0,
+            },
+        )]);
+        Analytics::with_connection(Connection::open_in_memory().unwrap(), &prices).unwrap()
+    }
+
+    fn message(channel: &str, sender: &str, content: &str) -> ChannelMessage {
+        ChannelMessage::test(channel, sender, content)
+    }
+
+    #[test]
+    fn commands_are_normalized() {
+        assert_eq!(command_of("/Help"), Some("/help".into()));
+        assert_eq!(command_of("/reset@zeroclaw_bot now"), Some("/reset".into()));
+        assert_eq!(command_of("/usr/bin/env"), None);
+        assert_eq!(command_of("hello /help"), None);
+        assert_eq!(command_of("/"), None);
+    }
+
+    #[test]
+    fn report_aggregates_flushed_activity() {
+        let analytics = analytics();
+        let monday = Utc.with_ymd_and_hms(2026, 10, 12, 14, 5, 0).unwrap();
+        let tuesday = monday + chrono::Duration::days(1);
+        analytics.record_message(&message("telegram", "alice", "/help"), monday);
+        analytics.record_message(&message("telegram", "alice", "hi"), monday);
+        analytics.record_message(&message("telegram", "bob", "/help"), tuesday);
+        analytics.record_message(&message("slack", "carol", "/reset"), tuesday);
+        analytics.flush().unwrap();
+        analytics.record_reply(
+            "telegram",
+            Duration::from_millis(1000),
+            "gpt-4o",
+            1_000_000,
+            0,
+            monday,
+        );
+        analytics.record_reply(
+            "telegram",
+            Duration::from_millis(3000),
+            "gpt-4o",
+            0,
+            0,
+            tuesday,
+        );
+        analytics.flush().unwrap();
+
+        let report = analytics
+            .report(monday.date_naive(), tuesday.date_naive())
+            .unwrap();
+        assert_eq!(report.messages, 4);
+        assert_eq!(report.replies, 2);
+        assert_eq!(report.avg_latency_ms, Some(2000));
+        assert!((report.cost_usd - 2.5).abs() < 1e-9);
+        assert_eq!(report.messages_per_hour[14], 4);
+
+        let telegram = &report.channels[0];
+        assert_eq!(telegram.channel, "telegram");
+        assert_eq!(telegram.active_users, 2);
+        assert_eq!(telegram.daily_active_users[&monday.date_naive()], 1);
+        assert_eq!(telegram.daily_active_users[&tuesday.date_naive()], 1);
+        assert_eq!(
+            report.top_commands[0],
+            CommandUse {
+                command: "/help".into(),
+                uses: 2
+            }
+        );
+
+        analytics.prune(tuesday.date_naive()).unwrap();
+        let report = analytics
+            .report(monday.date_naive(), tuesday.date_naive())
+            .unwrap();
+        assert_eq!(report.messages, 2);
+    }
+
+    #[test]
+    fn digest_is_due_on_the_configured_day_of_the_week() {
+        let thursday = Utc.with_ymd_and_hms(2026, 10, 15, 8, 0, 0).unwrap();
+        assert_eq!(
+            digest_time(thursday, Weekday::Mon, 9),
+            Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            digest_time(thursday, Weekday::Fri, 9),
+            Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap()
+        );
+    }
+}
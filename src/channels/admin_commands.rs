@@ -0,0 +1,318 @@
+//! Admin commands in chat, gated by [operator roles](crate::security::roles).
+//!
+//! - `/reload` — re-read config.toml and restart channels (owner)
+//! - `/mute <channel>`, `/unmute <channel>` — hold or release a channel's
+//!   replies (admin)
+//! - `/audit last <n>` — the latest admin commands from the audit log (admin)
+//! - `/debug on <channel|target> [level] [minutes]`, `/debug off [target]`,
+//!   `/debug` — raise a log level for a while, e.g. `/debug on qq` logs the
+//!   QQ channel at trace, raw gateway frames included, for ten minutes
+//!   (admin; see [`crate::logging::overrides`])
+//! - `/block <user> [minutes]`, `/unblock <user>` — ignore a user of this
+//!   channel, for good or for a while (moderator)
+//!
+//! `/announce` needs the admin role too (see [`super::announce`]). Every
+//! invocation, allowed or not, is recorded to the audit log with who ran it,
+//! when and what. Without any `[security.roles]` these commands aren't
+//! claimed and reach the agent like any other message.
+
+use super::control::ChannelControl;
+use super::traits::ChannelMessage;
+use crate::config::Config;
+use crate::logging::overrides::{self, LevelOverride};
+use crate::security::audit::{self, AdminCommandLog, AuditEvent, AuditEventType};
+use crate::security::roles::{self, Role};
+use chrono::{Duration as ChronoDuration, Utc};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Entries `/audit` shows when not told how many.
+const DEFAULT_AUDIT_ENTRIES: usize = 20;
+
+/// Most entries `/audit` shows.
+const MAX_AUDIT_ENTRIES: usize = 100;
+
+/// Answer an admin command. Returns `None` for any other message.
+pub async fn handle_command(control: &ChannelControl, msg: &ChannelMessage) -> Option<String> {
+    let mut words = msg.content.split_whitespace();
+    let command = words.next()?.split('@').next()?;
+    let required = roles::required_role(command).filter(|_| command != "/announce")?;
+    if !roles::configured() {
+        return None;
+    }
+    let args: Vec<&str> = words.collect();
+    let role = roles::role_of(&format!("{}:{}", msg.channel, msg.author_id()));
+    let allowed = role.is_some_and(|held| held >= required);
+    let outcome = if allowed {
+        run(control, msg, command, &args).await
+    } else {
+        Err(format!("{command} needs the {required} role"))
+    };
+    audit::record_admin_command(AdminCommandLog {
+        channel: &msg.channel,
+        user_id: msg.author_id(),
+        username: msg.sender_name.as_deref(),
+        command: msg.content.trim(),
+        role: role.map(Role::as_str),
+        allowed,
+        error: outcome
+            .as_ref()
+            .err()
+            .filter(|_| allowed)
+            .map(String::as_str),
+    });
+    Some(outcome.unwrap_or_else(|e| format!("⚠️ {e}")))
+}
+
+async fn run(
+    control: &ChannelControl,
+    msg: &ChannelMessage,
+    command: &str,
+    args: &[&str],
+) -> Result<String, String> {
+    match (command, args) {
+        ("/reload", []) => {
+            let config = tokio::task::spawn_blocking(Config::load_or_init)
+                .await
+                .map_err(|e| format!("Config reload failed: {e}"))?
+                .map_err(|e| format!("Config reload failed: {e:#}"))?;
+            tracing::info!("Config reload requested from {}", msg.channel);
+            control.request_reload(config);
+            Ok("🔄 Reloading config and restarting channels…".to_string())
+        }
+        ("/mute", [channel]) => {
+            known_channel(control, channel)?;
+            Ok(if control.mute(channel) {
+                format!("🔇 Muted {channel}; its replies are held until /unmute {channel}")
+            } else {
+                format!("{channel} is already muted")
+            })
+        }
+        ("/unmute", [channel]) => {
+            known_channel(control, channel)?;
+            Ok(if control.unmute(channel) {
+                format!("🔊 Unmuted {channel}")
+            } else {
+                format!("{channel} is not muted")
+            })
+        }
+        ("/block", [user, duration @ ..]) if duration.len() <= 1 => {
+            if roles::role_of(&format!("{}:{user}", msg.channel)).is_some() {
+                return Err(format!("{user} holds a role and can't be blocked"));
+            }
+            let minutes = match duration.first() {
+                Some(minutes) => Some(
+                    minutes
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|minutes| *minutes > 0)
+                        .ok_or_else(|| format!("Invalid number of minutes '{minutes}'"))?,
+                ),
+                None => None,
+            };
+            let until = minutes.map(|minutes| Utc::now() + ChronoDuration::minutes(minutes));
+            control.block_user(&msg.channel, user, until);
+            Ok(match minutes {
+                Some(minutes) => format!("🚫 Blocked {user} on {} for {minutes} min", msg.channel),
+                None => format!("🚫 Blocked {user} on {} until /unblock", msg.channel),
+            })
+        }
+        ("/unblock", [user]) => Ok(if control.unblock_user(&msg.channel, user) {
+            format!("Unblocked {user} on {}", msg.channel)
+        } else {
+            format!("{user} is not blocked on {}", msg.channel)
+        }),
+        ("/audit", args) => {
+            let limit = match args {
+                [] => DEFAULT_AUDIT_ENTRIES,
+                ["last", n] | [n] => n
+                    .parse::<usize>()
+                    .map_err(|_| "Usage: /audit last <n>".to_string())?,
+                _ => return Err("Usage: /audit last <n>".to_string()),
+            };
+            let logger = audit::global().ok_or("The audit log is off")?;
+            let events = logger
+                .recent(
+                    &AuditEventType::AdminCommand,
+                    limit.clamp(1, MAX_AUDIT_ENTRIES),
+                )
+                .map_err(|e| format!("Failed to read the audit log: {e}"))?;
+            Ok(describe(&events))
+        }
+        ("/debug", []) => Ok(describe_overrides(&overrides::active())),
+        ("/debug", ["on", target, rest @ ..]) if rest.len() <= 2 => {
+            let target = overrides::log_target(target).map_err(|e| e.to_string())?;
+            let mut level = overrides::DEFAULT_OVERRIDE_LEVEL;
+            let mut minutes = overrides::DEFAULT_OVERRIDE_MINUTES;
+            for arg in rest {
+                match arg.parse::<u64>() {
+                    Ok(n) if (1..=overrides::MAX_OVERRIDE_MINUTES).contains(&n) => minutes = n,
+                    Ok(_) => return Err(format!("Invalid number of minutes '{arg}'")),
+                    Err(_) => level = arg,
+                }
+            }
+            let entry = overrides::set(&target, level, Duration::from_secs(minutes * 60))
+                .map_err(|e| e.to_string())?;
+            Ok(format!(
+                "🐛 Logging {} at {} for {minutes} min (until {})",
+                entry.target,
+                entry.level,
+                entry.expires_at.format("%H:%M UTC")
+            ))
+        }
+        ("/debug", ["off", target @ ..]) if target.len() <= 1 => {
+            let target = target
+                .first()
+                .map(|target| overrides::log_target(target))
+                .transpose()
+                .map_err(|e| e.to_string())?;
+            let cleared = overrides::clear(target.as_deref()).map_err(|e| e.to_string())?;
+            Ok(match (cleared, target) {
+                (0, Some(target)) => format!("{target} has no log level override"),
+                (0, None) => "No log level overrides in force".to_string(),
+                (_, Some(target)) => format!("Log level of {target} restored"),
+                (n, None) => format!("Cleared {n} log level overrides"),
+            })
+        }
+        ("/debug", _) => Err(
+            "Usage: /debug on <channel|target> [level] [minutes] | /debug off [target]".to_string(),
+        ),
+        ("/reload", _) => Err("Usage: /reload".to_string()),
+        ("/mute" | "/unmute", _) => Err(format!("Usage: {command} <channel>")),
+        ("/block", _) => Err("Usage: /block <user> [minutes]".to_string()),
+        _ => Err(format!("Usage: {command} <user>")),
+    }
+}
+
+fn known_channel(control: &ChannelControl, channel: &str) -> Result<(), String> {
+    let known = control
+        .manager()
+        .is_some_and(|manager| manager.get(channel).is_some());
+    if known {
+        Ok(())
+    } else {
+        Err(format!("Unknown channel: {channel}"))
+    }
+}
+
+/// One line per log level override.
+fn describe_overrides(active: &[LevelOverride]) -> String {
+    if active.is_empty() {
+        return "No log level overrides in force.".to_string();
+    }
+    let mut text = "🐛 Log level overrides:".to_string();
+    for entry in active {
+        let _ = write!(
+            text,
+            "\n{}={} until {}",
+            entry.target,
+            entry.level,
+            entry.expires_at.format("%H:%M UTC")
+        );
+    }
+    text
+}
+
+/// One line per admin command, oldest first.
+fn describe(events: &[AuditEvent]) -> String {
+    if events.is_empty() {
+        return "No admin commands in the audit log yet.".to_string();
+    }
+    let mut text = format!("🧾 Last {} admin commands:", events.len());
+    for event in events {
+        let who = event.actor.as_ref().map_or_else(String::new, |actor| {
+            format!(
+                "{}:{}",
+                actor.channel,
+                actor.user_id.as_deref().unwrap_or("?")
+            )
+        });
+        let what = event
+            .action
+            .as_ref()
+            .and_then(|action| action.command.as_deref())
+            .unwrap_or("?");
+        let allowed = event.action.as_ref().is_some_and(|action| action.allowed);
+        let error = event
+            .result
+            .as_ref()
+            .and_then(|result| result.error.as_deref());
+        let status = match (allowed, error) {
+            (false, _) => "🚫 denied".to_string(),
+            (true, Some(error)) => format!("⚠️ {error}"),
+            (true, None) => "✅".to_string(),
+        };
+        let _ = write!(
+            text,
+            "\n{} {who} {what} {status}",
+            event.timestamp.format("%Y-%m-%d %H:%M")
+        );
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RolesConfig;
+
+    fn command(sender: &str, content: &str) -> ChannelMessage {
+        ChannelMessage {
+            reply_target: "ops-chat".into(),
+            ..ChannelMessage::test("telegram", sender, content)
+        }
+    }
+
+    #[tokio::test]
+    async fn roles_gate_commands_and_blocks_expire() {
+        roles::configure(&RolesConfig {
+            owners: vec!["telegram:owner".into()],
+            admins: Vec::new(),
+            moderators: vec!["telegram:mod".into()],
+        });
+        let control = &ChannelControl::new();
+        let run = move |sender: &'static str, content: &'static str| async move {
+            handle_command(control, &command(sender, content))
+                .await
+                .unwrap()
+        };
+
+        assert_eq!(
+            run("guest", "/block spammer").await,
+            "⚠️ /block needs the moderator role"
+        );
+        assert_eq!(
+            run("mod", "/mute slack").await,
+            "⚠️ /mute needs the admin role"
+        );
+        assert!(run("mod", "/block owner").await.contains("holds a role"));
+        assert!(run("mod", "/block spammer 0")
+            .await
+            .contains("Invalid number"));
+
+        assert_eq!(
+            run("mod", "/block spammer 10").await,
+            "🚫 Blocked spammer on telegram for 10 min"
+        );
+        assert!(control.is_blocked("telegram", "spammer"));
+        assert!(!control.is_blocked("slack", "spammer"));
+        control.block_user("telegram", "spammer", Some(Utc::now()));
+        assert!(!control.is_blocked("telegram", "spammer"));
+
+        assert!(run("owner", "/mute nowhere")
+            .await
+            .contains("Unknown channel"));
+        assert_eq!(
+            run("mod", "/debug on qq").await,
+            "⚠️ /debug needs the admin role"
+        );
+        assert!(run("owner", "/debug on").await.contains("Usage: /debug"));
+        assert!(run("owner", "/debug on qq 0")
+            .await
+            .contains("Invalid number"));
+        assert!(handle_command(control, &command("owner", "/help"))
+            .await
+            .is_none());
+        roles::configure(&RolesConfig::default());
+    }
+}
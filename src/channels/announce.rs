@@ -0,0 +1,529 @@
+//! Confirmed announcements (`/announce <tag> <text>`).
+//!
+//! An operator in `[channels_config.announcements] admins` drafts an
+//! announcement to every channel tagged `<tag>`, at its `default_recipient`.
+//! Nothing goes out yet: the reply previews the message and the chats it
+//! will reach, with Send and Cancel buttons where the channel has them and
+//! `/announce send <id>` / `/announce cancel <id>` everywhere. Only the admin
+//! who drafted it can confirm, within `confirm_secs`. The broadcast then
+//! reports progress every `progress_every` chats and ends with a delivery
+//! summary naming the chats that failed. Operators with the admin
+//! [role](crate::security::roles) may announce too, and every invocation is
+//! recorded to the audit log.
+
+use super::control::ChannelControl;
+use super::interactions::{self, Button, Interaction, Keyboard};
+use super::manager::{BroadcastReport, BroadcastTarget, TAG_SELECTOR_PREFIX};
+use super::priority::Priority;
+use super::traits::{Channel, ChannelMessage};
+use crate::config::AnnouncementsConfig;
+use crate::security::audit::{self, AdminCommandLog};
+use crate::security::roles::{self, Role};
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Prefix of the preview buttons' callback ids.
+const HANDLER: &str = "announce";
+
+/// Chats named in a preview or summary; the rest are counted.
+const MAX_LISTED: usize = 10;
+
+const USAGE: &str = "Usage: /announce <tag> <text>, /announce send <id> or /announce cancel <id>";
+
+struct Draft {
+    /// `<channel>:<user id>` of the admin who wrote it
+    author: String,
+    text: String,
+    targets: Vec<BroadcastTarget>,
+    created: Instant,
+}
+
+/// What to answer an `/announce` command with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnnounceReply {
+    Text(String),
+    /// A draft's preview, with buttons to send or cancel it
+    Preview {
+        text: String,
+        keyboard: Keyboard,
+    },
+}
+
+pub struct Announcements {
+    config: AnnouncementsConfig,
+    control: Arc<ChannelControl>,
+    drafts: Mutex<HashMap<String, Draft>>,
+}
+
+impl Announcements {
+    pub fn new(config: AnnouncementsConfig, control: Arc<ChannelControl>) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            control,
+            drafts: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Handle clicks on the Send and Cancel buttons of previews.
+    pub fn register_buttons(self: &Arc<Self>) {
+        let announcements = Arc::clone(self);
+        interactions::register(HANDLER, move |interaction: Interaction| {
+            let announcements = Arc::clone(&announcements);
+            async move { Some(announcements.handle_click(&interaction)) }
+        });
+    }
+
+    fn is_admin(&self, operator: &str) -> bool {
+        self.config.admins.iter().any(|admin| admin == operator)
+            || roles::has_role(operator, Role::Admin)
+    }
+
+    /// Answer `/announce`. Returns `None` for any other message.
+    pub fn handle_command(
+        self: &Arc<Self>,
+        channel: &dyn Channel,
+        msg: &ChannelMessage,
+    ) -> Option<AnnounceReply> {
+        let (command, args) = split_word(&msg.content)?;
+        if command.split('@').next()? != "/announce" {
+            return None;
+        }
+        let operator = format!("{}:{}", msg.channel, msg.author_id());
+        let allowed = self.is_admin(&operator);
+        let (first, rest) = split_word(args).unwrap_or_default();
+        let is_id = !rest.contains(char::is_whitespace);
+        let mut preview_id = None;
+        let outcome = match first {
+            _ if !allowed => Err("Only announcement admins can use /announce".to_string()),
+            _ if rest.is_empty() => Err(USAGE.to_string()),
+            "send" if is_id => self.confirm(&operator, rest, &msg.channel, &msg.reply_target),
+            "cancel" if is_id => self.cancel(&operator, rest),
+            tag => self
+                .draft(operator.clone(), tag, rest)
+                .map(|(id, preview)| {
+                    preview_id = Some(id);
+                    preview
+                })
+                .map_err(|e| e.to_string()),
+        };
+        record(
+            &msg.channel,
+            msg.author_id(),
+            msg.sender_name.as_deref(),
+            msg.content.trim(),
+            allowed,
+            &outcome,
+        );
+        let reply = match (outcome, preview_id) {
+            (Ok(preview), Some(id)) if channel.supports_buttons() => AnnounceReply::Preview {
+                text: preview,
+                keyboard: Keyboard::new().with_row(vec![
+                    Button::new(format!("{HANDLER}:{id}:send"), "📣 Send"),
+                    Button::new(format!("{HANDLER}:{id}:cancel"), "Cancel"),
+                ]),
+            },
+            (Ok(reply), _) => AnnounceReply::Text(reply),
+            (Err(e), _) => AnnounceReply::Text(format!("⚠️ {e}")),
+        };
+        Some(reply)
+    }
+
+    fn handle_click(self: &Arc<Self>, interaction: &Interaction) -> String {
+        let operator = format!("{}:{}", interaction.channel, interaction.user_id);
+        let (outcome, command) = match interaction.payload().rsplit_once(':') {
+            Some((id, "send")) => (
+                self.confirm(
+                    &operator,
+                    id,
+                    &interaction.channel,
+                    &interaction.reply_target,
+                ),
+                format!("/announce send {id}"),
+            ),
+            Some((id, "cancel")) => (self.cancel(&operator, id), format!("/announce cancel {id}")),
+            _ => return USAGE.to_string(),
+        };
+        record(
+            &interaction.channel,
+            &interaction.user_id,
+            interaction.user_name.as_deref(),
+            &command,
+            true,
+            &outcome,
+        );
+        outcome.unwrap_or_else(|e| format!("⚠️ {e}"))
+    }
+
+    /// Keep a draft of `text` to the chats tagged `tag`; returns its id and
+    /// preview.
+    fn draft(&self, author: String, tag: &str, text: &str) -> Result<(String, String)> {
+        let tag = tag.strip_prefix(TAG_SELECTOR_PREFIX).unwrap_or(tag);
+        let Some(manager) = self.control.manager() else {
+            bail!("Channels are not running");
+        };
+        let targets = manager.expand(&BroadcastTarget::new(
+            format!("{TAG_SELECTOR_PREFIX}{tag}"),
+            "",
+        ));
+        if targets.is_empty() {
+            bail!("No channel tagged {tag} has a default_recipient");
+        }
+
+        let id = Uuid::new_v4().simple().to_string()[..8].to_string();
+        let mut preview = format!(
+            "📣 Announcement {id} to {} chats tagged {tag}:\n\n{text}\n\nTo: {}",
+            targets.len(),
+            list_targets(&targets)
+        );
+        let _ = write!(
+            preview,
+            "\nSend it within {}: /announce send {id} (or /announce cancel {id})",
+            format_secs(self.config.confirm_secs)
+        );
+
+        let mut drafts = self.drafts.lock();
+        let window = self.confirm_window();
+        drafts.retain(|_, draft| draft.created.elapsed() <= window);
+        drafts.insert(
+            id.clone(),
+            Draft {
+                author,
+                text: text.to_string(),
+                targets,
+                created: Instant::now(),
+            },
+        );
+        Ok((id, preview))
+    }
+
+    fn confirm_window(&self) -> Duration {
+        Duration::from_secs(self.config.confirm_secs)
+    }
+
+    /// Remove the draft `id` if `operator` wrote it and it hasn't expired.
+    fn take_draft(&self, operator: &str, id: &str) -> Result<Draft, String> {
+        let mut drafts = self.drafts.lock();
+        let Some(draft) = drafts.remove(id) else {
+            return Err(format!("No announcement {id} is waiting to be sent"));
+        };
+        if draft.created.elapsed() > self.confirm_window() {
+            return Err(format!(
+                "Announcement {id} expired; draft it again with /announce"
+            ));
+        }
+        if draft.author != operator {
+            drafts.insert(id.to_string(), draft);
+            return Err(format!(
+                "Only the admin who drafted announcement {id} can send or cancel it"
+            ));
+        }
+        Ok(draft)
+    }
+
+    /// Start broadcasting the draft `id`; progress and the summary go to
+    /// `report_target` on `report_channel`.
+    fn confirm(
+        self: &Arc<Self>,
+        operator: &str,
+        id: &str,
+        report_channel: &str,
+        report_target: &str,
+    ) -> Result<String, String> {
+        let draft = self.take_draft(operator, id)?;
+        let total = draft.targets.len();
+        let announcements = Arc::clone(self);
+        let (id, report_channel, report_target) = (
+            id.to_string(),
+            report_channel.to_string(),
+            report_target.to_string(),
+        );
+        tokio::spawn(async move {
+            announcements
+                .broadcast(&id, draft, &report_channel, &report_target)
+                .await;
+        });
+        Ok(format!("📣 Sending announcement to {total} chats…"))
+    }
+
+    fn cancel(&self, operator: &str, id: &str) -> Result<String, String> {
+        self.take_draft(operator, id)?;
+        Ok(format!("Announcement {id} cancelled."))
+    }
+
+    async fn broadcast(&self, id: &str, draft: Draft, report_channel: &str, report_target: &str) {
+        let Some(manager) = self.control.manager() else {
+            self.report(report_channel, report_target, "⚠️ Channels are not running")
+                .await;
+            return;
+        };
+        let total = draft.targets.len();
+        let mut report = BroadcastReport::default();
+        for chunk in draft.targets.chunks(self.config.progress_every.max(1)) {
+            let sent = manager
+                .broadcast(&draft.text, chunk, self.config.concurrency)
+                .await;
+            report.results.extend(sent.results);
+            let done = report.results.len();
+            if done < total {
+                let failed = report.failed().count();
+                let progress =
+                    format!("📣 Announcement {id}: {done}/{total} chats, {failed} failed");
+                self.report(report_channel, report_target, &progress).await;
+            }
+        }
+        tracing::info!(
+            "Announcement {id} reached {} of {total} chats",
+            report.succeeded()
+        );
+        self.report(report_channel, report_target, &summarize(id, &report))
+            .await;
+    }
+
+    /// Tell the admin how the broadcast goes; quiet hours don't apply.
+    async fn report(&self, channel: &str, recipient: &str, text: &str) {
+        if let Err(e) = self
+            .control
+            .send_with_priority(channel, recipient, text, Priority::Urgent)
+            .await
+        {
+            tracing::warn!("Failed to report announcement progress on {channel}: {e}");
+        }
+    }
+}
+
+/// Record an `/announce` invocation in the audit log.
+fn record(
+    channel: &str,
+    user_id: &str,
+    username: Option<&str>,
+    command: &str,
+    allowed: bool,
+    outcome: &Result<String, String>,
+) {
+    audit::record_admin_command(AdminCommandLog {
+        channel,
+        user_id,
+        username,
+        command,
+        role: roles::role_of(&format!("{channel}:{user_id}")).map(Role::as_str),
+        allowed,
+        error: outcome
+            .as_ref()
+            .err()
+            .filter(|_| allowed)
+            .map(String::as_str),
+    });
+}
+
+/// The first word of `text` and the trimmed rest, keeping the rest's line
+/// breaks.
+fn split_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if text.is_empty() {
+        return None;
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    Some((&text[..end], text[end..].trim()))
+}
+
+fn list_targets(targets: &[BroadcastTarget]) -> String {
+    let mut listed = targets
+        .iter()
+        .take(MAX_LISTED)
+        .map(|target| format!("{} → {}", target.channel, target.recipient))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if targets.len() > MAX_LISTED {
+        let _ = write!(listed, ", …and {} more", targets.len() - MAX_LISTED);
+    }
+    listed
+}
+
+/// `300` → `5m`, `90` → `90s`.
+fn format_secs(secs: u64) -> String {
+    match (secs / 60, secs % 60) {
+        (mins, 0) if mins > 0 => format!("{mins}m"),
+        _ => format!("{secs}s"),
+    }
+}
+
+fn summarize(id: &str, report: &BroadcastReport) -> String {
+    let total = report.results.len();
+    let succeeded = report.succeeded();
+    if report.is_complete_success() {
+        return format!("✅ Announcement {id} delivered to all {total} chats");
+    }
+    let mut summary =
+        format!("⚠️ Announcement {id} delivered to {succeeded} of {total} chats. Failed:");
+    let failed: Vec<_> = report.failed().collect();
+    for result in failed.iter().take(MAX_LISTED) {
+        let reason = result.outcome.as_ref().err().map_or("", String::as_str);
+        let _ = write!(
+            summary,
+            "\n• {} → {}: {reason}",
+            result.target.channel, result.target.recipient
+        );
+    }
+    if failed.len() > MAX_LISTED {
+        let _ = write!(summary, "\n…and {} more", failed.len() - MAX_LISTED);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::manager::ChannelManager;
+    use crate::config::schema::ChannelTagsConfig;
+    use async_trait::async_trait;
+
+    struct StubChannel {
+        name: &'static str,
+        fail: bool,
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Channel for StubChannel {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+            if self.fail {
+                anyhow::bail!("bot was kicked");
+            }
+            self.sent
+                .lock()
+                .push((recipient.to_string(), message.to_string()));
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn stub(name: &'static str, fail: bool) -> Arc<StubChannel> {
+        Arc::new(StubChannel {
+            name,
+            fail,
+            sent: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn setup(confirm_secs: u64) -> (Arc<Announcements>, Arc<StubChannel>, Arc<StubChannel>) {
+        let telegram = stub("telegram", false);
+        let slack = stub("slack", true);
+        let channels: Vec<Arc<dyn Channel>> = vec![telegram.clone(), slack.clone()];
+        let tagged = |recipient: &str| ChannelTagsConfig {
+            tags: vec!["staff".into()],
+            default_recipient: Some(recipient.into()),
+        };
+        let control = Arc::new(ChannelControl::new());
+        control.attach(
+            ChannelManager::from_channels(&channels).with_tags(HashMap::from([
+                ("telegram".to_string(), tagged("-100")),
+                ("slack".to_string(), tagged("C1")),
+            ])),
+        );
+        let config = AnnouncementsConfig {
+            admins: vec!["telegram:ops".into(), "telegram:other".into()],
+            confirm_secs,
+            progress_every: 1,
+            ..AnnouncementsConfig::default()
+        };
+        (Announcements::new(config, control), telegram, slack)
+    }
+
+    fn command(from: &str, content: &str) -> ChannelMessage {
+        ChannelMessage {
+            reply_target: "admin-chat".into(),
+            ..ChannelMessage::test("telegram", from, content)
+        }
+    }
+
+    fn text(reply: Option<AnnounceReply>) -> String {
+        match reply {
+            Some(AnnounceReply::Text(text)) => text,
+            other => panic!("expected a text reply, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn drafts_are_sent_only_when_their_author_confirms() {
+        let (announcements, telegram, _slack) = setup(300);
+        let announce = |from: &str, content: &str| {
+            text(announcements.handle_command(telegram.as_ref(), &command(from, content)))
+        };
+
+        assert!(announce("guest", "/announce staff hi").contains("Only announcement admins"));
+        let preview = announce("ops", "/announce staff Office closed\non Friday");
+        assert!(preview.contains("to 2 chats tagged staff"), "{preview}");
+        assert!(preview.contains("Office closed\non Friday"), "{preview}");
+        assert!(preview.contains("slack → C1, telegram → -100"), "{preview}");
+        assert!(telegram.sent.lock().is_empty());
+
+        let id = announcements.drafts.lock().keys().next().cloned().unwrap();
+        let refused = announce("other", &format!("/announce send {id}"));
+        assert!(refused.contains("Only the admin who drafted"), "{refused}");
+        let sending = announce("ops", &format!("/announce send {id}"));
+        assert_eq!(sending, "📣 Sending announcement to 2 chats…");
+        assert!(announce("ops", &format!("/announce send {id}")).contains("No announcement"));
+
+        let summary = loop {
+            let last = telegram.sent.lock().last().cloned();
+            match last {
+                Some((_, message)) if message.contains("delivered") => break message,
+                _ => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let sent = telegram.sent.lock().clone();
+        assert!(sent[0].1.contains("1/2 chats, 1 failed"), "{sent:?}");
+        assert_eq!(sent[1], ("-100".into(), "Office closed\non Friday".into()));
+        assert!(
+            summary.contains("delivered to 1 of 2 chats")
+                && summary.contains("slack → C1: bot was kicked"),
+            "{summary}"
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_or_cancelled_drafts_are_dropped() {
+        let (announcements, telegram, _slack) = setup(0);
+        let announce = |content: &str| {
+            text(announcements.handle_command(telegram.as_ref(), &command("ops", content)))
+        };
+
+        announce("/announce tag:staff hi");
+        let id = announcements.drafts.lock().keys().next().cloned().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(announce(&format!("/announce send {id}")).contains("expired"));
+        assert!(announce("/announce nobody hi").contains("No channel tagged nobody"));
+
+        let (announcements, telegram, _slack) = setup(300);
+        let click = |callback_id: String| Interaction {
+            channel: "telegram".into(),
+            callback_id,
+            user_id: "ops".into(),
+            user_name: None,
+            reply_target: "admin-chat".into(),
+            message_id: None,
+        };
+        text(
+            announcements.handle_command(telegram.as_ref(), &command("ops", "/announce staff hi")),
+        );
+        let id = announcements.drafts.lock().keys().next().cloned().unwrap();
+        let reply = announcements.handle_click(&click(format!("{HANDLER}:{id}:cancel")));
+        assert_eq!(reply, format!("Announcement {id} cancelled."));
+        assert!(announcements.drafts.lock().is_empty());
+    }
+}
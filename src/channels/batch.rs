@@ -0,0 +1,126 @@
+//! Sending several messages to one recipient ([`Channel::send_batch`]).
+//!
+//! Long multi-part replies and digests (a feed's new entries) go out as a
+//! batch: in order, one at a time, [`Channel::send_interval`] apart so the
+//! platform's per-chat rate limit isn't hit halfway through. A failed message
+//! stops the batch, since the rest would arrive out of order.
+
+use super::middleware::OutboundMessage;
+use super::traits::Channel;
+use anyhow::{Context, Result};
+
+/// Send `messages` to `recipient` in order, pacing them by the channel's
+/// send interval.
+pub async fn send_paced<C: Channel + ?Sized>(
+    channel: &C,
+    recipient: &str,
+    messages: Vec<OutboundMessage>,
+) -> Result<()> {
+    let total = messages.len();
+    let interval = channel.send_interval();
+    for (index, message) in messages.into_iter().enumerate() {
+        if index > 0 && !interval.is_zero() {
+            tokio::time::sleep(interval).await;
+        }
+        channel
+            .send(&message.content, recipient)
+            .await
+            .with_context(|| {
+                format!(
+                    "{} sent {index} of {total} messages before failing",
+                    channel.name()
+                )
+            })?;
+    }
+    Ok(())
+}
+
+/// A batch of `parts` for `recipient` on `channel`, skipping blank ones.
+pub fn outbound_batch(
+    channel: &str,
+    recipient: &str,
+    parts: impl IntoIterator<Item = String>,
+) -> Vec<OutboundMessage> {
+    parts
+        .into_iter()
+        .filter(|part| !part.trim().is_empty())
+        .map(|content| OutboundMessage {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            content,
+            reply_to: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::ChannelMessage;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use std::time::{Duration, Instant};
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, Instant)>>,
+        fail_on: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(&self, message: &str, _recipient: &str) -> anyhow::Result<()> {
+            if self.fail_on == Some(message) {
+                anyhow::bail!("rate limited");
+            }
+            self.sent.lock().push((message.to_string(), Instant::now()));
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn send_interval(&self) -> Duration {
+            Duration::from_millis(20)
+        }
+    }
+
+    fn parts(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(ToString::to_string).collect()
+    }
+
+    #[tokio::test]
+    async fn sends_in_order_paced_by_the_channel() {
+        let channel = RecordingChannel::default();
+        let batch = outbound_batch("recording", "chat", parts(&["one", " ", "two", "three"]));
+        channel.send_batch("chat", batch).await.unwrap();
+
+        let sent = channel.sent.lock();
+        let texts: Vec<&str> = sent.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(texts, ["one", "two", "three"]);
+        for pair in sent.windows(2) {
+            assert!(pair[1].1 - pair[0].1 >= Duration::from_millis(20));
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_failure() {
+        let channel = RecordingChannel {
+            fail_on: Some("two"),
+            ..RecordingChannel::default()
+        };
+        let batch = outbound_batch("recording", "chat", parts(&["one", "two", "three"]));
+        let err = channel.send_batch("chat", batch).await.unwrap_err();
+
+        assert!(err.to_string().contains("sent 1 of 3"), "{err}");
+        assert_eq!(channel.sent.lock().len(), 1);
+    }
+}
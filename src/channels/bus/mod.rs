@@ -0,0 +1,410 @@
+//! Message bus for running several instances (`[bus]`), over Redis Streams
+//! or NATS JetStream.
+//!
+//! Gateway instances hold the platform connections. Instead of running the
+//! agent they publish every inbound message to the shared inbound queue and
+//! record themselves as owner of their channels. Worker instances read the
+//! inbound queue as one consumer group, so each message goes to exactly one
+//! worker, and answer it through proxy channels that queue the reply for
+//! the owning gateway, which sends it. Messages are acknowledged as soon as
+//! they are read: a worker that dies mid-reply loses that message, it is
+//! never answered twice.
+
+#[cfg(feature = "bus-nats")]
+mod nats;
+#[cfg(feature = "bus-redis")]
+mod redis_streams;
+
+use super::traits::{Channel, ChannelCapabilities, ChannelMessage};
+use crate::config::schema::{BusBackend, BusConfig, BusRole};
+use crate::security::ManagedSecret;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Pause after a bus error before reconnecting.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Attempts to publish one inbound message before it is dropped.
+const PUBLISH_ATTEMPTS: u32 = 3;
+
+/// A reply on its way to the gateway owning `channel`.
+#[derive(Debug, Serialize, Deserialize)]
+struct OutboundEntry {
+    channel: String,
+    recipient: String,
+    content: String,
+}
+
+/// Which queue a [`Reader`] consumes.
+#[derive(Debug, Clone, Copy)]
+enum Queue {
+    /// Inbound messages, shared by all workers
+    Inbound,
+    /// Replies for this gateway
+    Outbound,
+}
+
+/// A bus backend. Payloads are JSON documents.
+#[async_trait]
+trait Transport: Send + Sync {
+    /// Queue an inbound message from `channel` for the workers.
+    async fn publish_inbound(&self, channel: &str, payload: &str) -> Result<()>;
+
+    /// Queue a reply on `channel` for the gateway `owner`.
+    async fn publish_outbound(&self, owner: &str, channel: &str, payload: &str) -> Result<()>;
+
+    /// Record this instance as the owner of `channels` and make sure
+    /// replies queued for it from now on are kept.
+    async fn register(&self, channels: &[String]) -> Result<()>;
+
+    /// Registered channels and their owners.
+    async fn owners(&self) -> Result<HashMap<String, String>>;
+
+    /// The owner of `channel`, if a gateway registered it.
+    async fn owner(&self, channel: &str) -> Result<Option<String>>;
+
+    /// A reader of `queue` on a connection of its own.
+    async fn reader(&self, queue: Queue) -> Result<Box<dyn Reader>>;
+}
+
+#[async_trait]
+trait Reader: Send {
+    /// The next payloads, acknowledged. Waits a few seconds for some to
+    /// arrive and returns none if nothing did.
+    async fn read(&mut self) -> Result<Vec<String>>;
+}
+
+pub struct Bus {
+    transport: Box<dyn Transport>,
+    role: BusRole,
+}
+
+#[cfg(feature = "bus-redis")]
+async fn connect_redis(
+    url: &str,
+    config: &BusConfig,
+    instance: &str,
+) -> Result<Box<dyn Transport>> {
+    Ok(Box::new(
+        redis_streams::RedisStreams::connect(url, &config.prefix, instance, config.max_len).await?,
+    ))
+}
+
+#[cfg(not(feature = "bus-redis"))]
+#[allow(clippy::unused_async)]
+async fn connect_redis(
+    _url: &str,
+    _config: &BusConfig,
+    _instance: &str,
+) -> Result<Box<dyn Transport>> {
+    bail!("[bus] backend \"redis\" needs a build with --features bus-redis")
+}
+
+#[cfg(feature = "bus-nats")]
+async fn connect_nats(url: &str, config: &BusConfig, instance: &str) -> Result<Box<dyn Transport>> {
+    Ok(Box::new(
+        nats::Nats::connect(url, &config.prefix, instance, config.max_len).await?,
+    ))
+}
+
+#[cfg(not(feature = "bus-nats"))]
+#[allow(clippy::unused_async)]
+async fn connect_nats(
+    _url: &str,
+    _config: &BusConfig,
+    _instance: &str,
+) -> Result<Box<dyn Transport>> {
+    bail!("[bus] backend \"nats\" needs a build with --features bus-nats")
+}
+
+impl Bus {
+    /// Connect to the configured bus, or `None` when it's disabled.
+    /// `resolve` turns the configured URL reference into a managed secret.
+    pub async fn connect(
+        config: &BusConfig,
+        resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Option<Arc<Self>>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let raw_url = config
+            .url
+            .as_deref()
+            .unwrap_or_else(|| config.backend.default_url());
+        let url = resolve(raw_url)?.get().await?;
+        let instance = config.instance_id.clone().unwrap_or_else(|| {
+            hostname::get().map_or_else(|_| "zeroclaw".into(), |h| h.to_string_lossy().to_string())
+        });
+        // Both fail at startup rather than on the first message
+        let transport = match config.backend {
+            BusBackend::Redis => connect_redis(&url, config, &instance).await?,
+            BusBackend::Nats => connect_nats(&url, config, &instance).await?,
+        };
+        tracing::info!(
+            "Message bus: {instance} as {:?} over {:?} ({})",
+            config.role,
+            config.backend,
+            config.prefix
+        );
+        Ok(Some(Arc::new(Self {
+            transport,
+            role: config.role,
+        })))
+    }
+
+    pub fn role(&self) -> BusRole {
+        self.role
+    }
+
+    // ── Gateway side ─────────────────────────────────────────────
+
+    /// Record this instance as the owner of `channels`, so workers route
+    /// their replies here.
+    pub async fn register_channels<'a>(
+        &self,
+        channels: impl Iterator<Item = &'a String>,
+    ) -> Result<()> {
+        let names: Vec<String> = channels.cloned().collect();
+        self.transport.register(&names).await
+    }
+
+    /// Publish the messages from the channel listeners to the inbound
+    /// queue until they all stop.
+    pub async fn forward_inbound(self: Arc<Self>, mut rx: mpsc::Receiver<ChannelMessage>) {
+        while let Some(msg) = rx.recv().await {
+            let payload = match serde_json::to_string(&msg) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::error!("Message bus: cannot encode message {}: {e}", msg.id);
+                    continue;
+                }
+            };
+            for attempt in 1..=PUBLISH_ATTEMPTS {
+                match self.transport.publish_inbound(&msg.channel, &payload).await {
+                    Ok(()) => break,
+                    Err(e) if attempt == PUBLISH_ATTEMPTS => {
+                        tracing::error!(
+                            "Message bus: dropped message {} from {}: {e}",
+                            msg.id,
+                            msg.channel
+                        );
+                    }
+                    Err(e) => {
+                        tracing::warn!("Message bus: publish failed ({e}); retrying");
+                        tokio::time::sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send the replies workers queue for this instance through `channels`.
+    /// Runs until aborted.
+    pub async fn deliver_outbound(
+        self: Arc<Self>,
+        channels: Arc<HashMap<String, Arc<dyn Channel>>>,
+    ) {
+        loop {
+            let result: Result<()> = async {
+                let mut reader = self.transport.reader(Queue::Outbound).await?;
+                loop {
+                    for payload in reader.read().await? {
+                        deliver(&channels, &payload).await;
+                    }
+                }
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!("Message bus: outbound delivery interrupted: {e}");
+            }
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    }
+
+    // ── Worker side ──────────────────────────────────────────────
+
+    /// Proxies for every channel a gateway has registered. Waits for the
+    /// first gateway to come up.
+    pub async fn remote_channels(self: &Arc<Self>) -> Result<Vec<Arc<dyn Channel>>> {
+        loop {
+            let owners = self.transport.owners().await?;
+            if !owners.is_empty() {
+                let mut names: Vec<String> = owners.into_keys().collect();
+                names.sort();
+                return Ok(names
+                    .into_iter()
+                    .map(|name| {
+                        Arc::new(RemoteChannel {
+                            name,
+                            bus: Arc::clone(self),
+                        }) as Arc<dyn Channel>
+                    })
+                    .collect());
+            }
+            tracing::info!("Message bus: waiting for a gateway to register its channels");
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    }
+
+    /// Read inbound messages as a member of the worker group and hand them
+    /// to the dispatcher, until it goes away.
+    pub async fn consume_inbound(self: Arc<Self>, tx: mpsc::Sender<ChannelMessage>) {
+        while !tx.is_closed() {
+            let result: Result<()> = async {
+                let mut reader = self.transport.reader(Queue::Inbound).await?;
+                loop {
+                    for payload in reader.read().await? {
+                        match serde_json::from_str::<ChannelMessage>(&payload) {
+                            Ok(msg) => {
+                                if tx.send(msg).await.is_err() {
+                                    return Ok(());
+                                }
+                            }
+                            Err(e) => tracing::warn!("Message bus: malformed inbound entry: {e}"),
+                        }
+                    }
+                }
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!("Message bus: inbound read interrupted: {e}");
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+
+    async fn send_outbound(&self, channel: &str, recipient: &str, content: &str) -> Result<()> {
+        let Some(owner) = self.transport.owner(channel).await? else {
+            bail!("No gateway owns channel {channel}");
+        };
+        let entry = OutboundEntry {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            content: content.to_string(),
+        };
+        self.transport
+            .publish_outbound(&owner, channel, &serde_json::to_string(&entry)?)
+            .await
+    }
+}
+
+/// Send one outbound entry through the local channel it names.
+async fn deliver(channels: &HashMap<String, Arc<dyn Channel>>, payload: &str) {
+    let entry: OutboundEntry = match serde_json::from_str(payload) {
+        Ok(entry) => entry,
+        Err(e) => {
+            tracing::warn!("Message bus: malformed outbound entry: {e}");
+            return;
+        }
+    };
+    let Some(channel) = channels.get(&entry.channel) else {
+        tracing::warn!(
+            "Message bus: reply for {} which this gateway doesn't run",
+            entry.channel
+        );
+        return;
+    };
+    if let Err(e) = channel.send(&entry.content, &entry.recipient).await {
+        tracing::error!(
+            "Message bus: sending to {} on {} failed: {e}",
+            entry.recipient,
+            entry.channel
+        );
+    }
+}
+
+/// A gateway's channel as seen from a worker: sends go over the bus.
+struct RemoteChannel {
+    name: String,
+    bus: Arc<Bus>,
+}
+
+#[async_trait]
+impl Channel for RemoteChannel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        // The gateway's channel is not known here; assume plain text
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+        self.bus.send_outbound(&self.name, recipient, message).await
+    }
+
+    async fn listen(&self, _tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
+        // The gateway listens; workers consume the inbound stream instead.
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::{MediaAttachment, MediaKind};
+
+    #[test]
+    fn messages_survive_the_wire_format() {
+        let msg = ChannelMessage {
+            id: "m-1".into(),
+            sender: "alice".into(),
+            reply_target: "chat-9".into(),
+            content: "hello".into(),
+            channel: "telegram".into(),
+            timestamp: 1_700_000_000,
+            received_at: 1_700_000_001,
+            attachments: vec![MediaAttachment {
+                kind: MediaKind::Voice,
+                source: "file-1".into(),
+                mime_type: Some("audio/ogg".into()),
+                file_name: None,
+                stored_key: None,
+            }],
+            sender_id: Some("42".into()),
+            sender_name: Some("Alice".into()),
+            conversation_id: Some("chat-9".into()),
+            mentions: vec!["7".into()],
+            is_mention: true,
+        };
+        let wire = serde_json::to_string(&msg).unwrap();
+        assert!(wire.contains(r#""kind":"voice""#));
+        let back: ChannelMessage = serde_json::from_str(&wire).unwrap();
+        assert_eq!(back.id, msg.id);
+        assert_eq!(back.attachments, msg.attachments);
+        assert_eq!(back.sender_id, msg.sender_id);
+        assert!(back.is_mention);
+    }
+
+    #[tokio::test]
+    async fn outbound_entries_reach_the_named_channel() {
+        use crate::channels::cli::CliChannel;
+
+        let channels: HashMap<String, Arc<dyn Channel>> = HashMap::from([(
+            "cli".to_string(),
+            Arc::new(CliChannel::new()) as Arc<dyn Channel>,
+        )]);
+        let payload = serde_json::to_string(&OutboundEntry {
+            channel: "cli".into(),
+            recipient: "user".into(),
+            content: "hi".into(),
+        })
+        .unwrap();
+        // Neither a known nor an unknown channel panics
+        deliver(&channels, &payload).await;
+        deliver(&channels, &payload.replace("\"cli\"", "\"irc\"")).await;
+        deliver(&channels, "not json").await;
+    }
+}
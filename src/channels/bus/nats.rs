@@ -0,0 +1,250 @@
+//! NATS JetStream transport, with a subject per channel:
+//!
+//! ```text
+//! <prefix>.inbound.<channel>            stream <prefix>-inbound
+//! <prefix>.outbound.<instance>.<channel> stream <prefix>-outbound
+//! ```
+//!
+//! Workers share the durable pull consumer `workers` on the inbound
+//! stream, and each gateway has the durable consumer `delivery-<instance>`
+//! for its replies, so messages published while nobody reads wait in the
+//! stream. Channel owners live in the `<prefix>-owners` key-value bucket.
+//! Names that aren't valid subject tokens have the offending characters
+//! replaced by `_`.
+
+use super::{Queue, Reader, Transport};
+use anyhow::{anyhow, bail, Context, Result};
+use async_nats::jetstream::{self, consumer, kv, stream};
+use async_trait::async_trait;
+use futures_util::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Durable consumer the workers share on the inbound stream.
+const WORKER_CONSUMER: &str = "workers";
+
+/// How long a read waits for a message before returning empty.
+const READ_WAIT: Duration = Duration::from_secs(5);
+
+/// Value of an owners bucket entry. The key is the channel name as a
+/// token, so the name itself is kept here.
+#[derive(Debug, Serialize, Deserialize)]
+struct OwnerEntry {
+    channel: String,
+    owner: String,
+}
+
+/// `name` as a single subject token or bucket key.
+fn token(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub(super) struct Nats {
+    js: jetstream::Context,
+    owners: kv::Store,
+    prefix: String,
+    instance: String,
+    max_len: i64,
+}
+
+impl Nats {
+    pub(super) async fn connect(
+        url: &str,
+        prefix: &str,
+        instance: &str,
+        max_len: usize,
+    ) -> Result<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .context("Failed to connect to the message bus")?;
+        let js = jetstream::new(client);
+        let prefix = token(prefix);
+        let owners_bucket = format!("{prefix}-owners");
+        let owners = match js.get_key_value(&owners_bucket).await {
+            Ok(store) => store,
+            Err(_) => js
+                .create_key_value(kv::Config {
+                    bucket: owners_bucket,
+                    history: 1,
+                    ..Default::default()
+                })
+                .await
+                .context("Failed to create the owners bucket")?,
+        };
+        let transport = Self {
+            js,
+            owners,
+            prefix,
+            instance: token(instance),
+            max_len: i64::try_from(max_len).unwrap_or(i64::MAX),
+        };
+        transport.ensure_consumer(Queue::Inbound).await?;
+        Ok(transport)
+    }
+
+    fn inbound_subject(&self, channel: &str) -> String {
+        format!("{}.inbound.{}", self.prefix, token(channel))
+    }
+
+    fn outbound_subject(&self, owner: &str, channel: &str) -> String {
+        format!(
+            "{}.outbound.{}.{}",
+            self.prefix,
+            token(owner),
+            token(channel)
+        )
+    }
+
+    /// The stream, its subjects, and the durable consumer name and subject
+    /// filter behind `queue`.
+    fn consumer_spec(&self, queue: Queue) -> (String, String, String, String) {
+        let prefix = &self.prefix;
+        match queue {
+            Queue::Inbound => (
+                format!("{prefix}-inbound"),
+                format!("{prefix}.inbound.>"),
+                WORKER_CONSUMER.to_string(),
+                format!("{prefix}.inbound.>"),
+            ),
+            Queue::Outbound => (
+                format!("{prefix}-outbound"),
+                format!("{prefix}.outbound.>"),
+                format!("delivery-{}", self.instance),
+                format!("{prefix}.outbound.{}.>", self.instance),
+            ),
+        }
+    }
+
+    /// Create the stream and durable consumer behind `queue` unless they
+    /// exist. New consumers start with the next message published.
+    async fn ensure_consumer(&self, queue: Queue) -> Result<consumer::PullConsumer> {
+        let (stream_name, subjects, durable, filter) = self.consumer_spec(queue);
+        let stream = self
+            .js
+            .get_or_create_stream(stream::Config {
+                name: stream_name.clone(),
+                subjects: vec![subjects],
+                max_messages: self.max_len,
+                ..Default::default()
+            })
+            .await
+            .with_context(|| format!("Failed to create stream {stream_name}"))?;
+        stream
+            .get_or_create_consumer(
+                &durable,
+                consumer::pull::Config {
+                    durable_name: Some(durable.clone()),
+                    filter_subject: filter,
+                    deliver_policy: consumer::DeliverPolicy::New,
+                    ack_policy: consumer::AckPolicy::Explicit,
+                    ..Default::default()
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to create consumer {durable}"))
+    }
+
+    async fn publish(&self, subject: String, payload: &str) -> Result<()> {
+        self.js
+            .publish(subject, payload.to_string().into())
+            .await?
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Transport for Nats {
+    async fn publish_inbound(&self, channel: &str, payload: &str) -> Result<()> {
+        self.publish(self.inbound_subject(channel), payload).await
+    }
+
+    async fn publish_outbound(&self, owner: &str, channel: &str, payload: &str) -> Result<()> {
+        self.publish(self.outbound_subject(owner, channel), payload)
+            .await
+    }
+
+    async fn register(&self, channels: &[String]) -> Result<()> {
+        for name in channels {
+            let entry = OwnerEntry {
+                channel: name.clone(),
+                owner: self.instance.clone(),
+            };
+            self.owners
+                .put(token(name), serde_json::to_vec(&entry)?.into())
+                .await?;
+        }
+        self.ensure_consumer(Queue::Outbound).await?;
+        Ok(())
+    }
+
+    async fn owners(&self) -> Result<HashMap<String, String>> {
+        let keys: Vec<String> = self.owners.keys().await?.try_collect().await?;
+        let mut owners = HashMap::new();
+        for key in keys {
+            if let Some(value) = self.owners.get(&key).await? {
+                let entry: OwnerEntry = serde_json::from_slice(&value)?;
+                owners.insert(entry.channel, entry.owner);
+            }
+        }
+        Ok(owners)
+    }
+
+    async fn owner(&self, channel: &str) -> Result<Option<String>> {
+        let Some(value) = self.owners.get(token(channel)).await? else {
+            return Ok(None);
+        };
+        let entry: OwnerEntry = serde_json::from_slice(&value)?;
+        Ok(Some(entry.owner))
+    }
+
+    async fn reader(&self, queue: Queue) -> Result<Box<dyn Reader>> {
+        let messages = self.ensure_consumer(queue).await?.messages().await?;
+        Ok(Box::new(ConsumerReader { messages }))
+    }
+}
+
+struct ConsumerReader {
+    messages: consumer::pull::Stream,
+}
+
+#[async_trait]
+impl Reader for ConsumerReader {
+    async fn read(&mut self) -> Result<Vec<String>> {
+        let msg = match tokio::time::timeout(READ_WAIT, self.messages.next()).await {
+            Err(_) => return Ok(Vec::new()),
+            Ok(None) => bail!("Consumer closed"),
+            Ok(Some(msg)) => msg?,
+        };
+        msg.ack().await.map_err(|e| anyhow!(e))?;
+        match String::from_utf8(msg.payload.to_vec()) {
+            Ok(payload) => Ok(vec![payload]),
+            Err(_) => {
+                tracing::warn!("Message bus: dropped non-UTF-8 message on {}", msg.subject);
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_become_single_subject_tokens() {
+        assert_eq!(token("telegram"), "telegram");
+        assert_eq!(token("slack:work"), "slack_work");
+        assert_eq!(token("a.b *>"), "a_b___");
+        assert_eq!(token("worker-1_eu"), "worker-1_eu");
+    }
+}
@@ -0,0 +1,207 @@
+//! Redis Streams transport. Inbound messages go to `<prefix>:inbound`, read
+//! by the workers as one consumer group; replies for a gateway go to
+//! `<prefix>:outbound:<instance>`; `<prefix>:owners` maps channel names to
+//! the gateway running them.
+
+use super::{Queue, Reader, Transport};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::aio::{ConnectionManager, MultiplexedConnection};
+use redis::streams::StreamReadReply;
+use std::collections::HashMap;
+
+/// Group of the workers on the inbound stream.
+const WORKER_GROUP: &str = "workers";
+
+/// Group of a gateway on its own outbound stream.
+const DELIVERY_GROUP: &str = "delivery";
+
+/// How long a stream read blocks before it is issued again.
+const BLOCK_MS: u64 = 5_000;
+
+const READ_BATCH: usize = 32;
+
+pub(super) struct RedisStreams {
+    client: redis::Client,
+    /// Reconnecting connection for everything but blocking reads
+    manager: ConnectionManager,
+    prefix: String,
+    instance: String,
+    max_len: usize,
+}
+
+impl RedisStreams {
+    pub(super) async fn connect(
+        url: &str,
+        prefix: &str,
+        instance: &str,
+        max_len: usize,
+    ) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid [bus] url")?;
+        // Fails at startup rather than on the first message
+        let manager = ConnectionManager::new(client.clone())
+            .await
+            .context("Failed to connect to the message bus")?;
+        let transport = Self {
+            client,
+            manager,
+            prefix: prefix.to_string(),
+            instance: instance.to_string(),
+            max_len,
+        };
+        transport
+            .ensure_group(&transport.inbound_stream(), WORKER_GROUP)
+            .await?;
+        Ok(transport)
+    }
+
+    fn inbound_stream(&self) -> String {
+        format!("{}:inbound", self.prefix)
+    }
+
+    fn owners_key(&self) -> String {
+        format!("{}:owners", self.prefix)
+    }
+
+    fn outbound_stream(&self, owner: &str) -> String {
+        format!("{}:outbound:{owner}", self.prefix)
+    }
+
+    /// Create `group` on `stream` (and the stream) unless it exists. New
+    /// groups start at the end of the stream.
+    async fn ensure_group(&self, stream: &str, group: &str) -> Result<()> {
+        let mut conn = self.manager.clone();
+        let created: redis::RedisResult<()> = redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(stream)
+            .arg(group)
+            .arg("$")
+            .arg("MKSTREAM")
+            .query_async(&mut conn)
+            .await;
+        match created {
+            Err(e) if e.code() != Some("BUSYGROUP") => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn append(&self, stream: &str, payload: &str) -> Result<()> {
+        let mut conn = self.manager.clone();
+        let _: String = redis::cmd("XADD")
+            .arg(stream)
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(self.max_len)
+            .arg("*")
+            .arg("msg")
+            .arg(payload)
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Transport for RedisStreams {
+    async fn publish_inbound(&self, _channel: &str, payload: &str) -> Result<()> {
+        self.append(&self.inbound_stream(), payload).await
+    }
+
+    async fn publish_outbound(&self, owner: &str, _channel: &str, payload: &str) -> Result<()> {
+        self.append(&self.outbound_stream(owner), payload).await
+    }
+
+    async fn register(&self, channels: &[String]) -> Result<()> {
+        if !channels.is_empty() {
+            let mut conn = self.manager.clone();
+            let mut hset = redis::cmd("HSET");
+            hset.arg(self.owners_key());
+            for name in channels {
+                hset.arg(name).arg(&self.instance);
+            }
+            let _: u64 = hset.query_async(&mut conn).await?;
+        }
+        self.ensure_group(&self.outbound_stream(&self.instance), DELIVERY_GROUP)
+            .await
+    }
+
+    async fn owners(&self) -> Result<HashMap<String, String>> {
+        let mut conn = self.manager.clone();
+        Ok(redis::cmd("HGETALL")
+            .arg(self.owners_key())
+            .query_async(&mut conn)
+            .await?)
+    }
+
+    async fn owner(&self, channel: &str) -> Result<Option<String>> {
+        let mut conn = self.manager.clone();
+        Ok(redis::cmd("HGET")
+            .arg(self.owners_key())
+            .arg(channel)
+            .query_async(&mut conn)
+            .await?)
+    }
+
+    async fn reader(&self, queue: Queue) -> Result<Box<dyn Reader>> {
+        let (stream, group) = match queue {
+            Queue::Inbound => (self.inbound_stream(), WORKER_GROUP),
+            Queue::Outbound => (self.outbound_stream(&self.instance), DELIVERY_GROUP),
+        };
+        // A connection of its own, for a loop of blocking reads
+        let conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .context("Failed to connect to the message bus")?;
+        Ok(Box::new(StreamReader {
+            conn,
+            stream,
+            group,
+            consumer: self.instance.clone(),
+        }))
+    }
+}
+
+struct StreamReader {
+    conn: MultiplexedConnection,
+    stream: String,
+    group: &'static str,
+    consumer: String,
+}
+
+#[async_trait]
+impl Reader for StreamReader {
+    async fn read(&mut self) -> Result<Vec<String>> {
+        let reply: Option<StreamReadReply> = redis::cmd("XREADGROUP")
+            .arg("GROUP")
+            .arg(self.group)
+            .arg(&self.consumer)
+            .arg("COUNT")
+            .arg(READ_BATCH)
+            .arg("BLOCK")
+            .arg(BLOCK_MS)
+            .arg("STREAMS")
+            .arg(&self.stream)
+            .arg(">")
+            .query_async(&mut self.conn)
+            .await?;
+        let entries: Vec<_> = reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .collect();
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut ack = redis::cmd("XACK");
+        ack.arg(&self.stream).arg(self.group);
+        for entry in &entries {
+            ack.arg(&entry.id);
+        }
+        let _: u64 = ack.query_async(&mut self.conn).await?;
+        Ok(entries
+            .iter()
+            .filter_map(|entry| entry.get::<String>("msg"))
+            .collect())
+    }
+}
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use tokio::io::{self, AsyncBufReadExt, BufReader};
 use uuid::Uuid;
@@ -18,6 +18,16 @@ impl Channel for CliChannel {
         "cli"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, _recipient: &str) -> anyhow::Result<()> {
         println!("{message}");
         Ok(())
@@ -43,10 +53,14 @@ impl Channel for CliChannel {
                 reply_target: "user".to_string(),
                 content: line,
                 channel: "cli".to_string(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs(),
+                timestamp: unix_now(),
+                received_at: unix_now(),
+                attachments: vec![],
+                sender_id: None,
+                sender_name: None,
+                conversation_id: None,
+                mentions: vec![],
+                is_mention: false,
             };
 
             if tx.send(msg).await.is_err() {
@@ -90,11 +104,9 @@ mod tests {
     fn channel_message_struct() {
         let msg = ChannelMessage {
             id: "test-id".into(),
-            sender: "user".into(),
-            reply_target: "user".into(),
-            content: "hello".into(),
-            channel: "cli".into(),
             timestamp: 1_234_567_890,
+            received_at: 1_234_567_890,
+            ..ChannelMessage::test("cli", "user", "hello")
         };
         assert_eq!(msg.id, "test-id");
         assert_eq!(msg.sender, "user");
@@ -108,11 +120,7 @@ mod tests {
     fn channel_message_clone() {
         let msg = ChannelMessage {
             id: "id".into(),
-            sender: "s".into(),
-            reply_target: "s".into(),
-            content: "c".into(),
-            channel: "ch".into(),
-            timestamp: 0,
+            ..ChannelMessage::test("ch", "s", "c")
         };
         let cloned = msg.clone();
         assert_eq!(cloned.id, msg.id);
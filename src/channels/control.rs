@@ -0,0 +1,723 @@
+//! Runtime control state shared between the channel runtime and the admin API.
+//!
+//! Holds everything an operator can change or inspect while channels are
+//! running: which channels are muted, which users are blocked, replies held
+//! back for muted channels (the outbox), a ring buffer of recent channel
+//! errors, pending config reload requests, taps of accepted inbound messages
+//! and delivered replies for external subscribers, the usage ledger when
+//! quotas are enabled, the response cache when it is, the eraser of user
+//! data, the message archive, usage analytics when enabled, shadow channel
+//! replies, the running config and queue depths, and notifications buffered
+//! for digests or held for quiet hours. The channel runtime consults it on
+//! every reply; the admin and gRPC APIs read and mutate it.
+
+use super::batch::outbound_batch;
+use super::digest::Digests;
+use super::erasure::DataEraser;
+use super::history::MessageArchive;
+use super::inbound_queue::InboundQueue;
+use super::lanes::ConversationLanes;
+use super::manager::{tag_selector, BroadcastTarget, ChannelManager};
+use super::priority::Priority;
+use super::quiet_hours::{self, DeferredQueue};
+use super::shadow::ShadowLog;
+use super::traits::ChannelMessage;
+use crate::analytics::Analytics;
+use crate::config::{Config, DigestConfig};
+use crate::cost::QuotaLedger;
+use crate::groups::GroupStore;
+use crate::memory::ResponseCache;
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+
+/// Number of recent channel errors retained for inspection.
+pub const RECENT_ERRORS_CAPACITY: usize = 100;
+
+/// Inbound messages buffered per subscriber before slow subscribers lag.
+pub const INBOUND_TAP_CAPACITY: usize = 256;
+
+/// Delivered replies buffered per subscriber before slow subscribers lag.
+pub const OUTBOUND_TAP_CAPACITY: usize = 256;
+
+/// A reply delivered to a channel, as seen by outbound subscribers.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboundRecord {
+    pub channel: String,
+    pub recipient: String,
+    pub content: String,
+    /// ID of the inbound message answered
+    pub in_reply_to: Option<String>,
+    pub at: String,
+}
+
+/// Reply held back because its channel was muted.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEntry {
+    pub channel: String,
+    pub recipient: String,
+    pub message: String,
+    pub queued_at: String,
+}
+
+/// Messages buffered in one channel's inbound queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct InboundQueueDepth {
+    pub channel: String,
+    pub buffered: usize,
+    /// Messages dropped because the queue was full, since the listener
+    /// started
+    pub dropped: u64,
+}
+
+/// How much work is waiting, queue by queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDepths {
+    pub inbound: Vec<InboundQueueDepth>,
+    /// Conversations with a message being processed
+    pub active_conversations: usize,
+    /// Messages waiting behind one of their conversation
+    pub queued_messages: usize,
+    /// Replies held for muted channels
+    pub outbox: usize,
+    /// Notifications held for quiet hours
+    pub deferred: usize,
+    /// Notifications buffered for digests
+    pub digests: usize,
+}
+
+/// One recorded channel failure (listener crash or failed send).
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelErrorRecord {
+    pub channel: String,
+    pub error: String,
+    pub at: String,
+}
+
+pub struct ChannelControl {
+    manager: RwLock<Option<ChannelManager>>,
+    quotas: RwLock<Option<Arc<QuotaLedger>>>,
+    response_cache: RwLock<Option<Arc<ResponseCache>>>,
+    eraser: RwLock<Option<Arc<DataEraser>>>,
+    archive: RwLock<Option<Arc<MessageArchive>>>,
+    analytics: RwLock<Option<Arc<Analytics>>>,
+    shadows: RwLock<Option<Arc<ShadowLog>>>,
+    groups: RwLock<Option<Arc<GroupStore>>>,
+    config: RwLock<Option<Arc<Config>>>,
+    inbound_queues: Mutex<BTreeMap<String, Arc<InboundQueue>>>,
+    lanes: RwLock<Option<Arc<ConversationLanes<ChannelMessage>>>>,
+    muted: Mutex<BTreeSet<String>>,
+    /// `<channel>:<user id>` of blocked users, and when each block ends
+    blocked: Mutex<HashMap<String, Option<DateTime<Utc>>>>,
+    outbox: Mutex<VecDeque<OutboxEntry>>,
+    digests: Digests,
+    deferred: DeferredQueue,
+    errors: Mutex<VecDeque<ChannelErrorRecord>>,
+    pending_reload: Mutex<Option<Box<Config>>>,
+    reload_ready: Notify,
+    inbound: broadcast::Sender<ChannelMessage>,
+    outbound: broadcast::Sender<OutboundRecord>,
+}
+
+impl Default for ChannelControl {
+    fn default() -> Self {
+        Self {
+            manager: RwLock::new(None),
+            quotas: RwLock::new(None),
+            response_cache: RwLock::new(None),
+            eraser: RwLock::new(None),
+            archive: RwLock::new(None),
+            analytics: RwLock::new(None),
+            shadows: RwLock::new(None),
+            groups: RwLock::new(None),
+            config: RwLock::new(None),
+            inbound_queues: Mutex::new(BTreeMap::new()),
+            lanes: RwLock::new(None),
+            muted: Mutex::new(BTreeSet::new()),
+            blocked: Mutex::new(HashMap::new()),
+            outbox: Mutex::new(VecDeque::new()),
+            digests: Digests::default(),
+            deferred: DeferredQueue::default(),
+            errors: Mutex::new(VecDeque::new()),
+            pending_reload: Mutex::new(None),
+            reload_ready: Notify::new(),
+            inbound: broadcast::channel(INBOUND_TAP_CAPACITY).0,
+            outbound: broadcast::channel(OUTBOUND_TAP_CAPACITY).0,
+        }
+    }
+}
+
+impl ChannelControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish an accepted inbound message to external subscribers.
+    /// A no-op when nobody is subscribed.
+    pub fn publish_inbound(&self, msg: &ChannelMessage) {
+        if self.inbound.receiver_count() > 0 {
+            let _ = self.inbound.send(msg.clone());
+        }
+    }
+
+    /// Receive inbound messages accepted from now on.
+    pub fn subscribe_inbound(&self) -> broadcast::Receiver<ChannelMessage> {
+        self.inbound.subscribe()
+    }
+
+    /// Publish a delivered reply to external subscribers. A no-op when
+    /// nobody is subscribed.
+    pub fn publish_outbound(
+        &self,
+        channel: &str,
+        recipient: &str,
+        content: &str,
+        in_reply_to: Option<&str>,
+    ) {
+        if self.outbound.receiver_count() > 0 {
+            let _ = self.outbound.send(OutboundRecord {
+                channel: channel.to_string(),
+                recipient: recipient.to_string(),
+                content: content.to_string(),
+                in_reply_to: in_reply_to.map(str::to_string),
+                at: Utc::now().to_rfc3339(),
+            });
+        }
+    }
+
+    /// Receive replies delivered from now on.
+    pub fn subscribe_outbound(&self) -> broadcast::Receiver<OutboundRecord> {
+        self.outbound.subscribe()
+    }
+
+    /// Point the control plane at the channels of the current runtime.
+    pub fn attach(&self, manager: ChannelManager) {
+        *self.manager.write() = Some(manager);
+    }
+
+    /// Channels of the current runtime, if one is running.
+    pub fn manager(&self) -> Option<ChannelManager> {
+        self.manager.read().clone()
+    }
+
+    /// Expose the runtime's usage ledger (or `None` when quotas are off).
+    pub fn attach_quotas(&self, ledger: Option<Arc<QuotaLedger>>) {
+        *self.quotas.write() = ledger;
+    }
+
+    pub fn quotas(&self) -> Option<Arc<QuotaLedger>> {
+        self.quotas.read().clone()
+    }
+
+    /// Expose the runtime's response cache (or `None` when it is off).
+    pub fn attach_response_cache(&self, cache: Option<Arc<ResponseCache>>) {
+        *self.response_cache.write() = cache;
+    }
+
+    pub fn response_cache(&self) -> Option<Arc<ResponseCache>> {
+        self.response_cache.read().clone()
+    }
+
+    /// Expose the eraser over the runtime's stores.
+    pub fn attach_eraser(&self, eraser: Option<Arc<DataEraser>>) {
+        *self.eraser.write() = eraser;
+    }
+
+    pub fn eraser(&self) -> Option<Arc<DataEraser>> {
+        self.eraser.read().clone()
+    }
+
+    /// Expose the archive of auto-saved messages (or `None` when
+    /// auto-save is off).
+    pub fn attach_archive(&self, archive: Option<Arc<MessageArchive>>) {
+        *self.archive.write() = archive;
+    }
+
+    pub fn archive(&self) -> Option<Arc<MessageArchive>> {
+        self.archive.read().clone()
+    }
+
+    /// Expose the usage analytics collector (or `None` when analytics is
+    /// off).
+    pub fn attach_analytics(&self, analytics: Option<Arc<Analytics>>) {
+        *self.analytics.write() = analytics;
+    }
+
+    pub fn analytics(&self) -> Option<Arc<Analytics>> {
+        self.analytics.read().clone()
+    }
+
+    /// Expose the replies of shadow channels and their sources (or `None`
+    /// when no shadow is configured).
+    pub fn attach_shadows(&self, shadows: Option<Arc<ShadowLog>>) {
+        *self.shadows.write() = shadows;
+    }
+
+    pub fn shadows(&self) -> Option<Arc<ShadowLog>> {
+        self.shadows.read().clone()
+    }
+
+    /// Expose per-group settings (or `None` when group settings are off).
+    pub fn attach_groups(&self, groups: Option<Arc<GroupStore>>) {
+        *self.groups.write() = groups;
+    }
+
+    pub fn groups(&self) -> Option<Arc<GroupStore>> {
+        self.groups.read().clone()
+    }
+
+    /// Record the config the channel runtime is running with.
+    pub fn attach_config(&self, config: Arc<Config>) {
+        *self.config.write() = Some(config);
+    }
+
+    pub fn config(&self) -> Option<Arc<Config>> {
+        self.config.read().clone()
+    }
+
+    /// Track the inbound queue of `channel`'s listener, replacing the one
+    /// of an earlier listener.
+    pub fn register_inbound_queue(&self, channel: &str, queue: Arc<InboundQueue>) {
+        self.inbound_queues
+            .lock()
+            .insert(channel.to_string(), queue);
+    }
+
+    /// Track the conversation lanes of the dispatch loop.
+    pub fn attach_lanes(&self, lanes: Arc<ConversationLanes<ChannelMessage>>) {
+        *self.lanes.write() = Some(lanes);
+    }
+
+    /// Current depth of every queue. Inbound queues of channels no longer
+    /// running are left out.
+    pub fn queue_depths(&self) -> QueueDepths {
+        let running = self.manager().map(|manager| manager.names());
+        let inbound = self
+            .inbound_queues
+            .lock()
+            .iter()
+            .filter(|(channel, _)| running.as_ref().is_none_or(|names| names.contains(channel)))
+            .map(|(channel, queue)| InboundQueueDepth {
+                channel: channel.clone(),
+                buffered: queue.len(),
+                dropped: queue.dropped(),
+            })
+            .collect();
+        let lanes = self.lanes.read().clone();
+        QueueDepths {
+            inbound,
+            active_conversations: lanes.as_ref().map_or(0, |lanes| lanes.active_lanes()),
+            queued_messages: lanes.as_ref().map_or(0, |lanes| lanes.queued_items()),
+            outbox: self.outbox_len(),
+            deferred: self.deferred.len(),
+            digests: self.digests.buffered(),
+        }
+    }
+
+    /// Mute a channel. Returns `false` if it was already muted.
+    pub fn mute(&self, channel: &str) -> bool {
+        self.muted.lock().insert(channel.to_string())
+    }
+
+    /// Unmute a channel. Returns `false` if it was not muted.
+    pub fn unmute(&self, channel: &str) -> bool {
+        self.muted.lock().remove(channel)
+    }
+
+    pub fn is_muted(&self, channel: &str) -> bool {
+        self.muted.lock().contains(channel)
+    }
+
+    /// Muted channel names, sorted.
+    pub fn muted(&self) -> Vec<String> {
+        self.muted.lock().iter().cloned().collect()
+    }
+
+    /// Ignore messages from `user` on `channel` until `until`, or for good
+    /// when it is `None`.
+    pub fn block_user(&self, channel: &str, user: &str, until: Option<DateTime<Utc>>) {
+        self.blocked
+            .lock()
+            .insert(format!("{channel}:{user}"), until);
+    }
+
+    /// Unblock a user. Returns `false` if they were not blocked.
+    pub fn unblock_user(&self, channel: &str, user: &str) -> bool {
+        self.blocked
+            .lock()
+            .remove(&format!("{channel}:{user}"))
+            .is_some()
+    }
+
+    pub fn is_blocked(&self, channel: &str, user: &str) -> bool {
+        let mut blocked = self.blocked.lock();
+        let key = format!("{channel}:{user}");
+        match blocked.get(&key) {
+            Some(Some(until)) if *until <= Utc::now() => {
+                blocked.remove(&key);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Send through the attached runtime, holding the message in the outbox
+    /// when its channel is muted. Failures are recorded as channel errors.
+    /// A `tag:<tag>` channel sends to every tagged channel and fails only
+    /// when none of them accepted the message.
+    pub async fn send(&self, channel: &str, recipient: &str, message: &str) -> anyhow::Result<()> {
+        self.send_with_priority(channel, recipient, message, Priority::Normal)
+            .await
+    }
+
+    /// [`Self::send`], but a normal message waits out the recipient's quiet
+    /// hours (see [`super::quiet_hours`]); an urgent one goes out at once.
+    pub async fn send_with_priority(
+        &self,
+        channel: &str,
+        recipient: &str,
+        message: &str,
+        priority: Priority,
+    ) -> anyhow::Result<()> {
+        if tag_selector(channel).is_none() {
+            return self.send_one(channel, recipient, message, priority).await;
+        }
+        let manager = self
+            .manager()
+            .ok_or_else(|| anyhow::anyhow!("channels are not running"))?;
+        let targets = manager.expand(&BroadcastTarget::new(channel, recipient));
+        if targets.is_empty() {
+            anyhow::bail!("No channels match {channel}");
+        }
+        let mut errors = Vec::new();
+        for target in &targets {
+            if let Err(e) = self
+                .send_one(&target.channel, &target.recipient, message, priority)
+                .await
+            {
+                errors.push(format!("{}: {e}", target.channel));
+            }
+        }
+        if errors.len() == targets.len() {
+            anyhow::bail!("Send to {channel} failed: {}", errors.join("; "));
+        }
+        Ok(())
+    }
+
+    async fn send_one(
+        &self,
+        channel: &str,
+        recipient: &str,
+        message: &str,
+        priority: Priority,
+    ) -> anyhow::Result<()> {
+        if self.is_muted(channel) {
+            self.hold(channel, recipient, message);
+            return Ok(());
+        }
+        if let Some(until) = quiet_hours::hold_until(channel, recipient, priority) {
+            self.deferred.push(channel, recipient, message, until);
+            return Ok(());
+        }
+        let manager = self
+            .manager()
+            .ok_or_else(|| anyhow::anyhow!("channels are not running"))?;
+        manager
+            .send(channel, message, recipient)
+            .await
+            .inspect_err(|e| self.record_error(channel, e))
+    }
+
+    /// Send `parts` to `recipient` as one paced batch (see
+    /// [`super::batch`]); `channel` may be a `tag:` selector, as with
+    /// [`Self::send`]. A muted channel holds the parts in its outbox, and
+    /// quiet hours hold them until they end.
+    pub async fn send_batch(
+        &self,
+        channel: &str,
+        recipient: &str,
+        parts: Vec<String>,
+    ) -> anyhow::Result<()> {
+        let manager = self
+            .manager()
+            .ok_or_else(|| anyhow::anyhow!("channels are not running"))?;
+        let targets = if tag_selector(channel).is_some() {
+            manager.expand(&BroadcastTarget::new(channel, recipient))
+        } else {
+            vec![BroadcastTarget::new(channel, recipient)]
+        };
+        if targets.is_empty() {
+            anyhow::bail!("No channels match {channel}");
+        }
+        let mut errors = Vec::new();
+        for target in &targets {
+            if self.is_muted(&target.channel) {
+                for part in &parts {
+                    self.hold(&target.channel, &target.recipient, part);
+                }
+                continue;
+            }
+            if let Some(until) =
+                quiet_hours::hold_until(&target.channel, &target.recipient, Priority::Normal)
+            {
+                for part in &parts {
+                    self.deferred
+                        .push(&target.channel, &target.recipient, part, until);
+                }
+                continue;
+            }
+            let batch = outbound_batch(&target.channel, &target.recipient, parts.clone());
+            if let Err(e) = manager
+                .send_batch(&target.channel, &target.recipient, batch)
+                .await
+            {
+                self.record_error(&target.channel, &e);
+                errors.push(format!("{}: {e}", target.channel));
+            }
+        }
+        if errors.len() == targets.len() {
+            anyhow::bail!("Send to {channel} failed: {}", errors.join("; "));
+        }
+        Ok(())
+    }
+
+    /// Send a notification from `source`, or buffer it for the next
+    /// summary when its route is in digest mode (see [`super::digest`]).
+    /// Urgent notifications are never digested.
+    pub async fn send_or_digest(
+        &self,
+        channel: &str,
+        recipient: &str,
+        source: &str,
+        digest: Option<&DigestConfig>,
+        message: &str,
+        priority: Priority,
+    ) -> anyhow::Result<()> {
+        match digest.filter(|_| priority == Priority::Normal) {
+            Some(config) => {
+                self.digests
+                    .queue(channel, recipient, source, config, message);
+                Ok(())
+            }
+            None => {
+                self.send_with_priority(channel, recipient, message, priority)
+                    .await
+            }
+        }
+    }
+
+    pub fn digests(&self) -> &Digests {
+        &self.digests
+    }
+
+    /// Notifications held for quiet hours.
+    pub fn deferred(&self) -> &DeferredQueue {
+        &self.deferred
+    }
+
+    /// Hold a reply in the outbox instead of sending it.
+    pub fn hold(&self, channel: &str, recipient: &str, message: &str) {
+        self.outbox.lock().push_back(OutboxEntry {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            message: message.to_string(),
+            queued_at: Utc::now().to_rfc3339(),
+        });
+    }
+
+    pub fn outbox_len(&self) -> usize {
+        self.outbox.lock().len()
+    }
+
+    /// Snapshot of held replies, oldest first.
+    pub fn outbox(&self) -> Vec<OutboxEntry> {
+        self.outbox.lock().iter().cloned().collect()
+    }
+
+    /// Remove and return held replies, oldest first. When `channel` is given
+    /// only that channel's entries are taken.
+    pub fn take_outbox(&self, channel: Option<&str>) -> Vec<OutboxEntry> {
+        let mut outbox = self.outbox.lock();
+        match channel {
+            None => outbox.drain(..).collect(),
+            Some(name) => {
+                let (taken, kept): (VecDeque<_>, VecDeque<_>) =
+                    outbox.drain(..).partition(|entry| entry.channel == name);
+                *outbox = kept;
+                taken.into()
+            }
+        }
+    }
+
+    /// Put entries back at the front of the outbox (e.g. after a failed drain).
+    pub fn requeue(&self, entries: Vec<OutboxEntry>) {
+        let mut outbox = self.outbox.lock();
+        for entry in entries.into_iter().rev() {
+            outbox.push_front(entry);
+        }
+    }
+
+    /// Record a failed send; repeated failures reach the ops channel.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn record_error(&self, channel: &str, error: impl ToString) {
+        let error = error.to_string();
+        crate::ops::report(crate::ops::OpsEventKind::SendFailure, channel, &error);
+        self.push_error(channel, &error);
+    }
+
+    /// Record a listener crash or unexpected exit; a burst of them reaches
+    /// the ops channel as a reconnect storm.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn record_listener_error(&self, channel: &str, error: impl ToString) {
+        let error = error.to_string();
+        crate::ops::report(crate::ops::OpsEventKind::Reconnect, channel, &error);
+        self.push_error(channel, &error);
+    }
+
+    fn push_error(&self, channel: &str, error: &str) {
+        let mut errors = self.errors.lock();
+        if errors.len() >= RECENT_ERRORS_CAPACITY {
+            errors.pop_front();
+        }
+        errors.push_back(ChannelErrorRecord {
+            channel: channel.to_string(),
+            error: crate::security::redact::redact(error),
+            at: Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// Most recent errors, newest first.
+    pub fn recent_errors(&self, limit: usize) -> Vec<ChannelErrorRecord> {
+        self.errors
+            .lock()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Ask the channel runtime to restart with `config`.
+    pub fn request_reload(&self, config: Config) {
+        *self.pending_reload.lock() = Some(Box::new(config));
+        self.reload_ready.notify_one();
+    }
+
+    /// Wait until a reload is requested and return the new config.
+    pub async fn reload_requested(&self) -> Config {
+        loop {
+            if let Some(config) = self.pending_reload.lock().take() {
+                return *config;
+            }
+            self.reload_ready.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mute_and_unmute_track_channels() {
+        let control = ChannelControl::new();
+        assert!(control.mute("qq"));
+        assert!(!control.mute("qq"));
+        assert!(control.is_muted("qq"));
+        assert_eq!(control.muted(), vec!["qq".to_string()]);
+        assert!(control.unmute("qq"));
+        assert!(!control.is_muted("qq"));
+    }
+
+    #[test]
+    fn take_outbox_filters_by_channel_and_keeps_order() {
+        let control = ChannelControl::new();
+        control.hold("qq", "user:1", "a");
+        control.hold("telegram", "42", "b");
+        control.hold("qq", "user:2", "c");
+
+        let taken = control.take_outbox(Some("qq"));
+        let messages: Vec<_> = taken.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["a", "c"]);
+        assert_eq!(control.outbox_len(), 1);
+
+        control.requeue(taken);
+        let all: Vec<_> = control
+            .take_outbox(None)
+            .into_iter()
+            .map(|e| e.message)
+            .collect();
+        assert_eq!(all, ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn recent_errors_are_bounded_and_newest_first() {
+        let control = ChannelControl::new();
+        for i in 0..(RECENT_ERRORS_CAPACITY + 5) {
+            control.record_error("qq", format!("e{i}"));
+        }
+        let errors = control.recent_errors(usize::MAX);
+        assert_eq!(errors.len(), RECENT_ERRORS_CAPACITY);
+        assert_eq!(errors[0].error, format!("e{}", RECENT_ERRORS_CAPACITY + 4));
+    }
+
+    #[tokio::test]
+    async fn inbound_tap_reaches_subscribers() {
+        let control = ChannelControl::new();
+        let msg = ChannelMessage {
+            id: "m1".into(),
+            ..ChannelMessage::test("qq", "alice", "hi")
+        };
+        // Publishing without subscribers is a no-op.
+        control.publish_inbound(&msg);
+
+        let mut rx = control.subscribe_inbound();
+        control.publish_inbound(&msg);
+        assert_eq!(rx.recv().await.unwrap().id, "m1");
+    }
+
+    #[tokio::test]
+    async fn queue_depths_cover_every_queue() {
+        let control = ChannelControl::new();
+        let msg = ChannelMessage {
+            id: "m1".into(),
+            ..ChannelMessage::test("qq", "alice", "hi")
+        };
+        let queue = Arc::new(InboundQueue::new(
+            8,
+            crate::config::schema::InboundOverflowPolicy::DropNew,
+        ));
+        queue.push(msg.clone()).await;
+        control.register_inbound_queue("qq", queue);
+        let lanes = Arc::new(ConversationLanes::new());
+        lanes.enqueue("qq:alice", msg.clone());
+        lanes.enqueue("qq:alice", msg);
+        control.attach_lanes(lanes);
+        control.hold("qq", "bob", "later");
+
+        let depths = control.queue_depths();
+        assert_eq!(depths.inbound.len(), 1);
+        assert_eq!(depths.inbound[0].buffered, 1);
+        assert_eq!(depths.active_conversations, 1);
+        assert_eq!(depths.queued_messages, 1);
+        assert_eq!(depths.outbox, 1);
+    }
+
+    #[tokio::test]
+    async fn reload_request_wakes_waiter() {
+        let control = std::sync::Arc::new(ChannelControl::new());
+        let waiter = std::sync::Arc::clone(&control);
+        let handle = tokio::spawn(async move { waiter.reload_requested().await });
+
+        let mut config = Config::default();
+        config.default_temperature = 0.3;
+        control.request_reload(config);
+
+        let reloaded = handle.await.unwrap();
+        assert!((reloaded.default_temperature - 0.3).abs() < f64::EPSILON);
+    }
+}
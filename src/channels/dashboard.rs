@@ -0,0 +1,123 @@
+//! Operator dashboard (`[admin] dashboard`).
+//!
+//! A static page served by the admin API at `/dashboard`, compiled into the
+//! binary. It shows channel status, the live message flow, queue depths,
+//! recent errors and the running config, all read from the admin API
+//! (`/admin/channels`, `/admin/live`, `/admin/queues`, `/admin/errors`,
+//! `/admin/config`).
+//!
+//! The assets hold no data. With basic auth configured they are served
+//! behind it, so the browser asks for the username and password once and
+//! sends them along with every API call. With only a token, the page asks
+//! for the token and keeps it in the tab's session storage.
+
+use super::admin::{authorize, AdminState};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+const INDEX_HTML: &str = include_str!("dashboard/index.html");
+const APP_JS: &str = include_str!("dashboard/app.js");
+const STYLE_CSS: &str = include_str!("dashboard/style.css");
+
+pub fn routes() -> Router<AdminState> {
+    Router::new()
+        .route("/dashboard", get(handle_index))
+        .route("/dashboard/app.js", get(handle_app_js))
+        .route("/dashboard/style.css", get(handle_style_css))
+}
+
+async fn asset(
+    state: &AdminState,
+    headers: &HeaderMap,
+    content_type: &'static str,
+    body: &'static str,
+) -> Response {
+    if state.basic.is_some() {
+        if let Err(resp) = authorize(state, headers).await {
+            return resp;
+        }
+    }
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// GET /dashboard
+async fn handle_index(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    asset(&state, &headers, "text/html; charset=utf-8", INDEX_HTML).await
+}
+
+/// GET /dashboard/app.js
+async fn handle_app_js(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    asset(&state, &headers, "text/javascript; charset=utf-8", APP_JS).await
+}
+
+/// GET /dashboard/style.css
+async fn handle_style_css(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    asset(&state, &headers, "text/css; charset=utf-8", STYLE_CSS).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::admin::BasicCredentials;
+    use crate::channels::control::ChannelControl;
+    use crate::security::ManagedSecret;
+    use axum::http::{HeaderValue, StatusCode};
+    use base64::Engine;
+    use std::sync::Arc;
+
+    fn state(basic: bool) -> AdminState {
+        AdminState {
+            control: Arc::new(ChannelControl::new()),
+            token: Some(Arc::new(ManagedSecret::fixed("s3cret"))),
+            basic: basic.then(|| BasicCredentials {
+                username: "ops".into(),
+                password: Arc::new(ManagedSecret::fixed("hunter22")),
+            }),
+        }
+    }
+
+    fn basic_auth(credentials: &str) -> HeaderMap {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Basic {encoded}")).unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn page_is_public_with_token_auth_only() {
+        let resp = handle_index(State(state(false)), HeaderMap::new()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+    }
+
+    #[tokio::test]
+    async fn page_asks_for_basic_auth_when_configured() {
+        let resp = handle_index(State(state(true)), HeaderMap::new()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(resp.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        let resp = handle_index(State(state(true)), basic_auth("ops:wrong")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = handle_app_js(State(state(true)), basic_auth("ops:hunter22")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,218 @@
+"use strict";
+
+// Operator dashboard: polls the admin API and follows /admin/live.
+
+const TOKEN_KEY = "zeroclaw-admin-token";
+const REFRESH_MS = 5000;
+const LIVE_LIMIT = 200;
+const RECONNECT_MS = 3000;
+
+let started = false;
+
+function authHeaders() {
+  const token = sessionStorage.getItem(TOKEN_KEY);
+  return token ? { Authorization: `Bearer ${token}` } : {};
+}
+
+class Unauthorized extends Error {}
+
+async function api(path) {
+  const resp = await fetch(path, { headers: authHeaders() });
+  if (resp.status === 401) {
+    throw new Unauthorized();
+  }
+  const body = await resp.json().catch(() => ({}));
+  if (!resp.ok) {
+    throw new Error(body.error || resp.statusText);
+  }
+  return body;
+}
+
+// Build an element; strings become text nodes, never markup.
+function el(tag, attrs, ...children) {
+  const node = document.createElement(tag);
+  Object.entries(attrs || {}).forEach(([key, value]) => node.setAttribute(key, value));
+  children.forEach((child) => {
+    node.append(child instanceof Node ? child : document.createTextNode(String(child ?? "")));
+  });
+  return node;
+}
+
+function time(iso) {
+  const date = new Date(iso);
+  return Number.isNaN(date.getTime()) ? iso : date.toLocaleTimeString();
+}
+
+function showLogin() {
+  sessionStorage.removeItem(TOKEN_KEY);
+  document.getElementById("dashboard").hidden = true;
+  document.getElementById("login").hidden = false;
+  document.getElementById("updated").textContent = "signed out";
+}
+
+function renderChannels(data) {
+  const rows = data.channels.map((c) =>
+    el(
+      "tr",
+      {},
+      el("td", {}, c.name),
+      el("td", { class: `status-${c.status}` }, c.status),
+      el("td", {}, c.restart_count),
+      el("td", {}, c.muted ? "yes" : ""),
+      el("td", {}, c.last_error || "")
+    )
+  );
+  document.getElementById("channels").replaceChildren(...rows);
+}
+
+function renderQueues(q) {
+  const counters = [
+    ["Conversations active", q.active_conversations],
+    ["Messages waiting", q.queued_messages],
+    ["Outbox (muted)", q.outbox],
+    ["Quiet hours", q.deferred],
+    ["Digests", q.digests],
+  ].map(([label, value]) => el("div", {}, el("dt", {}, label), el("dd", {}, value)));
+  document.getElementById("queues").replaceChildren(...counters);
+  const rows = q.inbound.map((i) =>
+    el("tr", {}, el("td", {}, i.channel), el("td", {}, i.buffered), el("td", {}, i.dropped))
+  );
+  document.getElementById("inbound-queues").replaceChildren(...rows);
+}
+
+function renderErrors(data) {
+  const rows = data.errors.map((e) =>
+    el("tr", {}, el("td", {}, time(e.at)), el("td", {}, e.channel), el("td", {}, e.error))
+  );
+  document.getElementById("errors").replaceChildren(...rows);
+}
+
+async function loadConfig() {
+  const config = document.getElementById("config");
+  try {
+    config.textContent = JSON.stringify(await api("/admin/config"), null, 2);
+  } catch (e) {
+    if (e instanceof Unauthorized) {
+      showLogin();
+    } else {
+      config.textContent = `Unavailable: ${e.message}`;
+    }
+  }
+}
+
+async function refresh() {
+  const results = await Promise.allSettled([
+    api("/admin/channels").then(renderChannels),
+    api("/admin/queues").then(renderQueues),
+    api("/admin/errors?limit=50").then(renderErrors),
+  ]);
+  if (results.some((r) => r.status === "rejected" && r.reason instanceof Unauthorized)) {
+    showLogin();
+    return false;
+  }
+  const failed = results.find((r) => r.status === "rejected");
+  document.getElementById("updated").textContent = failed
+    ? `update failed: ${failed.reason.message}`
+    : `updated ${new Date().toLocaleTimeString()}`;
+  return true;
+}
+
+function describe(event) {
+  if (event.kind === "inbound") {
+    return [`⬅ ${event.channel} ${event.sender}: ${event.content}`, ""];
+  }
+  if (event.kind === "outbound") {
+    return [`➡ ${event.channel} ${event.recipient}: ${event.content}`, ""];
+  }
+  switch (event.event) {
+    case "message_sent":
+      // Replies are shown as outbound events; this covers other sends
+      return [`➡ ${event.channel} ${event.recipient} (sent)`, ""];
+    case "message_failed":
+      return [`✖ ${event.channel} ${event.recipient}: ${event.error}`, "failed"];
+    case "channel_connected":
+      return [`● ${event.channel} connected`, ""];
+    case "channel_disconnected":
+      return [`○ ${event.channel} disconnected${event.error ? `: ${event.error}` : ""}`, "failed"];
+    case "config_reloaded":
+      return ["↻ config reloaded", ""];
+    default:
+      return [`${event.event} ${event.channel || ""}`, ""];
+  }
+}
+
+function appendLive(event) {
+  const [text, cls] = describe(event);
+  const list = document.getElementById("live");
+  list.prepend(el("li", cls ? { class: cls } : {}, `${new Date().toLocaleTimeString()} ${text}`));
+  while (list.children.length > LIVE_LIMIT) {
+    list.lastChild.remove();
+  }
+}
+
+// EventSource can't send an Authorization header, so read the stream by hand.
+async function follow() {
+  const state = document.getElementById("live-state");
+  for (;;) {
+    try {
+      const resp = await fetch("/admin/live", { headers: authHeaders() });
+      if (resp.status === 401) {
+        showLogin();
+        return;
+      }
+      if (!resp.ok || !resp.body) {
+        throw new Error(resp.statusText);
+      }
+      state.textContent = "live";
+      const reader = resp.body.pipeThrough(new TextDecoderStream()).getReader();
+      let buffer = "";
+      for (;;) {
+        const { value, done } = await reader.read();
+        if (done) {
+          break;
+        }
+        buffer += value;
+        const frames = buffer.split("\n\n");
+        buffer = frames.pop();
+        frames.forEach((frame) => {
+          const data = frame
+            .split("\n")
+            .filter((line) => line.startsWith("data:"))
+            .map((line) => line.slice(5).trimStart())
+            .join("\n");
+          if (data) {
+            appendLive(JSON.parse(data));
+          }
+        });
+      }
+    } catch (e) {
+      console.warn("live stream failed", e);
+    }
+    state.textContent = "reconnecting…";
+    await new Promise((resolve) => setTimeout(resolve, RECONNECT_MS));
+  }
+}
+
+async function start() {
+  if (!(await refresh())) {
+    return;
+  }
+  document.getElementById("login").hidden = true;
+  document.getElementById("dashboard").hidden = false;
+  if (started) {
+    return;
+  }
+  started = true;
+  loadConfig();
+  follow();
+  setInterval(refresh, REFRESH_MS);
+}
+
+document.getElementById("login").addEventListener("submit", (event) => {
+  event.preventDefault();
+  sessionStorage.setItem(TOKEN_KEY, document.getElementById("token").value);
+  start();
+});
+document.getElementById("reload-config").addEventListener("click", loadConfig);
+
+start();
@@ -0,0 +1,207 @@
+//! Inbound message deduplication for the channel dispatch loop.
+//!
+//! Gateways occasionally redeliver events (e.g. after a RESUME), so the
+//! dispatcher remembers recently seen `(channel, message id)` pairs and drops
+//! repeats before they reach the agent. This is an LRU/TTL cache: an entry
+//! expires a TTL after the message was first delivered, and once the cache is
+//! full the least recently seen entry is evicted first. A redelivery counts
+//! as a sighting, so an id that is being replayed is not the next one evicted.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Default time an inbound message id is remembered.
+pub const DEFAULT_DEDUP_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Default maximum number of remembered message ids across all channels.
+pub const DEFAULT_DEDUP_CAPACITY: usize = 10_000;
+
+type DedupKey = (String, String);
+
+/// Bounded LRU/TTL cache of recently seen inbound message ids.
+pub struct InboundDeduplicator {
+    ttl: Duration,
+    capacity: usize,
+    /// First delivery of each id and the sequence number of its live entry
+    /// in `order`.
+    seen: HashMap<DedupKey, (Instant, u64)>,
+    /// Sightings, least recent first. An entry whose sequence number no
+    /// longer matches `seen` was superseded by a later sighting.
+    order: VecDeque<(DedupKey, u64)>,
+    next_seq: u64,
+}
+
+impl InboundDeduplicator {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity: capacity.max(1),
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Record `(channel, id)` and return `true` if it was already seen within
+    /// the TTL. Empty ids are never treated as duplicates.
+    pub fn is_duplicate(&mut self, channel: &str, id: &str) -> bool {
+        self.is_duplicate_at(channel, id, Instant::now())
+    }
+
+    fn is_duplicate_at(&mut self, channel: &str, id: &str, now: Instant) -> bool {
+        if id.is_empty() {
+            return false;
+        }
+
+        self.evict_expired(now);
+
+        let key = (channel.to_string(), id.to_string());
+        let first_seen = match self.seen.get(&key) {
+            Some((first_seen, _)) if now.duration_since(*first_seen) < self.ttl => {
+                Some(*first_seen)
+            }
+            Some(_) => {
+                self.seen.remove(&key);
+                None
+            }
+            None => None,
+        };
+        let duplicate = first_seen.is_some();
+        if !duplicate {
+            while self.seen.len() >= self.capacity {
+                if !self.pop_least_recent() {
+                    break;
+                }
+            }
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.seen
+            .insert(key.clone(), (first_seen.unwrap_or(now), seq));
+        self.order.push_back((key, seq));
+        self.compact();
+        duplicate
+    }
+
+    /// Number of ids currently remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn evict_expired(&mut self, now: Instant) {
+        while let Some((key, seq)) = self.order.front() {
+            let live = self.seen.get(key).filter(|(_, live)| live == seq);
+            if let Some((first_seen, _)) = live {
+                if now.duration_since(*first_seen) < self.ttl {
+                    break;
+                }
+                self.seen.remove(key);
+            }
+            // Expired, or superseded by a later sighting
+            self.order.pop_front();
+        }
+    }
+
+    /// Forget the least recently seen id. Returns `false` when empty.
+    fn pop_least_recent(&mut self) -> bool {
+        while let Some((key, seq)) = self.order.pop_front() {
+            if self.seen.get(&key).is_some_and(|(_, live)| *live == seq) {
+                self.seen.remove(&key);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Drop superseded sightings once they outnumber the live ones, so ids
+    /// that are replayed over and over do not grow `order` without bound.
+    fn compact(&mut self) {
+        if self.order.len() <= self.capacity.saturating_mul(2) {
+            return;
+        }
+        let seen = &self.seen;
+        self.order
+            .retain(|(key, seq)| seen.get(key).is_some_and(|(_, live)| live == seq));
+    }
+}
+
+impl Default for InboundDeduplicator {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEDUP_TTL, DEFAULT_DEDUP_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_id_is_duplicate() {
+        let mut dedup = InboundDeduplicator::default();
+        assert!(!dedup.is_duplicate("qq", "m1"));
+        assert!(dedup.is_duplicate("qq", "m1"));
+    }
+
+    #[test]
+    fn same_id_on_different_channels_is_not_duplicate() {
+        let mut dedup = InboundDeduplicator::default();
+        assert!(!dedup.is_duplicate("qq", "m1"));
+        assert!(!dedup.is_duplicate("discord", "m1"));
+    }
+
+    #[test]
+    fn empty_id_is_never_duplicate() {
+        let mut dedup = InboundDeduplicator::default();
+        assert!(!dedup.is_duplicate("qq", ""));
+        assert!(!dedup.is_duplicate("qq", ""));
+        assert!(dedup.is_empty());
+    }
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let mut dedup = InboundDeduplicator::new(Duration::from_secs(60), 100);
+        let start = Instant::now();
+        assert!(!dedup.is_duplicate_at("qq", "m1", start));
+        assert!(dedup.is_duplicate_at("qq", "m1", start + Duration::from_secs(30)));
+        assert!(!dedup.is_duplicate_at("qq", "m1", start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn least_recently_seen_entry_is_evicted_at_capacity() {
+        let mut dedup = InboundDeduplicator::new(Duration::from_secs(600), 2);
+        assert!(!dedup.is_duplicate("qq", "a"));
+        assert!(!dedup.is_duplicate("qq", "b"));
+        // A replay of "a" makes "b" the least recently seen
+        assert!(dedup.is_duplicate("qq", "a"));
+        assert!(!dedup.is_duplicate("qq", "c"));
+        assert!(dedup.is_duplicate("qq", "a"));
+        assert!(!dedup.is_duplicate("qq", "b"));
+    }
+
+    #[test]
+    fn repeated_replays_do_not_grow_the_order_queue() {
+        let mut dedup = InboundDeduplicator::new(Duration::from_secs(600), 4);
+        for _ in 0..100 {
+            dedup.is_duplicate("qq", "a");
+        }
+        assert_eq!(dedup.len(), 1);
+        assert!(dedup.order.len() <= 8);
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_at_capacity() {
+        let mut dedup = InboundDeduplicator::new(Duration::from_secs(600), 2);
+        assert!(!dedup.is_duplicate("qq", "a"));
+        assert!(!dedup.is_duplicate("qq", "b"));
+        assert!(!dedup.is_duplicate("qq", "c"));
+        assert_eq!(dedup.len(), 2);
+        // "a" was evicted, "c" is still remembered
+        assert!(dedup.is_duplicate("qq", "c"));
+        assert!(!dedup.is_duplicate("qq", "a"));
+    }
+}
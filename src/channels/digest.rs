@@ -0,0 +1,240 @@
+//! Digest mode for noisy notification routes.
+//!
+//! A feed subscription or ingress target with a `digest` table doesn't post
+//! each notification: [`ChannelControl::send_or_digest`] buffers it here,
+//! per target and source, and [`run_digest_flusher`] posts one templated
+//! summary once `interval_mins` have passed since the first buffered item.
+//! Buffered notifications live in memory only and are lost on restart.
+
+use super::control::ChannelControl;
+use crate::config::{Config, DigestConfig};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Notifications kept per digest; later ones are only counted.
+const MAX_BUFFERED_ITEMS: usize = 500;
+
+/// How often the flusher looks for digests that are due.
+const FLUSH_TICK: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DigestKey {
+    channel: String,
+    recipient: String,
+    source: String,
+}
+
+struct PendingDigest {
+    config: DigestConfig,
+    started: Instant,
+    items: Vec<String>,
+    /// Notifications past `MAX_BUFFERED_ITEMS`
+    overflow: usize,
+}
+
+/// A summary ready to be sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestSummary {
+    pub channel: String,
+    pub recipient: String,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct Digests {
+    pending: Mutex<HashMap<DigestKey, PendingDigest>>,
+}
+
+impl Digests {
+    /// Buffer `message` for the next summary of `source` to `recipient`.
+    pub fn queue(
+        &self,
+        channel: &str,
+        recipient: &str,
+        source: &str,
+        config: &DigestConfig,
+        message: &str,
+    ) {
+        let key = DigestKey {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            source: source.to_string(),
+        };
+        let mut pending = self.pending.lock();
+        let digest = pending.entry(key).or_insert_with(|| PendingDigest {
+            config: config.clone(),
+            started: Instant::now(),
+            items: Vec::new(),
+            overflow: 0,
+        });
+        if digest.items.len() < MAX_BUFFERED_ITEMS {
+            digest.items.push(message.trim().to_string());
+        } else {
+            digest.overflow += 1;
+        }
+    }
+
+    /// Notifications buffered across all digests.
+    pub fn buffered(&self) -> usize {
+        self.pending
+            .lock()
+            .values()
+            .map(|digest| digest.items.len() + digest.overflow)
+            .sum()
+    }
+
+    /// Remove and render every digest whose interval has passed by `now`.
+    pub fn take_due(&self, now: Instant) -> Vec<DigestSummary> {
+        let mut pending = self.pending.lock();
+        let due: Vec<DigestKey> = pending
+            .iter()
+            .filter(|(_, digest)| {
+                now.saturating_duration_since(digest.started) >= interval(&digest.config)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|key| {
+                let digest = pending.remove(&key)?;
+                Some(DigestSummary {
+                    text: render(&key.source, &digest),
+                    channel: key.channel,
+                    recipient: key.recipient,
+                })
+            })
+            .collect()
+    }
+}
+
+fn interval(config: &DigestConfig) -> Duration {
+    Duration::from_secs(config.interval_mins.max(1) * 60)
+}
+
+/// `90` → `1h30m`, `60` → `1h`, `15` → `15m`.
+fn format_interval(mins: u64) -> String {
+    match (mins / 60, mins % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m}m"),
+    }
+}
+
+fn render(source: &str, digest: &PendingDigest) -> String {
+    let count = digest.items.len() + digest.overflow;
+    let listed = digest.config.max_items.max(1).min(digest.items.len());
+    let mut items = digest.items[..listed].join("\n\n");
+    let more = count - listed;
+    if more > 0 {
+        let _ = write!(items, "\n\n…and {more} more");
+    }
+    digest
+        .config
+        .template
+        .replace("{source}", source)
+        .replace("{count}", &count.to_string())
+        .replace(
+            "{interval}",
+            &format_interval(digest.config.interval_mins.max(1)),
+        )
+        .replace("{items}", &items)
+        .trim()
+        .to_string()
+}
+
+/// Whether any feed subscription or ingress target is in digest mode.
+pub fn configured(config: &Config) -> bool {
+    let feeds = config.feeds.enabled
+        && config
+            .feeds
+            .subscriptions
+            .iter()
+            .any(|subscription| subscription.digest.is_some());
+    let ingress = &config.ingress;
+    let forge_targets = [&ingress.github, &ingress.gitlab]
+        .into_iter()
+        .flatten()
+        .flat_map(|forge| &forge.targets);
+    let alert_targets = ingress.alertmanager.iter().flat_map(|am| &am.targets);
+    feeds
+        || (ingress.enabled
+            && forge_targets
+                .chain(alert_targets)
+                .any(|target| target.digest.is_some()))
+}
+
+/// Post due digests until the process stops.
+pub async fn run_digest_flusher(control: Arc<ChannelControl>) {
+    let mut ticker = tokio::time::interval(FLUSH_TICK);
+    loop {
+        ticker.tick().await;
+        for summary in control.digests().take_due(Instant::now()) {
+            if let Err(e) = control
+                .send(&summary.channel, &summary.recipient, &summary.text)
+                .await
+            {
+                tracing::warn!("Failed to post a digest to {}: {e}", summary.channel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_items: usize) -> DigestConfig {
+        DigestConfig {
+            interval_mins: 90,
+            template: "{source}: {count} in {interval}\n{items}".into(),
+            max_items,
+        }
+    }
+
+    #[test]
+    fn summarizes_each_target_once_its_interval_passes() {
+        let digests = Digests::default();
+        for n in 1..=3 {
+            digests.queue("slack", "C1", "org/app", &config(2), &format!("build {n}"));
+        }
+        digests.queue("slack", "C2", "org/app", &config(2), "build 4");
+        assert_eq!(digests.buffered(), 4);
+        assert!(digests.take_due(Instant::now()).is_empty());
+
+        let later = Instant::now() + Duration::from_secs(90 * 60);
+        let mut due = digests.take_due(later);
+        due.sort_by(|a, b| a.recipient.cmp(&b.recipient));
+        assert_eq!(
+            due[0],
+            DigestSummary {
+                channel: "slack".into(),
+                recipient: "C1".into(),
+                text: "org/app: 3 in 1h30m\nbuild 1\n\nbuild 2\n\n…and 1 more".into(),
+            }
+        );
+        assert_eq!(due[1].text, "org/app: 1 in 1h30m\nbuild 4");
+        assert_eq!(digests.buffered(), 0);
+    }
+
+    #[test]
+    fn only_routes_with_a_digest_need_the_flusher() {
+        let mut config = Config::default();
+        config.feeds.enabled = true;
+        assert!(!configured(&config));
+        config
+            .feeds
+            .subscriptions
+            .push(crate::config::FeedSubscriptionConfig {
+                name: "blog".into(),
+                url: "https://example.com/feed.xml".into(),
+                channel: "telegram".into(),
+                recipient: "42".into(),
+                interval_secs: None,
+                template: None,
+                digest: Some(DigestConfig::default()),
+            });
+        assert!(configured(&config));
+    }
+}
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
@@ -32,7 +32,7 @@ impl DingTalkChannel {
             client_id,
             client_secret,
             allowed_users,
-            client: reqwest::Client::new(),
+            client: super::net::http_client_for("dingtalk"),
             session_webhooks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
@@ -84,6 +84,17 @@ impl Channel for DingTalkChannel {
         "dingtalk"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Replies go out as markdown messages
+        ChannelCapabilities {
+            supports_markdown: true,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let webhooks = self.session_webhooks.read().await;
         let webhook_url = webhooks.get(recipient).ok_or_else(|| {
@@ -119,7 +130,7 @@ impl Channel for DingTalkChannel {
         let ws_url = format!("{}?ticket={}", gw.endpoint, gw.ticket);
 
         tracing::info!("DingTalk: connecting to stream WebSocket...");
-        let (ws_stream, _) = tokio_tungstenite::connect_async(&ws_url).await?;
+        let ws_stream = super::net::connect_websocket("dingtalk", &ws_url).await?;
         let (mut write, mut read) = ws_stream.split();
 
         tracing::info!("DingTalk: connected and listening for messages...");
@@ -241,10 +252,14 @@ impl Channel for DingTalkChannel {
                         reply_target: chat_id,
                         content: content.to_string(),
                         channel: "dingtalk".to_string(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
+                        timestamp: unix_now(),
+                        received_at: unix_now(),
+                        attachments: vec![],
+                        sender_id: None,
+                        sender_name: None,
+                        conversation_id: None,
+                        mentions: vec![],
+                        is_mention: false,
                     };
 
                     if tx.send(channel_msg).await.is_err() {
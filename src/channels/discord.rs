@@ -1,19 +1,41 @@
-use super::traits::{Channel, ChannelMessage};
+use super::frame_tap::{self, Direction};
+use super::gateway::{self, op, Attachment, Author, GatewayFrame, MessageEvent};
+use super::gateway_state::{GatewayStateStore, SessionTracker};
+use super::interactions::{self, Interaction, Keyboard};
+use super::mentions;
+use super::polls::{self, Poll};
+use super::traits::{
+    unix_now, Channel, ChannelCapabilities, ChannelMessage, MediaAttachment, MediaKind,
+};
+use super::uploads::{self, FileReader};
+use crate::config::GatewayCompression;
+use crate::security::ManagedSecret;
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
 use serde_json::json;
+use std::sync::Arc;
 use tokio_tungstenite::tungstenite::Message;
-use uuid::Uuid;
+
+/// Largest attachment a bot may upload to a server without boosts.
+const DISCORD_MAX_UPLOAD_BYTES: u64 = 10 * 1024 * 1024;
 
 /// Discord channel — connects via Gateway WebSocket for real-time messages
 pub struct DiscordChannel {
-    bot_token: String,
+    /// Instance name; "discord" unless configured as a named account.
+    name: String,
+    bot_token: Arc<ManagedSecret>,
     guild_id: Option<String>,
     allowed_users: Vec<String>,
     listen_to_bots: bool,
     mention_only: bool,
     client: reqwest::Client,
     typing_handle: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Where the gateway session is saved for resuming after restarts.
+    state_store: Option<Arc<dyn GatewayStateStore>>,
+    /// Randomize the first heartbeat after Hello.
+    heartbeat_jitter: bool,
+    compression: GatewayCompression,
 }
 
 impl DiscordChannel {
@@ -23,18 +45,106 @@ impl DiscordChannel {
         allowed_users: Vec<String>,
         listen_to_bots: bool,
         mention_only: bool,
+    ) -> Self {
+        Self::with_secret(
+            Arc::new(ManagedSecret::fixed(bot_token)),
+            guild_id,
+            allowed_users,
+            listen_to_bots,
+            mention_only,
+        )
+    }
+
+    /// Create a channel whose bot token is read from a secret provider, so a
+    /// rotated token applies on the next request or reconnect.
+    pub fn with_secret(
+        bot_token: Arc<ManagedSecret>,
+        guild_id: Option<String>,
+        allowed_users: Vec<String>,
+        listen_to_bots: bool,
+        mention_only: bool,
     ) -> Self {
         Self {
+            name: "discord".to_string(),
             bot_token,
             guild_id,
             allowed_users,
             listen_to_bots,
             mention_only,
-            client: reqwest::Client::new(),
+            client: super::net::http_client_for("discord"),
             typing_handle: std::sync::Mutex::new(None),
+            state_store: None,
+            heartbeat_jitter: true,
+            compression: GatewayCompression::None,
         }
     }
 
+    /// `Authorization` header value for the REST API.
+    async fn authorization(&self) -> anyhow::Result<String> {
+        Ok(format!("Bot {}", self.bot_token.get().await?))
+    }
+
+    /// Run as a named instance (e.g. "discord-ops").
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Persist the gateway session so a restart can resume it.
+    pub fn with_state_store(mut self, store: Arc<dyn GatewayStateStore>) -> Self {
+        self.state_store = Some(store);
+        self
+    }
+
+    /// Send the first heartbeat after a random fraction of the interval
+    /// (the default) rather than a full interval.
+    pub fn with_heartbeat_jitter(mut self, jitter: bool) -> Self {
+        self.heartbeat_jitter = jitter;
+        self
+    }
+
+    /// Ask the gateway for a compressed transport.
+    pub fn with_compression(mut self, compression: GatewayCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Post a message carrying one file attachment.
+    async fn upload_file(
+        &self,
+        channel_id: &str,
+        bytes: Vec<u8>,
+        file_name: &str,
+        content: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
+        let mut form = reqwest::multipart::Form::new().part(
+            "files[0]",
+            reqwest::multipart::Part::bytes(bytes).file_name(file_name.to_string()),
+        );
+        if let Some(content) = content {
+            form = form.text("payload_json", json!({ "content": content }).to_string());
+        }
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", self.authorization().await?)
+            .multipart(form)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp
+                .text()
+                .await
+                .unwrap_or_else(|e| format!("<failed to read response body: {e}>"));
+            anyhow::bail!("Discord file upload failed ({status}): {err}");
+        }
+        Ok(())
+    }
+
     /// Check if a Discord user ID is in the allowlist.
     /// Empty list means deny everyone until explicitly configured.
     /// `"*"` means allow everyone.
@@ -142,54 +252,430 @@ fn base64_decode(input: &str) -> Option<String> {
     String::from_utf8(bytes).ok()
 }
 
+/// Files attached to a MESSAGE_CREATE payload.
+fn parse_attachments(items: &[Attachment]) -> Vec<MediaAttachment> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let mime_type = item.content_type.clone();
+            let kind = match mime_type.as_deref().unwrap_or_default() {
+                m if m.starts_with("image/") => MediaKind::Image,
+                m if m.starts_with("audio/") => MediaKind::Audio,
+                m if m.starts_with("video/") => MediaKind::Video,
+                _ => MediaKind::Document,
+            };
+            Some(MediaAttachment {
+                kind,
+                source: item.url.clone()?,
+                mime_type,
+                file_name: item.filename.clone(),
+                stored_key: None,
+            })
+        })
+        .collect()
+}
+
+/// Interaction type of a message component (button) click.
+const MESSAGE_COMPONENT: u8 = 3;
+
+/// The parts of an INTERACTION_CREATE payload a button click needs.
+#[derive(Debug, Default, Deserialize)]
+struct ComponentInteraction {
+    id: String,
+    token: String,
+    #[serde(rename = "type")]
+    kind: u8,
+    #[serde(default)]
+    channel_id: Option<String>,
+    #[serde(default)]
+    guild_id: Option<String>,
+    /// Set for clicks in guilds
+    #[serde(default)]
+    member: Option<Member>,
+    /// Set for clicks in DMs
+    #[serde(default)]
+    user: Option<Author>,
+    #[serde(default)]
+    data: Option<ComponentData>,
+    #[serde(default)]
+    message: Option<MessageRef>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Member {
+    user: Author,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ComponentData {
+    custom_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessageRef {
+    id: String,
+}
+
+impl ComponentInteraction {
+    fn user(&self) -> Option<&Author> {
+        self.member.as_ref().map(|m| &m.user).or(self.user.as_ref())
+    }
+
+    /// The click as an [`Interaction`]; `None` for other interaction types.
+    fn to_interaction(&self, channel: &str) -> Option<Interaction> {
+        if self.kind != MESSAGE_COMPONENT {
+            return None;
+        }
+        let user = self.user()?;
+        let user_id = user.id.clone()?;
+        Some(Interaction {
+            channel: channel.to_string(),
+            callback_id: self.data.as_ref()?.custom_id.clone(),
+            reply_target: self.channel_id.clone().unwrap_or_else(|| user_id.clone()),
+            user_name: user.display_name(),
+            user_id,
+            message_id: self.message.as_ref().map(|m| m.id.clone()),
+        })
+    }
+}
+
+/// A MESSAGE_REACTION_ADD / MESSAGE_REACTION_REMOVE payload.
+#[derive(Debug, Default, Deserialize)]
+struct ReactionEvent {
+    user_id: String,
+    message_id: String,
+    #[serde(default)]
+    emoji: ReactionEmoji,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ReactionEmoji {
+    /// Unicode emoji, or the name of a custom one
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// Count a reaction on a reaction poll as a vote (or withdraw it).
+fn record_reaction(channel: &str, reaction: &ReactionEvent, added: bool) {
+    let Some(option) = reaction
+        .emoji
+        .name
+        .as_deref()
+        .and_then(polls::option_for_emoji)
+    else {
+        return;
+    };
+    if added {
+        polls::add_vote(channel, &reaction.message_id, &reaction.user_id, option);
+    } else {
+        polls::remove_vote(channel, &reaction.message_id, &reaction.user_id, option);
+    }
+}
+
+/// Message components showing `keyboard`: one action row per keyboard row.
+fn components(keyboard: &Keyboard) -> serde_json::Value {
+    let rows: Vec<serde_json::Value> = keyboard
+        .rows
+        .iter()
+        .map(|row| {
+            let buttons: Vec<serde_json::Value> = row
+                .iter()
+                .map(|button| {
+                    json!({
+                        "type": 2,
+                        "style": 1,
+                        "label": button.label,
+                        "custom_id": button.id,
+                    })
+                })
+                .collect();
+            json!({ "type": 1, "components": buttons })
+        })
+        .collect();
+    serde_json::Value::Array(rows)
+}
+
+impl DiscordChannel {
+    /// Acknowledge a click without changing the message it was on; Discord
+    /// shows the interaction as failed if this doesn't arrive within 3s.
+    async fn acknowledge_interaction(&self, click: &ComponentInteraction) {
+        let url = format!(
+            "https://discord.com/api/v10/interactions/{}/{}/callback",
+            click.id, click.token
+        );
+        if let Err(e) = self
+            .client
+            .post(&url)
+            .json(&json!({ "type": 6 }))
+            .send()
+            .await
+        {
+            tracing::debug!("Discord: failed to acknowledge interaction: {e}");
+        }
+    }
+}
+
 #[async_trait]
 impl Channel for DiscordChannel {
     fn name(&self) -> &str {
-        "discord"
+        &self.name
     }
 
     async fn send(&self, message: &str, channel_id: &str) -> anyhow::Result<()> {
         let chunks = split_message_for_discord(message);
+        if chunks.len() > 1 {
+            let batch = super::batch::outbound_batch(&self.name, channel_id, chunks);
+            return self.send_batch(channel_id, batch).await;
+        }
+
+        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
+        let body = json!({ "content": message });
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", self.authorization().await?)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp
+                .text()
+                .await
+                .unwrap_or_else(|e| format!("<failed to read response body: {e}>"));
+            anyhow::bail!("Discord send message failed ({status}): {err}");
+        }
+
+        Ok(())
+    }
+
+    fn send_interval(&self) -> std::time::Duration {
+        // Discord allows 5 messages per 5 seconds in a channel
+        std::time::Duration::from_secs(1)
+    }
 
-        for (i, chunk) in chunks.iter().enumerate() {
-            let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
-            let body = json!({ "content": chunk });
+    fn supports_voice_notes(&self) -> bool {
+        true
+    }
+
+    async fn send_voice_note(
+        &self,
+        audio: Vec<u8>,
+        file_name: &str,
+        channel_id: &str,
+    ) -> anyhow::Result<()> {
+        self.upload_file(channel_id, audio, file_name, None).await
+    }
+
+    fn supports_buttons(&self) -> bool {
+        true
+    }
+
+    async fn send_buttons(
+        &self,
+        text: &str,
+        keyboard: &Keyboard,
+        channel_id: &str,
+    ) -> anyhow::Result<()> {
+        keyboard.validate()?;
+        // Only the last chunk of a long text carries the buttons
+        let mut chunks = split_message_for_discord(text);
+        let last = chunks.pop().unwrap_or_default();
+        if !chunks.is_empty() {
+            self.send(&chunks.concat(), channel_id).await?;
+        }
+
+        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
+        let body = json!({ "content": last, "components": components(keyboard) });
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", self.authorization().await?)
+            .json(&body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Discord send buttons failed ({status}): {err}");
+        }
+        Ok(())
+    }
+
+    fn supports_editing(&self) -> bool {
+        true
+    }
+
+    fn max_message_length(&self) -> usize {
+        DISCORD_MAX_MESSAGE_LENGTH
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: true,
+            supports_reactions: true,
+            supports_typing: true,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    async fn send_editable(&self, message: &str, channel_id: &str) -> anyhow::Result<String> {
+        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", self.authorization().await?)
+            .json(&json!({ "content": message }))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Discord send message failed ({status}): {err}");
+        }
+        let data: serde_json::Value = resp.json().await?;
+        data.get("id")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Discord send message returned no id"))
+    }
 
+    async fn edit_message(
+        &self,
+        message_id: &str,
+        message: &str,
+        channel_id: &str,
+    ) -> anyhow::Result<()> {
+        let url =
+            format!("https://discord.com/api/v10/channels/{channel_id}/messages/{message_id}");
+        let resp = self
+            .client
+            .patch(&url)
+            .header("Authorization", self.authorization().await?)
+            .json(&json!({ "content": message }))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Discord edit message failed ({status}): {err}");
+        }
+        Ok(())
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        Some(DISCORD_MAX_UPLOAD_BYTES)
+    }
+
+    async fn send_file(
+        &self,
+        channel_id: &str,
+        name: &str,
+        mime: &str,
+        size: Option<u64>,
+        reader: FileReader,
+    ) -> anyhow::Result<()> {
+        let body = uploads::streaming_body(reader);
+        let part = match size {
+            Some(len) => reqwest::multipart::Part::stream_with_length(body, len),
+            None => reqwest::multipart::Part::stream(body),
+        }
+        .file_name(name.to_string())
+        .mime_str(mime)?;
+        let form = reqwest::multipart::Form::new().part("files[0]", part);
+
+        let resp = self
+            .client
+            .post(format!(
+                "https://discord.com/api/v10/channels/{channel_id}/messages"
+            ))
+            .header("Authorization", self.authorization().await?)
+            .timeout(super::net::transfer_timeout(size))
+            .multipart(form)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Discord file upload failed ({status}): {err}");
+        }
+        Ok(())
+    }
+
+    fn supports_polls(&self) -> bool {
+        true
+    }
+
+    /// Reaction poll: the options are listed with keycap emojis, which the
+    /// bot adds as reactions for users to click.
+    async fn send_poll(&self, poll: &Poll, channel_id: &str) -> anyhow::Result<String> {
+        poll.validate()?;
+        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", self.authorization().await?)
+            .json(&json!({ "content": poll.render() }))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Discord send poll failed ({status}): {err}");
+        }
+        let sent: serde_json::Value = resp.json().await?;
+        let message_id = sent
+            .get("id")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Discord send poll returned no message id"))?
+            .to_string();
+        let id = polls::track(&self.name, &message_id, channel_id, poll.clone());
+
+        for emoji in poll.emojis() {
             let resp = self
                 .client
-                .post(&url)
-                .header("Authorization", format!("Bot {}", self.bot_token))
-                .json(&body)
+                .put(format!("{url}/{message_id}/reactions/{emoji}/@me"))
+                .header("Authorization", self.authorization().await?)
+                .header("Content-Length", "0")
                 .send()
                 .await?;
-
             if !resp.status().is_success() {
-                let status = resp.status();
-                let err = resp
-                    .text()
-                    .await
-                    .unwrap_or_else(|e| format!("<failed to read response body: {e}>"));
-                anyhow::bail!("Discord send message failed ({status}): {err}");
-            }
-
-            // Add a small delay between chunks to avoid rate limiting
-            if i < chunks.len() - 1 {
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                tracing::warn!("Discord: failed to add poll reaction ({})", resp.status());
             }
+            // Reactions are rate limited to roughly one per 250ms
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
         }
+        Ok(id)
+    }
 
-        Ok(())
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn send_image(
+        &self,
+        image: Vec<u8>,
+        file_name: &str,
+        caption: Option<&str>,
+        channel_id: &str,
+    ) -> anyhow::Result<()> {
+        self.upload_file(channel_id, image, file_name, caption)
+            .await
     }
 
     #[allow(clippy::too_many_lines)]
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
-        let bot_user_id = Self::bot_user_id_from_token(&self.bot_token).unwrap_or_default();
+        let token = self.bot_token.get().await?;
+        let bot_user_id = Self::bot_user_id_from_token(&token).unwrap_or_default();
 
         // Get Gateway URL
         let gw_resp: serde_json::Value = self
             .client
             .get("https://discord.com/api/v10/gateway/bot")
-            .header("Authorization", format!("Bot {}", self.bot_token))
+            .header("Authorization", self.authorization().await?)
             .send()
             .await?
             .json()
@@ -200,127 +686,215 @@ impl Channel for DiscordChannel {
             .and_then(|u| u.as_str())
             .unwrap_or("wss://gateway.discord.gg");
 
-        let ws_url = format!("{gw_url}/?v=10&encoding=json");
+        let mut session = SessionTracker::new(
+            self.state_store.as_deref(),
+            format!("discord-{bot_user_id}"),
+        );
+        let saved = session.resumable();
+        let gw_url = saved
+            .as_ref()
+            .and_then(|s| s.resume_url.as_deref())
+            .unwrap_or(gw_url);
+
+        let mut ws_url = format!("{gw_url}/?v=10&encoding=json");
+        if let Some(compress) = self.compression.query_param() {
+            ws_url.push_str("&compress=");
+            ws_url.push_str(compress);
+        }
         tracing::info!("Discord: connecting to gateway...");
 
-        let (ws_stream, _) = tokio_tungstenite::connect_async(&ws_url).await?;
+        let ws_stream = super::net::connect_websocket("discord", &ws_url).await?;
         let (mut write, mut read) = ws_stream.split();
 
-        // Read Hello (opcode 10)
-        let hello = read.next().await.ok_or(anyhow::anyhow!("No hello"))??;
-        let hello_data: serde_json::Value = serde_json::from_str(&hello.to_string())?;
-        let heartbeat_interval = hello_data
-            .get("d")
-            .and_then(|d| d.get("heartbeat_interval"))
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(41250);
-
-        // Send Identify (opcode 2)
-        let identify = json!({
-            "op": 2,
-            "d": {
-                "token": self.bot_token,
-                "intents": 37377, // GUILDS | GUILD_MESSAGES | MESSAGE_CONTENT | DIRECT_MESSAGES
-                "properties": {
-                    "os": "linux",
-                    "browser": "zeroclaw",
-                    "device": "zeroclaw"
-                }
-            }
-        });
-        write.send(Message::Text(identify.to_string())).await?;
-
-        tracing::info!("Discord: connected and identified");
+        // Read Hello (opcode 10); heartbeats start only once it arrived
+        let mut decoder = gateway::FrameDecoder::new(self.compression)?;
+        let heartbeat_interval = gateway::read_hello(&mut read, &mut decoder)
+            .await
+            .map_err(|e| anyhow::anyhow!("Discord: {e}"))?;
 
         // Track the last sequence number for heartbeats and resume.
         // Only accessed in the select! loop below, so a plain i64 suffices.
         let mut sequence: i64 = -1;
 
-        // Spawn heartbeat timer — sends a tick signal, actual heartbeat
-        // is assembled in the select! loop where `sequence` lives.
-        let (hb_tx, mut hb_rx) = tokio::sync::mpsc::channel::<()>(1);
-        let hb_interval = heartbeat_interval;
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_millis(hb_interval));
-            loop {
-                interval.tick().await;
-                if hb_tx.send(()).await.is_err() {
-                    break;
+        if let Some(saved) = saved {
+            // Resume (opcode 6) replays the events missed while we were away
+            let resume = json!({
+                "op": op::RESUME,
+                "d": {
+                    "token": token,
+                    "session_id": saved.session_id,
+                    "seq": saved.sequence
                 }
-            }
-        });
+            });
+            frame_tap::record_json(&self.name, Direction::Outbound, "gateway", &resume);
+            write.send(Message::Text(resume.to_string())).await?;
+            sequence = saved.sequence;
+            tracing::info!("Discord: connected, resuming session at seq {sequence}");
+        } else {
+            // Send Identify (opcode 2)
+            let identify = json!({
+                "op": op::IDENTIFY,
+                "d": {
+                    "token": token,
+                    // GUILDS | GUILD_MESSAGES | GUILD_MESSAGE_REACTIONS | DIRECT_MESSAGES |
+                    // DIRECT_MESSAGE_REACTIONS | MESSAGE_CONTENT
+                    "intents": 46593,
+                    "properties": {
+                        "os": "linux",
+                        "browser": "zeroclaw",
+                        "device": "zeroclaw"
+                    }
+                }
+            });
+            frame_tap::record_json(&self.name, Direction::Outbound, "gateway", &identify);
+            write.send(Message::Text(identify.to_string())).await?;
+            tracing::info!("Discord: connected and identified");
+        }
+
+        let mut hb_rx =
+            gateway::spawn_heartbeat(&self.name, heartbeat_interval, self.heartbeat_jitter);
 
         let guild_filter = self.guild_id.clone();
 
         loop {
             tokio::select! {
                 _ = hb_rx.recv() => {
-                    let d = if sequence >= 0 { json!(sequence) } else { json!(null) };
-                    let hb = json!({"op": 1, "d": d});
-                    if write.send(Message::Text(hb.to_string())).await.is_err() {
+                    if write.send(Message::Text(gateway::heartbeat_payload(sequence))).await.is_err() {
                         break;
                     }
+                    gateway::record_heartbeat(&self.name);
+                    session.persist(sequence);
                 }
                 msg = read.next() => {
                     let msg = match msg {
-                        Some(Ok(Message::Text(t))) => t,
-                        Some(Ok(Message::Close(_))) | None => break,
-                        _ => continue,
+                        Some(Ok(msg)) => msg,
+                        Some(Err(e)) => {
+                            tracing::warn!("Discord: gateway read failed: {e}");
+                            break;
+                        }
+                        None => break,
+                    };
+                    let msg = match decoder.decode(msg) {
+                        Ok(GatewayFrame::Payload(text)) => text,
+                        Ok(GatewayFrame::Ping(data)) => {
+                            if write.send(Message::Pong(data)).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+                        Ok(GatewayFrame::Close { code, reason }) => {
+                            tracing::warn!(
+                                "Discord: gateway closed ({}), reconnecting",
+                                gateway::describe_close(code, &reason)
+                            );
+                            break;
+                        }
+                        Ok(GatewayFrame::Skip) => continue,
+                        Err(e) => {
+                            // A broken compression stream cannot be resumed mid-connection
+                            tracing::warn!("Discord: {e:#}, reconnecting");
+                            break;
+                        }
                     };
+                    tracing::trace!("Discord: gateway frame ← {msg}");
+                    frame_tap::record(&self.name, Direction::Inbound, "gateway", &msg);
 
-                    let event: serde_json::Value = match serde_json::from_str(&msg) {
-                        Ok(e) => e,
-                        Err(_) => continue,
+                    let Some(event) = gateway::parse_payload(&self.name, &msg) else {
+                        continue;
                     };
 
                     // Track sequence number from all dispatch events
-                    if let Some(s) = event.get("s").and_then(serde_json::Value::as_i64) {
+                    if let Some(s) = event.s {
                         sequence = s;
                     }
 
-                    let op = event.get("op").and_then(serde_json::Value::as_u64).unwrap_or(0);
-
-                    match op {
-                        // Op 1: Server requests an immediate heartbeat
-                        1 => {
-                            let d = if sequence >= 0 { json!(sequence) } else { json!(null) };
-                            let hb = json!({"op": 1, "d": d});
-                            if write.send(Message::Text(hb.to_string())).await.is_err() {
+                    match event.op {
+                        // Server requests an immediate heartbeat
+                        op::HEARTBEAT => {
+                            if write.send(Message::Text(gateway::heartbeat_payload(sequence))).await.is_err() {
                                 break;
                             }
                             continue;
                         }
-                        // Op 7: Reconnect
-                        7 => {
+                        op::HEARTBEAT_ACK => {
+                            gateway::record_heartbeat_ack(&self.name);
+                            continue;
+                        }
+                        op::RECONNECT => {
                             tracing::warn!("Discord: received Reconnect (op 7), closing for restart");
                             break;
                         }
-                        // Op 9: Invalid Session
-                        9 => {
+                        op::INVALID_SESSION => {
                             tracing::warn!("Discord: received Invalid Session (op 9), closing for restart");
+                            session.invalidate();
                             break;
                         }
                         _ => {}
                     }
 
                     // Only handle MESSAGE_CREATE (opcode 0, type "MESSAGE_CREATE")
-                    let event_type = event.get("t").and_then(|t| t.as_str()).unwrap_or("");
-                    if event_type != "MESSAGE_CREATE" {
-                        continue;
+                    match event.event_type() {
+                        "READY" => {
+                            if let Some(ready) = event.decode::<gateway::Ready>(&self.name) {
+                                session.ready(
+                                    &ready.session_id,
+                                    ready.resume_gateway_url.as_deref(),
+                                    sequence,
+                                );
+                            }
+                            continue;
+                        }
+                        "RESUMED" => {
+                            tracing::info!("Discord: session resumed");
+                            continue;
+                        }
+                        "MESSAGE_CREATE" => {}
+                        "MESSAGE_REACTION_ADD" | "MESSAGE_REACTION_REMOVE" => {
+                            if let Some(reaction) = event.decode::<ReactionEvent>(&self.name) {
+                                if reaction.user_id != bot_user_id {
+                                    let added = event.event_type() == "MESSAGE_REACTION_ADD";
+                                    record_reaction(&self.name, &reaction, added);
+                                }
+                            }
+                            continue;
+                        }
+                        "INTERACTION_CREATE" => {
+                            let Some(click) = event.decode::<ComponentInteraction>(&self.name) else {
+                                continue;
+                            };
+                            let Some(interaction) = click.to_interaction(&self.name) else {
+                                continue;
+                            };
+                            self.acknowledge_interaction(&click).await;
+                            if !self.is_user_allowed(&interaction.user_id) {
+                                tracing::warn!("Discord: ignoring click from unauthorized user: {}", interaction.user_id);
+                                continue;
+                            }
+                            if let (Some(gid), Some(g)) = (&guild_filter, click.guild_id.as_deref()) {
+                                if g != gid {
+                                    continue;
+                                }
+                            }
+                            if !interactions::route(self, interaction, &tx).await {
+                                break;
+                            }
+                            continue;
+                        }
+                        _ => continue,
                     }
 
-                    let Some(d) = event.get("d") else {
+                    let Some(d) = event.decode::<MessageEvent>(&self.name) else {
                         continue;
                     };
 
                     // Skip messages from the bot itself
-                    let author_id = d.get("author").and_then(|a| a.get("id")).and_then(|i| i.as_str()).unwrap_or("");
+                    let author_id = d.author.id.as_deref().unwrap_or("");
                     if author_id == bot_user_id {
                         continue;
                     }
 
                     // Skip bot messages (unless listen_to_bots is enabled)
-                    if !self.listen_to_bots && d.get("author").and_then(|a| a.get("bot")).and_then(serde_json::Value::as_bool).unwrap_or(false) {
+                    if !self.listen_to_bots && d.author.bot {
                         continue;
                     }
 
@@ -332,57 +906,52 @@ impl Channel for DiscordChannel {
 
                     // Guild filter
                     if let Some(ref gid) = guild_filter {
-                        let msg_guild = d.get("guild_id").and_then(serde_json::Value::as_str);
                         // DMs have no guild_id — let them through; for guild messages, enforce the filter
-                        if let Some(g) = msg_guild {
+                        if let Some(g) = d.guild_id.as_deref() {
                             if g != gid {
                                 continue;
                             }
                         }
                     }
 
-                    let content = d.get("content").and_then(|c| c.as_str()).unwrap_or("");
-                    if content.is_empty() {
+                    // Strip the bot mention from content so the agent sees clean text
+                    let parsed = mentions::parse(&d.content, Some(&bot_user_id));
+                    let attachments = parse_attachments(&d.attachments);
+                    if parsed.text.is_empty() && attachments.is_empty() {
                         continue;
                     }
 
                     // Skip messages that don't @-mention the bot (when mention_only is enabled)
-                    if self.mention_only {
-                        let mention_tag = format!("<@{bot_user_id}>");
-                        if !content.contains(&mention_tag) {
-                            continue;
-                        }
+                    if self.mention_only && !parsed.addressed {
+                        continue;
                     }
 
-                    // Strip the bot mention from content so the agent sees clean text
-                    let clean_content = if self.mention_only {
-                        let mention_tag = format!("<@{bot_user_id}>");
-                        content.replace(&mention_tag, "").trim().to_string()
-                    } else {
-                        content.to_string()
-                    };
-
-                    let message_id = d.get("id").and_then(|i| i.as_str()).unwrap_or("");
-                    let channel_id = d.get("channel_id").and_then(|c| c.as_str()).unwrap_or("").to_string();
+                    // The snowflake is the dedup key; without it a redelivery
+                    // could not be told apart.
+                    if d.id.is_empty() {
+                        tracing::warn!("Discord: ignoring message without an id");
+                        continue;
+                    }
+                    let channel_id = d.channel_id.clone().unwrap_or_default();
 
                     let channel_msg = ChannelMessage {
-                        id: if message_id.is_empty() {
-                            Uuid::new_v4().to_string()
-                        } else {
-                            format!("discord_{message_id}")
-                        },
+                        id: format!("discord_{}", d.id),
                         sender: author_id.to_string(),
                         reply_target: if channel_id.is_empty() {
                             author_id.to_string()
                         } else {
                             channel_id.clone()
                         },
-                        content: clean_content,
-                        channel: channel_id,
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
+                        content: parsed.text,
+                        channel: self.name.clone(),
+                        timestamp: d.sent_at().unwrap_or_else(unix_now),
+                        received_at: unix_now(),
+                        attachments,
+                        sender_id: Some(author_id.to_string()),
+                        sender_name: d.author.display_name(),
+                        conversation_id: d.channel_id.clone(),
+                        mentions: parsed.mentioned,
+                        is_mention: parsed.addressed,
                     };
 
                     if tx.send(channel_msg).await.is_err() {
@@ -392,13 +961,17 @@ impl Channel for DiscordChannel {
             }
         }
 
+        session.persist(sequence);
         Ok(())
     }
 
     async fn health_check(&self) -> bool {
+        let Ok(authorization) = self.authorization().await else {
+            return false;
+        };
         self.client
             .get("https://discord.com/api/v10/users/@me")
-            .header("Authorization", format!("Bot {}", self.bot_token))
+            .header("Authorization", authorization)
             .send()
             .await
             .map(|r| r.status().is_success())
@@ -409,7 +982,7 @@ impl Channel for DiscordChannel {
         self.stop_typing(recipient).await?;
 
         let client = self.client.clone();
-        let token = self.bot_token.clone();
+        let authorization = self.authorization().await?;
         let channel_id = recipient.to_string();
 
         let handle = tokio::spawn(async move {
@@ -417,7 +990,7 @@ impl Channel for DiscordChannel {
             loop {
                 let _ = client
                     .post(&url)
-                    .header("Authorization", format!("Bot {token}"))
+                    .header("Authorization", &authorization)
                     .send()
                     .await;
                 tokio::time::sleep(std::time::Duration::from_secs(8)).await;
@@ -451,6 +1024,16 @@ mod tests {
         assert_eq!(ch.name(), "discord");
     }
 
+    #[test]
+    fn discord_capabilities() {
+        let ch = DiscordChannel::new("fake".into(), None, vec![], false, false);
+        let caps = ch.capabilities();
+        assert!(caps.supports_edit && caps.supports_files);
+        assert!(caps.supports_markdown && caps.supports_reactions && caps.supports_typing);
+        assert!(!caps.supports_media_markers);
+        assert_eq!(caps.max_message_len, DISCORD_MAX_MESSAGE_LENGTH);
+    }
+
     #[test]
     fn base64_decode_bot_id() {
         // "MTIzNDU2" decodes to "123456"
@@ -775,16 +1358,87 @@ mod tests {
     }
 
     #[test]
-    fn discord_message_id_fallback_to_uuid_on_empty() {
-        // Edge case: empty message_id falls back to UUID
-        let message_id = "";
-        let id = if message_id.is_empty() {
-            format!("discord_{}", uuid::Uuid::new_v4())
-        } else {
-            format!("discord_{message_id}")
+    fn parse_attachments_maps_content_types() {
+        let d: MessageEvent = serde_json::from_value(serde_json::json!({
+            "attachments": [
+                {
+                    "url": "https://cdn.discordapp.com/a/cat.png",
+                    "filename": "cat.png",
+                    "content_type": "image/png"
+                },
+                {
+                    "url": "https://cdn.discordapp.com/a/voice-message.ogg",
+                    "filename": "voice-message.ogg",
+                    "content_type": "audio/ogg"
+                },
+                { "filename": "no-url.txt" }
+            ]
+        }))
+        .unwrap();
+        let attachments = parse_attachments(&d.attachments);
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].kind, MediaKind::Image);
+        assert_eq!(attachments[0].file_name.as_deref(), Some("cat.png"));
+        assert_eq!(attachments[1].kind, MediaKind::Audio);
+        assert!(parse_attachments(&[]).is_empty());
+    }
+
+    #[test]
+    fn component_clicks_become_interactions() {
+        let click: ComponentInteraction = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "token": "tok",
+            "type": 3,
+            "channel_id": "c1",
+            "guild_id": "g1",
+            "member": { "user": { "id": "u1", "username": "alice" } },
+            "data": { "custom_id": "menu:yes", "component_type": 2 },
+            "message": { "id": "m1" }
+        }))
+        .unwrap();
+        let interaction = click.to_interaction("discord").unwrap();
+        assert_eq!(interaction.callback_id, "menu:yes");
+        assert_eq!(interaction.user_id, "u1");
+        assert_eq!(interaction.reply_target, "c1");
+        assert_eq!(interaction.message_id.as_deref(), Some("m1"));
+
+        let command: ComponentInteraction = serde_json::from_value(serde_json::json!({
+            "id": "2", "token": "tok", "type": 2, "user": { "id": "u1" }
+        }))
+        .unwrap();
+        assert!(command.to_interaction("discord").is_none());
+
+        let keyboard = Keyboard::new().with_row(vec![interactions::Button::new("menu:yes", "Yes")]);
+        assert_eq!(
+            components(&keyboard),
+            serde_json::json!([{ "type": 1, "components": [
+                { "type": 2, "style": 1, "label": "Yes", "custom_id": "menu:yes" }
+            ] }])
+        );
+    }
+
+    #[test]
+    fn reactions_vote_on_reaction_polls() {
+        let poll = Poll::new("Ship it?", vec!["Yes".into(), "No".into()]);
+        let id = polls::track("discord", "poll-msg", "c1", poll);
+        let reaction = |user: &str, emoji: &str| ReactionEvent {
+            user_id: user.into(),
+            message_id: "poll-msg".into(),
+            emoji: ReactionEmoji {
+                name: Some(emoji.into()),
+            },
         };
-        assert!(id.starts_with("discord_"));
-        // Should have UUID dashes
-        assert!(id.contains('-'));
+        record_reaction("discord", &reaction("u1", "1️⃣"), true);
+        record_reaction("discord", &reaction("u2", "2️⃣"), true);
+        record_reaction("discord", &reaction("u3", "👍"), true);
+        record_reaction("discord", &reaction("u2", "2️⃣"), false);
+
+        let votes: Vec<usize> = polls::results(&id)
+            .unwrap()
+            .options
+            .iter()
+            .map(|o| o.votes)
+            .collect();
+        assert_eq!(votes, vec![1, 0]);
     }
 }
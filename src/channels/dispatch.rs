@@ -0,0 +1,201 @@
+//! Worker pool of the channel dispatch loop (`[channels_config.dispatch]`).
+//!
+//! Each inbound message holds a [`Worker`] from the [`WorkerPool`] while it
+//! is processed: one of `workers` slots shared by all channels and, when
+//! `max_per_channel` is set, one of its channel's. Messages wait for a
+//! worker inside their conversation lane (see [`super::lanes`]), so each
+//! conversation stays in order, but only [`WorkerPool::max_admitted`] of them
+//! at a time: once that many are running or waiting, the dispatcher stops
+//! taking messages and the inbound queues fill up behind it.
+//! [Urgent](super::priority) messages skip the channel limit and may also take
+//! one of the `reserved` workers, which chat never uses.
+
+use super::net;
+use super::priority::Priority;
+use crate::config::DispatchConfig;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const WORKERS_PER_CHANNEL: usize = 4;
+const MIN_WORKERS: usize = 8;
+const MAX_WORKERS: usize = 64;
+/// Messages admitted per worker: the one it runs and one waiting for it
+const ADMITTED_PER_WORKER: usize = 2;
+
+pub struct WorkerPool {
+    workers: usize,
+    global: Arc<Semaphore>,
+    /// Slots only urgent messages use
+    reserved: Arc<Semaphore>,
+    max_per_channel: usize,
+    channels: Mutex<HashMap<String, Arc<Semaphore>>>,
+    /// Processing deadlines by channel name, in seconds
+    deadlines: HashMap<String, u64>,
+}
+
+/// A busy worker; dropping it frees its slots.
+pub struct Worker {
+    _channel: Option<OwnedSemaphorePermit>,
+    _global: OwnedSemaphorePermit,
+}
+
+impl WorkerPool {
+    /// `workers` slots in total and at most `max_per_channel` per channel
+    /// (0 for no limit).
+    pub fn new(workers: usize, max_per_channel: usize) -> Self {
+        let workers = workers.max(1);
+        Self {
+            workers,
+            global: Arc::new(Semaphore::new(workers)),
+            reserved: Arc::new(Semaphore::new(0)),
+            max_per_channel,
+            channels: Mutex::new(HashMap::new()),
+            deadlines: HashMap::new(),
+        }
+    }
+
+    pub fn from_config(config: &DispatchConfig, channel_count: usize) -> Self {
+        let workers = match config.workers {
+            0 => channel_count
+                .saturating_mul(WORKERS_PER_CHANNEL)
+                .clamp(MIN_WORKERS, MAX_WORKERS),
+            workers => workers,
+        };
+        Self {
+            deadlines: config.message_deadline_secs.clone(),
+            ..Self::new(workers, config.max_per_channel)
+        }
+    }
+
+    /// Keep `reserved` more workers for urgent messages.
+    pub fn with_reserved(mut self, reserved: usize) -> Self {
+        self.workers += reserved;
+        self.reserved = Arc::new(Semaphore::new(reserved));
+        self
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Messages the dispatcher may hold at once, running or waiting for a
+    /// worker.
+    pub fn max_admitted(&self) -> usize {
+        self.workers.saturating_mul(ADMITTED_PER_WORKER)
+    }
+
+    /// Workers processing a message right now.
+    pub fn busy(&self) -> usize {
+        self.workers - self.global.available_permits() - self.reserved.available_permits()
+    }
+
+    /// Wait for a worker to process a message of `priority` on `channel`.
+    pub async fn acquire(&self, channel: &str, priority: Priority) -> Worker {
+        if priority == Priority::Urgent {
+            let permit = tokio::select! {
+                permit = Arc::clone(&self.reserved).acquire_owned() => permit,
+                permit = Arc::clone(&self.global).acquire_owned() => permit,
+            };
+            return Worker {
+                _channel: None,
+                _global: permit.expect("worker pool semaphores are never closed"),
+            };
+        }
+        // The channel's slot comes first, so a channel at its limit doesn't
+        // hold shared slots while it waits
+        let channel_permit = match self.max_per_channel {
+            0 => None,
+            limit => {
+                let semaphore = Arc::clone(
+                    self.channels
+                        .lock()
+                        .entry(channel.to_string())
+                        .or_insert_with(|| Arc::new(Semaphore::new(limit))),
+                );
+                Some(
+                    semaphore
+                        .acquire_owned()
+                        .await
+                        .expect("worker pool semaphores are never closed"),
+                )
+            }
+        };
+        let global = Arc::clone(&self.global)
+            .acquire_owned()
+            .await
+            .expect("worker pool semaphores are never closed");
+        Worker {
+            _channel: channel_permit,
+            _global: global,
+        }
+    }
+
+    /// How long a message on `channel` may take to process, if limited.
+    pub fn deadline(&self, channel: &str) -> Option<Duration> {
+        match self.deadlines.get(channel) {
+            Some(0) => None,
+            Some(&secs) => Some(Duration::from_secs(secs)),
+            None => net::message_deadline(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn busy_channels_leave_workers_for_others() {
+        let pool = WorkerPool::new(3, 1);
+        let _first = pool.acquire("telegram", Priority::Normal).await;
+
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(50),
+            pool.acquire("telegram", Priority::Normal),
+        );
+        assert!(waiting.await.is_err());
+        let _other = pool.acquire("discord", Priority::Normal).await;
+        assert_eq!(pool.busy(), 2);
+    }
+
+    #[tokio::test]
+    async fn urgent_messages_use_reserved_workers() {
+        let pool = WorkerPool::new(1, 1).with_reserved(1);
+        let _chat = pool.acquire("telegram", Priority::Normal).await;
+
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(50),
+            pool.acquire("discord", Priority::Normal),
+        );
+        assert!(waiting.await.is_err());
+        let _urgent = pool.acquire("telegram", Priority::Urgent).await;
+        assert_eq!(pool.busy(), 2);
+        assert_eq!(pool.workers(), 2);
+    }
+
+    #[test]
+    fn sizes_the_pool_and_deadlines_from_config() {
+        let config = DispatchConfig {
+            message_deadline_secs: HashMap::from([
+                ("slow-bot".to_string(), 900),
+                ("cli".to_string(), 0),
+            ]),
+            ..DispatchConfig::default()
+        };
+        let pool = WorkerPool::from_config(&config, 3);
+        assert_eq!(pool.workers(), 12);
+        assert_eq!(pool.max_admitted(), 24);
+        assert_eq!(pool.deadline("slow-bot"), Some(Duration::from_secs(900)));
+        assert_eq!(pool.deadline("cli"), None);
+        assert_eq!(pool.deadline("telegram"), net::message_deadline());
+
+        let config = DispatchConfig {
+            workers: 2,
+            ..DispatchConfig::default()
+        };
+        assert_eq!(WorkerPool::from_config(&config, 30).workers(), 2);
+    }
+}
@@ -0,0 +1,297 @@
+//! Dry run (`[channels_config.dry_run]`, `zeroclaw channel start --dry-run`).
+//!
+//! Channels in dry run are wrapped in a [`DryRunChannel`]: they still listen,
+//! and inbound messages go through middleware, the agent and the reply
+//! pipeline as usual, but whatever the channel would send is logged instead
+//! of reaching the platform. Useful to try a new config or model against
+//! real traffic without anyone seeing the replies.
+
+use super::interactions::Keyboard;
+use super::polls::Poll;
+use super::traits::{Channel, ChannelCapabilities, ChannelMessage, MediaAttachment};
+use super::uploads::FileReader;
+use crate::config::DryRunConfig;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Wraps a channel so sends are logged rather than delivered.
+pub struct DryRunChannel {
+    inner: Arc<dyn Channel>,
+    next_id: AtomicU64,
+}
+
+impl DryRunChannel {
+    pub fn new(inner: Arc<dyn Channel>) -> Self {
+        Self {
+            inner,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn log(&self, recipient: &str, what: &str) {
+        tracing::info!("[dry-run] {} → {recipient}: {what}", self.inner.name());
+    }
+
+    /// Id handed out for a message that was never sent, so polls, receipts
+    /// and streamed edits can refer back to it.
+    fn fake_id(&self) -> String {
+        format!("dry-run-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Wrap the channels `config` puts in dry run.
+pub fn wrap(channels: Vec<Arc<dyn Channel>>, config: &DryRunConfig) -> Vec<Arc<dyn Channel>> {
+    channels
+        .into_iter()
+        .map(|channel| {
+            if config.applies_to(channel.name()) {
+                Arc::new(DryRunChannel::new(channel)) as Arc<dyn Channel>
+            } else {
+                channel
+            }
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Channel for DryRunChannel {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+        self.log(recipient, message);
+        Ok(())
+    }
+
+    fn send_interval(&self) -> Duration {
+        self.inner.send_interval()
+    }
+
+    async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        self.inner.listen(tx).await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+
+    async fn start_typing(&self, _recipient: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn stop_typing(&self, _recipient: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn supports_voice_notes(&self) -> bool {
+        self.inner.supports_voice_notes()
+    }
+
+    async fn send_voice_note(
+        &self,
+        audio: Vec<u8>,
+        file_name: &str,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        self.log(
+            recipient,
+            &format!("voice note {file_name} ({} bytes)", audio.len()),
+        );
+        Ok(())
+    }
+
+    fn supports_images(&self) -> bool {
+        self.inner.supports_images()
+    }
+
+    async fn send_image(
+        &self,
+        image: Vec<u8>,
+        file_name: &str,
+        caption: Option<&str>,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        self.log(
+            recipient,
+            &format!(
+                "image {file_name} ({} bytes){}",
+                image.len(),
+                caption.map(|c| format!(": {c}")).unwrap_or_default()
+            ),
+        );
+        Ok(())
+    }
+
+    fn supports_buttons(&self) -> bool {
+        self.inner.supports_buttons()
+    }
+
+    async fn send_buttons(
+        &self,
+        text: &str,
+        keyboard: &Keyboard,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        let labels: Vec<&str> = keyboard.buttons().map(|b| b.label.as_str()).collect();
+        self.log(recipient, &format!("{text} [{}]", labels.join(" | ")));
+        Ok(())
+    }
+
+    fn supports_polls(&self) -> bool {
+        self.inner.supports_polls()
+    }
+
+    async fn send_poll(&self, poll: &Poll, recipient: &str) -> anyhow::Result<String> {
+        self.log(
+            recipient,
+            &format!("poll {} [{}]", poll.question, poll.options.join(" | ")),
+        );
+        Ok(self.fake_id())
+    }
+
+    fn supports_receipts(&self) -> bool {
+        self.inner.supports_receipts()
+    }
+
+    async fn send_tracked(&self, message: &str, recipient: &str) -> anyhow::Result<String> {
+        self.log(recipient, message);
+        Ok(self.fake_id())
+    }
+
+    fn supports_editing(&self) -> bool {
+        self.inner.supports_editing()
+    }
+
+    fn max_message_length(&self) -> usize {
+        self.inner.max_message_length()
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn send_editable(&self, message: &str, recipient: &str) -> anyhow::Result<String> {
+        self.log(recipient, message);
+        Ok(self.fake_id())
+    }
+
+    async fn edit_message(
+        &self,
+        message_id: &str,
+        message: &str,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        self.log(recipient, &format!("(edit {message_id}) {message}"));
+        Ok(())
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        self.inner.max_file_size()
+    }
+
+    async fn send_file(
+        &self,
+        recipient: &str,
+        name: &str,
+        mime: &str,
+        size: Option<u64>,
+        _reader: FileReader,
+    ) -> anyhow::Result<()> {
+        let size = size.map_or_else(|| "unknown size".to_string(), |s| format!("{s} bytes"));
+        self.log(recipient, &format!("file {name} ({mime}, {size})"));
+        Ok(())
+    }
+
+    async fn download_attachment(&self, attachment: &MediaAttachment) -> anyhow::Result<Vec<u8>> {
+        self.inner.download_attachment(attachment).await
+    }
+
+    async fn is_group_admin(&self, msg: &ChannelMessage) -> anyhow::Result<bool> {
+        self.inner.is_group_admin(msg).await
+    }
+
+    fn conversation_key(&self, msg: &ChannelMessage) -> String {
+        self.inner.conversation_key(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(&self, message: &str, _recipient: &str) -> anyhow::Result<()> {
+            self.sent.lock().push(message.to_string());
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn supports_editing(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_are_logged_not_delivered() {
+        let inner = Arc::new(RecordingChannel::default());
+        let channel = DryRunChannel::new(inner.clone());
+
+        channel.send("hello", "alice").await.unwrap();
+        let first = channel.send_editable("draft", "alice").await.unwrap();
+        let second = channel.send_editable("draft", "alice").await.unwrap();
+        channel
+            .edit_message(&first, "final", "alice")
+            .await
+            .unwrap();
+
+        assert!(inner.sent.lock().is_empty());
+        assert_ne!(first, second);
+        assert_eq!(channel.name(), "recording");
+        assert!(channel.supports_editing());
+    }
+
+    #[test]
+    fn per_channel_setting_overrides_the_global_one() {
+        let config = DryRunConfig {
+            enabled: true,
+            channels: HashMap::from([("recording".to_string(), false)]),
+        };
+        assert!(!config.applies_to("recording"));
+        assert!(config.applies_to("telegram"));
+
+        assert!(!DryRunConfig::default().applies_to("recording"));
+    }
+
+    #[tokio::test]
+    async fn wrap_leaves_channels_out_of_dry_run_alone() {
+        let inner = Arc::new(RecordingChannel::default());
+        let config = DryRunConfig {
+            enabled: false,
+            channels: HashMap::from([("telegram".to_string(), true)]),
+        };
+        let channels = wrap(vec![inner.clone()], &config);
+        channels[0].send("hello", "alice").await.unwrap();
+        assert_eq!(*inner.sent.lock(), ["hello"]);
+    }
+}
@@ -25,7 +25,7 @@ use tokio::time::{interval, sleep};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 
 /// Email channel configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -375,6 +375,17 @@ impl Channel for EmailChannel {
         "email"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Replies are sent as plain text mail
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> Result<()> {
         let (subject, body) = if message.starts_with("Subject: ") {
             if let Some(pos) = message.find('\n') {
@@ -430,6 +441,13 @@ impl Channel for EmailChannel {
                             content,
                             channel: "email".to_string(),
                             timestamp: ts,
+                            received_at: unix_now(),
+                            attachments: vec![],
+                            sender_id: None,
+                            sender_name: None,
+                            conversation_id: None,
+                            mentions: vec![],
+                            is_mention: false,
                         };
                         if tx.send(msg).await.is_err() {
                             return Ok(());
@@ -0,0 +1,396 @@
+//! Erasing everything kept about a user (`/forget-me`, `POST
+//! /admin/users/{channel}/{user}/forget`).
+//!
+//! A [`DataEraser`] deletes a person's conversation sessions, their
+//! auto-saved messages and profile-scoped memories, preferences, persona
+//! choice, reminders, per-user usage history and identity links. With
+//! identity linking on, the request covers every account linked to the one
+//! it names. The returned [`DeletionReceipt`] counts what was deleted in
+//! each store and lists any store that failed, so the request can be
+//! retried and its completion shown to the user.
+
+use super::sessions::SessionStore;
+use super::traits::ChannelMessage;
+use crate::cost::QuotaLedger;
+use crate::identity::linking::IdentityStore;
+use crate::memory::Memory;
+use crate::personas::PersonaStore;
+use crate::preferences::PreferenceStore;
+use crate::reminders::ReminderStore;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// What an erasure deleted.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeletionReceipt {
+    pub id: String,
+    pub erased_at: String,
+    /// `<channel>:<user>` of every account covered
+    pub accounts: Vec<String>,
+    /// Identity profile the accounts shared, when linking is on
+    pub profile: Option<String>,
+    pub sessions: usize,
+    pub memories: usize,
+    pub preferences: usize,
+    pub personas: usize,
+    pub reminders: usize,
+    pub usage_records: usize,
+    pub identity_links: usize,
+    /// Stores that failed; their data may remain
+    pub errors: Vec<String>,
+}
+
+impl DeletionReceipt {
+    /// Whether every store was purged.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The receipt as a chat reply.
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "🗑️ Your data has been deleted.\nReceipt: {} ({})\n",
+            self.id, self.erased_at
+        );
+        for (what, count) in [
+            ("Conversations", self.sessions),
+            ("Stored messages", self.memories),
+            ("Preferences", self.preferences + self.personas),
+            ("Reminders", self.reminders),
+            ("Usage records", self.usage_records),
+            ("Linked accounts", self.identity_links),
+        ] {
+            let _ = write!(text, "\n• {what}: {count}");
+        }
+        if !self.is_complete() {
+            let _ = write!(
+                text,
+                "\n\n⚠️ Some data could not be deleted ({}). Send /forget-me confirm again or contact the operator.",
+                self.errors.join("; ")
+            );
+        }
+        text
+    }
+}
+
+pub struct DataEraser {
+    memory: Arc<dyn Memory>,
+    sessions: Arc<SessionStore>,
+    identities: Option<Arc<IdentityStore>>,
+    preferences: Option<Arc<PreferenceStore>>,
+    personas: Option<Arc<PersonaStore>>,
+    reminders: Option<Arc<ReminderStore>>,
+    quotas: Option<Arc<QuotaLedger>>,
+}
+
+impl DataEraser {
+    pub fn new(
+        memory: Arc<dyn Memory>,
+        sessions: Arc<SessionStore>,
+        identities: Option<Arc<IdentityStore>>,
+        preferences: Option<Arc<PreferenceStore>>,
+        personas: Option<Arc<PersonaStore>>,
+        reminders: Option<Arc<ReminderStore>>,
+        quotas: Option<Arc<QuotaLedger>>,
+    ) -> Self {
+        Self {
+            memory,
+            sessions,
+            identities,
+            preferences,
+            personas,
+            reminders,
+            quotas,
+        }
+    }
+
+    /// Delete everything kept about `user` on `channel` and the accounts
+    /// linked to it. `aliases` are other ids the same account goes by on
+    /// `channel` (a display handle next to the platform id).
+    pub async fn erase(&self, channel: &str, user: &str, aliases: &[&str]) -> DeletionReceipt {
+        let mut receipt = DeletionReceipt {
+            id: uuid::Uuid::new_v4().to_string(),
+            erased_at: Utc::now().to_rfc3339(),
+            ..DeletionReceipt::default()
+        };
+
+        let mut accounts: BTreeSet<(String, String)> = std::iter::once(user)
+            .chain(aliases.iter().copied())
+            .map(|id| (channel.to_string(), id.to_string()))
+            .collect();
+        if let Some(identities) = &self.identities {
+            match identities.forget(channel, user) {
+                Ok(Some((profile, linked))) => {
+                    receipt.identity_links = linked.len();
+                    accounts.extend(
+                        linked
+                            .into_iter()
+                            .map(|account| (account.channel, account.sender)),
+                    );
+                    receipt.profile = Some(profile);
+                }
+                Ok(None) => {}
+                Err(e) => receipt.errors.push(format!("identity links: {e}")),
+            }
+        }
+
+        // Per-user settings are stored under the profile, or under
+        // `<channel>:<user>` for accounts seen before linking was on
+        let user_keys: BTreeSet<String> = accounts
+            .iter()
+            .map(|(channel, user)| format!("{channel}:{user}"))
+            .chain(receipt.profile.clone())
+            .collect();
+
+        for (channel, user) in &accounts {
+            receipt.sessions += self.sessions.forget_user(channel, user);
+        }
+        match self.erase_memories(&accounts, &user_keys).await {
+            Ok(count) => receipt.memories = count,
+            Err(e) => receipt.errors.push(format!("memory: {e}")),
+        }
+        for key in &user_keys {
+            if let Some(preferences) = &self.preferences {
+                match preferences.forget(key) {
+                    Ok(removed) => receipt.preferences += usize::from(removed),
+                    Err(e) => receipt.errors.push(format!("preferences: {e}")),
+                }
+            }
+            if let Some(personas) = &self.personas {
+                match personas.forget(key) {
+                    Ok(removed) => receipt.personas += usize::from(removed),
+                    Err(e) => receipt.errors.push(format!("personas: {e}")),
+                }
+            }
+            if let Some(reminders) = &self.reminders {
+                match reminders.forget(key) {
+                    Ok(removed) => receipt.reminders += removed,
+                    Err(e) => receipt.errors.push(format!("reminders: {e}")),
+                }
+            }
+            if let Some(quotas) = &self.quotas {
+                match quotas.forget(key) {
+                    Ok(removed) => receipt.usage_records += removed,
+                    Err(e) => receipt.errors.push(format!("usage: {e}")),
+                }
+            }
+        }
+
+        receipt.accounts = accounts
+            .into_iter()
+            .map(|(channel, user)| format!("{channel}:{user}"))
+            .collect();
+        if receipt.is_complete() {
+            tracing::info!("Erased user data (receipt {})", receipt.id);
+        } else {
+            tracing::warn!(
+                "Erased user data partially (receipt {}): {}",
+                receipt.id,
+                receipt.errors.join("; ")
+            );
+        }
+        receipt
+    }
+
+    /// Memories owned by the accounts: stored under their profile or
+    /// `<channel>:<user>` key, as auto-saved messages are. Messages saved
+    /// before owners were recorded are matched on their key
+    /// `<channel>_<user>_<id>`, only when the rest is a bare message id, so
+    /// erasing `alice` never takes the messages of `alice_smith`.
+    async fn erase_memories(
+        &self,
+        accounts: &BTreeSet<(String, String)>,
+        user_keys: &BTreeSet<String>,
+    ) -> anyhow::Result<usize> {
+        let prefixes: Vec<String> = accounts
+            .iter()
+            .map(|(channel, user)| format!("{channel}_{user}_"))
+            .collect();
+        let mut removed = 0;
+        for entry in self.memory.list(None, None).await? {
+            let owned = match entry.session_id.as_deref() {
+                Some(owner) => user_keys.contains(owner),
+                None => prefixes.iter().any(|prefix| {
+                    entry
+                        .key
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|id| !id.is_empty() && !id.contains('_'))
+                }),
+            };
+            if owned && self.memory.forget(&entry.key).await? {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Answer `/forget-me`: without `confirm` it explains what will be
+/// deleted, with it the sender's data is erased. Returns `None` for any
+/// other message.
+pub async fn handle_command(eraser: &DataEraser, msg: &ChannelMessage) -> Option<String> {
+    let mut words = msg.content.split_whitespace();
+    if words.next()?.split('@').next()? != "/forget-me" {
+        return None;
+    }
+    let reply = match (words.next(), words.next()) {
+        (None, _) => "⚠️ This permanently deletes your conversation history, stored messages, \
+                      preferences, reminders, usage history and linked accounts. \
+                      Send /forget-me confirm to go ahead."
+            .to_string(),
+        (Some(word), None) if word.eq_ignore_ascii_case("confirm") => {
+            let aliases: Vec<&str> = [msg.sender.as_str()]
+                .into_iter()
+                .filter(|sender| *sender != msg.author_id())
+                .collect();
+            eraser
+                .erase(&msg.channel, msg.author_id(), &aliases)
+                .await
+                .describe()
+        }
+        _ => "Usage: /forget-me [confirm]".to_string(),
+    };
+    Some(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PreferencesConfig, RemindersConfig};
+    use crate::kv::KvStore;
+    use crate::memory::{MemoryCategory, SqliteMemory};
+    use tempfile::TempDir;
+
+    fn command(sender: &str, content: &str) -> ChannelMessage {
+        ChannelMessage {
+            id: "9".into(),
+            ..ChannelMessage::test("telegram", sender, content)
+        }
+    }
+
+    #[tokio::test]
+    async fn forget_me_purges_linked_accounts_across_stores() {
+        let tmp = TempDir::new().unwrap();
+        let memory: Arc<dyn Memory> = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        let sessions = Arc::new(SessionStore::default());
+        let identities = Arc::new(IdentityStore::in_memory(600).unwrap());
+        let kv = Arc::new(KvStore::in_memory().unwrap());
+        let preferences = Arc::new(PreferenceStore::new(
+            kv.namespace("preferences"),
+            PreferencesConfig::default(),
+        ));
+        let reminders = Arc::new(ReminderStore::in_memory(RemindersConfig::default()).unwrap());
+
+        let code = identities.issue_code("telegram", "alice").unwrap();
+        let profile = identities.redeem_code(&code, "slack", "U1").unwrap();
+        identities.profile_for("telegram", "bob").unwrap();
+        preferences.set(&profile, "language", "de").unwrap();
+        preferences.set("telegram:bob", "language", "fr").unwrap();
+        for key in ["telegram:dm:alice", "slack:dm:U1", "telegram:dm:bob"] {
+            sessions.begin(key);
+            sessions.append(key, "hello", "hi");
+        }
+        for (key, session) in [
+            ("telegram_alice_1", Some(profile.as_str())),
+            ("slack_U1_2", Some(profile.as_str())),
+            ("fact", Some(profile.as_str())),
+            ("telegram_bob_3", None),
+        ] {
+            memory
+                .store(key, "note", MemoryCategory::Conversation, session)
+                .await
+                .unwrap();
+        }
+
+        let eraser = DataEraser::new(
+            Arc::clone(&memory),
+            Arc::clone(&sessions),
+            Some(Arc::clone(&identities)),
+            Some(Arc::clone(&preferences)),
+            None,
+            Some(Arc::clone(&reminders)),
+            None,
+        );
+        let warning = handle_command(&eraser, &command("alice", "/forget-me"))
+            .await
+            .unwrap();
+        assert!(warning.contains("/forget-me confirm"));
+        assert!(sessions.current("telegram:dm:alice").is_some());
+
+        let receipt = eraser.erase("telegram", "alice", &[]).await;
+        assert!(receipt.is_complete());
+        assert_eq!(receipt.profile.as_deref(), Some(profile.as_str()));
+        assert_eq!(receipt.accounts, ["slack:U1", "telegram:alice"]);
+        assert_eq!(
+            (
+                receipt.sessions,
+                receipt.memories,
+                receipt.preferences,
+                receipt.identity_links
+            ),
+            (2, 3, 1, 2)
+        );
+
+        assert!(sessions.current("slack:dm:U1").is_none());
+        assert!(sessions.current("telegram:dm:bob").is_some());
+        assert_eq!(memory.count().await.unwrap(), 1);
+        assert!(preferences
+            .settings("telegram:bob")
+            .unwrap()
+            .language
+            .is_some());
+        assert!(identities.forget("slack", "U1").unwrap().is_none());
+
+        assert!(handle_command(&eraser, &command("alice", "/forget-me now"))
+            .await
+            .unwrap()
+            .starts_with("Usage"));
+        assert!(handle_command(&eraser, &command("alice", "/forget"))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn erasing_a_user_spares_senders_whose_name_extends_theirs() {
+        let tmp = TempDir::new().unwrap();
+        let memory: Arc<dyn Memory> = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        for (key, owner) in [
+            ("telegram_alice_1", Some("telegram:alice")),
+            ("telegram_alice_smith_2", Some("telegram:alice_smith")),
+            ("telegram_alice_smith_3", None),
+            ("telegram_alice_4", None),
+        ] {
+            memory
+                .store(key, "note", MemoryCategory::Conversation, owner)
+                .await
+                .unwrap();
+        }
+        let eraser = DataEraser::new(
+            Arc::clone(&memory),
+            Arc::new(SessionStore::default()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let receipt = eraser.erase("telegram", "alice", &[]).await;
+        assert!(receipt.is_complete());
+        assert_eq!(receipt.memories, 2);
+        assert!(memory
+            .get("telegram_alice_smith_2")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(memory
+            .get("telegram_alice_smith_3")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(memory.get("telegram_alice_1").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,146 @@
+//! One stream of everything happening on the channels, for live views.
+//!
+//! Merges the control plane's taps of accepted inbound messages and
+//! delivered replies with the [`hooks`] lifecycle events (sends, failures,
+//! listeners connecting and disconnecting, reloads). The admin API serves
+//! it as server-sent events to the dashboard (`GET /admin/live`) and over a
+//! WebSocket to external tools (`GET /ws/events`).
+
+use super::control::{ChannelControl, OutboundRecord};
+use super::hooks::{self, LifecycleEvent};
+use super::traits::ChannelMessage;
+use crate::security::redact::redact;
+use crate::util::truncate_with_ellipsis;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// One event of the stream, serialized with a `kind` tag.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Inbound {
+        channel: String,
+        message_id: String,
+        sender: String,
+        sender_name: Option<String>,
+        content: String,
+        at: String,
+    },
+    Outbound(OutboundRecord),
+    /// `event` names the lifecycle event (see [`LifecycleEvent`])
+    Lifecycle(LifecycleEvent),
+}
+
+impl StreamEvent {
+    fn inbound(msg: &ChannelMessage) -> Self {
+        Self::Inbound {
+            channel: msg.channel.clone(),
+            message_id: msg.id.clone(),
+            sender: msg.sender.clone(),
+            sender_name: msg.sender_name.clone(),
+            content: msg.content.clone(),
+            at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// The channel the event concerns, if any.
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            Self::Inbound { channel, .. } => Some(channel),
+            Self::Outbound(record) => Some(&record.channel),
+            Self::Lifecycle(event) => event.channel(),
+        }
+    }
+
+    /// The event with message text cut to `max_chars` and credentials
+    /// masked, for views that only need a glimpse.
+    #[must_use]
+    pub fn preview(mut self, max_chars: usize) -> Self {
+        if let Self::Inbound { content, .. } | Self::Outbound(OutboundRecord { content, .. }) =
+            &mut self
+        {
+            *content = redact(&truncate_with_ellipsis(content, max_chars));
+        }
+        self
+    }
+}
+
+pub struct EventStream {
+    inbound: broadcast::Receiver<ChannelMessage>,
+    outbound: broadcast::Receiver<OutboundRecord>,
+    lifecycle: broadcast::Receiver<LifecycleEvent>,
+}
+
+impl EventStream {
+    /// Events from now on.
+    pub fn subscribe(control: &ChannelControl) -> Self {
+        Self {
+            inbound: control.subscribe_inbound(),
+            outbound: control.subscribe_outbound(),
+            lifecycle: hooks::subscribe(),
+        }
+    }
+
+    /// The next event, or `None` once a source closes. A subscriber that
+    /// falls behind skips the events it missed.
+    pub async fn next(&mut self) -> Option<StreamEvent> {
+        loop {
+            let event = tokio::select! {
+                msg = self.inbound.recv() => msg.map(|msg| StreamEvent::inbound(&msg)),
+                record = self.outbound.recv() => record.map(StreamEvent::Outbound),
+                event = self.lifecycle.recv() => event.map(StreamEvent::Lifecycle),
+            };
+            match event {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Event stream subscriber skipped {skipped} events");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn merges_inbound_and_outbound_messages() {
+        let control = ChannelControl::new();
+        let mut events = EventStream::subscribe(&control);
+        control.publish_inbound(&ChannelMessage {
+            id: "m1".into(),
+            ..ChannelMessage::test("event-stream-test", "alice", "hello there")
+        });
+        control.publish_outbound("event-stream-test", "alice", "hi alice", Some("m1"));
+
+        // Lifecycle events from other tests may interleave, and ready
+        // sources are polled in no particular order
+        let mut seen = Vec::new();
+        while seen.len() < 2 {
+            let event = events.next().await.unwrap();
+            if event.channel() == Some("event-stream-test") {
+                seen.push(serde_json::to_value(event.preview(5)).unwrap());
+            }
+        }
+        seen.sort_by_key(|event| event["kind"].as_str().unwrap_or_default().to_string());
+        assert_eq!(seen[0]["kind"], "inbound");
+        assert_eq!(seen[0]["message_id"], "m1");
+        assert_eq!(seen[0]["content"], "hello...");
+        assert_eq!(seen[1]["kind"], "outbound");
+        assert_eq!(seen[1]["in_reply_to"], "m1");
+    }
+
+    #[test]
+    fn lifecycle_events_keep_their_event_tag() {
+        let event = StreamEvent::Lifecycle(LifecycleEvent::MessageSent {
+            channel: "test".into(),
+            recipient: "alice".into(),
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["kind"], "lifecycle");
+        assert_eq!(json["event"], "message_sent");
+        assert_eq!(json["channel"], "test");
+    }
+}
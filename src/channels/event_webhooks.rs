@@ -0,0 +1,152 @@
+//! Lifecycle events delivered to HTTP endpoints (`[[hooks.webhooks]]`).
+//!
+//! Every event from [`hooks::subscribe`] that an endpoint asks for is POSTed
+//! to it as the event's JSON (`{"event":"message_sent",...}`). Endpoints
+//! with a secret get `X-Zeroclaw-Timestamp` and `X-Zeroclaw-Signature`
+//! headers (see [`webhook_security`]), so the receiver can check that the
+//! event came from this instance and is fresh. Delivery is best effort: a
+//! failed POST is logged and not retried.
+
+use super::hooks::{self, LifecycleEvent};
+use crate::config::schema::EventWebhookConfig;
+use crate::security::webhook_security;
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+pub struct EventWebhook {
+    url: String,
+    secret: Option<Arc<ManagedSecret>>,
+    events: Vec<String>,
+}
+
+impl EventWebhook {
+    /// `resolve` turns the configured secret reference into a managed secret.
+    pub fn from_config(
+        config: &EventWebhookConfig,
+        resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Self> {
+        Ok(Self {
+            url: config.url.clone(),
+            secret: config.secret.as_deref().map(resolve).transpose()?,
+            events: config.events.clone(),
+        })
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|name| name == event)
+    }
+
+    /// The POST of `body`, signed with `secret` if there is one.
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        body: Vec<u8>,
+        secret: Option<&str>,
+    ) -> reqwest::RequestBuilder {
+        let mut request = client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = secret {
+            for (name, value) in webhook_security::signature_headers(secret, &body) {
+                request = request.header(name, value);
+            }
+        }
+        request.body(body)
+    }
+
+    async fn deliver(&self, client: &reqwest::Client, body: Vec<u8>) -> Result<()> {
+        let secret = match &self.secret {
+            Some(secret) => Some(secret.get().await?),
+            None => None,
+        };
+        self.request(client, body, secret.as_deref())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// The `event` tag of a lifecycle event (`message_sent`, ...).
+fn event_name(event: &LifecycleEvent) -> String {
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|value| value.get("event")?.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Deliver lifecycle events to `webhooks` until the event stream closes.
+pub async fn run_event_webhooks(webhooks: Vec<EventWebhook>) {
+    let webhooks: Vec<Arc<EventWebhook>> = webhooks.into_iter().map(Arc::new).collect();
+    let client = super::net::http_client();
+    let mut events = hooks::subscribe();
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                tracing::warn!("Event webhooks fell behind; {missed} events were not delivered");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let name = event_name(&event);
+        let Ok(body) = serde_json::to_vec(&event) else {
+            continue;
+        };
+        for webhook in webhooks.iter().filter(|webhook| webhook.wants(&name)) {
+            let (webhook, client, body) = (Arc::clone(webhook), client.clone(), body.clone());
+            tokio::spawn(async move {
+                if let Err(e) = webhook.deliver(&client, body).await {
+                    tracing::warn!("Event webhook {} failed: {e}", webhook.url);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::webhook_security::{SignedWebhookVerifier, DEFAULT_REPLAY_WINDOW_SECS};
+    use std::time::Duration;
+
+    #[test]
+    fn signed_events_verify_and_filter_by_name() {
+        let webhook = EventWebhook::from_config(
+            &EventWebhookConfig {
+                url: "https://hooks.example.com/zeroclaw".into(),
+                secret: Some("s3cret".into()),
+                events: vec!["message_failed".into()],
+            },
+            |raw| Ok(Arc::new(ManagedSecret::fixed(raw))),
+        )
+        .unwrap();
+        let event = LifecycleEvent::MessageFailed {
+            channel: "telegram".into(),
+            recipient: "42".into(),
+            error: "timeout".into(),
+        };
+        assert!(webhook.wants(&event_name(&event)));
+        assert!(!webhook.wants(&event_name(&LifecycleEvent::ConfigReloaded)));
+
+        let body = serde_json::to_vec(&event).unwrap();
+        let request = webhook
+            .request(&reqwest::Client::new(), body.clone(), Some("s3cret"))
+            .build()
+            .unwrap();
+        let verifier =
+            SignedWebhookVerifier::new("s3cret", Duration::from_secs(DEFAULT_REPLAY_WINDOW_SECS));
+        assert_eq!(verifier.verify(request.headers(), &body), Ok(()));
+
+        let unsigned = webhook
+            .request(&reqwest::Client::new(), body, None)
+            .build()
+            .unwrap();
+        assert!(unsigned
+            .headers()
+            .get(webhook_security::SIGNATURE_HEADER)
+            .is_none());
+    }
+}
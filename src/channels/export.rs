@@ -0,0 +1,200 @@
+//! `/export [markdown|json]`: the current conversation as a file.
+//!
+//! Renders the turns of the sender's current session (see
+//! [`super::sessions`]) as Markdown — the default — or JSON and sends the
+//! file through [`super::uploads::send_file`], so channels that can't take
+//! attachments get a link instead. Useful for handing a support
+//! conversation to someone else.
+
+use super::sessions::ActiveSession;
+use super::traits::{Channel, ChannelMessage};
+use super::uploads;
+use crate::providers::ChatMessage;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt::Write;
+use std::io::Cursor;
+
+const USAGE: &str = "Usage: /export [markdown|json]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+/// The format `/export` asks for, an error reply for bad arguments, or
+/// `None` when `content` isn't `/export`.
+pub fn export_command(content: &str) -> Option<Result<ExportFormat, String>> {
+    let mut words = content.split_whitespace();
+    if words.next()?.split('@').next()? != "/export" {
+        return None;
+    }
+    let format = match (words.next(), words.next()) {
+        (None, _) => Ok(ExportFormat::Markdown),
+        (Some(format), None) => match format.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(USAGE.to_string()),
+        },
+        _ => Err(USAGE.to_string()),
+    };
+    Some(format)
+}
+
+#[derive(Serialize)]
+struct Transcript<'a> {
+    channel: &'a str,
+    conversation: &'a str,
+    session: &'a str,
+    exported_at: String,
+    messages: Vec<&'a ChatMessage>,
+}
+
+/// `session` as a file in `format`; system messages are left out.
+fn render(
+    format: ExportFormat,
+    msg: &ChannelMessage,
+    session: &ActiveSession,
+    exported_at: DateTime<Utc>,
+) -> Result<Vec<u8>> {
+    let messages: Vec<&ChatMessage> = session
+        .history
+        .iter()
+        .filter(|message| message.role != "system")
+        .collect();
+    if format == ExportFormat::Json {
+        let transcript = Transcript {
+            channel: &msg.channel,
+            conversation: msg.conversation(),
+            session: &session.id,
+            exported_at: exported_at.to_rfc3339(),
+            messages,
+        };
+        return Ok(serde_json::to_vec_pretty(&transcript)?);
+    }
+
+    let mut text = format!(
+        "# Conversation export\n\n- Channel: {}\n- Conversation: {}\n- Session: {}\n- Exported: {}\n",
+        msg.channel,
+        msg.conversation(),
+        session.id,
+        exported_at.to_rfc3339()
+    );
+    for message in messages {
+        let speaker = match message.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            other => other,
+        };
+        let _ = write!(text, "\n**{speaker}:**\n\n{}\n", message.content.trim());
+    }
+    Ok(text.into_bytes())
+}
+
+/// Send the sender's current session to them as a file. Returns the reply
+/// to send as well, if any.
+pub async fn send_export(
+    channel: &dyn Channel,
+    msg: &ChannelMessage,
+    session: Option<ActiveSession>,
+    format: ExportFormat,
+) -> Option<String> {
+    let Some(session) = session.filter(|session| !session.history.is_empty()) else {
+        return Some("There is nothing to export yet in this conversation.".to_string());
+    };
+    let now = Utc::now();
+    let bytes = match render(format, msg, &session, now) {
+        Ok(bytes) => bytes,
+        Err(e) => return Some(format!("⚠️ Export failed: {e}")),
+    };
+    let name = format!(
+        "conversation-{}.{}",
+        now.format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    let len = bytes.len() as u64;
+    uploads::send_file(
+        channel,
+        &msg.reply_target,
+        &name,
+        format.mime(),
+        Some(len),
+        Box::new(Cursor::new(bytes)),
+    )
+    .await
+    .err()
+    .map(|e| format!("⚠️ Export failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg() -> ChannelMessage {
+        ChannelMessage {
+            reply_target: "dm-alice".into(),
+            ..ChannelMessage::test("telegram", "alice", "/export")
+        }
+    }
+
+    fn session() -> ActiveSession {
+        ActiveSession {
+            id: "s-1".into(),
+            history: vec![
+                ChatMessage::system("be brief"),
+                ChatMessage::user("My order is late"),
+                ChatMessage::assistant("Sorry! It ships tomorrow."),
+            ],
+        }
+    }
+
+    #[test]
+    fn export_command_parses_formats() {
+        assert_eq!(export_command("/export"), Some(Ok(ExportFormat::Markdown)));
+        assert_eq!(
+            export_command("/export@bot JSON"),
+            Some(Ok(ExportFormat::Json))
+        );
+        assert_eq!(export_command("/export pdf"), Some(Err(USAGE.to_string())));
+        assert_eq!(export_command("/exports"), None);
+    }
+
+    #[test]
+    fn renders_markdown_and_json_without_system_messages() {
+        let at = DateTime::parse_from_rfc3339("2026-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let markdown =
+            String::from_utf8(render(ExportFormat::Markdown, &msg(), &session(), at).unwrap())
+                .unwrap();
+        assert!(markdown.starts_with("# Conversation export\n\n- Channel: telegram\n"));
+        assert!(markdown.contains("**User:**\n\nMy order is late\n"));
+        assert!(markdown.contains("**Assistant:**\n\nSorry! It ships tomorrow.\n"));
+        assert!(!markdown.contains("be brief"));
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&render(ExportFormat::Json, &msg(), &session(), at).unwrap())
+                .unwrap();
+        assert_eq!(json["session"], "s-1");
+        assert_eq!(json["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(json["messages"][1]["role"], "assistant");
+    }
+}
@@ -0,0 +1,295 @@
+//! Failover delivery across a user's linked channel accounts.
+//!
+//! When a reply to a user cannot go out on the channel they wrote from, it
+//! is retried on the other accounts linked to their profile (see
+//! [`crate::identity::linking`]), in the configured priority order. A
+//! channel whose sends keep failing, or whose listener is reported down by
+//! the health registry, is skipped for a cool-down so replies go straight
+//! to a secondary. Every failover records which path delivered the reply.
+
+use crate::config::schema::FailoverConfig;
+use crate::identity::linking::{IdentityStore, LinkedAccount};
+use anyhow::Result;
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of failover deliveries retained for inspection.
+pub const RECENT_FAILOVERS_CAPACITY: usize = 100;
+
+/// A reply that went out on a secondary channel.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailoverRecord {
+    /// Channel the reply was meant for
+    pub primary: String,
+    /// Channel that delivered it
+    pub channel: String,
+    pub recipient: String,
+    /// Why the primary was not used
+    pub reason: String,
+    pub at: String,
+}
+
+#[derive(Debug, Default)]
+struct ChannelFailures {
+    consecutive: u32,
+    last_failure: Option<Instant>,
+}
+
+pub struct FailoverRouter {
+    identities: Arc<IdentityStore>,
+    config: FailoverConfig,
+    failures: Mutex<HashMap<String, ChannelFailures>>,
+    history: Mutex<VecDeque<FailoverRecord>>,
+}
+
+impl FailoverRouter {
+    pub fn new(identities: Arc<IdentityStore>, config: FailoverConfig) -> Self {
+        Self {
+            identities,
+            config,
+            failures: Mutex::new(HashMap::new()),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Whether `channel` should be skipped in favour of a secondary: it
+    /// failed `failure_threshold` times in a row within the cool-down, or
+    /// its listener is currently reported down.
+    pub fn is_down(&self, channel: &str) -> bool {
+        let tripped = self.failures.lock().get(channel).is_some_and(|f| {
+            f.consecutive >= self.config.failure_threshold.max(1)
+                && f.last_failure.is_some_and(|at| {
+                    at.elapsed() < Duration::from_secs(self.config.retry_after_secs)
+                })
+        });
+        tripped
+            || crate::health::snapshot()
+                .components
+                .get(&format!("channel:{channel}"))
+                .is_some_and(|c| matches!(c.status.as_str(), "error" | "failed"))
+    }
+
+    fn record_success(&self, channel: &str) {
+        self.failures.lock().remove(channel);
+    }
+
+    fn record_failure(&self, channel: &str) {
+        let mut failures = self.failures.lock();
+        let entry = failures.entry(channel.to_string()).or_default();
+        entry.consecutive = entry.consecutive.saturating_add(1);
+        entry.last_failure = Some(Instant::now());
+    }
+
+    /// Recipient address of a linked account on its channel.
+    fn recipient_for(&self, account: &LinkedAccount) -> String {
+        self.config.recipients.get(&account.channel).map_or_else(
+            || account.sender.clone(),
+            |template| template.replace("{sender}", &account.sender),
+        )
+    }
+
+    /// Secondary `(channel, recipient)` routes for `sender` on `primary`,
+    /// in priority order. With no `channels` configured every linked
+    /// account is eligible.
+    pub fn secondaries(&self, primary: &str, sender: &str) -> Result<Vec<(String, String)>> {
+        let mut accounts: Vec<_> = self
+            .identities
+            .linked_accounts(primary, sender)?
+            .into_iter()
+            .filter(|a| a.channel != primary)
+            .collect();
+        if !self.config.channels.is_empty() {
+            accounts.retain(|a| self.config.channels.contains(&a.channel));
+            accounts.sort_by_key(|a| {
+                self.config
+                    .channels
+                    .iter()
+                    .position(|c| *c == a.channel)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        Ok(accounts
+            .iter()
+            .map(|a| (a.channel.clone(), self.recipient_for(a)))
+            .collect())
+    }
+
+    /// Deliver via `send(channel, recipient)`: first on `primary` (unless it
+    /// is down), then on each secondary until one succeeds. Returns the
+    /// primary's error when no route worked.
+    pub async fn deliver<F, Fut>(
+        &self,
+        primary: &str,
+        sender: &str,
+        recipient: &str,
+        send: F,
+    ) -> Result<()>
+    where
+        F: Fn(String, String) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let primary_error = if self.is_down(primary) {
+            anyhow::anyhow!("{primary} is down")
+        } else {
+            match send(primary.to_string(), recipient.to_string()).await {
+                Ok(()) => {
+                    self.record_success(primary);
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.record_failure(primary);
+                    e
+                }
+            }
+        };
+
+        let secondaries = match self.secondaries(primary, sender) {
+            Ok(secondaries) => secondaries,
+            Err(e) => {
+                tracing::warn!("Failover lookup for {primary}/{sender} failed: {e}");
+                return Err(primary_error);
+            }
+        };
+        for (channel, target) in secondaries {
+            match send(channel.clone(), target.clone()).await {
+                Ok(()) => {
+                    self.record_success(&channel);
+                    tracing::info!(
+                        "Reply for {primary}/{sender} failed over to {channel}/{target}: {primary_error}"
+                    );
+                    self.push_record(FailoverRecord {
+                        primary: primary.to_string(),
+                        channel,
+                        recipient: target,
+                        reason: primary_error.to_string(),
+                        at: Utc::now().to_rfc3339(),
+                    });
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.record_failure(&channel);
+                    tracing::warn!("Failover to {channel}/{target} failed: {e}");
+                }
+            }
+        }
+        Err(primary_error)
+    }
+
+    fn push_record(&self, record: FailoverRecord) {
+        let mut history = self.history.lock();
+        if history.len() >= RECENT_FAILOVERS_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(record);
+    }
+
+    /// Most recent failover deliveries, newest first.
+    pub fn recent(&self, limit: usize) -> Vec<FailoverRecord> {
+        self.history
+            .lock()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router(config: FailoverConfig) -> FailoverRouter {
+        let identities = Arc::new(IdentityStore::in_memory(600).unwrap());
+        let code = identities.issue_code("telegram", "alice").unwrap();
+        identities
+            .redeem_code(&code, "email", "alice@example.com")
+            .unwrap();
+        let code = identities.issue_code("telegram", "alice").unwrap();
+        identities.redeem_code(&code, "qq", "u1").unwrap();
+        FailoverRouter::new(identities, config)
+    }
+
+    #[test]
+    fn secondaries_follow_priority_and_templates() {
+        let router = router(FailoverConfig {
+            enabled: true,
+            channels: vec!["qq".into(), "email".into()],
+            recipients: HashMap::from([("qq".into(), "user:{sender}".into())]),
+            ..FailoverConfig::default()
+        });
+        assert_eq!(
+            router.secondaries("telegram", "alice").unwrap(),
+            vec![
+                ("qq".to_string(), "user:u1".to_string()),
+                ("email".to_string(), "alice@example.com".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_primary_falls_over_and_is_recorded() {
+        let router = router(FailoverConfig {
+            enabled: true,
+            failure_threshold: 2,
+            channels: vec!["email".into()],
+            ..FailoverConfig::default()
+        });
+        let sent = Mutex::new(Vec::new());
+        let send = |channel: String, recipient: String| {
+            sent.lock().push(channel.clone());
+            async move {
+                if channel == "telegram" {
+                    anyhow::bail!("502 Bad Gateway");
+                }
+                assert_eq!(recipient, "alice@example.com");
+                Ok(())
+            }
+        };
+
+        router
+            .deliver("telegram", "alice", "42", &send)
+            .await
+            .unwrap();
+        router
+            .deliver("telegram", "alice", "42", &send)
+            .await
+            .unwrap();
+        assert!(router.is_down("telegram"));
+        // Tripped: the primary is skipped entirely.
+        router
+            .deliver("telegram", "alice", "42", &send)
+            .await
+            .unwrap();
+        assert_eq!(
+            *sent.lock(),
+            ["telegram", "email", "telegram", "email", "email"]
+        );
+
+        let records = router.recent(10);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].channel, "email");
+        assert_eq!(records[0].reason, "telegram is down");
+        assert_eq!(records[2].reason, "502 Bad Gateway");
+    }
+
+    #[tokio::test]
+    async fn unlinked_sender_returns_primary_error() {
+        let router = router(FailoverConfig {
+            enabled: true,
+            ..FailoverConfig::default()
+        });
+        let err = router
+            .deliver("telegram", "bob", "7", |_, _| async {
+                anyhow::bail!("timeout")
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "timeout");
+    }
+}
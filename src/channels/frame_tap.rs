@@ -0,0 +1,164 @@
+//! Per-channel debug tap of raw platform payloads.
+//!
+//! While a channel's tap is on (`POST /admin/channels/{name}/tap`), the raw
+//! payloads it exchanges with its platform — gateway frames in both
+//! directions, API request and response bodies — are copied, with
+//! credentials masked, into a ring buffer the admin API serves at
+//! `GET /admin/channels/{name}/tap`. Meant for chasing platform-specific
+//! parsing problems in production; off by default, and nothing is kept for
+//! a channel whose tap is off.
+
+use crate::security::redact::redact;
+use crate::util::truncate_with_ellipsis;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::LazyLock;
+
+/// Frames a tap keeps when not told.
+pub const DEFAULT_TAP_CAPACITY: usize = 200;
+
+/// Most frames a tap keeps.
+pub const MAX_TAP_CAPACITY: usize = 2_000;
+
+/// Longest payload kept whole; longer ones are cut.
+const MAX_PAYLOAD_CHARS: usize = 16_384;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// One payload as it crossed the wire.
+#[derive(Debug, Clone, Serialize)]
+pub struct TappedFrame {
+    pub direction: Direction,
+    /// Where it was seen: `gateway`, or the API call (`POST /v2/...`)
+    pub source: String,
+    pub payload: String,
+    pub at: String,
+}
+
+struct Tap {
+    capacity: usize,
+    frames: VecDeque<TappedFrame>,
+}
+
+static TAPS: LazyLock<Mutex<HashMap<String, Tap>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Start tapping `channel`, keeping its latest `capacity` frames. Frames
+/// already kept stay unless the new capacity is smaller.
+pub fn enable(channel: &str, capacity: usize) {
+    let capacity = capacity.clamp(1, MAX_TAP_CAPACITY);
+    let mut taps = TAPS.lock();
+    let tap = taps.entry(channel.to_string()).or_insert_with(|| Tap {
+        capacity,
+        frames: VecDeque::new(),
+    });
+    tap.capacity = capacity;
+    while tap.frames.len() > capacity {
+        tap.frames.pop_front();
+    }
+}
+
+/// Stop tapping `channel` and drop its frames. Returns whether it was on.
+pub fn disable(channel: &str) -> bool {
+    TAPS.lock().remove(channel).is_some()
+}
+
+pub fn is_enabled(channel: &str) -> bool {
+    TAPS.lock().contains_key(channel)
+}
+
+/// Capacity of every tap that is on, by channel.
+pub fn enabled() -> BTreeMap<String, usize> {
+    TAPS.lock()
+        .iter()
+        .map(|(channel, tap)| (channel.clone(), tap.capacity))
+        .collect()
+}
+
+/// Keep `payload` if `channel` is tapped.
+pub fn record(channel: &str, direction: Direction, source: &str, payload: &str) {
+    if !is_enabled(channel) {
+        return;
+    }
+    // Redact outside the lock; taps of other channels shouldn't wait on it
+    let frame = TappedFrame {
+        direction,
+        source: source.to_string(),
+        payload: redact(&truncate_with_ellipsis(payload, MAX_PAYLOAD_CHARS)),
+        at: chrono::Utc::now().to_rfc3339(),
+    };
+    let mut taps = TAPS.lock();
+    let Some(tap) = taps.get_mut(channel) else {
+        return;
+    };
+    if tap.frames.len() >= tap.capacity {
+        tap.frames.pop_front();
+    }
+    tap.frames.push_back(frame);
+}
+
+/// Like [`record`], serializing `payload` only when `channel` is tapped.
+pub fn record_json(channel: &str, direction: Direction, source: &str, payload: &serde_json::Value) {
+    if is_enabled(channel) {
+        record(channel, direction, source, &payload.to_string());
+    }
+}
+
+/// Up to `limit` of `channel`'s latest frames, newest first; `None` when
+/// its tap is off.
+pub fn frames(channel: &str, limit: usize) -> Option<Vec<TappedFrame>> {
+    TAPS.lock()
+        .get(channel)
+        .map(|tap| tap.frames.iter().rev().take(limit).cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taps_keep_the_latest_frames_of_enabled_channels_only() {
+        record("tap-test", Direction::Inbound, "gateway", "ignored");
+        assert!(frames("tap-test", 10).is_none());
+
+        enable("tap-test", 2);
+        for n in 1..=3 {
+            record(
+                "tap-test",
+                Direction::Inbound,
+                "gateway",
+                &format!("{{\"s\":{n}}}"),
+            );
+        }
+        record("tap-test-other", Direction::Inbound, "gateway", "ignored");
+        let kept = frames("tap-test", 10).unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].payload, "{\"s\":3}");
+        assert_eq!(kept[1].payload, "{\"s\":2}");
+        assert_eq!(enabled().get("tap-test"), Some(&2));
+
+        assert!(disable("tap-test"));
+        assert!(frames("tap-test", 10).is_none());
+        assert!(!disable("tap-test"));
+    }
+
+    #[test]
+    fn tapped_payloads_are_redacted() {
+        enable("tap-test-redact", 10);
+        record_json(
+            "tap-test-redact",
+            Direction::Outbound,
+            "POST /token",
+            &serde_json::json!({ "client_secret": "hunter2hunter2" }),
+        );
+        let kept = frames("tap-test-redact", 1).unwrap();
+        assert!(!kept[0].payload.contains("hunter2hunter2"));
+        assert_eq!(kept[0].direction, Direction::Outbound);
+        disable("tap-test-redact");
+    }
+}
@@ -0,0 +1,810 @@
+//! Shared plumbing of the WebSocket gateway channels (QQ, Discord).
+//!
+//! Both gateways open with a Hello (op 10) carrying the heartbeat interval.
+//! The first heartbeat is sent after `interval * jitter` (jitter uniformly
+//! random in `[0, 1)`, as the gateway docs ask, so reconnecting bots do not
+//! beat in lockstep) and every `interval` after that. Per-channel gateway
+//! stats are kept in a process-wide registry for the admin API; the
+//! heartbeat interval and skipped frames also go to the attached
+//! [`Observer`], so they reach the metrics backends.
+//!
+//! [`FrameDecoder`] turns raw WebSocket frames into gateway payloads. It
+//! inflates compressed transports — one zlib or zstd stream spanning the
+//! whole connection, so a decoder lives exactly as long as its socket — and
+//! tells the loop which frames want a Pong and which end the connection.
+//!
+//! Payloads are decoded into the typed structs below: the [`GatewayPayload`]
+//! envelope first, then its `d` per event with [`GatewayPayload::decode`].
+//! Fields either gateway may leave out are `Option` or defaulted explicitly;
+//! a payload of the wrong shape is logged and counted, never zero-filled.
+
+use crate::config::GatewayCompression;
+use crate::observability::traits::ObserverMetric;
+use crate::observability::{Observer, ObserverEvent};
+use anyhow::{bail, Context, Result};
+use flate2::{Decompress, FlushDecompress};
+use futures_util::{Stream, StreamExt};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Used when a Hello omits `heartbeat_interval`.
+pub const DEFAULT_HEARTBEAT_INTERVAL_MS: u64 = 41_250;
+
+/// How long to wait for the Hello after connecting.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest excerpt of a malformed frame put in the log.
+const MALFORMED_EXCERPT_CHARS: usize = 120;
+
+/// Every complete zlib-stream message ends with a sync flush marker.
+const ZLIB_SUFFIX: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Opcodes shared by the QQ and Discord gateways.
+pub mod op {
+    pub const DISPATCH: u8 = 0;
+    pub const HEARTBEAT: u8 = 1;
+    pub const IDENTIFY: u8 = 2;
+    pub const RESUME: u8 = 6;
+    pub const RECONNECT: u8 = 7;
+    pub const INVALID_SESSION: u8 = 9;
+    pub const HELLO: u8 = 10;
+    pub const HEARTBEAT_ACK: u8 = 11;
+}
+
+/// Envelope of every gateway payload.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GatewayPayload {
+    pub op: u8,
+    /// Event data, decoded per event with [`GatewayPayload::decode`]
+    #[serde(default)]
+    pub d: serde_json::Value,
+    /// Sequence number (dispatches only)
+    #[serde(default)]
+    pub s: Option<i64>,
+    /// Event name (dispatches only)
+    #[serde(default)]
+    pub t: Option<String>,
+}
+
+impl GatewayPayload {
+    /// Event name of a dispatch, empty otherwise.
+    pub fn event_type(&self) -> &str {
+        self.t.as_deref().unwrap_or_default()
+    }
+
+    /// Decode `d`. A mismatch is logged, counted in `malformed_frames` and
+    /// yields `None`.
+    pub fn decode<T: DeserializeOwned>(&self, channel: &str) -> Option<T> {
+        match T::deserialize(&self.d) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::warn!(
+                    "{channel}: skipping malformed {} payload (op {}): {e}",
+                    self.t.as_deref().unwrap_or("gateway"),
+                    self.op
+                );
+                record_malformed(channel);
+                None
+            }
+        }
+    }
+}
+
+/// `d` of a Hello (op 10).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Hello {
+    #[serde(default = "default_heartbeat_interval")]
+    pub heartbeat_interval: u64,
+}
+
+fn default_heartbeat_interval() -> u64 {
+    DEFAULT_HEARTBEAT_INTERVAL_MS
+}
+
+/// `d` of a READY dispatch.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ready {
+    pub session_id: String,
+    /// Where to resume (Discord only)
+    #[serde(default)]
+    pub resume_gateway_url: Option<String>,
+}
+
+/// Author of a message event. Discord sets `id`, `username`, `global_name`
+/// and `bot`; QQ sets `id` and `user_openid` on direct messages and
+/// `member_openid` in groups.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Author {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Display name chosen by the user (Discord)
+    #[serde(default)]
+    pub global_name: Option<String>,
+    #[serde(default)]
+    pub bot: bool,
+    #[serde(default)]
+    pub user_openid: Option<String>,
+    #[serde(default)]
+    pub member_openid: Option<String>,
+}
+
+/// A file attached to a message event.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Attachment {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Transcoded voice message (QQ only)
+    #[serde(default)]
+    pub voice_wav_url: Option<String>,
+}
+
+/// `d` of a message dispatch: Discord `MESSAGE_CREATE`, QQ
+/// `C2C_MESSAGE_CREATE` and `GROUP_AT_MESSAGE_CREATE`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MessageEvent {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub author: Author,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Discord channel or DM the message was posted in
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    /// Discord guild (absent in DMs)
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    /// QQ group the message was posted in
+    #[serde(default)]
+    pub group_openid: Option<String>,
+    /// When the message was sent, ISO 8601
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    /// Users mentioned in `content`
+    #[serde(default)]
+    pub mentions: Vec<Author>,
+}
+
+impl MessageEvent {
+    /// ID of the first mentioned bot: who a guild @-message was meant for.
+    pub fn mentioned_bot(&self) -> Option<&str> {
+        self.mentions
+            .iter()
+            .find(|user| user.bot)
+            .and_then(|user| user.id.as_deref())
+    }
+
+    /// Send time as unix seconds, if the event carries a parseable one.
+    pub fn sent_at(&self) -> Option<u64> {
+        let timestamp = self.timestamp.as_deref()?;
+        match chrono::DateTime::parse_from_rfc3339(timestamp) {
+            Ok(time) => u64::try_from(time.timestamp()).ok(),
+            Err(e) => {
+                tracing::debug!("Unparseable gateway timestamp {timestamp:?}: {e}");
+                None
+            }
+        }
+    }
+}
+
+impl Author {
+    /// Best display name: the chosen display name, else the username.
+    pub fn display_name(&self) -> Option<String> {
+        self.global_name.clone().or_else(|| self.username.clone())
+    }
+}
+
+/// Heartbeat (op 1) carrying the last sequence number, if any.
+pub fn heartbeat_payload(sequence: i64) -> String {
+    let d = if sequence >= 0 {
+        serde_json::json!(sequence)
+    } else {
+        serde_json::Value::Null
+    };
+    serde_json::json!({ "op": op::HEARTBEAT, "d": d }).to_string()
+}
+
+/// Gateway counters of one channel.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GatewayStats {
+    /// Heartbeat interval from the last Hello
+    pub heartbeat_interval_ms: u64,
+    /// Delay before the first heartbeat of the current connection
+    pub first_heartbeat_ms: u64,
+    pub heartbeats_sent: u64,
+    pub heartbeat_acks: u64,
+    pub connections: u64,
+    /// Frames and event payloads skipped because of their shape
+    pub malformed_frames: u64,
+}
+
+static STATS: LazyLock<Mutex<BTreeMap<String, GatewayStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+static OBSERVER: Mutex<Option<Arc<dyn Observer>>> = Mutex::new(None);
+
+/// Report gateway metrics to `observer` from now on (`None` stops).
+pub fn attach_observer(observer: Option<Arc<dyn Observer>>) {
+    *OBSERVER.lock() = observer;
+}
+
+fn observer() -> Option<Arc<dyn Observer>> {
+    OBSERVER.lock().clone()
+}
+
+fn update_stats(channel: &str, update: impl FnOnce(&mut GatewayStats)) {
+    update(STATS.lock().entry(channel.to_string()).or_default());
+}
+
+fn record_malformed(channel: &str) {
+    update_stats(channel, |s| s.malformed_frames += 1);
+    if let Some(observer) = observer() {
+        observer.record_event(&ObserverEvent::GatewayFrameMalformed {
+            channel: channel.to_string(),
+        });
+    }
+}
+
+/// Count a heartbeat sent by `channel`.
+pub fn record_heartbeat(channel: &str) {
+    update_stats(channel, |s| s.heartbeats_sent += 1);
+}
+
+/// Count a heartbeat ACK (op 11) received by `channel`.
+pub fn record_heartbeat_ack(channel: &str) {
+    update_stats(channel, |s| s.heartbeat_acks += 1);
+}
+
+/// Parse a gateway payload envelope. Anything but a JSON object with an
+/// `op` is logged, counted in `malformed_frames` and skipped — one bad frame
+/// must not cost the connection.
+pub fn parse_payload(channel: &str, text: &str) -> Option<GatewayPayload> {
+    // Objects only: serde would also accept `[op, d, s, t]` arrays.
+    let parsed = serde_json::from_str::<serde_json::Value>(text)
+        .map_err(|e| e.to_string())
+        .and_then(|value| {
+            if value.is_object() {
+                GatewayPayload::deserialize(value).map_err(|e| e.to_string())
+            } else {
+                Err("not an object".to_string())
+            }
+        });
+    match parsed {
+        Ok(payload) => Some(payload),
+        Err(why) => {
+            tracing::warn!(
+                "{channel}: skipping malformed gateway frame ({why}): {}",
+                crate::util::truncate_with_ellipsis(text, MALFORMED_EXCERPT_CHARS)
+            );
+            record_malformed(channel);
+            None
+        }
+    }
+}
+
+/// Gateway stats of every channel that has connected.
+pub fn stats_snapshot() -> BTreeMap<String, GatewayStats> {
+    STATS.lock().clone()
+}
+
+/// Delay before the first heartbeat.
+pub fn first_heartbeat_delay(interval: Duration, jitter: bool) -> Duration {
+    if jitter {
+        interval.mul_f64(rand::random::<f64>())
+    } else {
+        interval
+    }
+}
+
+/// Wait for the Hello (op 10) and return its heartbeat interval. Frames
+/// before it are skipped; the stream ending, a Close or a timeout is an
+/// error.
+pub async fn read_hello<S, E>(read: &mut S, decoder: &mut FrameDecoder) -> Result<Duration>
+where
+    S: Stream<Item = Result<Message, E>> + Unpin,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let hello = tokio::time::timeout(HELLO_TIMEOUT, async {
+        while let Some(frame) = read.next().await {
+            let text = match decoder.decode(frame?)? {
+                GatewayFrame::Payload(text) => text,
+                GatewayFrame::Close { code, reason } => {
+                    bail!(
+                        "gateway closed before Hello ({})",
+                        describe_close(code, &reason)
+                    )
+                }
+                GatewayFrame::Ping(_) | GatewayFrame::Skip => continue,
+            };
+            let Ok(payload) = serde_json::from_str::<GatewayPayload>(&text) else {
+                continue;
+            };
+            if payload.op == op::HELLO {
+                return Ok(payload);
+            }
+        }
+        bail!("gateway closed before Hello")
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("no Hello within {}s", HELLO_TIMEOUT.as_secs()))??;
+
+    let interval = Hello::deserialize(&hello.d).map_or(DEFAULT_HEARTBEAT_INTERVAL_MS, |hello| {
+        hello.heartbeat_interval
+    });
+    Ok(Duration::from_millis(interval))
+}
+
+/// The server closed a gateway connection with a code that reconnecting
+/// will not fix (bad intents, banned bot, ...).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{channel} gateway closed with code {code}: {reason}")]
+pub struct GatewayClosed {
+    pub channel: String,
+    pub code: u16,
+    pub reason: String,
+}
+
+/// A WebSocket frame as the gateway loop sees it.
+#[derive(Debug, PartialEq, Eq)]
+pub enum GatewayFrame {
+    /// A complete JSON payload
+    Payload(String),
+    /// Ping whose data must be echoed back in a Pong
+    Ping(Vec<u8>),
+    /// The server closed the connection; reconnect
+    Close { code: Option<u16>, reason: String },
+    /// Nothing to act on (a Pong, or part of a compressed message)
+    Skip,
+}
+
+enum Inflater {
+    Plain,
+    Zlib {
+        stream: Decompress,
+        /// Frames of a message whose sync flush marker has not arrived yet
+        pending: Vec<u8>,
+    },
+    Zstd(zstd::stream::raw::Decoder<'static>),
+}
+
+/// Per-connection decoder of gateway frames.
+pub struct FrameDecoder {
+    inflater: Inflater,
+}
+
+impl FrameDecoder {
+    pub fn new(compression: GatewayCompression) -> Result<Self> {
+        let inflater = match compression {
+            GatewayCompression::None => Inflater::Plain,
+            GatewayCompression::Zlib => Inflater::Zlib {
+                stream: Decompress::new(true),
+                pending: Vec::new(),
+            },
+            GatewayCompression::Zstd => Inflater::Zstd(
+                zstd::stream::raw::Decoder::new().context("Failed to create zstd decoder")?,
+            ),
+        };
+        Ok(Self { inflater })
+    }
+
+    /// Classify one frame, inflating binary frames of compressed transports.
+    /// Binary frames of an uncompressed connection are read as UTF-8 JSON.
+    pub fn decode(&mut self, message: Message) -> Result<GatewayFrame> {
+        match message {
+            Message::Text(text) => Ok(GatewayFrame::Payload(text)),
+            Message::Binary(data) => self.inflate(&data),
+            Message::Ping(data) => Ok(GatewayFrame::Ping(data)),
+            Message::Close(frame) => Ok(close_frame(frame)),
+            Message::Pong(_) | Message::Frame(_) => Ok(GatewayFrame::Skip),
+        }
+    }
+
+    fn inflate(&mut self, data: &[u8]) -> Result<GatewayFrame> {
+        let bytes = match &mut self.inflater {
+            Inflater::Plain => data.to_vec(),
+            Inflater::Zlib { stream, pending } => {
+                pending.extend_from_slice(data);
+                if !pending.ends_with(&ZLIB_SUFFIX) {
+                    return Ok(GatewayFrame::Skip);
+                }
+                let input = std::mem::take(pending);
+                inflate_zlib(stream, &input)?
+            }
+            Inflater::Zstd(decoder) => inflate_zstd(decoder, data)?,
+        };
+        let text = String::from_utf8(bytes).context("Gateway payload is not UTF-8")?;
+        Ok(GatewayFrame::Payload(text))
+    }
+}
+
+/// `code 4004: Authentication failed`, for logs.
+pub fn describe_close(code: Option<u16>, reason: &str) -> String {
+    match (code, reason.is_empty()) {
+        (Some(code), true) => format!("code {code}"),
+        (Some(code), false) => format!("code {code}: {reason}"),
+        (None, _) => "no close code".to_string(),
+    }
+}
+
+fn close_frame(frame: Option<CloseFrame<'_>>) -> GatewayFrame {
+    match frame {
+        Some(frame) => GatewayFrame::Close {
+            code: Some(u16::from(frame.code)),
+            reason: frame.reason.into_owned(),
+        },
+        None => GatewayFrame::Close {
+            code: None,
+            reason: String::new(),
+        },
+    }
+}
+
+fn inflate_zlib(stream: &mut Decompress, input: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len().saturating_mul(4).max(1024));
+    let mut consumed = 0;
+    loop {
+        if out.len() == out.capacity() {
+            out.reserve(out.capacity());
+        }
+        let before = stream.total_in();
+        stream
+            .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)
+            .context("Failed to inflate zlib gateway frame")?;
+        consumed += usize::try_from(stream.total_in() - before)?;
+        // Done once all input is read and the output was not cut short
+        if consumed >= input.len() && out.len() < out.capacity() {
+            return Ok(out);
+        }
+    }
+}
+
+fn inflate_zstd(
+    decoder: &mut zstd::stream::raw::Decoder<'static>,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    use zstd::stream::raw::{InBuffer, Operation, OutBuffer};
+
+    let mut out = Vec::new();
+    let mut chunk = vec![0_u8; 64 * 1024];
+    let mut input = InBuffer::around(input);
+    loop {
+        let mut output = OutBuffer::around(chunk.as_mut_slice());
+        decoder
+            .run(&mut input, &mut output)
+            .context("Failed to decode zstd gateway frame")?;
+        let written = output.pos();
+        out.extend_from_slice(&chunk[..written]);
+        if input.pos() >= input.src.len() && written < chunk.len() {
+            return Ok(out);
+        }
+    }
+}
+
+/// Start the heartbeat timer of a connection: the returned receiver yields
+/// once per heartbeat due. The timer stops when the receiver is dropped.
+pub fn spawn_heartbeat(
+    channel: &str,
+    interval: Duration,
+    jitter: bool,
+) -> tokio::sync::mpsc::Receiver<()> {
+    let first = first_heartbeat_delay(interval, jitter);
+    update_stats(channel, |s| {
+        s.heartbeat_interval_ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        s.first_heartbeat_ms = u64::try_from(first.as_millis()).unwrap_or(u64::MAX);
+        s.connections += 1;
+    });
+    if let Some(observer) = observer() {
+        observer.record_metric(&ObserverMetric::GatewayHeartbeatInterval {
+            channel: channel.to_string(),
+            interval,
+        });
+    }
+    tracing::debug!(
+        "{channel}: heartbeat every {}ms, first in {}ms",
+        interval.as_millis(),
+        first.as_millis()
+    );
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<()>(1);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + first, interval);
+        loop {
+            ticker.tick().await;
+            if tx.send(()).await.is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::tungstenite::Error as WsError;
+
+    #[allow(clippy::result_large_err)]
+    fn frames(texts: &[&str]) -> impl Stream<Item = Result<Message, WsError>> + Unpin {
+        futures_util::stream::iter(
+            texts
+                .iter()
+                .map(|t| Ok(Message::Text((*t).to_string())))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[tokio::test]
+    async fn hello_is_awaited_past_other_frames() {
+        let mut read = frames(&[
+            "not json",
+            r#"{"op":0,"t":"NOISE"}"#,
+            r#"{"op":10,"d":{"heartbeat_interval":45000}}"#,
+        ]);
+        let mut decoder = FrameDecoder::new(GatewayCompression::None).unwrap();
+        assert_eq!(
+            read_hello(&mut read, &mut decoder).await.unwrap(),
+            Duration::from_millis(45_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn hello_without_interval_uses_default() {
+        let mut decoder = FrameDecoder::new(GatewayCompression::None).unwrap();
+        let mut read = frames(&[r#"{"op":10,"d":{}}"#]);
+        assert_eq!(
+            read_hello(&mut read, &mut decoder).await.unwrap(),
+            Duration::from_millis(DEFAULT_HEARTBEAT_INTERVAL_MS)
+        );
+        let mut closed = frames(&[r#"{"op":0}"#]);
+        assert!(read_hello(&mut closed, &mut decoder).await.is_err());
+    }
+
+    #[test]
+    fn junk_frames_are_counted_and_skipped() {
+        let junk = [
+            "",
+            "not json",
+            "{\"op\":0,\"d\":",
+            "[1,2,3]",
+            "42",
+            "\u{0}\u{1}binary garbage",
+        ];
+        for frame in junk {
+            assert!(
+                parse_payload("test-gateway-junk", frame).is_none(),
+                "{frame:?}"
+            );
+        }
+        let payload = parse_payload("test-gateway-junk", r#"{"op":11}"#).unwrap();
+        assert_eq!(payload.op, op::HEARTBEAT_ACK);
+        assert!(parse_payload("test-gateway-junk", r#"{"t":"READY"}"#).is_none());
+        assert_eq!(
+            stats_snapshot()["test-gateway-junk"].malformed_frames,
+            junk.len() as u64 + 1
+        );
+    }
+
+    #[test]
+    fn message_events_decode_into_typed_fields() {
+        let payload = parse_payload(
+            "test-gateway-typed",
+            r#"{"op":0,"s":42,"t":"MESSAGE_CREATE","d":{
+                "id":"m1","content":"hi","channel_id":"c1",
+                "author":{"id":"u1","bot":true},
+                "attachments":[{"url":"https://x/a.png","content_type":"image/png"}]
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!((payload.op, payload.s), (op::DISPATCH, Some(42)));
+        assert_eq!(payload.event_type(), "MESSAGE_CREATE");
+        let event: MessageEvent = payload.decode("test-gateway-typed").unwrap();
+        assert_eq!(event.content, "hi");
+        assert_eq!(event.author.id.as_deref(), Some("u1"));
+        assert!(event.author.bot);
+        assert_eq!(event.attachments[0].url.as_deref(), Some("https://x/a.png"));
+        assert!(event.guild_id.is_none());
+        assert!(event.sent_at().is_none());
+
+        // A wrongly typed field is reported, not silently defaulted
+        let bad = parse_payload(
+            "test-gateway-typed",
+            r#"{"op":0,"t":"MESSAGE_CREATE","d":{"content":7}}"#,
+        )
+        .unwrap();
+        assert!(bad.decode::<MessageEvent>("test-gateway-typed").is_none());
+        assert_eq!(stats_snapshot()["test-gateway-typed"].malformed_frames, 1);
+    }
+
+    #[test]
+    fn message_timestamps_parse_as_unix_seconds() {
+        let event = |timestamp: &str| MessageEvent {
+            timestamp: Some(timestamp.into()),
+            ..MessageEvent::default()
+        };
+        // QQ sends local time with an offset, Discord UTC with microseconds
+        assert_eq!(
+            event("2023-11-06T13:37:18+08:00").sent_at(),
+            Some(1_699_249_038)
+        );
+        assert_eq!(
+            event("2023-11-06T05:37:18.123000+00:00").sent_at(),
+            Some(1_699_249_038)
+        );
+        assert_eq!(event("yesterday").sent_at(), None);
+    }
+
+    #[test]
+    fn heartbeat_carries_last_sequence() {
+        let parse = |text: String| serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        assert_eq!(
+            parse(heartbeat_payload(-1)),
+            serde_json::json!({"op": 1, "d": null})
+        );
+        assert_eq!(
+            parse(heartbeat_payload(7)),
+            serde_json::json!({"op": 1, "d": 7})
+        );
+    }
+
+    #[test]
+    fn jitter_stays_within_interval() {
+        let interval = Duration::from_secs(40);
+        for _ in 0..100 {
+            assert!(first_heartbeat_delay(interval, true) < interval);
+        }
+        assert_eq!(first_heartbeat_delay(interval, false), interval);
+    }
+
+    #[test]
+    fn zlib_stream_keeps_context_across_messages() {
+        use flate2::{Compress, Compression, FlushCompress};
+
+        let mut compress = Compress::new(Compression::default(), true);
+        let mut deflate = |text: &str| {
+            let mut out = Vec::with_capacity(1024);
+            compress
+                .compress_vec(text.as_bytes(), &mut out, FlushCompress::Sync)
+                .unwrap();
+            out
+        };
+        let first = deflate(r#"{"op":10,"d":{"heartbeat_interval":41250}}"#);
+        let second = deflate(r#"{"op":11}"#);
+
+        let mut decoder = FrameDecoder::new(GatewayCompression::Zlib).unwrap();
+        let (head, tail) = first.split_at(first.len() / 2);
+        assert_eq!(
+            decoder.decode(Message::Binary(head.to_vec())).unwrap(),
+            GatewayFrame::Skip
+        );
+        assert_eq!(
+            decoder.decode(Message::Binary(tail.to_vec())).unwrap(),
+            GatewayFrame::Payload(r#"{"op":10,"d":{"heartbeat_interval":41250}}"#.into())
+        );
+        assert_eq!(
+            decoder.decode(Message::Binary(second)).unwrap(),
+            GatewayFrame::Payload(r#"{"op":11}"#.into())
+        );
+    }
+
+    #[test]
+    fn zstd_frames_are_decoded() {
+        let frame = zstd::encode_all(r#"{"op":0,"t":"READY"}"#.as_bytes(), 3).unwrap();
+        let mut decoder = FrameDecoder::new(GatewayCompression::Zstd).unwrap();
+        assert_eq!(
+            decoder.decode(Message::Binary(frame)).unwrap(),
+            GatewayFrame::Payload(r#"{"op":0,"t":"READY"}"#.into())
+        );
+    }
+
+    #[test]
+    fn control_frames_are_classified() {
+        use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+        let mut decoder = FrameDecoder::new(GatewayCompression::None).unwrap();
+        assert_eq!(
+            decoder.decode(Message::Ping(vec![1, 2])).unwrap(),
+            GatewayFrame::Ping(vec![1, 2])
+        );
+        assert_eq!(
+            decoder
+                .decode(Message::Close(Some(CloseFrame {
+                    code: CloseCode::Library(4004),
+                    reason: "Authentication failed".into(),
+                })))
+                .unwrap(),
+            GatewayFrame::Close {
+                code: Some(4004),
+                reason: "Authentication failed".into(),
+            }
+        );
+        assert_eq!(
+            decoder
+                .decode(Message::Binary(b"{\"op\":11}".to_vec()))
+                .unwrap(),
+            GatewayFrame::Payload(r#"{"op":11}"#.into())
+        );
+        assert_eq!(
+            decoder.decode(Message::Pong(vec![])).unwrap(),
+            GatewayFrame::Skip
+        );
+    }
+
+    #[tokio::test]
+    async fn heartbeat_waits_for_first_delay_and_records_stats() {
+        let mut beats = spawn_heartbeat("test-gateway-hb", Duration::from_millis(50), false);
+        tokio::task::yield_now().await;
+        assert!(beats.try_recv().is_err());
+        let beat = tokio::time::timeout(Duration::from_secs(2), beats.recv()).await;
+        assert!(matches!(beat, Ok(Some(()))));
+
+        let stats = &stats_snapshot()["test-gateway-hb"];
+        assert_eq!(stats.heartbeat_interval_ms, 50);
+        assert_eq!(stats.first_heartbeat_ms, 50);
+        assert_eq!(stats.connections, 1);
+    }
+
+    #[derive(Default)]
+    struct GatewayRecorder {
+        malformed: Mutex<Vec<String>>,
+        intervals: Mutex<Vec<(String, Duration)>>,
+    }
+
+    impl Observer for GatewayRecorder {
+        fn record_event(&self, event: &ObserverEvent) {
+            if let ObserverEvent::GatewayFrameMalformed { channel } = event {
+                self.malformed.lock().push(channel.clone());
+            }
+        }
+
+        fn record_metric(&self, metric: &ObserverMetric) {
+            if let ObserverMetric::GatewayHeartbeatInterval { channel, interval } = metric {
+                self.intervals.lock().push((channel.clone(), *interval));
+            }
+        }
+
+        fn name(&self) -> &str {
+            "gateway-recorder"
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_intervals_and_malformed_frames_to_the_observer() {
+        let recorder = Arc::new(GatewayRecorder::default());
+        attach_observer(Some(Arc::clone(&recorder) as Arc<dyn Observer>));
+
+        assert!(parse_payload("test-gateway-observed", "[1, null]").is_none());
+        let payload = parse_payload("test-gateway-observed", r#"{"op":10,"d":"hello"}"#).unwrap();
+        assert!(payload.decode::<Hello>("test-gateway-observed").is_none());
+        drop(spawn_heartbeat(
+            "test-gateway-observed",
+            Duration::from_millis(41_250),
+            false,
+        ));
+        attach_observer(None);
+
+        let malformed = recorder.malformed.lock();
+        let ours = malformed
+            .iter()
+            .filter(|c| *c == "test-gateway-observed")
+            .count();
+        assert_eq!(ours, 2);
+        assert!(recorder.intervals.lock().contains(&(
+            "test-gateway-observed".to_string(),
+            Duration::from_millis(41_250)
+        )));
+    }
+}
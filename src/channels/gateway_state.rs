@@ -0,0 +1,259 @@
+//! Persisted gateway sessions, so a quick restart can RESUME instead of
+//! re-identifying and missing the events sent in between.
+//!
+//! Gateway channels (QQ, Discord) save their session id, last sequence
+//! number and resume URL on READY, on each heartbeat and when the socket
+//! closes. On connect they resume when a saved session is recent enough.
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Saved sessions older than this are not resumed; gateways drop
+/// disconnected sessions after a few minutes anyway.
+pub const DEFAULT_RESUME_MAX_AGE_SECS: u64 = 300;
+
+/// Everything needed to resume a gateway session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GatewaySession {
+    pub session_id: String,
+    /// Last dispatch sequence number received
+    pub sequence: i64,
+    /// Gateway URL to resume on (Discord's `resume_gateway_url`)
+    #[serde(default)]
+    pub resume_url: Option<String>,
+    /// Unix seconds when this was saved
+    pub saved_at: u64,
+}
+
+impl GatewaySession {
+    pub fn new(session_id: String, sequence: i64, resume_url: Option<String>) -> Self {
+        Self {
+            session_id,
+            sequence,
+            resume_url,
+            saved_at: now_secs(),
+        }
+    }
+
+    /// Whether the session is recent enough to try resuming.
+    pub fn is_fresh(&self, max_age_secs: u64) -> bool {
+        now_secs().saturating_sub(self.saved_at) <= max_age_secs
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Where gateway channels keep their sessions, keyed per bot account.
+pub trait GatewayStateStore: Send + Sync {
+    fn load(&self, key: &str) -> Option<GatewaySession>;
+    fn save(&self, key: &str, session: &GatewaySession) -> Result<()>;
+    fn clear(&self, key: &str) -> Result<()>;
+
+    /// A saved session younger than `max_age_secs`, if any.
+    fn resumable(&self, key: &str, max_age_secs: u64) -> Option<GatewaySession> {
+        self.load(key).filter(|s| s.is_fresh(max_age_secs))
+    }
+}
+
+/// One JSON file per key under `<workspace>/state/gateway/`.
+pub struct FileGatewayStateStore {
+    dir: PathBuf,
+}
+
+impl FileGatewayStateStore {
+    pub fn new(workspace_dir: &std::path::Path) -> Self {
+        Self {
+            dir: workspace_dir.join("state").join("gateway"),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let safe: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{safe}.json"))
+    }
+}
+
+impl GatewayStateStore for FileGatewayStateStore {
+    fn load(&self, key: &str) -> Option<GatewaySession> {
+        let raw = std::fs::read_to_string(self.path(key)).ok()?;
+        match serde_json::from_str(&raw) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable gateway state for {key}: {e}");
+                None
+            }
+        }
+    }
+
+    fn save(&self, key: &str, session: &GatewaySession) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).with_context(|| {
+            format!("Failed to create gateway state dir {}", self.dir.display())
+        })?;
+        // Write-then-rename so a crash mid-write never leaves a torn file.
+        let path = self.path(key);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(session)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn clear(&self, key: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Process-local store, for tests and ephemeral setups.
+#[derive(Default)]
+pub struct MemoryGatewayStateStore {
+    sessions: Mutex<HashMap<String, GatewaySession>>,
+}
+
+impl GatewayStateStore for MemoryGatewayStateStore {
+    fn load(&self, key: &str) -> Option<GatewaySession> {
+        self.sessions.lock().get(key).cloned()
+    }
+
+    fn save(&self, key: &str, session: &GatewaySession) -> Result<()> {
+        self.sessions
+            .lock()
+            .insert(key.to_string(), session.clone());
+        Ok(())
+    }
+
+    fn clear(&self, key: &str) -> Result<()> {
+        self.sessions.lock().remove(key);
+        Ok(())
+    }
+}
+
+/// Gateway session tracking inside a channel's `listen` loop.
+pub(crate) struct SessionTracker<'a> {
+    store: Option<&'a dyn GatewayStateStore>,
+    key: String,
+    session_id: Option<String>,
+    resume_url: Option<String>,
+}
+
+impl<'a> SessionTracker<'a> {
+    pub(crate) fn new(store: Option<&'a dyn GatewayStateStore>, key: String) -> Self {
+        Self {
+            store,
+            key,
+            session_id: None,
+            resume_url: None,
+        }
+    }
+
+    /// Saved session to resume, if one is fresh enough.
+    pub(crate) fn resumable(&mut self) -> Option<GatewaySession> {
+        let session = self
+            .store?
+            .resumable(&self.key, DEFAULT_RESUME_MAX_AGE_SECS)?;
+        self.session_id = Some(session.session_id.clone());
+        self.resume_url.clone_from(&session.resume_url);
+        Some(session)
+    }
+
+    /// Record the session from a READY dispatch.
+    pub(crate) fn ready(&mut self, session_id: &str, resume_url: Option<&str>, sequence: i64) {
+        self.session_id = Some(session_id.to_string());
+        self.resume_url = resume_url.map(str::to_string);
+        self.persist(sequence);
+    }
+
+    /// Save the session at `sequence`. Called on every heartbeat so
+    /// `saved_at` tracks when the connection was last known alive.
+    pub(crate) fn persist(&self, sequence: i64) {
+        let (Some(store), Some(session_id)) = (self.store, &self.session_id) else {
+            return;
+        };
+        let session = GatewaySession::new(session_id.clone(), sequence, self.resume_url.clone());
+        if let Err(e) = store.save(&self.key, &session) {
+            tracing::warn!("Failed to save gateway state for {}: {e}", self.key);
+        }
+    }
+
+    /// Forget the session after the gateway invalidated it.
+    pub(crate) fn invalidate(&mut self) {
+        self.session_id = None;
+        if let Some(store) = self.store {
+            if let Err(e) = store.clear(&self.key) {
+                tracing::warn!("Failed to clear gateway state for {}: {e}", self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_roundtrips_and_clears() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = FileGatewayStateStore::new(tmp.path());
+        assert!(store.load("qq-123").is_none());
+
+        let session = GatewaySession::new("abc".into(), 42, Some("wss://resume".into()));
+        store.save("qq-123", &session).unwrap();
+        assert_eq!(store.load("qq-123"), Some(session.clone()));
+        assert_eq!(store.resumable("qq-123", 60), Some(session));
+
+        store.clear("qq-123").unwrap();
+        assert!(store.load("qq-123").is_none());
+        store.clear("qq-123").unwrap();
+    }
+
+    #[test]
+    fn stale_sessions_are_not_resumable() {
+        let store = MemoryGatewayStateStore::default();
+        let mut session = GatewaySession::new("abc".into(), 1, None);
+        session.saved_at -= 600;
+        store.save("discord", &session).unwrap();
+        assert!(store.resumable("discord", 300).is_none());
+        assert!(store.resumable("discord", 900).is_some());
+    }
+
+    #[test]
+    fn tracker_saves_only_known_sessions() {
+        let store = MemoryGatewayStateStore::default();
+        let mut tracker = SessionTracker::new(Some(&store), "discord".into());
+        tracker.persist(5);
+        assert!(store.load("discord").is_none());
+
+        tracker.ready("sess", Some("wss://r"), 1);
+        assert_eq!(store.load("discord").unwrap().sequence, 1);
+        tracker.persist(9);
+        assert_eq!(store.load("discord").unwrap().sequence, 9);
+
+        let mut restarted = SessionTracker::new(Some(&store), "discord".into());
+        let resumed = restarted.resumable().unwrap();
+        assert_eq!(resumed.session_id, "sess");
+        assert_eq!(resumed.resume_url.as_deref(), Some("wss://r"));
+
+        restarted.invalidate();
+        assert!(store.load("discord").is_none());
+        restarted.persist(10);
+        assert!(store.load("discord").is_none());
+    }
+}
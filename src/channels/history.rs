@@ -0,0 +1,258 @@
+//! Searching archived messages (`/history search <terms>`, `GET
+//! /admin/history/search`).
+//!
+//! The archive is the messages auto-saved to memory with `[memory]
+//! auto_save` on, keyed `<channel>_<sender>_<id>` and stored under their
+//! owner (see [`MessageArchive::owner`]), so a search runs on the memory
+//! backend's keyword index: FTS5 for SQLite, `tsvector` for Postgres. In
+//! chat, a user only searches their own messages on the channel they ask
+//! from, and only in a direct conversation so results never land in a
+//! group. Searching across users and channels is for operators, through
+//! the admin API.
+
+use super::traits::{Channel, ChannelMessage};
+use crate::identity::linking::IdentityStore;
+use crate::memory::{Memory, MemoryCategory};
+use crate::util::truncate_with_ellipsis;
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage: /history search <terms>";
+
+/// Results shown in chat.
+const CHAT_RESULTS: usize = 10;
+
+/// Characters of each message shown in chat.
+const CHAT_PREVIEW_CHARS: usize = 200;
+
+/// Memory hits scanned per search before filtering to the requested
+/// channel and category; the owner is matched by the memory query itself.
+const SCAN_LIMIT: usize = 500;
+
+/// One archived message matching a search.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedMessage {
+    /// `<channel>_<sender>_<message id>`
+    pub key: String,
+    pub content: String,
+    pub timestamp: String,
+    pub score: Option<f64>,
+}
+
+pub struct MessageArchive {
+    memory: Arc<dyn Memory>,
+    identities: Option<Arc<IdentityStore>>,
+}
+
+impl MessageArchive {
+    pub fn new(memory: Arc<dyn Memory>, identities: Option<Arc<IdentityStore>>) -> Self {
+        Self { memory, identities }
+    }
+
+    /// Who the messages of `sender` on `channel` are stored under: their
+    /// identity profile with linking on, `<channel>:<sender>` otherwise.
+    /// Keys can't tell `alice` from `alice_smith`; the owner can.
+    pub fn owner(&self, channel: &str, sender: &str) -> Result<String> {
+        if let Some(identities) = &self.identities {
+            if let Some(profile) = identities.find_profile(channel, sender)? {
+                return Ok(profile);
+            }
+        }
+        Ok(format!("{channel}:{sender}"))
+    }
+
+    /// Archived messages matching any of `terms`, best first. `channel`
+    /// and `owner` (from [`Self::owner`]) narrow the search.
+    pub async fn search(
+        &self,
+        terms: &str,
+        channel: Option<&str>,
+        owner: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ArchivedMessage>> {
+        let prefix = channel.map_or_else(String::new, |channel| format!("{channel}_"));
+        let hits = self.memory.recall(terms, SCAN_LIMIT, owner).await?;
+        Ok(hits
+            .into_iter()
+            .filter(|entry| {
+                entry.category == MemoryCategory::Conversation && entry.key.starts_with(&prefix)
+            })
+            .take(limit)
+            .map(|entry| ArchivedMessage {
+                key: entry.key,
+                content: entry.content,
+                timestamp: entry.timestamp,
+                score: entry.score,
+            })
+            .collect())
+    }
+}
+
+/// The terms of `/history search <terms>`, a usage reply for anything
+/// else under `/history`, or `None` when `content` isn't `/history`.
+pub fn history_command(content: &str) -> Option<Result<String, String>> {
+    let mut words = content.split_whitespace();
+    if words.next()?.split('@').next()? != "/history" {
+        return None;
+    }
+    if !words
+        .next()
+        .is_some_and(|w| w.eq_ignore_ascii_case("search"))
+    {
+        return Some(Err(USAGE.to_string()));
+    }
+    let terms = words.collect::<Vec<_>>().join(" ");
+    if terms.is_empty() {
+        return Some(Err(USAGE.to_string()));
+    }
+    Some(Ok(terms))
+}
+
+fn render(terms: &str, results: &[ArchivedMessage]) -> String {
+    if results.is_empty() {
+        return format!("No earlier messages match “{terms}”.");
+    }
+    let mut reply = format!("🔎 {} message(s) matching “{terms}”:", results.len());
+    for result in results {
+        let when = chrono::DateTime::parse_from_rfc3339(&result.timestamp).map_or_else(
+            |_| result.timestamp.clone(),
+            |t| t.format("%Y-%m-%d %H:%M").to_string(),
+        );
+        let _ = write!(
+            reply,
+            "\n• {when} — {}",
+            truncate_with_ellipsis(&result.content, CHAT_PREVIEW_CHARS)
+        );
+    }
+    reply
+}
+
+/// Answer `/history`. Returns `None` for any other message.
+pub async fn handle_command(
+    archive: Option<&MessageArchive>,
+    channel: &dyn Channel,
+    msg: &ChannelMessage,
+) -> Option<String> {
+    let terms = match history_command(&msg.content)? {
+        Ok(terms) => terms,
+        Err(usage) => return Some(usage),
+    };
+    let Some(archive) = archive else {
+        return Some("Message history isn't kept here.".to_string());
+    };
+    if !channel.conversation_key(msg).starts_with("dm:") {
+        return Some("Search your history in a direct message.".to_string());
+    }
+    let results = match archive.owner(&msg.channel, msg.author_id()) {
+        Ok(owner) => {
+            archive
+                .search(&terms, Some(&msg.channel), Some(&owner), CHAT_RESULTS)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let reply = match results {
+        Ok(results) => render(&terms, &results),
+        Err(e) => {
+            tracing::warn!("History search failed for {}: {e}", msg.sender);
+            "⚠️ History search failed, try again later.".to_string()
+        }
+    };
+    Some(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteMemory;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_history_search() {
+        assert_eq!(
+            history_command("/history search rust  books"),
+            Some(Ok("rust books".to_string()))
+        );
+        assert_eq!(
+            history_command("/history@zeroclaw_bot search rust"),
+            Some(Ok("rust".to_string()))
+        );
+        assert_eq!(history_command("/history"), Some(Err(USAGE.to_string())));
+        assert_eq!(
+            history_command("/history search"),
+            Some(Err(USAGE.to_string()))
+        );
+        assert_eq!(history_command("/historyx search a"), None);
+        assert_eq!(history_command("tell me some history"), None);
+    }
+
+    #[tokio::test]
+    async fn search_is_scoped_to_channel_and_owner() {
+        let tmp = TempDir::new().unwrap();
+        let memory: Arc<dyn Memory> = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        for (key, content, owner) in [
+            (
+                "telegram_alice_1",
+                "booked the rust workshop",
+                "telegram:alice",
+            ),
+            ("telegram_bob_2", "rust workshop is full", "telegram:bob"),
+            ("slack_alice_3", "rust workshop notes", "slack:alice"),
+            (
+                "telegram_alice_smith_4",
+                "private workshop invite",
+                "telegram:alice_smith",
+            ),
+        ] {
+            memory
+                .store(key, content, MemoryCategory::Conversation, Some(owner))
+                .await
+                .unwrap();
+        }
+        memory
+            .store(
+                "fact",
+                "likes the rust workshop",
+                MemoryCategory::Core,
+                None,
+            )
+            .await
+            .unwrap();
+        let archive = MessageArchive::new(memory, None);
+
+        let owner = archive.owner("telegram", "alice").unwrap();
+        assert_eq!(owner, "telegram:alice");
+        let own = archive
+            .search("workshop", Some("telegram"), Some(&owner), 10)
+            .await
+            .unwrap();
+        assert_eq!(own.len(), 1);
+        assert_eq!(own[0].key, "telegram_alice_1");
+
+        let channel = archive
+            .search("workshop", Some("telegram"), None, 10)
+            .await
+            .unwrap();
+        assert_eq!(channel.len(), 3);
+
+        let all = archive.search("workshop", None, None, 10).await.unwrap();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    fn owner_is_the_profile_when_linking_is_on() {
+        let tmp = TempDir::new().unwrap();
+        let memory: Arc<dyn Memory> = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        let identities = Arc::new(IdentityStore::in_memory(600).unwrap());
+        let profile = identities.profile_for("telegram", "alice").unwrap();
+        let archive = MessageArchive::new(memory, Some(identities));
+
+        assert_eq!(archive.owner("telegram", "alice").unwrap(), profile);
+        assert_eq!(
+            archive.owner("telegram", "alice_smith").unwrap(),
+            "telegram:alice_smith"
+        );
+    }
+}
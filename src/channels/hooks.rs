@@ -0,0 +1,202 @@
+//! Lifecycle event hooks for integrations.
+//!
+//! Components announce lifecycle events with [`emit`]: listeners starting
+//! and stopping, access token refreshes, sent and failed messages, delivery
+//! and read receipts, and config reloads. Integrations observe them either with a typed callback
+//! from [`register`] or as a stream from [`subscribe`]. Callbacks run on the
+//! emitting task, so anything slow belongs in a spawned task; a panicking
+//! callback is logged and skipped. Stream subscribers that fall more than
+//! [`EVENT_BUFFER`] events behind miss the oldest ones.
+
+use super::receipts::DeliveryStatus;
+use crate::security::redact::redact_error;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use tokio::sync::broadcast;
+
+/// Events buffered per stream subscriber.
+pub const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    /// The channel's listener started (again, after a restart)
+    ChannelConnected {
+        channel: String,
+    },
+    /// The channel's listener stopped; it is restarted after a backoff
+    ChannelDisconnected {
+        channel: String,
+        error: Option<String>,
+    },
+    TokenRefreshed {
+        channel: String,
+    },
+    MessageSent {
+        channel: String,
+        recipient: String,
+    },
+    MessageFailed {
+        channel: String,
+        recipient: String,
+        error: String,
+    },
+    /// The platform reported the status of a tracked message (see
+    /// [`super::receipts`])
+    MessageStatus {
+        channel: String,
+        message_id: String,
+        reader: String,
+        status: DeliveryStatus,
+    },
+    /// Channels are restarting with a reloaded config
+    ConfigReloaded,
+}
+
+impl LifecycleEvent {
+    /// The channel the event concerns, if any.
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            Self::ChannelConnected { channel }
+            | Self::ChannelDisconnected { channel, .. }
+            | Self::TokenRefreshed { channel }
+            | Self::MessageSent { channel, .. }
+            | Self::MessageFailed { channel, .. }
+            | Self::MessageStatus { channel, .. } => Some(channel),
+            Self::ConfigReloaded => None,
+        }
+    }
+}
+
+type Hook = Arc<dyn Fn(&LifecycleEvent) + Send + Sync>;
+
+/// Handle of a registered callback, for [`unregister`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+static HOOKS: LazyLock<RwLock<Vec<(HookId, Hook)>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(1);
+static EVENTS: LazyLock<broadcast::Sender<LifecycleEvent>> =
+    LazyLock::new(|| broadcast::channel(EVENT_BUFFER).0);
+
+/// Call `hook` for every event from now on.
+pub fn register(hook: impl Fn(&LifecycleEvent) + Send + Sync + 'static) -> HookId {
+    let id = HookId(NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed));
+    HOOKS.write().push((id, Arc::new(hook)));
+    id
+}
+
+/// Remove a callback. Returns `false` if it was not registered.
+pub fn unregister(id: HookId) -> bool {
+    let mut hooks = HOOKS.write();
+    let before = hooks.len();
+    hooks.retain(|(hook_id, _)| *hook_id != id);
+    hooks.len() != before
+}
+
+/// Receive events emitted from now on.
+pub fn subscribe() -> broadcast::Receiver<LifecycleEvent> {
+    EVENTS.subscribe()
+}
+
+/// Deliver `event` to callbacks and subscribers.
+pub fn emit(event: LifecycleEvent) {
+    // Callbacks may register or unregister hooks, so none runs under the lock
+    let hooks: Vec<Hook> = HOOKS.read().iter().map(|(_, hook)| hook.clone()).collect();
+    for hook in hooks {
+        if std::panic::catch_unwind(AssertUnwindSafe(|| hook(&event))).is_err() {
+            tracing::warn!("Lifecycle hook panicked on {event:?}");
+        }
+    }
+    if EVENTS.receiver_count() > 0 {
+        let _ = EVENTS.send(event);
+    }
+}
+
+/// Emit [`LifecycleEvent::MessageSent`] or [`LifecycleEvent::MessageFailed`]
+/// for a send's `result`, and pass it on.
+pub fn track_send(
+    channel: &str,
+    recipient: &str,
+    result: anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let (channel, recipient) = (channel.to_string(), recipient.to_string());
+    emit(match &result {
+        Ok(()) => LifecycleEvent::MessageSent { channel, recipient },
+        Err(e) => LifecycleEvent::MessageFailed {
+            channel,
+            recipient,
+            error: redact_error(e),
+        },
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[test]
+    fn callbacks_see_events_until_unregistered() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        // Other tests emit too; keep only this test's channel
+        let id = register(move |event| {
+            if event.channel() == Some("hooks-test") {
+                sink.lock().push(event.clone());
+            }
+        });
+        let panicking = register(|_| panic!("broken hook"));
+
+        let _ = track_send("hooks-test", "alice", Ok(()));
+        let _ = track_send("hooks-test", "bob", Err(anyhow::anyhow!("rejected")));
+        assert!(unregister(id));
+        assert!(unregister(panicking));
+        assert!(!unregister(id));
+        emit(LifecycleEvent::TokenRefreshed {
+            channel: "hooks-test".into(),
+        });
+
+        assert_eq!(
+            *seen.lock(),
+            vec![
+                LifecycleEvent::MessageSent {
+                    channel: "hooks-test".into(),
+                    recipient: "alice".into(),
+                },
+                LifecycleEvent::MessageFailed {
+                    channel: "hooks-test".into(),
+                    recipient: "bob".into(),
+                    error: "rejected".into(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_emitted_events() {
+        let mut events = subscribe();
+        emit(LifecycleEvent::ChannelDisconnected {
+            channel: "hooks-stream".into(),
+            error: Some("reset".into()),
+        });
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.channel() == Some("hooks-stream") {
+                assert_eq!(
+                    serde_json::to_value(&event).unwrap(),
+                    serde_json::json!({
+                        "event": "channel_disconnected",
+                        "channel": "hooks-stream",
+                        "error": "reset",
+                    })
+                );
+                break;
+            }
+        }
+    }
+}
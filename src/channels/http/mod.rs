@@ -0,0 +1,384 @@
+//! Authenticated JSON API client shared by the HTTP-based channels.
+//!
+//! [`AuthedApiClient`] puts together what every bot API needs: a base URL,
+//! an access token from a [`TokenCache`] sent in the `Authorization`
+//! header, retries with backoff for rate limits and server errors, an
+//! optional client-side rate limit, and error messages that carry the
+//! API's own description instead of a bare status code. A rejected token
+//! (401) is dropped and the request retried once with a fresh one.
+//! Request and response bodies are copied to the channel's
+//! [debug tap](super::frame_tap) while it is on.
+
+pub mod token;
+
+pub use token::{IssuedToken, TokenCache, TokenSource};
+
+use super::frame_tap::{self, Direction};
+use anyhow::{bail, Result};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Longest server-requested wait (`Retry-After`) that is honoured.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How failed requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Every request is tried once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Wait before attempt `attempt + 1` (attempts count from 1).
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+/// Spaces requests at least `interval` apart.
+pub struct RateLimiter {
+    interval: Duration,
+    next: tokio::sync::Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests.max(1),
+            next: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for this request's slot.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// The description an API put in its error body, if any.
+fn error_message(body: &str) -> String {
+    let parsed: Option<Value> = serde_json::from_str(body).ok();
+    parsed
+        .as_ref()
+        .and_then(|v| {
+            [
+                "message",
+                "msg",
+                "description",
+                "error_description",
+                "error",
+            ]
+            .iter()
+            .find_map(|key| v.get(key).and_then(Value::as_str))
+        })
+        .map_or_else(|| body.trim().to_string(), str::to_string)
+}
+
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(|secs| Duration::from_secs_f64(secs).min(MAX_RETRY_AFTER))
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// A JSON API authenticated with tokens from `S`.
+pub struct AuthedApiClient<S> {
+    /// API name used in error messages, e.g. "QQ"
+    api: String,
+    /// Channel instance name for logs and ops reports
+    channel: String,
+    client: reqwest::Client,
+    base_url: String,
+    /// `Authorization` header prefix, e.g. `Bearer`
+    auth_scheme: String,
+    tokens: Arc<TokenCache<S>>,
+    retry: RetryPolicy,
+    limiter: Option<RateLimiter>,
+}
+
+impl<S: TokenSource> AuthedApiClient<S> {
+    pub fn new(
+        api: impl Into<String>,
+        client: reqwest::Client,
+        base_url: impl Into<String>,
+        source: S,
+    ) -> Self {
+        let api = api.into();
+        Self {
+            channel: api.to_lowercase(),
+            api,
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            auth_scheme: "Bearer".into(),
+            tokens: Arc::new(TokenCache::new(source)),
+            retry: RetryPolicy::default(),
+            limiter: None,
+        }
+    }
+
+    /// Name the owning channel instance in logs and ops reports.
+    pub fn with_channel(mut self, channel: impl Into<String>) -> Self {
+        self.channel = channel.into();
+        self
+    }
+
+    pub fn with_auth_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.auth_scheme = scheme.into();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, requests_per_sec: u32) -> Self {
+        self.limiter = Some(RateLimiter::per_second(requests_per_sec));
+        self
+    }
+
+    /// Send requests to another deployment of the same API.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn tokens(&self) -> &Arc<TokenCache<S>> {
+        &self.tokens
+    }
+
+    /// A current access token.
+    pub async fn token(&self) -> Result<String> {
+        self.tokens.get(&self.channel).await
+    }
+
+    /// Send a request to `path` (relative to the base URL) and return the
+    /// response JSON, `null` for an empty body. `what` describes the call
+    /// in error messages, e.g. "send message".
+    pub async fn send_json(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+        what: &str,
+    ) -> Result<Value> {
+        let url = format!("{}{path}", self.base_url);
+        let source = format!("{method} {path}");
+        if let Some(body) = body {
+            frame_tap::record_json(&self.channel, Direction::Outbound, &source, body);
+        }
+        let mut attempt = 0;
+        let mut reauthenticated = false;
+        loop {
+            attempt += 1;
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+            let token = self.token().await?;
+            let mut request = self
+                .client
+                .request(method.clone(), &url)
+                .header("Authorization", format!("{} {token}", self.auth_scheme));
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let resp = match request.send().await {
+                Ok(resp) => resp,
+                Err(e) if attempt < self.retry.max_attempts && !e.is_builder() => {
+                    tracing::debug!("{}: {what} failed ({e}), retrying", self.api);
+                    tokio::time::sleep(self.retry.backoff(attempt)).await;
+                    continue;
+                }
+                Err(e) => bail!("{} {what} failed: {e}", self.api),
+            };
+
+            let status = resp.status();
+            if status.is_success() {
+                let text = resp.text().await?;
+                frame_tap::record(&self.channel, Direction::Inbound, &source, &text);
+                if text.trim().is_empty() {
+                    return Ok(Value::Null);
+                }
+                return Ok(serde_json::from_str(&text)?);
+            }
+            if status == StatusCode::UNAUTHORIZED && !reauthenticated {
+                tracing::debug!("{}: token rejected, fetching a new one", self.api);
+                self.tokens.invalidate().await;
+                reauthenticated = true;
+                continue;
+            }
+            if is_retryable(status) && attempt < self.retry.max_attempts {
+                let wait = retry_after(&resp).unwrap_or_else(|| self.retry.backoff(attempt));
+                tracing::debug!(
+                    "{}: {what} failed ({status}), retrying in {}ms",
+                    self.api,
+                    wait.as_millis()
+                );
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            let body = resp.text().await.unwrap_or_default();
+            frame_tap::record(&self.channel, Direction::Inbound, &source, &body);
+            bail!(
+                "{} {what} failed ({status}): {}",
+                self.api,
+                error_message(&body)
+            );
+        }
+    }
+
+    pub async fn get_json(&self, path: &str, what: &str) -> Result<Value> {
+        self.send_json(Method::GET, path, None, what).await
+    }
+
+    pub async fn post_json(&self, path: &str, body: &Value, what: &str) -> Result<Value> {
+        self.send_json(Method::POST, path, Some(body), what).await
+    }
+
+    pub async fn put_json(&self, path: &str, body: &Value, what: &str) -> Result<Value> {
+        self.send_json(Method::PUT, path, Some(body), what).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use axum::{http::HeaderMap, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Numbered(AtomicUsize);
+
+    #[async_trait]
+    impl TokenSource for Numbered {
+        async fn fetch(&self) -> Result<IssuedToken> {
+            let n = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(IssuedToken {
+                token: format!("t{n}"),
+                lifetime_secs: 7_200,
+            })
+        }
+    }
+
+    #[test]
+    fn api_errors_are_described() {
+        assert_eq!(
+            error_message(r#"{"code":11244,"message":"token invalid"}"#),
+            "token invalid"
+        );
+        assert_eq!(error_message("Bad Gateway\n"), "Bad Gateway");
+        let retry = RetryPolicy::default();
+        assert_eq!(retry.backoff(1), Duration::from_millis(500));
+        assert_eq!(retry.backoff(2), Duration::from_secs(1));
+        assert_eq!(retry.backoff(10), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn requests_reauthenticate_and_retry_server_errors() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&calls);
+        let app = Router::new().route(
+            "/messages",
+            post(move |headers: HeaderMap, Json(body): Json<Value>| {
+                let seen = Arc::clone(&seen);
+                async move {
+                    let call = seen.fetch_add(1, Ordering::SeqCst) + 1;
+                    let auth = headers["authorization"].to_str().unwrap().to_string();
+                    match call {
+                        // The first token is rejected, then the server hiccups
+                        1 => (StatusCode::UNAUTHORIZED, Json(serde_json::json!({}))),
+                        2 => (
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            Json(serde_json::json!({ "message": "busy" })),
+                        ),
+                        _ => (
+                            StatusCode::OK,
+                            Json(serde_json::json!({ "auth": auth, "echo": body })),
+                        ),
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let api = AuthedApiClient::new(
+            "Test",
+            reqwest::Client::new(),
+            format!("http://{addr}/"),
+            Numbered(AtomicUsize::new(0)),
+        )
+        .with_auth_scheme("Bot")
+        .with_retry(RetryPolicy {
+            base_backoff: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        });
+        let reply = api
+            .post_json("/messages", &serde_json::json!({ "n": 1 }), "send")
+            .await
+            .unwrap();
+        assert_eq!(reply["auth"], "Bot t2");
+        assert_eq!(reply["echo"]["n"], 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let strict = AuthedApiClient::new(
+            "Test",
+            reqwest::Client::new(),
+            format!("http://{addr}"),
+            Numbered(AtomicUsize::new(0)),
+        )
+        .with_retry(RetryPolicy::none());
+        let err = strict
+            .get_json("/missing", "lookup")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Test lookup failed (404"), "{err}");
+    }
+}
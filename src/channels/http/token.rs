@@ -0,0 +1,239 @@
+//! Access tokens with refresh-ahead and single-flight refreshes.
+
+use crate::channels::traits::unix_now;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Tokens are refreshed in the background once this share of their
+/// lifetime (numerator, denominator) has passed.
+const REFRESH_AHEAD_RATIO: (u64, u64) = (4, 5);
+
+/// Tokens stop being handed out this long before they expire.
+const TOKEN_EXPIRY_MARGIN_SECS: u64 = 60;
+
+/// Wait before retrying a failed background refresh.
+const REFRESH_RETRY_SECS: u64 = 30;
+
+/// A freshly issued access token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssuedToken {
+    pub token: String,
+    pub lifetime_secs: u64,
+}
+
+/// Where a [`TokenCache`] gets new tokens from, e.g. an OAuth2
+/// client-credentials endpoint.
+#[async_trait]
+pub trait TokenSource: Send + Sync + 'static {
+    async fn fetch(&self) -> Result<IssuedToken>;
+}
+
+/// The cached access token and when to replace it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct TokenState {
+    token: Option<String>,
+    /// When the background task replaces the token
+    refresh_at: u64,
+    /// When callers stop using the token
+    expires_at: u64,
+}
+
+impl TokenState {
+    fn issued(token: String, now: u64, lifetime_secs: u64) -> Self {
+        let (num, den) = REFRESH_AHEAD_RATIO;
+        Self {
+            token: Some(token),
+            refresh_at: now + lifetime_secs * num / den,
+            expires_at: now + lifetime_secs.saturating_sub(TOKEN_EXPIRY_MARGIN_SECS),
+        }
+    }
+
+    /// The token, unless missing or expired.
+    fn valid(&self, now: u64) -> Option<String> {
+        self.token.clone().filter(|_| now < self.expires_at)
+    }
+
+    fn refresh_due(&self, now: u64) -> bool {
+        self.token.is_none() || now >= self.refresh_at
+    }
+}
+
+/// Access tokens from a [`TokenSource`], refreshed ahead of expiry by a
+/// background task.
+///
+/// The state lock is never held across a token request: a refresh fetches
+/// first and only then swaps the new token in. Concurrent callers that find
+/// the token expired wait on `refreshing` and reuse the token the first of
+/// them fetched.
+pub struct TokenCache<S> {
+    source: S,
+    state: RwLock<TokenState>,
+    /// Held while a token request is in flight
+    refreshing: tokio::sync::Mutex<()>,
+    refresher_started: AtomicBool,
+}
+
+impl<S: TokenSource> TokenCache<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            state: RwLock::new(TokenState::default()),
+            refreshing: tokio::sync::Mutex::new(()),
+            refresher_started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Fetch a token and cache it. Callers hold `refreshing`.
+    async fn refresh(&self, channel: &str) -> Result<String> {
+        let issued = self.source.fetch().await.inspect_err(|e| {
+            crate::ops::report(crate::ops::OpsEventKind::TokenRefresh, channel, e);
+        })?;
+        crate::security::redact::register_secret(&issued.token);
+        *self.state.write().await =
+            TokenState::issued(issued.token.clone(), unix_now(), issued.lifetime_secs);
+        crate::channels::hooks::emit(crate::channels::hooks::LifecycleEvent::TokenRefreshed {
+            channel: channel.to_string(),
+        });
+        Ok(issued.token)
+    }
+
+    /// A valid access token, fetched only when the cached one has expired.
+    /// `channel` names the owner in logs and ops reports.
+    pub async fn get(self: &Arc<Self>, channel: &str) -> Result<String> {
+        if let Some(token) = self.state.read().await.valid(unix_now()) {
+            return Ok(token);
+        }
+        let _flight = self.refreshing.lock().await;
+        // Another caller may have refreshed while we waited
+        if let Some(token) = self.state.read().await.valid(unix_now()) {
+            return Ok(token);
+        }
+        let token = self.refresh(channel).await?;
+        self.spawn_refresher(channel);
+        Ok(token)
+    }
+
+    /// Drop the cached token (the API rejected it).
+    pub async fn invalidate(&self) {
+        *self.state.write().await = TokenState::default();
+    }
+
+    /// Start the background task that replaces tokens before they expire.
+    /// It stops once the cache is dropped.
+    fn spawn_refresher(self: &Arc<Self>, channel: &str) {
+        if self.refresher_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let tokens = Arc::downgrade(self);
+        let channel = channel.to_string();
+        tokio::spawn(async move {
+            loop {
+                let Some(current) = tokens.upgrade() else {
+                    break;
+                };
+                let wait = current
+                    .state
+                    .read()
+                    .await
+                    .refresh_at
+                    .saturating_sub(unix_now());
+                drop(current);
+                tokio::time::sleep(Duration::from_secs(wait.max(1))).await;
+
+                let Some(current) = tokens.upgrade() else {
+                    break;
+                };
+                let flight = current.refreshing.lock().await;
+                if !current.state.read().await.refresh_due(unix_now()) {
+                    continue;
+                }
+                let result = current.refresh(&channel).await;
+                drop(flight);
+                drop(current);
+                match result {
+                    Ok(_) => tracing::debug!("{channel}: access token refreshed ahead of expiry"),
+                    Err(e) => {
+                        tracing::warn!("{channel}: background token refresh failed: {e}");
+                        tokio::time::sleep(Duration::from_secs(REFRESH_RETRY_SECS)).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    struct CountingSource {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TokenSource for CountingSource {
+        async fn fetch(&self) -> Result<IssuedToken> {
+            let n = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(IssuedToken {
+                token: format!("token-{n}"),
+                lifetime_secs: 7_200,
+            })
+        }
+    }
+
+    #[test]
+    fn tokens_are_refreshed_ahead_of_expiry() {
+        let state = TokenState::issued("t".into(), 1_000, 7_200);
+        assert_eq!(state.refresh_at, 1_000 + 5_760);
+        assert_eq!(state.expires_at, 1_000 + 7_140);
+        assert!(!state.refresh_due(6_000));
+        assert!(state.refresh_due(6_760));
+        // Still handed out while the background refresh runs
+        assert_eq!(state.valid(6_760).as_deref(), Some("t"));
+        assert_eq!(state.valid(8_140), None);
+        assert!(TokenState::default().refresh_due(0));
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_refresh() {
+        let cache = Arc::new(TokenCache::new(CountingSource {
+            fetches: AtomicUsize::new(0),
+        }));
+        let callers: Vec<_> = (0..5)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                tokio::spawn(async move { cache.get("test").await.unwrap() })
+            })
+            .collect();
+        for caller in callers {
+            assert_eq!(caller.await.unwrap(), "token-1");
+        }
+        assert_eq!(cache.source().fetches.load(Ordering::SeqCst), 1);
+
+        cache.invalidate().await;
+        assert_eq!(cache.get("test").await.unwrap(), "token-2");
+    }
+
+    #[tokio::test]
+    async fn cached_token_is_served_during_a_refresh() {
+        let cache = Arc::new(TokenCache::new(CountingSource {
+            fetches: AtomicUsize::new(0),
+        }));
+        *cache.state.write().await = TokenState::issued("cached".into(), unix_now(), 7_200);
+        let flight = cache.refreshing.lock().await;
+        // A refresh in flight does not block callers while a token is valid
+        assert_eq!(cache.get("test").await.unwrap(), "cached");
+        drop(flight);
+        assert_eq!(cache.source().fetches.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use directories::UserDirs;
 use rusqlite::{Connection, OpenFlags};
@@ -95,6 +95,16 @@ impl Channel for IMessageChannel {
         "imessage"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: true,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, target: &str) -> anyhow::Result<()> {
         // Defense-in-depth: validate target format before any interpolation
         if !is_valid_imessage_target(target) {
@@ -175,10 +185,14 @@ end tell"#
                             reply_target: sender.clone(),
                             content: text,
                             channel: "imessage".to_string(),
-                            timestamp: std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs(),
+                            timestamp: unix_now(),
+                            received_at: unix_now(),
+                            attachments: vec![],
+                            sender_id: None,
+                            sender_name: None,
+                            conversation_id: None,
+                            mentions: vec![],
+                            is_mention: false,
                         };
 
                         if tx.send(msg).await.is_err() {
@@ -0,0 +1,307 @@
+//! Bounded per-channel inbound queue with a configurable overflow policy.
+//!
+//! Each channel's `listen` loop hands messages to its own pump task instead of
+//! the shared dispatch bus. The pump buffers up to `capacity` messages and a
+//! forwarder moves them onto the bus, so a slow consumer no longer stalls the
+//! gateway read loop (and with it, heartbeats). When the buffer is full the
+//! overflow policy decides whether to drop the oldest message, drop the new
+//! one, or block the listener as before. [Urgent](super::priority) messages
+//! leave the queue first, and chat is dropped before them.
+
+use super::priority::{self, Priority};
+use super::traits::ChannelMessage;
+use crate::config::schema::InboundOverflowPolicy;
+use crate::observability::{Observer, ObserverEvent};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+
+/// Outcome of pushing a message into the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOutcome {
+    Queued,
+    /// Queue was full; the oldest buffered message was discarded.
+    DroppedOldest,
+    /// Queue was full; the new message was discarded.
+    DroppedNew,
+    /// Queue is closed.
+    Closed,
+}
+
+pub struct InboundQueue {
+    items: Mutex<VecDeque<(Priority, ChannelMessage)>>,
+    capacity: usize,
+    policy: InboundOverflowPolicy,
+    item_ready: Notify,
+    space_ready: Notify,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl InboundQueue {
+    pub fn new(capacity: usize, policy: InboundOverflowPolicy) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+            policy,
+            item_ready: Notify::new(),
+            space_ready: Notify::new(),
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Add a message, applying the overflow policy when full.
+    pub async fn push(&self, msg: ChannelMessage) -> PushOutcome {
+        let priority = priority::classify(&msg);
+        loop {
+            let space = self.space_ready.notified();
+            {
+                if self.closed.load(Ordering::Acquire) {
+                    return PushOutcome::Closed;
+                }
+                let mut items = self.items.lock();
+                if items.len() < self.capacity {
+                    items.push_back((priority, msg));
+                    drop(items);
+                    self.item_ready.notify_one();
+                    return PushOutcome::Queued;
+                }
+                if !matches!(self.policy, InboundOverflowPolicy::Block) {
+                    // Chat never pushes out urgent messages; urgent ones push
+                    // out chat under either dropping policy
+                    let oldest_chat = items.iter().position(|(p, _)| *p == Priority::Normal);
+                    let evict = match (self.policy, priority) {
+                        (InboundOverflowPolicy::DropOldest, Priority::Urgent) => {
+                            Some(oldest_chat.unwrap_or(0))
+                        }
+                        (InboundOverflowPolicy::DropOldest, Priority::Normal)
+                        | (InboundOverflowPolicy::DropNew, Priority::Urgent) => oldest_chat,
+                        (InboundOverflowPolicy::DropNew, Priority::Normal)
+                        | (InboundOverflowPolicy::Block, _) => None,
+                    };
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    let Some(index) = evict else {
+                        return PushOutcome::DroppedNew;
+                    };
+                    items.remove(index);
+                    items.push_back((priority, msg));
+                    drop(items);
+                    self.item_ready.notify_one();
+                    return PushOutcome::DroppedOldest;
+                }
+            }
+            space.await;
+        }
+    }
+
+    /// Take the oldest urgent message, else the oldest one, waiting until
+    /// one is available.
+    /// Returns `None` once the queue is closed and drained.
+    pub async fn pop(&self) -> Option<ChannelMessage> {
+        loop {
+            let ready = self.item_ready.notified();
+            {
+                let mut items = self.items.lock();
+                let next = items
+                    .iter()
+                    .position(|(p, _)| *p == Priority::Urgent)
+                    .unwrap_or(0);
+                if let Some((_, msg)) = items.remove(next) {
+                    drop(items);
+                    self.space_ready.notify_one();
+                    return Some(msg);
+                }
+                if self.closed.load(Ordering::Acquire) {
+                    return None;
+                }
+            }
+            ready.await;
+        }
+    }
+
+    /// Stop accepting messages; `pop` drains what is left, then returns `None`.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.item_ready.notify_waiters();
+        self.space_ready.notify_waiters();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.lock().is_empty()
+    }
+
+    /// Total messages discarded by the overflow policy.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Channel-facing sender capacity. Kept small: the pump drains it immediately
+/// and the real buffering happens in [`InboundQueue`].
+const LISTENER_SENDER_CAPACITY: usize = 16;
+
+/// Create a sender for one channel's `listen` loop that feeds `bus` through a
+/// bounded [`InboundQueue`].
+pub fn spawn_inbound_pump(
+    channel_name: &str,
+    capacity: usize,
+    policy: InboundOverflowPolicy,
+    bus: mpsc::Sender<ChannelMessage>,
+    observer: Arc<dyn Observer>,
+) -> (mpsc::Sender<ChannelMessage>, Arc<InboundQueue>) {
+    let (listener_tx, mut listener_rx) = mpsc::channel::<ChannelMessage>(LISTENER_SENDER_CAPACITY);
+    let queue = Arc::new(InboundQueue::new(capacity, policy));
+
+    let pump_queue = Arc::clone(&queue);
+    let name = channel_name.to_string();
+    tokio::spawn(async move {
+        while let Some(msg) = listener_rx.recv().await {
+            match pump_queue.push(msg).await {
+                PushOutcome::Queued => {}
+                PushOutcome::DroppedOldest | PushOutcome::DroppedNew => {
+                    tracing::warn!(
+                        "Channel {name} inbound queue full ({} buffered); dropped a message ({} total)",
+                        pump_queue.len(),
+                        pump_queue.dropped()
+                    );
+                    observer.record_event(&ObserverEvent::ChannelMessageDropped {
+                        channel: name.clone(),
+                        reason: policy.as_str().to_string(),
+                    });
+                }
+                PushOutcome::Closed => break,
+            }
+        }
+        pump_queue.close();
+    });
+
+    let forward_queue = Arc::clone(&queue);
+    tokio::spawn(async move {
+        while let Some(msg) = forward_queue.pop().await {
+            if bus.send(msg).await.is_err() {
+                break;
+            }
+        }
+        // Bus is gone: stop accepting so the listener sees a closed sender.
+        forward_queue.close();
+    });
+
+    (listener_tx, queue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::NoopObserver;
+    use std::time::Duration;
+
+    fn msg(id: &str) -> ChannelMessage {
+        ChannelMessage {
+            id: id.into(),
+            ..ChannelMessage::test("test", "alice", id)
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_newest_messages() {
+        let queue = InboundQueue::new(2, InboundOverflowPolicy::DropOldest);
+        assert_eq!(queue.push(msg("1")).await, PushOutcome::Queued);
+        assert_eq!(queue.push(msg("2")).await, PushOutcome::Queued);
+        assert_eq!(queue.push(msg("3")).await, PushOutcome::DroppedOldest);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pop().await.unwrap().id, "2");
+        assert_eq!(queue.pop().await.unwrap().id, "3");
+    }
+
+    #[tokio::test]
+    async fn drop_new_keeps_oldest_messages() {
+        let queue = InboundQueue::new(1, InboundOverflowPolicy::DropNew);
+        assert_eq!(queue.push(msg("1")).await, PushOutcome::Queued);
+        assert_eq!(queue.push(msg("2")).await, PushOutcome::DroppedNew);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pop().await.unwrap().id, "1");
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn urgent_messages_skip_ahead_and_outlast_chat() {
+        let urgent = |id: &str| ChannelMessage {
+            content: "/silence disk 1h".into(),
+            ..msg(id)
+        };
+        let queue = InboundQueue::new(2, InboundOverflowPolicy::DropNew);
+        queue.push(msg("1")).await;
+        queue.push(msg("2")).await;
+        assert_eq!(queue.push(urgent("3")).await, PushOutcome::DroppedOldest);
+        assert_eq!(queue.push(msg("4")).await, PushOutcome::DroppedNew);
+        assert_eq!(queue.pop().await.unwrap().id, "3");
+        assert_eq!(queue.pop().await.unwrap().id, "2");
+
+        let queue = InboundQueue::new(2, InboundOverflowPolicy::DropOldest);
+        queue.push(urgent("1")).await;
+        queue.push(msg("2")).await;
+        queue.push(msg("3")).await;
+        assert_eq!(queue.pop().await.unwrap().id, "1");
+        assert_eq!(queue.pop().await.unwrap().id, "3");
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_space() {
+        let queue = Arc::new(InboundQueue::new(1, InboundOverflowPolicy::Block));
+        queue.push(msg("1")).await;
+
+        let pusher = Arc::clone(&queue);
+        let handle = tokio::spawn(async move { pusher.push(msg("2")).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!handle.is_finished(), "push should block while full");
+
+        assert_eq!(queue.pop().await.unwrap().id, "1");
+        assert_eq!(handle.await.unwrap(), PushOutcome::Queued);
+        assert_eq!(queue.pop().await.unwrap().id, "2");
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn close_drains_then_ends() {
+        let queue = InboundQueue::new(4, InboundOverflowPolicy::Block);
+        queue.push(msg("1")).await;
+        queue.close();
+        assert_eq!(queue.push(msg("2")).await, PushOutcome::Closed);
+        assert_eq!(queue.pop().await.unwrap().id, "1");
+        assert!(queue.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn pump_forwards_to_bus_without_blocking_listener() {
+        let (bus_tx, mut bus_rx) = mpsc::channel(1);
+        let (listener_tx, queue) = spawn_inbound_pump(
+            "test",
+            2,
+            InboundOverflowPolicy::DropOldest,
+            bus_tx,
+            Arc::new(NoopObserver),
+        );
+
+        // The bus holds one message and nobody reads it, yet the listener can
+        // keep sending because overflow drops the oldest buffered message.
+        for i in 0..50 {
+            tokio::time::timeout(
+                Duration::from_secs(1),
+                listener_tx.send(msg(&i.to_string())),
+            )
+            .await
+            .expect("listener should not block")
+            .unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(queue.dropped() > 0);
+        assert!(bus_rx.recv().await.is_some());
+    }
+}
@@ -0,0 +1,283 @@
+//! Buttons on outbound messages and the clicks that come back.
+//!
+//! A [`Keyboard`] goes out with [`Channel::send_buttons`]: inline keyboards
+//! on Telegram and QQ, message components on Discord. Every [`Button`]
+//! carries a callback id of the form `handler:payload`. When a user clicks
+//! one, the channel acknowledges the click and passes an [`Interaction`] to
+//! [`route`], which hands it to the handler [`register`]ed under `handler`.
+//! Clicks nobody registered for reach the agent as an ordinary message whose
+//! text is the payload, so a menu choice reads like a typed answer.
+
+use super::traits::{unix_now, Channel, ChannelMessage};
+use anyhow::{bail, Result};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use uuid::Uuid;
+
+/// Longest callback id every platform accepts (Telegram's
+/// `callback_data` limit, in bytes).
+pub const MAX_CALLBACK_ID_LEN: usize = 64;
+
+/// Most buttons per row (Discord's action row limit).
+pub const MAX_BUTTONS_PER_ROW: usize = 5;
+
+/// A clickable button.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Button {
+    /// Returned in the [`Interaction`] when clicked: `handler:payload`
+    pub id: String,
+    pub label: String,
+}
+
+impl Button {
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+        }
+    }
+}
+
+/// Rows of buttons shown under a message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keyboard {
+    pub rows: Vec<Vec<Button>>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a row of buttons.
+    pub fn with_row(mut self, buttons: Vec<Button>) -> Self {
+        self.rows.push(buttons);
+        self
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = &Button> {
+        self.rows.iter().flatten()
+    }
+
+    /// Check the keyboard fits every platform's limits.
+    pub fn validate(&self) -> Result<()> {
+        if self.buttons().next().is_none() {
+            bail!("Keyboard has no buttons");
+        }
+        for row in &self.rows {
+            if row.len() > MAX_BUTTONS_PER_ROW {
+                bail!(
+                    "Keyboard row has {} buttons (at most {MAX_BUTTONS_PER_ROW})",
+                    row.len()
+                );
+            }
+        }
+        for button in self.buttons() {
+            if button.id.is_empty() || button.id.len() > MAX_CALLBACK_ID_LEN {
+                bail!(
+                    "Button callback id '{}' must be 1-{MAX_CALLBACK_ID_LEN} bytes",
+                    button.id
+                );
+            }
+            if button.label.trim().is_empty() {
+                bail!("Button '{}' has no label", button.id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A user clicked a button.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    /// Channel instance the click came from
+    pub channel: String,
+    /// The clicked [`Button::id`]
+    pub callback_id: String,
+    /// Platform ID of the user who clicked
+    pub user_id: String,
+    pub user_name: Option<String>,
+    /// Where replies to the click go (a recipient of `channel`)
+    pub reply_target: String,
+    /// Platform ID of the message the button was on
+    pub message_id: Option<String>,
+}
+
+impl Interaction {
+    /// The handler part of the callback id (before the first `:`).
+    pub fn handler(&self) -> &str {
+        self.callback_id
+            .split_once(':')
+            .map_or("", |(handler, _)| handler)
+    }
+
+    /// The payload part of the callback id; the whole id when it has no
+    /// handler prefix.
+    pub fn payload(&self) -> &str {
+        self.callback_id
+            .split_once(':')
+            .map_or(self.callback_id.as_str(), |(_, payload)| payload)
+    }
+
+    /// The click as a message from the user saying the payload.
+    pub fn into_message(self) -> ChannelMessage {
+        let now = unix_now();
+        let content = self.payload().to_string();
+        ChannelMessage {
+            id: format!("{}_click_{}", self.channel, Uuid::new_v4()),
+            sender: self.user_id.clone(),
+            reply_target: self.reply_target,
+            content,
+            channel: self.channel,
+            timestamp: now,
+            received_at: now,
+            attachments: vec![],
+            sender_id: Some(self.user_id),
+            sender_name: self.user_name,
+            conversation_id: None,
+            mentions: vec![],
+            is_mention: false,
+        }
+    }
+}
+
+/// Handles clicks on buttons with its prefix; the returned text, if any, is
+/// sent back to [`Interaction::reply_target`].
+pub type InteractionHandler =
+    Arc<dyn Fn(Interaction) -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+static HANDLERS: LazyLock<RwLock<HashMap<String, InteractionHandler>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Route clicks on buttons whose id starts with `{handler}:` to `f`,
+/// replacing any handler registered under the same name.
+pub fn register<F, Fut>(handler: &str, f: F)
+where
+    F: Fn(Interaction) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Option<String>> + Send + 'static,
+{
+    let f: InteractionHandler = Arc::new(move |interaction| f(interaction).boxed());
+    HANDLERS.write().insert(handler.to_string(), f);
+}
+
+pub fn unregister(handler: &str) {
+    HANDLERS.write().remove(handler);
+}
+
+fn handler_for(interaction: &Interaction) -> Option<InteractionHandler> {
+    HANDLERS.read().get(interaction.handler()).cloned()
+}
+
+/// Deliver a click to its handler, sending the handler's reply through
+/// `channel`, or to the agent through `tx` when no handler claims it.
+/// Returns false once `tx` is closed.
+pub async fn route(
+    channel: &dyn Channel,
+    interaction: Interaction,
+    tx: &tokio::sync::mpsc::Sender<ChannelMessage>,
+) -> bool {
+    let Some(handler) = handler_for(&interaction) else {
+        return tx.send(interaction.into_message()).await.is_ok();
+    };
+    let reply_target = interaction.reply_target.clone();
+    if let Some(reply) = handler(interaction).await {
+        if let Err(e) = channel.send(&reply, &reply_target).await {
+            tracing::warn!("Failed to answer button click on {}: {e}", channel.name());
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn click(callback_id: &str) -> Interaction {
+        Interaction {
+            channel: "telegram".into(),
+            callback_id: callback_id.into(),
+            user_id: "42".into(),
+            user_name: None,
+            reply_target: "-100".into(),
+            message_id: Some("7".into()),
+        }
+    }
+
+    #[test]
+    fn callback_ids_split_into_handler_and_payload() {
+        let interaction = click("confirm:announce-3:yes");
+        assert_eq!(interaction.handler(), "confirm");
+        assert_eq!(interaction.payload(), "announce-3:yes");
+
+        let bare = click("yes");
+        assert_eq!(bare.handler(), "");
+        assert_eq!(bare.payload(), "yes");
+
+        let msg = bare.into_message();
+        assert_eq!(msg.content, "yes");
+        assert_eq!(msg.author_id(), "42");
+        assert_eq!(msg.conversation(), "-100");
+    }
+
+    #[test]
+    fn keyboard_limits_are_enforced() {
+        let ok = Keyboard::new().with_row(vec![Button::new("menu:a", "A")]);
+        assert!(ok.validate().is_ok());
+        assert!(Keyboard::new().validate().is_err());
+
+        let long_id = Keyboard::new().with_row(vec![Button::new("x".repeat(65), "A")]);
+        assert!(long_id.validate().is_err());
+
+        let wide = Keyboard::new().with_row(
+            (0..6)
+                .map(|i| Button::new(format!("menu:{i}"), i.to_string()))
+                .collect(),
+        );
+        assert!(wide.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn clicks_reach_registered_handlers_or_the_agent() {
+        struct Silent;
+
+        #[async_trait::async_trait]
+        impl Channel for Silent {
+            fn name(&self) -> &str {
+                "telegram"
+            }
+
+            async fn send(&self, _message: &str, _recipient: &str) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            async fn listen(
+                &self,
+                _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+            ) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (seen_tx, mut seen_rx) = tokio::sync::mpsc::unbounded_channel();
+        register("test-route", move |interaction: Interaction| {
+            let seen_tx = seen_tx.clone();
+            async move {
+                let _ = seen_tx.send(interaction.payload().to_string());
+                Some("done".to_string())
+            }
+        });
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        assert!(route(&Silent, click("test-route:ok"), &tx).await);
+        assert_eq!(seen_rx.recv().await.unwrap(), "ok");
+        assert!(rx.try_recv().is_err());
+
+        assert!(route(&Silent, click("unclaimed:pick-2"), &tx).await);
+        assert_eq!(rx.recv().await.unwrap().content, "pick-2");
+        unregister("test-route");
+    }
+}
@@ -1,4 +1,4 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -345,6 +345,16 @@ impl Channel for IrcChannel {
         "irc"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let mut guard = self.writer.lock().await;
         let writer = guard
@@ -569,10 +579,14 @@ impl Channel for IrcChannel {
                         reply_target: reply_to,
                         content,
                         channel: "irc".to_string(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
+                        timestamp: unix_now(),
+                        received_at: unix_now(),
+                        attachments: vec![],
+                        sender_id: None,
+                        sender_name: None,
+                        conversation_id: None,
+                        mentions: vec![],
+                        is_mention: false,
                     };
 
                     if tx.send(channel_msg).await.is_err() {
@@ -0,0 +1,161 @@
+//! Per-conversation serialization for the channel dispatch loop.
+//!
+//! Messages that share a lane key (channel + sender) are processed strictly
+//! one after another, in arrival order, so replies within a conversation
+//! cannot overtake each other. Different conversations still run in parallel.
+//!
+//! The dispatcher calls [`ConversationLanes::enqueue`]; when it returns the
+//! item back, no lane is active for that key and the caller must start one.
+//! A running lane calls [`ConversationLanes::next`] after each item and exits
+//! once it returns `None`. Both operations share one lock, so an item can never
+//! be queued on a lane that has already decided to exit.
+//!
+//! Every running lane holds one item and queues the rest, so the lanes hold
+//! `active_lanes() + queued_items()` items in total. With a capacity set, the
+//! dispatcher calls [`ConversationLanes::wait_for_room`] before it takes the
+//! next message off its queue, which bounds that total and leaves the rest
+//! waiting in the inbound queues, where their overflow policy applies.
+
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::Notify;
+
+pub struct ConversationLanes<T> {
+    pending: Mutex<HashMap<String, VecDeque<T>>>,
+    /// Items the lanes may hold at once
+    capacity: usize,
+    item_done: Notify,
+}
+
+impl<T> ConversationLanes<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(usize::MAX)
+    }
+
+    /// Lanes that hold at most `capacity` items, running or queued.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            capacity: capacity.max(1),
+            item_done: Notify::new(),
+        }
+    }
+
+    /// Queue `item` behind the active lane for `key`.
+    ///
+    /// Returns `Some(item)` when no lane is active: the caller now owns the
+    /// lane and must process the item, then drain it via [`Self::next`].
+    pub fn enqueue(&self, key: &str, item: T) -> Option<T> {
+        let mut pending = self.pending.lock();
+        if let Some(queue) = pending.get_mut(key) {
+            queue.push_back(item);
+            None
+        } else {
+            pending.insert(key.to_string(), VecDeque::new());
+            Some(item)
+        }
+    }
+
+    /// Next queued item for the lane `key`. Returns `None` and closes the lane
+    /// when its queue is empty.
+    pub fn next(&self, key: &str) -> Option<T> {
+        let mut pending = self.pending.lock();
+        let queue = pending.get_mut(key)?;
+        let item = queue.pop_front();
+        if item.is_none() {
+            pending.remove(key);
+        }
+        drop(pending);
+        // Either way, the lane's previous item is done
+        self.item_done.notify_waiters();
+        item
+    }
+
+    /// Wait until the lanes hold fewer items than their capacity.
+    pub async fn wait_for_room(&self) {
+        loop {
+            let done = self.item_done.notified();
+            if self.held_items() < self.capacity {
+                return;
+            }
+            done.await;
+        }
+    }
+
+    fn held_items(&self) -> usize {
+        let pending = self.pending.lock();
+        pending.len() + pending.values().map(VecDeque::len).sum::<usize>()
+    }
+
+    /// Number of lanes currently running.
+    pub fn active_lanes(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Total number of items waiting behind running lanes.
+    pub fn queued_items(&self) -> usize {
+        self.pending.lock().values().map(VecDeque::len).sum()
+    }
+}
+
+impl<T> Default for ConversationLanes<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_item_opens_lane() {
+        let lanes = ConversationLanes::new();
+        assert_eq!(lanes.enqueue("qq:alice", 1), Some(1));
+        assert_eq!(lanes.active_lanes(), 1);
+    }
+
+    #[test]
+    fn items_queue_behind_active_lane_in_order() {
+        let lanes = ConversationLanes::new();
+        assert_eq!(lanes.enqueue("qq:alice", 1), Some(1));
+        assert_eq!(lanes.enqueue("qq:alice", 2), None);
+        assert_eq!(lanes.enqueue("qq:alice", 3), None);
+        assert_eq!(lanes.queued_items(), 2);
+
+        assert_eq!(lanes.next("qq:alice"), Some(2));
+        assert_eq!(lanes.next("qq:alice"), Some(3));
+        assert_eq!(lanes.next("qq:alice"), None);
+        assert_eq!(lanes.active_lanes(), 0);
+    }
+
+    #[test]
+    fn different_keys_get_independent_lanes() {
+        let lanes = ConversationLanes::new();
+        assert_eq!(lanes.enqueue("qq:alice", 1), Some(1));
+        assert_eq!(lanes.enqueue("qq:bob", 2), Some(2));
+        assert_eq!(lanes.active_lanes(), 2);
+    }
+
+    #[tokio::test]
+    async fn full_lanes_wait_until_an_item_is_done() {
+        let lanes = ConversationLanes::with_capacity(2);
+        assert_eq!(lanes.enqueue("qq:alice", 1), Some(1));
+        assert_eq!(lanes.enqueue("qq:alice", 2), None);
+
+        let full =
+            tokio::time::timeout(std::time::Duration::from_millis(50), lanes.wait_for_room());
+        assert!(full.await.is_err());
+
+        assert_eq!(lanes.next("qq:alice"), Some(2));
+        lanes.wait_for_room().await;
+    }
+
+    #[test]
+    fn closed_lane_reopens_on_next_enqueue() {
+        let lanes = ConversationLanes::new();
+        assert_eq!(lanes.enqueue("qq:alice", 1), Some(1));
+        assert_eq!(lanes.next("qq:alice"), None);
+        assert_eq!(lanes.enqueue("qq:alice", 2), Some(2));
+    }
+}
@@ -1,4 +1,4 @@
-use super::traits::{Channel, ChannelMessage};
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use prost::Message as ProstMessage;
@@ -121,6 +121,9 @@ struct LarkMessage {
     content: String,
     #[serde(default)]
     mentions: Vec<serde_json::Value>,
+    /// Send time, unix milliseconds as a string
+    #[serde(default)]
+    create_time: Option<String>,
 }
 
 /// Heartbeat timeout for WS connection — must be larger than ping_interval (default 120 s).
@@ -165,7 +168,7 @@ impl LarkChannel {
             allowed_users,
             use_feishu: true,
             receive_mode: crate::config::schema::LarkReceiveMode::default(),
-            client: reqwest::Client::new(),
+            client: super::net::http_client_for("lark"),
             tenant_token: Arc::new(RwLock::new(None)),
             ws_seen_ids: Arc::new(RwLock::new(HashMap::new())),
         }
@@ -253,7 +256,7 @@ impl LarkChannel {
             .unwrap_or(0);
         tracing::info!("Lark: connecting to {wss_url}");
 
-        let (ws_stream, _) = tokio_tungstenite::connect_async(&wss_url).await?;
+        let ws_stream = super::net::connect_websocket("lark", &wss_url).await?;
         let (mut write, mut read) = ws_stream.split();
         tracing::info!("Lark: WS connected (service_id={service_id})");
 
@@ -453,10 +456,18 @@ impl LarkChannel {
                         reply_target: lark_msg.chat_id.clone(),
                         content: text,
                         channel: "lark".to_string(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
+                        timestamp: lark_msg
+                            .create_time
+                            .as_deref()
+                            .and_then(|t| t.parse::<u64>().ok())
+                            .map_or_else(unix_now, |ms| ms / 1000),
+                        received_at: unix_now(),
+                        attachments: vec![],
+                        sender_id: Some(sender_open_id.to_string()).filter(|id| !id.is_empty()),
+                        sender_name: None,
+                        conversation_id: Some(lark_msg.chat_id.clone()),
+                        mentions: vec![],
+                        is_mention: false,
                     };
 
                     tracing::debug!("Lark WS: message in {}", lark_msg.chat_id);
@@ -618,6 +629,13 @@ impl LarkChannel {
             content: text,
             channel: "lark".to_string(),
             timestamp,
+            received_at: unix_now(),
+            attachments: vec![],
+            sender_id: Some(open_id.to_string()),
+            sender_name: None,
+            conversation_id: Some(chat_id.to_string()),
+            mentions: vec![],
+            is_mention: false,
         });
 
         messages
@@ -630,6 +648,17 @@ impl Channel for LarkChannel {
         "lark"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Replies go out as msg_type text
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: true,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let token = self.get_tenant_access_token().await?;
         let url = self.send_message_url();
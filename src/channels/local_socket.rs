@@ -0,0 +1,342 @@
+//! Local socket channel: newline-delimited JSON over a Unix domain socket
+//! (a named pipe on Windows), for scripts and processes on the same host.
+//!
+//! A client writes one JSON object per line:
+//!
+//! ```text
+//! {"content": "summarize today's alerts", "sender": "cron-job"}
+//! ```
+//!
+//! `sender` and `id` are optional. Each accepted message is acknowledged
+//! with `{"type":"accepted","id":"..."}`, malformed lines get
+//! `{"type":"error","error":"..."}`, and the agent's replies arrive on the
+//! same connection as `{"type":"reply","content":"..."}`. Every connection
+//! is its own reply target, so replies to a client that has hung up are
+//! dropped with an error; one that only closed its write side (`nc -N`)
+//! keeps receiving replies for two minutes. On Unix the socket is created readable and
+//! writable by its owner only.
+
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// How long replies are still delivered after a client stops writing.
+const REPLY_GRACE: Duration = Duration::from_secs(120);
+
+/// Connected clients by reply target, each with the queue of lines its
+/// writer task sends.
+type Clients = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<String>>>>;
+
+/// A line from a client.
+#[derive(Debug, Deserialize)]
+struct InboundFrame {
+    content: String,
+    #[serde(default)]
+    sender: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+}
+
+/// A line to a client.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OutboundFrame<'a> {
+    Accepted { id: &'a str },
+    Reply { content: &'a str },
+    Error { error: String },
+}
+
+impl OutboundFrame<'_> {
+    fn line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+/// The default socket: `<zeroclaw dir>/zeroclaw.sock`, or the
+/// `\\.\pipe\zeroclaw` named pipe on Windows.
+pub fn default_path(zeroclaw_dir: &std::path::Path) -> String {
+    if cfg!(windows) {
+        r"\\.\pipe\zeroclaw".to_string()
+    } else {
+        zeroclaw_dir.join("zeroclaw.sock").display().to_string()
+    }
+}
+
+/// Local socket channel — newline-delimited JSON from processes on the host
+pub struct LocalSocketChannel {
+    path: String,
+    clients: Clients,
+    next_connection: Arc<AtomicU64>,
+}
+
+impl LocalSocketChannel {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            next_connection: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    fn connection_id(&self) -> String {
+        format!(
+            "local-{}",
+            self.next_connection.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    #[cfg(unix)]
+    async fn accept_loop(&self, tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+        use tokio::net::{UnixListener, UnixStream};
+
+        let path = std::path::Path::new(&self.path);
+        if let Ok(meta) = std::fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() {
+                bail!("{} exists and is not a socket", self.path);
+            }
+            if UnixStream::connect(path).await.is_ok() {
+                bail!("{} is in use by another process", self.path);
+            }
+            // Left behind by a previous run
+            std::fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind local socket {}", self.path))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        tracing::info!("Local socket channel listening on {}", self.path);
+
+        while !tx.is_closed() {
+            let (stream, _) = listener.accept().await?;
+            self.spawn_connection(stream, &tx);
+        }
+        let _ = std::fs::remove_file(path);
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    async fn accept_loop(&self, tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&self.path)
+            .with_context(|| format!("Failed to create named pipe {}", self.path))?;
+        tracing::info!("Local socket channel listening on {}", self.path);
+
+        while !tx.is_closed() {
+            server.connect().await?;
+            let client = std::mem::replace(&mut server, ServerOptions::new().create(&self.path)?);
+            self.spawn_connection(client, &tx);
+        }
+        Ok(())
+    }
+
+    fn spawn_connection<S>(&self, stream: S, tx: &mpsc::Sender<ChannelMessage>)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let connection = self.connection_id();
+        let (lines_tx, lines_rx) = mpsc::unbounded_channel();
+        self.clients
+            .lock()
+            .insert(connection.clone(), lines_tx.clone());
+        let clients = Arc::clone(&self.clients);
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            serve_connection(stream, &connection, lines_tx, lines_rx, tx).await;
+            clients.lock().remove(&connection);
+        });
+    }
+}
+
+/// The message in a client line, from the connection `connection`.
+fn parse_line(line: &str, connection: &str) -> Result<ChannelMessage> {
+    let frame: InboundFrame = serde_json::from_str(line).context("Expected a JSON object")?;
+    if frame.content.trim().is_empty() {
+        bail!("content is empty");
+    }
+    let sender = frame
+        .sender
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "local".to_string());
+    Ok(ChannelMessage {
+        id: frame.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        sender,
+        reply_target: connection.to_string(),
+        content: frame.content,
+        channel: "local_socket".to_string(),
+        timestamp: unix_now(),
+        received_at: unix_now(),
+        attachments: vec![],
+        sender_id: None,
+        sender_name: None,
+        conversation_id: None,
+        mentions: vec![],
+        is_mention: false,
+    })
+}
+
+/// Read messages from a client until it hangs up, writing acknowledgements,
+/// errors and (through `lines_tx`) replies back to it.
+async fn serve_connection<S>(
+    stream: S,
+    connection: &str,
+    lines_tx: mpsc::UnboundedSender<String>,
+    mut lines_rx: mpsc::UnboundedReceiver<String>,
+    tx: mpsc::Sender<ChannelMessage>,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    let mut writer = tokio::spawn(async move {
+        while let Some(line) = lines_rx.recv().await {
+            if write.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(&line, connection) {
+            Ok(msg) => {
+                // Acknowledge first so it can't trail a fast reply
+                let _ = lines_tx.send(OutboundFrame::Accepted { id: &msg.id }.line());
+                if tx.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                let _ = lines_tx.send(
+                    OutboundFrame::Error {
+                        error: format!("{e:#}"),
+                    }
+                    .line(),
+                );
+            }
+        }
+    }
+    drop(lines_tx);
+    // A client that only closed its write side may still be waiting for
+    // the reply; a client that is gone fails the next write.
+    tokio::select! {
+        _ = &mut writer => {}
+        () = tokio::time::sleep(REPLY_GRACE) => writer.abort(),
+    }
+}
+
+#[async_trait]
+impl Channel for LocalSocketChannel {
+    fn name(&self) -> &str {
+        "local_socket"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+        let client = self.clients.lock().get(recipient).cloned();
+        let Some(client) = client else {
+            bail!("Local socket client {recipient} is not connected");
+        };
+        client
+            .send(OutboundFrame::Reply { content: message }.line())
+            .map_err(|_| anyhow::anyhow!("Local socket client {recipient} disconnected"))
+    }
+
+    async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
+        self.accept_loop(tx).await
+    }
+
+    async fn health_check(&self) -> bool {
+        if cfg!(windows) {
+            return true;
+        }
+        std::path::Path::new(&self.path)
+            .parent()
+            .is_some_and(std::path::Path::is_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_client_lines() {
+        let msg = parse_line(
+            r#"{"content":"disk is 91% full","sender":"monitor","id":"m-1"}"#,
+            "local-3",
+        )
+        .unwrap();
+        assert_eq!(msg.id, "m-1");
+        assert_eq!(msg.sender, "monitor");
+        assert_eq!(msg.reply_target, "local-3");
+        assert_eq!(msg.channel, "local_socket");
+
+        let msg = parse_line(r#"{"content":"hi"}"#, "local-4").unwrap();
+        assert_eq!(msg.sender, "local");
+        assert!(!msg.id.is_empty());
+
+        assert!(parse_line("hi", "local-4").is_err());
+        assert!(parse_line(r#"{"content":"  "}"#, "local-4").is_err());
+    }
+
+    #[tokio::test]
+    async fn replies_reach_the_sending_connection() {
+        let channel = LocalSocketChannel::new("unused".into());
+        let (tx, mut rx) = mpsc::channel(4);
+        let (client, server) = tokio::io::duplex(4096);
+        channel.spawn_connection(server, &tx);
+
+        let (read, mut write) = tokio::io::split(client);
+        let mut lines = BufReader::new(read).lines();
+        write
+            .write_all(b"{\"content\":\"ping\",\"id\":\"p1\"}\nnot json\n")
+            .await
+            .unwrap();
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.content, "ping");
+        let accepted: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(accepted["type"], "accepted");
+        assert_eq!(accepted["id"], "p1");
+        let error: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(error["type"], "error");
+
+        channel.send("pong", &msg.reply_target).await.unwrap();
+        let reply: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(reply["type"], "reply");
+        assert_eq!(reply["content"], "pong");
+
+        assert!(channel.send("pong", "local-99").await.is_err());
+    }
+}
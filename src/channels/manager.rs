@@ -0,0 +1,355 @@
+//! Registry of running channels with cross-channel send helpers.
+//!
+//! Channels can carry tags (`[channels_config.tags.<name>]`). Anywhere a
+//! channel name is expected, `tag:<tag>` addresses every channel with that
+//! tag; an empty recipient then means each channel's `default_recipient`.
+
+use super::middleware::OutboundMessage;
+use super::traits::Channel;
+use crate::config::schema::ChannelTagsConfig;
+use futures_util::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default number of concurrent sends for [`ChannelManager::broadcast`].
+pub const DEFAULT_BROADCAST_CONCURRENCY: usize = 8;
+
+/// Prefix of channel selectors that address a tag instead of one channel.
+pub const TAG_SELECTOR_PREFIX: &str = "tag:";
+
+/// The tag named by a `tag:<tag>` selector.
+pub fn tag_selector(channel: &str) -> Option<&str> {
+    channel.strip_prefix(TAG_SELECTOR_PREFIX)
+}
+
+/// One (channel, recipient) destination for a broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastTarget {
+    pub channel: String,
+    pub recipient: String,
+}
+
+impl BroadcastTarget {
+    pub fn new(channel: impl Into<String>, recipient: impl Into<String>) -> Self {
+        Self {
+            channel: channel.into(),
+            recipient: recipient.into(),
+        }
+    }
+}
+
+/// Delivery outcome for a single broadcast target.
+#[derive(Debug, Clone)]
+pub struct BroadcastResult {
+    pub target: BroadcastTarget,
+    /// `Err` carries a human-readable failure reason.
+    pub outcome: Result<(), String>,
+}
+
+/// Per-target results of a broadcast, in the order targets were given.
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastReport {
+    pub results: Vec<BroadcastResult>,
+}
+
+impl BroadcastReport {
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome.is_ok()).count()
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &BroadcastResult> {
+        self.results.iter().filter(|r| r.outcome.is_err())
+    }
+
+    pub fn is_complete_success(&self) -> bool {
+        self.results.iter().all(|r| r.outcome.is_ok())
+    }
+}
+
+/// Looks up channels by name and sends through them.
+#[derive(Clone)]
+pub struct ChannelManager {
+    channels: Arc<HashMap<String, Arc<dyn Channel>>>,
+    tags: Arc<HashMap<String, ChannelTagsConfig>>,
+}
+
+impl ChannelManager {
+    pub fn new(channels: Arc<HashMap<String, Arc<dyn Channel>>>) -> Self {
+        Self {
+            channels,
+            tags: Arc::default(),
+        }
+    }
+
+    /// Attach channel tags, keyed by channel name.
+    pub fn with_tags(mut self, tags: HashMap<String, ChannelTagsConfig>) -> Self {
+        self.tags = Arc::new(tags);
+        self
+    }
+
+    pub fn from_channels(channels: &[Arc<dyn Channel>]) -> Self {
+        Self::new(Arc::new(
+            channels
+                .iter()
+                .map(|ch| (ch.name().to_string(), Arc::clone(ch)))
+                .collect(),
+        ))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Channel>> {
+        self.channels.get(name).cloned()
+    }
+
+    /// Registered channel names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.channels.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn channels(&self) -> &Arc<HashMap<String, Arc<dyn Channel>>> {
+        &self.channels
+    }
+
+    /// Running channels tagged `tag`, sorted.
+    pub fn tagged(&self, tag: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .tags
+            .iter()
+            .filter(|(name, cfg)| {
+                self.channels.contains_key(*name) && cfg.tags.iter().any(|t| t == tag)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Concrete targets for a (possibly `tag:`) channel selector. Tag
+    /// members without a recipient and without a `default_recipient` are
+    /// skipped.
+    pub fn expand(&self, target: &BroadcastTarget) -> Vec<BroadcastTarget> {
+        let Some(tag) = tag_selector(&target.channel) else {
+            return vec![target.clone()];
+        };
+        self.tagged(tag)
+            .into_iter()
+            .filter_map(|channel| {
+                let recipient = if target.recipient.is_empty() {
+                    self.tags.get(&channel)?.default_recipient.clone()?
+                } else {
+                    target.recipient.clone()
+                };
+                Some(BroadcastTarget { channel, recipient })
+            })
+            .collect()
+    }
+
+    /// Send one message through the named channel.
+    pub async fn send(&self, channel: &str, message: &str, recipient: &str) -> anyhow::Result<()> {
+        let ch = self
+            .get(channel)
+            .ok_or_else(|| anyhow::anyhow!("Unknown channel: {channel}"))?;
+        let result =
+            crate::circuit::call(&format!("send:{channel}"), ch.send(message, recipient)).await;
+        super::hooks::track_send(channel, recipient, result)
+    }
+
+    /// Send `messages` through the named channel in order, paced by its
+    /// rate limits (see [`super::batch`]).
+    pub async fn send_batch(
+        &self,
+        channel: &str,
+        recipient: &str,
+        messages: Vec<OutboundMessage>,
+    ) -> anyhow::Result<()> {
+        let ch = self
+            .get(channel)
+            .ok_or_else(|| anyhow::anyhow!("Unknown channel: {channel}"))?;
+        let result = crate::circuit::call(
+            &format!("send:{channel}"),
+            ch.send_batch(recipient, messages),
+        )
+        .await;
+        super::hooks::track_send(channel, recipient, result)
+    }
+
+    /// Send `message` to every target, at most `concurrency` at a time.
+    /// `tag:` targets are expanded to the tagged channels first.
+    ///
+    /// Failures never abort the broadcast; each target's outcome is reported
+    /// individually so callers can retry or summarize partial delivery.
+    pub async fn broadcast(
+        &self,
+        message: &str,
+        targets: &[BroadcastTarget],
+        concurrency: usize,
+    ) -> BroadcastReport {
+        let targets: Vec<BroadcastTarget> = targets.iter().flat_map(|t| self.expand(t)).collect();
+        let results = stream::iter(targets)
+            .map(|target| async move {
+                let outcome = self
+                    .send(&target.channel, message, &target.recipient)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(ref e) = outcome {
+                    tracing::warn!(
+                        "Broadcast to {}:{} failed: {e}",
+                        target.channel,
+                        target.recipient
+                    );
+                }
+                BroadcastResult { target, outcome }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+
+        BroadcastReport { results }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::ChannelMessage;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    struct StubChannel {
+        name: &'static str,
+        fail_for: Option<&'static str>,
+        sent: tokio::sync::Mutex<Vec<String>>,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    impl StubChannel {
+        fn new(name: &'static str, fail_for: Option<&'static str>) -> Self {
+            Self {
+                name,
+                fail_for,
+                sent: tokio::sync::Mutex::new(Vec::new()),
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Channel for StubChannel {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if self.fail_for == Some(recipient) {
+                anyhow::bail!("recipient blocked");
+            }
+            self.sent
+                .lock()
+                .await
+                .push(format!("{recipient}:{message}"));
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn broadcast_reports_per_target_results() {
+        let qq = Arc::new(StubChannel::new("qq", Some("group:bad")));
+        let tg = Arc::new(StubChannel::new("telegram", None));
+        let channels: Vec<Arc<dyn Channel>> = vec![qq.clone(), tg.clone()];
+        let manager = ChannelManager::from_channels(&channels);
+
+        let targets = vec![
+            BroadcastTarget::new("qq", "group:1"),
+            BroadcastTarget::new("qq", "group:bad"),
+            BroadcastTarget::new("telegram", "-100"),
+            BroadcastTarget::new("missing", "x"),
+        ];
+        let report = manager.broadcast("hello", &targets, 4).await;
+
+        assert_eq!(report.results.len(), 4);
+        assert_eq!(report.succeeded(), 2);
+        assert!(!report.is_complete_success());
+        let failed: Vec<_> = report.failed().map(|r| r.target.clone()).collect();
+        assert_eq!(failed, vec![targets[1].clone(), targets[3].clone()]);
+        assert_eq!(qq.sent.lock().await.as_slice(), ["group:1:hello"]);
+        assert_eq!(tg.sent.lock().await.as_slice(), ["-100:hello"]);
+    }
+
+    #[tokio::test]
+    async fn broadcast_respects_concurrency_limit() {
+        let qq = Arc::new(StubChannel::new("qq", None));
+        let channels: Vec<Arc<dyn Channel>> = vec![qq.clone()];
+        let manager = ChannelManager::from_channels(&channels);
+        let targets: Vec<_> = (0..10)
+            .map(|i| BroadcastTarget::new("qq", format!("user:{i}")))
+            .collect();
+
+        let report = manager.broadcast("hi", &targets, 3).await;
+
+        assert!(report.is_complete_success());
+        assert!(qq.max_in_flight.load(Ordering::SeqCst) <= 3);
+        assert!(qq.max_in_flight.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn tag_targets_expand_to_tagged_channels() {
+        let qq = Arc::new(StubChannel::new("qq", None));
+        let tg = Arc::new(StubChannel::new("telegram", None));
+        let slack = Arc::new(StubChannel::new("slack", None));
+        let channels: Vec<Arc<dyn Channel>> = vec![qq.clone(), tg.clone(), slack.clone()];
+        let tagged = |tags: &[&str], default_recipient: Option<&str>| ChannelTagsConfig {
+            tags: tags.iter().map(|t| (*t).to_string()).collect(),
+            default_recipient: default_recipient.map(str::to_string),
+        };
+        let manager = ChannelManager::from_channels(&channels).with_tags(HashMap::from([
+            (
+                "qq".to_string(),
+                tagged(&["alerts", "cn"], Some("group:ops")),
+            ),
+            ("telegram".to_string(), tagged(&["alerts"], None)),
+            ("slack".to_string(), tagged(&["prod"], Some("C1"))),
+            ("gone".to_string(), tagged(&["alerts"], Some("x"))),
+        ]));
+
+        assert_eq!(manager.tagged("alerts"), vec!["qq", "telegram"]);
+        assert_eq!(
+            manager.expand(&BroadcastTarget::new("tag:alerts", "")),
+            vec![BroadcastTarget::new("qq", "group:ops")]
+        );
+        assert_eq!(
+            manager.expand(&BroadcastTarget::new("slack", "C9")),
+            vec![BroadcastTarget::new("slack", "C9")]
+        );
+
+        let report = manager
+            .broadcast("hi", &[BroadcastTarget::new("tag:alerts", "42")], 2)
+            .await;
+        assert_eq!(report.succeeded(), 2);
+        assert_eq!(qq.sent.lock().await.as_slice(), ["42:hi"]);
+        assert_eq!(tg.sent.lock().await.as_slice(), ["42:hi"]);
+        assert!(slack.sent.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn send_to_unknown_channel_errors() {
+        let manager = ChannelManager::from_channels(&[]);
+        assert!(manager.send("nope", "hi", "x").await.is_err());
+        assert!(manager.names().is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
@@ -117,6 +117,17 @@ impl Channel for MatrixChannel {
         "matrix"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Replies are sent as a plain m.text body
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: true,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, _target: &str) -> anyhow::Result<()> {
         let txn_id = format!("zc_{}", chrono::Utc::now().timestamp_millis());
         let url = format!(
@@ -233,10 +244,14 @@ impl Channel for MatrixChannel {
                         reply_target: event.sender.clone(),
                         content: body.clone(),
                         channel: "matrix".to_string(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
+                        timestamp: unix_now(),
+                        received_at: unix_now(),
+                        attachments: vec![],
+                        sender_id: None,
+                        sender_name: None,
+                        conversation_id: None,
+                        mentions: vec![],
+                        is_mention: false,
                     };
 
                     if tx.send(msg).await.is_err() {
@@ -0,0 +1,89 @@
+//! Parsing of `<@id>` user mentions.
+//!
+//! Discord, QQ guild channels and Slack all mention users inline as `<@id>`
+//! (Discord and QQ also as `<@!id>`). [`parse`] lists who was mentioned,
+//! tells whether the bot was among them, and removes the bot's own mentions
+//! so the agent sees the text the user meant to send.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+static MENTION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<@!?([A-Za-z0-9_]+)>").unwrap());
+
+/// A mention with the spaces after it, removed together.
+static MENTION_WITH_GAP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<@!?([A-Za-z0-9_]+)>[ \t]*").unwrap());
+
+/// Mentions found in a message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mentions {
+    /// The content with the bot's mentions removed and whitespace trimmed
+    pub text: String,
+    /// IDs of every mentioned user, bot included, in order of first mention
+    pub mentioned: Vec<String>,
+    /// Whether the bot was mentioned
+    pub addressed: bool,
+}
+
+/// Parse the `<@id>` mentions in `content`. Without a `bot_id` nothing is
+/// stripped and `addressed` is false.
+pub fn parse(content: &str, bot_id: Option<&str>) -> Mentions {
+    let bot_id = bot_id.filter(|id| !id.is_empty());
+    let mut mentioned: Vec<String> = Vec::new();
+    let mut addressed = false;
+    for caps in MENTION.captures_iter(content) {
+        let id = &caps[1];
+        if Some(id) == bot_id {
+            addressed = true;
+        }
+        if !mentioned.iter().any(|m| m == id) {
+            mentioned.push(id.to_string());
+        }
+    }
+
+    let text = if addressed {
+        MENTION_WITH_GAP
+            .replace_all(content, |caps: &regex::Captures| {
+                if Some(&caps[1]) == bot_id {
+                    String::new()
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .trim()
+            .to_string()
+    } else {
+        content.trim().to_string()
+    };
+
+    Mentions {
+        text,
+        mentioned,
+        addressed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_bot_mention_and_records_others() {
+        let parsed = parse("<@!42> ask <@7> about <@42>  the plan\nthanks", Some("42"));
+        assert_eq!(parsed.text, "ask <@7> about the plan\nthanks");
+        assert_eq!(parsed.mentioned, vec!["42", "7"]);
+        assert!(parsed.addressed);
+    }
+
+    #[test]
+    fn leaves_text_alone_when_bot_not_mentioned() {
+        let parsed = parse("  hi <@7>\nsecond line ", Some("42"));
+        assert_eq!(parsed.text, "hi <@7>\nsecond line");
+        assert_eq!(parsed.mentioned, vec!["7"]);
+        assert!(!parsed.addressed);
+
+        let unknown_bot = parse("<@42> hi", None);
+        assert_eq!(unknown_bot.text, "<@42> hi");
+        assert!(!unknown_bot.addressed);
+    }
+}
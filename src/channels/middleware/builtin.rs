@@ -0,0 +1,258 @@
+//! Built-in middleware: `logging`, `acl` and `rate_limit`.
+
+use super::{
+    InboundAction, InboundMiddleware, OutboundAction, OutboundMessage, OutboundMiddleware,
+};
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::{AclMiddlewareConfig, RateLimitMiddlewareConfig};
+use crate::identity::linking::IdentityStore;
+use crate::util::truncate_with_ellipsis;
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Logs every message passing through, in either direction.
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl InboundMiddleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn on_inbound(&self, msg: ChannelMessage) -> Result<InboundAction> {
+        tracing::info!(
+            channel = %msg.channel,
+            sender = %msg.sender,
+            id = %msg.id,
+            "inbound: {}",
+            truncate_with_ellipsis(&msg.content, 200)
+        );
+        Ok(InboundAction::Continue(msg))
+    }
+}
+
+#[async_trait]
+impl OutboundMiddleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn on_outbound(&self, msg: OutboundMessage) -> Result<OutboundAction> {
+        tracing::info!(
+            channel = %msg.channel,
+            recipient = %msg.recipient,
+            "outbound: {}",
+            truncate_with_ellipsis(&msg.content, 200)
+        );
+        Ok(OutboundAction::Send(msg))
+    }
+}
+
+/// Drops messages from blocked senders, and from anyone outside a channel's
+/// allow-list when one is configured. With identity linking, a rule naming
+/// any of a person's linked accounts applies to all of them.
+pub struct AclMiddleware {
+    blocked: HashSet<String>,
+    allow_only: HashMap<String, HashSet<String>>,
+    identities: Option<Arc<IdentityStore>>,
+}
+
+impl AclMiddleware {
+    pub fn new(config: &AclMiddlewareConfig, identities: Option<Arc<IdentityStore>>) -> Self {
+        Self {
+            identities,
+            blocked: config.blocked_senders.iter().cloned().collect(),
+            allow_only: config
+                .allow_only
+                .iter()
+                .map(|(channel, senders)| (channel.clone(), senders.iter().cloned().collect()))
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl InboundMiddleware for AclMiddleware {
+    fn name(&self) -> &str {
+        "acl"
+    }
+
+    async fn on_inbound(&self, msg: ChannelMessage) -> Result<InboundAction> {
+        let senders: Vec<String> = match &self.identities {
+            Some(store) => store
+                .linked_accounts(&msg.channel, msg.author_id())?
+                .into_iter()
+                .map(|account| account.sender)
+                .collect(),
+            None => vec![msg.author_id().to_string()],
+        };
+
+        if senders.iter().any(|s| self.blocked.contains(s)) {
+            return Ok(InboundAction::Drop {
+                reason: format!("sender {} is blocked", msg.author_id()),
+            });
+        }
+        if let Some(allowed) = self.allow_only.get(&msg.channel) {
+            if !senders.iter().any(|s| allowed.contains(s)) {
+                return Ok(InboundAction::Drop {
+                    reason: format!(
+                        "sender {} is not allowed on {}",
+                        msg.author_id(),
+                        msg.channel
+                    ),
+                });
+            }
+        }
+        Ok(InboundAction::Continue(msg))
+    }
+}
+
+#[derive(Default)]
+struct SenderWindow {
+    hits: VecDeque<Instant>,
+    notified: bool,
+}
+
+/// Sliding-window limit on messages per sender per channel. The optional
+/// notice is sent once per throttled window; further messages are dropped.
+pub struct RateLimitMiddleware {
+    max_messages: usize,
+    window: Duration,
+    notice: Option<String>,
+    senders: Mutex<HashMap<String, SenderWindow>>,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(config: &RateLimitMiddlewareConfig) -> Self {
+        Self {
+            max_messages: config.max_messages as usize,
+            window: Duration::from_secs(config.window_secs),
+            notice: config.notice.clone(),
+            senders: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl InboundMiddleware for RateLimitMiddleware {
+    fn name(&self) -> &str {
+        "rate_limit"
+    }
+
+    async fn on_inbound(&self, msg: ChannelMessage) -> Result<InboundAction> {
+        let now = Instant::now();
+        let key = format!("{}:{}", msg.channel, msg.sender);
+        let mut senders = self.senders.lock();
+        senders.retain(|_, w| {
+            w.hits
+                .back()
+                .is_some_and(|last| now.duration_since(*last) < self.window)
+        });
+
+        let window = senders.entry(key).or_default();
+        while window
+            .hits
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= self.window)
+        {
+            window.hits.pop_front();
+            window.notified = false;
+        }
+
+        if window.hits.len() < self.max_messages {
+            window.hits.push_back(now);
+            return Ok(InboundAction::Continue(msg));
+        }
+
+        match &self.notice {
+            Some(notice) if !window.notified => {
+                window.notified = true;
+                Ok(InboundAction::Reply(notice.clone()))
+            }
+            _ => Ok(InboundAction::Drop {
+                reason: format!("sender {} exceeded rate limit", msg.sender),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(channel: &str, sender: &str) -> ChannelMessage {
+        ChannelMessage::test(channel, sender, "hi")
+    }
+
+    #[tokio::test]
+    async fn acl_blocks_and_allow_lists() {
+        let mut config = AclMiddlewareConfig {
+            blocked_senders: vec!["mallory".into()],
+            ..AclMiddlewareConfig::default()
+        };
+        config
+            .allow_only
+            .insert("discord".into(), vec!["alice".into()]);
+        let acl = AclMiddleware::new(&config, None);
+
+        let verdict = acl.on_inbound(msg("qq", "mallory")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Drop { .. }));
+        let verdict = acl.on_inbound(msg("discord", "bob")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Drop { .. }));
+        let verdict = acl.on_inbound(msg("discord", "alice")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(_)));
+        let verdict = acl.on_inbound(msg("qq", "bob")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(_)));
+    }
+
+    #[tokio::test]
+    async fn acl_rules_follow_linked_accounts() {
+        let store = Arc::new(IdentityStore::in_memory(600).unwrap());
+        let code = store.issue_code("qq", "mallory").unwrap();
+        store.redeem_code(&code, "discord", "mal#2").unwrap();
+        let code = store.issue_code("qq", "alice").unwrap();
+        store.redeem_code(&code, "discord", "alice#1").unwrap();
+
+        let mut config = AclMiddlewareConfig {
+            blocked_senders: vec!["mallory".into()],
+            ..AclMiddlewareConfig::default()
+        };
+        config
+            .allow_only
+            .insert("discord".into(), vec!["alice".into(), "mal#2".into()]);
+        let acl = AclMiddleware::new(&config, Some(store));
+
+        let verdict = acl.on_inbound(msg("discord", "mal#2")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Drop { .. }));
+        let verdict = acl.on_inbound(msg("discord", "alice#1")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(_)));
+    }
+
+    #[tokio::test]
+    async fn rate_limit_notifies_once_then_drops() {
+        let limiter = RateLimitMiddleware::new(&RateLimitMiddlewareConfig {
+            max_messages: 2,
+            window_secs: 60,
+            notice: Some("slow down".into()),
+        });
+
+        for _ in 0..2 {
+            let verdict = limiter.on_inbound(msg("qq", "alice")).await.unwrap();
+            assert!(matches!(verdict, InboundAction::Continue(_)));
+        }
+        let verdict = limiter.on_inbound(msg("qq", "alice")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Reply(ref n) if n == "slow down"));
+        let verdict = limiter.on_inbound(msg("qq", "alice")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Drop { .. }));
+
+        // Other senders and channels have their own windows.
+        let verdict = limiter.on_inbound(msg("qq", "bob")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(_)));
+        let verdict = limiter.on_inbound(msg("discord", "alice")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(_)));
+    }
+}
@@ -0,0 +1,304 @@
+//! Spam and flood protection middleware (`flood`).
+//!
+//! Counts messages per sender per channel in a sliding window. Every message
+//! beyond the limit is a violation, and responses escalate with the number of
+//! recent violations:
+//!
+//! 1. below `warn_after_violations` — the excess message is silently ignored
+//! 2. at `warn_after_violations` — the sender gets a one-time warning
+//! 3. at `block_after_violations` — the sender is blocked for `block_secs`,
+//!    told so once, and an operator notice is sent to `notify_channel`
+//!
+//! Violations are forgotten after `violation_decay_secs` without a new one,
+//! and when a block expires.
+
+use super::{InboundAction, InboundMiddleware};
+use crate::channels::control::ChannelControl;
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::FloodMiddlewareConfig;
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct SenderState {
+    hits: VecDeque<Instant>,
+    violations: u32,
+    last_violation: Option<Instant>,
+    warned: bool,
+    blocked_until: Option<Instant>,
+}
+
+impl SenderState {
+    fn is_idle(&self, now: Instant, window: Duration, decay: Duration) -> bool {
+        let recent_hit = self
+            .hits
+            .back()
+            .is_some_and(|t| now.duration_since(*t) < window);
+        let recent_violation = self
+            .last_violation
+            .is_some_and(|t| now.duration_since(t) < decay);
+        let blocked = self.blocked_until.is_some_and(|until| until > now);
+        !recent_hit && !recent_violation && !blocked
+    }
+}
+
+/// What to do with one message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Verdict {
+    Allow,
+    Ignore,
+    Warn,
+    Block,
+    Blocked,
+}
+
+pub struct FloodMiddleware {
+    max_messages: usize,
+    window: Duration,
+    warn_after: u32,
+    block_after: u32,
+    block_for: Duration,
+    decay: Duration,
+    warning: String,
+    block_notice: String,
+    notify: Option<(String, String)>,
+    control: Option<Arc<ChannelControl>>,
+    senders: Mutex<HashMap<String, SenderState>>,
+}
+
+impl FloodMiddleware {
+    pub fn new(config: &FloodMiddlewareConfig, control: Option<Arc<ChannelControl>>) -> Self {
+        let notify = match (&config.notify_channel, &config.notify_recipient) {
+            (Some(channel), Some(recipient)) => Some((channel.clone(), recipient.clone())),
+            _ => None,
+        };
+        Self {
+            max_messages: config.max_messages as usize,
+            window: Duration::from_secs(config.window_secs),
+            warn_after: config.warn_after_violations.max(1),
+            block_after: config.block_after_violations.max(1),
+            block_for: Duration::from_secs(config.block_secs),
+            decay: Duration::from_secs(config.violation_decay_secs),
+            warning: config.warning.clone(),
+            block_notice: config.block_notice.clone(),
+            notify,
+            control,
+            senders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn verdict(&self, key: &str, now: Instant) -> Verdict {
+        let mut senders = self.senders.lock();
+        senders.retain(|_, state| !state.is_idle(now, self.window, self.decay));
+        let state = senders.entry(key.to_string()).or_default();
+
+        if let Some(until) = state.blocked_until {
+            if until > now {
+                return Verdict::Blocked;
+            }
+            *state = SenderState::default();
+        }
+        if state
+            .last_violation
+            .is_some_and(|t| now.duration_since(t) >= self.decay)
+        {
+            state.violations = 0;
+            state.warned = false;
+        }
+        while state
+            .hits
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            state.hits.pop_front();
+        }
+
+        if state.hits.len() < self.max_messages {
+            state.hits.push_back(now);
+            return Verdict::Allow;
+        }
+
+        state.violations += 1;
+        state.last_violation = Some(now);
+        if state.violations >= self.block_after {
+            state.blocked_until = Some(now + self.block_for);
+            Verdict::Block
+        } else if state.violations >= self.warn_after && !state.warned {
+            state.warned = true;
+            Verdict::Warn
+        } else {
+            Verdict::Ignore
+        }
+    }
+
+    async fn notify_operator(&self, msg: &ChannelMessage) {
+        let (Some((channel, recipient)), Some(control)) = (&self.notify, &self.control) else {
+            return;
+        };
+        let notice = format!(
+            "🚫 Flood protection blocked {} on {} for {}s",
+            msg.sender,
+            msg.channel,
+            self.block_for.as_secs()
+        );
+        if let Err(e) = control.send(channel, recipient, &notice).await {
+            tracing::warn!("Failed to send flood notice to {channel}: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl InboundMiddleware for FloodMiddleware {
+    fn name(&self) -> &str {
+        "flood"
+    }
+
+    async fn on_inbound(&self, msg: ChannelMessage) -> Result<InboundAction> {
+        let key = format!("{}:{}", msg.channel, msg.sender);
+        match self.verdict(&key, Instant::now()) {
+            Verdict::Allow => Ok(InboundAction::Continue(msg)),
+            Verdict::Ignore => Ok(InboundAction::Drop {
+                reason: format!("{} is flooding", msg.sender),
+            }),
+            Verdict::Warn => Ok(InboundAction::Reply(self.warning.clone())),
+            Verdict::Block => {
+                tracing::warn!(
+                    "Flood protection blocked {} on {} for {}s",
+                    msg.sender,
+                    msg.channel,
+                    self.block_for.as_secs()
+                );
+                self.notify_operator(&msg).await;
+                Ok(InboundAction::Reply(self.block_notice.clone()))
+            }
+            Verdict::Blocked => Ok(InboundAction::Drop {
+                reason: format!("{} is temporarily blocked", msg.sender),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::manager::ChannelManager;
+    use crate::channels::traits::Channel;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "ops"
+        }
+
+        async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .push((recipient.to_string(), message.to_string()));
+            Ok(())
+        }
+
+        async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn config() -> FloodMiddlewareConfig {
+        FloodMiddlewareConfig {
+            max_messages: 2,
+            warn_after_violations: 2,
+            block_after_violations: 4,
+            ..FloodMiddlewareConfig::default()
+        }
+    }
+
+    fn msg(sender: &str) -> ChannelMessage {
+        ChannelMessage::test("qq", sender, "spam")
+    }
+
+    #[test]
+    fn responses_escalate_from_ignore_to_warn_to_block() {
+        let flood = FloodMiddleware::new(&config(), None);
+        let now = Instant::now();
+        let verdicts: Vec<_> = (0..7).map(|_| flood.verdict("qq:alice", now)).collect();
+        assert_eq!(
+            verdicts,
+            [
+                Verdict::Allow,
+                Verdict::Allow,
+                Verdict::Ignore,
+                Verdict::Warn,
+                Verdict::Ignore,
+                Verdict::Block,
+                Verdict::Blocked,
+            ]
+        );
+        // Other senders are unaffected.
+        assert_eq!(flood.verdict("qq:bob", now), Verdict::Allow);
+    }
+
+    #[test]
+    fn block_expires_and_resets_violations() {
+        let flood = FloodMiddleware::new(&config(), None);
+        let start = Instant::now();
+        for _ in 0..6 {
+            flood.verdict("qq:alice", start);
+        }
+        let during = start + Duration::from_secs(599);
+        assert_eq!(flood.verdict("qq:alice", during), Verdict::Blocked);
+        let after = start + Duration::from_secs(601);
+        assert_eq!(flood.verdict("qq:alice", after), Verdict::Allow);
+        assert_eq!(flood.verdict("qq:alice", after), Verdict::Allow);
+        assert_eq!(flood.verdict("qq:alice", after), Verdict::Ignore);
+    }
+
+    #[tokio::test]
+    async fn block_notifies_operator() {
+        let ops = Arc::new(RecordingChannel::default());
+        let control = Arc::new(ChannelControl::new());
+        let channels: Vec<Arc<dyn Channel>> = vec![ops.clone()];
+        control.attach(ChannelManager::from_channels(&channels));
+
+        let flood = FloodMiddleware::new(
+            &FloodMiddlewareConfig {
+                max_messages: 1,
+                warn_after_violations: 1,
+                block_after_violations: 2,
+                notify_channel: Some("ops".into()),
+                notify_recipient: Some("admins".into()),
+                ..FloodMiddlewareConfig::default()
+            },
+            Some(control),
+        );
+
+        assert!(matches!(
+            flood.on_inbound(msg("mallory")).await.unwrap(),
+            InboundAction::Continue(_)
+        ));
+        assert!(matches!(
+            flood.on_inbound(msg("mallory")).await.unwrap(),
+            InboundAction::Reply(ref w) if w.contains("slow down")
+        ));
+        assert!(matches!(
+            flood.on_inbound(msg("mallory")).await.unwrap(),
+            InboundAction::Reply(ref n) if n.contains("blocked")
+        ));
+        assert!(matches!(
+            flood.on_inbound(msg("mallory")).await.unwrap(),
+            InboundAction::Drop { .. }
+        ));
+
+        let sent = ops.sent.lock();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, "admins");
+        assert!(sent[0].1.contains("mallory"));
+    }
+}
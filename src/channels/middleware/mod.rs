@@ -0,0 +1,524 @@
+//! Middleware chains for channel traffic.
+//!
+//! Cross-cutting concerns (logging, access control, rate limiting, content
+//! filtering, translation) are written once as [`InboundMiddleware`] or
+//! [`OutboundMiddleware`] and composed per channel from `[middleware]` config,
+//! instead of being hardcoded into each channel implementation.
+//!
+//! Inbound middleware sees every message before plugins and the agent, and
+//! may pass it on (possibly modified), drop it, or answer it directly.
+//! Outbound middleware sees every reply before it is sent and may modify or
+//! drop it. A middleware that returns an error stops its chain and the
+//! message is dropped; middleware calling external services should fall back
+//! to passing the message through on transient failures.
+
+mod builtin;
+pub mod flood;
+pub mod moderation;
+pub mod translation;
+
+pub use builtin::{AclMiddleware, LoggingMiddleware, RateLimitMiddleware};
+pub use flood::FloodMiddleware;
+pub use moderation::ModerationMiddleware;
+pub use translation::TranslationMiddleware;
+
+use super::control::ChannelControl;
+use super::manager::tag_selector;
+use super::traits::ChannelMessage;
+use crate::config::Config;
+use crate::identity::linking::IdentityStore;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Verdict of an inbound middleware.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum InboundAction {
+    /// Hand the (possibly modified) message to the next middleware.
+    Continue(ChannelMessage),
+    /// Discard the message.
+    Drop { reason: String },
+    /// Answer the sender with this text and stop processing.
+    Reply(String),
+}
+
+/// A reply on its way out to a channel.
+#[derive(Debug, Clone)]
+pub struct OutboundMessage {
+    pub channel: String,
+    pub recipient: String,
+    pub content: String,
+    /// The inbound message being answered, when there is one.
+    pub reply_to: Option<ChannelMessage>,
+}
+
+/// Verdict of an outbound middleware.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum OutboundAction {
+    /// Hand the (possibly modified) reply to the next middleware.
+    Send(OutboundMessage),
+    /// Do not send the reply.
+    Drop { reason: String },
+}
+
+#[async_trait]
+pub trait InboundMiddleware: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether this middleware looks at message text. Such middleware also
+    /// screens text transcribed or described from attachments, which is only
+    /// produced once the message got through the whole chain.
+    fn inspects_content(&self) -> bool {
+        false
+    }
+
+    async fn on_inbound(&self, msg: ChannelMessage) -> Result<InboundAction>;
+}
+
+#[async_trait]
+pub trait OutboundMiddleware: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn on_outbound(&self, msg: OutboundMessage) -> Result<OutboundAction>;
+}
+
+/// Ordered inbound and outbound middleware for one channel.
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    inbound: Vec<Arc<dyn InboundMiddleware>>,
+    outbound: Vec<Arc<dyn OutboundMiddleware>>,
+}
+
+impl MiddlewareChain {
+    pub fn new(
+        inbound: Vec<Arc<dyn InboundMiddleware>>,
+        outbound: Vec<Arc<dyn OutboundMiddleware>>,
+    ) -> Self {
+        Self { inbound, outbound }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inbound.is_empty() && self.outbound.is_empty()
+    }
+
+    /// Whether replies pass through any outbound middleware.
+    pub fn has_outbound(&self) -> bool {
+        !self.outbound.is_empty()
+    }
+
+    pub async fn run_inbound(&self, msg: ChannelMessage) -> InboundAction {
+        self.run_inbound_where(msg, |_| true).await
+    }
+
+    /// Run only the middleware that inspect content, over text added to a
+    /// message the full chain already let through.
+    pub async fn run_inbound_content(&self, msg: ChannelMessage) -> InboundAction {
+        self.run_inbound_where(msg, |middleware| middleware.inspects_content())
+            .await
+    }
+
+    async fn run_inbound_where(
+        &self,
+        mut msg: ChannelMessage,
+        applies: impl Fn(&dyn InboundMiddleware) -> bool,
+    ) -> InboundAction {
+        for middleware in self.inbound.iter().filter(|m| applies(m.as_ref())) {
+            match middleware.on_inbound(msg).await {
+                Ok(InboundAction::Continue(next)) => msg = next,
+                Ok(InboundAction::Drop { reason }) => {
+                    return InboundAction::Drop {
+                        reason: format!("{}: {reason}", middleware.name()),
+                    };
+                }
+                Ok(reply @ InboundAction::Reply(_)) => return reply,
+                Err(e) => {
+                    tracing::warn!("Inbound middleware {} failed: {e}", middleware.name());
+                    return InboundAction::Drop {
+                        reason: format!("{} failed", middleware.name()),
+                    };
+                }
+            }
+        }
+        InboundAction::Continue(msg)
+    }
+
+    pub async fn run_outbound(&self, mut msg: OutboundMessage) -> OutboundAction {
+        for middleware in &self.outbound {
+            match middleware.on_outbound(msg).await {
+                Ok(OutboundAction::Send(next)) => msg = next,
+                Ok(OutboundAction::Drop { reason }) => {
+                    return OutboundAction::Drop {
+                        reason: format!("{}: {reason}", middleware.name()),
+                    };
+                }
+                Err(e) => {
+                    tracing::warn!("Outbound middleware {} failed: {e}", middleware.name());
+                    return OutboundAction::Drop {
+                        reason: format!("{} failed", middleware.name()),
+                    };
+                }
+            }
+        }
+        OutboundAction::Send(msg)
+    }
+}
+
+/// Middleware chains for every channel: a default chain plus per-channel
+/// overrides.
+#[derive(Clone, Default)]
+pub struct MiddlewareStack {
+    default: MiddlewareChain,
+    channels: HashMap<String, MiddlewareChain>,
+}
+
+impl MiddlewareStack {
+    pub fn new(default: MiddlewareChain, channels: HashMap<String, MiddlewareChain>) -> Self {
+        Self { default, channels }
+    }
+
+    /// Chain applied to `channel`.
+    pub fn chain(&self, channel: &str) -> &MiddlewareChain {
+        self.channels.get(channel).unwrap_or(&self.default)
+    }
+
+    pub async fn run_inbound(&self, msg: ChannelMessage) -> InboundAction {
+        let chain = self.chain(&msg.channel);
+        if chain.inbound.is_empty() {
+            return InboundAction::Continue(msg);
+        }
+        chain.run_inbound(msg).await
+    }
+
+    pub async fn run_inbound_content(&self, msg: ChannelMessage) -> InboundAction {
+        self.chain(&msg.channel).run_inbound_content(msg).await
+    }
+
+    pub async fn run_outbound(&self, msg: OutboundMessage) -> OutboundAction {
+        let chain = self.chain(&msg.channel);
+        if chain.outbound.is_empty() {
+            return OutboundAction::Send(msg);
+        }
+        chain.run_outbound(msg).await
+    }
+}
+
+/// Runtime services available to middleware.
+#[derive(Clone, Default)]
+pub struct MiddlewareContext {
+    /// Lets middleware send messages of its own (e.g. flood alerts).
+    pub control: Option<Arc<ChannelControl>>,
+    /// Linked user profiles, when `[identity_linking]` is enabled.
+    pub identities: Option<Arc<IdentityStore>>,
+}
+
+/// Middleware instances shared by every chain that names them, so stateful
+/// middleware (rate limits) counts across channels consistently. Translation
+/// is additionally shared between directions: replies are translated into the
+/// language detected inbound.
+struct Registry<'a> {
+    config: &'a Config,
+    services: &'a MiddlewareContext,
+    inbound: HashMap<String, Arc<dyn InboundMiddleware>>,
+    outbound: HashMap<String, Arc<dyn OutboundMiddleware>>,
+    translation: Option<Arc<TranslationMiddleware>>,
+}
+
+impl Registry<'_> {
+    fn translation(&mut self) -> Result<Arc<TranslationMiddleware>> {
+        if let Some(existing) = &self.translation {
+            return Ok(Arc::clone(existing));
+        }
+        let created = Arc::new(TranslationMiddleware::from_config(self.config)?);
+        self.translation = Some(Arc::clone(&created));
+        Ok(created)
+    }
+
+    fn inbound(&mut self, names: &[String]) -> Result<Vec<Arc<dyn InboundMiddleware>>> {
+        names
+            .iter()
+            .map(|name| {
+                if let Some(existing) = self.inbound.get(name) {
+                    return Ok(Arc::clone(existing));
+                }
+                let created: Arc<dyn InboundMiddleware> = if name == "translation" {
+                    self.translation()?
+                } else {
+                    create_inbound(name, self.config, self.services)?
+                };
+                self.inbound.insert(name.clone(), Arc::clone(&created));
+                Ok(created)
+            })
+            .collect()
+    }
+
+    fn outbound(&mut self, names: &[String]) -> Result<Vec<Arc<dyn OutboundMiddleware>>> {
+        names
+            .iter()
+            .map(|name| {
+                if let Some(existing) = self.outbound.get(name) {
+                    return Ok(Arc::clone(existing));
+                }
+                let created: Arc<dyn OutboundMiddleware> = if name == "translation" {
+                    self.translation()?
+                } else {
+                    create_outbound(name, self.config)?
+                };
+                self.outbound.insert(name.clone(), Arc::clone(&created));
+                Ok(created)
+            })
+            .collect()
+    }
+}
+
+/// Create an inbound middleware by name.
+pub fn create_inbound(
+    name: &str,
+    config: &Config,
+    services: &MiddlewareContext,
+) -> Result<Arc<dyn InboundMiddleware>> {
+    let settings = &config.middleware;
+    match name {
+        "logging" => Ok(Arc::new(LoggingMiddleware)),
+        "acl" => Ok(Arc::new(AclMiddleware::new(
+            &settings.acl,
+            services.identities.clone(),
+        ))),
+        "rate_limit" => Ok(Arc::new(RateLimitMiddleware::new(&settings.rate_limit))),
+        "flood" => Ok(Arc::new(FloodMiddleware::new(
+            &settings.flood,
+            services.control.clone(),
+        ))),
+        "moderation" => Ok(Arc::new(ModerationMiddleware::from_config(config)?)),
+        "translation" => Ok(Arc::new(TranslationMiddleware::from_config(config)?)),
+        other => anyhow::bail!("Unknown inbound middleware: {other}"),
+    }
+}
+
+/// Create an outbound middleware by name.
+pub fn create_outbound(name: &str, config: &Config) -> Result<Arc<dyn OutboundMiddleware>> {
+    match name {
+        "logging" => Ok(Arc::new(LoggingMiddleware)),
+        "moderation" => Ok(Arc::new(ModerationMiddleware::from_config(config)?)),
+        "translation" => Ok(Arc::new(TranslationMiddleware::from_config(config)?)),
+        other => anyhow::bail!("Unknown outbound middleware: {other}"),
+    }
+}
+
+/// Build the middleware stack described by `[middleware]`.
+pub fn build_stack(config: &Config, services: &MiddlewareContext) -> Result<MiddlewareStack> {
+    let mut registry = Registry {
+        config,
+        services,
+        inbound: HashMap::new(),
+        outbound: HashMap::new(),
+        translation: None,
+    };
+    let channel_tags = &config.channels_config.tags;
+    let config = &config.middleware;
+    let default = MiddlewareChain::new(
+        registry.inbound(&config.inbound)?,
+        registry.outbound(&config.outbound)?,
+    );
+
+    let mut channels = HashMap::new();
+    for (channel, overrides) in &config.channels {
+        let inbound = match &overrides.inbound {
+            Some(names) => registry.inbound(names)?,
+            None => default.inbound.clone(),
+        };
+        let outbound = match &overrides.outbound {
+            Some(names) => registry.outbound(names)?,
+            None => default.outbound.clone(),
+        };
+        channels.insert(channel.clone(), MiddlewareChain::new(inbound, outbound));
+    }
+
+    // `tag:<tag>` overrides apply to every tagged channel without an
+    // override of its own.
+    let tag_chains: Vec<(String, MiddlewareChain)> = channels
+        .iter()
+        .filter_map(|(key, chain)| Some((tag_selector(key)?.to_string(), chain.clone())))
+        .collect();
+    for (tag, chain) in tag_chains {
+        channels.retain(|key, _| tag_selector(key) != Some(tag.as_str()));
+        for (channel, tagging) in channel_tags {
+            if tagging.tags.contains(&tag) && !config.channels.contains_key(channel) {
+                channels
+                    .entry(channel.clone())
+                    .or_insert_with(|| chain.clone());
+            }
+        }
+    }
+
+    Ok(MiddlewareStack::new(default, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::{MiddlewareChainConfig, MiddlewareConfig};
+
+    struct Upper;
+
+    #[async_trait]
+    impl InboundMiddleware for Upper {
+        fn name(&self) -> &str {
+            "upper"
+        }
+
+        async fn on_inbound(&self, mut msg: ChannelMessage) -> Result<InboundAction> {
+            msg.content = msg.content.to_uppercase();
+            Ok(InboundAction::Continue(msg))
+        }
+    }
+
+    struct Screening;
+
+    #[async_trait]
+    impl InboundMiddleware for Screening {
+        fn name(&self) -> &str {
+            "screening"
+        }
+
+        fn inspects_content(&self) -> bool {
+            true
+        }
+
+        async fn on_inbound(&self, mut msg: ChannelMessage) -> Result<InboundAction> {
+            msg.content = format!("[{}]", msg.content);
+            Ok(InboundAction::Continue(msg))
+        }
+    }
+
+    struct Failing;
+
+    #[async_trait]
+    impl InboundMiddleware for Failing {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn on_inbound(&self, _msg: ChannelMessage) -> Result<InboundAction> {
+            anyhow::bail!("boom")
+        }
+    }
+
+    fn msg(channel: &str, content: &str) -> ChannelMessage {
+        ChannelMessage::test(channel, "alice", content)
+    }
+
+    #[tokio::test]
+    async fn chain_runs_in_order_and_stops_on_failure() {
+        let chain = MiddlewareChain::new(vec![Arc::new(Upper)], Vec::new());
+        match chain.run_inbound(msg("qq", "hi")).await {
+            InboundAction::Continue(m) => assert_eq!(m.content, "HI"),
+            other => panic!("unexpected action: {other:?}"),
+        }
+
+        let chain = MiddlewareChain::new(vec![Arc::new(Failing), Arc::new(Upper)], Vec::new());
+        assert!(matches!(
+            chain.run_inbound(msg("qq", "hi")).await,
+            InboundAction::Drop { reason } if reason == "failing failed"
+        ));
+    }
+
+    #[tokio::test]
+    async fn content_run_skips_middleware_that_ignores_text() {
+        let chain = MiddlewareChain::new(vec![Arc::new(Upper), Arc::new(Screening)], Vec::new());
+        match chain.run_inbound_content(msg("qq", "hi")).await {
+            InboundAction::Continue(m) => assert_eq!(m.content, "[hi]"),
+            other => panic!("unexpected action: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn per_channel_override_replaces_default_chain() {
+        let mut config = Config::default();
+        config.middleware.inbound = vec!["acl".into()];
+        config.middleware.acl.blocked_senders = vec!["alice".into()];
+        config.middleware.channels.insert(
+            "discord".into(),
+            MiddlewareChainConfig {
+                inbound: Some(Vec::new()),
+                outbound: None,
+            },
+        );
+        let stack = build_stack(&config, &MiddlewareContext::default()).unwrap();
+
+        assert!(matches!(
+            stack.run_inbound(msg("qq", "hi")).await,
+            InboundAction::Drop { .. }
+        ));
+        assert!(matches!(
+            stack.run_inbound(msg("discord", "hi")).await,
+            InboundAction::Continue(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn tag_override_applies_to_tagged_channels() {
+        let mut config = Config::default();
+        config.middleware.inbound = vec!["acl".into()];
+        config.middleware.acl.blocked_senders = vec!["alice".into()];
+        config.middleware.channels.insert(
+            "tag:internal".into(),
+            MiddlewareChainConfig {
+                inbound: Some(Vec::new()),
+                outbound: None,
+            },
+        );
+        config.channels_config.tags.insert(
+            "slack".into(),
+            crate::config::ChannelTagsConfig {
+                tags: vec!["internal".into()],
+                default_recipient: None,
+            },
+        );
+        let stack = build_stack(&config, &MiddlewareContext::default()).unwrap();
+
+        assert!(matches!(
+            stack.run_inbound(msg("qq", "hi")).await,
+            InboundAction::Drop { .. }
+        ));
+        assert!(matches!(
+            stack.run_inbound(msg("slack", "hi")).await,
+            InboundAction::Continue(_)
+        ));
+    }
+
+    #[test]
+    fn translation_is_shared_between_directions() {
+        let mut config = Config::default();
+        config.middleware.inbound = vec!["translation".into()];
+        config.middleware.outbound = vec!["translation".into()];
+        let services = MiddlewareContext::default();
+        let mut registry = Registry {
+            config: &config,
+            services: &services,
+            inbound: HashMap::new(),
+            outbound: HashMap::new(),
+            translation: None,
+        };
+        registry.inbound(&config.middleware.inbound).unwrap();
+        registry.outbound(&config.middleware.outbound).unwrap();
+        let shared = registry.translation.as_ref().unwrap();
+        // Registry slot plus one handle per direction.
+        assert_eq!(Arc::strong_count(shared), 3);
+    }
+
+    #[test]
+    fn unknown_middleware_is_rejected() {
+        let mut config = Config::default();
+        config.middleware = MiddlewareConfig {
+            outbound: vec!["acl".into()],
+            ..MiddlewareConfig::default()
+        };
+        let err = build_stack(&config, &MiddlewareContext::default())
+            .err()
+            .expect("acl is inbound-only");
+        assert!(err.to_string().contains("Unknown outbound middleware"));
+    }
+}
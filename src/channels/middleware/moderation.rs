@@ -0,0 +1,377 @@
+//! Content moderation middleware (`moderation`).
+//!
+//! Checks message text against configured wordlists and regex rules and,
+//! when nothing matches locally, optionally an OpenAI-compatible moderation
+//! endpoint. Matching content is flagged, redacted or dropped according to
+//! the channel's policy. Works in both directions: add `moderation` to the
+//! inbound chain to screen users, to the outbound chain to screen replies.
+
+use super::{
+    InboundAction, InboundMiddleware, OutboundAction, OutboundMessage, OutboundMiddleware,
+};
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::{ModerationAction, ModerationMiddlewareConfig};
+use crate::config::Config;
+use crate::security::ManagedSecret;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const MODERATION_API_TIMEOUT_SECS: u64 = 10;
+
+/// Result of checking one piece of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Finding {
+    Clean,
+    /// Local rules matched; carries the text with matches replaced.
+    Matched {
+        redacted: String,
+    },
+    /// The moderation endpoint flagged the text as a whole.
+    Flagged {
+        categories: Vec<String>,
+    },
+}
+
+struct ModerationApi {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    key: Option<Arc<ManagedSecret>>,
+}
+
+#[derive(Deserialize)]
+struct ModerationResponse {
+    results: Vec<ModerationResult>,
+}
+
+#[derive(Deserialize)]
+struct ModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: HashMap<String, bool>,
+}
+
+impl ModerationApi {
+    async fn check(&self, text: &str) -> Result<Option<Vec<String>>> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(Duration::from_secs(MODERATION_API_TIMEOUT_SECS))
+            .json(&serde_json::json!({ "model": self.model, "input": text }));
+        if let Some(key) = &self.key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let response: ModerationResponse = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("invalid moderation response")?;
+
+        let flagged: Vec<_> = response.results.iter().filter(|r| r.flagged).collect();
+        if flagged.is_empty() {
+            return Ok(None);
+        }
+        let mut categories: Vec<String> = flagged
+            .iter()
+            .flat_map(|r| r.categories.iter().filter(|(_, hit)| **hit))
+            .map(|(name, _)| name.clone())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        Ok(Some(categories))
+    }
+}
+
+pub struct ModerationMiddleware {
+    rules: Option<Regex>,
+    action: ModerationAction,
+    channels: HashMap<String, ModerationAction>,
+    replacement: String,
+    api: Option<ModerationApi>,
+}
+
+/// Regex for one wordlist entry. Latin-script words match whole words only,
+/// so "ass" doesn't hit "class"; scripts without word separators (CJK)
+/// match anywhere.
+fn word_rule(word: &str) -> String {
+    let escaped = regex::escape(word);
+    let bounded = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    if bounded(word.chars().next()) && bounded(word.chars().last()) {
+        format!(r"\b{escaped}\b")
+    } else {
+        escaped
+    }
+}
+
+impl ModerationMiddleware {
+    pub fn new(
+        config: &ModerationMiddlewareConfig,
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Result<Self> {
+        let mut rules: Vec<String> = config
+            .words
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(word_rule)
+            .collect();
+        for pattern in &config.patterns {
+            Regex::new(pattern)
+                .with_context(|| format!("invalid moderation pattern: {pattern}"))?;
+            rules.push(format!("(?:{pattern})"));
+        }
+        let rules = if rules.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&format!("(?i){}", rules.join("|")))?)
+        };
+
+        let api = config.api_url.as_ref().map(|url| ModerationApi {
+            client: reqwest::Client::new(),
+            url: url.clone(),
+            model: config.api_model.clone(),
+            key: api_key,
+        });
+
+        Ok(Self {
+            rules,
+            action: config.action,
+            channels: config.channels.clone(),
+            replacement: config.replacement.clone(),
+            api,
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let settings = &config.middleware.moderation;
+        let api_key = settings
+            .api_key
+            .as_deref()
+            .map(|raw| crate::channels::resolve_channel_secret(config, raw))
+            .transpose()?;
+        Self::new(settings, api_key)
+    }
+
+    fn action_for(&self, channel: &str) -> ModerationAction {
+        self.channels.get(channel).copied().unwrap_or(self.action)
+    }
+
+    async fn check(&self, text: &str) -> Finding {
+        if let Some(rules) = &self.rules {
+            if rules.is_match(text) {
+                let redacted = rules.replace_all(text, self.replacement.as_str());
+                return Finding::Matched {
+                    redacted: redacted.into_owned(),
+                };
+            }
+        }
+        if let Some(api) = &self.api {
+            match api.check(text).await {
+                Ok(Some(categories)) => return Finding::Flagged { categories },
+                Ok(None) => {}
+                // Fail open: an unreachable moderation service must not
+                // silence every channel.
+                Err(e) => tracing::warn!("Moderation API check failed: {e}"),
+            }
+        }
+        Finding::Clean
+    }
+
+    /// Apply the channel policy. `None` means drop; otherwise the
+    /// (possibly redacted) text to deliver.
+    async fn moderate(&self, channel: &str, direction: &str, text: &str) -> Option<String> {
+        let action = self.action_for(channel);
+        if action == ModerationAction::Off {
+            return Some(text.to_string());
+        }
+        let finding = self.check(text).await;
+        let redacted = match &finding {
+            Finding::Clean => return Some(text.to_string()),
+            Finding::Matched { redacted } => redacted.clone(),
+            Finding::Flagged { categories } => {
+                tracing::debug!("Moderation API flagged {direction} text: {categories:?}");
+                self.replacement.clone()
+            }
+        };
+        match action {
+            ModerationAction::Off => Some(text.to_string()),
+            ModerationAction::Flag => {
+                tracing::warn!(
+                    channel,
+                    direction,
+                    "Moderation flagged message: {finding:?}"
+                );
+                Some(text.to_string())
+            }
+            ModerationAction::Redact => Some(redacted),
+            ModerationAction::Drop => None,
+        }
+    }
+}
+
+#[async_trait]
+impl InboundMiddleware for ModerationMiddleware {
+    fn name(&self) -> &str {
+        "moderation"
+    }
+
+    fn inspects_content(&self) -> bool {
+        true
+    }
+
+    async fn on_inbound(&self, mut msg: ChannelMessage) -> Result<InboundAction> {
+        // Media-only messages are screened once their text is extracted
+        if msg.content.trim().is_empty() {
+            return Ok(InboundAction::Continue(msg));
+        }
+        match self.moderate(&msg.channel, "inbound", &msg.content).await {
+            Some(content) => {
+                msg.content = content;
+                Ok(InboundAction::Continue(msg))
+            }
+            None => Ok(InboundAction::Drop {
+                reason: "content violates moderation policy".into(),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl OutboundMiddleware for ModerationMiddleware {
+    fn name(&self) -> &str {
+        "moderation"
+    }
+
+    async fn on_outbound(&self, mut msg: OutboundMessage) -> Result<OutboundAction> {
+        match self.moderate(&msg.channel, "outbound", &msg.content).await {
+            Some(content) => {
+                msg.content = content;
+                Ok(OutboundAction::Send(msg))
+            }
+            None => Ok(OutboundAction::Drop {
+                reason: "content violates moderation policy".into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(channel: &str, content: &str) -> ChannelMessage {
+        ChannelMessage::test(channel, "alice", content)
+    }
+
+    fn middleware(config: &ModerationMiddlewareConfig) -> ModerationMiddleware {
+        ModerationMiddleware::new(config, None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn redacts_words_and_patterns() {
+        let config = ModerationMiddlewareConfig {
+            words: vec!["darn".into(), "笨蛋".into()],
+            patterns: vec![r"\d{3}-\d{4}".into()],
+            ..ModerationMiddlewareConfig::default()
+        };
+        let moderation = middleware(&config);
+
+        let verdict = moderation
+            .on_inbound(msg("qq", "Darn it 笨蛋, call 555-1234"))
+            .await
+            .unwrap();
+        match verdict {
+            InboundAction::Continue(m) => assert_eq!(m.content, "*** it ***, call ***"),
+            other => panic!("unexpected action: {other:?}"),
+        }
+
+        // Latin words only match whole words.
+        let verdict = moderation.on_inbound(msg("qq", "darnation")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(m) if m.content == "darnation"));
+    }
+
+    #[tokio::test]
+    async fn per_channel_policy_overrides_default() {
+        let mut config = ModerationMiddlewareConfig {
+            words: vec!["darn".into()],
+            action: ModerationAction::Drop,
+            ..ModerationMiddlewareConfig::default()
+        };
+        config
+            .channels
+            .insert("discord".into(), ModerationAction::Flag);
+        config.channels.insert("irc".into(), ModerationAction::Off);
+        let moderation = middleware(&config);
+
+        let verdict = moderation.on_inbound(msg("qq", "darn")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Drop { .. }));
+        let verdict = moderation.on_inbound(msg("discord", "darn")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(m) if m.content == "darn"));
+        let verdict = moderation.on_inbound(msg("irc", "darn")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(m) if m.content == "darn"));
+
+        let reply = OutboundMessage {
+            channel: "qq".into(),
+            recipient: "alice".into(),
+            content: "well darn".into(),
+            reply_to: None,
+        };
+        let verdict = moderation.on_outbound(reply).await.unwrap();
+        assert!(matches!(verdict, OutboundAction::Drop { .. }));
+    }
+
+    #[test]
+    fn invalid_pattern_is_rejected() {
+        let config = ModerationMiddlewareConfig {
+            patterns: vec!["(".into()],
+            ..ModerationMiddlewareConfig::default()
+        };
+        assert!(ModerationMiddleware::new(&config, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn external_api_flags_are_redacted_and_failures_fail_open() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/moderations",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                let flagged = body["input"].as_str().unwrap_or("").contains("threat");
+                Json(serde_json::json!({
+                    "results": [{ "flagged": flagged, "categories": { "violence": flagged } }]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = ModerationMiddlewareConfig {
+            api_url: Some(format!("http://{addr}/moderations")),
+            replacement: "[removed]".into(),
+            ..ModerationMiddlewareConfig::default()
+        };
+        let moderation = middleware(&config);
+        let verdict = moderation.on_inbound(msg("qq", "a threat")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(m) if m.content == "[removed]"));
+        let verdict = moderation.on_inbound(msg("qq", "hello")).await.unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(m) if m.content == "hello"));
+
+        let unreachable = ModerationMiddlewareConfig {
+            api_url: Some("http://127.0.0.1:9/moderations".into()),
+            ..ModerationMiddlewareConfig::default()
+        };
+        let verdict = middleware(&unreachable)
+            .on_inbound(msg("qq", "a threat"))
+            .await
+            .unwrap();
+        assert!(matches!(verdict, InboundAction::Continue(m) if m.content == "a threat"));
+    }
+}
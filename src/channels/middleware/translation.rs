@@ -0,0 +1,597 @@
+//! Automatic translation middleware (`translation`).
+//!
+//! Inbound, messages are translated into the bot's working language and the
+//! sender's detected language is remembered. Outbound, replies are translated
+//! back into the language of the message being answered. Add `translation`
+//! to both the inbound and outbound chains.
+//!
+//! Backends implement [`Translator`]: DeepL, Google Cloud Translation (v2)
+//! and LibreTranslate are built in. Translation failures are logged and the
+//! original text is delivered.
+
+use super::{
+    InboundAction, InboundMiddleware, OutboundAction, OutboundMessage, OutboundMiddleware,
+};
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::TranslationMiddlewareConfig;
+use crate::config::Config;
+use crate::security::ManagedSecret;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TRANSLATION_TIMEOUT_SECS: u64 = 15;
+
+/// Remembered sender languages before the map is reset.
+const MAX_TRACKED_SENDERS: usize = 10_000;
+
+/// Output of one translation call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    pub text: String,
+    /// Source language as detected by the backend, when it reports one.
+    pub detected_source: Option<String>,
+}
+
+#[async_trait]
+pub trait Translator: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Translate `text` into `target`. `source` of `None` asks the backend to
+    /// detect the language.
+    async fn translate(
+        &self,
+        text: &str,
+        source: Option<&str>,
+        target: &str,
+    ) -> Result<Translation>;
+}
+
+/// Primary language subtag, lowercased: `"EN-US"` and `"en"` both give `"en"`.
+fn primary_language(tag: &str) -> String {
+    tag.split(['-', '_'])
+        .next()
+        .unwrap_or(tag)
+        .to_ascii_lowercase()
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(TRANSLATION_TIMEOUT_SECS))
+        .build()
+        .unwrap_or_default()
+}
+
+async fn resolve_key(key: Option<&Arc<ManagedSecret>>) -> Result<Option<String>> {
+    match key {
+        Some(secret) => Ok(Some(secret.get().await?)),
+        None => Ok(None),
+    }
+}
+
+// ── DeepL ────────────────────────────────────────────────────────
+
+pub struct DeepLTranslator {
+    client: reqwest::Client,
+    url: String,
+    key: Arc<ManagedSecret>,
+}
+
+impl DeepLTranslator {
+    pub fn new(url: Option<String>, key: Arc<ManagedSecret>) -> Self {
+        Self {
+            client: http_client(),
+            url: url.unwrap_or_else(|| "https://api-free.deepl.com/v2/translate".into()),
+            key,
+        }
+    }
+}
+
+#[async_trait]
+impl Translator for DeepLTranslator {
+    fn name(&self) -> &str {
+        "deepl"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: Option<&str>,
+        target: &str,
+    ) -> Result<Translation> {
+        #[derive(Deserialize)]
+        struct Response {
+            translations: Vec<Item>,
+        }
+        #[derive(Deserialize)]
+        struct Item {
+            text: String,
+            detected_source_language: Option<String>,
+        }
+
+        let mut body = serde_json::json!({
+            "text": [text],
+            "target_lang": target.to_ascii_uppercase(),
+        });
+        if let Some(source) = source {
+            body["source_lang"] = primary_language(source).to_ascii_uppercase().into();
+        }
+        let response: Response = self
+            .client
+            .post(&self.url)
+            .header(
+                "Authorization",
+                format!("DeepL-Auth-Key {}", self.key.get().await?),
+            )
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("invalid DeepL response")?;
+        let item = response
+            .translations
+            .into_iter()
+            .next()
+            .context("DeepL returned no translations")?;
+        Ok(Translation {
+            text: item.text,
+            detected_source: item.detected_source_language,
+        })
+    }
+}
+
+// ── Google Cloud Translation (v2) ────────────────────────────────
+
+pub struct GoogleTranslator {
+    client: reqwest::Client,
+    url: String,
+    key: Arc<ManagedSecret>,
+}
+
+impl GoogleTranslator {
+    pub fn new(url: Option<String>, key: Arc<ManagedSecret>) -> Self {
+        Self {
+            client: http_client(),
+            url: url.unwrap_or_else(|| {
+                "https://translation.googleapis.com/language/translate/v2".into()
+            }),
+            key,
+        }
+    }
+}
+
+#[async_trait]
+impl Translator for GoogleTranslator {
+    fn name(&self) -> &str {
+        "google"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: Option<&str>,
+        target: &str,
+    ) -> Result<Translation> {
+        #[derive(Deserialize)]
+        struct Response {
+            data: Data,
+        }
+        #[derive(Deserialize)]
+        struct Data {
+            translations: Vec<Item>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Item {
+            translated_text: String,
+            detected_source_language: Option<String>,
+        }
+
+        let mut body = serde_json::json!({ "q": text, "target": target, "format": "text" });
+        if let Some(source) = source {
+            body["source"] = source.into();
+        }
+        let response: Response = self
+            .client
+            .post(&self.url)
+            .query(&[("key", self.key.get().await?)])
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("invalid Google Translate response")?;
+        let item = response
+            .data
+            .translations
+            .into_iter()
+            .next()
+            .context("Google Translate returned no translations")?;
+        Ok(Translation {
+            text: item.translated_text,
+            detected_source: item.detected_source_language,
+        })
+    }
+}
+
+// ── LibreTranslate ───────────────────────────────────────────────
+
+pub struct LibreTranslator {
+    client: reqwest::Client,
+    url: String,
+    key: Option<Arc<ManagedSecret>>,
+}
+
+impl LibreTranslator {
+    /// `url` is the server base, e.g. `http://localhost:5000`.
+    pub fn new(url: Option<String>, key: Option<Arc<ManagedSecret>>) -> Self {
+        let base = url.unwrap_or_else(|| "http://localhost:5000".into());
+        Self {
+            client: http_client(),
+            url: format!("{}/translate", base.trim_end_matches('/')),
+            key,
+        }
+    }
+}
+
+#[async_trait]
+impl Translator for LibreTranslator {
+    fn name(&self) -> &str {
+        "libretranslate"
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source: Option<&str>,
+        target: &str,
+    ) -> Result<Translation> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            translated_text: String,
+            detected_language: Option<Detected>,
+        }
+        #[derive(Deserialize)]
+        struct Detected {
+            language: String,
+        }
+
+        let mut body = serde_json::json!({
+            "q": text,
+            "source": source.map_or_else(|| "auto".to_string(), primary_language),
+            "target": primary_language(target),
+            "format": "text",
+        });
+        if let Some(key) = resolve_key(self.key.as_ref()).await? {
+            body["api_key"] = key.into();
+        }
+        let response: Response = self
+            .client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("invalid LibreTranslate response")?;
+        Ok(Translation {
+            text: response.translated_text,
+            detected_source: response.detected_language.map(|d| d.language),
+        })
+    }
+}
+
+/// Create the translator selected by `[middleware.translation] backend`.
+pub fn create_translator(config: &Config) -> Result<Arc<dyn Translator>> {
+    let settings = &config.middleware.translation;
+    let key = settings
+        .api_key
+        .as_deref()
+        .map(|raw| crate::channels::resolve_channel_secret(config, raw))
+        .transpose()?;
+    let url = settings.api_url.clone();
+    match settings.backend.as_str() {
+        "deepl" => Ok(Arc::new(DeepLTranslator::new(
+            url,
+            key.context("DeepL translation requires [middleware.translation] api_key")?,
+        ))),
+        "google" => Ok(Arc::new(GoogleTranslator::new(
+            url,
+            key.context("Google translation requires [middleware.translation] api_key")?,
+        ))),
+        "libretranslate" => Ok(Arc::new(LibreTranslator::new(url, key))),
+        other => anyhow::bail!("Unknown translation backend: {other}"),
+    }
+}
+
+pub struct TranslationMiddleware {
+    translator: Arc<dyn Translator>,
+    working_language: String,
+    /// Last detected language per `channel:sender`.
+    languages: Mutex<HashMap<String, String>>,
+}
+
+fn sender_key(channel: &str, sender: &str) -> String {
+    format!("{channel}:{sender}")
+}
+
+/// Commands and empty messages are passed through untranslated.
+fn should_translate(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty() && !text.starts_with('/')
+}
+
+impl TranslationMiddleware {
+    pub fn new(translator: Arc<dyn Translator>, config: &TranslationMiddlewareConfig) -> Self {
+        Self {
+            translator,
+            working_language: config.working_language.clone(),
+            languages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new(
+            create_translator(config)?,
+            &config.middleware.translation,
+        ))
+    }
+
+    /// Language last detected for a sender, if it differs from the working one.
+    pub fn sender_language(&self, channel: &str, sender: &str) -> Option<String> {
+        self.languages
+            .lock()
+            .get(&sender_key(channel, sender))
+            .cloned()
+    }
+
+    fn remember(&self, channel: &str, sender: &str, language: Option<String>) {
+        let mut languages = self.languages.lock();
+        let key = sender_key(channel, sender);
+        match language {
+            Some(language) => {
+                if languages.len() >= MAX_TRACKED_SENDERS && !languages.contains_key(&key) {
+                    languages.clear();
+                }
+                languages.insert(key, language);
+            }
+            None => {
+                languages.remove(&key);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl InboundMiddleware for TranslationMiddleware {
+    fn name(&self) -> &str {
+        "translation"
+    }
+
+    fn inspects_content(&self) -> bool {
+        true
+    }
+
+    async fn on_inbound(&self, mut msg: ChannelMessage) -> Result<InboundAction> {
+        if !should_translate(&msg.content) {
+            return Ok(InboundAction::Continue(msg));
+        }
+        match self
+            .translator
+            .translate(&msg.content, None, &self.working_language)
+            .await
+        {
+            Ok(translation) => {
+                let foreign = translation.detected_source.filter(|lang| {
+                    primary_language(lang) != primary_language(&self.working_language)
+                });
+                if foreign.is_some() {
+                    msg.content = translation.text;
+                }
+                self.remember(&msg.channel, &msg.sender, foreign);
+            }
+            Err(e) => tracing::warn!(
+                "Translation via {} failed; passing original: {e}",
+                self.translator.name()
+            ),
+        }
+        Ok(InboundAction::Continue(msg))
+    }
+}
+
+#[async_trait]
+impl OutboundMiddleware for TranslationMiddleware {
+    fn name(&self) -> &str {
+        "translation"
+    }
+
+    async fn on_outbound(&self, mut msg: OutboundMessage) -> Result<OutboundAction> {
+        let target = msg
+            .reply_to
+            .as_ref()
+            .and_then(|original| self.sender_language(&original.channel, &original.sender));
+        let Some(target) = target else {
+            return Ok(OutboundAction::Send(msg));
+        };
+        if !should_translate(&msg.content) {
+            return Ok(OutboundAction::Send(msg));
+        }
+        match self
+            .translator
+            .translate(&msg.content, Some(&self.working_language), &target)
+            .await
+        {
+            Ok(translation) => msg.content = translation.text,
+            Err(e) => tracing::warn!(
+                "Reply translation via {} failed; sending original: {e}",
+                self.translator.name()
+            ),
+        }
+        Ok(OutboundAction::Send(msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tags text with its target language; treats text containing CJK as `zh`.
+    struct FakeTranslator;
+
+    #[async_trait]
+    impl Translator for FakeTranslator {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        async fn translate(
+            &self,
+            text: &str,
+            source: Option<&str>,
+            target: &str,
+        ) -> Result<Translation> {
+            if text.contains("fail") {
+                anyhow::bail!("backend down");
+            }
+            let detected = source.map_or_else(
+                || {
+                    if text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)) {
+                        "zh".to_string()
+                    } else {
+                        "en".to_string()
+                    }
+                },
+                str::to_string,
+            );
+            Ok(Translation {
+                text: format!("[{target}] {text}"),
+                detected_source: Some(detected),
+            })
+        }
+    }
+
+    fn middleware() -> TranslationMiddleware {
+        TranslationMiddleware::new(
+            Arc::new(FakeTranslator),
+            &TranslationMiddlewareConfig::default(),
+        )
+    }
+
+    fn msg(sender: &str, content: &str) -> ChannelMessage {
+        ChannelMessage::test("qq", sender, content)
+    }
+
+    fn reply_to(original: &ChannelMessage, content: &str) -> OutboundMessage {
+        OutboundMessage {
+            channel: original.channel.clone(),
+            recipient: original.reply_target.clone(),
+            content: content.into(),
+            reply_to: Some(original.clone()),
+        }
+    }
+
+    #[tokio::test]
+    async fn foreign_messages_round_trip_through_working_language() {
+        let translation = middleware();
+        let original = msg("alice", "你好");
+
+        let InboundAction::Continue(inbound) =
+            translation.on_inbound(original.clone()).await.unwrap()
+        else {
+            panic!("expected continue");
+        };
+        assert_eq!(inbound.content, "[en] 你好");
+
+        let OutboundAction::Send(reply) = translation
+            .on_outbound(reply_to(&original, "hello"))
+            .await
+            .unwrap()
+        else {
+            panic!("expected send");
+        };
+        assert_eq!(reply.content, "[zh] hello");
+    }
+
+    #[tokio::test]
+    async fn working_language_messages_and_commands_are_untouched() {
+        let translation = middleware();
+        let original = msg("bob", "hello");
+
+        let InboundAction::Continue(inbound) =
+            translation.on_inbound(original.clone()).await.unwrap()
+        else {
+            panic!("expected continue");
+        };
+        assert_eq!(inbound.content, "hello");
+        assert_eq!(translation.sender_language("qq", "bob"), None);
+
+        let OutboundAction::Send(reply) = translation
+            .on_outbound(reply_to(&original, "hi bob"))
+            .await
+            .unwrap()
+        else {
+            panic!("expected send");
+        };
+        assert_eq!(reply.content, "hi bob");
+
+        let InboundAction::Continue(command) =
+            translation.on_inbound(msg("bob", "/状态")).await.unwrap()
+        else {
+            panic!("expected continue");
+        };
+        assert_eq!(command.content, "/状态");
+    }
+
+    #[tokio::test]
+    async fn backend_failure_passes_original_text() {
+        let translation = middleware();
+        let InboundAction::Continue(inbound) = translation
+            .on_inbound(msg("carol", "fail 你好"))
+            .await
+            .unwrap()
+        else {
+            panic!("expected continue");
+        };
+        assert_eq!(inbound.content, "fail 你好");
+    }
+
+    #[tokio::test]
+    async fn libretranslate_backend_parses_detected_language() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/translate",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                assert_eq!(body["source"], "auto");
+                Json(serde_json::json!({
+                    "translatedText": format!("{}!", body["q"].as_str().unwrap()),
+                    "detectedLanguage": { "language": "zh", "confidence": 90.0 }
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let translator = LibreTranslator::new(Some(format!("http://{addr}/")), None);
+        let translation = translator.translate("你好", None, "en").await.unwrap();
+        assert_eq!(translation.text, "你好!");
+        assert_eq!(translation.detected_source.as_deref(), Some("zh"));
+    }
+
+    #[test]
+    fn primary_language_normalizes_tags() {
+        assert_eq!(primary_language("EN-US"), "en");
+        assert_eq!(primary_language("zh_CN"), "zh");
+        assert_eq!(primary_language("ja"), "ja");
+    }
+}
@@ -1,16 +1,60 @@
+pub mod admin;
+pub mod admin_commands;
+pub mod announce;
+pub mod batch;
+pub mod bus;
 pub mod cli;
+pub mod control;
+pub mod dashboard;
+pub mod dedup;
+pub mod digest;
 pub mod dingtalk;
 pub mod discord;
+pub mod dispatch;
+pub mod dry_run;
 pub mod email_channel;
+pub mod erasure;
+pub mod event_stream;
+pub mod event_webhooks;
+pub mod export;
+pub mod failover;
+pub mod frame_tap;
+pub mod gateway;
+pub mod gateway_state;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+pub mod hooks;
+pub mod http;
 pub mod imessage;
+pub mod inbound_queue;
+pub mod interactions;
 pub mod irc;
+pub mod lanes;
 pub mod lark;
+pub mod local_socket;
+pub mod manager;
 pub mod matrix;
+pub mod mentions;
+pub mod middleware;
+pub mod net;
+#[cfg(feature = "channel-nostr")]
+pub mod nostr;
+pub mod pipe;
+pub mod polls;
+pub mod priority;
 pub mod qq;
+pub mod quiet_hours;
+pub mod receipts;
+pub mod sessions;
+pub mod shadow;
 pub mod signal;
 pub mod slack;
+pub mod streaming;
 pub mod telegram;
+pub mod tls;
 pub mod traits;
+pub mod uploads;
 pub mod whatsapp;
 
 pub use cli::CliChannel;
@@ -20,27 +64,50 @@ pub use email_channel::EmailChannel;
 pub use imessage::IMessageChannel;
 pub use irc::IrcChannel;
 pub use lark::LarkChannel;
+pub use local_socket::LocalSocketChannel;
+#[allow(unused_imports)]
+pub use manager::{BroadcastTarget, ChannelManager};
 pub use matrix::MatrixChannel;
-pub use qq::QQChannel;
+#[cfg(feature = "channel-nostr")]
+pub use nostr::NostrChannel;
+pub use pipe::PipeChannel;
+pub use qq::{QQChannel, QQEnvironment};
 pub use signal::SignalChannel;
 pub use slack::SlackChannel;
 pub use telegram::TelegramChannel;
 pub use traits::Channel;
 pub use whatsapp::WhatsAppChannel;
 
-use crate::agent::loop_::{build_tool_instructions, run_tool_call_loop};
-use crate::config::Config;
+/// Startup and status lines of the channel runtime: stdout, or stderr in
+/// pipe mode, where stdout carries outbound messages.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if pipe::is_active() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+use crate::agent::loop_::{build_tool_instructions, run_tool_call_loop_streaming};
+use crate::config::{BusRole, Config};
 use crate::identity;
 use crate::memory::{self, Memory};
-use crate::observability::{self, Observer};
+use crate::observability::{self, message_trace, Observer};
+use crate::plugins::{InboundOutcome, PluginRegistry};
 use crate::providers::{self, ChatMessage, Provider};
 use crate::runtime;
-use crate::security::SecurityPolicy;
+use crate::security::redact::redact_error;
+use crate::security::{resolve_secret, AuditLogger, ManagedSecret, SecurityPolicy};
 use crate::tools::{self, Tool};
 use crate::util::truncate_with_ellipsis;
 use anyhow::{Context, Result};
+use middleware::{InboundAction, MiddlewareStack, OutboundAction, OutboundMessage};
+use opentelemetry::KeyValue;
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::panic::AssertUnwindSafe;
 use std::path::PathBuf;
 use std::process::Command;
 use std::sync::Arc;
@@ -51,12 +118,6 @@ const BOOTSTRAP_MAX_CHARS: usize = 20_000;
 
 const DEFAULT_CHANNEL_INITIAL_BACKOFF_SECS: u64 = 2;
 const DEFAULT_CHANNEL_MAX_BACKOFF_SECS: u64 = 60;
-/// Timeout for processing a single channel message (LLM + tools).
-/// 300s for on-device LLMs (Ollama) which are slower than cloud APIs.
-const CHANNEL_MESSAGE_TIMEOUT_SECS: u64 = 300;
-const CHANNEL_PARALLELISM_PER_CHANNEL: usize = 4;
-const CHANNEL_MIN_IN_FLIGHT_MESSAGES: usize = 8;
-const CHANNEL_MAX_IN_FLIGHT_MESSAGES: usize = 64;
 
 #[derive(Clone)]
 struct ChannelRuntimeContext {
@@ -67,27 +128,73 @@ struct ChannelRuntimeContext {
     observer: Arc<dyn Observer>,
     system_prompt: Arc<String>,
     model: Arc<String>,
+    /// Replacements of `model` by channel name
+    channel_models: Arc<HashMap<String, String>>,
     temperature: f64,
     auto_save_memory: bool,
+    control: Arc<control::ChannelControl>,
+    plugins: Arc<PluginRegistry>,
+    middleware: Arc<MiddlewareStack>,
+    identities: Option<Arc<crate::identity::linking::IdentityStore>>,
+    failover: Option<Arc<failover::FailoverRouter>>,
+    quotas: Option<Arc<crate::cost::QuotaLedger>>,
+    sessions: Arc<sessions::SessionStore>,
+    transcription: Option<Arc<crate::transcription::TranscriptionService>>,
+    tts: Option<Arc<crate::tts::TtsService>>,
+    vision: Option<Arc<crate::vision::VisionService>>,
+    image_generator: Option<Arc<dyn crate::imagegen::ImageGenerator>>,
+    knowledge: Option<Arc<crate::knowledge::KnowledgeBase>>,
+    web_search: Option<Arc<dyn crate::websearch::SearchBackend>>,
+    web_search_max_results: usize,
+    streaming: crate::config::StreamingConfig,
+    /// Time the model and tools get to answer one message
+    llm_timeout: Duration,
+    alert_silencer: Option<Arc<crate::ingress::alertmanager::AlertSilencer>>,
+    topics: Option<Arc<crate::ingress::notify::TopicSubscriptions>>,
+    preferences: Option<Arc<crate::preferences::PreferenceStore>>,
+    reminders: Option<Arc<crate::reminders::ReminderStore>>,
+    groups: Option<Arc<crate::groups::GroupStore>>,
+    personas: Option<Arc<crate::personas::PersonaStore>>,
+    response_cache: Option<Arc<crate::memory::ResponseCache>>,
+    announcements: Option<Arc<announce::Announcements>>,
+}
+
+impl ChannelRuntimeContext {
+    /// Model answering messages on `channel`.
+    fn model_for(&self, channel: &str) -> &str {
+        self.channel_models
+            .get(channel)
+            .map_or(self.model.as_str(), String::as_str)
+    }
 }
 
 fn conversation_memory_key(msg: &traits::ChannelMessage) -> String {
     format!("{}_{}_{}", msg.channel, msg.sender, msg.id)
 }
 
-fn channel_delivery_instructions(channel_name: &str) -> Option<&'static str> {
-    match channel_name {
-        "telegram" => Some(
-            "When responding on Telegram, include media markers for files or URLs that should be sent as attachments. Use one marker per attachment with this exact syntax: [IMAGE:<path-or-url>], [DOCUMENT:<path-or-url>], [VIDEO:<path-or-url>], [AUDIO:<path-or-url>], or [VOICE:<path-or-url>]. Keep normal user-facing text outside markers and never wrap markers in code fences.",
-        ),
-        _ => None,
+/// Told to the model on channels that show Markdown as typed.
+const PLAIN_TEXT_INSTRUCTIONS: &str = "This chat shows plain text only: don't use Markdown formatting such as **bold**, headings, tables or code fences.";
+
+/// Told to the model on channels that turn media markers into attachments.
+const MEDIA_MARKER_INSTRUCTIONS: &str = "When responding, include media markers for files or URLs that should be sent as attachments. Use one marker per attachment with this exact syntax: [IMAGE:<path-or-url>], [DOCUMENT:<path-or-url>], [VIDEO:<path-or-url>], [AUDIO:<path-or-url>], or [VOICE:<path-or-url>]. Keep normal user-facing text outside markers and never wrap markers in code fences.";
+
+/// Formatting instructions for replies on a channel with `capabilities`.
+fn channel_delivery_instructions(capabilities: traits::ChannelCapabilities) -> Vec<&'static str> {
+    let mut instructions = Vec::new();
+    if capabilities.supports_media_markers {
+        instructions.push(MEDIA_MARKER_INSTRUCTIONS);
+    }
+    if !capabilities.supports_markdown {
+        instructions.push(PLAIN_TEXT_INSTRUCTIONS);
     }
+    instructions
 }
 
-async fn build_memory_context(mem: &dyn Memory, user_msg: &str) -> String {
+/// `session` scopes recall to one user profile when identity linking is on.
+async fn build_memory_context(mem: &dyn Memory, user_msg: &str, session: Option<&str>) -> String {
     let mut context = String::new();
 
-    if let Ok(entries) = mem.recall(user_msg, 5, None).await {
+    if let Ok(entries) = mem.recall(user_msg, 5, session).await {
         if !entries.is_empty() {
             context.push_str("[Memory context]\n");
             for entry in &entries {
@@ -100,9 +207,36 @@ async fn build_memory_context(mem: &dyn Memory, user_msg: &str) -> String {
     context
 }
 
+/// Resolve a channel credential that may be an `env:`/`file:`/`vault:`/`keyring:`
+/// reference into a managed, periodically refreshed secret.
+pub(crate) fn resolve_channel_secret(config: &Config, raw: &str) -> Result<Arc<ManagedSecret>> {
+    let zeroclaw_dir = config
+        .config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), std::path::Path::to_path_buf);
+    Ok(Arc::new(resolve_secret(
+        raw,
+        &config.secrets,
+        &zeroclaw_dir,
+    )?))
+}
+
+/// Tokens of the OAuth client a Slack config signs in with, if any.
+pub(crate) fn slack_oauth(
+    config: &Config,
+    slack: &crate::config::schema::SlackConfig,
+) -> Result<Option<Arc<http::token::TokenCache<crate::oauth::OAuthTokenSource>>>> {
+    slack
+        .oauth
+        .as_deref()
+        .map(|name| crate::oauth::token_cache(config, name))
+        .transpose()
+}
+
 fn spawn_supervised_listener(
     ch: Arc<dyn Channel>,
     tx: tokio::sync::mpsc::Sender<traits::ChannelMessage>,
+    control: Arc<control::ChannelControl>,
     initial_backoff_secs: u64,
     max_backoff_secs: u64,
 ) -> tokio::task::JoinHandle<()> {
@@ -113,8 +247,24 @@ fn spawn_supervised_listener(
 
         loop {
             crate::health::mark_component_ok(&component);
-            let result = ch.listen(tx.clone()).await;
+            hooks::emit(hooks::LifecycleEvent::ChannelConnected {
+                channel: ch.name().to_string(),
+            });
+            // A panic inside `listen` is caught here so it neither takes the
+            // process down nor silently ends this channel.
+            let result = futures::FutureExt::catch_unwind(AssertUnwindSafe(ch.listen(tx.clone())))
+                .await
+                .unwrap_or_else(|panic| {
+                    Err(anyhow::anyhow!(
+                        "panicked: {}",
+                        panic_message(panic.as_ref())
+                    ))
+                });
 
+            hooks::emit(hooks::LifecycleEvent::ChannelDisconnected {
+                channel: ch.name().to_string(),
+                error: result.as_ref().err().map(redact_error),
+            });
             if tx.is_closed() {
                 break;
             }
@@ -123,12 +273,22 @@ fn spawn_supervised_listener(
                 Ok(()) => {
                     tracing::warn!("Channel {} exited unexpectedly; restarting", ch.name());
                     crate::health::mark_component_error(&component, "listener exited unexpectedly");
+                    control.record_listener_error(ch.name(), "listener exited unexpectedly");
                     // Clean exit — reset backoff since the listener ran successfully
                     backoff = initial_backoff_secs.max(1);
                 }
+                // The server refused the bot for good; reconnecting would
+                // only be refused again.
+                Err(e) if e.downcast_ref::<gateway::GatewayClosed>().is_some() => {
+                    tracing::error!("Channel {} stopped: {e}", ch.name());
+                    crate::health::mark_component_failed(&component, e.to_string());
+                    control.record_listener_error(ch.name(), &e);
+                    break;
+                }
                 Err(e) => {
                     tracing::error!("Channel {} error: {e}; restarting", ch.name());
                     crate::health::mark_component_error(&component, e.to_string());
+                    control.record_listener_error(ch.name(), &e);
                 }
             }
 
@@ -140,13 +300,13 @@ fn spawn_supervised_listener(
     })
 }
 
-fn compute_max_in_flight_messages(channel_count: usize) -> usize {
-    channel_count
-        .saturating_mul(CHANNEL_PARALLELISM_PER_CHANNEL)
-        .clamp(
-            CHANNEL_MIN_IN_FLIGHT_MESSAGES,
-            CHANNEL_MAX_IN_FLIGHT_MESSAGES,
-        )
+/// Text of a caught panic payload.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| (*s).to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
 }
 
 fn log_worker_join_result(result: Result<(), tokio::task::JoinError>) {
@@ -155,17 +315,514 @@ fn log_worker_join_result(result: Result<(), tokio::task::JoinError>) {
     }
 }
 
+/// Run a reply to `reply_to` through the outbound middleware, then send it,
+/// or hold it in the outbox while the channel is muted. Send failures are
+/// recorded for the admin API.
+async fn deliver_reply(
+    ctx: &ChannelRuntimeContext,
+    channel: &dyn Channel,
+    message: &str,
+    reply_to: &traits::ChannelMessage,
+) -> Result<()> {
+    deliver_reply_as(ctx, channel, message, reply_to, false).await
+}
+
+/// [`deliver_reply`], optionally spoken as a voice message. Falls back to
+/// text if synthesis or the upload fails.
+async fn deliver_reply_as(
+    ctx: &ChannelRuntimeContext,
+    channel: &dyn Channel,
+    message: &str,
+    reply_to: &traits::ChannelMessage,
+    speak: bool,
+) -> Result<()> {
+    message_trace::in_span_result(
+        "channel.send",
+        vec![
+            KeyValue::new("channel", channel.name().to_string()),
+            KeyValue::new("recipient", reply_to.reply_target.clone()),
+        ],
+        send_reply(ctx, channel, message, reply_to, speak),
+    )
+    .await
+}
+
+async fn send_reply(
+    ctx: &ChannelRuntimeContext,
+    channel: &dyn Channel,
+    message: &str,
+    reply_to: &traits::ChannelMessage,
+    speak: bool,
+) -> Result<()> {
+    let outbound = OutboundMessage {
+        channel: channel.name().to_string(),
+        recipient: reply_to.reply_target.clone(),
+        content: message.to_string(),
+        reply_to: Some(reply_to.clone()),
+    };
+    let outbound = match ctx.middleware.run_outbound(outbound).await {
+        OutboundAction::Send(outbound) => outbound,
+        OutboundAction::Drop { reason } => {
+            tracing::debug!(
+                "Reply on {} dropped by middleware ({reason})",
+                channel.name()
+            );
+            return Ok(());
+        }
+    };
+
+    if ctx.control.is_muted(channel.name()) {
+        tracing::debug!("Channel {} is muted; holding reply", channel.name());
+        ctx.control
+            .hold(channel.name(), &outbound.recipient, &outbound.content);
+        return Ok(());
+    }
+    if let (true, Some(tts)) = (speak, &ctx.tts) {
+        match tts.synthesize(&outbound.content).await {
+            Ok(audio) => match channel
+                .send_voice_note(audio.bytes, &audio.file_name, &outbound.recipient)
+                .await
+            {
+                Ok(()) => {
+                    ctx.control.publish_outbound(
+                        channel.name(),
+                        &outbound.recipient,
+                        &outbound.content,
+                        Some(&reply_to.id),
+                    );
+                    return Ok(());
+                }
+                Err(e) => tracing::warn!("Voice reply on {} failed: {e}", channel.name()),
+            },
+            Err(e) => tracing::warn!("Speech synthesis failed: {e}"),
+        }
+    }
+    let Some(failover) = &ctx.failover else {
+        let result = crate::circuit::call(
+            &format!("send:{}", channel.name()),
+            channel.send(&outbound.content, &outbound.recipient),
+        )
+        .await;
+        return hooks::track_send(channel.name(), &outbound.recipient, result)
+            .inspect(|()| {
+                ctx.control.publish_outbound(
+                    channel.name(),
+                    &outbound.recipient,
+                    &outbound.content,
+                    Some(&reply_to.id),
+                );
+            })
+            .inspect_err(|e| {
+                ctx.control.record_error(channel.name(), e);
+            });
+    };
+    let send = |name: String, recipient: String| {
+        let target = ctx.channels_by_name.get(&name).cloned();
+        let content = outbound.content.clone();
+        let control = Arc::clone(&ctx.control);
+        let in_reply_to = reply_to.id.clone();
+        async move {
+            let target = target.ok_or_else(|| anyhow::anyhow!("Channel {name} is not running"))?;
+            let result =
+                crate::circuit::call(&format!("send:{name}"), target.send(&content, &recipient))
+                    .await;
+            hooks::track_send(&name, &recipient, result)
+                .inspect(|()| {
+                    control.publish_outbound(&name, &recipient, &content, Some(&in_reply_to));
+                })
+                .inspect_err(|e| control.record_error(&name, e))
+        }
+    };
+    failover
+        .deliver(channel.name(), &reply_to.sender, &outbound.recipient, send)
+        .await
+}
+
+/// Answer `msg` on the channel it came from. A failed send is logged; the
+/// caller is done with the message either way.
+async fn answer(ctx: &ChannelRuntimeContext, msg: &traits::ChannelMessage, reply: &str) {
+    let Some(channel) = ctx.channels_by_name.get(&msg.channel) else {
+        return;
+    };
+    if let Err(e) = deliver_reply(ctx, channel.as_ref(), reply, msg).await {
+        tracing::warn!("Failed to reply on {}: {e}", channel.name());
+    }
+}
+
+/// Pass text transcribed or described from attachments through the content
+/// middleware (moderation, translation), which only saw the message before
+/// that text existed. `None` when the message was dropped or answered.
+async fn screen_media_text(
+    ctx: &ChannelRuntimeContext,
+    msg: traits::ChannelMessage,
+) -> Option<traits::ChannelMessage> {
+    match ctx.middleware.run_inbound_content(msg.clone()).await {
+        InboundAction::Continue(msg) => Some(msg),
+        InboundAction::Drop { reason } => {
+            tracing::debug!(
+                "Middleware dropped message {} on {} ({reason})",
+                msg.id,
+                msg.channel
+            );
+            None
+        }
+        InboundAction::Reply(reply) => {
+            answer(ctx, &msg, &reply).await;
+            None
+        }
+    }
+}
+
 async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::ChannelMessage) {
+    let voice_in = msg
+        .attachments
+        .iter()
+        .any(|a| matches!(a.kind, traits::MediaKind::Voice | traits::MediaKind::Audio));
+    if ctx.control.is_blocked(&msg.channel, msg.author_id()) {
+        tracing::debug!(
+            "Ignoring message {} from blocked user {} on {}",
+            msg.id,
+            msg.author_id(),
+            msg.channel
+        );
+        return;
+    }
+    // Attachments may still turn into text once the message is admitted
+    if msg.content.trim().is_empty() && msg.attachments.is_empty() {
+        tracing::debug!(
+            "Ignoring message {} on {} without text",
+            msg.id,
+            msg.channel
+        );
+        return;
+    }
+
+    let inbound = message_trace::in_span(
+        "middleware.inbound",
+        Vec::new(),
+        ctx.middleware.run_inbound(msg.clone()),
+    )
+    .await;
+    let msg = match inbound {
+        InboundAction::Continue(msg) => msg,
+        InboundAction::Drop { reason } => {
+            tracing::debug!(
+                "Middleware dropped message {} on {} ({reason})",
+                msg.id,
+                msg.channel
+            );
+            return;
+        }
+        InboundAction::Reply(reply) => {
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
+    };
+
+    let mut msg = match ctx.plugins.process_inbound(msg.clone()).await {
+        InboundOutcome::Continue(msg) => msg,
+        InboundOutcome::Dropped { plugin } => {
+            tracing::debug!(
+                "Plugin {plugin} dropped message {} on {}",
+                msg.id,
+                msg.channel
+            );
+            return;
+        }
+        InboundOutcome::Reply { plugin, reply } => {
+            tracing::debug!(
+                "Plugin {plugin} answered message {} on {}",
+                msg.id,
+                msg.channel
+            );
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
+    };
+
     println!(
         "  💬 [{}] from {}: {}",
         msg.channel,
         msg.sender,
         truncate_with_ellipsis(&msg.content, 80)
     );
+    // Shadow turns leave no trace in memory, quotas or analytics
+    let is_shadow = ctx
+        .control
+        .shadows()
+        .is_some_and(|shadows| shadows.is_shadow(&msg.channel));
+    if let Some(analytics) = ctx.control.analytics().filter(|_| !is_shadow) {
+        analytics.record_message(&msg, chrono::Utc::now());
+    }
+
+    let group = match (&ctx.groups, ctx.channels_by_name.get(&msg.channel)) {
+        (Some(groups), Some(channel)) => {
+            let group = groups.for_message(channel.as_ref(), &msg);
+            if let Some(content) = group.normalize_command(&msg.content) {
+                msg.content = content;
+            }
+            let reply = match groups.handle_command(channel.as_ref(), &msg).await {
+                Some(reply) => Some(reply),
+                None => group
+                    .blocked_command(&msg.content)
+                    .map(|command| format!("⚠️ /{command} is disabled in this group")),
+            };
+            if let Some(reply) = reply {
+                answer(&ctx, &msg, &reply).await;
+                return;
+            }
+            group
+        }
+        _ => crate::groups::GroupSettings::default(),
+    };
+
+    let mut profile = None;
+    if let Some(identities) = &ctx.identities {
+        if let Some(reply) = identities.handle_command(&msg.channel, msg.author_id(), &msg.content)
+        {
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
+        match identities.profile_for(&msg.channel, msg.author_id()) {
+            Ok(id) => profile = Some(id),
+            Err(e) => tracing::warn!("Failed to resolve profile for {}: {e}", msg.author_id()),
+        }
+    }
+    // The profile when identity linking is on, so settings follow the person
+    let user_key = profile
+        .clone()
+        .unwrap_or_else(|| format!("{}:{}", msg.channel, msg.author_id()));
+
+    let conversation = ctx.channels_by_name.get(&msg.channel).map_or_else(
+        || format!("{}:{}", msg.conversation(), msg.author_id()),
+        |channel| channel.conversation_key(&msg),
+    );
+    let session_key = format!("{}:{conversation}", msg.channel);
+    let command_reply = ctx
+        .sessions
+        .handle_command(&session_key, &msg.content)
+        .or_else(|| {
+            ctx.tts
+                .as_ref()
+                .and_then(|tts| tts.handle_command(&msg.channel, &msg.sender, &msg.content))
+        })
+        .or_else(|| {
+            ctx.preferences.as_ref().and_then(|preferences| {
+                preferences.handle_command(&msg.channel, &user_key, &msg.content)
+            })
+        })
+        .or_else(|| {
+            let reminders = ctx.reminders.as_ref()?;
+            let timezone = ctx
+                .preferences
+                .as_ref()
+                .map_or(chrono_tz::Tz::UTC, |preferences| {
+                    preferences.resolve(&msg.channel, &user_key).timezone
+                });
+            reminders.handle_command(
+                &msg.channel,
+                &msg.reply_target,
+                &user_key,
+                timezone,
+                &msg.content,
+            )
+        })
+        .or_else(|| {
+            ctx.personas
+                .as_ref()
+                .and_then(|personas| personas.handle_command(&msg.channel, &user_key, &msg.content))
+        });
+    if let Some(reply) = command_reply {
+        answer(&ctx, &msg, &reply).await;
+        return;
+    }
+
+    if let Some(format) = export::export_command(&msg.content) {
+        if let Some(channel) = ctx.channels_by_name.get(&msg.channel) {
+            let reply = match format {
+                Ok(format) => {
+                    let session = ctx.sessions.current(&session_key);
+                    export::send_export(channel.as_ref(), &msg, session, format).await
+                }
+                Err(usage) => Some(usage),
+            };
+            if let Some(reply) = reply {
+                answer(&ctx, &msg, &reply).await;
+            }
+        }
+        return;
+    }
+
+    if let Some(channel) = ctx.channels_by_name.get(&msg.channel) {
+        let archive = ctx.control.archive();
+        if let Some(reply) =
+            history::handle_command(archive.as_deref(), channel.as_ref(), &msg).await
+        {
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
+    }
+
+    if let Some(eraser) = ctx.control.eraser() {
+        if let Some(reply) = erasure::handle_command(&eraser, &msg).await {
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
+    }
+
+    if let Some(reply) = ctx
+        .topics
+        .as_ref()
+        .and_then(|topics| topics.handle_command(&msg.channel, &msg.reply_target, &msg.content))
+    {
+        answer(&ctx, &msg, &reply).await;
+        return;
+    }
+
+    if let Some(silencer) = &ctx.alert_silencer {
+        if let Some(reply) = silencer
+            .handle_command(&msg.channel, &msg.reply_target, &msg.sender, &msg.content)
+            .await
+        {
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
+    }
+
+    if let Some(reply) = admin_commands::handle_command(&ctx.control, &msg).await {
+        answer(&ctx, &msg, &reply).await;
+        return;
+    }
+
+    if let (Some(announcements), Some(channel)) =
+        (&ctx.announcements, ctx.channels_by_name.get(&msg.channel))
+    {
+        match announcements.handle_command(channel.as_ref(), &msg) {
+            Some(announce::AnnounceReply::Text(text)) => {
+                answer(&ctx, &msg, &text).await;
+                return;
+            }
+            Some(announce::AnnounceReply::Preview { text, keyboard }) => {
+                if let Err(e) = channel
+                    .send_buttons(&text, &keyboard, &msg.reply_target)
+                    .await
+                {
+                    tracing::warn!("Failed to reply on {}: {e}", channel.name());
+                }
+                return;
+            }
+            None => {}
+        }
+    }
+
+    if let Some(quotas) = &ctx.quotas {
+        match quotas.check(&user_key, &msg.channel, msg.author_id()) {
+            Ok(crate::cost::QuotaCheck::Allowed) => {}
+            Ok(exceeded) => {
+                tracing::info!(
+                    "Quota reached for {} on {}: {exceeded}",
+                    msg.sender,
+                    msg.channel
+                );
+                answer(&ctx, &msg, &quotas.exceeded_message(&exceeded)).await;
+                return;
+            }
+            // Fail open: a broken ledger must not silence every channel.
+            Err(e) => tracing::warn!("Quota check failed: {e}"),
+        }
+    }
+
+    // Media is stored, and paid speech-to-text and vision run, only for
+    // messages that got past the block list, middleware and quotas
+    if let (Some(store), Some(channel)) = (
+        crate::media_store::global().filter(|store| store.persist_inbound()),
+        ctx.channels_by_name.get(&msg.channel),
+    ) {
+        if !msg.attachments.is_empty() {
+            store.persist_attachments(channel.as_ref(), &mut msg).await;
+        }
+    }
+    let text_before_media = msg.content.clone();
+    if let (Some(transcription), Some(channel)) =
+        (&ctx.transcription, ctx.channels_by_name.get(&msg.channel))
+    {
+        let transcribed = transcription
+            .transcribe_message(channel.as_ref(), &mut msg)
+            .await;
+        if !transcribed && msg.content.trim().is_empty() {
+            let reply = "⚠️ Sorry, I couldn't transcribe that voice message.";
+            answer(&ctx, &msg, reply).await;
+            return;
+        }
+    }
+    if let (Some(vision), Some(channel)) = (&ctx.vision, ctx.channels_by_name.get(&msg.channel)) {
+        let described = vision.describe_message(channel.as_ref(), &mut msg).await;
+        if !described && msg.content.trim().is_empty() {
+            let reply = "⚠️ Sorry, I couldn't make out that image.";
+            answer(&ctx, &msg, reply).await;
+            return;
+        }
+    }
+    if msg.content != text_before_media {
+        let Some(screened) = screen_media_text(&ctx, msg).await else {
+            return;
+        };
+        msg = screened;
+    }
+    if msg.content.trim().is_empty() {
+        tracing::debug!(
+            "Ignoring message {} on {} without text",
+            msg.id,
+            msg.channel
+        );
+        return;
+    }
 
-    let memory_context = build_memory_context(ctx.memory.as_ref(), &msg.content).await;
+    if let (Some(generator), Some(prompt)) = (&ctx.image_generator, imagine_prompt(&msg.content)) {
+        if let Some(channel) = ctx.channels_by_name.get(&msg.channel) {
+            reply_with_generated_image(&ctx, generator.as_ref(), channel.as_ref(), &msg, prompt)
+                .await;
+        }
+        return;
+    }
 
-    if ctx.auto_save_memory {
+    if let (Some(backend), Some(query)) = (
+        &ctx.web_search,
+        crate::websearch::search_command_query(&msg.content),
+    ) {
+        let reply = if query.is_empty() {
+            "Usage: /search <query>".to_string()
+        } else {
+            match backend.search(query, ctx.web_search_max_results).await {
+                Ok(results) => crate::websearch::summarize_results(query, &results),
+                Err(e) => format!("⚠️ Search failed: {}", redact_error(&e)),
+            }
+        };
+        answer(&ctx, &msg, &reply).await;
+        return;
+    }
+
+    let no_cache = match nocache_question(&msg.content) {
+        Some(question) => {
+            msg.content = question.to_string();
+            true
+        }
+        None => false,
+    };
+
+    let memory_context =
+        build_memory_context(ctx.memory.as_ref(), &msg.content, profile.as_deref()).await;
+    let knowledge = match &ctx.knowledge {
+        Some(kb) => kb.retrieve(&msg.content).await.unwrap_or_else(|e| {
+            tracing::warn!("Knowledge retrieval failed: {e}");
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+
+    if ctx.auto_save_memory && !is_shadow {
+        // Stored under the user key so erasure and history search can tell
+        // whose message it is; the key alone can't (`alice`, `alice_smith`)
         let autosave_key = conversation_memory_key(&msg);
         let _ = ctx
             .memory
@@ -173,53 +830,160 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
                 &autosave_key,
                 &msg.content,
                 crate::memory::MemoryCategory::Conversation,
-                None,
+                Some(user_key.as_str()),
             )
             .await;
     }
 
-    let enriched_message = if memory_context.is_empty() {
-        msg.content.clone()
-    } else {
-        format!("{memory_context}{}", msg.content)
-    };
+    let enriched_message = format!(
+        "{memory_context}{}{}",
+        crate::knowledge::context_block(&knowledge),
+        msg.content
+    );
 
     let target_channel = ctx.channels_by_name.get(&msg.channel).cloned();
-
-    if let Some(channel) = target_channel.as_ref() {
-        if let Err(e) = channel.start_typing(&msg.reply_target).await {
-            tracing::debug!("Failed to start typing on {}: {e}", channel.name());
-        }
-    }
+    let capabilities = target_channel
+        .as_ref()
+        .map(|channel| channel.capabilities());
+    let typing_channel = target_channel
+        .as_ref()
+        .filter(|_| capabilities.is_some_and(|caps| caps.supports_typing));
 
     println!("  ⏳ Processing message...");
     let started_at = Instant::now();
 
-    let mut history = vec![
-        ChatMessage::system(ctx.system_prompt.as_str()),
-        ChatMessage::user(&enriched_message),
-    ];
-
-    if let Some(instructions) = channel_delivery_instructions(&msg.channel) {
+    let session = ctx.sessions.begin(&session_key);
+    tracing::debug!("Message {} is in session {}", msg.id, session.id);
+    // Only self-contained questions share cached replies
+    let cacheable =
+        session.history.is_empty() && memory_context.is_empty() && msg.attachments.is_empty();
+    // What besides the question shapes the reply, for the cache key
+    let mut reply_context = Vec::new();
+    let mut history = vec![ChatMessage::system(ctx.system_prompt.as_str())];
+    history.extend(session.history);
+    history.push(ChatMessage::user(&enriched_message));
+
+    for instructions in capabilities
+        .map(channel_delivery_instructions)
+        .unwrap_or_default()
+    {
         history.push(ChatMessage::system(instructions));
+        reply_context.push(instructions.to_string());
+    }
+    if let Some(preferences) = &ctx.preferences {
+        let mut resolved = preferences.resolve(&msg.channel, &user_key);
+        // The group's language wins over its members' own
+        if group.language.is_some() {
+            resolved.language.clone_from(&group.language);
+        }
+        history.push(ChatMessage::system(
+            resolved.agent_instructions(chrono::Utc::now()),
+        ));
+        reply_context.push(resolved.language.clone().unwrap_or_default());
+        reply_context.push(format!("{:?}", resolved.verbosity));
+    } else if let Some(language) = &group.language {
+        history.push(ChatMessage::system(format!(
+            "Always reply in this language: {language}."
+        )));
+        reply_context.push(language.clone());
+    }
+    if let Some(personas) = &ctx.personas {
+        let vars = crate::personas::PersonaVars {
+            user: msg.sender_name.as_deref().unwrap_or(&msg.sender),
+            user_id: msg.author_id(),
+            channel: &msg.channel,
+        };
+        if let Some(prompt) = personas.prompt(&user_key, &vars) {
+            reply_context.push(prompt.clone());
+            history.push(ChatMessage::system(prompt));
+        }
+    }
+    // After the persona so a group's own instructions take precedence
+    if let Some(persona) = &group.persona {
+        reply_context.push(persona.clone());
+        history.push(ChatMessage::system(persona.as_str()));
+    }
+    let prompt_len = history.len();
+
+    let model = ctx.model_for(&msg.channel);
+    let cache_key = ctx
+        .response_cache
+        .as_ref()
+        .filter(|_| cacheable && !is_shadow)
+        .map(|cache| {
+            let context = format!("{}\n{}", ctx.system_prompt, reply_context.join("\n"));
+            let key = crate::memory::ResponseCache::cache_key(
+                model,
+                Some(&context),
+                &crate::memory::response_cache::normalize_prompt(&msg.content),
+            );
+            (cache, key)
+        });
+    if let Some((cache, key)) = cache_key.as_ref().filter(|_| !no_cache) {
+        let cached = cache.get(key).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read the response cache: {e}");
+            None
+        });
+        if let Some(reply) = cached {
+            println!("  💾 Cached reply: {}", truncate_with_ellipsis(&reply, 80));
+            ctx.sessions.append(&session_key, &msg.content, &reply);
+            answer(&ctx, &msg, &reply).await;
+            return;
+        }
     }
 
-    let llm_result = tokio::time::timeout(
-        Duration::from_secs(CHANNEL_MESSAGE_TIMEOUT_SECS),
-        run_tool_call_loop(
-            ctx.provider.as_ref(),
-            &mut history,
-            ctx.tools_registry.as_ref(),
-            ctx.observer.as_ref(),
-            "channel-runtime",
-            ctx.model.as_str(),
-            ctx.temperature,
-            true, // silent — channels don't write to stdout
-        ),
-    )
-    .await;
+    if let Some(channel) = typing_channel {
+        if let Err(e) = channel.start_typing(&msg.reply_target).await {
+            tracing::debug!("Failed to start typing on {}: {e}", channel.name());
+        }
+    }
+
+    let mut streamer = target_channel
+        .as_ref()
+        .filter(|channel| should_stream(&ctx, channel.as_ref()))
+        .map(|channel| {
+            streaming::ReplyStreamer::new(channel.as_ref(), &msg.reply_target, &ctx.streaming)
+        });
+    let shadow_tools = is_shadow.then(|| shadow::shadow_tools(&ctx.tools_registry));
+    let tools = shadow_tools
+        .as_deref()
+        .unwrap_or(ctx.tools_registry.as_slice());
+    let stream = streamer.is_some();
+    let (deltas, delta_rx) = tokio::sync::mpsc::unbounded_channel();
+    let turn = async {
+        // Owned by the turn so the stream ends with it, even on timeout
+        let deltas = deltas;
+        message_trace::in_span(
+            "agent.turn",
+            vec![
+                KeyValue::new("model", model.to_string()),
+                KeyValue::new("session.id", session.id.clone()),
+            ],
+            tokio::time::timeout(
+                ctx.llm_timeout,
+                run_tool_call_loop_streaming(
+                    ctx.provider.as_ref(),
+                    &mut history,
+                    tools,
+                    ctx.observer.as_ref(),
+                    "channel-runtime",
+                    model,
+                    ctx.temperature,
+                    true, // silent — channels don't write to stdout
+                    stream.then_some(&deltas),
+                ),
+            ),
+        )
+        .await
+    };
+    let streaming = async {
+        if let Some(streamer) = streamer.as_mut() {
+            streamer.run(delta_rx).await;
+        }
+    };
+    let (llm_result, ()) = tokio::join!(turn, streaming);
 
-    if let Some(channel) = target_channel.as_ref() {
+    if let Some(channel) = typing_channel {
         if let Err(e) = channel.stop_typing(&msg.reply_target).await {
             tracing::debug!("Failed to stop typing on {}: {e}", channel.name());
         }
@@ -227,13 +991,51 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
 
     match llm_result {
         Ok(Ok(response)) => {
+            let response = crate::knowledge::append_citations(&response, &knowledge);
             println!(
                 "  🤖 Reply ({}ms): {}",
                 started_at.elapsed().as_millis(),
                 truncate_with_ellipsis(&response, 80)
             );
+            ctx.sessions.append(&session_key, &msg.content, &response);
+            // A reply built on tool results may be stale by the next ask
+            if let (Some((cache, key)), true) = (&cache_key, history.len() == prompt_len + 1) {
+                let tokens = crate::cost::quota::estimate_tokens(&response);
+                let tokens = u32::try_from(tokens).unwrap_or(u32::MAX);
+                if let Err(e) = cache.put(key, model, &response, tokens) {
+                    tracing::warn!("Failed to cache the reply: {e}");
+                }
+            }
+            let (input_tokens, output_tokens) = estimate_usage(&history, prompt_len);
+            if let Some(quotas) = ctx.quotas.as_ref().filter(|_| !is_shadow) {
+                record_usage(
+                    &ctx,
+                    quotas,
+                    &user_key,
+                    &msg.channel,
+                    input_tokens,
+                    output_tokens,
+                );
+            }
+            if let Some(analytics) = ctx.control.analytics().filter(|_| !is_shadow) {
+                analytics.record_reply(
+                    &msg.channel,
+                    started_at.elapsed(),
+                    model,
+                    input_tokens,
+                    output_tokens,
+                    chrono::Utc::now(),
+                );
+            }
             if let Some(channel) = target_channel.as_ref() {
-                if let Err(e) = channel.send(&response, &msg.reply_target).await {
+                let speak = channel.supports_voice_notes()
+                    && ctx.tts.as_ref().is_some_and(|tts| {
+                        tts.should_speak(&msg.channel, &msg.sender, voice_in, &response)
+                    });
+                if let Err(e) =
+                    deliver_turn_reply(&ctx, channel.as_ref(), streamer, &response, &msg, speak)
+                        .await
+                {
                     eprintln!("  ❌ Failed to reply on {}: {e}", channel.name());
                 }
             }
@@ -244,15 +1046,15 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
                 started_at.elapsed().as_millis()
             );
             if let Some(channel) = target_channel.as_ref() {
-                let _ = channel
-                    .send(&format!("⚠️ Error: {e}"), &msg.reply_target)
-                    .await;
+                let reply = format!("⚠️ Error: {}", redact_error(&e));
+                let _ =
+                    deliver_turn_reply(&ctx, channel.as_ref(), streamer, &reply, &msg, false).await;
             }
         }
         Err(_) => {
             let timeout_msg = format!(
                 "LLM response timed out after {}s",
-                CHANNEL_MESSAGE_TIMEOUT_SECS
+                ctx.llm_timeout.as_secs()
             );
             eprintln!(
                 "  ❌ {} (elapsed: {}ms)",
@@ -260,36 +1062,288 @@ async fn process_channel_message(ctx: Arc<ChannelRuntimeContext>, msg: traits::C
                 started_at.elapsed().as_millis()
             );
             if let Some(channel) = target_channel.as_ref() {
-                let _ = channel
-                    .send(
-                        "⚠️ Request timed out while waiting for the model. Please try again.",
-                        &msg.reply_target,
-                    )
-                    .await;
+                let _ = deliver_turn_reply(
+                    &ctx,
+                    channel.as_ref(),
+                    streamer,
+                    "⚠️ Request timed out while waiting for the model. Please try again.",
+                    &msg,
+                    false,
+                )
+                .await;
+            }
+        }
+    }
+}
+
+/// Whether to stream replies on `channel`. Replies that outbound middleware
+/// may rewrite, that may be spoken or that a mute would hold are sent whole.
+fn should_stream(ctx: &ChannelRuntimeContext, channel: &dyn Channel) -> bool {
+    ctx.streaming.enabled
+        && ctx.provider.supports_streaming()
+        && !ctx.middleware.chain(channel.name()).has_outbound()
+        && !ctx.control.is_muted(channel.name())
+        && !(channel.supports_voice_notes() && ctx.tts.is_some())
+}
+
+/// Deliver the reply of an agent turn, completing `streamer` when the turn
+/// was streamed.
+async fn deliver_turn_reply(
+    ctx: &ChannelRuntimeContext,
+    channel: &dyn Channel,
+    streamer: Option<streaming::ReplyStreamer<'_>>,
+    message: &str,
+    reply_to: &traits::ChannelMessage,
+    speak: bool,
+) -> Result<()> {
+    let Some(streamer) = streamer else {
+        return deliver_reply_as(ctx, channel, message, reply_to, speak).await;
+    };
+    let result = streamer.finish(message).await;
+    hooks::track_send(channel.name(), &reply_to.reply_target, result)
+        .inspect(|()| {
+            ctx.control.publish_outbound(
+                channel.name(),
+                &reply_to.reply_target,
+                message,
+                Some(&reply_to.id),
+            );
+        })
+        .inspect_err(|e| ctx.control.record_error(channel.name(), e))
+}
+
+/// Prompt of an `/imagine` command, or `None` for any other message.
+fn imagine_prompt(content: &str) -> Option<&str> {
+    let content = content.trim_start();
+    let command = content.split_whitespace().next()?;
+    if command.split('@').next()? != "/imagine" {
+        return None;
+    }
+    Some(content[command.len()..].trim())
+}
+
+/// Question of a `/nocache` message, which skips the response cache, or
+/// `None` for any other message.
+fn nocache_question(content: &str) -> Option<&str> {
+    let content = content.trim_start();
+    let command = content.split_whitespace().next()?;
+    if command.split('@').next()? != "/nocache" {
+        return None;
+    }
+    Some(content[command.len()..].trim())
+}
+
+/// Answer `/imagine` by generating an image and uploading it natively.
+async fn reply_with_generated_image(
+    ctx: &ChannelRuntimeContext,
+    generator: &dyn crate::imagegen::ImageGenerator,
+    channel: &dyn Channel,
+    msg: &traits::ChannelMessage,
+    prompt: &str,
+) {
+    let notice = if prompt.is_empty() {
+        Some("Usage: /imagine <description>".to_string())
+    } else if !channel.supports_images() {
+        Some("⚠️ Images can't be sent on this channel.".to_string())
+    } else {
+        let typing = channel.capabilities().supports_typing;
+        if typing {
+            if let Err(e) = channel.start_typing(&msg.reply_target).await {
+                tracing::debug!("Failed to start typing on {}: {e}", channel.name());
+            }
+        }
+        let result = generator.generate(prompt).await;
+        if typing {
+            if let Err(e) = channel.stop_typing(&msg.reply_target).await {
+                tracing::debug!("Failed to stop typing on {}: {e}", channel.name());
+            }
+        }
+        match result {
+            Ok(_) if ctx.control.is_muted(channel.name()) => {
+                tracing::debug!("Channel {} is muted; dropping image", channel.name());
+                None
+            }
+            Ok(image) => {
+                persist_generated_image(&image).await;
+                let file_name = image.file_name("image");
+                if let Err(e) = channel
+                    .send_image(image.bytes, &file_name, Some(prompt), &msg.reply_target)
+                    .await
+                {
+                    ctx.control.record_error(channel.name(), &e);
+                    tracing::warn!("Failed to send image on {}: {e}", channel.name());
+                }
+                None
+            }
+            Err(e) => Some(format!("⚠️ Image generation failed: {}", redact_error(&e))),
+        }
+    };
+    if let Some(notice) = notice {
+        answer(ctx, msg, &notice).await;
+    }
+}
+
+/// Keep a copy of a generated image in the media store, if one is set up.
+async fn persist_generated_image(image: &crate::imagegen::GeneratedImage) {
+    let Some(store) = crate::media_store::global().filter(|store| store.persist_generated()) else {
+        return;
+    };
+    match store.store(image.bytes.clone(), &image.mime_type).await {
+        Ok(stored) => tracing::info!("Stored generated image as {}", stored.key),
+        Err(e) => tracing::warn!("Failed to store generated image: {e}"),
+    }
+}
+
+/// Estimated (input, output) tokens of one answered message. Providers
+/// don't report token counts, so they are estimated from the conversation:
+/// assistant turns produced after the first `prompt_len` messages are
+/// output, everything else was input.
+fn estimate_usage(history: &[ChatMessage], prompt_len: usize) -> (u64, u64) {
+    let (output, input): (Vec<_>, Vec<_>) = history
+        .iter()
+        .enumerate()
+        .partition(|(i, m)| *i >= prompt_len && m.role == "assistant");
+    let (output, input): (Vec<_>, Vec<_>) = (
+        output.into_iter().map(|(_, m)| m).collect(),
+        input.into_iter().map(|(_, m)| m).collect(),
+    );
+    let count = |messages: Vec<&ChatMessage>| {
+        messages
+            .iter()
+            .map(|m| crate::cost::quota::estimate_tokens(&m.content))
+            .sum::<u64>()
+    };
+    (count(input), count(output))
+}
+
+/// Account one answered message in the usage ledger.
+fn record_usage(
+    ctx: &ChannelRuntimeContext,
+    quotas: &crate::cost::QuotaLedger,
+    user: &str,
+    channel: &str,
+    input_tokens: u64,
+    output_tokens: u64,
+) {
+    match quotas.record(
+        user,
+        channel,
+        ctx.model_for(channel),
+        input_tokens,
+        output_tokens,
+    ) {
+        Ok(usage) => {
+            ctx.observer
+                .record_metric(&crate::observability::traits::ObserverMetric::TokensUsed(
+                    usage.total_tokens,
+                ));
+        }
+        Err(e) => tracing::warn!("Failed to record usage for {user}: {e}"),
+    }
+}
+
+/// Lane key for per-conversation ordering: messages from the same author on
+/// the same channel are processed one at a time, in arrival order. Urgent
+/// messages have a lane of their own, so they don't wait behind chat.
+fn conversation_lane_key(msg: &traits::ChannelMessage, priority: priority::Priority) -> String {
+    match priority {
+        priority::Priority::Normal => format!("{}:{}", msg.channel, msg.author_id()),
+        priority::Priority::Urgent => format!("{}:{}#urgent", msg.channel, msg.author_id()),
+    }
+}
+
+/// Process `first` and then every message queued behind it on the same lane.
+async fn run_conversation_lane(
+    ctx: Arc<ChannelRuntimeContext>,
+    lanes: Arc<lanes::ConversationLanes<traits::ChannelMessage>>,
+    pool: Arc<dispatch::WorkerPool>,
+    key: String,
+    first: traits::ChannelMessage,
+) {
+    let mut next = Some(first);
+    while let Some(msg) = next {
+        let _worker = pool.acquire(&msg.channel, priority::classify(&msg)).await;
+        let trace = message_trace::message_context(&msg);
+        let (channel, id) = (msg.channel.clone(), msg.id.clone());
+        // A panicking message must not take the lane with it: whatever
+        // happens, the lane moves on, or its conversation stalls for good
+        let processed = futures::FutureExt::catch_unwind(AssertUnwindSafe(net::with_deadline(
+            pool.deadline(&channel),
+            message_trace::traced_message(
+                trace,
+                Box::pin(process_channel_message(Arc::clone(&ctx), msg)),
+            ),
+        )))
+        .await;
+        let failure = match processed {
+            Ok(Some(())) => None,
+            Ok(None) => {
+                tracing::warn!("Processing message {id} on {channel} exceeded its deadline");
+                Some(format!("message deadline exceeded on {channel}"))
             }
+            Err(panic) => {
+                let reason = panic_message(panic.as_ref());
+                tracing::error!("Processing message {id} on {channel} panicked: {reason}");
+                Some(format!("message processing panicked on {channel}"))
+            }
+        };
+        if let Some(message) = failure {
+            ctx.observer
+                .record_event(&observability::ObserverEvent::Error {
+                    component: "channel".into(),
+                    message,
+                });
         }
+        next = lanes.next(&key);
     }
 }
 
 async fn run_message_dispatch_loop(
     mut rx: tokio::sync::mpsc::Receiver<traits::ChannelMessage>,
     ctx: Arc<ChannelRuntimeContext>,
-    max_in_flight_messages: usize,
+    pool: dispatch::WorkerPool,
 ) {
-    let semaphore = Arc::new(tokio::sync::Semaphore::new(max_in_flight_messages));
+    // Lanes wait for a worker themselves, but only as many messages as the
+    // pool can work through are admitted; the rest stay in the inbound
+    // queues, whose overflow policy decides what a busy pool drops.
+    let pool = Arc::new(pool);
+    let lanes = Arc::new(lanes::ConversationLanes::with_capacity(pool.max_admitted()));
+    ctx.control.attach_lanes(Arc::clone(&lanes));
     let mut workers = tokio::task::JoinSet::new();
+    let mut dedup = dedup::InboundDeduplicator::default();
+
+    loop {
+        lanes.wait_for_room().await;
+        let Some(msg) = rx.recv().await else {
+            break;
+        };
+        if dedup.is_duplicate(&msg.channel, &msg.id) {
+            tracing::debug!(
+                "Dropping duplicate inbound message {} on {}",
+                msg.id,
+                msg.channel
+            );
+            continue;
+        }
+
+        ctx.control.publish_inbound(&msg);
 
-    while let Some(msg) = rx.recv().await {
-        let permit = match Arc::clone(&semaphore).acquire_owned().await {
-            Ok(permit) => permit,
-            Err(_) => break,
+        let key = conversation_lane_key(&msg, priority::classify(&msg));
+        let Some(msg) = lanes.enqueue(&key, msg) else {
+            // Queued behind an in-progress message from the same conversation.
+            continue;
         };
 
         let worker_ctx = Arc::clone(&ctx);
-        workers.spawn(async move {
-            let _permit = permit;
-            process_channel_message(worker_ctx, msg).await;
-        });
+        let worker_lanes = Arc::clone(&lanes);
+        let worker_pool = Arc::clone(&pool);
+        workers.spawn(run_conversation_lane(
+            worker_ctx,
+            worker_lanes,
+            worker_pool,
+            key,
+            msg,
+        ));
 
         while let Some(result) = workers.try_join_next() {
             log_worker_join_result(result);
@@ -699,7 +1753,7 @@ fn maybe_restart_managed_daemon_service() -> Result<bool> {
 
 pub fn handle_command(command: crate::ChannelCommands, config: &Config) -> Result<()> {
     match command {
-        crate::ChannelCommands::Start => {
+        crate::ChannelCommands::Start { .. } => {
             anyhow::bail!("Start must be handled in main.rs (requires async runtime)")
         }
         crate::ChannelCommands::Doctor => {
@@ -722,6 +1776,11 @@ pub fn handle_command(command: crate::ChannelCommands, config: &Config) -> Resul
                 ("Lark", config.channels_config.lark.is_some()),
                 ("DingTalk", config.channels_config.dingtalk.is_some()),
                 ("QQ", config.channels_config.qq.is_some()),
+                ("Nostr", config.channels_config.nostr.is_some()),
+                (
+                    "Local socket",
+                    config.channels_config.local_socket.is_some(),
+                ),
             ] {
                 println!("  {} {name}", if configured { "✅" } else { "❌" });
             }
@@ -764,181 +1823,770 @@ fn classify_health_result(
     }
 }
 
-/// Run health checks for configured channels.
-pub async fn doctor_channels(config: Config) -> Result<()> {
-    let mut channels: Vec<(&'static str, Arc<dyn Channel>)> = Vec::new();
+/// Run health checks for configured channels.
+pub async fn doctor_channels(config: Config) -> Result<()> {
+    net::configure(&config.channels_config);
+    let mut channels: Vec<(&'static str, Arc<dyn Channel>)> = Vec::new();
+
+    if let Some(ref tg) = config.channels_config.telegram {
+        channels.push((
+            "Telegram",
+            Arc::new(TelegramChannel::with_secret(
+                resolve_channel_secret(&config, &tg.bot_token)?,
+                tg.allowed_users.clone(),
+            )),
+        ));
+    }
+
+    if let Some(ref dc) = config.channels_config.discord {
+        channels.push((
+            "Discord",
+            Arc::new(DiscordChannel::with_secret(
+                resolve_channel_secret(&config, &dc.bot_token)?,
+                dc.guild_id.clone(),
+                dc.allowed_users.clone(),
+                dc.listen_to_bots,
+                dc.mention_only,
+            )),
+        ));
+    }
+
+    if let Some(ref sl) = config.channels_config.slack {
+        channels.push((
+            "Slack",
+            Arc::new(
+                SlackChannel::with_secret(
+                    resolve_channel_secret(&config, &sl.bot_token)?,
+                    sl.channel_id.clone(),
+                    sl.allowed_users.clone(),
+                )
+                .with_oauth(slack_oauth(&config, sl)?)
+                .with_events_api(sl.signing_secret.is_some()),
+            ),
+        ));
+    }
+
+    if let Some(ref im) = config.channels_config.imessage {
+        channels.push((
+            "iMessage",
+            Arc::new(IMessageChannel::new(im.allowed_contacts.clone())),
+        ));
+    }
+
+    if let Some(ref mx) = config.channels_config.matrix {
+        channels.push((
+            "Matrix",
+            Arc::new(MatrixChannel::new(
+                mx.homeserver.clone(),
+                mx.access_token.clone(),
+                mx.room_id.clone(),
+                mx.allowed_users.clone(),
+            )),
+        ));
+    }
+
+    if let Some(ref sig) = config.channels_config.signal {
+        channels.push((
+            "Signal",
+            Arc::new(
+                SignalChannel::new(
+                    sig.http_url.clone(),
+                    sig.account.clone(),
+                    sig.group_id.clone(),
+                    sig.allowed_from.clone(),
+                    sig.ignore_attachments,
+                    sig.ignore_stories,
+                )
+                .with_rpc_socket(sig.rpc_socket.clone()),
+            ),
+        ));
+    }
+
+    if let Some(ref wa) = config.channels_config.whatsapp {
+        channels.push((
+            "WhatsApp",
+            Arc::new(
+                WhatsAppChannel::new(
+                    wa.access_token.clone(),
+                    wa.phone_number_id.clone(),
+                    wa.verify_token.clone(),
+                    wa.allowed_numbers.clone(),
+                )
+                .with_session_template(wa.session_template.clone(), wa.template_language.clone()),
+            ),
+        ));
+    }
+
+    if let Some(ref email_cfg) = config.channels_config.email {
+        channels.push(("Email", Arc::new(EmailChannel::new(email_cfg.clone()))));
+    }
+
+    if let Some(ref irc) = config.channels_config.irc {
+        channels.push((
+            "IRC",
+            Arc::new(IrcChannel::new(irc::IrcChannelConfig {
+                server: irc.server.clone(),
+                port: irc.port,
+                nickname: irc.nickname.clone(),
+                username: irc.username.clone(),
+                channels: irc.channels.clone(),
+                allowed_users: irc.allowed_users.clone(),
+                server_password: irc.server_password.clone(),
+                nickserv_password: irc.nickserv_password.clone(),
+                sasl_password: irc.sasl_password.clone(),
+                verify_tls: irc.verify_tls.unwrap_or(true),
+            })),
+        ));
+    }
+
+    if let Some(ref lk) = config.channels_config.lark {
+        channels.push(("Lark", Arc::new(LarkChannel::from_config(lk))));
+    }
+
+    if let Some(ref dt) = config.channels_config.dingtalk {
+        channels.push((
+            "DingTalk",
+            Arc::new(DingTalkChannel::new(
+                dt.client_id.clone(),
+                dt.client_secret.clone(),
+                dt.allowed_users.clone(),
+            )),
+        ));
+    }
+
+    if let Some(ref qq) = config.channels_config.qq {
+        channels.push((
+            "QQ",
+            Arc::new(
+                QQChannel::with_secret(
+                    qq.app_id.clone(),
+                    resolve_channel_secret(&config, &qq.app_secret)?,
+                    qq.allowed_users.clone(),
+                )
+                .with_environment(QQEnvironment::from_config(qq)?)
+                .with_gateway_url(qq::configured_gateway_url(qq)?)
+                .with_callback(qq.callback),
+            ),
+        ));
+    }
+
+    #[cfg(feature = "channel-nostr")]
+    if let Some(ref ns) = config.channels_config.nostr {
+        channels.push((
+            "Nostr",
+            Arc::new(NostrChannel::new(
+                resolve_channel_secret(&config, &ns.private_key)?,
+                ns.relays.clone(),
+                ns.allowed_pubkeys.clone(),
+            )),
+        ));
+    }
+
+    if let Some(ref ls) = config.channels_config.local_socket {
+        channels.push((
+            "Local socket",
+            Arc::new(LocalSocketChannel::new(local_socket_path(&config, ls))),
+        ));
+    }
+
+    if channels.is_empty() {
+        println!("No real-time channels configured. Run `zeroclaw onboard` first.");
+        return Ok(());
+    }
+
+    println!("🩺 ZeroClaw Channel Doctor");
+    println!();
+
+    let mut healthy = 0_u32;
+    let mut unhealthy = 0_u32;
+    let mut timeout = 0_u32;
+
+    for (name, channel) in channels {
+        let result = tokio::time::timeout(Duration::from_secs(10), channel.health_check()).await;
+        let state = classify_health_result(&result);
+
+        match state {
+            ChannelHealthState::Healthy => {
+                healthy += 1;
+                println!("  ✅ {name:<9} healthy");
+            }
+            ChannelHealthState::Unhealthy => {
+                unhealthy += 1;
+                println!("  ❌ {name:<9} unhealthy (auth/config/network)");
+            }
+            ChannelHealthState::Timeout => {
+                timeout += 1;
+                println!("  ⏱️  {name:<9} timed out (>10s)");
+            }
+        }
+    }
+
+    if config.channels_config.webhook.is_some() {
+        println!("  ℹ️  Webhook   check via `zeroclaw gateway` then GET /health");
+    }
+    #[cfg(not(feature = "channel-nostr"))]
+    if config.channels_config.nostr.is_some() {
+        println!("  ℹ️  Nostr     needs a build with --features channel-nostr");
+    }
+
+    println!();
+    println!("Summary: {healthy} healthy, {unhealthy} unhealthy, {timeout} timed out");
+    Ok(())
+}
+
+/// The configured local socket path, or the default in the config directory.
+fn local_socket_path(config: &Config, ls: &crate::config::schema::LocalSocketConfig) -> String {
+    ls.path.clone().unwrap_or_else(|| {
+        let zeroclaw_dir = config.config_path.parent().map_or_else(
+            || std::path::PathBuf::from("."),
+            std::path::Path::to_path_buf,
+        );
+        local_socket::default_path(&zeroclaw_dir)
+    })
+}
+
+/// Build a `[[channels_config.accounts]]` entry running under its own name.
+fn build_account_channel(
+    config: &Config,
+    account: &crate::config::ChannelAccountConfig,
+    gateway_state: &Arc<dyn gateway_state::GatewayStateStore>,
+) -> Result<Arc<dyn Channel>> {
+    use crate::config::ChannelAccountKind;
+
+    let name = account.name.clone();
+    let channel: Arc<dyn Channel> = match &account.kind {
+        ChannelAccountKind::Telegram(tg) => Arc::new(
+            TelegramChannel::with_secret(
+                resolve_channel_secret(config, &tg.bot_token)?,
+                tg.allowed_users.clone(),
+            )
+            .with_name(name),
+        ),
+        ChannelAccountKind::Discord(dc) => Arc::new(
+            DiscordChannel::with_secret(
+                resolve_channel_secret(config, &dc.bot_token)?,
+                dc.guild_id.clone(),
+                dc.allowed_users.clone(),
+                dc.listen_to_bots,
+                dc.mention_only,
+            )
+            .with_name(name)
+            .with_state_store(Arc::clone(gateway_state))
+            .with_heartbeat_jitter(dc.heartbeat_jitter)
+            .with_compression(dc.compression),
+        ),
+        ChannelAccountKind::Slack(sl) => Arc::new(
+            SlackChannel::with_secret(
+                resolve_channel_secret(config, &sl.bot_token)?,
+                sl.channel_id.clone(),
+                sl.allowed_users.clone(),
+            )
+            .with_name(name)
+            .with_oauth(slack_oauth(config, sl)?)
+            .with_events_api(sl.signing_secret.is_some()),
+        ),
+        ChannelAccountKind::Qq(qq) => Arc::new(
+            QQChannel::with_secret(
+                qq.app_id.clone(),
+                resolve_channel_secret(config, &qq.app_secret)?,
+                qq.allowed_users.clone(),
+            )
+            .with_name(name)
+            .with_environment(QQEnvironment::from_config(qq)?)
+            .with_gateway_url(qq::configured_gateway_url(qq)?)
+            .with_callback(qq.callback)
+            .with_state_store(Arc::clone(gateway_state))
+            .with_heartbeat_jitter(qq.heartbeat_jitter),
+        ),
+    };
+    Ok(channel)
+}
+
+/// Start all configured channels and route messages to the agent
+#[allow(clippy::too_many_lines)]
+pub async fn start_channels(config: Config) -> Result<()> {
+    let control = Arc::new(control::ChannelControl::new());
+
+    // Control-plane servers outlive config reloads of the channel runtime.
+    let mut servers = Vec::new();
+
+    if config.admin.enabled {
+        let token = config
+            .admin
+            .token
+            .as_deref()
+            .map(|raw| resolve_channel_secret(&config, raw))
+            .transpose()?;
+        let basic = match (&config.admin.username, &config.admin.password) {
+            (Some(username), Some(password)) => Some(admin::BasicCredentials {
+                username: username.clone(),
+                password: resolve_channel_secret(&config, password)?,
+            }),
+            (None, None) => None,
+            _ => anyhow::bail!("[admin] username and password must be set together"),
+        };
+        let admin_config = config.admin.clone();
+        let admin_control = Arc::clone(&control);
+        servers.push(tokio::spawn(async move {
+            if let Err(e) =
+                admin::run_admin_server(&admin_config, token, basic, admin_control).await
+            {
+                tracing::error!("Admin API stopped: {e}");
+                crate::health::mark_component_error("admin", e.to_string());
+            }
+        }));
+    }
+
+    if config.grpc.enabled {
+        #[cfg(feature = "grpc")]
+        {
+            let token = config
+                .grpc
+                .token
+                .as_deref()
+                .map(|raw| resolve_channel_secret(&config, raw))
+                .transpose()?;
+            let grpc_config = config.grpc.clone();
+            let grpc_control = Arc::clone(&control);
+            servers.push(tokio::spawn(async move {
+                if let Err(e) = grpc::run_grpc_server(&grpc_config, token, grpc_control).await {
+                    tracing::error!("gRPC API stopped: {e}");
+                    crate::health::mark_component_error("grpc", e.to_string());
+                }
+            }));
+        }
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!("[grpc] is enabled but this build lacks the `grpc` feature; skipping");
+    }
+
+    if config.feeds.enabled && !config.feeds.subscriptions.is_empty() {
+        let feeds_config = config.feeds.clone();
+        let workspace = config.workspace_dir.clone();
+        let feeds_control = Arc::clone(&control);
+        servers.push(tokio::spawn(async move {
+            if let Err(e) =
+                crate::feeds::run_feed_watcher(feeds_config, &workspace, feeds_control).await
+            {
+                tracing::error!("Feed watcher stopped: {e}");
+                crate::health::mark_component_error("feeds", e.to_string());
+            }
+        }));
+    }
+
+    uploads::install(uploads::Uploader::from_config(&config.uploads, |raw| {
+        resolve_channel_secret(&config, raw)
+    })?);
+    crate::media_store::install(crate::media_store::MediaStore::from_config(
+        &config.media_store,
+        &config.workspace_dir,
+        |raw| resolve_channel_secret(&config, raw),
+    )?);
+    crate::kv::install(crate::kv::KvStore::from_config(
+        &config.kv,
+        &config.workspace_dir,
+    )?);
+    let zeroclaw_dir = config
+        .config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), std::path::Path::to_path_buf);
+    crate::security::audit::install(
+        config
+            .security
+            .audit
+            .enabled
+            .then(|| AuditLogger::new(config.security.audit.clone(), zeroclaw_dir))
+            .transpose()?,
+    );
+
+    if config.reminders.enabled {
+        let reminders_config = config.reminders.clone();
+        let workspace = config.workspace_dir.clone();
+        let reminders_control = Arc::clone(&control);
+        servers.push(tokio::spawn(async move {
+            if let Err(e) =
+                crate::reminders::run_reminder_loop(reminders_config, &workspace, reminders_control)
+                    .await
+            {
+                tracing::error!("Reminder delivery stopped: {e}");
+                crate::health::mark_component_error("reminders", e.to_string());
+            }
+        }));
+    }
+
+    if config.ops.enabled && !config.ops.channel.is_empty() {
+        let ops_config = config.ops.clone();
+        let ops_control = Arc::clone(&control);
+        servers.push(tokio::spawn(crate::ops::run_ops_notifier(
+            ops_config,
+            ops_control,
+        )));
+    }
+
+    if config.analytics.enabled {
+        match crate::analytics::Analytics::open(&config.workspace_dir, &config.cost.prices) {
+            Ok(analytics) => {
+                let analytics = Arc::new(analytics);
+                control.attach_analytics(Some(Arc::clone(&analytics)));
+                servers.push(tokio::spawn(crate::analytics::run_analytics(
+                    config.analytics.clone(),
+                    config.ops.clone(),
+                    analytics,
+                    Arc::clone(&control),
+                )));
+            }
+            Err(e) => tracing::warn!("Analytics disabled: {e}"),
+        }
+    }
+
+    if !config.hooks.webhooks.is_empty() {
+        let webhooks = config
+            .hooks
+            .webhooks
+            .iter()
+            .map(|webhook| {
+                event_webhooks::EventWebhook::from_config(webhook, |raw| {
+                    resolve_channel_secret(&config, raw)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        servers.push(tokio::spawn(event_webhooks::run_event_webhooks(webhooks)));
+    }
+
+    if config.monitor.enabled && !config.monitor.checks.is_empty() {
+        let monitor_config = config.monitor.clone();
+        let monitor_control = Arc::clone(&control);
+        servers.push(tokio::spawn(async move {
+            if let Err(e) = crate::monitor::run_monitor(monitor_config, monitor_control).await {
+                tracing::error!("Uptime monitor stopped: {e}");
+                crate::health::mark_component_error("monitor", e.to_string());
+            }
+        }));
+    }
+
+    // Always running: a config reload may add quiet hours later
+    servers.push(tokio::spawn(quiet_hours::run_release_loop(Arc::clone(
+        &control,
+    ))));
+
+    if digest::configured(&config) {
+        servers.push(tokio::spawn(digest::run_digest_flusher(Arc::clone(
+            &control,
+        ))));
+    }
+
+    if config.ingress.enabled {
+        let ingress_config = config.ingress.clone();
+        let secrets_config = config.clone();
+        let workspace = config.workspace_dir.clone();
+        let ingress_control = Arc::clone(&control);
+        servers.push(tokio::spawn(async move {
+            let resolve = |raw: &str| resolve_channel_secret(&secrets_config, raw);
+            if let Err(e) = crate::ingress::run_ingress_server(
+                &ingress_config,
+                &workspace,
+                ingress_control,
+                resolve,
+            )
+            .await
+            {
+                tracing::error!("Ingress receiver stopped: {e}");
+                crate::health::mark_component_error("ingress", e.to_string());
+            }
+        }));
+    }
+
+    // Leadership, like the servers above, outlives config reloads
+    let election = crate::cluster::LeaderElection::connect(&config.cluster, |raw| {
+        resolve_channel_secret(&config, raw)
+    })
+    .await?;
+    if let Some(election) = &election {
+        status!(
+            "  👑 Cluster:  {} (listeners run on the leader only)",
+            election.instance()
+        );
+        servers.push(tokio::spawn(Arc::clone(election).run()));
+    }
+    let leadership = election.as_ref().map(|election| election.subscribe());
+
+    let mut config = config;
+    let result = loop {
+        match Box::pin(run_channel_runtime(
+            config,
+            Arc::clone(&control),
+            leadership.clone(),
+        ))
+        .await
+        {
+            Ok(Some(reloaded)) => {
+                status!("  🔄 Config reloaded; restarting channels");
+                hooks::emit(hooks::LifecycleEvent::ConfigReloaded);
+                config = reloaded;
+            }
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+
+    for handle in servers {
+        handle.abort();
+    }
+    if let Some(election) = election {
+        election.resign().await;
+    }
+    result
+}
+
+/// Listeners aborted when dropped, so stopping the task that owns them
+/// stops them too.
+struct Listeners(Vec<tokio::task::JoinHandle<()>>);
+
+impl Drop for Listeners {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+/// Run the listeners `spawn` starts while this instance leads the cluster,
+/// and stop them when it stops leading.
+async fn follow_leadership(
+    mut leadership: tokio::sync::watch::Receiver<bool>,
+    spawn: impl Fn() -> Vec<tokio::task::JoinHandle<()>>,
+) {
+    loop {
+        if leadership.wait_for(|leads| *leads).await.is_err() {
+            return;
+        }
+        status!("  👑 Leading the cluster; starting listeners");
+        let listeners = Listeners(spawn());
+        if leadership.wait_for(|leads| !*leads).await.is_err() {
+            return;
+        }
+        status!("  💤 No longer leading the cluster; stopping listeners");
+        drop(listeners);
+    }
+}
+
+/// Open the knowledge base and bring it up to date with the files on disk.
+/// Ingestion failures are logged; retrieval still serves what was indexed.
+async fn build_knowledge_base(
+    config: &Config,
+) -> Result<Option<Arc<crate::knowledge::KnowledgeBase>>> {
+    if !config.knowledge.enabled {
+        return Ok(None);
+    }
+    let embedder: Arc<dyn memory::embeddings::EmbeddingProvider> =
+        Arc::from(memory::embeddings::create_embedding_provider(
+            &config.memory.embedding_provider,
+            config.api_key.as_deref(),
+            &config.memory.embedding_model,
+            config.memory.embedding_dimensions,
+        ));
+    let qdrant_key = config
+        .knowledge
+        .qdrant_api_key
+        .as_deref()
+        .map(|raw| resolve_channel_secret(config, raw))
+        .transpose()?;
+    let Some(kb) = crate::knowledge::create_knowledge_base(
+        &config.knowledge,
+        embedder,
+        &config.workspace_dir,
+        qdrant_key,
+    )?
+    else {
+        return Ok(None);
+    };
+    match kb.ingest().await {
+        Ok(report) => status!(
+            "  📚 Knowledge: {} indexed, {} unchanged, {} removed ({})",
+            report.indexed,
+            report.unchanged,
+            report.removed,
+            kb.store_name()
+        ),
+        Err(e) => tracing::warn!("Knowledge ingestion failed: {e}"),
+    }
+    Ok(Some(Arc::new(kb)))
+}
+
+/// The channels configured in `config`, each under a unique name.
+fn configured_channels(config: &Config) -> Result<Vec<Arc<dyn Channel>>> {
+    let mut channels: Vec<Arc<dyn Channel>> = Vec::new();
+    let gateway_state: Arc<dyn gateway_state::GatewayStateStore> = Arc::new(
+        gateway_state::FileGatewayStateStore::new(&config.workspace_dir),
+    );
 
     if let Some(ref tg) = config.channels_config.telegram {
-        channels.push((
-            "Telegram",
-            Arc::new(TelegramChannel::new(
-                tg.bot_token.clone(),
-                tg.allowed_users.clone(),
-            )),
-        ));
+        channels.push(Arc::new(TelegramChannel::with_secret(
+            resolve_channel_secret(config, &tg.bot_token)?,
+            tg.allowed_users.clone(),
+        )));
     }
 
     if let Some(ref dc) = config.channels_config.discord {
-        channels.push((
-            "Discord",
-            Arc::new(DiscordChannel::new(
-                dc.bot_token.clone(),
+        channels.push(Arc::new(
+            DiscordChannel::with_secret(
+                resolve_channel_secret(config, &dc.bot_token)?,
                 dc.guild_id.clone(),
                 dc.allowed_users.clone(),
                 dc.listen_to_bots,
                 dc.mention_only,
-            )),
+            )
+            .with_state_store(Arc::clone(&gateway_state))
+            .with_heartbeat_jitter(dc.heartbeat_jitter)
+            .with_compression(dc.compression),
         ));
     }
 
     if let Some(ref sl) = config.channels_config.slack {
-        channels.push((
-            "Slack",
-            Arc::new(SlackChannel::new(
-                sl.bot_token.clone(),
+        channels.push(Arc::new(
+            SlackChannel::with_secret(
+                resolve_channel_secret(config, &sl.bot_token)?,
                 sl.channel_id.clone(),
                 sl.allowed_users.clone(),
-            )),
+            )
+            .with_oauth(slack_oauth(config, sl)?)
+            .with_events_api(sl.signing_secret.is_some()),
         ));
     }
 
     if let Some(ref im) = config.channels_config.imessage {
-        channels.push((
-            "iMessage",
-            Arc::new(IMessageChannel::new(im.allowed_contacts.clone())),
-        ));
+        channels.push(Arc::new(IMessageChannel::new(im.allowed_contacts.clone())));
     }
 
     if let Some(ref mx) = config.channels_config.matrix {
-        channels.push((
-            "Matrix",
-            Arc::new(MatrixChannel::new(
-                mx.homeserver.clone(),
-                mx.access_token.clone(),
-                mx.room_id.clone(),
-                mx.allowed_users.clone(),
-            )),
-        ));
+        channels.push(Arc::new(MatrixChannel::new(
+            mx.homeserver.clone(),
+            mx.access_token.clone(),
+            mx.room_id.clone(),
+            mx.allowed_users.clone(),
+        )));
     }
 
     if let Some(ref sig) = config.channels_config.signal {
-        channels.push((
-            "Signal",
-            Arc::new(SignalChannel::new(
+        channels.push(Arc::new(
+            SignalChannel::new(
                 sig.http_url.clone(),
                 sig.account.clone(),
                 sig.group_id.clone(),
                 sig.allowed_from.clone(),
                 sig.ignore_attachments,
                 sig.ignore_stories,
-            )),
+            )
+            .with_rpc_socket(sig.rpc_socket.clone()),
         ));
     }
 
     if let Some(ref wa) = config.channels_config.whatsapp {
-        channels.push((
-            "WhatsApp",
-            Arc::new(WhatsAppChannel::new(
+        channels.push(Arc::new(
+            WhatsAppChannel::new(
                 wa.access_token.clone(),
                 wa.phone_number_id.clone(),
                 wa.verify_token.clone(),
                 wa.allowed_numbers.clone(),
-            )),
+            )
+            .with_session_template(wa.session_template.clone(), wa.template_language.clone()),
         ));
     }
 
     if let Some(ref email_cfg) = config.channels_config.email {
-        channels.push(("Email", Arc::new(EmailChannel::new(email_cfg.clone()))));
+        channels.push(Arc::new(EmailChannel::new(email_cfg.clone())));
     }
 
     if let Some(ref irc) = config.channels_config.irc {
-        channels.push((
-            "IRC",
-            Arc::new(IrcChannel::new(irc::IrcChannelConfig {
-                server: irc.server.clone(),
-                port: irc.port,
-                nickname: irc.nickname.clone(),
-                username: irc.username.clone(),
-                channels: irc.channels.clone(),
-                allowed_users: irc.allowed_users.clone(),
-                server_password: irc.server_password.clone(),
-                nickserv_password: irc.nickserv_password.clone(),
-                sasl_password: irc.sasl_password.clone(),
-                verify_tls: irc.verify_tls.unwrap_or(true),
-            })),
-        ));
+        channels.push(Arc::new(IrcChannel::new(irc::IrcChannelConfig {
+            server: irc.server.clone(),
+            port: irc.port,
+            nickname: irc.nickname.clone(),
+            username: irc.username.clone(),
+            channels: irc.channels.clone(),
+            allowed_users: irc.allowed_users.clone(),
+            server_password: irc.server_password.clone(),
+            nickserv_password: irc.nickserv_password.clone(),
+            sasl_password: irc.sasl_password.clone(),
+            verify_tls: irc.verify_tls.unwrap_or(true),
+        })));
     }
 
     if let Some(ref lk) = config.channels_config.lark {
-        channels.push(("Lark", Arc::new(LarkChannel::from_config(lk))));
+        channels.push(Arc::new(LarkChannel::from_config(lk)));
     }
 
     if let Some(ref dt) = config.channels_config.dingtalk {
-        channels.push((
-            "DingTalk",
-            Arc::new(DingTalkChannel::new(
-                dt.client_id.clone(),
-                dt.client_secret.clone(),
-                dt.allowed_users.clone(),
-            )),
-        ));
+        channels.push(Arc::new(DingTalkChannel::new(
+            dt.client_id.clone(),
+            dt.client_secret.clone(),
+            dt.allowed_users.clone(),
+        )));
     }
 
     if let Some(ref qq) = config.channels_config.qq {
-        channels.push((
-            "QQ",
-            Arc::new(QQChannel::new(
+        channels.push(Arc::new(
+            QQChannel::with_secret(
                 qq.app_id.clone(),
-                qq.app_secret.clone(),
+                resolve_channel_secret(config, &qq.app_secret)?,
                 qq.allowed_users.clone(),
-            )),
+            )
+            .with_environment(QQEnvironment::from_config(qq)?)
+            .with_gateway_url(qq::configured_gateway_url(qq)?)
+            .with_callback(qq.callback)
+            .with_state_store(Arc::clone(&gateway_state))
+            .with_heartbeat_jitter(qq.heartbeat_jitter),
         ));
     }
 
-    if channels.is_empty() {
-        println!("No real-time channels configured. Run `zeroclaw onboard` first.");
-        return Ok(());
-    }
-
-    println!("🩺 ZeroClaw Channel Doctor");
-    println!();
-
-    let mut healthy = 0_u32;
-    let mut unhealthy = 0_u32;
-    let mut timeout = 0_u32;
-
-    for (name, channel) in channels {
-        let result = tokio::time::timeout(Duration::from_secs(10), channel.health_check()).await;
-        let state = classify_health_result(&result);
-
-        match state {
-            ChannelHealthState::Healthy => {
-                healthy += 1;
-                println!("  ✅ {name:<9} healthy");
-            }
-            ChannelHealthState::Unhealthy => {
-                unhealthy += 1;
-                println!("  ❌ {name:<9} unhealthy (auth/config/network)");
-            }
-            ChannelHealthState::Timeout => {
-                timeout += 1;
-                println!("  ⏱️  {name:<9} timed out (>10s)");
-            }
+    if let Some(ref ns) = config.channels_config.nostr {
+        #[cfg(feature = "channel-nostr")]
+        channels.push(Arc::new(NostrChannel::new(
+            resolve_channel_secret(config, &ns.private_key)?,
+            ns.relays.clone(),
+            ns.allowed_pubkeys.clone(),
+        )));
+        #[cfg(not(feature = "channel-nostr"))]
+        {
+            let _ = ns;
+            tracing::warn!(
+                "[channels_config.nostr] is set but this build lacks the `channel-nostr` feature; skipping"
+            );
         }
     }
 
-    if config.channels_config.webhook.is_some() {
-        println!("  ℹ️  Webhook   check via `zeroclaw gateway` then GET /health");
+    if let Some(ref ls) = config.channels_config.local_socket {
+        channels.push(Arc::new(LocalSocketChannel::new(local_socket_path(
+            config, ls,
+        ))));
     }
 
-    println!();
-    println!("Summary: {healthy} healthy, {unhealthy} unhealthy, {timeout} timed out");
-    Ok(())
+    for account in &config.channels_config.accounts {
+        channels.push(build_account_channel(config, account, &gateway_state)?);
+    }
+    let mut names = std::collections::HashSet::new();
+    if let Some(duplicate) = channels
+        .iter()
+        .map(|c| c.name())
+        .find(|n| !names.insert(*n))
+    {
+        anyhow::bail!("Duplicate channel name '{duplicate}'; account names must be unique");
+    }
+    Ok(channels)
 }
 
-/// Start all configured channels and route messages to the agent
-#[allow(clippy::too_many_lines)]
-pub async fn start_channels(config: Config) -> Result<()> {
+/// Run channels until they all stop (`None`) or a config reload is requested
+/// through `control` (`Some(new_config)`).
+async fn run_channel_runtime(
+    config: Config,
+    control: Arc<control::ChannelControl>,
+    leadership: Option<tokio::sync::watch::Receiver<bool>>,
+) -> Result<Option<Config>> {
+    net::configure(&config.channels_config);
+    priority::configure(&config.channels_config);
+    quiet_hours::configure(&config.channels_config);
+    crate::security::roles::configure(&config.security.roles);
+    crate::circuit::configure(&config.reliability.circuit_breaker);
+    control.attach_config(Arc::new(config.clone()));
     let provider_name = config
         .default_provider
         .clone()
@@ -958,6 +2606,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
 
     let observer: Arc<dyn Observer> =
         Arc::from(observability::create_observer(&config.observability));
+    gateway::attach_observer(Some(Arc::clone(&observer)));
     let runtime: Arc<dyn runtime::RuntimeAdapter> =
         Arc::from(runtime::create_runtime(&config.runtime)?);
     let security = Arc::new(SecurityPolicy::from_config(
@@ -984,7 +2633,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
     };
     // Build system prompt from workspace identity files + skills
     let workspace = config.workspace_dir.clone();
-    let tools_registry = Arc::new(tools::all_tools_with_runtime(
+    let mut tools_registry = tools::all_tools_with_runtime(
         Arc::new(config.clone()),
         &security,
         runtime,
@@ -997,7 +2646,192 @@ pub async fn start_channels(config: Config) -> Result<()> {
         &config.agents,
         config.api_key.as_deref(),
         &config,
+    );
+    let plugins = Arc::new(crate::plugins::load_plugins(
+        &config.plugins,
+        &workspace,
+        &control,
+    ));
+    tools_registry.extend(plugins.tools());
+    let identities = if config.identity_linking.enabled {
+        Some(Arc::new(crate::identity::linking::IdentityStore::open(
+            &workspace,
+            config.identity_linking.code_ttl_secs,
+        )?))
+    } else {
+        None
+    };
+    let failover = match (&identities, config.channels_config.failover.enabled) {
+        (Some(identities), true) => Some(Arc::new(failover::FailoverRouter::new(
+            Arc::clone(identities),
+            config.channels_config.failover.clone(),
+        ))),
+        (None, true) => {
+            tracing::warn!("Channel failover needs [identity_linking] enabled; ignoring");
+            None
+        }
+        _ => None,
+    };
+    let quotas = if config.quotas.enabled {
+        Some(Arc::new(crate::cost::QuotaLedger::open(
+            &workspace,
+            &config.quotas,
+            &config.cost.prices,
+        )?))
+    } else {
+        None
+    };
+    control.attach_quotas(quotas.clone());
+    let transcription_key = config
+        .transcription
+        .api_key
+        .as_deref()
+        .map(|raw| resolve_channel_secret(&config, raw))
+        .transpose()?;
+    let transcription =
+        crate::transcription::create_transcriber(&config.transcription, transcription_key)?.map(
+            |transcriber| {
+                Arc::new(crate::transcription::TranscriptionService::new(
+                    transcriber,
+                    config.transcription.max_bytes,
+                ))
+            },
+        );
+    let tts_key = config
+        .tts
+        .api_key
+        .as_deref()
+        .map(|raw| resolve_channel_secret(&config, raw))
+        .transpose()?;
+    let tts = crate::tts::create_synthesizer(&config.tts, tts_key)?
+        .map(|synthesizer| Arc::new(crate::tts::TtsService::new(synthesizer, &config.tts)));
+    let vision = if config.vision.enabled {
+        let vision_provider = match &config.vision.provider {
+            Some(name) => {
+                let key = match config.vision.api_key.as_deref() {
+                    Some(raw) => Some(resolve_channel_secret(&config, raw)?.get().await?),
+                    None => None,
+                };
+                Arc::from(providers::create_provider(name, key.as_deref())?)
+            }
+            None => Arc::clone(&provider),
+        };
+        Some(Arc::new(crate::vision::VisionService::new(
+            vision_provider,
+            config.vision.model.clone().unwrap_or_else(|| model.clone()),
+            config.vision.prompt.clone(),
+            config.vision.max_bytes,
+        )))
+    } else {
+        None
+    };
+    let image_generation_key = config
+        .image_generation
+        .api_key
+        .as_deref()
+        .map(|raw| resolve_channel_secret(&config, raw))
+        .transpose()?;
+    let image_generator =
+        crate::imagegen::create_image_generator(&config.image_generation, image_generation_key)?;
+    let knowledge = build_knowledge_base(&config).await?;
+    let web_search_key = config
+        .web_search
+        .api_key
+        .as_deref()
+        .map(|raw| resolve_channel_secret(&config, raw))
+        .transpose()?;
+    let web_search = crate::websearch::create_search_backend(&config.web_search, web_search_key)?;
+    let alert_silencer = config
+        .ingress
+        .alertmanager
+        .as_ref()
+        .filter(|_| config.ingress.enabled)
+        .map(|am| Arc::new(crate::ingress::alertmanager::AlertSilencer::from_config(am)));
+    let topics = match config.ingress.notify.as_ref() {
+        Some(notify) if config.ingress.enabled => {
+            Some(Arc::new(crate::ingress::notify::TopicSubscriptions::open(
+                &config.workspace_dir,
+                notify.topics.clone(),
+            )?))
+        }
+        _ => None,
+    };
+    let preferences = crate::kv::global()
+        .filter(|_| config.preferences.enabled)
+        .map(|kv| {
+            Arc::new(crate::preferences::PreferenceStore::new(
+                kv.namespace("preferences"),
+                config.preferences.clone(),
+            ))
+        });
+    let groups = crate::kv::global()
+        .filter(|_| config.groups.enabled)
+        .map(|kv| {
+            Arc::new(crate::groups::GroupStore::new(
+                kv.namespace("groups"),
+                config.groups.clone(),
+            ))
+        });
+    control.attach_groups(groups.clone());
+    let personas = config.personas.enabled.then(|| {
+        let mut personas = config.personas.clone();
+        for shadow in &config.channels_config.shadows {
+            if let Some(persona) = &shadow.persona {
+                personas
+                    .channels
+                    .insert(shadow.name.clone(), persona.clone());
+            }
+        }
+        Arc::new(crate::personas::PersonaStore::new(
+            crate::kv::global().map(|kv| kv.namespace("personas")),
+            personas,
+        ))
+    });
+    let response_cache =
+        crate::memory::create_response_cache(&config.memory, &config.workspace_dir).map(Arc::new);
+    control.attach_response_cache(response_cache.clone());
+    let announcement_config = &config.channels_config.announcements;
+    let has_announcers =
+        !announcement_config.admins.is_empty() || crate::security::roles::configured();
+    let announcements = (announcement_config.enabled && has_announcers)
+        .then(|| announce::Announcements::new(announcement_config.clone(), Arc::clone(&control)));
+    if let Some(announcements) = &announcements {
+        announcements.register_buttons();
+    }
+    let reminders = if config.reminders.enabled {
+        Some(Arc::new(crate::reminders::ReminderStore::open(
+            &config.workspace_dir,
+            config.reminders.clone(),
+        )?))
+    } else {
+        None
+    };
+    let sessions = Arc::new(sessions::SessionStore::new(
+        &config.channels_config.sessions,
     ));
+    control.attach_eraser(Some(Arc::new(erasure::DataEraser::new(
+        Arc::clone(&mem),
+        Arc::clone(&sessions),
+        identities.clone(),
+        preferences.clone(),
+        personas.clone(),
+        reminders.clone(),
+        quotas.clone(),
+    ))));
+    control.attach_archive(config.memory.auto_save.then(|| {
+        Arc::new(history::MessageArchive::new(
+            Arc::clone(&mem),
+            identities.clone(),
+        ))
+    }));
+    let middleware = Arc::new(middleware::build_stack(
+        &config,
+        &middleware::MiddlewareContext {
+            control: Some(Arc::clone(&control)),
+            identities: identities.clone(),
+        },
+    )?);
+    let tools_registry = Arc::new(tools_registry);
 
     let skills = crate::skills::load_skills(&workspace);
 
@@ -1049,6 +2883,12 @@ pub async fn start_channels(config: Config) -> Result<()> {
         "pushover",
         "Send a Pushover notification to your device. Requires PUSHOVER_TOKEN and PUSHOVER_USER_KEY in .env file.",
     ));
+    if config.web_search.enabled {
+        tool_descs.push((
+            "web_search",
+            "Search the web. Use when: the answer needs current or external information. Don't use when: the answer is already known or in memory.",
+        ));
+    }
     if !config.agents.is_empty() {
         tool_descs.push((
             "delegate",
@@ -1072,7 +2912,7 @@ pub async fn start_channels(config: Config) -> Result<()> {
     system_prompt.push_str(&build_tool_instructions(tools_registry.as_ref()));
 
     if !skills.is_empty() {
-        println!(
+        status!(
             "  🧩 Skills:   {}",
             skills
                 .iter()
@@ -1081,120 +2921,58 @@ pub async fn start_channels(config: Config) -> Result<()> {
                 .join(", ")
         );
     }
-
-    // Collect active channels
-    let mut channels: Vec<Arc<dyn Channel>> = Vec::new();
-
-    if let Some(ref tg) = config.channels_config.telegram {
-        channels.push(Arc::new(TelegramChannel::new(
-            tg.bot_token.clone(),
-            tg.allowed_users.clone(),
-        )));
-    }
-
-    if let Some(ref dc) = config.channels_config.discord {
-        channels.push(Arc::new(DiscordChannel::new(
-            dc.bot_token.clone(),
-            dc.guild_id.clone(),
-            dc.allowed_users.clone(),
-            dc.listen_to_bots,
-            dc.mention_only,
-        )));
-    }
-
-    if let Some(ref sl) = config.channels_config.slack {
-        channels.push(Arc::new(SlackChannel::new(
-            sl.bot_token.clone(),
-            sl.channel_id.clone(),
-            sl.allowed_users.clone(),
-        )));
-    }
-
-    if let Some(ref im) = config.channels_config.imessage {
-        channels.push(Arc::new(IMessageChannel::new(im.allowed_contacts.clone())));
-    }
-
-    if let Some(ref mx) = config.channels_config.matrix {
-        channels.push(Arc::new(MatrixChannel::new(
-            mx.homeserver.clone(),
-            mx.access_token.clone(),
-            mx.room_id.clone(),
-            mx.allowed_users.clone(),
-        )));
-    }
-
-    if let Some(ref sig) = config.channels_config.signal {
-        channels.push(Arc::new(SignalChannel::new(
-            sig.http_url.clone(),
-            sig.account.clone(),
-            sig.group_id.clone(),
-            sig.allowed_from.clone(),
-            sig.ignore_attachments,
-            sig.ignore_stories,
-        )));
-    }
-
-    if let Some(ref wa) = config.channels_config.whatsapp {
-        channels.push(Arc::new(WhatsAppChannel::new(
-            wa.access_token.clone(),
-            wa.phone_number_id.clone(),
-            wa.verify_token.clone(),
-            wa.allowed_numbers.clone(),
-        )));
-    }
-
-    if let Some(ref email_cfg) = config.channels_config.email {
-        channels.push(Arc::new(EmailChannel::new(email_cfg.clone())));
-    }
-
-    if let Some(ref irc) = config.channels_config.irc {
-        channels.push(Arc::new(IrcChannel::new(irc::IrcChannelConfig {
-            server: irc.server.clone(),
-            port: irc.port,
-            nickname: irc.nickname.clone(),
-            username: irc.username.clone(),
-            channels: irc.channels.clone(),
-            allowed_users: irc.allowed_users.clone(),
-            server_password: irc.server_password.clone(),
-            nickserv_password: irc.nickserv_password.clone(),
-            sasl_password: irc.sasl_password.clone(),
-            verify_tls: irc.verify_tls.unwrap_or(true),
-        })));
-    }
-
-    if let Some(ref lk) = config.channels_config.lark {
-        channels.push(Arc::new(LarkChannel::from_config(lk)));
+    if !plugins.is_empty() {
+        status!("  🔌 Plugins:  {}", plugins.names().join(", "));
     }
 
-    if let Some(ref dt) = config.channels_config.dingtalk {
-        channels.push(Arc::new(DingTalkChannel::new(
-            dt.client_id.clone(),
-            dt.client_secret.clone(),
-            dt.allowed_users.clone(),
-        )));
-    }
+    let bus = if pipe::is_active() {
+        None
+    } else {
+        bus::Bus::connect(&config.bus, |raw| resolve_channel_secret(&config, raw)).await?
+    };
+    let worker_bus = bus.as_ref().filter(|bus| bus.role() == BusRole::Worker);
+    let gateway_bus = bus.as_ref().filter(|bus| bus.role() == BusRole::Gateway);
 
-    if let Some(ref qq) = config.channels_config.qq {
-        channels.push(Arc::new(QQChannel::new(
-            qq.app_id.clone(),
-            qq.app_secret.clone(),
-            qq.allowed_users.clone(),
-        )));
-    }
+    // Collect active channels
+    let channels: Vec<Arc<dyn Channel>> = if pipe::is_active() {
+        vec![Arc::new(PipeChannel::new())]
+    } else if let Some(bus) = worker_bus {
+        // The gateways listen; replies are routed back to them
+        bus.remote_channels().await?
+    } else {
+        configured_channels(&config)?
+    };
+    // Pipe mode's stdout is the operator's own view, not a platform
+    let channels = if pipe::is_active() {
+        channels
+    } else {
+        dry_run::wrap(channels, &config.channels_config.dry_run)
+    };
+    // Shadows are fed by this instance's dispatcher, which a bus replaces
+    let shadows = &config.channels_config.shadows;
+    let channels = if bus.is_none() {
+        shadow::attach(channels, shadows, &control)?
+    } else {
+        if !shadows.is_empty() {
+            tracing::warn!("Shadow channels are not supported with [bus]; ignoring them");
+        }
+        control.attach_shadows(None);
+        channels
+    };
 
     if channels.is_empty() {
-        println!("No channels configured. Run `zeroclaw onboard` to set up channels.");
-        return Ok(());
+        status!("No channels configured. Run `zeroclaw onboard` to set up channels.");
+        return Ok(None);
     }
 
-    println!("🦀 ZeroClaw Channel Server");
-    println!("  🤖 Model:    {model}");
-    println!(
+    status!("🦀 ZeroClaw Channel Server");
+    status!("  🤖 Model:    {model}");
+    status!(
         "  🧠 Memory:   {} (auto-save: {})",
         config.memory.backend,
         if config.memory.auto_save { "on" } else { "off" }
     );
-    println!(
+    status!(
         "  📡 Channels: {}",
         channels
             .iter()
@@ -1202,9 +2980,20 @@ pub async fn start_channels(config: Config) -> Result<()> {
             .collect::<Vec<_>>()
             .join(", ")
     );
-    println!();
-    println!("  Listening for messages... (Ctrl+C to stop)");
-    println!();
+    let dry_run: Vec<&str> = channels
+        .iter()
+        .map(|c| c.name())
+        .filter(|name| !pipe::is_active() && config.channels_config.dry_run.applies_to(name))
+        .collect();
+    if !dry_run.is_empty() {
+        status!(
+            "  🧪 Dry run:  {} (replies are logged, not sent)",
+            dry_run.join(", ")
+        );
+    }
+    status!();
+    status!("  Listening for messages... (Ctrl+C to stop)");
+    status!();
 
     crate::health::mark_component_ok("channels");
 
@@ -1220,27 +3009,83 @@ pub async fn start_channels(config: Config) -> Result<()> {
     // Single message bus — all channels send messages here
     let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(100);
 
-    // Spawn a listener for each channel
+    // Spawn a listener for each channel, buffered through its own bounded
+    // inbound queue so a slow dispatcher cannot stall the channel's read loop.
+    let inbound_queue = &config.channels_config.inbound_queue;
     let mut handles = Vec::new();
-    for ch in &channels {
-        handles.push(spawn_supervised_listener(
-            ch.clone(),
-            tx.clone(),
-            initial_backoff_secs,
-            max_backoff_secs,
-        ));
+    if let Some(bus) = worker_bus {
+        handles.push(tokio::spawn(Arc::clone(bus).consume_inbound(tx.clone())));
+    } else if pipe::is_active() {
+        for ch in &channels {
+            let (channel_tx, queue) = inbound_queue::spawn_inbound_pump(
+                ch.name(),
+                inbound_queue.capacity,
+                inbound_queue.overflow,
+                tx.clone(),
+                Arc::clone(&observer),
+            );
+            control.register_inbound_queue(ch.name(), queue);
+            // Not restarted: stdin closing ends the run once in-flight
+            // messages are answered.
+            let ch = ch.clone();
+            handles.push(tokio::spawn(async move {
+                if let Err(e) = ch.listen(channel_tx).await {
+                    tracing::error!("Pipe input failed: {e}");
+                }
+            }));
+        }
+    } else {
+        let spawn_listeners = {
+            let channels = channels.clone();
+            let tx = tx.clone();
+            let (capacity, overflow) = (inbound_queue.capacity, inbound_queue.overflow);
+            let observer = Arc::clone(&observer);
+            let control = Arc::clone(&control);
+            move || -> Vec<tokio::task::JoinHandle<()>> {
+                channels
+                    .iter()
+                    .map(|ch| {
+                        let (channel_tx, queue) = inbound_queue::spawn_inbound_pump(
+                            ch.name(),
+                            capacity,
+                            overflow,
+                            tx.clone(),
+                            Arc::clone(&observer),
+                        );
+                        control.register_inbound_queue(ch.name(), queue);
+                        spawn_supervised_listener(
+                            ch.clone(),
+                            channel_tx,
+                            Arc::clone(&control),
+                            initial_backoff_secs,
+                            max_backoff_secs,
+                        )
+                    })
+                    .collect()
+            }
+        };
+        match leadership {
+            Some(leadership) => {
+                handles.push(tokio::spawn(follow_leadership(leadership, spawn_listeners)));
+            }
+            None => handles.extend(spawn_listeners()),
+        }
     }
     drop(tx); // Drop our copy so rx closes when all channels stop
 
-    let channels_by_name = Arc::new(
-        channels
-            .iter()
-            .map(|ch| (ch.name().to_string(), Arc::clone(ch)))
-            .collect::<HashMap<_, _>>(),
-    );
-    let max_in_flight_messages = compute_max_in_flight_messages(channels.len());
+    let manager =
+        ChannelManager::from_channels(&channels).with_tags(config.channels_config.tags.clone());
+    let channels_by_name = Arc::clone(manager.channels());
+    control.attach(manager);
+    let priority = &config.channels_config.priority;
+    let pool = dispatch::WorkerPool::from_config(&config.channels_config.dispatch, channels.len())
+        .with_reserved(if priority.enabled {
+            priority.reserved_workers
+        } else {
+            0
+        });
 
-    println!("  🚦 In-flight message limit: {max_in_flight_messages}");
+    status!("  🚦 In-flight message limit: {}", pool.workers());
 
     let runtime_ctx = Arc::new(ChannelRuntimeContext {
         channels_by_name,
@@ -1250,18 +3095,79 @@ pub async fn start_channels(config: Config) -> Result<()> {
         observer,
         system_prompt: Arc::new(system_prompt),
         model: Arc::new(model.clone()),
+        channel_models: Arc::new(
+            config
+                .channels_config
+                .shadows
+                .iter()
+                .filter_map(|shadow| Some((shadow.name.clone(), shadow.model.clone()?)))
+                .collect(),
+        ),
         temperature,
         auto_save_memory: config.memory.auto_save,
+        control: Arc::clone(&control),
+        plugins: Arc::clone(&plugins),
+        middleware,
+        identities,
+        failover,
+        quotas,
+        sessions,
+        transcription,
+        tts,
+        vision,
+        image_generator,
+        knowledge,
+        web_search,
+        web_search_max_results: config.web_search.max_results,
+        streaming: config.channels_config.streaming.clone(),
+        llm_timeout: Duration::from_secs(config.channels_config.dispatch.llm_timeout_secs),
+        alert_silencer,
+        topics,
+        preferences,
+        reminders,
+        groups,
+        personas,
+        response_cache,
+        announcements,
     });
 
-    run_message_dispatch_loop(rx, runtime_ctx, max_in_flight_messages).await;
+    let mut outbound = None;
+    let dispatch: std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> =
+        match gateway_bus {
+            Some(bus) => {
+                // The workers run the agent; this instance only relays
+                bus.register_channels(runtime_ctx.channels_by_name.keys())
+                    .await?;
+                let channels = Arc::clone(&runtime_ctx.channels_by_name);
+                outbound = Some(tokio::spawn(Arc::clone(bus).deliver_outbound(channels)));
+                Box::pin(Arc::clone(bus).forward_inbound(rx))
+            }
+            None => Box::pin(run_message_dispatch_loop(rx, runtime_ctx, pool)),
+        };
+    tokio::pin!(dispatch);
+    let reload = tokio::select! {
+        () = &mut dispatch => None,
+        reloaded = control.reload_requested() => Some(reloaded),
+    };
+
+    if reload.is_some() {
+        // Stopping the listeners closes the bus; the dispatcher then finishes
+        // in-flight messages before the runtime restarts.
+        for h in &handles {
+            h.abort();
+        }
+        dispatch.await;
+    }
 
     // Wait for all channel tasks
     for h in handles {
         let _ = h.await;
     }
+    if let Some(outbound) = outbound {
+        outbound.abort();
+    }
 
-    Ok(())
+    Ok(reload)
 }
 
 #[cfg(test)]
@@ -1297,6 +3203,52 @@ mod tests {
         tmp
     }
 
+    /// Runtime context answering on `channel` with `provider`, with every
+    /// optional service off. Set other fields with `..test_context(..)`.
+    fn test_context(
+        channel: Arc<dyn Channel>,
+        provider: Arc<dyn Provider>,
+    ) -> ChannelRuntimeContext {
+        let mut channels_by_name = HashMap::new();
+        channels_by_name.insert(channel.name().to_string(), channel);
+        ChannelRuntimeContext {
+            channels_by_name: Arc::new(channels_by_name),
+            provider,
+            memory: Arc::new(NoopMemory),
+            tools_registry: Arc::new(vec![]),
+            observer: Arc::new(NoopObserver),
+            system_prompt: Arc::new("test-system-prompt".to_string()),
+            model: Arc::new("test-model".to_string()),
+            channel_models: Arc::default(),
+            temperature: 0.0,
+            auto_save_memory: false,
+            control: Arc::new(control::ChannelControl::new()),
+            plugins: Arc::new(PluginRegistry::default()),
+            middleware: Arc::new(MiddlewareStack::default()),
+            identities: None,
+            failover: None,
+            quotas: None,
+            sessions: Arc::default(),
+            transcription: None,
+            tts: None,
+            vision: None,
+            image_generator: None,
+            knowledge: None,
+            web_search: None,
+            web_search_max_results: 5,
+            streaming: crate::config::StreamingConfig::default(),
+            llm_timeout: Duration::from_secs(300),
+            alert_silencer: None,
+            topics: None,
+            preferences: None,
+            reminders: None,
+            groups: None,
+            personas: None,
+            response_cache: None,
+            announcements: None,
+        }
+    }
+
     #[derive(Default)]
     struct RecordingChannel {
         sent_messages: tokio::sync::Mutex<Vec<String>>,
@@ -1308,6 +3260,14 @@ mod tests {
             "test-channel"
         }
 
+        fn capabilities(&self) -> traits::ChannelCapabilities {
+            // Renders Markdown, so replies carry no plain-text instructions
+            traits::ChannelCapabilities {
+                supports_markdown: true,
+                ..traits::ChannelCapabilities::of(self)
+            }
+        }
+
         async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
             self.sent_messages
                 .lock()
@@ -1412,53 +3372,463 @@ mod tests {
                 });
             }
 
-            Ok(ToolResult {
-                success: true,
-                output: r#"{"symbol":"BTC","price_usd":65000}"#.to_string(),
-                error: None,
-            })
+            Ok(ToolResult {
+                success: true,
+                output: r#"{"symbol":"BTC","price_usd":65000}"#.to_string(),
+                error: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn process_channel_message_executes_tool_calls_instead_of_sending_raw_json() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            tools_registry: Arc::new(vec![Box::new(MockPriceTool)]),
+            ..test_context(channel, Arc::new(ToolCallingProvider))
+        });
+
+        process_channel_message(
+            runtime_ctx,
+            traits::ChannelMessage {
+                id: "msg-1".to_string(),
+                reply_target: "chat-42".to_string(),
+                timestamp: 1,
+                received_at: 1,
+                ..traits::ChannelMessage::test(
+                    "test-channel",
+                    "alice",
+                    "What is the BTC price now?",
+                )
+            },
+        )
+        .await;
+
+        let sent_messages = channel_impl.sent_messages.lock().await;
+        assert_eq!(sent_messages.len(), 1);
+        assert!(sent_messages[0].starts_with("chat-42:"));
+        assert!(sent_messages[0].contains("BTC is currently around"));
+        assert!(!sent_messages[0].contains("\"tool_calls\""));
+        assert!(!sent_messages[0].contains("mock_price"));
+    }
+
+    struct HistoryLengthProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for HistoryLengthProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok("no history".to_string())
+        }
+
+        async fn chat_with_history(
+            &self,
+            messages: &[ChatMessage],
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok(format!("{} messages", messages.len()))
+        }
+    }
+
+    #[tokio::test]
+    async fn process_channel_message_replays_session_until_reset() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+
+        let runtime_ctx = Arc::new(test_context(channel, Arc::new(HistoryLengthProvider)));
+
+        for (i, content) in ["hello", "and then?", "/reset", "hi again"]
+            .iter()
+            .enumerate()
+        {
+            process_channel_message(
+                Arc::clone(&runtime_ctx),
+                traits::ChannelMessage {
+                    id: format!("msg-{i}"),
+                    timestamp: 1,
+                    received_at: 1,
+                    ..traits::ChannelMessage::test("test-channel", "alice", content)
+                },
+            )
+            .await;
+        }
+
+        let sent = channel_impl.sent_messages.lock().await;
+        assert_eq!(sent[0], "alice:2 messages");
+        assert_eq!(sent[1], "alice:4 messages");
+        assert!(sent[2].contains("new conversation"));
+        assert_eq!(sent[3], "alice:2 messages");
+    }
+
+    #[derive(Default)]
+    struct VoiceChannel {
+        sent: tokio::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for VoiceChannel {
+        fn name(&self) -> &str {
+            "test-channel"
+        }
+
+        fn capabilities(&self) -> traits::ChannelCapabilities {
+            traits::ChannelCapabilities {
+                supports_markdown: true,
+                ..traits::ChannelCapabilities::of(self)
+            }
+        }
+
+        async fn send(&self, message: &str, _recipient: &str) -> anyhow::Result<()> {
+            self.sent.lock().await.push(format!("text:{message}"));
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<traits::ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn supports_voice_notes(&self) -> bool {
+            true
+        }
+
+        async fn send_voice_note(
+            &self,
+            audio: Vec<u8>,
+            file_name: &str,
+            _recipient: &str,
+        ) -> anyhow::Result<()> {
+            let audio = String::from_utf8_lossy(&audio);
+            self.sent
+                .lock()
+                .await
+                .push(format!("voice:{file_name}:{audio}"));
+            Ok(())
+        }
+
+        async fn download_attachment(
+            &self,
+            _attachment: &traits::MediaAttachment,
+        ) -> anyhow::Result<Vec<u8>> {
+            Ok(b"media".to_vec())
+        }
+    }
+
+    /// Describes every image the same way, counting the (paid) calls.
+    struct CountingVision {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for CountingVision {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+
+        async fn describe_image(
+            &self,
+            _image: &[u8],
+            _mime_type: &str,
+            _prompt: &str,
+            _model: &str,
+        ) -> anyhow::Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok("a cat".into())
+        }
+    }
+
+    /// Transcribes every clip to the same text, counting the (paid) calls.
+    struct CountingTranscriber {
+        text: &'static str,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::transcription::Transcriber for CountingTranscriber {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn transcribe(&self, _audio: &[u8], _file_name: &str) -> anyhow::Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.text.to_string())
+        }
+    }
+
+    /// Drops messages containing "forbidden"; screens content.
+    struct ForbiddenWords;
+
+    #[async_trait::async_trait]
+    impl middleware::InboundMiddleware for ForbiddenWords {
+        fn name(&self) -> &str {
+            "forbidden"
+        }
+
+        fn inspects_content(&self) -> bool {
+            true
+        }
+
+        async fn on_inbound(&self, msg: traits::ChannelMessage) -> anyhow::Result<InboundAction> {
+            if msg.content.contains("forbidden") {
+                return Ok(InboundAction::Drop {
+                    reason: "forbidden word".into(),
+                });
+            }
+            Ok(InboundAction::Continue(msg))
+        }
+    }
+
+    fn media_message(sender: &str, kind: traits::MediaKind) -> traits::ChannelMessage {
+        traits::ChannelMessage {
+            attachments: vec![traits::MediaAttachment {
+                kind,
+                source: "media-1".into(),
+                mime_type: None,
+                file_name: None,
+                stored_key: None,
+            }],
+            ..traits::ChannelMessage::test("test-channel", sender, "")
+        }
+    }
+
+    #[tokio::test]
+    async fn voice_from_blocked_sender_is_not_transcribed() {
+        let channel_impl = Arc::new(VoiceChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+        let transcriber = Arc::new(CountingTranscriber {
+            text: "hello",
+            calls: AtomicUsize::new(0),
+        });
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            transcription: Some(Arc::new(crate::transcription::TranscriptionService::new(
+                transcriber.clone(),
+                1 << 20,
+            ))),
+            ..test_context(channel, Arc::new(HistoryLengthProvider))
+        });
+        runtime_ctx
+            .control
+            .block_user("test-channel", "mallory", None);
+
+        process_channel_message(
+            Arc::clone(&runtime_ctx),
+            media_message("mallory", traits::MediaKind::Voice),
+        )
+        .await;
+        assert_eq!(transcriber.calls.load(Ordering::SeqCst), 0);
+        assert!(channel_impl.sent.lock().await.is_empty());
+
+        process_channel_message(
+            runtime_ctx,
+            media_message("alice", traits::MediaKind::Voice),
+        )
+        .await;
+        assert_eq!(transcriber.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(channel_impl.sent.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn image_from_blocked_sender_is_not_described() {
+        let channel_impl = Arc::new(VoiceChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+        let vision = Arc::new(CountingVision {
+            calls: AtomicUsize::new(0),
+        });
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            vision: Some(Arc::new(crate::vision::VisionService::new(
+                vision.clone(),
+                "vision-model".into(),
+                "Describe".into(),
+                1 << 20,
+            ))),
+            ..test_context(channel, Arc::new(HistoryLengthProvider))
+        });
+        runtime_ctx
+            .control
+            .block_user("test-channel", "mallory", None);
+
+        let image = |sender| media_message(sender, traits::MediaKind::Image);
+        process_channel_message(Arc::clone(&runtime_ctx), image("mallory")).await;
+        assert_eq!(vision.calls.load(Ordering::SeqCst), 0);
+        assert!(channel_impl.sent.lock().await.is_empty());
+
+        process_channel_message(runtime_ctx, image("alice")).await;
+        assert_eq!(vision.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(channel_impl.sent.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn transcripts_pass_through_content_middleware() {
+        let channel_impl = Arc::new(VoiceChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+        let transcriber = Arc::new(CountingTranscriber {
+            text: "something forbidden",
+            calls: AtomicUsize::new(0),
+        });
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            transcription: Some(Arc::new(crate::transcription::TranscriptionService::new(
+                transcriber.clone(),
+                1 << 20,
+            ))),
+            middleware: Arc::new(MiddlewareStack::new(
+                middleware::MiddlewareChain::new(vec![Arc::new(ForbiddenWords)], Vec::new()),
+                HashMap::new(),
+            )),
+            ..test_context(channel, Arc::new(HistoryLengthProvider))
+        });
+
+        process_channel_message(
+            runtime_ctx,
+            media_message("alice", traits::MediaKind::Voice),
+        )
+        .await;
+        assert_eq!(transcriber.calls.load(Ordering::SeqCst), 1);
+        assert!(channel_impl.sent.lock().await.is_empty());
+    }
+
+    struct EchoSynthesizer;
+
+    #[async_trait::async_trait]
+    impl crate::tts::SpeechSynthesizer for EchoSynthesizer {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        async fn synthesize(&self, text: &str) -> anyhow::Result<crate::tts::SynthesizedAudio> {
+            Ok(crate::tts::SynthesizedAudio {
+                bytes: text.as_bytes().to_vec(),
+                file_name: "reply.ogg".into(),
+                mime_type: "audio/ogg".into(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn process_channel_message_speaks_replies_after_voice_on() {
+        let channel_impl = Arc::new(VoiceChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            tts: Some(Arc::new(crate::tts::TtsService::new(
+                Arc::new(EchoSynthesizer),
+                &crate::config::TtsConfig::default(),
+            ))),
+            ..test_context(channel, Arc::new(HistoryLengthProvider))
+        });
+
+        for (i, content) in ["hello", "/voice on", "hello again"].iter().enumerate() {
+            process_channel_message(
+                Arc::clone(&runtime_ctx),
+                traits::ChannelMessage {
+                    id: format!("msg-{i}"),
+                    timestamp: 1,
+                    received_at: 1,
+                    ..traits::ChannelMessage::test("test-channel", "alice", content)
+                },
+            )
+            .await;
         }
+
+        let sent = channel_impl.sent.lock().await;
+        assert_eq!(sent[0], "text:2 messages");
+        assert!(sent[1].starts_with("text:🔊"));
+        assert_eq!(sent[2], "voice:reply.ogg:4 messages");
     }
 
     #[tokio::test]
-    async fn process_channel_message_executes_tool_calls_instead_of_sending_raw_json() {
+    async fn process_channel_message_holds_reply_while_channel_muted() {
         let channel_impl = Arc::new(RecordingChannel::default());
         let channel: Arc<dyn Channel> = channel_impl.clone();
 
-        let mut channels_by_name = HashMap::new();
-        channels_by_name.insert(channel.name().to_string(), channel);
+        let control = Arc::new(control::ChannelControl::new());
+        control.mute("test-channel");
 
         let runtime_ctx = Arc::new(ChannelRuntimeContext {
-            channels_by_name: Arc::new(channels_by_name),
-            provider: Arc::new(ToolCallingProvider),
-            memory: Arc::new(NoopMemory),
-            tools_registry: Arc::new(vec![Box::new(MockPriceTool)]),
-            observer: Arc::new(NoopObserver),
-            system_prompt: Arc::new("test-system-prompt".to_string()),
-            model: Arc::new("test-model".to_string()),
-            temperature: 0.0,
-            auto_save_memory: false,
+            control: Arc::clone(&control),
+            ..test_context(
+                channel,
+                Arc::new(SlowProvider {
+                    delay: Duration::from_millis(1),
+                }),
+            )
         });
 
         process_channel_message(
             runtime_ctx,
             traits::ChannelMessage {
                 id: "msg-1".to_string(),
-                sender: "alice".to_string(),
                 reply_target: "chat-42".to_string(),
-                content: "What is the BTC price now?".to_string(),
-                channel: "test-channel".to_string(),
                 timestamp: 1,
+                received_at: 1,
+                ..traits::ChannelMessage::test("test-channel", "alice", "hello")
             },
         )
         .await;
 
-        let sent_messages = channel_impl.sent_messages.lock().await;
-        assert_eq!(sent_messages.len(), 1);
-        assert!(sent_messages[0].starts_with("chat-42:"));
-        assert!(sent_messages[0].contains("BTC is currently around"));
-        assert!(!sent_messages[0].contains("\"tool_calls\""));
-        assert!(!sent_messages[0].contains("mock_price"));
+        assert!(channel_impl.sent_messages.lock().await.is_empty());
+        let held = control.outbox();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].recipient, "chat-42");
+        assert_eq!(held[0].message, "echo: hello");
+    }
+
+    #[tokio::test]
+    async fn process_channel_message_answers_from_inbound_middleware() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+
+        let mut config = Config::default();
+        config.middleware.inbound = vec!["rate_limit".into()];
+        config.middleware.rate_limit = crate::config::schema::RateLimitMiddlewareConfig {
+            max_messages: 0,
+            window_secs: 60,
+            notice: Some("slow down".into()),
+        };
+
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            middleware: Arc::new(
+                middleware::build_stack(&config, &middleware::MiddlewareContext::default())
+                    .unwrap(),
+            ),
+            ..test_context(
+                channel,
+                Arc::new(SlowProvider {
+                    delay: Duration::from_millis(1),
+                }),
+            )
+        });
+
+        process_channel_message(
+            runtime_ctx,
+            traits::ChannelMessage {
+                id: "msg-1".to_string(),
+                reply_target: "chat-42".to_string(),
+                timestamp: 1,
+                received_at: 1,
+                ..traits::ChannelMessage::test("test-channel", "alice", "hello")
+            },
+        )
+        .await;
+
+        let sent = channel_impl.sent_messages.lock().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0], "chat-42:slow down");
     }
 
     struct NoopMemory;
@@ -1518,48 +3888,33 @@ mod tests {
         let channel_impl = Arc::new(RecordingChannel::default());
         let channel: Arc<dyn Channel> = channel_impl.clone();
 
-        let mut channels_by_name = HashMap::new();
-        channels_by_name.insert(channel.name().to_string(), channel);
-
-        let runtime_ctx = Arc::new(ChannelRuntimeContext {
-            channels_by_name: Arc::new(channels_by_name),
-            provider: Arc::new(SlowProvider {
+        let runtime_ctx = Arc::new(test_context(
+            channel,
+            Arc::new(SlowProvider {
                 delay: Duration::from_millis(250),
             }),
-            memory: Arc::new(NoopMemory),
-            tools_registry: Arc::new(vec![]),
-            observer: Arc::new(NoopObserver),
-            system_prompt: Arc::new("test-system-prompt".to_string()),
-            model: Arc::new("test-model".to_string()),
-            temperature: 0.0,
-            auto_save_memory: false,
-        });
+        ));
 
         let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(4);
         tx.send(traits::ChannelMessage {
-            id: "1".to_string(),
-            sender: "alice".to_string(),
-            reply_target: "alice".to_string(),
-            content: "hello".to_string(),
-            channel: "test-channel".to_string(),
             timestamp: 1,
+            received_at: 1,
+            ..traits::ChannelMessage::test("test-channel", "alice", "hello")
         })
         .await
         .unwrap();
         tx.send(traits::ChannelMessage {
             id: "2".to_string(),
-            sender: "bob".to_string(),
-            reply_target: "bob".to_string(),
-            content: "world".to_string(),
-            channel: "test-channel".to_string(),
             timestamp: 2,
+            received_at: 2,
+            ..traits::ChannelMessage::test("test-channel", "bob", "world")
         })
         .await
         .unwrap();
         drop(tx);
 
         let started = Instant::now();
-        run_message_dispatch_loop(rx, runtime_ctx, 2).await;
+        run_message_dispatch_loop(rx, runtime_ctx, dispatch::WorkerPool::new(2, 0)).await;
         let elapsed = started.elapsed();
 
         assert!(
@@ -1572,6 +3927,215 @@ mod tests {
         assert_eq!(sent_messages.len(), 2);
     }
 
+    #[tokio::test]
+    async fn message_dispatch_stops_receiving_while_workers_are_saturated() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let runtime_ctx = Arc::new(test_context(
+            channel_impl.clone(),
+            Arc::new(SlowProvider {
+                delay: Duration::from_millis(200),
+            }),
+        ));
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
+        let dispatch = tokio::spawn(run_message_dispatch_loop(
+            rx,
+            runtime_ctx,
+            dispatch::WorkerPool::new(1, 0),
+        ));
+        // One worker admits two messages; the third waits in the channel
+        for (id, sender) in [("1", "alice"), ("2", "bob"), ("3", "carol")] {
+            tx.send(traits::ChannelMessage {
+                id: id.to_string(),
+                ..traits::ChannelMessage::test("test-channel", sender, "hello")
+            })
+            .await
+            .unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let overflow = tx.try_send(traits::ChannelMessage {
+            id: "4".to_string(),
+            ..traits::ChannelMessage::test("test-channel", "dave", "hello")
+        });
+        assert!(matches!(
+            overflow,
+            Err(tokio::sync::mpsc::error::TrySendError::Full(_))
+        ));
+        drop(tx);
+
+        dispatch.await.unwrap();
+        assert_eq!(channel_impl.sent_messages.lock().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn message_dispatch_drops_redelivered_messages() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+
+        let runtime_ctx = Arc::new(test_context(
+            channel,
+            Arc::new(SlowProvider {
+                delay: Duration::from_millis(1),
+            }),
+        ));
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(4);
+        for _ in 0..2 {
+            tx.send(traits::ChannelMessage {
+                id: "same-id".to_string(),
+                timestamp: 1,
+                received_at: 1,
+                ..traits::ChannelMessage::test("test-channel", "alice", "hello")
+            })
+            .await
+            .unwrap();
+        }
+        drop(tx);
+
+        run_message_dispatch_loop(rx, runtime_ctx, dispatch::WorkerPool::new(2, 0)).await;
+
+        let sent_messages = channel_impl.sent_messages.lock().await;
+        assert_eq!(sent_messages.len(), 1);
+    }
+
+    struct EchoProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for EchoProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            // Earlier messages take longer so any reordering would show up.
+            let delay = if message.contains("first") { 150 } else { 1 };
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            Ok(message.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn message_dispatch_preserves_order_within_conversation() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let channel: Arc<dyn Channel> = channel_impl.clone();
+
+        let runtime_ctx = Arc::new(test_context(channel, Arc::new(EchoProvider)));
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(4);
+        for (id, content) in [("1", "first"), ("2", "second")] {
+            tx.send(traits::ChannelMessage {
+                id: id.to_string(),
+                timestamp: 1,
+                received_at: 1,
+                ..traits::ChannelMessage::test("test-channel", "alice", content)
+            })
+            .await
+            .unwrap();
+        }
+        drop(tx);
+
+        run_message_dispatch_loop(rx, runtime_ctx, dispatch::WorkerPool::new(4, 0)).await;
+
+        let sent_messages = channel_impl.sent_messages.lock().await;
+        assert_eq!(sent_messages.len(), 2);
+        assert!(sent_messages[0].contains("first"));
+        assert!(sent_messages[1].contains("second"));
+    }
+
+    struct PanickingProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for PanickingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            assert!(!message.contains("boom"), "provider blew up");
+            Ok(message.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn message_dispatch_moves_on_after_a_panicking_message() {
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let runtime_ctx = Arc::new(test_context(
+            channel_impl.clone(),
+            Arc::new(PanickingProvider),
+        ));
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(4);
+        for (id, content) in [("1", "boom"), ("2", "after")] {
+            tx.send(traits::ChannelMessage {
+                id: id.to_string(),
+                ..traits::ChannelMessage::test("test-channel", "alice", content)
+            })
+            .await
+            .unwrap();
+        }
+        drop(tx);
+
+        run_message_dispatch_loop(rx, runtime_ctx, dispatch::WorkerPool::new(2, 0)).await;
+
+        let sent_messages = channel_impl.sent_messages.lock().await;
+        assert_eq!(sent_messages.len(), 1);
+        assert!(sent_messages[0].contains("after"));
+    }
+
+    #[test]
+    fn delivery_instructions_follow_capabilities() {
+        let plain = traits::ChannelCapabilities {
+            supports_edit: false,
+            supports_files: false,
+            max_message_len: 4096,
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+        };
+        assert_eq!(
+            channel_delivery_instructions(plain),
+            vec![PLAIN_TEXT_INSTRUCTIONS]
+        );
+
+        let rich = traits::ChannelCapabilities {
+            supports_markdown: true,
+            supports_media_markers: true,
+            ..plain
+        };
+        assert_eq!(
+            channel_delivery_instructions(rich),
+            vec![MEDIA_MARKER_INSTRUCTIONS]
+        );
+    }
+
+    #[test]
+    fn imagine_prompt_parses_command() {
+        assert_eq!(imagine_prompt("/imagine a red fox"), Some("a red fox"));
+        assert_eq!(imagine_prompt("/imagine@zeroclaw_bot  cat "), Some("cat"));
+        assert_eq!(imagine_prompt("/imagine"), Some(""));
+        assert_eq!(imagine_prompt("/imagined"), None);
+        assert_eq!(imagine_prompt("please /imagine a fox"), None);
+    }
+
+    #[test]
+    fn nocache_question_parses_command() {
+        assert_eq!(
+            nocache_question("/nocache opening hours?"),
+            Some("opening hours?")
+        );
+        assert_eq!(
+            nocache_question("/nocache@zeroclaw_bot hours"),
+            Some("hours")
+        );
+        assert_eq!(nocache_question("/nocached hours"), None);
+        assert_eq!(nocache_question("hours"), None);
+    }
+
     #[test]
     fn prompt_contains_all_sections() {
         let ws = make_workspace();
@@ -1811,11 +4375,10 @@ mod tests {
     fn conversation_memory_key_uses_message_id() {
         let msg = traits::ChannelMessage {
             id: "msg_abc123".into(),
-            sender: "U123".into(),
             reply_target: "C456".into(),
-            content: "hello".into(),
-            channel: "slack".into(),
             timestamp: 1,
+            received_at: 1,
+            ..traits::ChannelMessage::test("slack", "U123", "hello")
         };
 
         assert_eq!(conversation_memory_key(&msg), "slack_U123_msg_abc123");
@@ -1825,19 +4388,17 @@ mod tests {
     fn conversation_memory_key_is_unique_per_message() {
         let msg1 = traits::ChannelMessage {
             id: "msg_1".into(),
-            sender: "U123".into(),
             reply_target: "C456".into(),
-            content: "first".into(),
-            channel: "slack".into(),
             timestamp: 1,
+            received_at: 1,
+            ..traits::ChannelMessage::test("slack", "U123", "first")
         };
         let msg2 = traits::ChannelMessage {
             id: "msg_2".into(),
-            sender: "U123".into(),
             reply_target: "C456".into(),
-            content: "second".into(),
-            channel: "slack".into(),
             timestamp: 2,
+            received_at: 2,
+            ..traits::ChannelMessage::test("slack", "U123", "second")
         };
 
         assert_ne!(
@@ -1853,19 +4414,17 @@ mod tests {
 
         let msg1 = traits::ChannelMessage {
             id: "msg_1".into(),
-            sender: "U123".into(),
             reply_target: "C456".into(),
-            content: "I'm Paul".into(),
-            channel: "slack".into(),
             timestamp: 1,
+            received_at: 1,
+            ..traits::ChannelMessage::test("slack", "U123", "I'm Paul")
         };
         let msg2 = traits::ChannelMessage {
             id: "msg_2".into(),
-            sender: "U123".into(),
             reply_target: "C456".into(),
-            content: "I'm 45".into(),
-            channel: "slack".into(),
             timestamp: 2,
+            received_at: 2,
+            ..traits::ChannelMessage::test("slack", "U123", "I'm 45")
         };
 
         mem.store(
@@ -1891,6 +4450,37 @@ mod tests {
         assert!(recalled.iter().any(|entry| entry.content.contains("45")));
     }
 
+    #[tokio::test]
+    async fn shadow_turns_are_not_auto_saved() {
+        let tmp = TempDir::new().unwrap();
+        let mem = Arc::new(SqliteMemory::new(tmp.path()).unwrap());
+        let control = Arc::new(control::ChannelControl::new());
+        control.attach_shadows(Some(Arc::new(shadow::ShadowLog::new(&[
+            crate::config::ShadowChannelConfig {
+                name: "test-channel".into(),
+                source: "telegram".into(),
+                model: None,
+                persona: None,
+            },
+        ]))));
+        let channel_impl = Arc::new(RecordingChannel::default());
+        let runtime_ctx = Arc::new(ChannelRuntimeContext {
+            memory: mem.clone(),
+            auto_save_memory: true,
+            control,
+            ..test_context(channel_impl.clone(), Arc::new(EchoProvider))
+        });
+
+        process_channel_message(
+            runtime_ctx,
+            traits::ChannelMessage::test("test-channel", "alice", "remember me"),
+        )
+        .await;
+
+        assert_eq!(channel_impl.sent_messages.lock().await.len(), 1);
+        assert_eq!(mem.count().await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn build_memory_context_includes_recalled_entries() {
         let tmp = TempDir::new().unwrap();
@@ -1899,7 +4489,7 @@ mod tests {
             .await
             .unwrap();
 
-        let context = build_memory_context(&mem, "age").await;
+        let context = build_memory_context(&mem, "age", None).await;
         assert!(context.contains("[Memory context]"));
         assert!(context.contains("Age is 45"));
     }
@@ -2100,6 +4690,85 @@ mod tests {
         }
     }
 
+    struct ClosedGatewayChannel {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for ClosedGatewayChannel {
+        fn name(&self) -> &str {
+            "test-supervised-closed"
+        }
+
+        async fn send(&self, _message: &str, _recipient: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<traits::ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(gateway::GatewayClosed {
+                channel: "test-supervised-closed".into(),
+                code: 4914,
+                reason: "bot banned".into(),
+            }
+            .into())
+        }
+    }
+
+    struct PanickingChannel {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for PanickingChannel {
+        fn name(&self) -> &str {
+            "test-supervised-panic"
+        }
+
+        async fn send(&self, _message: &str, _recipient: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<traits::ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            panic!("gateway frame exploded");
+        }
+    }
+
+    #[tokio::test]
+    async fn supervised_listener_survives_panics() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let channel: Arc<dyn Channel> = Arc::new(PanickingChannel {
+            calls: Arc::clone(&calls),
+        });
+        let control = Arc::new(control::ChannelControl::new());
+
+        let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
+        let handle = spawn_supervised_listener(channel, tx, Arc::clone(&control), 1, 1);
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(!handle.is_finished());
+        drop(rx);
+        handle.abort();
+        let _ = handle.await;
+
+        let snapshot = crate::health::snapshot_json();
+        let component = &snapshot["components"]["channel:test-supervised-panic"];
+        assert_eq!(component["status"], "error");
+        assert_eq!(component["last_error"], "panicked: gateway frame exploded");
+        assert_eq!(
+            control.recent_errors(1)[0].error,
+            "panicked: gateway frame exploded"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
     #[tokio::test]
     async fn supervised_listener_marks_error_and_restarts_on_failures() {
         let calls = Arc::new(AtomicUsize::new(0));
@@ -2109,7 +4778,8 @@ mod tests {
         });
 
         let (tx, rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
-        let handle = spawn_supervised_listener(channel, tx, 1, 1);
+        let handle =
+            spawn_supervised_listener(channel, tx, Arc::new(control::ChannelControl::new()), 1, 1);
 
         tokio::time::sleep(Duration::from_millis(80)).await;
         drop(rx);
@@ -2126,4 +4796,27 @@ mod tests {
             .contains("listen boom"));
         assert!(calls.load(Ordering::SeqCst) >= 1);
     }
+
+    #[tokio::test]
+    async fn supervised_listener_stops_when_the_gateway_is_closed_for_good() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let channel: Arc<dyn Channel> = Arc::new(ClosedGatewayChannel {
+            calls: Arc::clone(&calls),
+        });
+        let control = Arc::new(control::ChannelControl::new());
+
+        let (tx, _rx) = tokio::sync::mpsc::channel::<traits::ChannelMessage>(1);
+        let handle = spawn_supervised_listener(channel, tx, Arc::clone(&control), 1, 1);
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("the supervisor should give up")
+            .unwrap();
+
+        let snapshot = crate::health::snapshot_json();
+        let component = &snapshot["components"]["channel:test-supervised-closed"];
+        assert_eq!(component["status"], "failed");
+        assert_eq!(component["restart_count"], 0);
+        assert!(control.recent_errors(1)[0].error.contains("code 4914"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,197 @@
+//! Timeouts and connection settings for channel network calls.
+//!
+//! Channels build their HTTP clients with [`http_client_for`] and open
+//! gateway WebSockets with [`connect_websocket`], so no call waits on a hung
+//! connection forever. [`with_deadline`] bounds the processing of one
+//! inbound message. The limits come from `[channels_config.timeouts]` and
+//! the client settings from `[channels_config.http]` via [`configure`],
+//! which runs before any channel is built. Both clients and WebSockets get
+//! the channel's TLS restrictions from [`super::tls`].
+
+use crate::config::{
+    ChannelsConfig, HttpClientConfig, HttpClientsConfig, HttpVersionPreference,
+    NetworkTimeoutsConfig,
+};
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+pub type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+static TIMEOUTS: LazyLock<RwLock<NetworkTimeoutsConfig>> =
+    LazyLock::new(|| RwLock::new(NetworkTimeoutsConfig::default()));
+
+static CLIENTS: LazyLock<RwLock<HttpClientsConfig>> =
+    LazyLock::new(|| RwLock::new(HttpClientsConfig::default()));
+
+/// Apply `config` to clients and connections created from now on.
+pub fn configure(config: &ChannelsConfig) {
+    *TIMEOUTS.write() = config.timeouts.clone();
+    *CLIENTS.write() = config.http.clone();
+}
+
+fn timeouts() -> NetworkTimeoutsConfig {
+    TIMEOUTS.read().clone()
+}
+
+/// An HTTP client with the default connection settings.
+pub fn http_client() -> reqwest::Client {
+    let settings = CLIENTS.read().default.clone();
+    build_client(&settings)
+}
+
+/// An HTTP client with the connection settings of the `channel` type.
+pub fn http_client_for(channel: &str) -> reqwest::Client {
+    let settings = CLIENTS.read().for_channel(channel).clone();
+    build_client(&settings)
+}
+
+/// Apply the configured timeouts and `settings` to a client builder.
+fn client_builder(settings: &HttpClientConfig) -> reqwest::ClientBuilder {
+    let timeouts = timeouts();
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(timeouts.connect_secs.max(1)))
+        .timeout(Duration::from_secs(timeouts.request_secs.max(1)))
+        .pool_idle_timeout(Duration::from_secs(settings.pool_idle_timeout_secs))
+        .tcp_nodelay(settings.tcp_nodelay);
+    if let Some(max_idle) = settings.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if settings.tcp_keepalive_secs > 0 {
+        builder = builder.tcp_keepalive(Duration::from_secs(settings.tcp_keepalive_secs));
+    }
+    builder = match settings.http_version {
+        HttpVersionPreference::Http1 => builder.http1_only(),
+        HttpVersionPreference::Auto => builder,
+        HttpVersionPreference::Http2 => builder.http2_prior_knowledge(),
+    };
+    if settings.http2_keepalive_secs > 0 {
+        builder = builder
+            .http2_keep_alive_interval(Duration::from_secs(settings.http2_keepalive_secs))
+            .http2_keep_alive_while_idle(true);
+    }
+    if let Some(tls) = super::tls::client_config(settings) {
+        // Preconfigured TLS skips reqwest's own ALPN setup
+        let mut tls = (*tls).clone();
+        tls.alpn_protocols = match settings.http_version {
+            HttpVersionPreference::Http1 => vec![b"http/1.1".to_vec()],
+            HttpVersionPreference::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            HttpVersionPreference::Http2 => vec![b"h2".to_vec()],
+        };
+        builder = builder.use_preconfigured_tls(tls);
+    }
+    builder
+}
+
+fn build_client(settings: &HttpClientConfig) -> reqwest::Client {
+    client_builder(settings).build().unwrap_or_else(|e| {
+        tracing::warn!("Failed to build HTTP client with configured settings: {e}");
+        reqwest::Client::new()
+    })
+}
+
+/// Request timeout of a long poll that the server holds for `poll_secs`.
+pub fn long_poll_timeout(poll_secs: u64) -> Duration {
+    Duration::from_secs(poll_secs + timeouts().request_secs.max(1))
+}
+
+/// Slowest transfer rate file uploads are allowed before timing out.
+const MIN_TRANSFER_BYTES_PER_SEC: u64 = 64 * 1024;
+
+/// Request timeout of a file upload of `size` bytes (unknown sizes get the
+/// allowance of the largest file channels accept).
+pub fn transfer_timeout(size: Option<u64>) -> Duration {
+    let size = size.unwrap_or(50 * 1024 * 1024);
+    Duration::from_secs(timeouts().request_secs.max(1) + size / MIN_TRANSFER_BYTES_PER_SEC)
+}
+
+/// Open a gateway WebSocket of the `channel` type with its TLS settings,
+/// giving up after the configured timeout.
+pub async fn connect_websocket(channel: &str, url: &str) -> Result<WebSocket> {
+    let limit = Duration::from_secs(timeouts().gateway_connect_secs.max(1));
+    let tls = super::tls::client_config(CLIENTS.read().for_channel(channel));
+    connect_websocket_within(url, limit, tls).await
+}
+
+async fn connect_websocket_within(
+    url: &str,
+    limit: Duration,
+    tls: Option<Arc<rustls::ClientConfig>>,
+) -> Result<WebSocket> {
+    let connector = tls.map(Connector::Rustls);
+    let connect = tokio_tungstenite::connect_async_tls_with_config(url, None, false, connector);
+    let (stream, _) = tokio::time::timeout(limit, connect)
+        .await
+        .with_context(|| format!("WebSocket connect timed out after {}s", limit.as_secs()))??;
+    Ok(stream)
+}
+
+/// The per-message processing deadline, if enabled.
+pub fn message_deadline() -> Option<Duration> {
+    match timeouts().message_deadline_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Run `fut` within `deadline`, such as [`message_deadline`]. Returns
+/// `None` when the deadline passed first and `fut` was dropped.
+pub async fn with_deadline<F: Future>(deadline: Option<Duration>, fut: F) -> Option<F::Output> {
+    match deadline {
+        Some(limit) => tokio::time::timeout(limit, fut).await.ok(),
+        None => Some(fut.await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stalled_websocket_handshakes_time_out() {
+        // Accepts the TCP connection but never answers the upgrade
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(socket);
+        });
+
+        let err =
+            connect_websocket_within(&format!("ws://{addr}"), Duration::from_millis(200), None)
+                .await
+                .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        server.abort();
+    }
+
+    #[test]
+    fn channel_clients_use_their_own_settings() {
+        let mut config = HttpClientsConfig::default();
+        config.channels.insert(
+            "qq".into(),
+            HttpClientConfig {
+                http_version: HttpVersionPreference::Http2,
+                http2_keepalive_secs: 20,
+                pool_max_idle_per_host: Some(4),
+                ..HttpClientConfig::default()
+            },
+        );
+        assert_eq!(config.for_channel("qq").pool_max_idle_per_host, Some(4));
+        assert_eq!(config.for_channel("telegram"), &HttpClientConfig::default());
+        assert!(client_builder(config.for_channel("qq")).build().is_ok());
+
+        // An unreadable CA file is skipped instead of failing the client
+        let settings = HttpClientConfig {
+            ca_certs: vec!["/nonexistent/ca.pem".into()],
+            ..HttpClientConfig::default()
+        };
+        assert!(client_builder(&settings).build().is_ok());
+    }
+}
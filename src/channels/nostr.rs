@@ -0,0 +1,987 @@
+//! Nostr direct messages.
+//!
+//! The channel subscribes on every configured relay to DMs addressed to its
+//! key: legacy NIP-04 messages (kind 4, AES-CBC) and NIP-17 private DMs
+//! (kind 14 rumors, sealed in kind 13 and gift-wrapped in kind 1059, both
+//! with NIP-44 encryption). Replies use the protocol the person last wrote
+//! with, NIP-17 by default, and are published to every relay. The private
+//! key is resolved through the secret providers, so it can live in an
+//! environment variable, a file or a vault instead of the config.
+
+use super::dedup::InboundDeduplicator;
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
+use crate::security::ManagedSecret;
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, StreamCipher};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use k256::schnorr::{Signature, SigningKey, VerifyingKey};
+use parking_lot::Mutex;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+const KIND_ENCRYPTED_DM: u16 = 4;
+const KIND_SEAL: u16 = 13;
+const KIND_CHAT_MESSAGE: u16 = 14;
+const KIND_GIFT_WRAP: u16 = 1059;
+
+/// Seals and gift wraps are backdated by up to this much to hide when a
+/// message was sent, so subscriptions reach back as far.
+const TIMESTAMP_TWEAK_SECS: u64 = 2 * 24 * 60 * 60;
+
+const SUBSCRIPTION_ID: &str = "zeroclaw-dms";
+
+/// How long a relay gets to acknowledge a published event.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ── Keys and events ─────────────────────────────────────────────
+
+/// A Nostr key pair.
+pub struct NostrKeys {
+    signing: SigningKey,
+    secret: k256::SecretKey,
+}
+
+impl NostrKeys {
+    /// A private key as `nsec1...` or 64 hex digits.
+    pub fn parse(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let bytes = if encoded.starts_with("nsec1") {
+            bech32_decode("nsec", encoded)?
+        } else {
+            hex::decode(encoded).context("Nostr private keys are nsec1... or hex")?
+        };
+        Self::from_bytes(&bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self {
+            signing: SigningKey::from_bytes(bytes)
+                .map_err(|_| anyhow::anyhow!("Invalid Nostr private key"))?,
+            secret: k256::SecretKey::from_slice(bytes)
+                .map_err(|_| anyhow::anyhow!("Invalid Nostr private key"))?,
+        })
+    }
+
+    /// A fresh random key pair (for gift wraps).
+    pub fn generate() -> Self {
+        loop {
+            if let Ok(keys) = Self::from_bytes(&rand::random::<[u8; 32]>()) {
+                return keys;
+            }
+        }
+    }
+
+    /// The x-only public key, hex encoded.
+    pub fn public_key(&self) -> String {
+        hex::encode(self.signing.verifying_key().to_bytes())
+    }
+
+    pub fn npub(&self) -> String {
+        bech32_encode("npub", &self.signing.verifying_key().to_bytes())
+    }
+
+    /// X coordinate of the ECDH point shared with `pubkey`.
+    fn shared_x(&self, pubkey: &str) -> Result<[u8; 32]> {
+        let x = hex::decode(pubkey).context("Invalid Nostr public key")?;
+        if x.len() != 32 {
+            bail!("Invalid Nostr public key");
+        }
+        // x-only keys stand for the point with the even y
+        let public = k256::PublicKey::from_sec1_bytes(&[&[0x02], x.as_slice()].concat())
+            .map_err(|_| anyhow::anyhow!("Invalid Nostr public key"))?;
+        let shared =
+            k256::ecdh::diffie_hellman(self.secret.to_nonzero_scalar(), public.as_affine());
+        let mut out = [0u8; 32];
+        out.copy_from_slice(shared.raw_secret_bytes());
+        Ok(out)
+    }
+
+    fn conversation_key(&self, pubkey: &str) -> Result<[u8; 32]> {
+        Ok(nip44::conversation_key(&self.shared_x(pubkey)?))
+    }
+
+    fn sign(
+        &self,
+        created_at: u64,
+        kind: u16,
+        tags: Vec<Vec<String>>,
+        content: String,
+    ) -> Result<Event> {
+        let mut event = Event::unsigned(self.public_key(), created_at, kind, tags, content);
+        let aux: [u8; 32] = rand::random();
+        let sig = self
+            .signing
+            .sign_raw(&event.compute_id(), &aux)
+            .map_err(|e| anyhow::anyhow!("Signing failed: {e}"))?;
+        event.sig = hex::encode(sig.to_bytes());
+        Ok(event)
+    }
+}
+
+/// A Nostr event (NIP-01). Rumors are events without a signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub pubkey: String,
+    pub created_at: u64,
+    pub kind: u16,
+    #[serde(default)]
+    pub tags: Vec<Vec<String>>,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sig: String,
+}
+
+impl Event {
+    fn unsigned(
+        pubkey: String,
+        created_at: u64,
+        kind: u16,
+        tags: Vec<Vec<String>>,
+        content: String,
+    ) -> Self {
+        let mut event = Self {
+            id: String::new(),
+            pubkey,
+            created_at,
+            kind,
+            tags,
+            content,
+            sig: String::new(),
+        };
+        event.id = hex::encode(event.compute_id());
+        event
+    }
+
+    /// SHA-256 of the NIP-01 serialization.
+    fn compute_id(&self) -> [u8; 32] {
+        let serialized = serde_json::json!([
+            0,
+            self.pubkey,
+            self.created_at,
+            self.kind,
+            self.tags,
+            self.content
+        ])
+        .to_string();
+        Sha256::digest(serialized.as_bytes()).into()
+    }
+
+    fn has_valid_id(&self) -> bool {
+        hex::encode(self.compute_id()) == self.id
+    }
+
+    /// Check the id and the author's signature.
+    fn verify(&self) -> Result<()> {
+        if !self.has_valid_id() {
+            bail!("Event id does not match its content");
+        }
+        let pubkey = hex::decode(&self.pubkey).context("Invalid event pubkey")?;
+        let sig = hex::decode(&self.sig).context("Invalid event signature")?;
+        let key = VerifyingKey::from_bytes(&pubkey)
+            .map_err(|_| anyhow::anyhow!("Invalid event pubkey"))?;
+        let sig = Signature::try_from(sig.as_slice())
+            .map_err(|_| anyhow::anyhow!("Invalid event signature"))?;
+        key.verify_raw(&self.compute_id(), &sig)
+            .map_err(|_| anyhow::anyhow!("Event signature does not verify"))
+    }
+
+    /// Whether a `p` tag names `pubkey`.
+    fn addresses(&self, pubkey: &str) -> bool {
+        self.tags.iter().any(|tag| {
+            tag.first().map(String::as_str) == Some("p")
+                && tag.get(1).map(String::as_str) == Some(pubkey)
+        })
+    }
+}
+
+// ── Encryption ──────────────────────────────────────────────────
+
+/// NIP-04: AES-256-CBC with the ECDH x coordinate as key,
+/// `<base64 ciphertext>?iv=<base64 iv>`.
+mod nip04 {
+    use super::{BlockDecryptMut, BlockEncryptMut, Context, Engine, KeyIvInit, Pkcs7, Result};
+
+    pub fn encrypt(key: &[u8; 32], plaintext: &str) -> Result<String> {
+        let iv: [u8; 16] = rand::random();
+        let ciphertext = cbc::Encryptor::<aes::Aes256>::new_from_slices(key, &iv)
+            .map_err(|e| anyhow::anyhow!("NIP-04: {e}"))?
+            .encrypt_padded_vec_mut::<Pkcs7>(plaintext.as_bytes());
+        let b64 = base64::engine::general_purpose::STANDARD;
+        Ok(format!("{}?iv={}", b64.encode(ciphertext), b64.encode(iv)))
+    }
+
+    pub fn decrypt(key: &[u8; 32], content: &str) -> Result<String> {
+        let (ciphertext, iv) = content
+            .split_once("?iv=")
+            .context("NIP-04 content has no iv")?;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let ciphertext = b64.decode(ciphertext).context("Malformed NIP-04 content")?;
+        let iv = b64.decode(iv).context("Malformed NIP-04 iv")?;
+        let plaintext = cbc::Decryptor::<aes::Aes256>::new_from_slices(key, &iv)
+            .map_err(|e| anyhow::anyhow!("NIP-04: {e}"))?
+            .decrypt_padded_vec_mut::<Pkcs7>(&ciphertext)
+            .map_err(|_| anyhow::anyhow!("NIP-04 decryption failed"))?;
+        String::from_utf8(plaintext).context("NIP-04 message is not UTF-8")
+    }
+}
+
+/// NIP-44 version 2: ChaCha20 with HKDF-derived keys, HMAC-SHA256 and
+/// length-hiding padding.
+mod nip44 {
+    use super::{bail, Context, Engine, Hkdf, Hmac, KeyIvInit, Mac, Result, Sha256, StreamCipher};
+
+    const VERSION: u8 = 2;
+
+    pub fn conversation_key(shared_x: &[u8; 32]) -> [u8; 32] {
+        let (prk, _) = Hkdf::<Sha256>::extract(Some(b"nip44-v2"), shared_x);
+        prk.into()
+    }
+
+    /// ChaCha20 key, ChaCha20 nonce and HMAC key for one message.
+    fn message_keys(conversation_key: &[u8; 32], nonce: &[u8]) -> Result<[u8; 76]> {
+        let hkdf = Hkdf::<Sha256>::from_prk(conversation_key)
+            .map_err(|_| anyhow::anyhow!("Invalid NIP-44 conversation key"))?;
+        let mut keys = [0u8; 76];
+        hkdf.expand(nonce, &mut keys)
+            .map_err(|_| anyhow::anyhow!("NIP-44 key derivation failed"))?;
+        Ok(keys)
+    }
+
+    pub fn padded_len(len: usize) -> usize {
+        if len <= 32 {
+            return 32;
+        }
+        let next_power = 1usize << (usize::BITS - (len - 1).leading_zeros());
+        let chunk = if next_power <= 256 {
+            32
+        } else {
+            next_power / 8
+        };
+        chunk * ((len - 1) / chunk + 1)
+    }
+
+    fn mac(keys: &[u8; 76], nonce: &[u8], ciphertext: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&keys[44..]).expect("HMAC takes keys of any length");
+        mac.update(nonce);
+        mac.update(ciphertext);
+        mac
+    }
+
+    pub fn encrypt(conversation_key: &[u8; 32], plaintext: &str) -> Result<String> {
+        encrypt_with_nonce(conversation_key, plaintext, &rand::random())
+    }
+
+    pub fn encrypt_with_nonce(
+        conversation_key: &[u8; 32],
+        plaintext: &str,
+        nonce: &[u8; 32],
+    ) -> Result<String> {
+        let len = plaintext.len();
+        if !(1..=65_535).contains(&len) {
+            bail!("NIP-44 messages are 1 to 65535 bytes");
+        }
+        let keys = message_keys(conversation_key, nonce)?;
+        let mut buf = Vec::with_capacity(2 + padded_len(len));
+        buf.extend_from_slice(&u16::try_from(len)?.to_be_bytes());
+        buf.extend_from_slice(plaintext.as_bytes());
+        buf.resize(2 + padded_len(len), 0);
+        chacha20::ChaCha20::new_from_slices(&keys[..32], &keys[32..44])
+            .map_err(|e| anyhow::anyhow!("NIP-44: {e}"))?
+            .apply_keystream(&mut buf);
+        let tag = mac(&keys, nonce, &buf).finalize().into_bytes();
+
+        let mut payload = Vec::with_capacity(1 + 32 + buf.len() + 32);
+        payload.push(VERSION);
+        payload.extend_from_slice(nonce);
+        payload.extend_from_slice(&buf);
+        payload.extend_from_slice(&tag);
+        Ok(base64::engine::general_purpose::STANDARD.encode(payload))
+    }
+
+    pub fn decrypt(conversation_key: &[u8; 32], payload: &str) -> Result<String> {
+        let payload = base64::engine::general_purpose::STANDARD
+            .decode(payload)
+            .context("Malformed NIP-44 payload")?;
+        if !(99..=65_603).contains(&payload.len()) || payload[0] != VERSION {
+            bail!("Unsupported NIP-44 payload");
+        }
+        let (nonce, rest) = payload[1..].split_at(32);
+        let (ciphertext, tag) = rest.split_at(rest.len() - 32);
+        let keys = message_keys(conversation_key, nonce)?;
+        mac(&keys, nonce, ciphertext)
+            .verify_slice(tag)
+            .map_err(|_| anyhow::anyhow!("NIP-44 message authentication failed"))?;
+
+        let mut buf = ciphertext.to_vec();
+        chacha20::ChaCha20::new_from_slices(&keys[..32], &keys[32..44])
+            .map_err(|e| anyhow::anyhow!("NIP-44: {e}"))?
+            .apply_keystream(&mut buf);
+        let len = usize::from(u16::from_be_bytes([buf[0], buf[1]]));
+        if len == 0 || buf.len() != 2 + padded_len(len) {
+            bail!("Invalid NIP-44 padding");
+        }
+        String::from_utf8(buf[2..2 + len].to_vec()).context("NIP-44 message is not UTF-8")
+    }
+}
+
+// ── Gift wraps (NIP-59) ─────────────────────────────────────────
+
+fn backdated(now: u64) -> u64 {
+    now.saturating_sub(rand::thread_rng().gen_range(0..TIMESTAMP_TWEAK_SECS))
+}
+
+/// A NIP-17 message from `sender` to `recipient`: the unsigned kind 14
+/// rumor, sealed by the sender and gift-wrapped with a one-time key.
+fn gift_wrap(sender: &NostrKeys, recipient: &str, message: &str, now: u64) -> Result<Event> {
+    let tags = vec![vec!["p".to_string(), recipient.to_string()]];
+    let rumor = Event::unsigned(
+        sender.public_key(),
+        now,
+        KIND_CHAT_MESSAGE,
+        tags.clone(),
+        message.to_string(),
+    );
+    let sealed = nip44::encrypt(
+        &sender.conversation_key(recipient)?,
+        &serde_json::to_string(&rumor)?,
+    )?;
+    let seal = sender.sign(backdated(now), KIND_SEAL, vec![], sealed)?;
+
+    let ephemeral = NostrKeys::generate();
+    let wrapped = nip44::encrypt(
+        &ephemeral.conversation_key(recipient)?,
+        &serde_json::to_string(&seal)?,
+    )?;
+    ephemeral.sign(backdated(now), KIND_GIFT_WRAP, tags, wrapped)
+}
+
+/// The rumor inside a gift wrap addressed to `keys`. Its author is the
+/// seal's signer.
+fn unwrap_gift(keys: &NostrKeys, wrap: &Event) -> Result<Event> {
+    let seal: Event = serde_json::from_str(&nip44::decrypt(
+        &keys.conversation_key(&wrap.pubkey)?,
+        &wrap.content,
+    )?)
+    .context("Malformed seal")?;
+    if seal.kind != KIND_SEAL {
+        bail!("Gift wrap holds a kind {} event, not a seal", seal.kind);
+    }
+    seal.verify()?;
+    let rumor: Event = serde_json::from_str(&nip44::decrypt(
+        &keys.conversation_key(&seal.pubkey)?,
+        &seal.content,
+    )?)
+    .context("Malformed rumor")?;
+    if rumor.pubkey != seal.pubkey {
+        bail!("Rumor author does not match its seal");
+    }
+    if !rumor.has_valid_id() {
+        bail!("Rumor id does not match its content");
+    }
+    Ok(rumor)
+}
+
+// ── Bech32 (NIP-19) ─────────────────────────────────────────────
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATORS: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk: u32 = 1;
+    for &value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ u32::from(value);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn bech32_hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|b| b & 31));
+    expanded
+}
+
+/// Regroup `data` from `from`-bit to `to`-bit values.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>> {
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut out = Vec::new();
+    let max = (1u32 << to) - 1;
+    for &value in data {
+        acc = (acc << from) | u32::from(value);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(u8::try_from((acc >> bits) & max)?);
+        }
+    }
+    if pad && bits > 0 {
+        out.push(u8::try_from((acc << (to - bits)) & max)?);
+    } else if !pad && (bits >= from || (acc << (to - bits)) & max != 0) {
+        bail!("Invalid bech32 padding");
+    }
+    Ok(out)
+}
+
+fn bech32_encode(hrp: &str, bytes: &[u8]) -> String {
+    let data = convert_bits(bytes, 8, 5, true).unwrap_or_default();
+    let mut values = bech32_hrp_expand(hrp);
+    values.extend(&data);
+    values.extend([0u8; 6]);
+    let checksum = bech32_polymod(&values) ^ 1;
+    let mut out = format!("{hrp}1");
+    for value in data {
+        out.push(char::from(BECH32_CHARSET[usize::from(value)]));
+    }
+    for i in 0..6 {
+        let value = (checksum >> (5 * (5 - i))) & 31;
+        out.push(char::from(BECH32_CHARSET[value as usize]));
+    }
+    out
+}
+
+/// The bytes of a bech32 string with human-readable part `hrp`.
+fn bech32_decode(hrp: &str, encoded: &str) -> Result<Vec<u8>> {
+    let encoded = encoded.to_ascii_lowercase();
+    let (found_hrp, data) = encoded.rsplit_once('1').context("Invalid bech32 string")?;
+    if found_hrp != hrp {
+        bail!("Expected a {hrp} key, got {found_hrp}");
+    }
+    let values = data
+        .bytes()
+        .map(|c| {
+            BECH32_CHARSET
+                .iter()
+                .position(|&b| b == c)
+                .and_then(|i| u8::try_from(i).ok())
+                .context("Invalid bech32 character")
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    if values.len() < 6 {
+        bail!("Invalid bech32 string");
+    }
+    let mut checked = bech32_hrp_expand(hrp);
+    checked.extend(&values);
+    if bech32_polymod(&checked) != 1 {
+        bail!("Invalid bech32 checksum");
+    }
+    convert_bits(&values[..values.len() - 6], 5, 8, false)
+}
+
+/// A public key as hex, from `npub1...` or hex.
+pub fn parse_public_key(encoded: &str) -> Result<String> {
+    let encoded = encoded.trim();
+    let bytes = if encoded.starts_with("npub1") {
+        bech32_decode("npub", encoded)?
+    } else {
+        hex::decode(encoded).context("Nostr public keys are npub1... or hex")?
+    };
+    if bytes.len() != 32 {
+        bail!("Nostr public keys are 32 bytes");
+    }
+    Ok(hex::encode(bytes))
+}
+
+// ── Channel ─────────────────────────────────────────────────────
+
+/// Read relay frames until the `OK` for event `id`.
+async fn wait_for_ok<S>(read: &mut S, id: &str) -> Result<()>
+where
+    S: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    while let Some(frame) = read.next().await {
+        let Message::Text(text) = frame? else {
+            continue;
+        };
+        let Ok(reply) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+        if reply[0] == "OK" && reply[1] == id {
+            if reply[2] == true {
+                return Ok(());
+            }
+            bail!("rejected: {}", reply[3].as_str().unwrap_or_default());
+        }
+    }
+    bail!("connection closed before the event was acknowledged")
+}
+
+/// Nostr channel — NIP-04 and NIP-17 direct messages over relays
+pub struct NostrChannel {
+    private_key: Arc<ManagedSecret>,
+    relays: Vec<String>,
+    /// Hex public keys allowed to talk to the bot; "*" allows everyone
+    allowed_pubkeys: Vec<String>,
+    /// People whose last message was NIP-04, so replies use it too
+    legacy_senders: Mutex<HashSet<String>>,
+    /// Events already handled; relays redeliver gift wraps from up to two
+    /// days back on every reconnect
+    seen: Mutex<InboundDeduplicator>,
+    started_at: u64,
+}
+
+impl NostrChannel {
+    pub fn new(
+        private_key: Arc<ManagedSecret>,
+        relays: Vec<String>,
+        allowed_pubkeys: Vec<String>,
+    ) -> Self {
+        let allowed_pubkeys = allowed_pubkeys
+            .into_iter()
+            .map(|key| {
+                if key == "*" {
+                    return key;
+                }
+                parse_public_key(&key).unwrap_or_else(|e| {
+                    tracing::warn!("Nostr: ignoring allowed_pubkeys entry {key}: {e}");
+                    key
+                })
+            })
+            .collect();
+        Self {
+            private_key,
+            relays,
+            allowed_pubkeys,
+            legacy_senders: Mutex::new(HashSet::new()),
+            seen: Mutex::new(InboundDeduplicator::new(
+                Duration::from_secs(TIMESTAMP_TWEAK_SECS + 24 * 60 * 60),
+                super::dedup::DEFAULT_DEDUP_CAPACITY,
+            )),
+            started_at: unix_now(),
+        }
+    }
+
+    async fn keys(&self) -> Result<NostrKeys> {
+        NostrKeys::parse(&self.private_key.get().await?)
+    }
+
+    fn is_sender_allowed(&self, pubkey: &str) -> bool {
+        self.allowed_pubkeys
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == pubkey)
+    }
+
+    /// The DM in a relay message, if it is one for us that we haven't seen.
+    fn process_relay_message(&self, keys: &NostrKeys, text: &str) -> Option<ChannelMessage> {
+        let frame: serde_json::Value = serde_json::from_str(text).ok()?;
+        match frame.get(0).and_then(serde_json::Value::as_str)? {
+            "EVENT" => {}
+            "NOTICE" => {
+                tracing::info!("Nostr relay notice: {}", frame[1]);
+                return None;
+            }
+            _ => return None,
+        }
+        let event: Event = serde_json::from_value(frame.get(2)?.clone()).ok()?;
+        let me = keys.public_key();
+        if !event.addresses(&me) {
+            return None;
+        }
+        if let Err(e) = event.verify() {
+            tracing::debug!("Nostr: dropping event {}: {e}", event.id);
+            return None;
+        }
+        if self.seen.lock().is_duplicate("nostr", &event.id) {
+            return None;
+        }
+
+        let (id, sender, content, created_at) = match event.kind {
+            KIND_ENCRYPTED_DM => {
+                let content = keys
+                    .shared_x(&event.pubkey)
+                    .and_then(|key| nip04::decrypt(&key, &event.content));
+                let content = match content {
+                    Ok(content) => content,
+                    Err(e) => {
+                        tracing::debug!("Nostr: undecryptable DM {}: {e}", event.id);
+                        return None;
+                    }
+                };
+                self.legacy_senders.lock().insert(event.pubkey.clone());
+                (event.id, event.pubkey, content, event.created_at)
+            }
+            KIND_GIFT_WRAP => {
+                let rumor = match unwrap_gift(keys, &event) {
+                    Ok(rumor) => rumor,
+                    Err(e) => {
+                        tracing::debug!("Nostr: dropping gift wrap {}: {e}", event.id);
+                        return None;
+                    }
+                };
+                // Wraps are backdated, the rumor has the real time;
+                // skip the history relays replay on connect.
+                if rumor.kind != KIND_CHAT_MESSAGE
+                    || rumor.created_at.saturating_add(60) < self.started_at
+                {
+                    return None;
+                }
+                self.legacy_senders.lock().remove(&rumor.pubkey);
+                (rumor.id, rumor.pubkey, rumor.content, rumor.created_at)
+            }
+            _ => return None,
+        };
+
+        if !self.is_sender_allowed(&sender) {
+            tracing::warn!(
+                "Nostr: ignoring DM from unauthorized pubkey {sender}. \
+                Add it to allowed_pubkeys in config.toml."
+            );
+            return None;
+        }
+        if content.trim().is_empty() {
+            return None;
+        }
+
+        Some(ChannelMessage {
+            id,
+            reply_target: sender.clone(),
+            sender,
+            content,
+            channel: "nostr".to_string(),
+            timestamp: created_at,
+            received_at: unix_now(),
+            attachments: vec![],
+            sender_id: None,
+            sender_name: None,
+            conversation_id: None,
+            mentions: vec![],
+            is_mention: false,
+        })
+    }
+
+    /// Subscribe on `relay` and forward DMs until the connection drops.
+    /// Returns `false` once the receiver is gone.
+    async fn listen_relay_once(
+        &self,
+        relay: &str,
+        keys: &NostrKeys,
+        tx: &mpsc::Sender<ChannelMessage>,
+    ) -> Result<bool> {
+        let (ws, _) = tokio_tungstenite::connect_async(relay).await?;
+        let (mut write, mut read) = ws.split();
+        let me = keys.public_key();
+        let request = serde_json::json!([
+            "REQ",
+            SUBSCRIPTION_ID,
+            { "kinds": [KIND_ENCRYPTED_DM], "#p": [me], "since": self.started_at },
+            {
+                "kinds": [KIND_GIFT_WRAP],
+                "#p": [me],
+                "since": self.started_at.saturating_sub(TIMESTAMP_TWEAK_SECS),
+            },
+        ]);
+        write.send(Message::Text(request.to_string())).await?;
+
+        while let Some(frame) = read.next().await {
+            match frame? {
+                Message::Text(text) => {
+                    if let Some(msg) = self.process_relay_message(keys, &text) {
+                        if tx.send(msg).await.is_err() {
+                            return Ok(false);
+                        }
+                    }
+                }
+                Message::Ping(payload) => write.send(Message::Pong(payload)).await?,
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    async fn listen_relay(&self, relay: &str, keys: &NostrKeys, tx: &mpsc::Sender<ChannelMessage>) {
+        let mut retry_delay_secs = 2u64;
+        loop {
+            match self.listen_relay_once(relay, keys, tx).await {
+                Ok(false) => return,
+                Ok(true) => {
+                    tracing::debug!("Nostr relay {relay} closed the connection, reconnecting...");
+                    retry_delay_secs = 2;
+                }
+                Err(e) => {
+                    tracing::warn!("Nostr relay {relay} error: {e}, retrying...");
+                    retry_delay_secs = (retry_delay_secs * 2).min(300);
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(retry_delay_secs)).await;
+        }
+    }
+
+    /// Publish `event` to `relay` and wait for its `OK`.
+    async fn publish_to(relay: &str, event: &Event) -> Result<()> {
+        let (ws, _) = tokio_tungstenite::connect_async(relay).await?;
+        let (mut write, mut read) = ws.split();
+        write
+            .send(Message::Text(
+                serde_json::json!(["EVENT", event]).to_string(),
+            ))
+            .await?;
+        let accepted = tokio::time::timeout(PUBLISH_TIMEOUT, wait_for_ok(&mut read, &event.id))
+            .await
+            .map_err(|_| anyhow::anyhow!("no acknowledgement"))?;
+        let _ = write.close().await;
+        accepted
+    }
+
+    /// Publish `event` to every relay; succeeds if any relay accepts it.
+    async fn publish(&self, event: &Event) -> Result<()> {
+        let results = futures::future::join_all(
+            self.relays
+                .iter()
+                .map(|relay| Self::publish_to(relay, event)),
+        )
+        .await;
+        let mut errors = Vec::new();
+        for (relay, result) in self.relays.iter().zip(results) {
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) => errors.push(format!("{relay}: {e}")),
+            }
+        }
+        bail!(
+            "No Nostr relay accepted the message ({})",
+            errors.join("; ")
+        )
+    }
+}
+
+#[async_trait]
+impl Channel for NostrChannel {
+    fn name(&self) -> &str {
+        "nostr"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: true,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+        let keys = self.keys().await?;
+        let recipient = parse_public_key(recipient)?;
+        let now = unix_now();
+        let event = if self.legacy_senders.lock().contains(&recipient) {
+            let content = nip04::encrypt(&keys.shared_x(&recipient)?, message)?;
+            keys.sign(
+                now,
+                KIND_ENCRYPTED_DM,
+                vec![vec!["p".to_string(), recipient.clone()]],
+                content,
+            )?
+        } else {
+            gift_wrap(&keys, &recipient, message, now)?
+        };
+        self.publish(&event).await
+    }
+
+    async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
+        let keys = self.keys().await?;
+        if self.relays.is_empty() {
+            bail!("Nostr needs at least one relay");
+        }
+        tracing::info!(
+            "Nostr channel listening as {} on {} relay(s)...",
+            keys.npub(),
+            self.relays.len()
+        );
+        futures::future::join_all(
+            self.relays
+                .iter()
+                .map(|relay| self.listen_relay(relay, &keys, &tx)),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        if self.keys().await.is_err() {
+            return false;
+        }
+        for relay in &self.relays {
+            let connect = tokio_tungstenite::connect_async(relay.as_str());
+            if let Ok(Ok((mut ws, _))) =
+                tokio::time::timeout(Duration::from_secs(10), connect).await
+            {
+                let _ = ws.close(None).await;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET_ONE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+    const SECRET_TWO: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+
+    fn channel_for(keys: &str, allowed: Vec<String>) -> NostrChannel {
+        NostrChannel::new(
+            Arc::new(ManagedSecret::fixed(keys)),
+            vec!["wss://relay.example".into()],
+            allowed,
+        )
+    }
+
+    #[test]
+    fn parses_nip19_keys() {
+        // Vectors from NIP-19
+        let keys =
+            NostrKeys::parse("nsec1vl029mgpspedva04g90vltkh6fvh240zqtv9k0t9af8935ke9laqsnlfe5")
+                .unwrap();
+        let same =
+            NostrKeys::parse("67dea2ed018072d675f5415ecfaed7d2597555e202d85b3d65ea4e58d2d92ffa")
+                .unwrap();
+        assert_eq!(keys.public_key(), same.public_key());
+        assert_eq!(
+            parse_public_key("npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg")
+                .unwrap(),
+            "7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e"
+        );
+        assert_eq!(
+            bech32_encode(
+                "npub",
+                &hex::decode("7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e")
+                    .unwrap()
+            ),
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        );
+        assert!(parse_public_key(
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjpth"
+        )
+        .is_err());
+
+        let one = NostrKeys::parse(SECRET_ONE).unwrap();
+        assert_eq!(
+            one.public_key(),
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+    }
+
+    #[test]
+    fn nip44_matches_the_spec_vectors() {
+        let one = NostrKeys::parse(SECRET_ONE).unwrap();
+        let two = NostrKeys::parse(SECRET_TWO).unwrap();
+        let key = one.conversation_key(&two.public_key()).unwrap();
+        assert_eq!(
+            hex::encode(key),
+            "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d"
+        );
+        assert_eq!(key, two.conversation_key(&one.public_key()).unwrap());
+
+        let mut nonce = [0u8; 32];
+        nonce[31] = 1;
+        let payload = nip44::encrypt_with_nonce(&key, "a", &nonce).unwrap();
+        assert_eq!(
+            payload,
+            "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4DwrcNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb"
+        );
+        assert_eq!(nip44::decrypt(&key, &payload).unwrap(), "a");
+
+        for (len, padded) in [
+            (16, 32),
+            (33, 64),
+            (65, 96),
+            (100, 128),
+            (320, 320),
+            (515, 640),
+            (1020, 1024),
+        ] {
+            assert_eq!(nip44::padded_len(len), padded, "padded length of {len}");
+        }
+
+        let long = "🦀".repeat(300);
+        let sealed = nip44::encrypt(&key, &long).unwrap();
+        assert_eq!(nip44::decrypt(&key, &sealed).unwrap(), long);
+        let mut tampered = base64::engine::general_purpose::STANDARD
+            .decode(&sealed)
+            .unwrap();
+        tampered[40] ^= 1;
+        let tampered = base64::engine::general_purpose::STANDARD.encode(tampered);
+        assert!(nip44::decrypt(&key, &tampered).is_err());
+    }
+
+    #[test]
+    fn signed_events_verify() {
+        let keys = NostrKeys::generate();
+        let event = keys.sign(1_700_000_000, 1, vec![], "gm".into()).unwrap();
+        assert!(event.verify().is_ok());
+        let mut forged = event.clone();
+        forged.content = "gn".into();
+        assert!(forged.verify().is_err());
+        forged.id = hex::encode(forged.compute_id());
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn gift_wrapped_and_legacy_dms_reach_the_channel() {
+        let alice = NostrKeys::generate();
+        let bot = NostrKeys::generate();
+        let channel = channel_for(&hex::encode(bot.secret.to_bytes()), vec![alice.npub()]);
+
+        let wrap = gift_wrap(&alice, &bot.public_key(), "hello bot", unix_now()).unwrap();
+        assert_ne!(wrap.pubkey, alice.public_key());
+        assert!(wrap.addresses(&bot.public_key()));
+        let frame = serde_json::json!(["EVENT", SUBSCRIPTION_ID, wrap]).to_string();
+        let msg = channel.process_relay_message(&bot, &frame).unwrap();
+        assert_eq!(msg.content, "hello bot");
+        assert_eq!(msg.sender, alice.public_key());
+        assert_eq!(msg.reply_target, alice.public_key());
+        // The same wrap from a second relay is dropped
+        assert!(channel.process_relay_message(&bot, &frame).is_none());
+        assert!(!channel.legacy_senders.lock().contains(&alice.public_key()));
+
+        let content =
+            nip04::encrypt(&alice.shared_x(&bot.public_key()).unwrap(), "old school").unwrap();
+        let dm = alice
+            .sign(
+                unix_now(),
+                KIND_ENCRYPTED_DM,
+                vec![vec!["p".into(), bot.public_key()]],
+                content,
+            )
+            .unwrap();
+        let frame = serde_json::json!(["EVENT", SUBSCRIPTION_ID, dm]).to_string();
+        let msg = channel.process_relay_message(&bot, &frame).unwrap();
+        assert_eq!(msg.content, "old school");
+        assert!(channel.legacy_senders.lock().contains(&alice.public_key()));
+
+        let stranger = NostrKeys::generate();
+        let wrap = gift_wrap(&stranger, &bot.public_key(), "let me in", unix_now()).unwrap();
+        let frame = serde_json::json!(["EVENT", SUBSCRIPTION_ID, wrap]).to_string();
+        assert!(channel.process_relay_message(&bot, &frame).is_none());
+    }
+}
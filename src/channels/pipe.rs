@@ -0,0 +1,176 @@
+//! Pipe mode (`zeroclaw channel start --pipe`): the router and agent with
+//! stdin and stdout as their only channel, for shell pipelines and CI.
+//!
+//! Each stdin line is a JSON message:
+//!
+//! ```text
+//! {"content": "what's 2+2?", "sender": "ci", "id": "q1"}
+//! ```
+//!
+//! Only `content` is required; `sender` defaults to "pipe" and is also the
+//! reply target. Every outbound message is one stdout line:
+//!
+//! ```text
+//! {"recipient": "ci", "content": "4"}
+//! ```
+//!
+//! Startup banners move to stderr, and the admin, gRPC and ingress servers
+//! and cluster election stay off, so stdout carries nothing else. When
+//! stdin closes, messages still in flight are answered and the process
+//! exits.
+
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether stdout is reserved for pipe-mode messages.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Run the channel runtime in pipe mode until stdin closes.
+pub async fn run(mut config: Config) -> Result<()> {
+    ACTIVE.store(true, Ordering::Relaxed);
+    config.admin.enabled = false;
+    config.grpc.enabled = false;
+    config.ingress.enabled = false;
+    config.cluster.enabled = false;
+    Box::pin(super::start_channels(config)).await
+}
+
+#[derive(Debug, Deserialize)]
+struct InputLine {
+    content: String,
+    #[serde(default)]
+    sender: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutputLine<'a> {
+    recipient: &'a str,
+    content: &'a str,
+}
+
+fn parse_line(line: &str) -> Result<ChannelMessage> {
+    let input: InputLine = serde_json::from_str(line).context("Expected a JSON object")?;
+    if input.content.trim().is_empty() {
+        bail!("content is empty");
+    }
+    let sender = input
+        .sender
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "pipe".to_string());
+    Ok(ChannelMessage {
+        id: input.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        reply_target: sender.clone(),
+        sender,
+        content: input.content,
+        channel: "pipe".to_string(),
+        timestamp: unix_now(),
+        received_at: unix_now(),
+        attachments: vec![],
+        sender_id: None,
+        sender_name: None,
+        conversation_id: None,
+        mentions: vec![],
+        is_mention: false,
+    })
+}
+
+fn output_line(recipient: &str, content: &str) -> String {
+    let mut line = serde_json::to_string(&OutputLine { recipient, content }).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// Pipe channel — JSON lines on stdin in, JSON lines on stdout out
+pub struct PipeChannel;
+
+impl PipeChannel {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Channel for PipeChannel {
+    fn name(&self) -> &str {
+        "pipe"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+        // One locked write per message keeps concurrent replies on separate lines
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(output_line(recipient, message).as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> Result<()> {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_line(&line) {
+                Ok(msg) => {
+                    if tx.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => tracing::warn!("Pipe: skipping input line: {e:#}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_input_lines() {
+        let msg = parse_line(r#"{"content":"what's 2+2?","sender":"ci","id":"q1"}"#).unwrap();
+        assert_eq!(msg.id, "q1");
+        assert_eq!(msg.sender, "ci");
+        assert_eq!(msg.reply_target, "ci");
+        assert_eq!(msg.channel, "pipe");
+
+        let msg = parse_line(r#"{"content":"hello"}"#).unwrap();
+        assert_eq!(msg.sender, "pipe");
+
+        assert!(parse_line("hello").is_err());
+        assert!(parse_line(r#"{"content":""}"#).is_err());
+    }
+
+    #[test]
+    fn output_is_one_json_line_per_message() {
+        let line = output_line("ci", "line one\nline two");
+        assert_eq!(line.matches('\n').count(), 1);
+        let parsed: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(parsed["recipient"], "ci");
+        assert_eq!(parsed["content"], "line one\nline two");
+    }
+}
@@ -0,0 +1,310 @@
+//! Polls sent to channels and the votes cast on them.
+//!
+//! [`Channel::send_poll`] posts a [`Poll`]: a native poll on Telegram, and
+//! on Discord a message listing the options with keycap emojis (1️⃣, 2️⃣, …)
+//! that users vote on by reacting. Either way the channel records the poll
+//! with [`track`] and feeds votes in as they arrive; [`results`] tallies
+//! them for the admin API.
+
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::LazyLock;
+
+/// Keycap emojis for reaction polls; also the most options a poll may have.
+pub static OPTION_EMOJIS: [&str; 10] = ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟"];
+
+/// Polls kept for result queries; the oldest are forgotten beyond this.
+const MAX_TRACKED_POLLS: usize = 500;
+
+/// A question with options to vote on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<String>,
+    /// Voters may pick more than one option
+    pub allows_multiple: bool,
+}
+
+impl Poll {
+    pub fn new(question: impl Into<String>, options: Vec<String>) -> Self {
+        Self {
+            question: question.into(),
+            options,
+            allows_multiple: false,
+        }
+    }
+
+    pub fn with_multiple_answers(mut self, allows_multiple: bool) -> Self {
+        self.allows_multiple = allows_multiple;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.question.trim().is_empty() {
+            bail!("Poll has no question");
+        }
+        if !(2..=OPTION_EMOJIS.len()).contains(&self.options.len()) {
+            bail!(
+                "Poll needs 2-{} options, got {}",
+                OPTION_EMOJIS.len(),
+                self.options.len()
+            );
+        }
+        if self.options.iter().any(|o| o.trim().is_empty()) {
+            bail!("Poll options must not be empty");
+        }
+        Ok(())
+    }
+
+    /// The poll as text for a reaction poll.
+    pub fn render(&self) -> String {
+        let mut text = format!("📊 {}", self.question);
+        for (emoji, option) in OPTION_EMOJIS.iter().zip(&self.options) {
+            text.push('\n');
+            text.push_str(emoji);
+            text.push(' ');
+            text.push_str(option);
+        }
+        text.push_str(if self.allows_multiple {
+            "\n\nReact to vote (pick any)."
+        } else {
+            "\n\nReact to vote."
+        });
+        text
+    }
+
+    /// Reaction emojis for the options, in order.
+    pub fn emojis(&self) -> &'static [&'static str] {
+        &OPTION_EMOJIS[..self.options.len().min(OPTION_EMOJIS.len())]
+    }
+}
+
+/// Index of the option a reaction emoji votes for.
+pub fn option_for_emoji(emoji: &str) -> Option<usize> {
+    OPTION_EMOJIS.iter().position(|e| *e == emoji)
+}
+
+struct TrackedPoll {
+    channel: String,
+    recipient: String,
+    poll: Poll,
+    created_at: u64,
+    /// Options picked by each voter
+    votes: HashMap<String, BTreeSet<usize>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    polls: HashMap<String, TrackedPoll>,
+    /// Poll ids, oldest first
+    order: Vec<String>,
+}
+
+static POLLS: LazyLock<Mutex<Registry>> = LazyLock::new(|| Mutex::new(Registry::default()));
+
+/// Id under which a channel's poll is tracked.
+pub fn poll_id(channel: &str, platform_id: &str) -> String {
+    format!("{channel}:{platform_id}")
+}
+
+/// Start counting votes for a poll the channel just sent. `platform_id`
+/// is the platform's poll or message id. Returns the poll's id.
+pub fn track(channel: &str, platform_id: &str, recipient: &str, poll: Poll) -> String {
+    let id = poll_id(channel, platform_id);
+    let mut registry = POLLS.lock();
+    if registry.order.len() >= MAX_TRACKED_POLLS {
+        let oldest = registry.order.remove(0);
+        registry.polls.remove(&oldest);
+    }
+    registry.order.push(id.clone());
+    registry.polls.insert(
+        id.clone(),
+        TrackedPoll {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            poll,
+            created_at: super::traits::unix_now(),
+            votes: HashMap::new(),
+        },
+    );
+    id
+}
+
+/// Replace a voter's choices (Telegram reports the full set on every
+/// change; an empty set retracts the vote).
+pub fn set_vote(channel: &str, platform_id: &str, voter: &str, options: &[usize]) {
+    let mut registry = POLLS.lock();
+    let Some(tracked) = registry.polls.get_mut(&poll_id(channel, platform_id)) else {
+        return;
+    };
+    let picked: BTreeSet<usize> = options
+        .iter()
+        .copied()
+        .filter(|&o| o < tracked.poll.options.len())
+        .collect();
+    if picked.is_empty() {
+        tracked.votes.remove(voter);
+    } else {
+        tracked.votes.insert(voter.to_string(), picked);
+    }
+}
+
+/// Record a vote for one option. On single-answer polls it replaces the
+/// voter's earlier choice.
+pub fn add_vote(channel: &str, platform_id: &str, voter: &str, option: usize) {
+    let mut registry = POLLS.lock();
+    let Some(tracked) = registry.polls.get_mut(&poll_id(channel, platform_id)) else {
+        return;
+    };
+    if option >= tracked.poll.options.len() {
+        return;
+    }
+    let picked = tracked.votes.entry(voter.to_string()).or_default();
+    if !tracked.poll.allows_multiple {
+        picked.clear();
+    }
+    picked.insert(option);
+}
+
+/// Withdraw a vote for one option.
+pub fn remove_vote(channel: &str, platform_id: &str, voter: &str, option: usize) {
+    let mut registry = POLLS.lock();
+    let Some(tracked) = registry.polls.get_mut(&poll_id(channel, platform_id)) else {
+        return;
+    };
+    if let Some(picked) = tracked.votes.get_mut(voter) {
+        picked.remove(&option);
+        if picked.is_empty() {
+            tracked.votes.remove(voter);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OptionTally {
+    pub text: String,
+    pub votes: usize,
+}
+
+/// Vote counts of a poll.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PollResults {
+    pub id: String,
+    pub channel: String,
+    pub recipient: String,
+    pub question: String,
+    pub allows_multiple: bool,
+    pub options: Vec<OptionTally>,
+    /// People who voted at all
+    pub voters: usize,
+    pub created_at: u64,
+}
+
+fn tally(id: &str, tracked: &TrackedPoll) -> PollResults {
+    let mut counts = vec![0; tracked.poll.options.len()];
+    for picked in tracked.votes.values() {
+        for &option in picked {
+            counts[option] += 1;
+        }
+    }
+    PollResults {
+        id: id.to_string(),
+        channel: tracked.channel.clone(),
+        recipient: tracked.recipient.clone(),
+        question: tracked.poll.question.clone(),
+        allows_multiple: tracked.poll.allows_multiple,
+        options: tracked
+            .poll
+            .options
+            .iter()
+            .zip(counts)
+            .map(|(text, votes)| OptionTally {
+                text: text.clone(),
+                votes,
+            })
+            .collect(),
+        voters: tracked.votes.len(),
+        created_at: tracked.created_at,
+    }
+}
+
+/// Current results of a tracked poll.
+pub fn results(id: &str) -> Option<PollResults> {
+    let registry = POLLS.lock();
+    registry.polls.get(id).map(|tracked| tally(id, tracked))
+}
+
+/// Results of every tracked poll, newest first.
+pub fn all_results() -> Vec<PollResults> {
+    let registry = POLLS.lock();
+    registry
+        .order
+        .iter()
+        .rev()
+        .filter_map(|id| registry.polls.get(id).map(|tracked| tally(id, tracked)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lunch() -> Poll {
+        Poll::new(
+            "Lunch?",
+            vec!["Pizza".into(), "Sushi".into(), "Tacos".into()],
+        )
+    }
+
+    #[test]
+    fn polls_render_with_keycap_options() {
+        let poll = lunch();
+        assert!(poll.validate().is_ok());
+        assert_eq!(
+            poll.render(),
+            "📊 Lunch?\n1️⃣ Pizza\n2️⃣ Sushi\n3️⃣ Tacos\n\nReact to vote."
+        );
+        assert_eq!(poll.emojis(), &["1️⃣", "2️⃣", "3️⃣"]);
+        assert_eq!(option_for_emoji("2️⃣"), Some(1));
+        assert_eq!(option_for_emoji("👍"), None);
+        assert!(Poll::new("Lunch?", vec!["Pizza".into()])
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn votes_are_tallied_per_voter() {
+        let id = track("discord", "m-tally", "c1", lunch());
+        add_vote("discord", "m-tally", "alice", 0);
+        add_vote("discord", "m-tally", "alice", 1); // single answer: replaces
+        add_vote("discord", "m-tally", "bob", 1);
+        add_vote("discord", "m-tally", "carol", 2);
+        remove_vote("discord", "m-tally", "carol", 2);
+        add_vote("discord", "m-tally", "dave", 9); // no such option
+
+        let results = results(&id).unwrap();
+        let counts: Vec<usize> = results.options.iter().map(|o| o.votes).collect();
+        assert_eq!(counts, vec![0, 2, 0]);
+        assert_eq!(results.voters, 2);
+
+        let multi = track(
+            "telegram",
+            "p-tally",
+            "42",
+            lunch().with_multiple_answers(true),
+        );
+        set_vote("telegram", "p-tally", "1", &[0, 2]);
+        set_vote("telegram", "p-tally", "2", &[2]);
+        set_vote("telegram", "p-tally", "2", &[]);
+        let counts: Vec<usize> = super::results(&multi)
+            .unwrap()
+            .options
+            .iter()
+            .map(|o| o.votes)
+            .collect();
+        assert_eq!(counts, vec![1, 0, 1]);
+        assert!(super::results("telegram:unknown").is_none());
+    }
+}
@@ -0,0 +1,101 @@
+//! Priority of inbound messages (`[channels_config.priority]`).
+//!
+//! Operational traffic — alert silences, subscriptions, group settings and
+//! any command from an operator in `admins` — is [`Priority::Urgent`]; the
+//! rest is chat. Urgent messages leave a channel's
+//! [inbound queue](super::inbound_queue) first and are the last it drops,
+//! get a conversation lane of their own so they don't wait behind the
+//! sender's chat, and may use the pool's reserved workers (see
+//! [`super::dispatch`]). Alerts the runtime sends itself go straight to the
+//! channel and never queue.
+
+use super::traits::ChannelMessage;
+use crate::config::{ChannelsConfig, PriorityConfig};
+use parking_lot::RwLock;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Normal,
+    Urgent,
+}
+
+static CONFIG: LazyLock<RwLock<PriorityConfig>> =
+    LazyLock::new(|| RwLock::new(PriorityConfig::default()));
+
+/// Classify messages with `config` from now on.
+pub fn configure(config: &ChannelsConfig) {
+    *CONFIG.write() = config.priority.clone();
+}
+
+/// Priority of `msg` under the configured rules.
+pub fn classify(msg: &ChannelMessage) -> Priority {
+    classify_with(&CONFIG.read(), msg)
+}
+
+fn classify_with(config: &PriorityConfig, msg: &ChannelMessage) -> Priority {
+    if !config.enabled {
+        return Priority::Normal;
+    }
+    let content = msg.content.trim_start();
+    if !content.starts_with('/') {
+        return Priority::Normal;
+    }
+    let operator = format!("{}:{}", msg.channel, msg.author_id());
+    if config.admins.contains(&operator) {
+        return Priority::Urgent;
+    }
+    let command = content
+        .split_whitespace()
+        .next()
+        .and_then(|word| word.split('@').next())
+        .unwrap_or_default();
+    if config.commands.iter().any(|urgent| urgent == command) {
+        Priority::Urgent
+    } else {
+        Priority::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(sender: &str, content: &str) -> ChannelMessage {
+        ChannelMessage {
+            reply_target: "chat".into(),
+            ..ChannelMessage::test("telegram", sender, content)
+        }
+    }
+
+    #[test]
+    fn operational_commands_are_urgent() {
+        let config = PriorityConfig {
+            admins: vec!["telegram:ops".into()],
+            ..PriorityConfig::default()
+        };
+        let classify = |sender, content| classify_with(&config, &msg(sender, content));
+
+        assert_eq!(classify("alice", "/silence disk 1h"), Priority::Urgent);
+        assert_eq!(
+            classify("alice", "/subscribe@zeroclaw_bot deploys"),
+            Priority::Urgent
+        );
+        assert_eq!(classify("alice", "/silenced"), Priority::Normal);
+        assert_eq!(
+            classify("alice", "please /silence the alerts"),
+            Priority::Normal
+        );
+        assert_eq!(classify("ops", "/reset"), Priority::Urgent);
+        assert_eq!(classify("ops", "how are you?"), Priority::Normal);
+
+        let disabled = PriorityConfig {
+            enabled: false,
+            ..PriorityConfig::default()
+        };
+        assert_eq!(
+            classify_with(&disabled, &msg("alice", "/silence")),
+            Priority::Normal
+        );
+    }
+}
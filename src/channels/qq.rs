@@ -1,6 +1,18 @@
-use super::traits::{Channel, ChannelMessage};
+use super::frame_tap::{self, Direction};
+use super::gateway::{self, op, Attachment, GatewayClosed, GatewayFrame, MessageEvent};
+use super::gateway_state::{GatewayStateStore, SessionTracker};
+use super::http::{AuthedApiClient, IssuedToken, TokenSource};
+use super::interactions::{self, Interaction, Keyboard};
+use super::mentions;
+use super::traits::{
+    unix_now, Channel, ChannelCapabilities, ChannelMessage, MediaAttachment, MediaKind,
+};
+use crate::config::{ApiEnvironment, GatewayCompression, QQConfig};
+use crate::security::webhook_security::{Ed25519Signer, ReplayGuard, DEFAULT_REPLAY_WINDOW_SECS};
+use crate::security::ManagedSecret;
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -9,45 +21,131 @@ use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 
 const QQ_API_BASE: &str = "https://api.sgroup.qq.com";
+const QQ_SANDBOX_API_BASE: &str = "https://sandbox.api.sgroup.qq.com";
 const QQ_AUTH_URL: &str = "https://bots.qq.com/app/getAppAccessToken";
+/// "Manage channel" bit of a member's channel permissions.
+const MANAGE_CHANNEL_PERMISSION: u64 = 1 << 1;
+/// How old a signed callback request may be.
+const CALLBACK_REPLAY_WINDOW: std::time::Duration =
+    std::time::Duration::from_secs(DEFAULT_REPLAY_WINDOW_SECS);
+
+/// What a QQ gateway close code asks of the listener.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseAction {
+    /// Reconnect and resume the session
+    Resume,
+    /// Reconnect with a fresh Identify
+    Identify,
+    /// Reconnect after refetching the access token
+    Reauthenticate,
+    /// Give up: reconnecting with the same settings cannot succeed
+    Fatal,
+}
 
-/// Deduplication set capacity — evict half of entries when full.
-const DEDUP_CAPACITY: usize = 10_000;
+/// Close codes from the QQ bot gateway docs.
+fn close_action(code: Option<u16>) -> CloseAction {
+    match code {
+        Some(4004) => CloseAction::Reauthenticate,
+        // Invalid session / invalid seq: the session cannot be resumed
+        Some(4006 | 4007) => CloseAction::Identify,
+        // Invalid shard, too many guilds, invalid version, invalid or
+        // unauthorized intents, bot offline (sandbox only), bot banned
+        Some(4010..=4014 | 4914 | 4915) => CloseAction::Fatal,
+        // Rate limited, session timed out, internal errors, network closes
+        _ => CloseAction::Resume,
+    }
+}
 
-/// QQ Official Bot channel — uses Tencent's official QQ Bot API with
-/// OAuth2 authentication and a Discord-like WebSocket gateway protocol.
-pub struct QQChannel {
-    app_id: String,
-    app_secret: String,
-    allowed_users: Vec<String>,
-    client: reqwest::Client,
-    /// Cached access token + expiry timestamp.
-    token_cache: Arc<RwLock<Option<(String, u64)>>>,
-    /// Message deduplication set.
-    dedup: Arc<RwLock<HashSet<String>>>,
+/// Which QQ bot API the channel talks to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QQEnvironment {
+    Production,
+    /// The sandbox API, for bots not yet published
+    Sandbox,
+    /// A QQ-compatible API at this base URL, e.g. an internal relay
+    Custom(String),
 }
 
-impl QQChannel {
-    pub fn new(app_id: String, app_secret: String, allowed_users: Vec<String>) -> Self {
-        Self {
-            app_id,
-            app_secret,
-            allowed_users,
-            client: reqwest::Client::new(),
-            token_cache: Arc::new(RwLock::new(None)),
-            dedup: Arc::new(RwLock::new(HashSet::new())),
+/// A configured URL override, trimmed and checked against `schemes`.
+fn configured_url(
+    field: &str,
+    value: Option<&str>,
+    schemes: &[&str],
+) -> anyhow::Result<Option<String>> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    let url = reqwest::Url::parse(value)
+        .map_err(|e| anyhow::anyhow!("Invalid QQ {field} {value:?}: {e}"))?;
+    if !schemes.contains(&url.scheme()) {
+        anyhow::bail!("QQ {field} must be a {} URL: {value}", schemes.join("/"));
+    }
+    Ok(Some(value.trim_end_matches('/').to_string()))
+}
+
+/// The gateway WebSocket URL set in `config`, used instead of the one the
+/// API's `/gateway` endpoint returns.
+pub fn configured_gateway_url(config: &QQConfig) -> anyhow::Result<Option<String>> {
+    configured_url("gateway_url", config.gateway_url.as_deref(), &["ws", "wss"])
+}
+
+impl QQEnvironment {
+    /// The environment selected by `config`. An `api_base` overrides the
+    /// environment's URL (and is required for "custom"), so QQ-compatible
+    /// relays work in front of either deployment.
+    pub fn from_config(config: &QQConfig) -> anyhow::Result<Self> {
+        let api_base = configured_url("api_base", config.api_base.as_deref(), &["http", "https"])?;
+        Ok(match (config.environment, api_base) {
+            (_, Some(base)) => Self::Custom(base),
+            (ApiEnvironment::Production, None) => Self::Production,
+            (ApiEnvironment::Sandbox, None) => Self::Sandbox,
+            (ApiEnvironment::Custom, None) => {
+                anyhow::bail!("QQ environment \"custom\" requires api_base")
+            }
+        })
+    }
+
+    pub fn api_base(&self) -> &str {
+        match self {
+            Self::Production => QQ_API_BASE,
+            Self::Sandbox => QQ_SANDBOX_API_BASE,
+            Self::Custom(base) => base,
         }
     }
+}
 
-    fn is_user_allowed(&self, user_id: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == user_id)
+impl std::fmt::Display for QQEnvironment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Production => f.write_str("production"),
+            Self::Sandbox => f.write_str("sandbox"),
+            Self::Custom(base) => write!(f, "custom ({base})"),
+        }
     }
+}
+
+/// Deduplication set capacity — evict half of entries when full.
+const DEDUP_CAPACITY: usize = 10_000;
 
-    /// Fetch an access token from QQ's OAuth2 endpoint.
-    async fn fetch_access_token(&self) -> anyhow::Result<(String, u64)> {
+/// Acknowledges an event delivered by HTTP callback.
+const OP_CALLBACK_ACK: u8 = 12;
+/// Asks a callback endpoint to prove it holds the bot secret.
+const OP_CALLBACK_VALIDATION: u8 = 13;
+
+/// QQ's OAuth2 client-credentials endpoint as a token source.
+struct QQAuth {
+    client: reqwest::Client,
+    app_id: String,
+    app_secret: Arc<ManagedSecret>,
+}
+
+#[async_trait]
+impl TokenSource for QQAuth {
+    async fn fetch(&self) -> anyhow::Result<IssuedToken> {
+        let app_secret = self.app_secret.get().await?;
         let body = json!({
             "appId": self.app_id,
-            "clientSecret": self.app_secret,
+            "clientSecret": app_secret,
         });
 
         let resp = self.client.post(QQ_AUTH_URL).json(&body).send().await?;
@@ -65,63 +163,319 @@ impl QQChannel {
             .ok_or_else(|| anyhow::anyhow!("Missing access_token in QQ response"))?
             .to_string();
 
-        let expires_in = data
+        let lifetime_secs = data
             .get("expires_in")
             .and_then(|e| e.as_str())
             .and_then(|e| e.parse::<u64>().ok())
             .unwrap_or(7200);
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        Ok(IssuedToken {
+            token,
+            lifetime_secs,
+        })
+    }
+}
 
-        // Expire 60 seconds early to avoid edge cases
-        let expiry = now + expires_in.saturating_sub(60);
+/// QQ Official Bot channel — uses Tencent's official QQ Bot API with
+/// OAuth2 authentication and a Discord-like WebSocket gateway protocol.
+pub struct QQChannel {
+    /// Instance name; "qq" unless configured as a named account.
+    name: String,
+    app_id: String,
+    allowed_users: Vec<String>,
+    /// Bot API with token caching, retries and error parsing.
+    api: AuthedApiClient<QQAuth>,
+    environment: QQEnvironment,
+    /// Replaces the gateway URL the API hands out
+    gateway_url: Option<String>,
+    /// Message deduplication set.
+    dedup: Arc<RwLock<HashSet<String>>>,
+    /// Where the gateway session is saved for resuming after restarts.
+    state_store: Option<Arc<dyn GatewayStateStore>>,
+    /// Randomize the first heartbeat after Hello.
+    heartbeat_jitter: bool,
+    app_secret: Arc<ManagedSecret>,
+    /// Events arrive on the gateway's `POST /qq` instead of the WebSocket.
+    callback: bool,
+    /// Signatures of callback requests already received.
+    callbacks_seen: ReplayGuard,
+}
 
-        Ok((token, expiry))
+impl QQChannel {
+    pub fn new(app_id: String, app_secret: String, allowed_users: Vec<String>) -> Self {
+        Self::with_secret(
+            app_id,
+            Arc::new(ManagedSecret::fixed(app_secret)),
+            allowed_users,
+        )
     }
 
-    /// Get a valid access token, refreshing if expired.
-    async fn get_token(&self) -> anyhow::Result<String> {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-
-        {
-            let cache = self.token_cache.read().await;
-            if let Some((ref token, expiry)) = *cache {
-                if now < expiry {
-                    return Ok(token.clone());
-                }
-            }
+    /// Create a channel whose app secret is read from a secret provider on
+    /// every token refresh, so rotated secrets apply without a restart.
+    pub fn with_secret(
+        app_id: String,
+        app_secret: Arc<ManagedSecret>,
+        allowed_users: Vec<String>,
+    ) -> Self {
+        let client = super::net::http_client_for("qq");
+        let auth = QQAuth {
+            client: client.clone(),
+            app_id: app_id.clone(),
+            app_secret: Arc::clone(&app_secret),
+        };
+        Self {
+            name: "qq".to_string(),
+            api: AuthedApiClient::new("QQ", client, QQ_API_BASE, auth).with_auth_scheme("QQBot"),
+            environment: QQEnvironment::Production,
+            gateway_url: None,
+            app_id,
+            allowed_users,
+            dedup: Arc::new(RwLock::new(HashSet::new())),
+            state_store: None,
+            heartbeat_jitter: true,
+            app_secret,
+            callback: false,
+            callbacks_seen: ReplayGuard::new(CALLBACK_REPLAY_WINDOW),
         }
+    }
+
+    /// Run as a named instance (e.g. "qq-sandbox").
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.api = self.api.with_channel(self.name.clone());
+        self
+    }
+
+    /// Talk to the sandbox or a custom API instead of production. Tokens
+    /// are shared: QQ issues the same ones for every environment.
+    pub fn with_environment(mut self, environment: QQEnvironment) -> Self {
+        self.api = self.api.with_base_url(environment.api_base());
+        self.environment = environment;
+        self
+    }
+
+    /// Connect to this gateway URL instead of asking the API for one, e.g.
+    /// a relay that proxies the WebSocket too.
+    pub fn with_gateway_url(mut self, url: Option<String>) -> Self {
+        self.gateway_url = url;
+        self
+    }
 
-        let (token, expiry) = self.fetch_access_token().await?;
-        {
-            let mut cache = self.token_cache.write().await;
-            *cache = Some((token.clone(), expiry));
+    /// Persist the gateway session so a restart can resume it.
+    pub fn with_state_store(mut self, store: Arc<dyn GatewayStateStore>) -> Self {
+        self.state_store = Some(store);
+        self
+    }
+
+    /// Send the first heartbeat after a random fraction of the interval
+    /// (the default) rather than a full interval.
+    pub fn with_heartbeat_jitter(mut self, jitter: bool) -> Self {
+        self.heartbeat_jitter = jitter;
+        self
+    }
+
+    /// Receive events by HTTP callback (see [`Self::handle_callback`]); the
+    /// listener then only keeps the channel alive.
+    pub fn with_callback(mut self, callback: bool) -> Self {
+        self.callback = callback;
+        self
+    }
+
+    fn is_user_allowed(&self, user_id: &str) -> bool {
+        self.allowed_users.iter().any(|u| u == "*" || u == user_id)
+    }
+
+    /// Answer a callback-mode request: check its `X-Signature-Ed25519` over
+    /// `<X-Signature-Timestamp><body>` with the key derived from the app
+    /// secret, then sign the address validation challenge (op 13) or
+    /// acknowledge the event (op 12). Returns the response body and the
+    /// message the event carried, if any.
+    pub async fn handle_callback(
+        &self,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+    ) -> anyhow::Result<(serde_json::Value, Option<ChannelMessage>)> {
+        let secret = self.app_secret.get().await?;
+        let signer = Ed25519Signer::from_secret(&secret)
+            .ok_or_else(|| anyhow::anyhow!("QQ app secret is empty"))?;
+        signer.verify(
+            timestamp,
+            signature,
+            body,
+            CALLBACK_REPLAY_WINDOW,
+            chrono::Utc::now().timestamp(),
+        )?;
+        self.callbacks_seen.check(signature)?;
+
+        let text = String::from_utf8_lossy(body);
+        frame_tap::record(&self.name, Direction::Inbound, "callback", &text);
+        let Some(event) = gateway::parse_payload(&self.name, &text) else {
+            return Ok((json!({ "op": OP_CALLBACK_ACK }), None));
+        };
+        match event.op {
+            OP_CALLBACK_VALIDATION => {
+                let challenge = event
+                    .decode::<CallbackValidation>(&self.name)
+                    .ok_or_else(|| anyhow::anyhow!("Malformed QQ callback validation"))?;
+                let message = format!("{}{}", challenge.event_ts, challenge.plain_token);
+                let reply = json!({
+                    "plain_token": challenge.plain_token,
+                    "signature": signer.sign(message.as_bytes()),
+                });
+                Ok((reply, None))
+            }
+            op::DISPATCH => Ok((
+                json!({ "op": OP_CALLBACK_ACK }),
+                self.message_from_dispatch(&event).await,
+            )),
+            _ => Ok((json!({ "op": OP_CALLBACK_ACK }), None)),
         }
-        Ok(token)
     }
 
-    /// Get the WebSocket gateway URL.
-    async fn get_gateway_url(&self, token: &str) -> anyhow::Result<String> {
-        let resp = self
-            .client
-            .get(format!("{QQ_API_BASE}/gateway"))
-            .header("Authorization", format!("QQBot {token}"))
-            .send()
-            .await?;
+    /// The chat message a dispatch event carries, if it is one from an
+    /// allowed user that was not seen before.
+    async fn message_from_dispatch(
+        &self,
+        event: &gateway::GatewayPayload,
+    ) -> Option<ChannelMessage> {
+        let kind = event.event_type();
+        if !matches!(
+            kind,
+            "C2C_MESSAGE_CREATE" | "GROUP_AT_MESSAGE_CREATE" | "AT_MESSAGE_CREATE"
+        ) {
+            return None;
+        }
+        let d = event.decode::<MessageEvent>(&self.name)?;
+        if self.is_duplicate(&d.id).await {
+            return None;
+        }
+        let attachments = parse_attachments(&d.attachments);
 
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let err = resp.text().await.unwrap_or_default();
-            anyhow::bail!("QQ gateway request failed ({status}): {err}");
+        match kind {
+            "C2C_MESSAGE_CREATE" => {
+                let content = d.content.trim();
+                if content.is_empty() && attachments.is_empty() {
+                    return None;
+                }
+
+                // For QQ, user_openid is the identifier
+                let user_openid = d
+                    .author
+                    .user_openid
+                    .as_deref()
+                    .or(d.author.id.as_deref())
+                    .unwrap_or("unknown");
+
+                if !self.is_user_allowed(user_openid) {
+                    tracing::warn!(
+                        "QQ: ignoring C2C message from unauthorized user: {user_openid}"
+                    );
+                    return None;
+                }
+
+                let chat_id = format!("user:{user_openid}");
+
+                Some(ChannelMessage {
+                    id: platform_message_id(&d.id),
+                    sender: user_openid.to_string(),
+                    reply_target: chat_id.clone(),
+                    content: content.to_string(),
+                    channel: self.name.clone(),
+                    timestamp: d.sent_at().unwrap_or_else(unix_now),
+                    received_at: unix_now(),
+                    attachments,
+                    sender_id: Some(user_openid.to_string()),
+                    sender_name: d.author.display_name(),
+                    conversation_id: Some(chat_id),
+                    mentions: vec![],
+                    is_mention: false,
+                })
+            }
+            "GROUP_AT_MESSAGE_CREATE" => {
+                let content = d.content.trim();
+                if content.is_empty() && attachments.is_empty() {
+                    return None;
+                }
+
+                let author_id = d.author.member_openid.as_deref().unwrap_or("unknown");
+
+                if !self.is_user_allowed(author_id) {
+                    tracing::warn!(
+                        "QQ: ignoring group message from unauthorized user: {author_id}"
+                    );
+                    return None;
+                }
+
+                let group_openid = d.group_openid.as_deref().unwrap_or("unknown");
+                let chat_id = format!("group:{group_openid}");
+
+                Some(ChannelMessage {
+                    id: platform_message_id(&d.id),
+                    sender: author_id.to_string(),
+                    reply_target: chat_id.clone(),
+                    content: content.to_string(),
+                    channel: self.name.clone(),
+                    timestamp: d.sent_at().unwrap_or_else(unix_now),
+                    received_at: unix_now(),
+                    attachments,
+                    sender_id: Some(author_id.to_string()),
+                    sender_name: d.author.display_name(),
+                    conversation_id: Some(chat_id),
+                    mentions: vec![],
+                    // Group events are only sent for @-mentions,
+                    // with the mention already removed
+                    is_mention: true,
+                })
+            }
+            // Guild channel message that @-mentions the bot
+            _ => {
+                let parsed = mentions::parse(&d.content, d.mentioned_bot());
+                if parsed.text.is_empty() && attachments.is_empty() {
+                    return None;
+                }
+
+                let author_id = d.author.id.as_deref().unwrap_or("unknown");
+
+                if !self.is_user_allowed(author_id) {
+                    tracing::warn!(
+                        "QQ: ignoring guild message from unauthorized user: {author_id}"
+                    );
+                    return None;
+                }
+
+                let channel_id = d.channel_id.as_deref().unwrap_or("unknown");
+                let chat_id = format!("channel:{channel_id}");
+
+                Some(ChannelMessage {
+                    id: platform_message_id(&d.id),
+                    sender: author_id.to_string(),
+                    reply_target: chat_id.clone(),
+                    content: parsed.text,
+                    channel: self.name.clone(),
+                    timestamp: d.sent_at().unwrap_or_else(unix_now),
+                    received_at: unix_now(),
+                    attachments,
+                    sender_id: Some(author_id.to_string()),
+                    sender_name: d.author.display_name(),
+                    conversation_id: Some(chat_id),
+                    mentions: parsed.mentioned,
+                    is_mention: true,
+                })
+            }
         }
+    }
 
-        let data: serde_json::Value = resp.json().await?;
+    async fn get_token(&self) -> anyhow::Result<String> {
+        self.api.token().await
+    }
+
+    /// Get the WebSocket gateway URL.
+    async fn get_gateway_url(&self) -> anyhow::Result<String> {
+        if let Some(url) = &self.gateway_url {
+            return Ok(url.clone());
+        }
+        let data = self.api.get_json("/gateway", "gateway request").await?;
         let url = data
             .get("url")
             .and_then(|u| u.as_str())
@@ -156,247 +510,514 @@ impl QQChannel {
     }
 }
 
+/// `d` of a callback address validation (op 13).
+#[derive(Debug, Deserialize)]
+struct CallbackValidation {
+    plain_token: String,
+    event_ts: String,
+}
+
+/// Use QQ's own message id so redelivered events can be deduplicated
+/// downstream; fall back to a random id when the platform omits it.
+fn platform_message_id(msg_id: &str) -> String {
+    if msg_id.is_empty() {
+        Uuid::new_v4().to_string()
+    } else {
+        msg_id.to_string()
+    }
+}
+
+/// Attachments of a message event. Voice notes are QQ's SILK format; prefer
+/// the WAV rendition the platform provides alongside when present.
+fn parse_attachments(items: &[Attachment]) -> Vec<MediaAttachment> {
+    let absolute = |url: &str| {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            url.to_string()
+        } else {
+            format!("https://{}", url.trim_start_matches('/'))
+        }
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let content_type = item.content_type.as_deref().unwrap_or("");
+            let url = item.url.as_deref();
+            let (kind, source, mime_type) = match content_type {
+                "voice" => match item.voice_wav_url.as_deref() {
+                    Some(wav) => (MediaKind::Voice, wav, Some("audio/wav".to_string())),
+                    None => (MediaKind::Voice, url?, Some("audio/silk".to_string())),
+                },
+                t if t.starts_with("audio/") => (MediaKind::Audio, url?, Some(t.to_string())),
+                t if t.starts_with("image/") => (MediaKind::Image, url?, Some(t.to_string())),
+                t if t.starts_with("video/") => (MediaKind::Video, url?, Some(t.to_string())),
+                t => (
+                    MediaKind::Document,
+                    url?,
+                    (!t.is_empty()).then(|| t.to_string()),
+                ),
+            };
+            Some(MediaAttachment {
+                kind,
+                source: absolute(source),
+                mime_type,
+                file_name: item.filename.clone(),
+                stored_key: None,
+            })
+        })
+        .collect()
+}
+
+/// API path of the conversation a recipient refers to. Recipients are
+/// `user:{openid}`, `group:{group_openid}` or `channel:{channel_id}` (guild
+/// channels).
+fn recipient_path(recipient: &str) -> String {
+    if let Some(group_id) = recipient.strip_prefix("group:") {
+        format!("/v2/groups/{group_id}")
+    } else if let Some(channel_id) = recipient.strip_prefix("channel:") {
+        format!("/channels/{channel_id}")
+    } else {
+        let user_id = recipient.strip_prefix("user:").unwrap_or(recipient);
+        format!("/v2/users/{user_id}")
+    }
+}
+
+/// An INTERACTION_CREATE payload: a click on a keyboard button.
+#[derive(Debug, Default, Deserialize)]
+struct ButtonClick {
+    id: String,
+    /// 0 guild channel, 1 group, 2 C2C
+    #[serde(default)]
+    chat_type: u8,
+    #[serde(default)]
+    group_openid: Option<String>,
+    #[serde(default)]
+    group_member_openid: Option<String>,
+    #[serde(default)]
+    user_openid: Option<String>,
+    #[serde(default)]
+    channel_id: Option<String>,
+    #[serde(default)]
+    data: ButtonClickData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ButtonClickData {
+    #[serde(default)]
+    resolved: ButtonResolved,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ButtonResolved {
+    #[serde(default)]
+    button_data: Option<String>,
+    /// Guild member who clicked
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    message_id: Option<String>,
+}
+
+impl ButtonClick {
+    /// The click as an [`Interaction`], replying where the button was.
+    fn to_interaction(&self, channel: &str) -> Option<Interaction> {
+        let (user_id, reply_target) = match self.chat_type {
+            0 => (
+                self.data.resolved.user_id.clone()?,
+                format!("channel:{}", self.channel_id.as_deref()?),
+            ),
+            1 => (
+                self.group_member_openid.clone()?,
+                format!("group:{}", self.group_openid.as_deref()?),
+            ),
+            _ => {
+                let user = self.user_openid.clone()?;
+                let target = format!("user:{user}");
+                (user, target)
+            }
+        };
+        Some(Interaction {
+            channel: channel.to_string(),
+            callback_id: self.data.resolved.button_data.clone()?,
+            user_id,
+            user_name: None,
+            reply_target,
+            message_id: self.data.resolved.message_id.clone(),
+        })
+    }
+}
+
+/// `keyboard` in QQ's message keyboard format. Buttons are callback
+/// buttons anyone in the chat may click.
+fn keyboard_json(keyboard: &Keyboard) -> serde_json::Value {
+    let rows: Vec<serde_json::Value> = keyboard
+        .rows
+        .iter()
+        .map(|row| {
+            let buttons: Vec<serde_json::Value> = row
+                .iter()
+                .map(|button| {
+                    json!({
+                        "id": button.id,
+                        "render_data": {
+                            "label": button.label,
+                            "visited_label": button.label,
+                            "style": 1,
+                        },
+                        "action": {
+                            "type": 1,
+                            "permission": { "type": 2 },
+                            "data": button.id,
+                            "unsupport_tips": "Please update QQ to use this button",
+                        },
+                    })
+                })
+                .collect();
+            json!({ "buttons": buttons })
+        })
+        .collect();
+    json!({ "content": { "rows": rows } })
+}
+
+/// Pull `[IMAGE:path-or-url]` markers out of an outgoing message.
+fn split_image_markers(message: &str) -> (String, Vec<String>) {
+    let mut text = String::with_capacity(message.len());
+    let mut images = Vec::new();
+    let mut rest = message;
+    while let Some(open) = rest.find("[IMAGE:") {
+        let Some(close) = rest[open..].find(']') else {
+            break;
+        };
+        text.push_str(&rest[..open]);
+        let target = rest[open + "[IMAGE:".len()..open + close].trim();
+        if !target.is_empty() {
+            images.push(target.to_string());
+        }
+        rest = &rest[open + close + 1..];
+    }
+    text.push_str(rest);
+    (text.trim().to_string(), images)
+}
+
+impl QQChannel {
+    async fn post_message(&self, recipient: &str, body: serde_json::Value) -> anyhow::Result<()> {
+        let path = format!("{}/messages", recipient_path(recipient));
+        self.api.post_json(&path, &body, "send message").await?;
+        Ok(())
+    }
+
+    /// Acknowledge a button click; QQ shows it as failed otherwise.
+    async fn acknowledge_interaction(&self, interaction_id: &str) {
+        let path = format!("/interactions/{interaction_id}");
+        let result = self
+            .api
+            .put_json(&path, &json!({ "code": 0 }), "acknowledge interaction")
+            .await;
+        if let Err(e) = result {
+            tracing::debug!("QQ: failed to acknowledge interaction: {e}");
+        }
+    }
+
+    /// Upload an image (`{"url": ..}` or `{"file_data": base64}`) to the rich
+    /// media API, then send it as a media message.
+    async fn send_media(
+        &self,
+        recipient: &str,
+        mut source: serde_json::Value,
+        caption: Option<&str>,
+    ) -> anyhow::Result<()> {
+        source["file_type"] = json!(1);
+        source["srv_send_msg"] = json!(false);
+        let path = format!("{}/files", recipient_path(recipient));
+        let uploaded = self.api.post_json(&path, &source, "media upload").await?;
+        let file_info = uploaded
+            .get("file_info")
+            .and_then(|f| f.as_str())
+            .ok_or_else(|| anyhow::anyhow!("QQ media upload returned no file_info"))?;
+
+        self.post_message(
+            recipient,
+            json!({
+                "content": caption.unwrap_or(" "),
+                "msg_type": 7,
+                "media": { "file_info": file_info },
+            }),
+        )
+        .await
+    }
+}
+
 #[async_trait]
 impl Channel for QQChannel {
     fn name(&self) -> &str {
-        "qq"
+        &self.name
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Replies go out as plain text messages (msg_type 0)
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
     }
 
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
-        let token = self.get_token().await?;
+        let (text, images) = split_image_markers(message);
+        if !text.is_empty() || images.is_empty() {
+            self.post_message(recipient, json!({ "content": text, "msg_type": 0 }))
+                .await?;
+        }
+        for target in images {
+            let source = if target.starts_with("http://") || target.starts_with("https://") {
+                json!({ "url": target })
+            } else {
+                use base64::Engine;
+                let bytes = tokio::fs::read(&target).await?;
+                json!({ "file_data": base64::engine::general_purpose::STANDARD.encode(bytes) })
+            };
+            self.send_media(recipient, source, None).await?;
+        }
+        Ok(())
+    }
 
-        // Determine if this is a group or private message based on recipient format
-        // Format: "user:{openid}" or "group:{group_openid}"
-        let (url, body) = if let Some(group_id) = recipient.strip_prefix("group:") {
-            (
-                format!("{QQ_API_BASE}/v2/groups/{group_id}/messages"),
-                json!({
-                    "content": message,
-                    "msg_type": 0,
-                }),
-            )
-        } else {
-            let user_id = recipient.strip_prefix("user:").unwrap_or(recipient);
-            (
-                format!("{QQ_API_BASE}/v2/users/{user_id}/messages"),
-                json!({
-                    "content": message,
-                    "msg_type": 0,
-                }),
-            )
-        };
+    fn supports_buttons(&self) -> bool {
+        true
+    }
 
-        let resp = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("QQBot {token}"))
-            .json(&body)
-            .send()
-            .await?;
+    /// Keyboards ride on markdown messages (msg_type 2), which the bot must
+    /// be approved for on the QQ open platform.
+    async fn send_buttons(
+        &self,
+        text: &str,
+        keyboard: &Keyboard,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        keyboard.validate()?;
+        self.post_message(
+            recipient,
+            json!({
+                "msg_type": 2,
+                "markdown": { "content": text },
+                "keyboard": keyboard_json(keyboard),
+            }),
+        )
+        .await
+    }
 
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let err = resp.text().await.unwrap_or_default();
-            anyhow::bail!("QQ send message failed ({status}): {err}");
-        }
+    fn supports_images(&self) -> bool {
+        true
+    }
 
-        Ok(())
+    async fn send_image(
+        &self,
+        image: Vec<u8>,
+        _file_name: &str,
+        caption: Option<&str>,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        use base64::Engine;
+        let source =
+            json!({ "file_data": base64::engine::general_purpose::STANDARD.encode(image) });
+        self.send_media(recipient, source, caption).await
     }
 
     #[allow(clippy::too_many_lines)]
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
-        tracing::info!("QQ: authenticating...");
+        if self.callback {
+            // Events are received via the gateway's /qq endpoint
+            tracing::info!("QQ channel active (callback mode) on the gateway's /qq endpoint");
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+            }
+        }
+
+        tracing::info!("QQ: authenticating ({} API)...", self.environment);
         let token = self.get_token().await?;
 
         tracing::info!("QQ: fetching gateway URL...");
-        let gw_url = self.get_gateway_url(&token).await?;
+        let gw_url = self.get_gateway_url().await?;
 
         tracing::info!("QQ: connecting to gateway WebSocket...");
-        let (ws_stream, _) = tokio_tungstenite::connect_async(&gw_url).await?;
+        let ws_stream = super::net::connect_websocket("qq", &gw_url).await?;
         let (mut write, mut read) = ws_stream.split();
 
-        // Read Hello (opcode 10)
-        let hello = read
-            .next()
+        // Read Hello (opcode 10); heartbeats start only once it arrived
+        let mut decoder = gateway::FrameDecoder::new(GatewayCompression::None)?;
+        let heartbeat_interval = gateway::read_hello(&mut read, &mut decoder)
             .await
-            .ok_or(anyhow::anyhow!("QQ: no hello frame"))??;
-        let hello_data: serde_json::Value = serde_json::from_str(&hello.to_string())?;
-        let heartbeat_interval = hello_data
-            .get("d")
-            .and_then(|d| d.get("heartbeat_interval"))
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(41250);
-
-        // Send Identify (opcode 2)
-        // Intents: PUBLIC_GUILD_MESSAGES (1<<30) | C2C_MESSAGE_CREATE & GROUP_AT_MESSAGE_CREATE (1<<25)
-        let intents: u64 = (1 << 25) | (1 << 30);
-        let identify = json!({
-            "op": 2,
-            "d": {
-                "token": format!("QQBot {token}"),
-                "intents": intents,
-                "properties": {
-                    "os": "linux",
-                    "browser": "zeroclaw",
-                    "device": "zeroclaw",
-                }
-            }
-        });
-        write.send(Message::Text(identify.to_string())).await?;
-
-        tracing::info!("QQ: connected and identified");
+            .map_err(|e| anyhow::anyhow!("QQ: {e}"))?;
 
+        let mut session =
+            SessionTracker::new(self.state_store.as_deref(), format!("qq-{}", self.app_id));
         let mut sequence: i64 = -1;
 
-        // Spawn heartbeat timer
-        let (hb_tx, mut hb_rx) = tokio::sync::mpsc::channel::<()>(1);
-        let hb_interval = heartbeat_interval;
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_millis(hb_interval));
-            loop {
-                interval.tick().await;
-                if hb_tx.send(()).await.is_err() {
-                    break;
+        if let Some(saved) = session.resumable() {
+            // Resume (opcode 6) replays the events missed while we were away
+            let resume = json!({
+                "op": op::RESUME,
+                "d": {
+                    "token": format!("QQBot {token}"),
+                    "session_id": saved.session_id,
+                    "seq": saved.sequence,
                 }
-            }
-        });
+            });
+            frame_tap::record_json(&self.name, Direction::Outbound, "gateway", &resume);
+            write.send(Message::Text(resume.to_string())).await?;
+            sequence = saved.sequence;
+            tracing::info!("QQ: connected, resuming session at seq {sequence}");
+        } else {
+            // Send Identify (opcode 2)
+            // Intents: PUBLIC_GUILD_MESSAGES (1<<30) | INTERACTION (1<<26) |
+            // C2C_MESSAGE_CREATE & GROUP_AT_MESSAGE_CREATE (1<<25)
+            let intents: u64 = (1 << 25) | (1 << 26) | (1 << 30);
+            let identify = json!({
+                "op": op::IDENTIFY,
+                "d": {
+                    "token": format!("QQBot {token}"),
+                    "intents": intents,
+                    "properties": {
+                        "os": "linux",
+                        "browser": "zeroclaw",
+                        "device": "zeroclaw",
+                    }
+                }
+            });
+            frame_tap::record_json(&self.name, Direction::Outbound, "gateway", &identify);
+            write.send(Message::Text(identify.to_string())).await?;
+            tracing::info!("QQ: connected and identified");
+        }
+
+        let mut hb_rx =
+            gateway::spawn_heartbeat(&self.name, heartbeat_interval, self.heartbeat_jitter);
 
         loop {
             tokio::select! {
                 _ = hb_rx.recv() => {
-                    let d = if sequence >= 0 { json!(sequence) } else { json!(null) };
-                    let hb = json!({"op": 1, "d": d});
-                    if write.send(Message::Text(hb.to_string())).await.is_err() {
+                    if write.send(Message::Text(gateway::heartbeat_payload(sequence))).await.is_err() {
                         break;
                     }
+                    gateway::record_heartbeat(&self.name);
+                    session.persist(sequence);
                 }
                 msg = read.next() => {
                     let msg = match msg {
-                        Some(Ok(Message::Text(t))) => t,
-                        Some(Ok(Message::Close(_))) | None => break,
-                        _ => continue,
+                        Some(Ok(msg)) => msg,
+                        Some(Err(e)) => {
+                            tracing::warn!("QQ: gateway read failed: {e}");
+                            break;
+                        }
+                        None => break,
+                    };
+                    let msg = match decoder.decode(msg) {
+                        Ok(GatewayFrame::Payload(text)) => text,
+                        Ok(GatewayFrame::Ping(data)) => {
+                            if write.send(Message::Pong(data)).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+                        Ok(GatewayFrame::Close { code, reason }) => {
+                            let description = gateway::describe_close(code, &reason);
+                            match close_action(code) {
+                                CloseAction::Resume => {
+                                    tracing::warn!("QQ: gateway closed ({description}), reconnecting");
+                                }
+                                CloseAction::Identify => {
+                                    tracing::warn!("QQ: gateway closed ({description}), starting a new session");
+                                    session.invalidate();
+                                }
+                                CloseAction::Reauthenticate => {
+                                    tracing::warn!("QQ: gateway closed ({description}), refreshing token");
+                                    self.api.tokens().invalidate().await;
+                                }
+                                CloseAction::Fatal => {
+                                    tracing::error!("QQ: gateway closed ({description})");
+                                    session.persist(sequence);
+                                    return Err(GatewayClosed {
+                                        channel: self.name.clone(),
+                                        code: code.unwrap_or_default(),
+                                        reason,
+                                    }
+                                    .into());
+                                }
+                            }
+                            break;
+                        }
+                        Ok(GatewayFrame::Skip) => continue,
+                        Err(e) => {
+                            tracing::warn!("QQ: undecodable gateway frame: {e:#}");
+                            continue;
+                        }
                     };
+                    tracing::trace!("QQ: gateway frame ← {msg}");
+                    frame_tap::record(&self.name, Direction::Inbound, "gateway", &msg);
 
-                    let event: serde_json::Value = match serde_json::from_str(&msg) {
-                        Ok(e) => e,
-                        Err(_) => continue,
+                    let Some(event) = gateway::parse_payload(&self.name, &msg) else {
+                        continue;
                     };
 
                     // Track sequence number
-                    if let Some(s) = event.get("s").and_then(serde_json::Value::as_i64) {
+                    if let Some(s) = event.s {
                         sequence = s;
                     }
 
-                    let op = event.get("op").and_then(serde_json::Value::as_u64).unwrap_or(0);
-
-                    match op {
+                    match event.op {
                         // Server requests immediate heartbeat
-                        1 => {
-                            let d = if sequence >= 0 { json!(sequence) } else { json!(null) };
-                            let hb = json!({"op": 1, "d": d});
-                            if write.send(Message::Text(hb.to_string())).await.is_err() {
+                        op::HEARTBEAT => {
+                            if write.send(Message::Text(gateway::heartbeat_payload(sequence))).await.is_err() {
                                 break;
                             }
                             continue;
                         }
-                        // Reconnect
-                        7 => {
+                        op::HEARTBEAT_ACK => {
+                            gateway::record_heartbeat_ack(&self.name);
+                            continue;
+                        }
+                        op::RECONNECT => {
                             tracing::warn!("QQ: received Reconnect (op 7)");
                             break;
                         }
-                        // Invalid Session
-                        9 => {
+                        op::INVALID_SESSION => {
                             tracing::warn!("QQ: received Invalid Session (op 9)");
+                            session.invalidate();
                             break;
                         }
-                        _ => {}
-                    }
-
-                    // Only process dispatch events (op 0)
-                    if op != 0 {
-                        continue;
+                        // Only process dispatch events
+                        op::DISPATCH => {}
+                        _ => continue,
                     }
 
-                    let event_type = event.get("t").and_then(|t| t.as_str()).unwrap_or("");
-                    let d = match event.get("d") {
-                        Some(d) => d,
-                        None => continue,
-                    };
-
-                    match event_type {
-                        "C2C_MESSAGE_CREATE" => {
-                            let msg_id = d.get("id").and_then(|i| i.as_str()).unwrap_or("");
-                            if self.is_duplicate(msg_id).await {
-                                continue;
-                            }
-
-                            let content = d.get("content").and_then(|c| c.as_str()).unwrap_or("").trim();
-                            if content.is_empty() {
-                                continue;
+                    match event.event_type() {
+                        "READY" => {
+                            if let Some(ready) = event.decode::<gateway::Ready>(&self.name) {
+                                session.ready(&ready.session_id, None, sequence);
                             }
-
-                            let author_id = d.get("author").and_then(|a| a.get("id")).and_then(|i| i.as_str()).unwrap_or("unknown");
-                            // For QQ, user_openid is the identifier
-                            let user_openid = d.get("author").and_then(|a| a.get("user_openid")).and_then(|u| u.as_str()).unwrap_or(author_id);
-
-                            if !self.is_user_allowed(user_openid) {
-                                tracing::warn!("QQ: ignoring C2C message from unauthorized user: {user_openid}");
+                        }
+                        "RESUMED" => tracing::info!("QQ: session resumed"),
+                        "C2C_MESSAGE_CREATE" | "GROUP_AT_MESSAGE_CREATE" | "AT_MESSAGE_CREATE" => {
+                            let Some(channel_msg) = self.message_from_dispatch(&event).await else {
                                 continue;
-                            }
-
-                            let chat_id = format!("user:{user_openid}");
-
-                            let channel_msg = ChannelMessage {
-                                id: Uuid::new_v4().to_string(),
-                                sender: user_openid.to_string(),
-                                reply_target: chat_id,
-                                content: content.to_string(),
-                                channel: "qq".to_string(),
-                                timestamp: std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap_or_default()
-                                    .as_secs(),
                             };
-
                             if tx.send(channel_msg).await.is_err() {
                                 tracing::warn!("QQ: message channel closed");
                                 break;
                             }
                         }
-                        "GROUP_AT_MESSAGE_CREATE" => {
-                            let msg_id = d.get("id").and_then(|i| i.as_str()).unwrap_or("");
-                            if self.is_duplicate(msg_id).await {
+                        "INTERACTION_CREATE" => {
+                            let Some(click) = event.decode::<ButtonClick>(&self.name) else {
                                 continue;
-                            }
-
-                            let content = d.get("content").and_then(|c| c.as_str()).unwrap_or("").trim();
-                            if content.is_empty() {
+                            };
+                            self.acknowledge_interaction(&click.id).await;
+                            let Some(interaction) = click.to_interaction(&self.name) else {
                                 continue;
-                            }
-
-                            let author_id = d.get("author").and_then(|a| a.get("member_openid")).and_then(|m| m.as_str()).unwrap_or("unknown");
-
-                            if !self.is_user_allowed(author_id) {
-                                tracing::warn!("QQ: ignoring group message from unauthorized user: {author_id}");
+                            };
+                            if !self.is_user_allowed(&interaction.user_id) {
+                                tracing::warn!("QQ: ignoring click from unauthorized user: {}", interaction.user_id);
                                 continue;
                             }
-
-                            let group_openid = d.get("group_openid").and_then(|g| g.as_str()).unwrap_or("unknown");
-                            let chat_id = format!("group:{group_openid}");
-
-                            let channel_msg = ChannelMessage {
-                                id: Uuid::new_v4().to_string(),
-                                sender: author_id.to_string(),
-                                reply_target: chat_id,
-                                content: content.to_string(),
-                                channel: "qq".to_string(),
-                                timestamp: std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap_or_default()
-                                    .as_secs(),
-                            };
-
-                            if tx.send(channel_msg).await.is_err() {
+                            if !interactions::route(self, interaction, &tx).await {
                                 tracing::warn!("QQ: message channel closed");
                                 break;
                             }
@@ -407,11 +1028,41 @@ impl Channel for QQChannel {
             }
         }
 
+        session.persist(sequence);
         anyhow::bail!("QQ WebSocket connection closed")
     }
 
     async fn health_check(&self) -> bool {
-        self.fetch_access_token().await.is_ok()
+        self.api.tokens().source().fetch().await.is_ok()
+    }
+
+    /// Guild channel members allowed to manage the channel. QQ does not tell
+    /// bots who administers a group, so groups have no admins here.
+    async fn is_group_admin(&self, msg: &ChannelMessage) -> anyhow::Result<bool> {
+        let Some(channel_id) = msg.reply_target.strip_prefix("channel:") else {
+            return Ok(false);
+        };
+        let path = format!(
+            "/channels/{channel_id}/members/{}/permissions",
+            msg.author_id()
+        );
+        let resp = self.api.get_json(&path, "get channel permissions").await?;
+        let permissions = resp
+            .get("permissions")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|bits| bits.parse::<u64>().ok())
+            .unwrap_or(0);
+        Ok(permissions & MANAGE_CHANNEL_PERMISSION != 0)
+    }
+
+    /// C2C chats are one session per user; group and guild channel members
+    /// each get their own session within the group or channel.
+    fn conversation_key(&self, msg: &ChannelMessage) -> String {
+        if msg.reply_target.starts_with("group:") || msg.reply_target.starts_with("channel:") {
+            format!("{}:{}", msg.reply_target, msg.sender)
+        } else {
+            format!("dm:{}", msg.sender)
+        }
     }
 }
 
@@ -425,6 +1076,67 @@ mod tests {
         assert_eq!(ch.name(), "qq");
     }
 
+    #[tokio::test]
+    async fn test_callback_requests_are_signed_with_the_bot_secret() {
+        const SECRET: &str = "naOC0ocQE3shWLAfffVLB1rhYPG7";
+        let ch = QQChannel::new("id".into(), SECRET.into(), vec!["*".into()]).with_callback(true);
+        let signer = Ed25519Signer::from_secret(SECRET).unwrap();
+        let sign =
+            |timestamp: &str, body: &str| signer.sign(format!("{timestamp}{body}").as_bytes());
+        let now = chrono::Utc::now().timestamp().to_string();
+
+        let validation =
+            r#"{"op":13,"d":{"plain_token":"Arq0D5A61EgUu4OxUvOp","event_ts":"1725442341"}}"#;
+        assert!(ch
+            .handle_callback(&now, &"00".repeat(64), validation.as_bytes())
+            .await
+            .is_err());
+        let (reply, message) = ch
+            .handle_callback(&now, &sign(&now, validation), validation.as_bytes())
+            .await
+            .unwrap();
+        assert!(message.is_none());
+        assert_eq!(reply["plain_token"], "Arq0D5A61EgUu4OxUvOp");
+        assert_eq!(
+            reply["signature"],
+            signer.sign(b"1725442341Arq0D5A61EgUu4OxUvOp")
+        );
+
+        let dispatch = r#"{"op":0,"t":"C2C_MESSAGE_CREATE","d":{"id":"m1","content":"hello","author":{"user_openid":"u1"}}}"#;
+        let signature = sign(&now, dispatch);
+        let (reply, message) = ch
+            .handle_callback(&now, &signature, dispatch.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(reply["op"], 12);
+        let message = message.unwrap();
+        assert_eq!(message.content, "hello");
+        assert_eq!(message.reply_target, "user:u1");
+        // The same signed request is refused the second time
+        assert!(ch
+            .handle_callback(&now, &signature, dispatch.as_bytes())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_close_codes() {
+        assert_eq!(close_action(None), CloseAction::Resume);
+        assert_eq!(close_action(Some(1000)), CloseAction::Resume);
+        assert_eq!(close_action(Some(4009)), CloseAction::Resume);
+        assert_eq!(close_action(Some(4900)), CloseAction::Resume);
+        assert_eq!(close_action(Some(4004)), CloseAction::Reauthenticate);
+        assert_eq!(close_action(Some(4007)), CloseAction::Identify);
+        assert_eq!(close_action(Some(4013)), CloseAction::Fatal);
+        assert_eq!(close_action(Some(4915)), CloseAction::Fatal);
+    }
+
+    #[test]
+    fn test_named_instance() {
+        let ch = QQChannel::new("id".into(), "secret".into(), vec![]).with_name("qq-sandbox");
+        assert_eq!(ch.name(), "qq-sandbox");
+    }
+
     #[test]
     fn test_user_allowed_wildcard() {
         let ch = QQChannel::new("id".into(), "secret".into(), vec!["*".into()]);
@@ -444,6 +1156,16 @@ mod tests {
         assert!(!ch.is_user_allowed("anyone"));
     }
 
+    #[tokio::test]
+    async fn test_with_secret_reads_managed_secret() {
+        let secret = Arc::new(ManagedSecret::fixed("rotating"));
+        let ch = QQChannel::with_secret("id".into(), secret, vec![]);
+        assert_eq!(
+            ch.api.tokens().source().app_secret.get().await.unwrap(),
+            "rotating"
+        );
+    }
+
     #[tokio::test]
     async fn test_dedup() {
         let ch = QQChannel::new("id".into(), "secret".into(), vec![]);
@@ -460,6 +1182,107 @@ mod tests {
         assert!(!ch.is_duplicate("").await);
     }
 
+    #[test]
+    fn test_platform_message_id() {
+        assert_eq!(platform_message_id("ROBOT1.0_abc"), "ROBOT1.0_abc");
+        assert!(!platform_message_id("").is_empty());
+    }
+
+    #[test]
+    fn test_split_image_markers() {
+        let (text, images) = split_image_markers(
+            "Here you go [IMAGE:/ws/generated/a.png] and [IMAGE: https://x/b.png ]",
+        );
+        assert_eq!(text, "Here you go  and");
+        assert_eq!(images, ["/ws/generated/a.png", "https://x/b.png"]);
+
+        let (text, images) = split_image_markers("no images [here]");
+        assert_eq!(text, "no images [here]");
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn test_recipient_path() {
+        assert_eq!(recipient_path("group:G1"), "/v2/groups/G1");
+        assert_eq!(recipient_path("user:U1"), "/v2/users/U1");
+        assert_eq!(recipient_path("U2"), "/v2/users/U2");
+        assert_eq!(recipient_path("channel:C1"), "/channels/C1");
+    }
+
+    #[test]
+    fn test_button_clicks() {
+        let group: ButtonClick = serde_json::from_value(json!({
+            "id": "i1",
+            "chat_type": 1,
+            "group_openid": "G1",
+            "group_member_openid": "M1",
+            "data": { "type": 11, "resolved": { "button_data": "menu:yes", "message_id": "m1" } }
+        }))
+        .unwrap();
+        let interaction = group.to_interaction("qq").unwrap();
+        assert_eq!(interaction.callback_id, "menu:yes");
+        assert_eq!(interaction.user_id, "M1");
+        assert_eq!(interaction.reply_target, "group:G1");
+
+        let c2c: ButtonClick = serde_json::from_value(json!({
+            "id": "i2",
+            "chat_type": 2,
+            "user_openid": "U1",
+            "data": { "resolved": { "button_data": "menu:no" } }
+        }))
+        .unwrap();
+        assert_eq!(c2c.to_interaction("qq").unwrap().reply_target, "user:U1");
+
+        let keyboard = Keyboard::new().with_row(vec![interactions::Button::new("menu:yes", "Yes")]);
+        let rendered = keyboard_json(&keyboard);
+        assert_eq!(
+            rendered["content"]["rows"][0]["buttons"][0]["action"]["data"],
+            "menu:yes"
+        );
+        assert_eq!(
+            rendered["content"]["rows"][0]["buttons"][0]["render_data"]["label"],
+            "Yes"
+        );
+    }
+
+    #[test]
+    fn test_parse_attachments() {
+        let d: MessageEvent = serde_json::from_value(json!({
+            "attachments": [
+                {
+                    "content_type": "voice",
+                    "filename": "a.silk",
+                    "url": "multimedia.nt.qq.com.cn/download?x=1",
+                    "voice_wav_url": "https://multimedia.nt.qq.com.cn/a.wav"
+                },
+                { "content_type": "image/png", "url": "//gchat.qpic.cn/p.png" }
+            ]
+        }))
+        .unwrap();
+        let attachments = parse_attachments(&d.attachments);
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].kind, MediaKind::Voice);
+        assert_eq!(
+            attachments[0].source,
+            "https://multimedia.nt.qq.com.cn/a.wav"
+        );
+        assert_eq!(attachments[1].kind, MediaKind::Image);
+        assert_eq!(attachments[1].source, "https://gchat.qpic.cn/p.png");
+        assert!(parse_attachments(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_conversation_key() {
+        let ch = QQChannel::new("id".into(), "secret".into(), vec![]);
+        let mut msg = ChannelMessage {
+            reply_target: "user:u1".into(),
+            ..ChannelMessage::test("qq", "u1", "hi")
+        };
+        assert_eq!(ch.conversation_key(&msg), "dm:u1");
+        msg.reply_target = "group:g1".into();
+        assert_eq!(ch.conversation_key(&msg), "group:g1:u1");
+    }
+
     #[test]
     fn test_config_serde() {
         let toml_str = r#"
@@ -471,5 +1294,59 @@ allowed_users = ["user1"]
         assert_eq!(config.app_id, "12345");
         assert_eq!(config.app_secret, "secret_abc");
         assert_eq!(config.allowed_users, vec!["user1"]);
+        assert_eq!(
+            QQEnvironment::from_config(&config).unwrap(),
+            QQEnvironment::Production
+        );
+    }
+
+    #[test]
+    fn test_environment_selects_api_base() {
+        let ch = QQChannel::new("id".into(), "secret".into(), vec![])
+            .with_environment(QQEnvironment::Sandbox);
+        assert_eq!(ch.api.base_url(), QQ_SANDBOX_API_BASE);
+
+        let mut config: QQConfig = toml::from_str(
+            r#"
+app_id = "12345"
+app_secret = "secret_abc"
+environment = "custom"
+"#,
+        )
+        .unwrap();
+        assert!(QQEnvironment::from_config(&config).is_err());
+        config.api_base = Some("https://qq-relay.internal/".into());
+        let custom = QQEnvironment::from_config(&config).unwrap();
+        assert_eq!(custom.api_base(), "https://qq-relay.internal");
+        config.api_base = Some("qq-relay.internal".into());
+        assert!(QQEnvironment::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_relay_overrides_api_base_and_gateway() {
+        let config: QQConfig = toml::from_str(
+            r#"
+app_id = "12345"
+app_secret = "secret_abc"
+environment = "sandbox"
+api_base = "https://qq-relay.internal/sandbox"
+gateway_url = "wss://qq-relay.internal/websocket"
+"#,
+        )
+        .unwrap();
+        let ch = QQChannel::new("id".into(), "secret".into(), vec![])
+            .with_environment(QQEnvironment::from_config(&config).unwrap())
+            .with_gateway_url(configured_gateway_url(&config).unwrap());
+        assert_eq!(ch.api.base_url(), "https://qq-relay.internal/sandbox");
+        assert_eq!(
+            ch.get_gateway_url().await.unwrap(),
+            "wss://qq-relay.internal/websocket"
+        );
+
+        let bad = QQConfig {
+            gateway_url: Some("https://qq-relay.internal/websocket".into()),
+            ..config
+        };
+        assert!(configured_gateway_url(&bad).is_err());
     }
 }
@@ -0,0 +1,285 @@
+//! Quiet hours (`[channels_config.quiet_hours]`).
+//!
+//! While a rule's window is open for a chat, [`ChannelControl::send`] holds
+//! [normal](Priority::Normal) notifications in a [`DeferredQueue`] instead of
+//! sending them; [`run_release_loop`] sends them, in order, once the window
+//! closes. Urgent notifications — reminders, critical alerts, monitor alerts
+//! marked `urgent`, topic notifications published with `"urgent": true` —
+//! and replies to the user's own messages are never held. Held messages live
+//! in memory only.
+
+use super::control::ChannelControl;
+use super::priority::Priority;
+use crate::config::{ChannelsConfig, QuietHoursRule};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use parking_lot::{Mutex, RwLock};
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+/// How often held messages are checked for release.
+const RELEASE_TICK: Duration = Duration::from_secs(30);
+
+/// Messages held at once; the oldest are sent early beyond this.
+const MAX_DEFERRED: usize = 1000;
+
+#[derive(Debug, Clone)]
+struct QuietWindow {
+    channel: String,
+    recipient: Option<String>,
+    start: NaiveTime,
+    end: NaiveTime,
+    timezone: Tz,
+}
+
+impl QuietWindow {
+    fn parse(rule: &QuietHoursRule) -> anyhow::Result<Self> {
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                .map_err(|_| anyhow::anyhow!("invalid time {value:?} (expected HH:MM)"))
+        };
+        let timezone = match rule.timezone.as_deref() {
+            Some(name) => {
+                Tz::from_str(name).map_err(|_| anyhow::anyhow!("unknown timezone {name:?}"))?
+            }
+            None => Tz::UTC,
+        };
+        Ok(Self {
+            channel: rule.channel.clone(),
+            recipient: rule.recipient.clone(),
+            start: time(&rule.start)?,
+            end: time(&rule.end)?,
+            timezone,
+        })
+    }
+
+    fn applies_to(&self, channel: &str, recipient: &str) -> bool {
+        self.channel == channel && self.recipient.iter().all(|chat| chat == recipient)
+    }
+
+    /// When the window open at `now` closes, or `None` if it isn't open.
+    fn closes_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&self.timezone);
+        let time = local.time();
+        let open = if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        };
+        if !open {
+            return None;
+        }
+        let mut date = local.date_naive();
+        if time >= self.end {
+            date = date.succ_opt()?;
+        }
+        let end = self
+            .timezone
+            .from_local_datetime(&date.and_time(self.end))
+            .earliest()
+            // The end falls into a DST gap
+            .map_or_else(
+                || now + ChronoDuration::hours(1),
+                |end| end.with_timezone(&Utc),
+            );
+        Some(end)
+    }
+}
+
+static WINDOWS: LazyLock<RwLock<Vec<QuietWindow>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Apply the quiet hours of `config` from now on; invalid rules are logged
+/// and skipped.
+pub fn configure(config: &ChannelsConfig) {
+    let quiet_hours = &config.quiet_hours;
+    let windows = if quiet_hours.enabled {
+        quiet_hours
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                QuietWindow::parse(rule)
+                    .inspect_err(|e| {
+                        tracing::warn!("Ignoring quiet hours rule for {}: {e}", rule.channel);
+                    })
+                    .ok()
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    *WINDOWS.write() = windows;
+}
+
+/// When quiet hours for `recipient` on `channel` end, if they are on at
+/// `now`.
+pub fn quiet_until(channel: &str, recipient: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    quiet_until_in(&WINDOWS.read(), channel, recipient, now)
+}
+
+fn quiet_until_in(
+    windows: &[QuietWindow],
+    channel: &str,
+    recipient: &str,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let matching = windows.iter().filter(|w| w.applies_to(channel, recipient));
+    let specific: Vec<&QuietWindow> = matching.clone().filter(|w| w.recipient.is_some()).collect();
+    let windows: Vec<&QuietWindow> = if specific.is_empty() {
+        matching.collect()
+    } else {
+        specific
+    };
+    windows.iter().filter_map(|w| w.closes_at(now)).max()
+}
+
+/// Whether a message of `priority` to `recipient` must wait, and until when.
+pub fn hold_until(channel: &str, recipient: &str, priority: Priority) -> Option<DateTime<Utc>> {
+    match priority {
+        Priority::Urgent => None,
+        Priority::Normal => quiet_until(channel, recipient, Utc::now()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferredMessage {
+    pub channel: String,
+    pub recipient: String,
+    pub message: String,
+    pub release_at: DateTime<Utc>,
+}
+
+/// Notifications held until their chat's quiet hours end.
+#[derive(Default)]
+pub struct DeferredQueue {
+    messages: Mutex<Vec<DeferredMessage>>,
+}
+
+impl DeferredQueue {
+    pub fn push(&self, channel: &str, recipient: &str, message: &str, release_at: DateTime<Utc>) {
+        let mut messages = self.messages.lock();
+        if messages.len() >= MAX_DEFERRED {
+            // Release the oldest early rather than drop it
+            messages[0].release_at = Utc::now();
+        }
+        messages.push(DeferredMessage {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            message: message.to_string(),
+            release_at,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().is_empty()
+    }
+
+    /// Remove the messages due by `now`, oldest first.
+    pub fn take_due(&self, now: DateTime<Utc>) -> Vec<DeferredMessage> {
+        let mut messages = self.messages.lock();
+        let (due, held) = messages.drain(..).partition(|m| m.release_at <= now);
+        *messages = held;
+        due
+    }
+}
+
+/// Send held notifications as their quiet hours end, until the process
+/// stops.
+pub async fn run_release_loop(control: Arc<ChannelControl>) {
+    let mut ticker = tokio::time::interval(RELEASE_TICK);
+    loop {
+        ticker.tick().await;
+        for held in control.deferred().take_due(Utc::now()) {
+            if let Err(e) = control
+                .send_with_priority(
+                    &held.channel,
+                    &held.recipient,
+                    &held.message,
+                    Priority::Urgent,
+                )
+                .await
+            {
+                tracing::warn!(
+                    "Failed to send a notification held for quiet hours to {}: {e}",
+                    held.channel
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(recipient: Option<&str>, start: &str, end: &str) -> QuietHoursRule {
+        QuietHoursRule {
+            channel: "telegram".into(),
+            recipient: recipient.map(str::to_string),
+            start: start.into(),
+            end: end.into(),
+            timezone: Some("Europe/Berlin".into()),
+        }
+    }
+
+    fn utc(at: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(at)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn windows_cross_midnight_in_the_rules_timezone() {
+        let windows = vec![QuietWindow::parse(&rule(None, "22:00", "07:00")).unwrap()];
+        let quiet = |at| quiet_until_in(&windows, "telegram", "42", utc(at));
+
+        // 23:30 in Berlin (CEST) is 21:30 UTC; quiet until 07:00 local
+        assert_eq!(
+            quiet("2026-06-01T21:30:00Z"),
+            Some(utc("2026-06-02T05:00:00Z"))
+        );
+        assert_eq!(
+            quiet("2026-06-02T03:00:00Z"),
+            Some(utc("2026-06-02T05:00:00Z"))
+        );
+        assert_eq!(quiet("2026-06-02T12:00:00Z"), None);
+        assert_eq!(
+            quiet_until_in(&windows, "slack", "42", utc("2026-06-01T21:30:00Z")),
+            None
+        );
+        assert!(QuietWindow::parse(&rule(None, "25:00", "07:00")).is_err());
+    }
+
+    #[test]
+    fn recipient_rules_win_over_channel_rules() {
+        let windows = vec![
+            QuietWindow::parse(&rule(None, "22:00", "07:00")).unwrap(),
+            QuietWindow::parse(&rule(Some("oncall"), "03:00", "04:00")).unwrap(),
+        ];
+        let at = utc("2026-06-01T21:30:00Z");
+        assert!(quiet_until_in(&windows, "telegram", "42", at).is_some());
+        assert_eq!(quiet_until_in(&windows, "telegram", "oncall", at), None);
+    }
+
+    #[test]
+    fn deferred_messages_release_in_order() {
+        let queue = DeferredQueue::default();
+        let morning = utc("2026-06-02T05:00:00Z");
+        queue.push("telegram", "42", "first", morning);
+        queue.push("telegram", "42", "second", morning);
+        queue.push("telegram", "7", "later", morning + ChronoDuration::hours(2));
+
+        assert!(queue.take_due(utc("2026-06-02T04:59:00Z")).is_empty());
+        let due: Vec<String> = queue
+            .take_due(morning)
+            .into_iter()
+            .map(|m| m.message)
+            .collect();
+        assert_eq!(due, ["first", "second"]);
+        assert_eq!(queue.len(), 1);
+    }
+}
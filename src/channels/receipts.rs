@@ -0,0 +1,221 @@
+//! Delivery and read receipts of tracked messages.
+//!
+//! [`Channel::send_tracked`](super::traits::Channel::send_tracked) sends an
+//! announcement and records it with [`track`]. Channels whose platform
+//! reports message status (WhatsApp's `statuses` webhooks) feed each report
+//! to [`record`], which keeps the furthest status per reader and emits a
+//! [`LifecycleEvent::MessageStatus`] hook event. [`stats`] answers "was my
+//! announcement seen" for the admin API. Receipts are kept in memory for the
+//! newest [`MAX_TRACKED_MESSAGES`] messages.
+
+use super::hooks::{self, LifecycleEvent};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Messages whose receipts are kept; the oldest are forgotten beyond this.
+pub const MAX_TRACKED_MESSAGES: usize = 500;
+
+/// Longest preview of a message kept for the stats.
+const PREVIEW_CHARS: usize = 80;
+
+/// Status of a sent message, in the order it normally advances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// Parse a platform status name (`sent`, `delivered`, `read`, `failed`).
+    pub fn parse(status: &str) -> Option<Self> {
+        match status.to_ascii_lowercase().as_str() {
+            "sent" => Some(Self::Sent),
+            "delivered" => Some(Self::Delivered),
+            "read" | "seen" | "played" => Some(Self::Read),
+            "failed" | "undelivered" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+struct TrackedMessage {
+    channel: String,
+    recipient: String,
+    preview: String,
+    sent_at: u64,
+    /// Furthest status reported by each reader
+    readers: HashMap<String, (DeliveryStatus, u64)>,
+}
+
+#[derive(Default)]
+struct Registry {
+    messages: HashMap<String, TrackedMessage>,
+    /// Message ids, oldest first
+    order: Vec<String>,
+}
+
+static MESSAGES: LazyLock<Mutex<Registry>> = LazyLock::new(|| Mutex::new(Registry::default()));
+
+/// Id under which a channel's message is tracked.
+pub fn message_id(channel: &str, platform_id: &str) -> String {
+    format!("{channel}:{platform_id}")
+}
+
+/// Start collecting receipts for a message the channel just sent.
+/// `platform_id` is the platform's message id. Returns the tracked id.
+pub fn track(channel: &str, platform_id: &str, recipient: &str, message: &str) -> String {
+    let id = message_id(channel, platform_id);
+    let mut registry = MESSAGES.lock();
+    if registry.order.len() >= MAX_TRACKED_MESSAGES {
+        let oldest = registry.order.remove(0);
+        registry.messages.remove(&oldest);
+    }
+    registry.order.push(id.clone());
+    registry.messages.insert(
+        id.clone(),
+        TrackedMessage {
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            preview: crate::util::truncate_with_ellipsis(message, PREVIEW_CHARS),
+            sent_at: super::traits::unix_now(),
+            readers: HashMap::new(),
+        },
+    );
+    id
+}
+
+/// Record a status report for a message. Statuses only move forward, so a
+/// late `delivered` doesn't undo a `read`; `failed` always sticks. Reports
+/// for untracked messages are ignored.
+pub fn record(channel: &str, platform_id: &str, reader: &str, status: DeliveryStatus, at: u64) {
+    let id = message_id(channel, platform_id);
+    {
+        let mut registry = MESSAGES.lock();
+        let Some(tracked) = registry.messages.get_mut(&id) else {
+            return;
+        };
+        let entry = tracked
+            .readers
+            .entry(reader.to_string())
+            .or_insert((status, at));
+        if status > entry.0 {
+            *entry = (status, at);
+        }
+    }
+    hooks::emit(LifecycleEvent::MessageStatus {
+        channel: channel.to_string(),
+        message_id: id,
+        reader: reader.to_string(),
+        status,
+    });
+}
+
+/// Receipts of a tracked message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptStats {
+    pub id: String,
+    pub channel: String,
+    pub recipient: String,
+    pub preview: String,
+    pub sent_at: u64,
+    /// Readers the message reached (delivered or read)
+    pub delivered: usize,
+    pub read: usize,
+    pub failed: usize,
+    /// When the first reader read it
+    pub first_read_at: Option<u64>,
+}
+
+fn summarize(id: &str, tracked: &TrackedMessage) -> ReceiptStats {
+    let count = |wanted: &[DeliveryStatus]| {
+        tracked
+            .readers
+            .values()
+            .filter(|(status, _)| wanted.contains(status))
+            .count()
+    };
+    ReceiptStats {
+        id: id.to_string(),
+        channel: tracked.channel.clone(),
+        recipient: tracked.recipient.clone(),
+        preview: tracked.preview.clone(),
+        sent_at: tracked.sent_at,
+        delivered: count(&[DeliveryStatus::Delivered, DeliveryStatus::Read]),
+        read: count(&[DeliveryStatus::Read]),
+        failed: count(&[DeliveryStatus::Failed]),
+        first_read_at: tracked
+            .readers
+            .values()
+            .filter(|(status, _)| *status == DeliveryStatus::Read)
+            .map(|(_, at)| *at)
+            .min(),
+    }
+}
+
+/// Receipts of one tracked message.
+pub fn stats(id: &str) -> Option<ReceiptStats> {
+    let registry = MESSAGES.lock();
+    registry
+        .messages
+        .get(id)
+        .map(|tracked| summarize(id, tracked))
+}
+
+/// Receipts of every tracked message, newest first.
+pub fn all_stats() -> Vec<ReceiptStats> {
+    let registry = MESSAGES.lock();
+    registry
+        .order
+        .iter()
+        .rev()
+        .filter_map(|id| {
+            registry
+                .messages
+                .get(id)
+                .map(|tracked| summarize(id, tracked))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_only_move_forward() {
+        let id = track("whatsapp", "wamid.receipts", "+100", "Office closed Friday");
+        let report = |reader, status, at| record("whatsapp", "wamid.receipts", reader, status, at);
+        report("+100", DeliveryStatus::Sent, 10);
+        report("+100", DeliveryStatus::Read, 30);
+        report("+100", DeliveryStatus::Delivered, 20);
+        report("+200", DeliveryStatus::Delivered, 25);
+        record(
+            "whatsapp",
+            "wamid.unknown",
+            "+100",
+            DeliveryStatus::Read,
+            40,
+        );
+
+        assert!(stats("whatsapp:wamid.unknown").is_none());
+        let stats = stats(&id).unwrap();
+        assert_eq!(stats.preview, "Office closed Friday");
+        assert_eq!((stats.delivered, stats.read, stats.failed), (2, 1, 0));
+        assert_eq!(stats.first_read_at, Some(30));
+    }
+
+    #[test]
+    fn platform_status_names_parse() {
+        assert_eq!(DeliveryStatus::parse("READ"), Some(DeliveryStatus::Read));
+        assert_eq!(
+            DeliveryStatus::parse("delivered"),
+            Some(DeliveryStatus::Delivered)
+        );
+        assert_eq!(DeliveryStatus::parse("typing"), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! Conversation sessions for channel chats.
+//!
+//! Each native conversation (see [`Channel::conversation_key`]) has a
+//! current session holding recent turns, which are replayed into the prompt
+//! so follow-up questions have context. A session ends after
+//! `idle_timeout_secs` without messages or when the user sends `/reset`;
+//! the ended session is kept so `/continue` can bring it back.
+//!
+//! Sessions live in memory and do not survive restarts; long-term recall is
+//! the memory backend's job.
+//!
+//! [`Channel::conversation_key`]: super::traits::Channel::conversation_key
+
+use crate::config::schema::SessionConfig;
+use crate::providers::ChatMessage;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Conversations untouched for this long are forgotten entirely, including
+/// the session `/continue` would restore.
+const CONVERSATION_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+struct Session {
+    id: String,
+    history: Vec<ChatMessage>,
+    last_active: Instant,
+}
+
+impl Session {
+    fn new(now: Instant) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            history: Vec::new(),
+            last_active: now,
+        }
+    }
+}
+
+struct Conversation {
+    current: Session,
+    previous: Option<Session>,
+}
+
+/// The session a message belongs to and the turns that precede it.
+pub struct ActiveSession {
+    pub id: String,
+    pub history: Vec<ChatMessage>,
+}
+
+pub struct SessionStore {
+    idle_timeout: Duration,
+    max_history: usize,
+    conversations: Mutex<HashMap<String, Conversation>>,
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new(&SessionConfig::default())
+    }
+}
+
+impl SessionStore {
+    pub fn new(config: &SessionConfig) -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(config.idle_timeout_secs),
+            max_history: config.max_history,
+            conversations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Current session for `key`, starting a new one if the last went idle.
+    pub fn begin(&self, key: &str) -> ActiveSession {
+        self.begin_at(key, Instant::now())
+    }
+
+    fn begin_at(&self, key: &str, now: Instant) -> ActiveSession {
+        let mut conversations = self.conversations.lock();
+        conversations
+            .retain(|_, c| now.duration_since(c.current.last_active) < CONVERSATION_RETENTION);
+        let conversation = conversations
+            .entry(key.to_string())
+            .or_insert_with(|| Conversation {
+                current: Session::new(now),
+                previous: None,
+            });
+        if now.duration_since(conversation.current.last_active) >= self.idle_timeout {
+            let expired = std::mem::replace(&mut conversation.current, Session::new(now));
+            if !expired.history.is_empty() {
+                conversation.previous = Some(expired);
+            }
+        }
+        ActiveSession {
+            id: conversation.current.id.clone(),
+            history: conversation.current.history.clone(),
+        }
+    }
+
+    /// The current session of `key` without starting one; `None` when there
+    /// is none or it went idle.
+    pub fn current(&self, key: &str) -> Option<ActiveSession> {
+        let conversations = self.conversations.lock();
+        let session = &conversations.get(key)?.current;
+        (session.last_active.elapsed() < self.idle_timeout).then(|| ActiveSession {
+            id: session.id.clone(),
+            history: session.history.clone(),
+        })
+    }
+
+    /// Record a completed exchange in the current session.
+    pub fn append(&self, key: &str, user: &str, assistant: &str) {
+        self.append_at(key, user, assistant, Instant::now());
+    }
+
+    fn append_at(&self, key: &str, user: &str, assistant: &str, now: Instant) {
+        let mut conversations = self.conversations.lock();
+        let Some(conversation) = conversations.get_mut(key) else {
+            return;
+        };
+        let session = &mut conversation.current;
+        session.history.push(ChatMessage::user(user));
+        session.history.push(ChatMessage::assistant(assistant));
+        let excess = session.history.len().saturating_sub(self.max_history);
+        session.history.drain(..excess);
+        session.last_active = now;
+    }
+
+    /// End the current session. Returns `false` if it was already empty.
+    pub fn reset(&self, key: &str) -> bool {
+        let mut conversations = self.conversations.lock();
+        let Some(conversation) = conversations.get_mut(key) else {
+            return false;
+        };
+        let ended = std::mem::replace(&mut conversation.current, Session::new(Instant::now()));
+        if ended.history.is_empty() {
+            return false;
+        }
+        conversation.previous = Some(ended);
+        true
+    }
+
+    /// Bring back the session ended by the last reset or idle expiry.
+    /// Returns the number of messages restored.
+    pub fn resume(&self, key: &str) -> Option<usize> {
+        let mut conversations = self.conversations.lock();
+        let conversation = conversations.get_mut(key)?;
+        let mut previous = conversation.previous.take()?;
+        previous.last_active = Instant::now();
+        let restored = previous.history.len();
+        let replaced = std::mem::replace(&mut conversation.current, previous);
+        if !replaced.history.is_empty() {
+            conversation.previous = Some(replaced);
+        }
+        Some(restored)
+    }
+
+    /// Drop every conversation of `user` on `channel`, current and ended
+    /// sessions alike. Returns the number of conversations dropped.
+    pub fn forget_user(&self, channel: &str, user: &str) -> usize {
+        let prefix = format!("{channel}:");
+        let suffix = format!(":{user}");
+        let mut conversations = self.conversations.lock();
+        let before = conversations.len();
+        conversations.retain(|key, _| !(key.starts_with(&prefix) && key.ends_with(&suffix)));
+        before - conversations.len()
+    }
+
+    /// Answer `/reset` (or `/new`) and `/continue`. Returns `None` for any
+    /// other message.
+    pub fn handle_command(&self, key: &str, content: &str) -> Option<String> {
+        let command = content.split_whitespace().next()?.split('@').next()?;
+        let reply = match command {
+            "/reset" | "/new" => {
+                if self.reset(key) {
+                    "🧹 Started a new conversation. Send /continue to go back.".to_string()
+                } else {
+                    "🧹 Started a new conversation.".to_string()
+                }
+            }
+            "/continue" => match self.resume(key) {
+                Some(n) => format!("↩️ Resumed the previous conversation ({n} messages)."),
+                None => "There is no earlier conversation to resume.".to_string(),
+            },
+            _ => return None,
+        };
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(idle_timeout_secs: u64, max_history: usize) -> SessionStore {
+        SessionStore::new(&SessionConfig {
+            idle_timeout_secs,
+            max_history,
+        })
+    }
+
+    #[test]
+    fn history_accumulates_and_is_capped() {
+        let sessions = store(1800, 4);
+        let first = sessions.begin("dm:alice");
+        assert!(first.history.is_empty());
+        for i in 0..3 {
+            sessions.append("dm:alice", &format!("q{i}"), &format!("a{i}"));
+        }
+        let active = sessions.begin("dm:alice");
+        assert_eq!(active.id, first.id);
+        let contents: Vec<_> = active.history.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["q1", "a1", "q2", "a2"]);
+        assert!(sessions.begin("dm:bob").history.is_empty());
+    }
+
+    #[test]
+    fn idle_sessions_expire_and_can_be_resumed() {
+        let sessions = store(60, 20);
+        let start = Instant::now();
+        let first = sessions.begin_at("dm:alice", start);
+        sessions.append_at("dm:alice", "hello", "hi", start);
+
+        let later = sessions.begin_at("dm:alice", start + Duration::from_secs(61));
+        assert_ne!(later.id, first.id);
+        assert!(later.history.is_empty());
+
+        assert_eq!(sessions.resume("dm:alice"), Some(2));
+        let resumed = sessions.begin("dm:alice");
+        assert_eq!(resumed.id, first.id);
+        assert_eq!(resumed.history.len(), 2);
+    }
+
+    #[test]
+    fn reset_and_continue_commands() {
+        let sessions = store(1800, 20);
+        sessions.begin("dm:alice");
+        assert!(sessions
+            .handle_command("dm:alice", "/continue")
+            .unwrap()
+            .contains("no earlier"));
+
+        sessions.append("dm:alice", "hello", "hi");
+        assert!(sessions
+            .handle_command("dm:alice", "/reset")
+            .unwrap()
+            .contains("/continue"));
+        assert!(sessions.begin("dm:alice").history.is_empty());
+
+        assert!(sessions
+            .handle_command("dm:alice", "/continue@zeroclaw_bot")
+            .unwrap()
+            .contains("2 messages"));
+        assert_eq!(sessions.begin("dm:alice").history.len(), 2);
+        assert!(sessions.handle_command("dm:alice", "hello").is_none());
+    }
+
+    #[test]
+    fn forget_user_drops_only_their_conversations() {
+        let sessions = store(1800, 20);
+        for key in [
+            "telegram:dm:alice",
+            "telegram:group:-100:alice",
+            "telegram:dm:malice",
+            "slack:dm:alice",
+        ] {
+            sessions.begin(key);
+            sessions.append(key, "hello", "hi");
+        }
+        assert_eq!(sessions.forget_user("telegram", "alice"), 2);
+        assert!(sessions.current("telegram:dm:alice").is_none());
+        assert!(sessions.current("telegram:dm:malice").is_some());
+        assert!(sessions.current("slack:dm:alice").is_some());
+    }
+}
@@ -0,0 +1,496 @@
+//! Shadow channels (`[[channels_config.shadows]]`).
+//!
+//! A shadow mirrors a live source channel: every message the source
+//! accepts is handed to the shadow as well, under the shadow's name, and
+//! answered through the full pipeline with the shadow's own model or
+//! persona. Nothing the shadow sends leaves the process. Its replies and
+//! the source's replies to the same message are paired up in a
+//! [`ShadowLog`], which the admin API serves at `GET /admin/shadows`, so a
+//! new persona or model can be compared against real traffic.
+//!
+//! Commands (`/...`, or a group's own command prefix) are not mirrored:
+//! they change state shared with the source (settings, erasure,
+//! announcements), which must happen once. For the same reason a shadow turn leaves no trace: only read-only tools run
+//! (the others answer that they are disabled), and its messages are not
+//! auto-saved to memory or counted in quotas and analytics.
+
+use super::control::{ChannelControl, OutboundRecord};
+use super::traits::{Channel, ChannelCapabilities, ChannelMessage};
+use crate::config::ShadowChannelConfig;
+use crate::tools::{Tool, ToolResult};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Weak};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Comparisons kept for the admin API.
+const SHADOW_LOG_CAPACITY: usize = 200;
+
+/// Channel that answers a copy of `source`'s traffic and delivers nothing.
+pub struct ShadowChannel {
+    name: String,
+    source: String,
+    control: Arc<ChannelControl>,
+}
+
+impl ShadowChannel {
+    pub fn new(name: &str, source: &str, control: Arc<ChannelControl>) -> Self {
+        Self {
+            name: name.to_string(),
+            source: source.to_string(),
+            control,
+        }
+    }
+
+    /// The shadow's copy of `msg`, or `None` when it isn't mirrored.
+    fn mirror(&self, msg: &ChannelMessage) -> Option<ChannelMessage> {
+        if msg.channel != self.source || self.is_command(msg) {
+            return None;
+        }
+        let mut copy = msg.clone();
+        copy.channel.clone_from(&self.name);
+        Some(copy)
+    }
+
+    /// Whether the pipeline will take `msg` as a command, including ones
+    /// written with the group's own command prefix.
+    fn is_command(&self, msg: &ChannelMessage) -> bool {
+        if msg.content.trim_start().starts_with('/') {
+            return true;
+        }
+        let source = self.control.manager().and_then(|m| m.get(&self.source));
+        match (self.control.groups(), source) {
+            (Some(groups), Some(source)) => groups
+                .for_message(source.as_ref(), msg)
+                .normalize_command(&msg.content)
+                .is_some(),
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl Channel for ShadowChannel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Shape replies like the source's, so the comparison is like for
+        // like; edits can't be captured, so drafts stay off
+        let Some(source) = self.control.manager().and_then(|m| m.get(&self.source)) else {
+            return ChannelCapabilities::of(self);
+        };
+        ChannelCapabilities {
+            supports_edit: false,
+            ..source.capabilities()
+        }
+    }
+
+    async fn send(&self, _message: &str, recipient: &str) -> Result<()> {
+        // The reply itself reaches the log through the outbound tap
+        tracing::debug!("Captured shadow reply on {} to {recipient}", self.name);
+        Ok(())
+    }
+
+    async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+        let mut inbound = self.control.subscribe_inbound();
+        loop {
+            match inbound.recv().await {
+                Ok(msg) => {
+                    if let Some(copy) = self.mirror(&msg) {
+                        if tx.send(copy).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Shadow {} missed {skipped} messages", self.name);
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Replies of a source channel and one of its shadows to the same message.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowComparison {
+    pub shadow: String,
+    pub source: String,
+    pub message_id: String,
+    /// Replies delivered on the source channel
+    pub primary: Vec<String>,
+    /// Replies captured on the shadow
+    pub candidate: Vec<String>,
+    pub at: String,
+}
+
+/// Recent comparisons of every shadow, newest last.
+pub struct ShadowLog {
+    /// Source channel by shadow name
+    sources: HashMap<String, String>,
+    entries: Mutex<VecDeque<ShadowComparison>>,
+}
+
+impl ShadowLog {
+    pub fn new(shadows: &[ShadowChannelConfig]) -> Self {
+        Self {
+            sources: shadows
+                .iter()
+                .map(|shadow| (shadow.name.clone(), shadow.source.clone()))
+                .collect(),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Whether `channel` is one of the shadows.
+    pub fn is_shadow(&self, channel: &str) -> bool {
+        self.sources.contains_key(channel)
+    }
+
+    /// File a reply delivered on a source or captured on a shadow.
+    pub fn record(&self, reply: &OutboundRecord) {
+        let Some(message_id) = reply.in_reply_to.as_deref() else {
+            return;
+        };
+        let mut entries = self.entries.lock();
+        if let Some(source) = self.sources.get(&reply.channel) {
+            let entry = entry(&mut entries, &reply.channel, source, message_id);
+            entry.candidate.push(reply.content.clone());
+            return;
+        }
+        for (shadow, source) in &self.sources {
+            if *source == reply.channel {
+                let entry = entry(&mut entries, shadow, source, message_id);
+                entry.primary.push(reply.content.clone());
+            }
+        }
+    }
+
+    /// Up to `limit` of the latest comparisons, newest first, optionally of
+    /// one shadow only.
+    pub fn recent(&self, shadow: Option<&str>, limit: usize) -> Vec<ShadowComparison> {
+        self.entries
+            .lock()
+            .iter()
+            .rev()
+            .filter(|entry| shadow.is_none_or(|name| entry.shadow == name))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+fn entry<'a>(
+    entries: &'a mut VecDeque<ShadowComparison>,
+    shadow: &str,
+    source: &str,
+    message_id: &str,
+) -> &'a mut ShadowComparison {
+    let index = entries
+        .iter()
+        .position(|entry| entry.shadow == shadow && entry.message_id == message_id);
+    let index = index.unwrap_or_else(|| {
+        if entries.len() == SHADOW_LOG_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(ShadowComparison {
+            shadow: shadow.to_string(),
+            source: source.to_string(),
+            message_id: message_id.to_string(),
+            primary: Vec::new(),
+            candidate: Vec::new(),
+            at: chrono::Utc::now().to_rfc3339(),
+        });
+        entries.len() - 1
+    });
+    &mut entries[index]
+}
+
+/// File replies into `log` until a reload replaces it.
+async fn run_recorder(log: Weak<ShadowLog>, mut replies: broadcast::Receiver<OutboundRecord>) {
+    loop {
+        match replies.recv().await {
+            Ok(reply) => match log.upgrade() {
+                Some(log) => log.record(&reply),
+                None => return,
+            },
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Shadow log missed {skipped} replies");
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// One of the agent's tools as a shadow turn sees it: read-only tools run,
+/// the rest refuse.
+struct ShadowTool {
+    registry: Arc<Vec<Box<dyn Tool>>>,
+    index: usize,
+}
+
+impl ShadowTool {
+    fn inner(&self) -> &dyn Tool {
+        self.registry[self.index].as_ref()
+    }
+}
+
+#[async_trait]
+impl Tool for ShadowTool {
+    fn name(&self) -> &str {
+        self.inner().name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner().description()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.inner().parameters_schema()
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner().is_read_only()
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult> {
+        if self.inner().is_read_only() {
+            return self.inner().execute(args).await;
+        }
+        Ok(ToolResult {
+            success: false,
+            output: String::new(),
+            error: Some(format!(
+                "{} is disabled on shadow channels because it has side effects",
+                self.name()
+            )),
+        })
+    }
+}
+
+/// `registry` for a shadow turn: the same tools, with every one that isn't
+/// read-only disabled.
+pub fn shadow_tools(registry: &Arc<Vec<Box<dyn Tool>>>) -> Vec<Box<dyn Tool>> {
+    (0..registry.len())
+        .map(|index| {
+            Box::new(ShadowTool {
+                registry: Arc::clone(registry),
+                index,
+            }) as Box<dyn Tool>
+        })
+        .collect()
+}
+
+/// Add the configured shadows to `channels` and start filing their replies.
+pub fn attach(
+    mut channels: Vec<Arc<dyn Channel>>,
+    shadows: &[ShadowChannelConfig],
+    control: &Arc<ChannelControl>,
+) -> Result<Vec<Arc<dyn Channel>>> {
+    if shadows.is_empty() {
+        control.attach_shadows(None);
+        return Ok(channels);
+    }
+    let configured: HashSet<&str> = channels.iter().map(|c| c.name()).collect();
+    let mut names = HashSet::new();
+    for shadow in shadows {
+        if !configured.contains(shadow.source.as_str()) {
+            bail!(
+                "Shadow {} mirrors {}, which is not a configured channel",
+                shadow.name,
+                shadow.source
+            );
+        }
+        if configured.contains(shadow.name.as_str()) || !names.insert(shadow.name.as_str()) {
+            bail!("Shadow name {} is already taken", shadow.name);
+        }
+    }
+
+    let log = Arc::new(ShadowLog::new(shadows));
+    tokio::spawn(run_recorder(
+        Arc::downgrade(&log),
+        control.subscribe_outbound(),
+    ));
+    control.attach_shadows(Some(log));
+    channels.extend(shadows.iter().map(|shadow| {
+        Arc::new(ShadowChannel::new(
+            &shadow.name,
+            &shadow.source,
+            Arc::clone(control),
+        )) as Arc<dyn Channel>
+    }));
+    Ok(channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shadow_config() -> Vec<ShadowChannelConfig> {
+        vec![ShadowChannelConfig {
+            name: "telegram-canary".into(),
+            source: "telegram".into(),
+            model: Some("candidate-model".into()),
+            persona: None,
+        }]
+    }
+
+    fn message(channel: &str, content: &str) -> ChannelMessage {
+        ChannelMessage {
+            id: "m1".into(),
+            ..ChannelMessage::test(channel, "alice", content)
+        }
+    }
+
+    fn reply(channel: &str, content: &str) -> OutboundRecord {
+        OutboundRecord {
+            channel: channel.into(),
+            recipient: "alice".into(),
+            content: content.into(),
+            in_reply_to: Some("m1".into()),
+            at: String::new(),
+        }
+    }
+
+    #[test]
+    fn mirrors_source_messages_but_not_commands() {
+        let shadow = ShadowChannel::new(
+            "telegram-canary",
+            "telegram",
+            Arc::new(ChannelControl::new()),
+        );
+        let copy = shadow.mirror(&message("telegram", "hello")).unwrap();
+        assert_eq!(copy.channel, "telegram-canary");
+        assert_eq!(copy.id, "m1");
+        assert!(shadow.mirror(&message("telegram", "/forget")).is_none());
+        assert!(shadow.mirror(&message("discord", "hello")).is_none());
+    }
+
+    #[test]
+    fn does_not_mirror_commands_with_a_group_prefix() {
+        let control = Arc::new(ChannelControl::new());
+        let source: Arc<dyn Channel> = Arc::new(crate::channels::DiscordChannel::new(
+            "fake".into(),
+            None,
+            vec![],
+            false,
+            false,
+        ));
+        control.attach(crate::channels::manager::ChannelManager::from_channels(&[
+            source,
+        ]));
+        let kv = Arc::new(crate::kv::KvStore::in_memory().unwrap());
+        let groups = crate::groups::GroupStore::new(
+            kv.namespace("groups"),
+            crate::config::GroupsConfig::default(),
+        );
+        groups.set("discord", "g1", "prefix", "!").unwrap();
+        control.attach_groups(Some(Arc::new(groups)));
+        let shadow = ShadowChannel::new("discord-canary", "discord", control);
+
+        let in_group = |content: &str| ChannelMessage {
+            reply_target: "g1".into(),
+            ..message("discord", content)
+        };
+        assert!(shadow.mirror(&in_group("!reset")).is_none());
+        assert!(shadow.mirror(&in_group("/reset")).is_none());
+        assert!(shadow.mirror(&in_group("! great")).is_some());
+        assert!(shadow.mirror(&message("discord", "!reset")).is_some());
+    }
+
+    #[test]
+    fn reports_source_capabilities_without_editing() {
+        let control = Arc::new(ChannelControl::new());
+        let source: Arc<dyn Channel> = Arc::new(crate::channels::DiscordChannel::new(
+            "fake".into(),
+            None,
+            vec![],
+            false,
+            false,
+        ));
+        control.attach(crate::channels::manager::ChannelManager::from_channels(&[
+            Arc::clone(&source),
+        ]));
+        let shadow = ShadowChannel::new("discord-canary", "discord", control);
+
+        let caps = shadow.capabilities();
+        assert!(caps.supports_markdown && caps.supports_files);
+        assert!(!caps.supports_edit);
+        assert_eq!(caps.max_message_len, source.max_message_length());
+    }
+
+    struct StubTool {
+        name: &'static str,
+        read_only: bool,
+    }
+
+    #[async_trait]
+    impl Tool for StubTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "answers \"ran\""
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({ "type": "object" })
+        }
+
+        fn is_read_only(&self) -> bool {
+            self.read_only
+        }
+
+        async fn execute(&self, _args: serde_json::Value) -> Result<ToolResult> {
+            Ok(ToolResult {
+                success: true,
+                output: "ran".into(),
+                error: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn shadow_turns_only_run_read_only_tools() {
+        let tool = |name, read_only| Box::new(StubTool { name, read_only }) as Box<dyn Tool>;
+        let registry = Arc::new(vec![tool("memory_recall", true), tool("shell", false)]);
+        let tools = shadow_tools(&registry);
+        assert_eq!(tools[1].name(), "shell");
+
+        let read = tools[0].execute(serde_json::json!({})).await.unwrap();
+        assert!(read.success);
+        let write = tools[1].execute(serde_json::json!({})).await.unwrap();
+        assert!(!write.success);
+        assert!(write.error.unwrap().contains("disabled on shadow channels"));
+    }
+
+    #[test]
+    fn pairs_source_and_shadow_replies_by_message() {
+        let log = ShadowLog::new(&shadow_config());
+        log.record(&reply("telegram-canary", "candidate answer"));
+        log.record(&reply("telegram", "primary answer"));
+        log.record(&reply("discord", "unrelated"));
+
+        let recent = log.recent(None, 10);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].source, "telegram");
+        assert_eq!(recent[0].primary, ["primary answer"]);
+        assert_eq!(recent[0].candidate, ["candidate answer"]);
+        assert!(log.recent(Some("other"), 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_shadows_of_unknown_channels() {
+        let control = Arc::new(ChannelControl::new());
+        let err = attach(Vec::new(), &shadow_config(), &control)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("not a configured channel"));
+    }
+}
@@ -1,14 +1,19 @@
-use crate::channels::traits::{Channel, ChannelMessage};
+use crate::channels::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde::Deserialize;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
 const GROUP_TARGET_PREFIX: &str = "group:";
 
+/// signal-cli marks where a mention sits in the text with this character.
+const MENTION_PLACEHOLDER: char = '\u{FFFC}';
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum RecipientTarget {
     Direct(String),
@@ -19,10 +24,14 @@ enum RecipientTarget {
 ///
 /// Connects to a running `signal-cli daemon --http <host:port>`.
 /// Listens via SSE at `/api/v1/events` and sends via JSON-RPC at
-/// `/api/v1/rpc`.
+/// `/api/v1/rpc`. With [`SignalChannel::with_rpc_socket`] it talks to
+/// `signal-cli daemon --tcp <host:port>` instead: a `subscribeReceive`
+/// subscription delivers messages as `receive` notifications and each
+/// request gets its own connection.
 #[derive(Clone)]
 pub struct SignalChannel {
     http_url: String,
+    rpc_socket: Option<String>,
     account: String,
     group_id: Option<String>,
     allowed_from: Vec<String>,
@@ -45,6 +54,10 @@ struct Envelope {
     source: Option<String>,
     #[serde(rename = "sourceNumber", default)]
     source_number: Option<String>,
+    #[serde(rename = "sourceUuid", default)]
+    source_uuid: Option<String>,
+    #[serde(rename = "sourceName", default)]
+    source_name: Option<String>,
     #[serde(rename = "dataMessage", default)]
     data_message: Option<DataMessage>,
     #[serde(rename = "storyMessage", default)]
@@ -63,6 +76,22 @@ struct DataMessage {
     group_info: Option<GroupInfo>,
     #[serde(default)]
     attachments: Option<Vec<serde_json::Value>>,
+    #[serde(default)]
+    mentions: Option<Vec<SignalMention>>,
+}
+
+/// A mention in a data message; `start` and `length` count UTF-16 units
+/// and cover its placeholder in the text.
+#[derive(Debug, Deserialize)]
+struct SignalMention {
+    #[serde(default)]
+    number: Option<String>,
+    #[serde(default)]
+    uuid: Option<String>,
+    #[serde(default)]
+    start: usize,
+    #[serde(default)]
+    length: usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,6 +116,7 @@ impl SignalChannel {
             .expect("Signal HTTP client should build");
         Self {
             http_url,
+            rpc_socket: None,
             account,
             group_id,
             allowed_from,
@@ -96,6 +126,13 @@ impl SignalChannel {
         }
     }
 
+    /// Use the JSON-RPC socket of `signal-cli daemon --tcp` (e.g.
+    /// "127.0.0.1:7583") instead of the HTTP daemon.
+    pub fn with_rpc_socket(mut self, addr: Option<String>) -> Self {
+        self.rpc_socket = addr.filter(|addr| !addr.is_empty());
+        self
+    }
+
     /// Effective sender: prefer `sourceNumber` (E.164), fall back to `source`.
     fn sender(envelope: &Envelope) -> Option<String> {
         envelope
@@ -167,9 +204,7 @@ impl SignalChannel {
         method: &str,
         params: serde_json::Value,
     ) -> anyhow::Result<Option<serde_json::Value>> {
-        let url = format!("{}/api/v1/rpc", self.http_url);
         let id = Uuid::new_v4().to_string();
-
         let body = serde_json::json!({
             "jsonrpc": "2.0",
             "method": method,
@@ -177,6 +212,15 @@ impl SignalChannel {
             "id": id,
         });
 
+        if let Some(addr) = &self.rpc_socket {
+            let response =
+                tokio::time::timeout(Duration::from_secs(30), Self::socket_request(addr, &body))
+                    .await
+                    .map_err(|_| anyhow::anyhow!("Signal RPC {method} timed out"))??;
+            return Self::rpc_result(&response);
+        }
+
+        let url = format!("{}/api/v1/rpc", self.http_url);
         let resp = self
             .client
             .post(&url)
@@ -196,7 +240,11 @@ impl SignalChannel {
             return Ok(None);
         }
 
-        let parsed: serde_json::Value = serde_json::from_str(&text)?;
+        Self::rpc_result(&serde_json::from_str(&text)?)
+    }
+
+    /// The `result` of a JSON-RPC response, or its `error`.
+    fn rpc_result(parsed: &serde_json::Value) -> anyhow::Result<Option<serde_json::Value>> {
         if let Some(err) = parsed.get("error") {
             let code = err.get("code").and_then(|c| c.as_i64()).unwrap_or(-1);
             let msg = err
@@ -209,6 +257,142 @@ impl SignalChannel {
         Ok(parsed.get("result").cloned())
     }
 
+    /// Send `request` over a fresh socket connection and wait for the
+    /// response with its id, skipping notifications in between.
+    async fn socket_request(
+        addr: &str,
+        request: &serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        let stream = TcpStream::connect(addr).await?;
+        let (read, mut write) = stream.into_split();
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        write.write_all(line.as_bytes()).await?;
+
+        let mut lines = BufReader::new(read).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&line) else {
+                continue;
+            };
+            if parsed.get("id") == request.get("id") {
+                return Ok(parsed);
+            }
+        }
+        anyhow::bail!("signal-cli closed the connection before responding")
+    }
+
+    /// The envelope of a `receive` notification. It is in `params` when
+    /// the daemon pushes messages on its own and in `params.result` for a
+    /// `subscribeReceive` subscription.
+    fn notification_envelope(notification: &serde_json::Value) -> Option<Envelope> {
+        if notification.get("method")?.as_str()? != "receive" {
+            return None;
+        }
+        let params = notification.get("params")?;
+        let payload = params.get("result").unwrap_or(params);
+        serde_json::from_value::<SseEnvelope>(payload.clone())
+            .ok()?
+            .envelope
+    }
+
+    /// Receive over the JSON-RPC socket until the connection drops.
+    async fn listen_socket_once(
+        &self,
+        addr: &str,
+        tx: &mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<bool> {
+        let stream = TcpStream::connect(addr).await?;
+        let (read, mut write) = stream.into_split();
+        let subscribe_id = Uuid::new_v4().to_string();
+        let mut subscribe = serde_json::to_string(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "subscribeReceive",
+            "params": { "account": self.account },
+            "id": subscribe_id,
+        }))?;
+        subscribe.push('\n');
+        write.write_all(subscribe.as_bytes()).await?;
+
+        let mut lines = BufReader::new(read).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&line) else {
+                tracing::debug!("Signal RPC parse skip: {line}");
+                continue;
+            };
+            if parsed.get("id").and_then(|id| id.as_str()) == Some(subscribe_id.as_str()) {
+                // A daemon that already pushes every message refuses the
+                // subscription; its notifications arrive all the same.
+                if let Err(e) = Self::rpc_result(&parsed) {
+                    tracing::debug!("Signal subscribeReceive: {e}");
+                }
+                continue;
+            }
+            if let Some(envelope) = Self::notification_envelope(&parsed) {
+                if let Some(msg) = self.process_envelope(&envelope) {
+                    if tx.send(msg).await.is_err() {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    async fn listen_socket(
+        &self,
+        addr: &str,
+        tx: mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<()> {
+        tracing::info!("Signal channel listening via JSON-RPC on {addr}...");
+        let mut retry_delay_secs = 2u64;
+        loop {
+            match self.listen_socket_once(addr, &tx).await {
+                Ok(false) => return Ok(()),
+                Ok(true) => {
+                    tracing::debug!("Signal RPC connection closed, reconnecting...");
+                    retry_delay_secs = 2;
+                }
+                Err(e) => {
+                    tracing::warn!("Signal RPC connection error: {e}, retrying...");
+                    retry_delay_secs = (retry_delay_secs * 2).min(60);
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(retry_delay_secs)).await;
+        }
+    }
+
+    /// Mentioned users (uuid, else number), whether this account is one of
+    /// them, and `text` with this account's mentions removed.
+    fn mentions(&self, data_msg: &DataMessage, text: &str) -> (Vec<String>, bool, String) {
+        let mentions = data_msg.mentions.as_deref().unwrap_or_default();
+        let mut mentioned: Vec<String> = Vec::new();
+        let mut own: Vec<(usize, usize)> = Vec::new();
+        for mention in mentions {
+            if mention.number.as_deref() == Some(self.account.as_str()) {
+                own.push((mention.start, mention.start + mention.length));
+            }
+            if let Some(id) = mention.uuid.as_ref().or(mention.number.as_ref()) {
+                if !mentioned.contains(id) {
+                    mentioned.push(id.clone());
+                }
+            }
+        }
+        if own.is_empty() {
+            return (mentioned, false, text.to_string());
+        }
+
+        let mut stripped = String::with_capacity(text.len());
+        let mut pos = 0;
+        for c in text.chars() {
+            let inside = own.iter().any(|&(start, end)| pos >= start && pos < end);
+            if !(inside && c == MENTION_PLACEHOLDER) {
+                stripped.push(c);
+            }
+            pos += c.len_utf16();
+        }
+        (mentioned, true, stripped.trim().to_string())
+    }
+
     /// Process a single SSE envelope, returning a ChannelMessage if valid.
     fn process_envelope(&self, envelope: &Envelope) -> Option<ChannelMessage> {
         // Skip story messages when configured
@@ -238,6 +422,10 @@ impl SignalChannel {
         }
 
         let target = self.reply_target(data_msg, &sender);
+        let (mentions, is_mention, content) = self.mentions(data_msg, text);
+        if content.is_empty() {
+            return None;
+        }
 
         let timestamp = data_msg
             .timestamp
@@ -253,12 +441,19 @@ impl SignalChannel {
             });
 
         Some(ChannelMessage {
-            id: format!("sig_{timestamp}"),
+            id: format!("sig_{sender}_{timestamp}"),
             sender: sender.clone(),
+            conversation_id: Some(target.clone()),
             reply_target: target,
-            content: text.to_string(),
+            content,
             channel: "signal".to_string(),
             timestamp: timestamp / 1000, // millis → secs
+            received_at: unix_now(),
+            attachments: vec![],
+            sender_id: envelope.source_uuid.clone(),
+            sender_name: envelope.source_name.clone(),
+            mentions,
+            is_mention,
         })
     }
 }
@@ -269,6 +464,16 @@ impl Channel for SignalChannel {
         "signal"
     }
 
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: true,
+            supports_typing: true,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
         let params = match Self::parse_recipient_target(recipient) {
             RecipientTarget::Direct(number) => serde_json::json!({
@@ -288,6 +493,10 @@ impl Channel for SignalChannel {
     }
 
     async fn listen(&self, tx: mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        if let Some(addr) = &self.rpc_socket {
+            return self.listen_socket(addr, tx).await;
+        }
+
         let mut url = reqwest::Url::parse(&format!("{}/api/v1/events", self.http_url))?;
         url.query_pairs_mut().append_pair("account", &self.account);
 
@@ -406,6 +615,12 @@ impl Channel for SignalChannel {
     }
 
     async fn health_check(&self) -> bool {
+        if self.rpc_socket.is_some() {
+            return self
+                .rpc_request("version", serde_json::json!({}))
+                .await
+                .is_ok();
+        }
         let url = format!("{}/api/v1/check", self.http_url);
         let Ok(resp) = self
             .client
@@ -471,11 +686,14 @@ mod tests {
         Envelope {
             source: source_number.map(String::from),
             source_number: source_number.map(String::from),
+            source_uuid: None,
+            source_name: None,
             data_message: message.map(|m| DataMessage {
                 message: Some(m.to_string()),
                 timestamp: Some(1_700_000_000_000),
                 group_info: None,
                 attachments: None,
+                mentions: None,
             }),
             story_message: None,
             timestamp: Some(1_700_000_000_000),
@@ -551,6 +769,7 @@ mod tests {
             timestamp: Some(1000),
             group_info: None,
             attachments: None,
+            mentions: None,
         };
         assert!(ch.matches_group(&dm));
 
@@ -561,6 +780,7 @@ mod tests {
                 group_id: Some("group123".to_string()),
             }),
             attachments: None,
+            mentions: None,
         };
         assert!(ch.matches_group(&group));
     }
@@ -575,6 +795,7 @@ mod tests {
                 group_id: Some("group123".to_string()),
             }),
             attachments: None,
+            mentions: None,
         };
         assert!(ch.matches_group(&matching));
 
@@ -585,6 +806,7 @@ mod tests {
                 group_id: Some("other_group".to_string()),
             }),
             attachments: None,
+            mentions: None,
         };
         assert!(!ch.matches_group(&non_matching));
     }
@@ -597,6 +819,7 @@ mod tests {
             timestamp: Some(1000),
             group_info: None,
             attachments: None,
+            mentions: None,
         };
         assert!(ch.matches_group(&dm));
 
@@ -607,6 +830,7 @@ mod tests {
                 group_id: Some("group123".to_string()),
             }),
             attachments: None,
+            mentions: None,
         };
         assert!(!ch.matches_group(&group));
     }
@@ -619,6 +843,7 @@ mod tests {
             timestamp: Some(1000),
             group_info: None,
             attachments: None,
+            mentions: None,
         };
         assert_eq!(ch.reply_target(&dm, "+1111111111"), "+1111111111");
     }
@@ -633,6 +858,7 @@ mod tests {
                 group_id: Some("group123".to_string()),
             }),
             attachments: None,
+            mentions: None,
         };
         assert_eq!(ch.reply_target(&group, "+1111111111"), "group:group123");
     }
@@ -666,6 +892,8 @@ mod tests {
         let env = Envelope {
             source: Some("uuid-123".to_string()),
             source_number: Some("+1111111111".to_string()),
+            source_uuid: None,
+            source_name: None,
             data_message: None,
             story_message: None,
             timestamp: Some(1000),
@@ -678,6 +906,8 @@ mod tests {
         let env = Envelope {
             source: Some("uuid-123".to_string()),
             source_number: None,
+            source_uuid: None,
+            source_name: None,
             data_message: None,
             story_message: None,
             timestamp: Some(1000),
@@ -690,6 +920,8 @@ mod tests {
         let env = Envelope {
             source: None,
             source_number: None,
+            source_uuid: None,
+            source_name: None,
             data_message: None,
             story_message: None,
             timestamp: None,
@@ -742,11 +974,14 @@ mod tests {
         let env = Envelope {
             source: Some("+1111111111".to_string()),
             source_number: Some("+1111111111".to_string()),
+            source_uuid: None,
+            source_name: None,
             data_message: Some(DataMessage {
                 message: None,
                 timestamp: Some(1_700_000_000_000),
                 group_info: None,
                 attachments: Some(vec![serde_json::json!({"contentType": "image/png"})]),
+                mentions: None,
             }),
             story_message: None,
             timestamp: Some(1_700_000_000_000),
@@ -806,4 +1041,79 @@ mod tests {
         assert!(env.story_message.is_none());
         assert!(env.timestamp.is_none());
     }
+
+    #[test]
+    fn process_envelope_maps_group_and_author() {
+        let ch = make_channel_with_group("abc123");
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "receive",
+            "params": {
+                "subscription": 0,
+                "result": {
+                    "envelope": {
+                        "sourceNumber": "+2222222222",
+                        "sourceUuid": "9f8e-uuid",
+                        "sourceName": "Ana",
+                        "timestamp": 1_700_000_000_000_u64,
+                        "dataMessage": {
+                            "message": "\u{FFFC} what's the plan?",
+                            "groupInfo": { "groupId": "abc123" },
+                            "mentions": [
+                                { "number": "+1234567890", "uuid": "bot-uuid", "start": 0, "length": 1 }
+                            ]
+                        }
+                    }
+                }
+            }
+        });
+        let env = SignalChannel::notification_envelope(&notification).unwrap();
+        let msg = ch.process_envelope(&env).unwrap();
+        assert_eq!(msg.reply_target, "group:abc123");
+        assert_eq!(msg.conversation_id.as_deref(), Some("group:abc123"));
+        assert_eq!(msg.sender_id.as_deref(), Some("9f8e-uuid"));
+        assert_eq!(msg.sender_name.as_deref(), Some("Ana"));
+        assert_eq!(msg.id, "sig_+2222222222_1700000000000");
+        assert!(msg.is_mention);
+        assert_eq!(msg.mentions, vec!["bot-uuid".to_string()]);
+        assert_eq!(msg.content, "what's the plan?");
+
+        // Without a subscription the envelope sits directly in params.
+        let pushed = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "receive",
+            "params": { "envelope": { "sourceNumber": "+1111111111" } }
+        });
+        assert!(SignalChannel::notification_envelope(&pushed).is_some());
+        let response = serde_json::json!({ "jsonrpc": "2.0", "result": 0, "id": "1" });
+        assert!(SignalChannel::notification_envelope(&response).is_none());
+    }
+
+    #[tokio::test]
+    async fn rpc_socket_request_skips_notifications() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let line = BufReader::new(read)
+                .lines()
+                .next_line()
+                .await
+                .unwrap()
+                .unwrap();
+            let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(request["method"], "send");
+            assert_eq!(request["params"]["groupId"], "abc123");
+            let reply = format!(
+                "{}\n{}\n",
+                serde_json::json!({"jsonrpc": "2.0", "method": "receive", "params": {}}),
+                serde_json::json!({"jsonrpc": "2.0", "result": {"timestamp": 1}, "id": request["id"]}),
+            );
+            write.write_all(reply.as_bytes()).await.unwrap();
+        });
+
+        let ch = make_channel().with_rpc_socket(Some(addr));
+        ch.send("hi", "group:abc123").await.unwrap();
+    }
 }
@@ -1,21 +1,79 @@
-use super::traits::{Channel, ChannelMessage};
+use super::http::token::TokenCache;
+use super::mentions;
+use super::traits::{unix_now, Channel, ChannelCapabilities, ChannelMessage};
+use crate::oauth::OAuthTokenSource;
+use crate::security::ManagedSecret;
 use async_trait::async_trait;
+use std::sync::Arc;
 
-/// Slack channel — polls conversations.history via Web API
+/// Slack channel — polls conversations.history via Web API, or receives
+/// events API callbacks on the gateway
 pub struct SlackChannel {
-    bot_token: String,
+    /// Instance name; "slack" unless configured as a named account.
+    name: String,
+    bot_token: Arc<ManagedSecret>,
+    /// Tokens of an OAuth sign-in, used instead of `bot_token` when set
+    oauth: Option<Arc<TokenCache<OAuthTokenSource>>>,
     channel_id: Option<String>,
     allowed_users: Vec<String>,
     client: reqwest::Client,
+    /// Messages arrive on the gateway's `POST /slack/events` instead of
+    /// being polled.
+    events_api: bool,
 }
 
 impl SlackChannel {
     pub fn new(bot_token: String, channel_id: Option<String>, allowed_users: Vec<String>) -> Self {
+        Self::with_secret(
+            Arc::new(ManagedSecret::fixed(bot_token)),
+            channel_id,
+            allowed_users,
+        )
+    }
+
+    /// Create a channel whose bot token is read from a secret provider, so a
+    /// rotated token applies without a restart.
+    pub fn with_secret(
+        bot_token: Arc<ManagedSecret>,
+        channel_id: Option<String>,
+        allowed_users: Vec<String>,
+    ) -> Self {
         Self {
+            name: "slack".to_string(),
             bot_token,
+            oauth: None,
             channel_id,
             allowed_users,
-            client: reqwest::Client::new(),
+            client: super::net::http_client_for("slack"),
+            events_api: false,
+        }
+    }
+
+    /// Run as a named instance (e.g. "slack-eng").
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Authenticate with the access token of an OAuth sign-in (refreshed
+    /// as it expires) instead of the configured bot token.
+    pub fn with_oauth(mut self, tokens: Option<Arc<TokenCache<OAuthTokenSource>>>) -> Self {
+        self.oauth = tokens;
+        self
+    }
+
+    /// Receive messages through the events API (see
+    /// [`Self::parse_event_callback`]); the listener then only keeps the
+    /// channel alive.
+    pub fn with_events_api(mut self, events_api: bool) -> Self {
+        self.events_api = events_api;
+        self
+    }
+
+    async fn token(&self) -> anyhow::Result<String> {
+        match &self.oauth {
+            Some(tokens) => tokens.get(&self.name).await,
+            None => self.bot_token.get().await,
         }
     }
 
@@ -28,10 +86,11 @@ impl SlackChannel {
 
     /// Get the bot's own user ID so we can ignore our own messages
     async fn get_bot_user_id(&self) -> Option<String> {
+        let token = self.token().await.ok()?;
         let resp: serde_json::Value = self
             .client
             .get("https://slack.com/api/auth.test")
-            .bearer_auth(&self.bot_token)
+            .bearer_auth(&token)
             .send()
             .await
             .ok()?
@@ -43,12 +102,83 @@ impl SlackChannel {
             .and_then(|u| u.as_str())
             .map(String::from)
     }
+
+    /// The message of an events API `event_callback` payload, if it is a
+    /// user's message from an allowed user. Bot messages and edits are
+    /// skipped.
+    pub fn parse_event_callback(&self, payload: &serde_json::Value) -> Option<ChannelMessage> {
+        let event = payload.get("event")?;
+        let kind = event.get("type").and_then(|t| t.as_str())?;
+        if !matches!(kind, "message" | "app_mention")
+            || event.get("bot_id").is_some()
+            || event.get("subtype").is_some()
+        {
+            return None;
+        }
+        let field = |name: &str| event.get(name).and_then(|v| v.as_str()).unwrap_or("");
+        let (user, text, ts, channel_id) =
+            (field("user"), field("text"), field("ts"), field("channel"));
+        if user.is_empty() || text.is_empty() || channel_id.is_empty() {
+            return None;
+        }
+        if !self.is_user_allowed(user) {
+            tracing::warn!("Slack: ignoring message from unauthorized user: {user}");
+            return None;
+        }
+        let bot_user_id = payload
+            .pointer("/authorizations/0/user_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        Some(self.channel_message(channel_id, user, ts, text, bot_user_id))
+    }
+
+    fn channel_message(
+        &self,
+        channel_id: &str,
+        user: &str,
+        ts: &str,
+        text: &str,
+        bot_user_id: &str,
+    ) -> ChannelMessage {
+        let parsed = mentions::parse(text, Some(bot_user_id));
+        ChannelMessage {
+            id: format!("slack_{channel_id}_{ts}"),
+            sender: user.to_string(),
+            reply_target: channel_id.to_string(),
+            content: parsed.text,
+            channel: self.name.clone(),
+            timestamp: slack_ts_secs(ts).unwrap_or_else(unix_now),
+            received_at: unix_now(),
+            attachments: vec![],
+            sender_id: Some(user.to_string()),
+            sender_name: None,
+            conversation_id: Some(channel_id.to_string()),
+            mentions: parsed.mentioned,
+            is_mention: parsed.addressed,
+        }
+    }
+}
+
+/// Whole seconds of a Slack message `ts` ("1700000000.000100").
+fn slack_ts_secs(ts: &str) -> Option<u64> {
+    ts.split('.').next()?.parse().ok()
 }
 
 #[async_trait]
 impl Channel for SlackChannel {
     fn name(&self) -> &str {
-        "slack"
+        &self.name
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        // Slack renders its mrkdwn flavour; bots get no typing indicator
+        ChannelCapabilities {
+            supports_markdown: true,
+            supports_reactions: true,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
     }
 
     async fn send(&self, message: &str, channel: &str) -> anyhow::Result<()> {
@@ -60,7 +190,7 @@ impl Channel for SlackChannel {
         let resp = self
             .client
             .post("https://slack.com/api/chat.postMessage")
-            .bearer_auth(&self.bot_token)
+            .bearer_auth(self.token().await?)
             .json(&body)
             .send()
             .await?;
@@ -88,7 +218,22 @@ impl Channel for SlackChannel {
         Ok(())
     }
 
+    fn send_interval(&self) -> std::time::Duration {
+        // chat.postMessage allows about one message per second per channel
+        std::time::Duration::from_secs(1)
+    }
+
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        if self.events_api {
+            // Messages are received via the gateway's /slack/events endpoint
+            tracing::info!(
+                "Slack channel active (events API) on the gateway's /slack/events endpoint"
+            );
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+            }
+        }
+
         let channel_id = self
             .channel_id
             .clone()
@@ -107,10 +252,17 @@ impl Channel for SlackChannel {
                 params.push(("oldest", last_ts.clone()));
             }
 
+            let token = match self.token().await {
+                Ok(token) => token,
+                Err(e) => {
+                    tracing::warn!("Slack token error: {e}");
+                    continue;
+                }
+            };
             let resp = match self
                 .client
                 .get("https://slack.com/api/conversations.history")
-                .bearer_auth(&self.bot_token)
+                .bearer_auth(&token)
                 .query(&params)
                 .send()
                 .await
@@ -158,17 +310,8 @@ impl Channel for SlackChannel {
 
                     last_ts = ts.to_string();
 
-                    let channel_msg = ChannelMessage {
-                        id: format!("slack_{channel_id}_{ts}"),
-                        sender: user.to_string(),
-                        reply_target: channel_id.clone(),
-                        content: text.to_string(),
-                        channel: "slack".to_string(),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                    };
+                    let channel_msg =
+                        self.channel_message(&channel_id, user, ts, text, &bot_user_id);
 
                     if tx.send(channel_msg).await.is_err() {
                         return Ok(());
@@ -179,9 +322,12 @@ impl Channel for SlackChannel {
     }
 
     async fn health_check(&self) -> bool {
+        let Ok(token) = self.token().await else {
+            return false;
+        };
         self.client
             .get("https://slack.com/api/auth.test")
-            .bearer_auth(&self.bot_token)
+            .bearer_auth(&token)
             .send()
             .await
             .map(|r| r.status().is_success())
@@ -199,6 +345,48 @@ mod tests {
         assert_eq!(ch.name(), "slack");
     }
 
+    #[test]
+    fn event_callbacks_become_messages_from_allowed_users() {
+        let ch = SlackChannel::new("xoxb-fake".into(), None, vec!["U1".into()]);
+        let payload = |event: serde_json::Value| {
+            serde_json::json!({
+                "type": "event_callback",
+                "authorizations": [{ "user_id": "UBOT" }],
+                "event": event,
+            })
+        };
+
+        let msg = ch
+            .parse_event_callback(&payload(serde_json::json!({
+                "type": "message",
+                "user": "U1",
+                "text": "<@UBOT> hello",
+                "ts": "1700000000.000100",
+                "channel": "C1",
+            })))
+            .unwrap();
+        assert_eq!(msg.id, "slack_C1_1700000000.000100");
+        assert_eq!(msg.reply_target, "C1");
+        assert_eq!(msg.content, "hello");
+        assert!(msg.is_mention);
+        assert_eq!(msg.timestamp, 1_700_000_000);
+
+        for event in [
+            serde_json::json!({"type": "message", "user": "U2", "text": "hi", "ts": "1", "channel": "C1"}),
+            serde_json::json!({"type": "message", "bot_id": "B1", "user": "U1", "text": "hi", "ts": "1", "channel": "C1"}),
+            serde_json::json!({"type": "message", "subtype": "message_changed", "user": "U1", "text": "hi", "ts": "1", "channel": "C1"}),
+            serde_json::json!({"type": "reaction_added", "user": "U1"}),
+        ] {
+            assert!(ch.parse_event_callback(&payload(event)).is_none());
+        }
+    }
+
+    #[test]
+    fn slack_ts_is_whole_seconds() {
+        assert_eq!(slack_ts_secs("1700000000.000100"), Some(1_700_000_000));
+        assert_eq!(slack_ts_secs(""), None);
+    }
+
     #[test]
     fn slack_channel_with_channel_id() {
         let ch = SlackChannel::new("xoxb-fake".into(), Some("C12345".into()), vec![]);
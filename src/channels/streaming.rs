@@ -0,0 +1,323 @@
+//! Streaming agent replies into a chat (`[channels_config.streaming]`).
+//!
+//! [`ReplyStreamer`] takes the [`ReplyDelta`]s of
+//! [`crate::agent::loop_::run_tool_call_loop_streaming`]. On channels whose
+//! [capabilities](Channel::capabilities) include editing it sends a
+//! placeholder and edits it as text arrives: after about `edit_every_tokens`
+//! new tokens, and at most once per `min_edit_interval_ms`. Text past the
+//! channel's `max_message_len` continues in a new message. Other
+//! channels get the reply a few paragraphs at a time, never splitting a
+//! code block. [`ReplyStreamer::finish`] then delivers the final text.
+
+use super::traits::Channel;
+use crate::agent::loop_::ReplyDelta;
+use crate::config::StreamingConfig;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Text of the placeholder, and the marker after text still being written.
+const CURSOR: &str = " …";
+
+pub(crate) struct ReplyStreamer<'a> {
+    channel: &'a dyn Channel,
+    recipient: &'a str,
+    config: &'a StreamingConfig,
+    /// Edit a draft; `false` sends paragraphs instead
+    editing: bool,
+    /// Longest text one message of the channel holds
+    max_message_len: usize,
+    /// Text of the current model response
+    text: String,
+    /// Bytes of `text` already in finished messages
+    done: usize,
+    /// Message showing `text[done..]`
+    draft: Option<String>,
+    /// Bytes received since the draft was last edited
+    pending: usize,
+    last_edit: Option<Instant>,
+}
+
+impl<'a> ReplyStreamer<'a> {
+    pub fn new(channel: &'a dyn Channel, recipient: &'a str, config: &'a StreamingConfig) -> Self {
+        let capabilities = channel.capabilities();
+        Self {
+            channel,
+            recipient,
+            config,
+            editing: capabilities.supports_edit,
+            max_message_len: capabilities.max_message_len,
+            text: String::new(),
+            done: 0,
+            draft: None,
+            pending: 0,
+            last_edit: None,
+        }
+    }
+
+    /// Stream `deltas` into the chat until their sender is dropped.
+    pub async fn run(&mut self, mut deltas: UnboundedReceiver<ReplyDelta>) {
+        if self.editing {
+            if let Err(e) = self.show(CURSOR.trim()).await {
+                tracing::warn!(
+                    "Failed to send a draft on {}, streaming by paragraph: {e}",
+                    self.channel.name()
+                );
+                self.editing = false;
+            }
+        }
+        while let Some(delta) = deltas.recv().await {
+            match delta {
+                ReplyDelta::Text(text) => {
+                    self.pending += text.len();
+                    self.text.push_str(&text);
+                    if self.editing {
+                        self.update_draft().await;
+                    } else {
+                        self.send_paragraphs().await;
+                    }
+                }
+                ReplyDelta::Restart => {
+                    self.text.clear();
+                    self.done = 0;
+                }
+            }
+        }
+    }
+
+    /// Deliver the final `reply`: the draft becomes its text, or what the
+    /// streamed paragraphs didn't cover is sent.
+    pub async fn finish(self, reply: &str) -> Result<()> {
+        let delivered = self.text[..self.done].trim();
+        let rest = reply
+            .trim_start()
+            .strip_prefix(delivered)
+            .unwrap_or(reply)
+            .trim();
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let Some(draft) = &self.draft else {
+            return self.channel.send(rest, self.recipient).await;
+        };
+        let split = split_point(rest, self.max_message_len);
+        self.channel
+            .edit_message(draft, rest[..split].trim_end(), self.recipient)
+            .await?;
+        let tail = rest[split..].trim();
+        if !tail.is_empty() {
+            self.channel.send(tail, self.recipient).await?;
+        }
+        Ok(())
+    }
+
+    async fn update_draft(&mut self) {
+        if self.pending.div_ceil(4) < self.config.edit_every_tokens {
+            return;
+        }
+        let interval = Duration::from_millis(self.config.min_edit_interval_ms);
+        if self.last_edit.is_some_and(|at| at.elapsed() < interval) {
+            return;
+        }
+        let max_chars = self.max_message_len.saturating_sub(CURSOR.chars().count());
+        // Finish full drafts and continue in a new message
+        loop {
+            let rest = &self.text[self.done..];
+            let split = split_point(rest, max_chars);
+            if split == rest.len() {
+                break;
+            }
+            let full = rest[..split].trim_end().to_string();
+            let consumed = split + rest[split..].len() - rest[split..].trim_start().len();
+            if let Err(e) = self.show(&full).await {
+                tracing::warn!("Failed to update a draft on {}: {e}", self.channel.name());
+            }
+            self.draft = None;
+            self.done += consumed;
+        }
+        let rest = self.text[self.done..].trim();
+        if rest.is_empty() {
+            return;
+        }
+        let shown = format!("{rest}{CURSOR}");
+        if let Err(e) = self.show(&shown).await {
+            tracing::warn!("Failed to update a draft on {}: {e}", self.channel.name());
+        }
+        self.pending = 0;
+        self.last_edit = Some(Instant::now());
+    }
+
+    /// Show `text` in the draft, sending one if there is none yet.
+    async fn show(&mut self, text: &str) -> Result<()> {
+        match &self.draft {
+            Some(draft) => self.channel.edit_message(draft, text, self.recipient).await,
+            None => {
+                self.draft = Some(self.channel.send_editable(text, self.recipient).await?);
+                Ok(())
+            }
+        }
+    }
+
+    async fn send_paragraphs(&mut self) {
+        let Some(end) = paragraphs_end(&self.text[self.done..]) else {
+            return;
+        };
+        let paragraphs = self.text[self.done..self.done + end].trim();
+        if !paragraphs.is_empty() {
+            if let Err(e) = self.channel.send(paragraphs, self.recipient).await {
+                tracing::warn!("Failed to send a paragraph on {}: {e}", self.channel.name());
+            }
+        }
+        self.done += end;
+    }
+}
+
+/// End of the last complete paragraph in `text` that isn't inside a code
+/// block.
+fn paragraphs_end(text: &str) -> Option<usize> {
+    text.match_indices("\n\n")
+        .filter(|(pos, _)| text[..*pos].matches("```").count().is_multiple_of(2))
+        .map(|(pos, separator)| pos + separator.len())
+        .last()
+}
+
+/// Byte length of the longest start of `text` that fits in `max_chars`,
+/// ending at a paragraph, line or word when one is near.
+fn split_point(text: &str, max_chars: usize) -> usize {
+    let Some((limit, _)) = text.char_indices().nth(max_chars) else {
+        return text.len();
+    };
+    let area = &text[..limit];
+    ["\n\n", "\n", " "]
+        .iter()
+        .find_map(|separator| area.rfind(separator).filter(|&pos| pos >= limit / 2))
+        .unwrap_or(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::ChannelMessage;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        editing: bool,
+        /// Sent messages, by id, in their latest text
+        messages: Mutex<Vec<String>>,
+        edits: Mutex<usize>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(&self, message: &str, _recipient: &str) -> anyhow::Result<()> {
+            self.messages.lock().push(message.to_string());
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn supports_editing(&self) -> bool {
+            self.editing
+        }
+
+        fn max_message_length(&self) -> usize {
+            40
+        }
+
+        async fn send_editable(&self, message: &str, _recipient: &str) -> anyhow::Result<String> {
+            let mut messages = self.messages.lock();
+            messages.push(message.to_string());
+            Ok((messages.len() - 1).to_string())
+        }
+
+        async fn edit_message(
+            &self,
+            message_id: &str,
+            message: &str,
+            _recipient: &str,
+        ) -> anyhow::Result<()> {
+            self.messages.lock()[message_id.parse::<usize>()?] = message.to_string();
+            *self.edits.lock() += 1;
+            Ok(())
+        }
+    }
+
+    fn config() -> StreamingConfig {
+        StreamingConfig {
+            enabled: true,
+            edit_every_tokens: 2,
+            min_edit_interval_ms: 0,
+        }
+    }
+
+    async fn stream(channel: &RecordingChannel, deltas: &[ReplyDelta], reply: &str) {
+        let config = config();
+        let mut streamer = ReplyStreamer::new(channel, "chat", &config);
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        for delta in deltas {
+            tx.send(delta.clone()).unwrap();
+        }
+        drop(tx);
+        streamer.run(rx).await;
+        streamer.finish(reply).await.unwrap();
+    }
+
+    fn text(text: &str) -> ReplyDelta {
+        ReplyDelta::Text(text.to_string())
+    }
+
+    #[tokio::test]
+    async fn edits_a_draft_and_continues_long_replies() {
+        let channel = RecordingChannel {
+            editing: true,
+            ..RecordingChannel::default()
+        };
+        let reply = "The first part of the answer.\n\nAnd a second paragraph.";
+        stream(
+            &channel,
+            &[
+                text("Checking"),
+                ReplyDelta::Restart,
+                text("The first part "),
+                text("of the answer.\n\nAnd a second"),
+                text(" paragraph."),
+            ],
+            reply,
+        )
+        .await;
+
+        assert_eq!(
+            *channel.messages.lock(),
+            ["The first part of the answer.", "And a second paragraph."]
+        );
+        assert!(*channel.edits.lock() >= 3);
+    }
+
+    #[tokio::test]
+    async fn sends_paragraphs_without_splitting_code() {
+        let channel = RecordingChannel::default();
+        let reply = "Intro.\n\n```\na\n\nb\n```\n\nDone.\n\n[1] Source";
+        stream(
+            &channel,
+            &[text("Intro.\n\n```\na\n\n"), text("b\n```\n\nDone.")],
+            reply,
+        )
+        .await;
+
+        assert_eq!(
+            *channel.messages.lock(),
+            ["Intro.", "```\na\n\nb\n```", "Done.\n\n[1] Source"]
+        );
+    }
+}
@@ -1,6 +1,13 @@
-use super::traits::{Channel, ChannelMessage};
+use super::frame_tap::{self, Direction};
+use super::interactions::{self, Interaction, Keyboard};
+use super::polls::{self, Poll};
+use super::traits::{
+    unix_now, Channel, ChannelCapabilities, ChannelMessage, MediaAttachment, MediaKind,
+};
+use super::uploads::{self, FileReader};
 use crate::config::Config;
 use crate::security::pairing::PairingGuard;
+use crate::security::ManagedSecret;
 use anyhow::Context;
 use async_trait::async_trait;
 use directories::UserDirs;
@@ -12,6 +19,8 @@ use std::time::Duration;
 
 /// Telegram's maximum message length for text messages
 const TELEGRAM_MAX_MESSAGE_LENGTH: usize = 4096;
+/// Largest file the Bot API accepts in an upload
+const TELEGRAM_MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
 const TELEGRAM_BIND_COMMAND: &str = "/bind";
 
 /// Split a message into chunks that respect Telegram's 4096 character limit.
@@ -185,9 +194,55 @@ fn parse_attachment_markers(message: &str) -> (String, Vec<TelegramAttachment>)
     (cleaned.trim().to_string(), attachments)
 }
 
+/// `reply_markup` showing `keyboard` as an inline keyboard.
+fn inline_keyboard(keyboard: &Keyboard) -> serde_json::Value {
+    let rows: Vec<Vec<serde_json::Value>> = keyboard
+        .rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|button| {
+                    serde_json::json!({
+                        "text": button.label,
+                        "callback_data": button.id,
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    serde_json::json!({ "inline_keyboard": rows })
+}
+
+/// Count a `poll_answer` update, which carries the voter's full choice.
+fn record_poll_answer(channel: &str, answer: &serde_json::Value) {
+    let Some(poll_id) = answer.get("poll_id").and_then(serde_json::Value::as_str) else {
+        return;
+    };
+    let Some(voter) = answer
+        .get("user")
+        .and_then(|user| user.get("id"))
+        .and_then(serde_json::Value::as_i64)
+    else {
+        return;
+    };
+    let options: Vec<usize> = answer
+        .get("option_ids")
+        .and_then(serde_json::Value::as_array)
+        .map(|ids| {
+            ids.iter()
+                .filter_map(serde_json::Value::as_u64)
+                .filter_map(|id| usize::try_from(id).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    polls::set_vote(channel, poll_id, &voter.to_string(), &options);
+}
+
 /// Telegram channel — long-polls the Bot API for updates
 pub struct TelegramChannel {
-    bot_token: String,
+    /// Instance name; "telegram" unless configured as a named account.
+    name: String,
+    bot_token: Arc<ManagedSecret>,
     allowed_users: Arc<RwLock<Vec<String>>>,
     pairing: Option<PairingGuard>,
     client: reqwest::Client,
@@ -195,6 +250,12 @@ pub struct TelegramChannel {
 
 impl TelegramChannel {
     pub fn new(bot_token: String, allowed_users: Vec<String>) -> Self {
+        Self::with_secret(Arc::new(ManagedSecret::fixed(bot_token)), allowed_users)
+    }
+
+    /// Create a channel whose bot token is read from a secret provider, so a
+    /// rotated token applies without a restart.
+    pub fn with_secret(bot_token: Arc<ManagedSecret>, allowed_users: Vec<String>) -> Self {
         let normalized_allowed = Self::normalize_allowed_users(allowed_users);
         let pairing = if normalized_allowed.is_empty() {
             let guard = PairingGuard::new(true, &[]);
@@ -208,13 +269,20 @@ impl TelegramChannel {
         };
 
         Self {
+            name: "telegram".to_string(),
             bot_token,
             allowed_users: Arc::new(RwLock::new(normalized_allowed)),
             pairing,
-            client: reqwest::Client::new(),
+            client: super::net::http_client_for("telegram"),
         }
     }
 
+    /// Run as a named instance (e.g. "telegram-support").
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
     fn normalize_identity(value: &str) -> String {
         value.trim().trim_start_matches('@').to_string()
     }
@@ -301,8 +369,9 @@ impl TelegramChannel {
             .is_some()
     }
 
-    fn api_url(&self, method: &str) -> String {
-        format!("https://api.telegram.org/bot{}/{method}", self.bot_token)
+    async fn api_url(&self, method: &str) -> anyhow::Result<String> {
+        let token = self.bot_token.get().await?;
+        Ok(format!("https://api.telegram.org/bot{token}/{method}"))
     }
 
     fn is_user_allowed(&self, username: &str) -> bool {
@@ -477,10 +546,81 @@ After operator runs it, send your message again."
         }
     }
 
+    /// Voice notes and audio files, referenced by Telegram file id.
+    fn parse_attachments(message: &serde_json::Value) -> Vec<MediaAttachment> {
+        let text_field = |media: &serde_json::Value, name: &str| {
+            media
+                .get(name)
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+        };
+        let mut attachments: Vec<MediaAttachment> =
+            [("voice", MediaKind::Voice), ("audio", MediaKind::Audio)]
+                .into_iter()
+                .filter_map(|(field, kind)| {
+                    let media = message.get(field)?;
+                    Some(MediaAttachment {
+                        kind,
+                        source: text_field(media, "file_id")?,
+                        mime_type: text_field(media, "mime_type"),
+                        file_name: text_field(media, "file_name"),
+                        stored_key: None,
+                    })
+                })
+                .collect();
+
+        // Photos arrive in several sizes, smallest first.
+        if let Some(photo) = message
+            .get("photo")
+            .and_then(serde_json::Value::as_array)
+            .and_then(|sizes| sizes.last())
+        {
+            if let Some(file_id) = text_field(photo, "file_id") {
+                attachments.push(MediaAttachment {
+                    kind: MediaKind::Image,
+                    source: file_id,
+                    mime_type: Some("image/jpeg".into()),
+                    file_name: None,
+                    stored_key: None,
+                });
+            }
+        }
+        if let Some(document) = message.get("document") {
+            if let Some(file_id) = text_field(document, "file_id") {
+                let mime_type = text_field(document, "mime_type");
+                let kind = if mime_type
+                    .as_deref()
+                    .is_some_and(|m| m.starts_with("image/"))
+                {
+                    MediaKind::Image
+                } else {
+                    MediaKind::Document
+                };
+                attachments.push(MediaAttachment {
+                    kind,
+                    source: file_id,
+                    mime_type,
+                    file_name: text_field(document, "file_name"),
+                    stored_key: None,
+                });
+            }
+        }
+        attachments
+    }
+
     fn parse_update_message(&self, update: &serde_json::Value) -> Option<ChannelMessage> {
         let message = update.get("message")?;
 
-        let text = message.get("text").and_then(serde_json::Value::as_str)?;
+        let attachments = Self::parse_attachments(message);
+        let text = match message
+            .get("text")
+            .or_else(|| message.get("caption"))
+            .and_then(serde_json::Value::as_str)
+        {
+            Some(text) => text,
+            None if !attachments.is_empty() => "",
+            None => return None,
+        };
 
         let username = message
             .get("from")
@@ -495,6 +635,12 @@ After operator runs it, send your message again."
             .and_then(serde_json::Value::as_i64)
             .map(|id| id.to_string());
 
+        let sender_name = message
+            .get("from")
+            .and_then(|from| from.get("first_name"))
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+
         let sender_identity = if username == "unknown" {
             user_id.clone().unwrap_or_else(|| "unknown".to_string())
         } else {
@@ -516,24 +662,92 @@ After operator runs it, send your message again."
             .and_then(serde_json::Value::as_i64)
             .map(|id| id.to_string())?;
 
-        let message_id = message
+        // The id is the dedup key, so a message without one falls back to
+        // the update id, which a redelivery keeps too.
+        let id = match message
             .get("message_id")
             .and_then(serde_json::Value::as_i64)
-            .unwrap_or(0);
+        {
+            Some(message_id) => format!("telegram_{chat_id}_{message_id}"),
+            None => {
+                let update_id = update
+                    .get("update_id")
+                    .and_then(serde_json::Value::as_i64)?;
+                format!("telegram_{chat_id}_update_{update_id}")
+            }
+        };
 
         Some(ChannelMessage {
-            id: format!("telegram_{chat_id}_{message_id}"),
+            id,
             sender: sender_identity,
-            reply_target: chat_id,
+            reply_target: chat_id.clone(),
             content: text.to_string(),
-            channel: "telegram".to_string(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            channel: self.name.clone(),
+            timestamp: message
+                .get("date")
+                .and_then(serde_json::Value::as_u64)
+                .unwrap_or_else(unix_now),
+            received_at: unix_now(),
+            attachments,
+            sender_id: user_id,
+            sender_name,
+            conversation_id: Some(chat_id),
+            mentions: vec![],
+            is_mention: false,
+        })
+    }
+
+    /// The button click in a `callback_query` update, if it came from an
+    /// allowed user.
+    fn parse_callback_query(&self, query: &serde_json::Value) -> Option<Interaction> {
+        let callback_id = query.get("data").and_then(serde_json::Value::as_str)?;
+        let from = query.get("from")?;
+        let user_id = from
+            .get("id")
+            .and_then(serde_json::Value::as_i64)
+            .map(|id| id.to_string())?;
+        let username = from.get("username").and_then(serde_json::Value::as_str);
+
+        let mut identities = vec![user_id.as_str()];
+        identities.extend(username);
+        if !self.is_any_user_allowed(identities) {
+            return None;
+        }
+
+        let message = query.get("message");
+        let chat_id = message
+            .and_then(|m| m.get("chat"))
+            .and_then(|chat| chat.get("id"))
+            .and_then(serde_json::Value::as_i64)
+            .map_or_else(|| user_id.clone(), |id| id.to_string());
+
+        Some(Interaction {
+            channel: self.name.clone(),
+            callback_id: callback_id.to_string(),
+            user_id,
+            user_name: username.map(str::to_string),
+            reply_target: chat_id,
+            message_id: message
+                .and_then(|m| m.get("message_id"))
+                .and_then(serde_json::Value::as_i64)
+                .map(|id| id.to_string()),
         })
     }
 
+    /// Stop the client's loading spinner on a clicked button.
+    async fn answer_callback_query(&self, query: &serde_json::Value) {
+        let Some(id) = query.get("id").and_then(serde_json::Value::as_str) else {
+            return;
+        };
+        let Ok(url) = self.api_url("answerCallbackQuery").await else {
+            return;
+        };
+        let body = serde_json::json!({ "callback_query_id": id });
+        if let Err(e) = self.client.post(url).json(&body).send().await {
+            tracing::debug!("Telegram answerCallbackQuery failed: {e}");
+        }
+    }
+
     async fn send_text_chunks(&self, message: &str, chat_id: &str) -> anyhow::Result<()> {
         let chunks = split_message_for_telegram(message);
 
@@ -558,14 +772,14 @@ After operator runs it, send your message again."
 
             let markdown_resp = self
                 .client
-                .post(self.api_url("sendMessage"))
+                .post(self.api_url("sendMessage").await?)
                 .json(&markdown_body)
                 .send()
                 .await?;
 
             if markdown_resp.status().is_success() {
                 if index < chunks.len() - 1 {
-                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    tokio::time::sleep(self.send_interval()).await;
                 }
                 continue;
             }
@@ -583,7 +797,7 @@ After operator runs it, send your message again."
             });
             let plain_resp = self
                 .client
-                .post(self.api_url("sendMessage"))
+                .post(self.api_url("sendMessage").await?)
                 .json(&plain_body)
                 .send()
                 .await?;
@@ -601,7 +815,7 @@ After operator runs it, send your message again."
             }
 
             if index < chunks.len() - 1 {
-                tokio::time::sleep(Duration::from_millis(100)).await;
+                tokio::time::sleep(self.send_interval()).await;
             }
         }
 
@@ -627,7 +841,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url(method))
+            .post(self.api_url(method).await?)
             .json(&body)
             .send()
             .await?;
@@ -695,6 +909,7 @@ After operator runs it, send your message again."
             .unwrap_or("file");
 
         let file_bytes = tokio::fs::read(file_path).await?;
+        let size = u64::try_from(file_bytes.len()).ok();
         let part = Part::bytes(file_bytes).file_name(file_name.to_string());
 
         let mut form = Form::new()
@@ -707,7 +922,8 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendDocument"))
+            .post(self.api_url("sendDocument").await?)
+            .timeout(super::net::transfer_timeout(size))
             .multipart(form)
             .send()
             .await?;
@@ -729,6 +945,7 @@ After operator runs it, send your message again."
         file_name: &str,
         caption: Option<&str>,
     ) -> anyhow::Result<()> {
+        let size = u64::try_from(file_bytes.len()).ok();
         let part = Part::bytes(file_bytes).file_name(file_name.to_string());
 
         let mut form = Form::new()
@@ -741,7 +958,8 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendDocument"))
+            .post(self.api_url("sendDocument").await?)
+            .timeout(super::net::transfer_timeout(size))
             .multipart(form)
             .send()
             .await?;
@@ -780,7 +998,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendPhoto"))
+            .post(self.api_url("sendPhoto").await?)
             .multipart(form)
             .send()
             .await?;
@@ -814,7 +1032,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendPhoto"))
+            .post(self.api_url("sendPhoto").await?)
             .multipart(form)
             .send()
             .await?;
@@ -853,7 +1071,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendVideo"))
+            .post(self.api_url("sendVideo").await?)
             .multipart(form)
             .send()
             .await?;
@@ -892,7 +1110,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendAudio"))
+            .post(self.api_url("sendAudio").await?)
             .multipart(form)
             .send()
             .await?;
@@ -931,7 +1149,35 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendVoice"))
+            .post(self.api_url("sendVoice").await?)
+            .multipart(form)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendVoice failed: {err}");
+        }
+
+        tracing::info!("Telegram voice sent to {chat_id}: {file_name}");
+        Ok(())
+    }
+
+    /// Send a voice message from bytes (OGG/Opus) to a Telegram chat
+    pub async fn send_voice_bytes(
+        &self,
+        chat_id: &str,
+        file_bytes: Vec<u8>,
+        file_name: &str,
+    ) -> anyhow::Result<()> {
+        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
+        let form = Form::new()
+            .text("chat_id", chat_id.to_string())
+            .part("voice", part);
+
+        let resp = self
+            .client
+            .post(self.api_url("sendVoice").await?)
             .multipart(form)
             .send()
             .await?;
@@ -945,6 +1191,34 @@ After operator runs it, send your message again."
         Ok(())
     }
 
+    /// Send an audio file from bytes to a Telegram chat
+    pub async fn send_audio_bytes(
+        &self,
+        chat_id: &str,
+        file_bytes: Vec<u8>,
+        file_name: &str,
+    ) -> anyhow::Result<()> {
+        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
+        let form = Form::new()
+            .text("chat_id", chat_id.to_string())
+            .part("audio", part);
+
+        let resp = self
+            .client
+            .post(self.api_url("sendAudio").await?)
+            .multipart(form)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendAudio failed: {err}");
+        }
+
+        tracing::info!("Telegram audio sent to {chat_id}: {file_name}");
+        Ok(())
+    }
+
     /// Send a file by URL (Telegram will download it)
     pub async fn send_document_by_url(
         &self,
@@ -963,7 +1237,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendDocument"))
+            .post(self.api_url("sendDocument").await?)
             .json(&body)
             .send()
             .await?;
@@ -995,7 +1269,7 @@ After operator runs it, send your message again."
 
         let resp = self
             .client
-            .post(self.api_url("sendPhoto"))
+            .post(self.api_url("sendPhoto").await?)
             .json(&body)
             .send()
             .await?;
@@ -1046,7 +1320,7 @@ After operator runs it, send your message again."
 #[async_trait]
 impl Channel for TelegramChannel {
     fn name(&self) -> &str {
-        "telegram"
+        &self.name
     }
 
     async fn send(&self, message: &str, chat_id: &str) -> anyhow::Result<()> {
@@ -1079,14 +1353,21 @@ impl Channel for TelegramChannel {
         tracing::info!("Telegram channel listening for messages...");
 
         loop {
-            let url = self.api_url("getUpdates");
+            let url = self.api_url("getUpdates").await?;
             let body = serde_json::json!({
                 "offset": offset,
                 "timeout": 30,
-                "allowed_updates": ["message"]
+                "allowed_updates": ["message", "callback_query", "poll_answer"]
             });
 
-            let resp = match self.client.post(&url).json(&body).send().await {
+            let resp = match self
+                .client
+                .post(&url)
+                .timeout(super::net::long_poll_timeout(30))
+                .json(&body)
+                .send()
+                .await
+            {
                 Ok(r) => r,
                 Err(e) => {
                     tracing::warn!("Telegram poll error: {e}");
@@ -1136,11 +1417,27 @@ Ensure only one `zeroclaw` process is using this bot token."
 
             if let Some(results) = data.get("result").and_then(serde_json::Value::as_array) {
                 for update in results {
+                    frame_tap::record_json(&self.name, Direction::Inbound, "getUpdates", update);
                     // Advance offset past this update
                     if let Some(uid) = update.get("update_id").and_then(serde_json::Value::as_i64) {
                         offset = uid + 1;
                     }
 
+                    if let Some(answer) = update.get("poll_answer") {
+                        record_poll_answer(&self.name, answer);
+                        continue;
+                    }
+
+                    if let Some(query) = update.get("callback_query") {
+                        self.answer_callback_query(query).await;
+                        if let Some(interaction) = self.parse_callback_query(query) {
+                            if !interactions::route(self, interaction, &tx).await {
+                                return Ok(());
+                            }
+                        }
+                        continue;
+                    }
+
                     let Some(msg) = self.parse_update_message(update) else {
                         self.handle_unauthorized_message(update).await;
                         continue;
@@ -1152,7 +1449,7 @@ Ensure only one `zeroclaw` process is using this bot token."
                     });
                     let _ = self
                         .client
-                        .post(self.api_url("sendChatAction"))
+                        .post(self.api_url("sendChatAction").await?)
                         .json(&typing_body)
                         .send()
                         .await; // Ignore errors for typing indicator
@@ -1167,13 +1464,12 @@ Ensure only one `zeroclaw` process is using this bot token."
 
     async fn health_check(&self) -> bool {
         let timeout_duration = Duration::from_secs(5);
+        let Ok(url) = self.api_url("getMe").await else {
+            tracing::debug!("Telegram health check failed: bot token unavailable");
+            return false;
+        };
 
-        match tokio::time::timeout(
-            timeout_duration,
-            self.client.get(self.api_url("getMe")).send(),
-        )
-        .await
-        {
+        match tokio::time::timeout(timeout_duration, self.client.get(url).send()).await {
             Ok(Ok(resp)) => resp.status().is_success(),
             Ok(Err(e)) => {
                 tracing::debug!("Telegram health check failed: {e}");
@@ -1185,6 +1481,302 @@ Ensure only one `zeroclaw` process is using this bot token."
             }
         }
     }
+
+    /// Group creators and administrators, from `getChatMember`.
+    async fn is_group_admin(&self, msg: &ChannelMessage) -> anyhow::Result<bool> {
+        let Some(user_id) = msg
+            .sender_id
+            .as_deref()
+            .and_then(|id| id.parse::<i64>().ok())
+        else {
+            return Ok(false);
+        };
+        let body = serde_json::json!({ "chat_id": msg.reply_target, "user_id": user_id });
+        let resp: serde_json::Value = self
+            .client
+            .post(self.api_url("getChatMember").await?)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let status = resp
+            .pointer("/result/status")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default();
+        Ok(matches!(status, "creator" | "administrator"))
+    }
+
+    /// Attachment sources are file ids; resolve them with `getFile` first.
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn send_image(
+        &self,
+        image: Vec<u8>,
+        file_name: &str,
+        caption: Option<&str>,
+        chat_id: &str,
+    ) -> anyhow::Result<()> {
+        self.send_photo_bytes(chat_id, image, file_name, caption)
+            .await
+    }
+
+    fn supports_buttons(&self) -> bool {
+        true
+    }
+
+    async fn send_buttons(
+        &self,
+        text: &str,
+        keyboard: &Keyboard,
+        chat_id: &str,
+    ) -> anyhow::Result<()> {
+        keyboard.validate()?;
+        // Only the last chunk of a long text carries the keyboard
+        let mut chunks = split_message_for_telegram(text);
+        let last = chunks.pop().unwrap_or_default();
+        for chunk in &chunks {
+            self.send_text_chunks(chunk, chat_id).await?;
+        }
+
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": last,
+            "reply_markup": inline_keyboard(keyboard),
+        });
+        let resp = self
+            .client
+            .post(self.api_url("sendMessage").await?)
+            .json(&body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Telegram sendMessage with keyboard failed ({status}): {err}");
+        }
+        Ok(())
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        Some(TELEGRAM_MAX_UPLOAD_BYTES)
+    }
+
+    async fn send_file(
+        &self,
+        chat_id: &str,
+        name: &str,
+        mime: &str,
+        size: Option<u64>,
+        reader: FileReader,
+    ) -> anyhow::Result<()> {
+        let body = uploads::streaming_body(reader);
+        let part = match size {
+            Some(len) => Part::stream_with_length(body, len),
+            None => Part::stream(body),
+        }
+        .file_name(name.to_string())
+        .mime_str(mime)?;
+        let form = Form::new()
+            .text("chat_id", chat_id.to_string())
+            .part("document", part);
+
+        let resp = self
+            .client
+            .post(self.api_url("sendDocument").await?)
+            .timeout(super::net::transfer_timeout(size))
+            .multipart(form)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendDocument failed: {err}");
+        }
+        tracing::info!("Telegram document sent to {chat_id}: {name}");
+        Ok(())
+    }
+
+    fn supports_polls(&self) -> bool {
+        true
+    }
+
+    /// Native polls; they are sent non-anonymous so answers are reported.
+    async fn send_poll(&self, poll: &Poll, chat_id: &str) -> anyhow::Result<String> {
+        poll.validate()?;
+        let options: Vec<serde_json::Value> = poll
+            .options
+            .iter()
+            .map(|option| serde_json::json!({ "text": option }))
+            .collect();
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "question": poll.question,
+            "options": options,
+            "is_anonymous": false,
+            "allows_multiple_answers": poll.allows_multiple,
+        });
+        let resp = self
+            .client
+            .post(self.api_url("sendPoll").await?)
+            .json(&body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Telegram sendPoll failed ({status}): {err}");
+        }
+        let data: serde_json::Value = resp.json().await?;
+        let poll_id = data
+            .pointer("/result/poll/id")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Telegram sendPoll returned no poll id"))?;
+        Ok(polls::track(&self.name, poll_id, chat_id, poll.clone()))
+    }
+
+    fn supports_editing(&self) -> bool {
+        true
+    }
+
+    fn max_message_length(&self) -> usize {
+        TELEGRAM_MAX_MESSAGE_LENGTH
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: true,
+            supports_reactions: true,
+            // Typing is shown by the listener while a message is handled
+            supports_typing: true,
+            supports_media_markers: true,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    fn send_interval(&self) -> Duration {
+        // Telegram allows about one message per second in a chat
+        Duration::from_secs(1)
+    }
+
+    async fn send_editable(&self, message: &str, chat_id: &str) -> anyhow::Result<String> {
+        let body = serde_json::json!({ "chat_id": chat_id, "text": message });
+        let resp = self
+            .client
+            .post(self.api_url("sendMessage").await?)
+            .json(&body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Telegram sendMessage failed ({status}): {err}");
+        }
+        let data: serde_json::Value = resp.json().await?;
+        data.pointer("/result/message_id")
+            .and_then(serde_json::Value::as_i64)
+            .map(|id| id.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Telegram sendMessage returned no message id"))
+    }
+
+    /// Edits as Markdown, falling back to plain text like
+    /// [`Channel::send`] does; partial replies often aren't valid Markdown.
+    async fn edit_message(
+        &self,
+        message_id: &str,
+        message: &str,
+        chat_id: &str,
+    ) -> anyhow::Result<()> {
+        let message_id: i64 = message_id.parse()?;
+        let mut err = String::new();
+        for parse_mode in [Some("Markdown"), None] {
+            let mut body = serde_json::json!({
+                "chat_id": chat_id,
+                "message_id": message_id,
+                "text": message,
+            });
+            if let Some(parse_mode) = parse_mode {
+                body["parse_mode"] = parse_mode.into();
+            }
+            let resp = self
+                .client
+                .post(self.api_url("editMessageText").await?)
+                .json(&body)
+                .send()
+                .await?;
+            if resp.status().is_success() {
+                return Ok(());
+            }
+            let status = resp.status();
+            err = resp.text().await.unwrap_or_default();
+            // Editing to the same text is an error, but nothing to retry
+            if err.contains("message is not modified") {
+                return Ok(());
+            }
+            err = format!("{status}: {err}");
+        }
+        anyhow::bail!("Telegram editMessageText failed ({err})")
+    }
+
+    fn supports_voice_notes(&self) -> bool {
+        true
+    }
+
+    async fn send_voice_note(
+        &self,
+        audio: Vec<u8>,
+        file_name: &str,
+        chat_id: &str,
+    ) -> anyhow::Result<()> {
+        // sendVoice only renders OGG/Opus as a voice note; other formats
+        // go out as a playable audio file.
+        if file_name.ends_with(".ogg") || file_name.ends_with(".opus") {
+            self.send_voice_bytes(chat_id, audio, file_name).await
+        } else {
+            self.send_audio_bytes(chat_id, audio, file_name).await
+        }
+    }
+
+    async fn download_attachment(&self, attachment: &MediaAttachment) -> anyhow::Result<Vec<u8>> {
+        let resp: serde_json::Value = self
+            .client
+            .get(self.api_url("getFile").await?)
+            .query(&[("file_id", attachment.source.as_str())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let file_path = resp
+            .get("result")
+            .and_then(|r| r.get("file_path"))
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Telegram getFile returned no file_path"))?;
+        let bytes = self
+            .client
+            .get(format!(
+                "https://api.telegram.org/file/bot{}/{file_path}",
+                self.bot_token.get().await?
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Private chats have positive ids and are one session per user; group
+    /// and supergroup ids are negative and get a session per member.
+    fn conversation_key(&self, msg: &ChannelMessage) -> String {
+        if msg.reply_target.starts_with('-') {
+            format!("group:{}:{}", msg.reply_target, msg.sender)
+        } else {
+            format!("dm:{}", msg.reply_target)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1198,10 +1790,31 @@ mod tests {
     }
 
     #[test]
-    fn telegram_api_url() {
+    fn telegram_capabilities() {
+        let ch = TelegramChannel::new("fake-token".into(), vec!["*".into()]);
+        let caps = ch.capabilities();
+        assert!(caps.supports_edit && caps.supports_markdown && caps.supports_typing);
+        assert!(caps.supports_media_markers);
+    }
+
+    #[test]
+    fn telegram_conversation_key_separates_dms_and_groups() {
+        let ch = TelegramChannel::new("fake-token".into(), vec!["*".into()]);
+        let mut msg = ChannelMessage {
+            id: "telegram_42_1".into(),
+            reply_target: "42".into(),
+            ..ChannelMessage::test("telegram", "alice", "hi")
+        };
+        assert_eq!(ch.conversation_key(&msg), "dm:42");
+        msg.reply_target = "-100123".into();
+        assert_eq!(ch.conversation_key(&msg), "group:-100123:alice");
+    }
+
+    #[tokio::test]
+    async fn telegram_api_url() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
         assert_eq!(
-            ch.api_url("getMe"),
+            ch.api_url("getMe").await.unwrap(),
             "https://api.telegram.org/bot123:ABC/getMe"
         );
     }
@@ -1383,6 +1996,54 @@ mod tests {
         assert_eq!(msg.id, "telegram_-100200300_33");
     }
 
+    #[test]
+    fn callback_queries_become_interactions() {
+        let ch = TelegramChannel::new("token".into(), vec!["alice".into()]);
+        let query = serde_json::json!({
+            "id": "cb1",
+            "data": "menu:settings",
+            "from": { "id": 555, "username": "alice" },
+            "message": { "message_id": 34, "chat": { "id": -100_200_300 } }
+        });
+        let interaction = ch.parse_callback_query(&query).expect("click should parse");
+        assert_eq!(interaction.callback_id, "menu:settings");
+        assert_eq!(interaction.user_id, "555");
+        assert_eq!(interaction.reply_target, "-100200300");
+        assert_eq!(interaction.message_id.as_deref(), Some("34"));
+
+        let stranger = serde_json::json!({
+            "id": "cb2",
+            "data": "menu:settings",
+            "from": { "id": 777, "username": "mallory" }
+        });
+        assert!(ch.parse_callback_query(&stranger).is_none());
+
+        let keyboard = Keyboard::new().with_row(vec![
+            interactions::Button::new("menu:yes", "Yes"),
+            interactions::Button::new("menu:no", "No"),
+        ]);
+        assert_eq!(
+            inline_keyboard(&keyboard),
+            serde_json::json!({ "inline_keyboard": [[
+                { "text": "Yes", "callback_data": "menu:yes" },
+                { "text": "No", "callback_data": "menu:no" },
+            ]] })
+        );
+    }
+
+    #[test]
+    fn poll_answers_are_counted() {
+        let poll = Poll::new("Deploy?", vec!["Now".into(), "Later".into()]);
+        let id = polls::track("telegram", "poll-answers", "-1", poll);
+        record_poll_answer(
+            "telegram",
+            &serde_json::json!({ "poll_id": "poll-answers", "user": { "id": 5 }, "option_ids": [1] }),
+        );
+        let results = polls::results(&id).unwrap();
+        assert_eq!(results.options[1].votes, 1);
+        assert_eq!(results.voters, 1);
+    }
+
     #[test]
     fn parse_update_message_allows_numeric_id_without_username() {
         let ch = TelegramChannel::new("token".into(), vec!["555".into()]);
@@ -1408,49 +2069,130 @@ mod tests {
         assert_eq!(msg.reply_target, "12345");
     }
 
-    // ── File sending API URL tests ──────────────────────────────────
+    #[test]
+    fn parse_update_message_without_message_id_is_keyed_by_update() {
+        let ch = TelegramChannel::new("token".into(), vec!["*".into()]);
+        let update = |update_id: i64| {
+            serde_json::json!({
+                "update_id": update_id,
+                "message": {
+                    "text": "ping",
+                    "from": { "id": 555 },
+                    "chat": { "id": 12345 }
+                }
+            })
+        };
+
+        let first = ch.parse_update_message(&update(7)).unwrap();
+        let second = ch.parse_update_message(&update(8)).unwrap();
+        assert_eq!(first.id, "telegram_12345_update_7");
+        assert_ne!(first.id, second.id);
+    }
 
     #[test]
-    fn telegram_api_url_send_document() {
+    fn parse_update_message_attaches_voice_notes() {
+        let ch = TelegramChannel::new("token".into(), vec!["*".into()]);
+        let update = serde_json::json!({
+            "update_id": 3,
+            "message": {
+                "message_id": 10,
+                "voice": {
+                    "file_id": "AwACAgIAAxkBAAI",
+                    "mime_type": "audio/ogg",
+                    "duration": 3
+                },
+                "from": { "id": 555, "username": "alice" },
+                "chat": { "id": 555 }
+            }
+        });
+
+        let msg = ch
+            .parse_update_message(&update)
+            .expect("voice message should parse");
+
+        assert_eq!(msg.content, "");
+        assert_eq!(
+            msg.attachments,
+            vec![MediaAttachment {
+                kind: MediaKind::Voice,
+                source: "AwACAgIAAxkBAAI".into(),
+                mime_type: Some("audio/ogg".into()),
+                file_name: None,
+                stored_key: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_update_message_attaches_largest_photo() {
+        let ch = TelegramChannel::new("token".into(), vec!["*".into()]);
+        let update = serde_json::json!({
+            "update_id": 4,
+            "message": {
+                "message_id": 11,
+                "caption": "what plant is this?",
+                "photo": [
+                    { "file_id": "small", "width": 90, "height": 90 },
+                    { "file_id": "large", "width": 1280, "height": 1280 }
+                ],
+                "from": { "id": 555, "username": "alice" },
+                "chat": { "id": 555 }
+            }
+        });
+
+        let msg = ch
+            .parse_update_message(&update)
+            .expect("photo message should parse");
+
+        assert_eq!(msg.content, "what plant is this?");
+        assert_eq!(msg.attachments.len(), 1);
+        assert_eq!(msg.attachments[0].kind, MediaKind::Image);
+        assert_eq!(msg.attachments[0].source, "large");
+    }
+
+    // ── File sending API URL tests ──────────────────────────────────
+
+    #[tokio::test]
+    async fn telegram_api_url_send_document() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
         assert_eq!(
-            ch.api_url("sendDocument"),
+            ch.api_url("sendDocument").await.unwrap(),
             "https://api.telegram.org/bot123:ABC/sendDocument"
         );
     }
 
-    #[test]
-    fn telegram_api_url_send_photo() {
+    #[tokio::test]
+    async fn telegram_api_url_send_photo() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
         assert_eq!(
-            ch.api_url("sendPhoto"),
+            ch.api_url("sendPhoto").await.unwrap(),
             "https://api.telegram.org/bot123:ABC/sendPhoto"
         );
     }
 
-    #[test]
-    fn telegram_api_url_send_video() {
+    #[tokio::test]
+    async fn telegram_api_url_send_video() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
         assert_eq!(
-            ch.api_url("sendVideo"),
+            ch.api_url("sendVideo").await.unwrap(),
             "https://api.telegram.org/bot123:ABC/sendVideo"
         );
     }
 
-    #[test]
-    fn telegram_api_url_send_audio() {
+    #[tokio::test]
+    async fn telegram_api_url_send_audio() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
         assert_eq!(
-            ch.api_url("sendAudio"),
+            ch.api_url("sendAudio").await.unwrap(),
             "https://api.telegram.org/bot123:ABC/sendAudio"
         );
     }
 
-    #[test]
-    fn telegram_api_url_send_voice() {
+    #[tokio::test]
+    async fn telegram_api_url_send_voice() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
         assert_eq!(
-            ch.api_url("sendVoice"),
+            ch.api_url("sendVoice").await.unwrap(),
             "https://api.telegram.org/bot123:ABC/sendVoice"
         );
     }
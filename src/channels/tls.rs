@@ -0,0 +1,227 @@
+//! TLS settings of channel connections.
+//!
+//! [`client_config`] turns a channel's `[channels_config.http]` settings into
+//! a rustls configuration shared by its HTTP client and gateway WebSocket:
+//! a root store of the configured roots (or the built-in ones) plus
+//! `ca_certs`, a minimum protocol version and optional SPKI pins, checked
+//! after the chain has been verified. Invalid roots or pins fail closed: the
+//! channel gets a configuration that rejects every server.
+
+use crate::config::{HttpClientConfig, TlsMinVersion};
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The rustls configuration of `settings`, or `None` when the defaults of
+/// the HTTP and WebSocket libraries already apply.
+pub fn client_config(settings: &HttpClientConfig) -> Option<Arc<ClientConfig>> {
+    if !settings.tls.is_restricted() && settings.ca_certs.is_empty() {
+        return None;
+    }
+    let config = build(settings).unwrap_or_else(|e| {
+        tracing::error!("Invalid channel TLS settings, refusing all connections: {e:#}");
+        reject_all()
+    });
+    Some(Arc::new(config))
+}
+
+fn crypto_provider() -> Arc<rustls::crypto::CryptoProvider> {
+    rustls::crypto::CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()))
+}
+
+fn build(settings: &HttpClientConfig) -> Result<ClientConfig> {
+    let tls = &settings.tls;
+    let mut roots = RootCertStore::empty();
+    if tls.roots.is_empty() {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    }
+    for path in &tls.roots {
+        for cert in load_pem(path)? {
+            roots
+                .add(cert)
+                .with_context(|| format!("invalid root certificate in {}", path.display()))?;
+        }
+    }
+    // Extra CAs only widen trust, so a bad one is skipped rather than fatal
+    for path in &settings.ca_certs {
+        let added = load_pem(path).and_then(|certs| {
+            certs
+                .into_iter()
+                .try_for_each(|cert| roots.add(cert))
+                .context("invalid certificate")
+        });
+        if let Err(e) = added {
+            tracing::warn!("Skipping CA certificate {}: {e:#}", path.display());
+        }
+    }
+
+    let provider = crypto_provider();
+    let verifier =
+        WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()?;
+    let versions: &[&rustls::SupportedProtocolVersion] = match tls.min_version {
+        TlsMinVersion::Tls12 => rustls::DEFAULT_VERSIONS,
+        TlsMinVersion::Tls13 => &[&rustls::version::TLS13],
+    };
+    let builder = ClientConfig::builder_with_provider(provider).with_protocol_versions(versions)?;
+
+    let pins = parse_pins(&tls.pinned_spki_sha256)?;
+    let config = if pins.is_empty() {
+        builder.with_webpki_verifier(verifier)
+    } else {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                inner: verifier,
+                pins,
+            }))
+    };
+    Ok(config.with_no_client_auth())
+}
+
+/// A configuration without trusted roots, so every handshake fails.
+fn reject_all() -> ClientConfig {
+    ClientConfig::builder_with_provider(crypto_provider())
+        .with_safe_default_protocol_versions()
+        .expect("default protocol versions are supported")
+        .with_root_certificates(RootCertStore::empty())
+        .with_no_client_auth()
+}
+
+fn load_pem(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .with_context(|| format!("failed to read certificates from {}", path.display()))?;
+    if certs.is_empty() {
+        bail!("no certificates in {}", path.display());
+    }
+    Ok(certs)
+}
+
+fn parse_pins(pins: &[String]) -> Result<Vec<[u8; 32]>> {
+    pins.iter()
+        .map(|pin| {
+            let hash = pin.trim().trim_start_matches("sha256/");
+            base64::engine::general_purpose::STANDARD
+                .decode(hash)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .with_context(|| format!("invalid SPKI pin {pin:?}: expected base64 SHA-256"))
+        })
+        .collect()
+}
+
+/// SHA-256 of a certificate's SubjectPublicKeyInfo.
+fn spki_sha256(cert: &CertificateDer<'_>) -> Option<[u8; 32]> {
+    let cert = webpki::EndEntityCert::try_from(cert).ok()?;
+    Some(Sha256::digest(cert.subject_public_key_info().as_ref()).into())
+}
+
+/// Verifies the chain as usual, then requires one of its keys to be pinned.
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(spki_sha256)
+            .any(|hash| self.pins.contains(&hash));
+        if !pinned {
+            return Err(rustls::Error::General(format!(
+                "no pinned key in the certificate chain of {}",
+                server_name.to_str()
+            )));
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChannelTlsConfig;
+
+    #[test]
+    fn pins_accept_base64_sha256_hashes_only() {
+        let hash = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+        let pins = parse_pins(&[hash.clone(), format!("sha256/{hash}")]).unwrap();
+        assert_eq!(pins, vec![[7u8; 32]; 2]);
+        assert!(parse_pins(&["not-a-hash".into()]).is_err());
+        assert!(
+            parse_pins(&[base64::engine::general_purpose::STANDARD.encode([1u8; 20])]).is_err()
+        );
+    }
+
+    #[test]
+    fn tls_config_is_only_built_when_restricted() {
+        assert!(client_config(&HttpClientConfig::default()).is_none());
+
+        let tls13 = HttpClientConfig {
+            tls: ChannelTlsConfig {
+                min_version: TlsMinVersion::Tls13,
+                ..ChannelTlsConfig::default()
+            },
+            ..HttpClientConfig::default()
+        };
+        assert!(build(&tls13).is_ok());
+
+        // A missing root store fails instead of trusting the built-in roots
+        let missing_roots = HttpClientConfig {
+            tls: ChannelTlsConfig {
+                roots: vec!["/nonexistent/root.pem".into()],
+                ..ChannelTlsConfig::default()
+            },
+            ..HttpClientConfig::default()
+        };
+        assert!(build(&missing_roots).is_err());
+        assert!(client_config(&missing_roots).is_some());
+    }
+}
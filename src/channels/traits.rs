@@ -1,14 +1,149 @@
+use super::interactions::Keyboard;
+use super::middleware::OutboundMessage;
+use super::polls::Poll;
+use super::uploads::FileReader;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Kind of media attached to a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Voice,
+    Audio,
+    Image,
+    Video,
+    Document,
+}
+
+/// Media attached to an inbound message. `source` is whatever the channel
+/// needs to fetch it with [`Channel::download_attachment`]: usually a URL,
+/// or a platform file id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaAttachment {
+    pub kind: MediaKind,
+    pub source: String,
+    pub mime_type: Option<String>,
+    pub file_name: Option<String>,
+    /// Content-addressed key of the copy kept in the media store, once
+    /// persisted (see [`crate::media_store`])
+    pub stored_key: Option<String>,
+}
+
+/// Current time as unix seconds.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
 
 /// A message received from or sent to a channel
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelMessage {
     pub id: String,
     pub sender: String,
     pub reply_target: String,
     pub content: String,
     pub channel: String,
+    /// When the platform says the message was sent (unix seconds); the
+    /// receive time when it gives none
     pub timestamp: u64,
+    /// When this process received the message (unix seconds)
+    pub received_at: u64,
+    /// Media that came with the message (voice notes, images, files)
+    pub attachments: Vec<MediaAttachment>,
+    /// Stable platform ID of the person who wrote the message, when the
+    /// channel knows it and `sender` is something else (a room, a nick)
+    pub sender_id: Option<String>,
+    /// Display name of the author, when the platform sends one
+    pub sender_name: Option<String>,
+    /// Chat, room or thread the message was posted in
+    pub conversation_id: Option<String>,
+    /// IDs of the users mentioned in the message (see [`super::mentions`])
+    pub mentions: Vec<String>,
+    /// The bot was @-mentioned; its mention is already stripped from
+    /// `content`
+    pub is_mention: bool,
+}
+
+impl ChannelMessage {
+    /// Who wrote the message: `sender_id`, else `sender`. Per-person state
+    /// (allowlists, memory) is keyed by this.
+    pub fn author_id(&self) -> &str {
+        self.sender_id.as_deref().unwrap_or(&self.sender)
+    }
+
+    /// Where the message was posted: `conversation_id`, else `reply_target`.
+    pub fn conversation(&self) -> &str {
+        self.conversation_id
+            .as_deref()
+            .unwrap_or(&self.reply_target)
+    }
+}
+
+#[cfg(test)]
+impl ChannelMessage {
+    /// Text message `1` from `sender` on `channel`, answered in the
+    /// sender's DM. Set other fields with `..ChannelMessage::test(..)`.
+    pub fn test(channel: &str, sender: &str, content: &str) -> Self {
+        Self {
+            id: "1".into(),
+            sender: sender.into(),
+            reply_target: sender.into(),
+            content: content.into(),
+            channel: channel.into(),
+            timestamp: 0,
+            received_at: 0,
+            attachments: vec![],
+            sender_id: None,
+            sender_name: None,
+            conversation_id: None,
+            mentions: vec![],
+            is_mention: false,
+        }
+    }
+}
+
+/// What a channel can do, so the reply pipeline adapts to it instead of
+/// matching on channel names.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChannelCapabilities {
+    /// Sent messages can be edited (see [`Channel::supports_editing`])
+    pub supports_edit: bool,
+    /// Files can be sent as attachments (see [`Channel::max_file_size`])
+    pub supports_files: bool,
+    /// Longest text, in characters, one message holds
+    pub max_message_len: usize,
+    /// Markdown in replies is rendered rather than shown as typed
+    pub supports_markdown: bool,
+    /// Users can react to messages with emoji
+    pub supports_reactions: bool,
+    /// The platform shows a typing indicator (see [`Channel::start_typing`])
+    pub supports_typing: bool,
+    /// Replies can carry `[IMAGE:...]`-style markers that the channel sends
+    /// as attachments
+    pub supports_media_markers: bool,
+}
+
+impl ChannelCapabilities {
+    /// Capabilities `channel` reports through its `supports_*` methods;
+    /// markdown, reactions, typing and media markers are off. Every built-in
+    /// channel overrides [`Channel::capabilities`] to state those, starting
+    /// from this.
+    pub fn of<C: Channel + ?Sized>(channel: &C) -> Self {
+        Self {
+            supports_edit: channel.supports_editing(),
+            supports_files: channel.max_file_size().is_some(),
+            max_message_len: channel.max_message_length(),
+            supports_markdown: false,
+            supports_reactions: false,
+            supports_typing: false,
+            supports_media_markers: false,
+        }
+    }
 }
 
 /// Core channel trait — implement for any messaging platform
@@ -20,6 +155,22 @@ pub trait Channel: Send + Sync {
     /// Send a message through this channel
     async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()>;
 
+    /// Shortest gap between two messages to one chat that stays within the
+    /// platform's rate limits; [`Channel::send_batch`] paces by it.
+    fn send_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Send `messages` to `recipient` one after another, in order and paced
+    /// by [`Channel::send_interval`] (see [`super::batch`]).
+    async fn send_batch(
+        &self,
+        recipient: &str,
+        messages: Vec<OutboundMessage>,
+    ) -> anyhow::Result<()> {
+        super::batch::send_paced(self, recipient, messages).await
+    }
+
     /// Start listening for incoming messages (long-running)
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()>;
 
@@ -38,6 +189,160 @@ pub trait Channel: Send + Sync {
     async fn stop_typing(&self, _recipient: &str) -> anyhow::Result<()> {
         Ok(())
     }
+
+    /// Whether [`Channel::send_voice_note`] can deliver audio on this platform.
+    fn supports_voice_notes(&self) -> bool {
+        false
+    }
+
+    /// Send encoded audio as a voice message. `file_name` carries the
+    /// container format (e.g. `reply.ogg`).
+    async fn send_voice_note(
+        &self,
+        _audio: Vec<u8>,
+        _file_name: &str,
+        _recipient: &str,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("{} does not support voice messages", self.name())
+    }
+
+    /// Whether [`Channel::send_image`] uploads images natively.
+    fn supports_images(&self) -> bool {
+        false
+    }
+
+    /// Send an image as a native image message rather than a link.
+    async fn send_image(
+        &self,
+        _image: Vec<u8>,
+        _file_name: &str,
+        _caption: Option<&str>,
+        _recipient: &str,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("{} does not support images", self.name())
+    }
+
+    /// Whether [`Channel::send_buttons`] shows clickable buttons.
+    fn supports_buttons(&self) -> bool {
+        false
+    }
+
+    /// Send `text` with a keyboard of buttons under it. Clicks come back
+    /// through [`super::interactions::route`].
+    async fn send_buttons(
+        &self,
+        _text: &str,
+        _keyboard: &Keyboard,
+        _recipient: &str,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("{} does not support buttons", self.name())
+    }
+
+    /// Whether [`Channel::send_poll`] can post polls.
+    fn supports_polls(&self) -> bool {
+        false
+    }
+
+    /// Post a poll and start tracking its votes (see [`super::polls`]).
+    /// Returns the id its results are queried by.
+    async fn send_poll(&self, _poll: &Poll, _recipient: &str) -> anyhow::Result<String> {
+        anyhow::bail!("{} does not support polls", self.name())
+    }
+
+    /// Whether the platform reports delivery or read receipts for messages
+    /// sent with [`Channel::send_tracked`].
+    fn supports_receipts(&self) -> bool {
+        false
+    }
+
+    /// Send `message` and collect its receipts (see [`super::receipts`]).
+    /// Returns the id its receipts are queried by.
+    async fn send_tracked(&self, _message: &str, _recipient: &str) -> anyhow::Result<String> {
+        anyhow::bail!("{} does not report message receipts", self.name())
+    }
+
+    /// Whether sent messages can be edited, so replies can be streamed into
+    /// one message (see [`super::streaming`]).
+    fn supports_editing(&self) -> bool {
+        false
+    }
+
+    /// Longest text, in characters, one message holds.
+    fn max_message_length(&self) -> usize {
+        4096
+    }
+
+    /// What this channel can do, in one place.
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities::of(self)
+    }
+
+    /// Send `message` as plain text and return the id
+    /// [`Channel::edit_message`] takes.
+    async fn send_editable(&self, _message: &str, _recipient: &str) -> anyhow::Result<String> {
+        anyhow::bail!("{} does not support editing messages", self.name())
+    }
+
+    /// Replace the text of a message sent with [`Channel::send_editable`].
+    async fn edit_message(
+        &self,
+        _message_id: &str,
+        _message: &str,
+        _recipient: &str,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("{} does not support editing messages", self.name())
+    }
+
+    /// Largest file [`Channel::send_file`] takes, in bytes; `None` when the
+    /// channel can't send files.
+    fn max_file_size(&self) -> Option<u64> {
+        None
+    }
+
+    /// Stream a file to `recipient` as an attachment. `size` is its exact
+    /// length when known. Callers should go through
+    /// [`super::uploads::send_file`], which enforces
+    /// [`Channel::max_file_size`] and falls back to a link.
+    async fn send_file(
+        &self,
+        _recipient: &str,
+        _name: &str,
+        _mime: &str,
+        _size: Option<u64>,
+        _reader: FileReader,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("{} does not support files", self.name())
+    }
+
+    /// Download an attachment of an inbound message. The default fetches
+    /// `source` as a URL.
+    async fn download_attachment(&self, attachment: &MediaAttachment) -> anyhow::Result<Vec<u8>> {
+        let bytes = reqwest::get(&attachment.source)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Whether the author of `msg` administers the group, guild or channel
+    /// it was posted in. The default knows of no admins.
+    async fn is_group_admin(&self, _msg: &ChannelMessage) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
+    /// Key of the native conversation a message belongs to, used to map it
+    /// to a session. The default treats [`ChannelMessage::conversation`] as
+    /// the conversation (DM, group channel or thread) and gives each author
+    /// in it their own session.
+    fn conversation_key(&self, msg: &ChannelMessage) -> String {
+        let author = msg.author_id();
+        if msg.conversation() == author {
+            format!("dm:{author}")
+        } else {
+            format!("{}:{author}", msg.conversation())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -61,12 +366,9 @@ mod tests {
             tx: tokio::sync::mpsc::Sender<ChannelMessage>,
         ) -> anyhow::Result<()> {
             tx.send(ChannelMessage {
-                id: "1".into(),
-                sender: "tester".into(),
-                reply_target: "tester".into(),
-                content: "hello".into(),
-                channel: "dummy".into(),
                 timestamp: 123,
+                received_at: 123,
+                ..ChannelMessage::test("dummy", "tester", "hello")
             })
             .await
             .map_err(|e| anyhow::anyhow!(e.to_string()))
@@ -77,11 +379,9 @@ mod tests {
     fn channel_message_clone_preserves_fields() {
         let message = ChannelMessage {
             id: "42".into(),
-            sender: "alice".into(),
-            reply_target: "alice".into(),
-            content: "ping".into(),
-            channel: "dummy".into(),
             timestamp: 999,
+            received_at: 999,
+            ..ChannelMessage::test("dummy", "alice", "ping")
         };
 
         let cloned = message.clone();
@@ -93,6 +393,19 @@ mod tests {
         assert_eq!(cloned.timestamp, 999);
     }
 
+    #[test]
+    fn author_and_conversation_prefer_explicit_ids() {
+        let mut message = ChannelMessage::test("lark", "oc_chat", "hi");
+        assert_eq!(message.author_id(), "oc_chat");
+        assert_eq!(DummyChannel.conversation_key(&message), "dm:oc_chat");
+
+        message.sender_id = Some("ou_alice".into());
+        message.conversation_id = Some("oc_chat".into());
+        assert_eq!(message.author_id(), "ou_alice");
+        assert_eq!(message.conversation(), "oc_chat");
+        assert_eq!(DummyChannel.conversation_key(&message), "oc_chat:ou_alice");
+    }
+
     #[tokio::test]
     async fn default_trait_methods_return_success() {
         let channel = DummyChannel;
@@ -103,6 +416,16 @@ mod tests {
         assert!(channel.send("hello", "bob").await.is_ok());
     }
 
+    #[test]
+    fn default_capabilities_follow_the_supports_methods() {
+        let caps = DummyChannel.capabilities();
+        assert!(!caps.supports_edit);
+        assert!(!caps.supports_files);
+        assert_eq!(caps.max_message_len, 4096);
+        assert!(!caps.supports_markdown);
+        assert!(!caps.supports_typing);
+    }
+
     #[tokio::test]
     async fn listen_sends_message_to_channel() {
         let channel = DummyChannel;
@@ -0,0 +1,293 @@
+//! Sending files to channels.
+//!
+//! [`send_file`] is the entry point. It checks the file against the
+//! channel's [`Channel::max_file_size`] — reading ahead when the size is
+//! not known — and streams it through [`Channel::send_file`] when it fits.
+//! Files the channel can't take are uploaded to the `[uploads]` S3 bucket
+//! instead and sent as a time-limited link.
+
+use super::traits::Channel;
+use crate::config::UploadsConfig;
+use crate::media_store::s3::{S3Client, MAX_PRESIGN_SECS};
+use crate::security::ManagedSecret;
+use anyhow::{bail, Context, Result};
+use parking_lot::RwLock;
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use uuid::Uuid;
+
+/// File contents handed to [`Channel::send_file`].
+pub type FileReader = Box<dyn AsyncRead + Send + Sync + Unpin>;
+
+/// Read size of [`streaming_body`].
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+static UPLOADER: RwLock<Option<Arc<Uploader>>> = RwLock::new(None);
+
+/// Use `uploader` for oversized files from now on (`None` disables the
+/// fallback).
+pub fn install(uploader: Option<Uploader>) {
+    *UPLOADER.write() = uploader.map(Arc::new);
+}
+
+fn uploader() -> Option<Arc<Uploader>> {
+    UPLOADER.read().clone()
+}
+
+/// A request body that streams `reader` instead of buffering it.
+pub fn streaming_body(reader: FileReader) -> reqwest::Body {
+    let chunks = futures_util::stream::unfold(reader, |mut reader| async move {
+        let mut buf = vec![0u8; STREAM_CHUNK_BYTES];
+        match reader.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(buf), reader))
+            }
+            Err(e) => Some((Err::<Vec<u8>, std::io::Error>(e), reader)),
+        }
+    });
+    reqwest::Body::wrap_stream(chunks)
+}
+
+/// How [`send_file`] delivered a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileDelivery {
+    /// As a native attachment
+    Attached,
+    /// As a link to the uploaded copy
+    Linked(String),
+}
+
+/// Send a file to `recipient`, as an attachment when the channel takes
+/// files of its size and as a link otherwise. `size` is the exact length
+/// when known.
+pub async fn send_file(
+    channel: &dyn Channel,
+    recipient: &str,
+    name: &str,
+    mime: &str,
+    size: Option<u64>,
+    mut reader: FileReader,
+) -> Result<FileDelivery> {
+    if let Some(limit) = channel.max_file_size() {
+        match size {
+            Some(len) if len <= limit => {
+                channel
+                    .send_file(recipient, name, mime, Some(len), reader)
+                    .await?;
+                return Ok(FileDelivery::Attached);
+            }
+            Some(_) => {}
+            None => {
+                // Read one byte past the limit to learn which side it's on
+                let mut head = Vec::new();
+                (&mut reader).take(limit + 1).read_to_end(&mut head).await?;
+                let len = head.len() as u64;
+                if len <= limit {
+                    channel
+                        .send_file(
+                            recipient,
+                            name,
+                            mime,
+                            Some(len),
+                            Box::new(Cursor::new(head)),
+                        )
+                        .await?;
+                    return Ok(FileDelivery::Attached);
+                }
+                reader = Box::new(Cursor::new(head).chain(reader));
+            }
+        }
+    }
+
+    let Some(uploader) = uploader() else {
+        bail!(
+            "{name} is too large for {} and no [uploads] store is configured",
+            channel.name()
+        );
+    };
+    let spooled;
+    let (reader, len): (FileReader, u64) = match size {
+        Some(len) => (reader, len),
+        None => {
+            // S3 needs the length up front
+            spooled = Spooled::new(reader).await?;
+            (Box::new(spooled.open().await?), spooled.len)
+        }
+    };
+    let url = uploader.upload(name, mime, len, reader).await?;
+    channel
+        .send(
+            &format!("📎 {name} ({}): {url}", format_size(len)),
+            recipient,
+        )
+        .await?;
+    Ok(FileDelivery::Linked(url))
+}
+
+/// `bytes` as a short human-readable size.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// A stream of unknown length copied to a temporary file.
+struct Spooled {
+    path: std::path::PathBuf,
+    len: u64,
+}
+
+impl Spooled {
+    async fn new(mut reader: FileReader) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("zeroclaw-upload-{}", Uuid::new_v4()));
+        let mut file = tokio::fs::File::create(&path)
+            .await
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        // Created before copying so a failed copy still removes the file
+        let mut spooled = Self { path, len: 0 };
+        spooled.len = tokio::io::copy(&mut reader, &mut file).await?;
+        Ok(spooled)
+    }
+
+    async fn open(&self) -> Result<tokio::fs::File> {
+        Ok(tokio::fs::File::open(&self.path).await?)
+    }
+}
+
+impl Drop for Spooled {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Uploads oversized files to S3 and links to them.
+pub struct Uploader {
+    store: S3Client,
+    link_expiry_secs: u64,
+}
+
+impl Uploader {
+    /// `None` when `[uploads]` has no store configured.
+    pub fn from_config(
+        config: &UploadsConfig,
+        secret: impl FnOnce(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Option<Self>> {
+        let Some(s3) = &config.s3 else {
+            return Ok(None);
+        };
+        let secret = secret(&s3.secret_access_key)?;
+        Ok(Some(Self {
+            store: S3Client::new(s3, secret)?,
+            link_expiry_secs: config.link_expiry_secs.clamp(1, MAX_PRESIGN_SECS),
+        }))
+    }
+
+    /// Store the file under a fresh key and return a presigned link to it.
+    async fn upload(&self, name: &str, mime: &str, len: u64, reader: FileReader) -> Result<String> {
+        let key = format!("uploads/{}/{}", Uuid::new_v4(), sanitize_file_name(name));
+        self.store
+            .put(&key, mime, len, streaming_body(reader))
+            .await?;
+        self.store.presigned_get(&key, self.link_expiry_secs).await
+    }
+}
+
+/// `name` reduced to characters safe in an object key and a URL.
+fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches('.');
+    if cleaned.is_empty() {
+        "file".into()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::ChannelMessage;
+
+    #[test]
+    fn file_names_and_sizes_are_made_presentable() {
+        assert_eq!(
+            sanitize_file_name("report (final).pdf"),
+            "report__final_.pdf"
+        );
+        assert_eq!(sanitize_file_name("..."), "file");
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MB");
+    }
+
+    #[tokio::test]
+    async fn oversized_files_without_a_store_are_refused() {
+        struct Tiny;
+
+        #[async_trait::async_trait]
+        impl Channel for Tiny {
+            fn name(&self) -> &str {
+                "tiny"
+            }
+
+            async fn send(&self, _message: &str, _recipient: &str) -> Result<()> {
+                Ok(())
+            }
+
+            async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+                Ok(())
+            }
+
+            fn max_file_size(&self) -> Option<u64> {
+                Some(4)
+            }
+
+            async fn send_file(
+                &self,
+                _recipient: &str,
+                _name: &str,
+                _mime: &str,
+                size: Option<u64>,
+                mut reader: FileReader,
+            ) -> Result<()> {
+                let mut body = Vec::new();
+                reader.read_to_end(&mut body).await?;
+                assert_eq!(size, Some(body.len() as u64));
+                Ok(())
+            }
+        }
+
+        let small: FileReader = Box::new(Cursor::new(b"abc".to_vec()));
+        let delivery = send_file(&Tiny, "r", "a.txt", "text/plain", None, small)
+            .await
+            .unwrap();
+        assert_eq!(delivery, FileDelivery::Attached);
+
+        let large: FileReader = Box::new(Cursor::new(b"abcdef".to_vec()));
+        let err = send_file(&Tiny, "r", "b.txt", "text/plain", None, large)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("too large for tiny"));
+    }
+}
@@ -1,18 +1,60 @@
-use super::traits::{Channel, ChannelMessage};
+use super::receipts::{self, DeliveryStatus};
+use super::traits::{
+    unix_now, Channel, ChannelCapabilities, ChannelMessage, MediaAttachment, MediaKind,
+};
+use super::uploads::{self, FileReader};
 use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
 use uuid::Uuid;
 
+const GRAPH_API: &str = "https://graph.facebook.com/v18.0";
+
+/// Free-form messages can only be sent this long after the user last
+/// wrote; outside it only approved templates are delivered.
+const SESSION_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Cloud API error for a free-form message outside the session window.
+const REENGAGEMENT_ERROR_CODE: i64 = 131_047;
+
+/// Largest document the Cloud API accepts.
+const WHATSAPP_MAX_UPLOAD_BYTES: u64 = 100 * 1024 * 1024;
+
+/// When each number last wrote to us. Inbound messages arrive at the
+/// gateway's instance of the channel, replies may go out through another,
+/// so this is shared by all of them.
+static LAST_INBOUND: LazyLock<Mutex<HashMap<String, u64>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// An error response of the Cloud API.
+#[derive(Debug, thiserror::Error)]
+#[error("WhatsApp API error {status}: {message}")]
+pub struct WhatsAppApiError {
+    pub status: u16,
+    /// Cloud API error code (e.g. 131047 for a closed session window)
+    pub code: Option<i64>,
+    pub message: String,
+}
+
 /// `WhatsApp` channel — uses `WhatsApp` Business Cloud API
 ///
 /// This channel operates in webhook mode (push-based) rather than polling.
 /// Messages are received via the gateway's `/whatsapp` webhook endpoint.
 /// The `listen` method here is a no-op placeholder; actual message handling
 /// happens in the gateway when Meta sends webhook events.
+///
+/// Text goes out free-form while the recipient's 24-hour session window is
+/// open. Outside it, and when the API reports the window closed, the text is
+/// sent through the session template if one is configured (see
+/// [`WhatsAppChannel::with_session_template`]).
 pub struct WhatsAppChannel {
     access_token: String,
     endpoint_id: String,
     verify_token: String,
     allowed_numbers: Vec<String>,
+    /// Template name and language code used outside the session window
+    session_template: Option<(String, String)>,
     client: reqwest::Client,
 }
 
@@ -28,10 +70,19 @@ impl WhatsAppChannel {
             endpoint_id,
             verify_token,
             allowed_numbers,
-            client: reqwest::Client::new(),
+            session_template: None,
+            client: super::net::http_client_for("whatsapp"),
         }
     }
 
+    /// Deliver text outside the session window through the approved
+    /// template `name`, whose body has one `{{1}}` parameter that gets the
+    /// text.
+    pub fn with_session_template(mut self, name: Option<String>, language: String) -> Self {
+        self.session_template = name.map(|name| (name, language));
+        self
+    }
+
     /// Check if a phone number is allowed (E.164 format: +1234567890)
     fn is_number_allowed(&self, phone: &str) -> bool {
         self.allowed_numbers.iter().any(|n| n == "*" || n == phone)
@@ -88,20 +139,24 @@ impl WhatsAppChannel {
                         continue;
                     }
 
-                    // Extract text content (support text messages only for now)
-                    let content = if let Some(text_obj) = msg.get("text") {
-                        text_obj
-                            .get("body")
-                            .and_then(|b| b.as_str())
-                            .unwrap_or("")
-                            .to_string()
+                    // Any message from the user opens the session window
+                    LAST_INBOUND
+                        .lock()
+                        .insert(normalized_from.clone(), unix_now());
+
+                    // Text, or media with its caption
+                    let (content, attachments) = if let Some(text_obj) = msg.get("text") {
+                        let body = text_obj.get("body").and_then(|b| b.as_str()).unwrap_or("");
+                        (body.to_string(), vec![])
+                    } else if let Some((attachment, caption)) = media_attachment(msg) {
+                        (caption, vec![attachment])
                     } else {
-                        // Could be image, audio, etc. — skip for now
-                        tracing::debug!("WhatsApp: skipping non-text message from {from}");
+                        // Location, contacts, reactions, stickers, ...
+                        tracing::debug!("WhatsApp: skipping unsupported message from {from}");
                         continue;
                     };
 
-                    if content.is_empty() {
+                    if content.is_empty() && attachments.is_empty() {
                         continue;
                     }
 
@@ -124,6 +179,13 @@ impl WhatsAppChannel {
                         content,
                         channel: "whatsapp".to_string(),
                         timestamp,
+                        received_at: unix_now(),
+                        attachments,
+                        sender_id: None,
+                        sender_name: None,
+                        conversation_id: None,
+                        mentions: vec![],
+                        is_mention: false,
                     });
                 }
             }
@@ -131,34 +193,28 @@ impl WhatsAppChannel {
 
         messages
     }
-}
 
-#[async_trait]
-impl Channel for WhatsAppChannel {
-    fn name(&self) -> &str {
-        "whatsapp"
-    }
-
-    async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+    /// POST a message object (`type` plus its payload) to `recipient`;
+    /// returns the API's response body.
+    async fn post_message(
+        &self,
+        recipient: &str,
+        message: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
         // WhatsApp Cloud API: POST to /v18.0/{phone_number_id}/messages
-        let url = format!(
-            "https://graph.facebook.com/v18.0/{}/messages",
-            self.endpoint_id
-        );
+        let url = format!("{GRAPH_API}/{}/messages", self.endpoint_id);
 
         // Normalize recipient (remove leading + if present for API)
         let to = recipient.strip_prefix('+').unwrap_or(recipient);
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "messaging_product": "whatsapp",
             "recipient_type": "individual",
             "to": to,
-            "type": "text",
-            "text": {
-                "preview_url": false,
-                "body": message
-            }
         });
+        if let (Some(body), Some(message)) = (body.as_object_mut(), message.as_object()) {
+            body.extend(message.clone());
+        }
 
         let resp = self
             .client
@@ -173,12 +229,352 @@ impl Channel for WhatsAppChannel {
             let status = resp.status();
             let error_body = resp.text().await.unwrap_or_default();
             tracing::error!("WhatsApp send failed: {status} — {error_body}");
-            anyhow::bail!("WhatsApp API error: {status}");
+            return Err(api_error(status.as_u16(), &error_body).into());
+        }
+
+        Ok(resp.json().await.unwrap_or_default())
+    }
+
+    /// Send an approved template with `params` for its body's `{{1}}`,
+    /// `{{2}}`, ... Templates are delivered outside the session window.
+    pub async fn send_template(
+        &self,
+        name: &str,
+        language: &str,
+        params: &[&str],
+        recipient: &str,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.post_message(recipient, template_message(name, language, params))
+            .await
+    }
+
+    /// Send a text message; returns the API's response body. Outside the
+    /// session window the text goes through the session template.
+    async fn post_text(&self, message: &str, recipient: &str) -> anyhow::Result<serde_json::Value> {
+        if let Some((name, language)) = &self.session_template {
+            if !session_open(recipient, unix_now()) {
+                return self
+                    .send_template(name, language, &[message], recipient)
+                    .await;
+            }
+        }
+
+        let text = serde_json::json!({
+            "type": "text",
+            "text": {
+                "preview_url": false,
+                "body": message
+            }
+        });
+        match self.post_message(recipient, text).await {
+            Err(e) if is_reengagement_error(&e) => {
+                let Some((name, language)) = &self.session_template else {
+                    anyhow::bail!(
+                        "{e}; the 24-hour session with {recipient} has closed, configure \
+                        session_template to reach them"
+                    );
+                };
+                LAST_INBOUND.lock().remove(&normalize_number(recipient));
+                self.send_template(name, language, &[message], recipient)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Upload media for sending; returns its media id.
+    async fn upload_media(
+        &self,
+        part: reqwest::multipart::Part,
+        mime: &str,
+    ) -> anyhow::Result<String> {
+        let form = reqwest::multipart::Form::new()
+            .text("messaging_product", "whatsapp")
+            .text("type", mime.to_string())
+            .part("file", part);
+        let resp = self
+            .client
+            .post(format!("{GRAPH_API}/{}/media", self.endpoint_id))
+            .bearer_auth(&self.access_token)
+            .multipart(form)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let error_body = resp.text().await.unwrap_or_default();
+            return Err(api_error(status.as_u16(), &error_body).into());
+        }
+        let body: serde_json::Value = resp.json().await?;
+        body["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("WhatsApp media upload returned no id"))
+    }
+
+    /// Upload `bytes` and send them as a `kind` ("image", "audio",
+    /// "document") message.
+    async fn send_media_bytes(
+        &self,
+        kind: &str,
+        bytes: Vec<u8>,
+        file_name: &str,
+        mime: &str,
+        caption: Option<&str>,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(file_name.to_string())
+            .mime_str(mime)?;
+        let media_id = self.upload_media(part, mime).await?;
+        self.post_message(
+            recipient,
+            media_message(kind, &media_id, file_name, caption),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Record the message statuses (`sent`, `delivered`, `read`, `failed`)
+    /// in a webhook payload as receipts. Returns how many were recorded.
+    pub fn record_statuses(&self, payload: &serde_json::Value) -> usize {
+        let statuses = payload["entry"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|entry| entry["changes"].as_array().into_iter().flatten())
+            .flat_map(|change| change["value"]["statuses"].as_array().into_iter().flatten());
+        let mut recorded = 0;
+        for update in statuses {
+            let (Some(platform_id), Some(status)) = (
+                update["id"].as_str(),
+                update["status"].as_str().and_then(DeliveryStatus::parse),
+            ) else {
+                continue;
+            };
+            let reader = update["recipient_id"].as_str().map_or_else(
+                || "unknown".to_string(),
+                |id| format!("+{}", id.trim_start_matches('+')),
+            );
+            let at = update["timestamp"]
+                .as_str()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or_else(unix_now);
+            receipts::record("whatsapp", platform_id, &reader, status, at);
+            recorded += 1;
         }
+        recorded
+    }
+}
+
+fn normalize_number(number: &str) -> String {
+    format!("+{}", number.trim_start_matches('+'))
+}
+
+/// Whether free-form messages to `recipient` can still be delivered. A
+/// number that hasn't written since startup is given the benefit of the
+/// doubt; the API's re-engagement error catches a closed window.
+fn session_open(recipient: &str, now: u64) -> bool {
+    LAST_INBOUND
+        .lock()
+        .get(&normalize_number(recipient))
+        .is_none_or(|&last| now.saturating_sub(last) < SESSION_WINDOW_SECS)
+}
+
+fn api_error(status: u16, body: &str) -> WhatsAppApiError {
+    let parsed: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+    WhatsAppApiError {
+        status,
+        code: parsed["error"]["code"].as_i64(),
+        message: parsed["error"]["message"]
+            .as_str()
+            .unwrap_or(body)
+            .to_string(),
+    }
+}
+
+fn is_reengagement_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<WhatsAppApiError>()
+        .is_some_and(|e| e.code == Some(REENGAGEMENT_ERROR_CODE))
+}
+
+fn template_message(name: &str, language: &str, params: &[&str]) -> serde_json::Value {
+    let mut template = serde_json::json!({
+        "name": name,
+        "language": { "code": language },
+    });
+    if !params.is_empty() {
+        let parameters: Vec<serde_json::Value> = params
+            .iter()
+            .map(|text| serde_json::json!({ "type": "text", "text": text }))
+            .collect();
+        template["components"] = serde_json::json!([{ "type": "body", "parameters": parameters }]);
+    }
+    serde_json::json!({ "type": "template", "template": template })
+}
+
+fn media_message(
+    kind: &str,
+    media_id: &str,
+    file_name: &str,
+    caption: Option<&str>,
+) -> serde_json::Value {
+    let mut media = serde_json::json!({ "id": media_id });
+    if kind == "document" {
+        media["filename"] = file_name.into();
+    }
+    // Audio can't carry a caption
+    if let Some(caption) = caption.filter(|_| kind != "audio") {
+        media["caption"] = caption.into();
+    }
+    serde_json::json!({ "type": kind, kind: media })
+}
+
+/// The media of an inbound image, audio, video or document message, with
+/// its caption.
+fn media_attachment(msg: &serde_json::Value) -> Option<(MediaAttachment, String)> {
+    let (field, kind) = [
+        ("image", MediaKind::Image),
+        ("audio", MediaKind::Audio),
+        ("video", MediaKind::Video),
+        ("document", MediaKind::Document),
+    ]
+    .into_iter()
+    .find(|(field, _)| msg.get(*field).is_some())?;
+    let media = &msg[field];
+    let kind = if kind == MediaKind::Audio && media["voice"].as_bool() == Some(true) {
+        MediaKind::Voice
+    } else {
+        kind
+    };
+    let text = |key: &str| media[key].as_str().map(str::to_string);
+    Some((
+        MediaAttachment {
+            kind,
+            source: text("id")?,
+            mime_type: text("mime_type"),
+            file_name: text("filename"),
+            stored_key: None,
+        },
+        text("caption").unwrap_or_default(),
+    ))
+}
 
+#[async_trait]
+impl Channel for WhatsAppChannel {
+    fn name(&self) -> &str {
+        "whatsapp"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            supports_markdown: false,
+            supports_reactions: true,
+            supports_typing: false,
+            supports_media_markers: false,
+            ..ChannelCapabilities::of(self)
+        }
+    }
+
+    async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+        self.post_text(message, recipient).await.map(|_| ())
+    }
+
+    fn supports_receipts(&self) -> bool {
+        true
+    }
+
+    async fn send_tracked(&self, message: &str, recipient: &str) -> anyhow::Result<String> {
+        let response = self.post_text(message, recipient).await?;
+        let Some(platform_id) = response["messages"][0]["id"].as_str() else {
+            anyhow::bail!("WhatsApp API response has no message id");
+        };
+        Ok(receipts::track("whatsapp", platform_id, recipient, message))
+    }
+
+    fn supports_images(&self) -> bool {
+        true
+    }
+
+    async fn send_image(
+        &self,
+        image: Vec<u8>,
+        file_name: &str,
+        caption: Option<&str>,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        let mime = crate::vision::image_mime_type(None, &image);
+        self.send_media_bytes("image", image, file_name, &mime, caption, recipient)
+            .await
+    }
+
+    fn supports_voice_notes(&self) -> bool {
+        true
+    }
+
+    async fn send_voice_note(
+        &self,
+        audio: Vec<u8>,
+        file_name: &str,
+        recipient: &str,
+    ) -> anyhow::Result<()> {
+        let mime = crate::media_store::mime_for_key(file_name);
+        self.send_media_bytes("audio", audio, file_name, mime, None, recipient)
+            .await
+    }
+
+    fn max_file_size(&self) -> Option<u64> {
+        Some(WHATSAPP_MAX_UPLOAD_BYTES)
+    }
+
+    async fn send_file(
+        &self,
+        recipient: &str,
+        name: &str,
+        mime: &str,
+        size: Option<u64>,
+        reader: FileReader,
+    ) -> anyhow::Result<()> {
+        let body = uploads::streaming_body(reader);
+        let part = match size {
+            Some(len) => reqwest::multipart::Part::stream_with_length(body, len),
+            None => reqwest::multipart::Part::stream(body),
+        }
+        .file_name(name.to_string())
+        .mime_str(mime)?;
+        let media_id = self.upload_media(part, mime).await?;
+        self.post_message(recipient, media_message("document", &media_id, name, None))
+            .await?;
+        tracing::info!("WhatsApp document sent to {recipient}: {name}");
         Ok(())
     }
 
+    /// Attachment sources are media ids; their download URL is looked up
+    /// first and needs the access token too.
+    async fn download_attachment(&self, attachment: &MediaAttachment) -> anyhow::Result<Vec<u8>> {
+        let media: serde_json::Value = self
+            .client
+            .get(format!("{GRAPH_API}/{}", attachment.source))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let url = media["url"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("WhatsApp media lookup returned no url"))?;
+        let bytes = self
+            .client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(bytes.to_vec())
+    }
+
     async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
         // WhatsApp uses webhooks (push-based), not polling.
         // Messages are received via the gateway's /whatsapp endpoint.
@@ -196,7 +592,7 @@ impl Channel for WhatsAppChannel {
 
     async fn health_check(&self) -> bool {
         // Check if we can reach the WhatsApp API
-        let url = format!("https://graph.facebook.com/v18.0/{}", self.endpoint_id);
+        let url = format!("{GRAPH_API}/{}", self.endpoint_id);
 
         self.client
             .get(&url)
@@ -322,7 +718,7 @@ mod tests {
     }
 
     #[test]
-    fn whatsapp_parse_non_text_message_skipped() {
+    fn whatsapp_parse_image_message_becomes_attachment() {
         let ch = WhatsAppChannel::new("tok".into(), "123".into(), "ver".into(), vec!["*".into()]);
         let payload = serde_json::json!({
             "entry": [{
@@ -332,7 +728,7 @@ mod tests {
                             "from": "1234567890",
                             "timestamp": "1699999999",
                             "type": "image",
-                            "image": { "id": "img123" }
+                            "image": { "id": "img123", "mime_type": "image/jpeg", "caption": "my receipt" }
                         }]
                     }
                 }]
@@ -340,7 +736,18 @@ mod tests {
         });
 
         let msgs = ch.parse_webhook_payload(&payload);
-        assert!(msgs.is_empty(), "Non-text messages should be skipped");
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].content, "my receipt");
+        assert_eq!(
+            msgs[0].attachments,
+            vec![MediaAttachment {
+                kind: MediaKind::Image,
+                source: "img123".into(),
+                mime_type: Some("image/jpeg".into()),
+                file_name: None,
+                stored_key: None,
+            }]
+        );
     }
 
     #[test]
@@ -709,7 +1116,35 @@ mod tests {
     }
 
     #[test]
-    fn whatsapp_parse_audio_message_skipped() {
+    fn whatsapp_status_updates_become_receipts() {
+        let ch = make_channel();
+        let id = receipts::track(
+            "whatsapp",
+            "wamid.status-test",
+            "+111",
+            "Maintenance at 6pm",
+        );
+        let payload = serde_json::json!({
+            "entry": [{
+                "changes": [{
+                    "value": {
+                        "statuses": [
+                            { "id": "wamid.status-test", "status": "delivered", "timestamp": "100", "recipient_id": "111" },
+                            { "id": "wamid.status-test", "status": "read", "timestamp": "160", "recipient_id": "111" },
+                            { "id": "wamid.status-test", "status": "deleted", "recipient_id": "111" }
+                        ]
+                    }
+                }]
+            }]
+        });
+        assert_eq!(ch.record_statuses(&payload), 2);
+        let stats = receipts::stats(&id).unwrap();
+        assert_eq!((stats.delivered, stats.read), (1, 1));
+        assert_eq!(stats.first_read_at, Some(160));
+    }
+
+    #[test]
+    fn whatsapp_parse_voice_message_becomes_attachment() {
         let ch = WhatsAppChannel::new("tok".into(), "123".into(), "ver".into(), vec!["*".into()]);
         let payload = serde_json::json!({
             "entry": [{
@@ -719,18 +1154,21 @@ mod tests {
                             "from": "111",
                             "timestamp": "1",
                             "type": "audio",
-                            "audio": { "id": "audio123", "mime_type": "audio/ogg" }
+                            "audio": { "id": "audio123", "mime_type": "audio/ogg", "voice": true }
                         }]
                     }
                 }]
             }]
         });
         let msgs = ch.parse_webhook_payload(&payload);
-        assert!(msgs.is_empty());
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].content, "");
+        assert_eq!(msgs[0].attachments[0].kind, MediaKind::Voice);
+        assert_eq!(msgs[0].attachments[0].source, "audio123");
     }
 
     #[test]
-    fn whatsapp_parse_video_message_skipped() {
+    fn whatsapp_parse_video_message_becomes_attachment() {
         let ch = WhatsAppChannel::new("tok".into(), "123".into(), "ver".into(), vec!["*".into()]);
         let payload = serde_json::json!({
             "entry": [{
@@ -747,11 +1185,11 @@ mod tests {
             }]
         });
         let msgs = ch.parse_webhook_payload(&payload);
-        assert!(msgs.is_empty());
+        assert_eq!(msgs[0].attachments[0].kind, MediaKind::Video);
     }
 
     #[test]
-    fn whatsapp_parse_document_message_skipped() {
+    fn whatsapp_parse_document_message_becomes_attachment() {
         let ch = WhatsAppChannel::new("tok".into(), "123".into(), "ver".into(), vec!["*".into()]);
         let payload = serde_json::json!({
             "entry": [{
@@ -768,7 +1206,11 @@ mod tests {
             }]
         });
         let msgs = ch.parse_webhook_payload(&payload);
-        assert!(msgs.is_empty());
+        assert_eq!(msgs[0].attachments[0].kind, MediaKind::Document);
+        assert_eq!(
+            msgs[0].attachments[0].file_name.as_deref(),
+            Some("file.pdf")
+        );
     }
 
     #[test]
@@ -1108,4 +1550,49 @@ mod tests {
             "<script>alert('xss')</script> & \"quotes\" 'apostrophe'"
         );
     }
+
+    #[test]
+    fn whatsapp_session_window_follows_inbound_messages() {
+        let ch = make_channel();
+        let now = unix_now();
+        assert!(
+            session_open("+1234567890", now),
+            "unknown numbers get a try"
+        );
+        let payload = serde_json::json!({
+            "entry": [{ "changes": [{ "value": { "messages": [{
+                "from": "1234567890",
+                "type": "text",
+                "text": { "body": "hi" }
+            }] } }] }]
+        });
+        ch.parse_webhook_payload(&payload);
+        assert!(session_open("1234567890", now));
+        assert!(!session_open("+1234567890", now + SESSION_WINDOW_SECS + 1));
+    }
+
+    #[test]
+    fn whatsapp_outbound_message_shapes() {
+        let template = template_message("follow_up", "en_US", &["Your order shipped"]);
+        assert_eq!(template["type"], "template");
+        assert_eq!(template["template"]["language"]["code"], "en_US");
+        assert_eq!(
+            template["template"]["components"][0]["parameters"][0]["text"],
+            "Your order shipped"
+        );
+
+        let document = media_message("document", "media-1", "report.pdf", Some("Q3"));
+        assert_eq!(document["document"]["id"], "media-1");
+        assert_eq!(document["document"]["filename"], "report.pdf");
+        assert_eq!(document["document"]["caption"], "Q3");
+        let audio = media_message("audio", "media-2", "reply.ogg", Some("ignored"));
+        assert!(audio["audio"].get("caption").is_none());
+
+        let closed = api_error(
+            400,
+            r#"{"error":{"message":"Re-engagement message","code":131047}}"#,
+        );
+        assert!(is_reengagement_error(&closed.into()));
+        assert!(!is_reengagement_error(&api_error(500, "oops").into()));
+    }
 }
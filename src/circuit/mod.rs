@@ -0,0 +1,296 @@
+//! Circuit breakers around upstream APIs (`[reliability.circuit_breaker]`).
+//!
+//! Each dependency — an LLM provider (`provider:<name>`) or a channel's send
+//! API (`send:<channel>`) — has a [`CircuitBreaker`]. After
+//! `failure_threshold` consecutive failures it opens, and calls fail at once
+//! instead of waiting out another timeout. Once `open_secs` pass it lets
+//! `half_open_probes` calls through: a success closes it again, a failure
+//! reopens it. State changes are reported to the health registry as
+//! `circuit:<dependency>`, and [`snapshot`] lists every breaker for the
+//! `/health` and `/admin/circuits` endpoints.
+
+use crate::config::CircuitBreakerConfig;
+use anyhow::{bail, Result};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// A breaker's state and counters, as reported by [`snapshot`].
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitSnapshot {
+    pub dependency: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Times the breaker opened
+    pub opened: u64,
+    /// Calls failed fast while open
+    pub rejected: u64,
+    /// Seconds until an open breaker lets a probe through
+    pub retry_in_secs: Option<u64>,
+}
+
+struct Inner {
+    config: CircuitBreakerConfig,
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probes: u32,
+    opened: u64,
+    rejected: u64,
+}
+
+pub struct CircuitBreaker {
+    dependency: String,
+    inner: Mutex<Inner>,
+}
+
+/// Permission to make one call; report how it went with
+/// [`success`](Self::success) or [`failure`](Self::failure). Dropping it
+/// reports nothing, and frees its probe slot.
+pub struct CircuitPermit {
+    breaker: Arc<CircuitBreaker>,
+    probe: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(dependency: &str, config: CircuitBreakerConfig) -> Self {
+        Self {
+            dependency: dependency.to_string(),
+            inner: Mutex::new(Inner {
+                config,
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probes: 0,
+                opened: 0,
+                rejected: 0,
+            }),
+        }
+    }
+
+    /// Ask to make a call, failing fast while the breaker is open.
+    pub fn try_acquire(self: &Arc<Self>) -> Result<CircuitPermit> {
+        let mut inner = self.inner.lock();
+        if !inner.config.enabled {
+            return Ok(self.permit(false));
+        }
+        if inner.state == CircuitState::Open {
+            let open_for = Duration::from_secs(inner.config.open_secs);
+            let opened_at = inner.opened_at.unwrap_or_else(Instant::now);
+            if opened_at.elapsed() < open_for {
+                inner.rejected += 1;
+                let retry_in = open_for.saturating_sub(opened_at.elapsed()).as_secs() + 1;
+                bail!(
+                    "{} is unavailable (circuit open); retrying in {retry_in}s",
+                    self.dependency
+                );
+            }
+            tracing::info!("Circuit for {} is half-open; probing", self.dependency);
+            inner.state = CircuitState::HalfOpen;
+            inner.probes = 0;
+        }
+        if inner.state == CircuitState::HalfOpen {
+            if inner.probes >= inner.config.half_open_probes.max(1) {
+                inner.rejected += 1;
+                bail!(
+                    "{} is unavailable (circuit half-open, probe in flight)",
+                    self.dependency
+                );
+            }
+            inner.probes += 1;
+            return Ok(self.permit(true));
+        }
+        Ok(self.permit(false))
+    }
+
+    fn permit(self: &Arc<Self>, probe: bool) -> CircuitPermit {
+        CircuitPermit {
+            breaker: Arc::clone(self),
+            probe,
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut inner = self.inner.lock();
+        if success {
+            inner.consecutive_failures = 0;
+            if inner.state != CircuitState::Closed {
+                inner.state = CircuitState::Closed;
+                inner.opened_at = None;
+                tracing::info!("Circuit for {} closed", self.dependency);
+                crate::health::mark_component_ok(&self.health_component());
+            }
+            return;
+        }
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let reopen = inner.state == CircuitState::HalfOpen;
+        let trip = inner.state == CircuitState::Closed
+            && inner.consecutive_failures >= inner.config.failure_threshold.max(1);
+        if inner.config.enabled && (reopen || trip) {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.opened += 1;
+            let failures = inner.consecutive_failures;
+            tracing::warn!(
+                "Circuit for {} opened after {failures} consecutive failures",
+                self.dependency
+            );
+            crate::health::mark_component_error(
+                &self.health_component(),
+                format!("circuit open after {failures} consecutive failures"),
+            );
+        }
+    }
+
+    fn health_component(&self) -> String {
+        format!("circuit:{}", self.dependency)
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().state
+    }
+
+    pub fn snapshot(&self) -> CircuitSnapshot {
+        let inner = self.inner.lock();
+        let retry_in_secs = match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(at)) => Some(
+                Duration::from_secs(inner.config.open_secs)
+                    .saturating_sub(at.elapsed())
+                    .as_secs(),
+            ),
+            _ => None,
+        };
+        CircuitSnapshot {
+            dependency: self.dependency.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            opened: inner.opened,
+            rejected: inner.rejected,
+            retry_in_secs,
+        }
+    }
+}
+
+impl CircuitPermit {
+    pub fn success(self) {
+        self.breaker.record(true);
+    }
+
+    pub fn failure(self) {
+        self.breaker.record(false);
+    }
+}
+
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if self.probe {
+            let mut inner = self.breaker.inner.lock();
+            inner.probes = inner.probes.saturating_sub(1);
+        }
+    }
+}
+
+static CONFIG: LazyLock<RwLock<CircuitBreakerConfig>> =
+    LazyLock::new(|| RwLock::new(CircuitBreakerConfig::default()));
+
+static BREAKERS: LazyLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Apply `config` to every breaker, existing and future.
+pub fn configure(config: &CircuitBreakerConfig) {
+    *CONFIG.write() = config.clone();
+    for breaker in BREAKERS.lock().values() {
+        breaker.inner.lock().config = config.clone();
+    }
+}
+
+/// The breaker of `dependency`, created on first use.
+pub fn breaker(dependency: &str) -> Arc<CircuitBreaker> {
+    let mut breakers = BREAKERS.lock();
+    Arc::clone(
+        breakers
+            .entry(dependency.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(dependency, CONFIG.read().clone()))),
+    )
+}
+
+/// Run `call` through the breaker of `dependency`; any error counts as a
+/// failure.
+pub async fn call<T>(dependency: &str, call: impl Future<Output = Result<T>>) -> Result<T> {
+    let permit = breaker(dependency).try_acquire()?;
+    let result = call.await;
+    match &result {
+        Ok(_) => permit.success(),
+        Err(_) => permit.failure(),
+    }
+    result
+}
+
+/// Every breaker, by dependency name.
+pub fn snapshot() -> Vec<CircuitSnapshot> {
+    let mut circuits: Vec<_> = BREAKERS.lock().values().map(|b| b.snapshot()).collect();
+    circuits.sort_by(|a, b| a.dependency.cmp(&b.dependency));
+    circuits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_breaker(open_secs: u64) -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(
+            &format!("test-{}", uuid::Uuid::new_v4()),
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_secs,
+                ..CircuitBreakerConfig::default()
+            },
+        ))
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = test_breaker(60);
+        breaker.try_acquire().unwrap().failure();
+        breaker.try_acquire().unwrap().success();
+        breaker.try_acquire().unwrap().failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.try_acquire().unwrap().failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let err = breaker.try_acquire().err().unwrap();
+        assert!(err.to_string().contains("circuit open"), "{err}");
+        let snapshot = breaker.snapshot();
+        assert_eq!((snapshot.opened, snapshot.rejected), (1, 1));
+        assert!(snapshot.retry_in_secs.is_some());
+    }
+
+    #[test]
+    fn half_open_probe_closes_or_reopens() {
+        let breaker = test_breaker(0);
+        breaker.try_acquire().unwrap().failure();
+        breaker.try_acquire().unwrap().failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let probe = breaker.try_acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire().is_err(), "one probe at a time");
+        probe.failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        breaker.try_acquire().unwrap().success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.snapshot().opened, 2);
+    }
+}
@@ -0,0 +1,155 @@
+//! Lease on an etcd key, over the v3 JSON gateway: a key put with an etcd
+//! lease only if it doesn't exist yet, kept alive while this instance
+//! leads and revoked (deleting the key) when it resigns.
+
+use super::Lease;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+
+pub(super) struct EtcdLease {
+    client: reqwest::Client,
+    base_url: String,
+    key: String,
+    instance: String,
+    ttl_secs: u64,
+    /// The etcd lease the key was put with, while this instance holds it
+    lease_id: Mutex<Option<i64>>,
+}
+
+/// An etcd int64, which the gateway encodes as a JSON string.
+fn int(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_i64(),
+    }
+}
+
+impl EtcdLease {
+    pub(super) fn new(url: &str, key: &str, instance: &str, lease_secs: u64) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: url.trim_end_matches('/').to_string(),
+            key: key.to_string(),
+            instance: instance.to_string(),
+            ttl_secs: lease_secs,
+            lease_id: Mutex::new(None),
+        }
+    }
+
+    async fn call(&self, path: &str, body: Value) -> Result<Value> {
+        let resp = self
+            .client
+            .post(format!("{}/v3/{path}", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to reach etcd")?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            bail!("etcd {path} failed ({status}): {text}");
+        }
+        Ok(resp.json().await?)
+    }
+
+    async fn grant(&self) -> Result<i64> {
+        let granted = self
+            .call("lease/grant", json!({ "TTL": self.ttl_secs }))
+            .await?;
+        int(&granted["ID"]).context("etcd granted a lease without an ID")
+    }
+
+    async fn revoke(&self, id: i64) -> Result<()> {
+        self.call("lease/revoke", json!({ "ID": id.to_string() }))
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Lease for EtcdLease {
+    async fn acquire(&self) -> Result<bool> {
+        let id = self.grant().await?;
+        let key = STANDARD.encode(&self.key);
+        let txn = self
+            .call(
+                "kv/txn",
+                json!({
+                    "compare": [{
+                        "key": key,
+                        "target": "CREATE",
+                        "create_revision": "0",
+                    }],
+                    "success": [{
+                        "request_put": {
+                            "key": key,
+                            "value": STANDARD.encode(&self.instance),
+                            "lease": id.to_string(),
+                        }
+                    }],
+                    "failure": [{ "request_range": { "key": key } }],
+                }),
+            )
+            .await?;
+        if txn["succeeded"].as_bool() == Some(true) {
+            *self.lease_id.lock() = Some(id);
+            return Ok(true);
+        }
+        let _ = self.revoke(id).await;
+
+        // Still ours from before a restart of the campaign
+        let holder = &txn["responses"][0]["response_range"]["kvs"][0];
+        let previous = *self.lease_id.lock();
+        let ours = holder["value"]
+            .as_str()
+            .and_then(|v| STANDARD.decode(v).ok())
+            == Some(self.instance.clone().into_bytes());
+        if ours && previous.is_some() && int(&holder["lease"]) == previous {
+            return self.renew().await;
+        }
+        Ok(false)
+    }
+
+    async fn renew(&self) -> Result<bool> {
+        let Some(id) = *self.lease_id.lock() else {
+            return Ok(false);
+        };
+        let kept = self
+            .call("lease/keepalive", json!({ "ID": id.to_string() }))
+            .await?;
+        // An expired lease comes back without a TTL
+        let alive = int(&kept["result"]["TTL"]).is_some_and(|ttl| ttl > 0);
+        if !alive {
+            *self.lease_id.lock() = None;
+        }
+        Ok(alive)
+    }
+
+    async fn release(&self) -> Result<()> {
+        let id = self.lease_id.lock().take();
+        match id {
+            Some(id) => self.revoke(id).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_gateway_integers() {
+        assert_eq!(
+            int(&json!("7587862048913341193")),
+            Some(7_587_862_048_913_341_193)
+        );
+        assert_eq!(int(&json!(15)), Some(15));
+        assert_eq!(int(&json!(null)), None);
+        assert_eq!(int(&json!("soon")), None);
+    }
+}
@@ -0,0 +1,303 @@
+//! Leader election between redundant instances (`[cluster]`).
+//!
+//! Every instance configured with the same key competes for a lease in
+//! Redis or etcd. The holder is the leader and renews the lease every third
+//! of its lifetime; the others retry on the same schedule and take over
+//! once it runs out. A leader that cannot renew for half the lease steps
+//! down on its own, before anyone else can acquire it, so two instances
+//! never lead at once as long as clocks tick at the same rate.
+//!
+//! The channel runtime follows [`LeaderElection::subscribe`]: listeners
+//! (and the gateway connections they hold) run only on the leader.
+
+mod etcd;
+#[cfg(feature = "bus-redis")]
+mod redis_lease;
+
+use crate::config::schema::{ClusterBackend, ClusterConfig};
+use crate::security::ManagedSecret;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Shortest lease accepted, in seconds.
+pub const MIN_LEASE_SECS: u64 = 3;
+
+/// A lease in the coordination store, held by at most one instance.
+#[async_trait]
+trait Lease: Send + Sync {
+    /// Take the lease if nobody holds it. `true` when this instance holds
+    /// it afterwards.
+    async fn acquire(&self) -> Result<bool>;
+
+    /// Extend the lease. `false` when it is no longer this instance's.
+    async fn renew(&self) -> Result<bool>;
+
+    /// Give the lease up if this instance holds it.
+    async fn release(&self) -> Result<()>;
+}
+
+pub struct LeaderElection {
+    lease: Box<dyn Lease>,
+    instance: String,
+    ttl: Duration,
+    leader: watch::Sender<bool>,
+}
+
+impl LeaderElection {
+    /// Connect to the configured store, or `None` when clustering is off.
+    /// `resolve` turns the configured URL reference into a managed secret.
+    pub async fn connect(
+        config: &ClusterConfig,
+        resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Option<Arc<Self>>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.lease_secs < MIN_LEASE_SECS {
+            bail!("[cluster] lease_secs must be at least {MIN_LEASE_SECS}");
+        }
+        let raw_url = config
+            .url
+            .as_deref()
+            .unwrap_or_else(|| config.backend.default_url());
+        let url = resolve(raw_url)?.get().await?;
+        let instance = config.instance_id.clone().unwrap_or_else(|| {
+            hostname::get().map_or_else(|_| "zeroclaw".into(), |h| h.to_string_lossy().to_string())
+        });
+        let lease: Box<dyn Lease> = match config.backend {
+            #[cfg(feature = "bus-redis")]
+            ClusterBackend::Redis => Box::new(
+                redis_lease::RedisLease::connect(&url, &config.key, &instance, config.lease_secs)
+                    .await?,
+            ),
+            #[cfg(not(feature = "bus-redis"))]
+            ClusterBackend::Redis => {
+                bail!("[cluster] backend \"redis\" needs a build with --features bus-redis")
+            }
+            ClusterBackend::Etcd => Box::new(etcd::EtcdLease::new(
+                &url,
+                &config.key,
+                &instance,
+                config.lease_secs,
+            )),
+        };
+        Ok(Some(Arc::new(Self {
+            lease,
+            instance,
+            ttl: Duration::from_secs(config.lease_secs),
+            leader: watch::channel(false).0,
+        })))
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.leader.borrow()
+    }
+
+    /// Follow leadership changes; the value is whether this instance leads.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.leader.subscribe()
+    }
+
+    /// Campaign for the lease and keep it while this instance leads.
+    /// Runs until aborted.
+    pub async fn run(self: Arc<Self>) {
+        let tick = self.ttl / 3;
+        let mut renewed = Instant::now();
+        loop {
+            if self.is_leader() {
+                // Every renewal is bounded by the time left before half the
+                // lease has run out; past that, step down without waiting on
+                // the store.
+                let deadline = renewed + self.ttl / 2;
+                let left = deadline.saturating_duration_since(Instant::now());
+                let outcome = if left.is_zero() {
+                    None
+                } else {
+                    tokio::time::timeout(left.min(tick), self.lease.renew())
+                        .await
+                        .ok()
+                };
+                match outcome {
+                    Some(Ok(true)) => renewed = Instant::now(),
+                    Some(Ok(false)) => self.step_down("the lease was taken over"),
+                    Some(Err(e)) if Instant::now() >= deadline => {
+                        self.step_down(&format!("cannot renew the lease: {e}"));
+                    }
+                    None if Instant::now() >= deadline => {
+                        self.step_down("renewing the lease timed out");
+                    }
+                    Some(Err(e)) => tracing::warn!("Cluster: lease renewal failed: {e}"),
+                    None => tracing::warn!("Cluster: lease renewal timed out"),
+                }
+            } else {
+                match tokio::time::timeout(tick, self.lease.acquire()).await {
+                    Ok(Ok(true)) => {
+                        renewed = Instant::now();
+                        tracing::info!("Cluster: {} is now the leader", self.instance);
+                        self.leader.send_replace(true);
+                    }
+                    Ok(Ok(false)) => {}
+                    Ok(Err(e)) => tracing::warn!("Cluster: campaign failed: {e}"),
+                    Err(_) => tracing::warn!("Cluster: campaign timed out"),
+                }
+            }
+            let mut wake = Instant::now() + tick;
+            if self.is_leader() {
+                wake = wake.min(renewed + self.ttl / 2);
+            }
+            tokio::time::sleep_until(wake).await;
+        }
+    }
+
+    fn step_down(&self, reason: &str) {
+        tracing::warn!(
+            "Cluster: {} is no longer the leader: {reason}",
+            self.instance
+        );
+        self.leader.send_replace(false);
+    }
+
+    /// Stop leading and release the lease, so a follower takes over without
+    /// waiting for it to run out.
+    pub async fn resign(&self) {
+        if !self.leader.send_replace(false) {
+            return;
+        }
+        match tokio::time::timeout(self.ttl / 3, self.lease.release()).await {
+            Ok(Ok(())) => tracing::info!("Cluster: {} resigned", self.instance),
+            Ok(Err(e)) => tracing::warn!("Cluster: releasing the lease failed: {e}"),
+            Err(_) => tracing::warn!("Cluster: releasing the lease timed out"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    /// A lease shared in memory between elections, like the real store.
+    #[derive(Clone, Default)]
+    struct SharedLease(Arc<Mutex<Option<String>>>);
+
+    struct MemoryLease {
+        store: SharedLease,
+        instance: String,
+    }
+
+    #[async_trait]
+    impl Lease for MemoryLease {
+        async fn acquire(&self) -> Result<bool> {
+            let mut holder = self.store.0.lock();
+            let holder = holder.get_or_insert_with(|| self.instance.clone());
+            Ok(*holder == self.instance)
+        }
+
+        async fn renew(&self) -> Result<bool> {
+            Ok(self.store.0.lock().as_deref() == Some(self.instance.as_str()))
+        }
+
+        async fn release(&self) -> Result<()> {
+            let mut holder = self.store.0.lock();
+            if holder.as_deref() == Some(self.instance.as_str()) {
+                *holder = None;
+            }
+            Ok(())
+        }
+    }
+
+    /// A lease that can be taken but whose renewals never answer, like a
+    /// store that stopped responding.
+    struct HangingLease;
+
+    #[async_trait]
+    impl Lease for HangingLease {
+        async fn acquire(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn renew(&self) -> Result<bool> {
+            std::future::pending().await
+        }
+
+        async fn release(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn election_with(lease: Box<dyn Lease>, instance: &str, ttl: Duration) -> Arc<LeaderElection> {
+        Arc::new(LeaderElection {
+            lease,
+            instance: instance.into(),
+            ttl,
+            leader: watch::channel(false).0,
+        })
+    }
+
+    fn election(store: &SharedLease, instance: &str) -> Arc<LeaderElection> {
+        let lease = MemoryLease {
+            store: store.clone(),
+            instance: instance.into(),
+        };
+        election_with(Box::new(lease), instance, Duration::from_millis(300))
+    }
+
+    #[tokio::test]
+    async fn follower_takes_over_when_the_leader_resigns() {
+        let store = SharedLease::default();
+        let first = election(&store, "a");
+        let second = election(&store, "b");
+        let mut first_rx = first.subscribe();
+        let mut second_rx = second.subscribe();
+
+        let first_task = tokio::spawn(Arc::clone(&first).run());
+        first_rx.wait_for(|leads| *leads).await.unwrap();
+        let second_task = tokio::spawn(Arc::clone(&second).run());
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(first.is_leader());
+        assert!(!second.is_leader());
+
+        first_task.abort();
+        first.resign().await;
+        assert!(!first.is_leader());
+        second_rx.wait_for(|leads| *leads).await.unwrap();
+        second_task.abort();
+    }
+
+    #[tokio::test]
+    async fn leader_steps_down_when_the_lease_is_lost() {
+        let store = SharedLease::default();
+        let election = election(&store, "a");
+        let mut rx = election.subscribe();
+        let task = tokio::spawn(Arc::clone(&election).run());
+        rx.wait_for(|leads| *leads).await.unwrap();
+
+        *store.0.lock() = Some("b".into());
+        rx.wait_for(|leads| !*leads).await.unwrap();
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn leader_steps_down_by_half_the_lease_when_renewal_hangs() {
+        let ttl = Duration::from_millis(600);
+        let election = election_with(Box::new(HangingLease), "a", ttl);
+        let mut rx = election.subscribe();
+        let task = tokio::spawn(Arc::clone(&election).run());
+        rx.wait_for(|leads| *leads).await.unwrap();
+        let led = Instant::now();
+
+        rx.wait_for(|leads| !*leads).await.unwrap();
+        // Half the lease plus scheduling slack, well short of the sleep and
+        // the full renewal timeout stacked on top of each other.
+        assert!(led.elapsed() < ttl / 2 + Duration::from_millis(70));
+        task.abort();
+    }
+}
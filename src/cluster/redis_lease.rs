@@ -0,0 +1,89 @@
+//! Lease on a Redis key: `SET NX PX` to acquire, and scripts that only
+//! extend or delete the key while it still names this instance.
+
+use super::Lease;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+
+const RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+pub(super) struct RedisLease {
+    manager: ConnectionManager,
+    key: String,
+    instance: String,
+    ttl_ms: u64,
+}
+
+impl RedisLease {
+    pub(super) async fn connect(
+        url: &str,
+        key: &str,
+        instance: &str,
+        lease_secs: u64,
+    ) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid [cluster] url")?;
+        let manager = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to the cluster store")?;
+        Ok(Self {
+            manager,
+            key: key.to_string(),
+            instance: instance.to_string(),
+            ttl_ms: lease_secs.saturating_mul(1000),
+        })
+    }
+}
+
+#[async_trait]
+impl Lease for RedisLease {
+    async fn acquire(&self) -> Result<bool> {
+        let mut conn = self.manager.clone();
+        let set: Option<String> = redis::cmd("SET")
+            .arg(&self.key)
+            .arg(&self.instance)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl_ms)
+            .query_async(&mut conn)
+            .await?;
+        if set.is_some() {
+            return Ok(true);
+        }
+        // Still ours from before a restart of the campaign
+        self.renew().await
+    }
+
+    async fn renew(&self) -> Result<bool> {
+        let mut conn = self.manager.clone();
+        let extended: i64 = redis::Script::new(RENEW_SCRIPT)
+            .key(&self.key)
+            .arg(&self.instance)
+            .arg(self.ttl_ms)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(extended == 1)
+    }
+
+    async fn release(&self) -> Result<()> {
+        let mut conn = self.manager.clone();
+        let _: i64 = redis::Script::new(RELEASE_SCRIPT)
+            .key(&self.key)
+            .arg(&self.instance)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,15 +1,29 @@
 pub mod schema;
+pub mod validate;
 
 #[allow(unused_imports)]
 pub use schema::{
-    AgentConfig, AuditConfig, AutonomyConfig, BrowserComputerUseConfig, BrowserConfig,
-    ChannelsConfig, ComposioConfig, Config, CostConfig, CronConfig, DelegateAgentConfig,
-    DiscordConfig, DockerRuntimeConfig, GatewayConfig, HardwareConfig, HardwareTransport,
-    HeartbeatConfig, HttpRequestConfig, IMessageConfig, IdentityConfig, LarkConfig, MatrixConfig,
-    MemoryConfig, ModelRouteConfig, ObservabilityConfig, PeripheralBoardConfig, PeripheralsConfig,
-    ReliabilityConfig, ResourceLimitsConfig, RuntimeConfig, SandboxBackend, SandboxConfig,
-    SchedulerConfig, SecretsConfig, SecurityConfig, SlackConfig, TelegramConfig, TunnelConfig,
-    WebhookConfig,
+    AdminConfig, AgentConfig, AlertmanagerConfig, AnalyticsConfig, AnnouncementsConfig,
+    ApiEnvironment, AuditConfig, AutonomyConfig, BrowserComputerUseConfig, BrowserConfig,
+    BusBackend, BusConfig, BusRole, ChannelAccountConfig, ChannelAccountKind, ChannelTagsConfig,
+    ChannelTlsConfig, ChannelsConfig, CircuitBreakerConfig, ClusterBackend, ClusterConfig,
+    ComposioConfig, Config, CostConfig, CronConfig, DelegateAgentConfig, DigestConfig,
+    DiscordConfig, DispatchConfig, DockerRuntimeConfig, DryRunConfig, EventWebhookConfig,
+    FailoverConfig, FeedSubscriptionConfig, FeedsConfig, ForgeWebhookConfig, GatewayCompression,
+    GatewayConfig, GroupsConfig, GrpcConfig, HardwareConfig, HardwareTransport, HeartbeatConfig,
+    HooksConfig, HttpClientConfig, HttpClientsConfig, HttpRequestConfig, HttpVersionPreference,
+    IMessageConfig, IdentityConfig, IdentityLinkingConfig, ImageGenerationConfig, IngressConfig,
+    IngressTargetConfig, KnowledgeConfig, KvConfig, LarkConfig, LoggingConfig, MatrixConfig,
+    MediaBackendKind, MediaStoreConfig, MemoryConfig, MiddlewareConfig, ModelRouteConfig,
+    ModerationAction, MonitorAlertConfig, MonitorCheckConfig, MonitorConfig, NetworkTimeoutsConfig,
+    NotifyConfig, OAuthClientConfig, ObservabilityConfig, OpsConfig, PeripheralBoardConfig,
+    PeripheralsConfig, PersonaConfig, PersonasConfig, PluginsConfig, PreferenceSettings,
+    PreferencesConfig, PriorityConfig, QQConfig, QuietHoursConfig, QuietHoursRule, QuotaConfig, QuotaLimits,
+    ReliabilityConfig, RemindersConfig, ReplyVerbosity, ResourceLimitsConfig, RolesConfig,
+    RuntimeConfig, S3StorageConfig, SandboxBackend, SandboxConfig, SchedulerConfig, SecretsConfig,
+    SecurityConfig, ShadowChannelConfig, SlackConfig, StorageEncryptionConfig, StreamingConfig,
+    TelegramConfig, TlsMinVersion, TranscriptionConfig, TtsConfig, TtsMode, TunnelConfig,
+    UploadsConfig, VaultSecretsConfig, VisionConfig, WebSearchConfig, WebhookConfig,
 };
 
 #[cfg(test)]
@@ -38,6 +52,8 @@ mod tests {
             allowed_users: vec![],
             listen_to_bots: false,
             mention_only: false,
+            heartbeat_jitter: true,
+            compression: GatewayCompression::None,
         };
 
         let lark = LarkConfig {
@@ -64,12 +64,111 @@ pub struct Config {
     #[serde(default)]
     pub gateway: GatewayConfig,
 
+    #[serde(default)]
+    pub admin: AdminConfig,
+
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+
+    #[serde(default)]
+    pub bus: BusConfig,
+
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+
+    #[serde(default)]
+    pub middleware: MiddlewareConfig,
+
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+
+    #[serde(default)]
+    pub tts: TtsConfig,
+
+    #[serde(default)]
+    pub vision: VisionConfig,
+
+    #[serde(default)]
+    pub image_generation: ImageGenerationConfig,
+
+    #[serde(default)]
+    pub knowledge: KnowledgeConfig,
+
+    #[serde(default)]
+    pub web_search: WebSearchConfig,
+
+    #[serde(default)]
+    pub feeds: FeedsConfig,
+
+    #[serde(default)]
+    pub ingress: IngressConfig,
+
+    /// Lifecycle events delivered to HTTP endpoints
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+
+    /// Log format, levels, redaction and file rotation
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// Operator notifications about internal failures
+    #[serde(default)]
+    pub ops: OpsConfig,
+
+    /// Usage analytics and the weekly digest to the ops target
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+
+    /// Where files too large for a channel are uploaded instead
+    #[serde(default)]
+    pub uploads: UploadsConfig,
+
+    /// Persistent, content-addressed storage for attachments and generated
+    /// media
+    #[serde(default)]
+    pub media_store: MediaStoreConfig,
+
+    /// Namespaced key-value state for plugins and command handlers
+    #[serde(default)]
+    pub kv: KvConfig,
+
+    /// Per-user settings changed with `/set`, and their defaults
+    #[serde(default)]
+    pub preferences: PreferencesConfig,
+
+    /// `/remind` reminders delivered back to the chat they were set in
+    #[serde(default)]
+    pub reminders: RemindersConfig,
+
+    /// Per-group overrides changed by group admins with `/group`
+    #[serde(default)]
+    pub groups: GroupsConfig,
+
+    /// Named agent personas, per channel and switchable with `/persona`
+    #[serde(default)]
+    pub personas: PersonasConfig,
+
     #[serde(default)]
     pub composio: ComposioConfig,
 
     #[serde(default)]
     pub secrets: SecretsConfig,
 
+    /// Sandboxing, resource limits, audit log, admin roles and encryption
+    /// at rest
+    #[serde(default)]
+    pub security: SecurityConfig,
+
+    /// OAuth2 clients channels sign in with (`zeroclaw oauth login <name>`)
+    #[serde(default)]
+    pub oauth: HashMap<String, OAuthClientConfig>,
+
     #[serde(default)]
     pub browser: BrowserConfig,
 
@@ -79,9 +178,15 @@ pub struct Config {
     #[serde(default)]
     pub identity: IdentityConfig,
 
+    #[serde(default)]
+    pub identity_linking: IdentityLinkingConfig,
+
     #[serde(default)]
     pub cost: CostConfig,
 
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+
     #[serde(default)]
     pub peripherals: PeripheralsConfig,
 
@@ -124,20 +229,15 @@ fn default_max_depth() -> u32 {
 // ── Hardware Config (wizard-driven) ─────────────────────────────
 
 /// Hardware transport mode.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HardwareTransport {
+    #[default]
     None,
     Native,
     Serial,
     Probe,
 }
 
-impl Default for HardwareTransport {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl std::fmt::Display for HardwareTransport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -264,6 +364,33 @@ impl Default for IdentityConfig {
     }
 }
 
+// ── User identity linking ────────────────────────────────────────
+
+/// Link one person's accounts across channels into a single profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityLinkingConfig {
+    /// Enable `/link`, `/unlink` and `/whoami` and key memory, ACLs and
+    /// quotas by profile (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Lifetime of a link code in seconds (default: 600)
+    #[serde(default = "default_link_code_ttl_secs")]
+    pub code_ttl_secs: u64,
+}
+
+fn default_link_code_ttl_secs() -> u64 {
+    600
+}
+
+impl Default for IdentityLinkingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            code_ttl_secs: default_link_code_ttl_secs(),
+        }
+    }
+}
+
 // ── Cost tracking and budget enforcement ───────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -405,9 +532,61 @@ fn get_default_pricing() -> std::collections::HashMap<String, ModelPricing> {
     prices
 }
 
-// ── Peripherals (hardware: STM32, RPi GPIO, etc.) ────────────────────────
+// ── Per-user usage quotas ───────────────────────────────────────────
+
+/// Daily limits for one user or one channel. Unset limits are unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    /// Messages answered per day
+    #[serde(default)]
+    pub messages: Option<u64>,
+    /// LLM tokens (input + output) per day
+    #[serde(default)]
+    pub tokens: Option<u64>,
+    /// Spend in USD per day, priced with `[cost.prices]`
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+}
 
+/// Usage accounting and daily quotas for channel conversations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// Track usage and enforce quotas (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Limits for each user; a linked profile counts as one user
+    #[serde(default)]
+    pub per_user: QuotaLimits,
+    /// Limits for each channel as a whole
+    #[serde(default)]
+    pub per_channel: QuotaLimits,
+    /// Users never limited, as sender ids or `channel:sender`
+    #[serde(default)]
+    pub exempt: Vec<String>,
+    /// Reply when a quota is used up; `{limit}` names the limit
+    #[serde(default = "default_quota_exceeded_message")]
+    pub exceeded_message: String,
+}
+
+fn default_quota_exceeded_message() -> String {
+    "🙏 Sorry, {limit} has been reached for today. It resets at midnight UTC.".into()
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            per_user: QuotaLimits::default(),
+            per_channel: QuotaLimits::default(),
+            exempt: Vec::new(),
+            exceeded_message: default_quota_exceeded_message(),
+        }
+    }
+}
+
+// ── Peripherals (hardware: STM32, RPi GPIO, etc.) ────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PeripheralsConfig {
     /// Enable peripheral support (boards become agent tools)
     #[serde(default)]
@@ -444,16 +623,6 @@ fn default_peripheral_baud() -> u32 {
     115_200
 }
 
-impl Default for PeripheralsConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            boards: Vec::new(),
-            datasheet_dir: None,
-        }
-    }
-}
-
 impl Default for PeripheralBoardConfig {
     fn default() -> Self {
         Self {
@@ -537,381 +706,2186 @@ impl Default for GatewayConfig {
     }
 }
 
-// ── Composio (managed tool surface) ─────────────────────────────
+// ── Admin control API ───────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComposioConfig {
-    /// Enable Composio integration for 1000+ OAuth tools
+pub struct AdminConfig {
+    /// Serve the admin API alongside the channel runtime (default: false)
     #[serde(default)]
     pub enabled: bool,
-    /// Composio API key (stored encrypted when secrets.encrypt = true)
+    /// Admin API host (default: 127.0.0.1)
+    #[serde(default = "default_gateway_host")]
+    pub host: String,
+    /// Admin API port (default: 3001)
+    #[serde(default = "default_admin_port")]
+    pub port: u16,
+    /// Bearer token required on every request. Accepts `env:`/`file:`/
+    /// `vault:`/`keyring:` references. Required for non-loopback hosts
+    /// unless `username` and `password` are set.
     #[serde(default)]
-    pub api_key: Option<String>,
-    /// Default entity ID for multi-user setups
-    #[serde(default = "default_entity_id")]
-    pub entity_id: String,
+    pub token: Option<String>,
+    /// Basic auth user accepted instead of the token, e.g. by a browser
+    /// opening the dashboard. Needs `password`.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Basic auth password. Accepts the same references as `token`.
+    /// Sent in the clear unless a TLS proxy sits in front.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Serve the operator dashboard at `/dashboard` (default: false)
+    #[serde(default)]
+    pub dashboard: bool,
 }
 
-fn default_entity_id() -> String {
-    "default".into()
+fn default_admin_port() -> u16 {
+    3001
 }
 
-impl Default for ComposioConfig {
+impl Default for AdminConfig {
     fn default() -> Self {
         Self {
             enabled: false,
-            api_key: None,
-            entity_id: default_entity_id(),
+            host: default_gateway_host(),
+            port: default_admin_port(),
+            token: None,
+            username: None,
+            password: None,
+            dashboard: false,
         }
     }
 }
 
-// ── Secrets (encrypted credential store) ────────────────────────
+// ── gRPC API ────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SecretsConfig {
-    /// Enable encryption for API keys and tokens in config.toml
-    #[serde(default = "default_true")]
-    pub encrypt: bool,
+pub struct GrpcConfig {
+    /// Serve the gRPC API alongside the channel runtime (default: false).
+    /// Requires a build with `--features grpc`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// gRPC host (default: 127.0.0.1)
+    #[serde(default = "default_gateway_host")]
+    pub host: String,
+    /// gRPC port (default: 50051)
+    #[serde(default = "default_grpc_port")]
+    pub port: u16,
+    /// Bearer token expected in the `authorization` metadata. Accepts
+    /// `env:`/`file:`/`vault:`/`keyring:` references. Required for
+    /// non-loopback hosts.
+    #[serde(default)]
+    pub token: Option<String>,
 }
 
-impl Default for SecretsConfig {
-    fn default() -> Self {
-        Self { encrypt: true }
-    }
+fn default_grpc_port() -> u16 {
+    50051
 }
 
-// ── Browser (friendly-service browsing only) ───────────────────
+// ── Message bus ─────────────────────────────────────────────────
 
+/// Message bus between instances (Redis Streams or NATS JetStream):
+/// gateways hold the platform connections, workers run the agent.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BrowserComputerUseConfig {
-    /// Sidecar endpoint for computer-use actions (OS-level mouse/keyboard/screenshot)
-    #[serde(default = "default_browser_computer_use_endpoint")]
-    pub endpoint: String,
-    /// Optional bearer token for computer-use sidecar
+pub struct BusConfig {
+    /// Route messages through the bus instead of handling them in this
+    /// process (default: false)
     #[serde(default)]
-    pub api_key: Option<String>,
-    /// Per-action request timeout in milliseconds
-    #[serde(default = "default_browser_computer_use_timeout_ms")]
-    pub timeout_ms: u64,
-    /// Allow remote/public endpoint for computer-use sidecar (default: false)
+    pub enabled: bool,
+    /// "gateway" (hold channel connections) | "worker" (run the agent)
     #[serde(default)]
-    pub allow_remote_endpoint: bool,
-    /// Optional window title/process allowlist forwarded to sidecar policy
+    pub role: BusRole,
+    /// "redis" | "nats" (default: "redis"); needs a build with
+    /// `--features bus-redis` or `bus-nats`
     #[serde(default)]
-    pub window_allowlist: Vec<String>,
-    /// Optional X-axis boundary for coordinate-based actions
+    pub backend: BusBackend,
+    /// Server URL (default: `redis://127.0.0.1:6379` or
+    /// `nats://127.0.0.1:4222`). Accepts `env:`/`file:`/`vault:`/`keyring:`
+    /// references.
     #[serde(default)]
-    pub max_coordinate_x: Option<i64>,
-    /// Optional Y-axis boundary for coordinate-based actions
+    pub url: Option<String>,
+    /// Prefix of the Redis keys or NATS subjects and streams, so
+    /// deployments can share a server
+    #[serde(default = "default_bus_prefix")]
+    pub prefix: String,
+    /// Name of this instance on the bus (default: the hostname). Gateways
+    /// need a stable one to pick up replies queued while they restart.
     #[serde(default)]
-    pub max_coordinate_y: Option<i64>,
+    pub instance_id: Option<String>,
+    /// Messages kept per stream, approximately (default: 10000)
+    #[serde(default = "default_bus_max_len")]
+    pub max_len: usize,
 }
 
-fn default_browser_computer_use_endpoint() -> String {
-    "http://127.0.0.1:8787/v1/actions".into()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BusRole {
+    #[default]
+    Gateway,
+    Worker,
 }
 
-fn default_browser_computer_use_timeout_ms() -> u64 {
-    15_000
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BusBackend {
+    #[default]
+    Redis,
+    Nats,
 }
 
-impl Default for BrowserComputerUseConfig {
+impl BusBackend {
+    pub fn default_url(self) -> &'static str {
+        match self {
+            Self::Redis => "redis://127.0.0.1:6379",
+            Self::Nats => "nats://127.0.0.1:4222",
+        }
+    }
+}
+
+fn default_bus_prefix() -> String {
+    "zeroclaw".into()
+}
+
+fn default_bus_max_len() -> usize {
+    10_000
+}
+
+impl Default for BusConfig {
     fn default() -> Self {
         Self {
-            endpoint: default_browser_computer_use_endpoint(),
-            api_key: None,
-            timeout_ms: default_browser_computer_use_timeout_ms(),
-            allow_remote_endpoint: false,
-            window_allowlist: Vec::new(),
-            max_coordinate_x: None,
-            max_coordinate_y: None,
+            enabled: false,
+            role: BusRole::default(),
+            backend: BusBackend::default(),
+            url: None,
+            prefix: default_bus_prefix(),
+            instance_id: None,
+            max_len: default_bus_max_len(),
         }
     }
 }
 
+// ── Cluster ─────────────────────────────────────────────────────
+
+/// Leader election between redundant instances: only the leader runs the
+/// channel listeners (and so holds the gateway connections); a follower
+/// starts them when the leader's lease runs out.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BrowserConfig {
-    /// Enable `browser_open` tool (opens URLs in Brave without scraping)
+pub struct ClusterConfig {
     #[serde(default)]
     pub enabled: bool,
-    /// Allowed domains for `browser_open` (exact or subdomain match)
-    #[serde(default)]
-    pub allowed_domains: Vec<String>,
-    /// Browser session name (for agent-browser automation)
+    /// "redis" | "etcd" (default: "redis"); Redis needs a build with
+    /// `--features bus-redis`
     #[serde(default)]
-    pub session_name: Option<String>,
-    /// Browser automation backend: "agent_browser" | "rust_native" | "computer_use" | "auto"
-    #[serde(default = "default_browser_backend")]
-    pub backend: String,
-    /// Headless mode for rust-native backend
-    #[serde(default = "default_true")]
-    pub native_headless: bool,
-    /// WebDriver endpoint URL for rust-native backend (e.g. http://127.0.0.1:9515)
-    #[serde(default = "default_browser_webdriver_url")]
-    pub native_webdriver_url: String,
-    /// Optional Chrome/Chromium executable path for rust-native backend
+    pub backend: ClusterBackend,
+    /// Server URL (default: `redis://127.0.0.1:6379` or
+    /// `http://127.0.0.1:2379`). Accepts `env:`/`file:`/`vault:`/`keyring:`
+    /// references.
     #[serde(default)]
-    pub native_chrome_path: Option<String>,
-    /// Computer-use sidecar configuration
+    pub url: Option<String>,
+    /// Key holding the current leader; instances with the same key compete
+    #[serde(default = "default_cluster_key")]
+    pub key: String,
+    /// Name of this instance (default: the hostname)
     #[serde(default)]
-    pub computer_use: BrowserComputerUseConfig,
+    pub instance_id: Option<String>,
+    /// Seconds a leader's lease lasts without renewal; also roughly how
+    /// long a failover takes (default: 15, min: 3)
+    #[serde(default = "default_cluster_lease_secs")]
+    pub lease_secs: u64,
 }
 
-fn default_browser_backend() -> String {
-    "agent_browser".into()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClusterBackend {
+    #[default]
+    Redis,
+    Etcd,
 }
 
-fn default_browser_webdriver_url() -> String {
-    "http://127.0.0.1:9515".into()
+impl ClusterBackend {
+    pub fn default_url(self) -> &'static str {
+        match self {
+            Self::Redis => "redis://127.0.0.1:6379",
+            Self::Etcd => "http://127.0.0.1:2379",
+        }
+    }
 }
 
-impl Default for BrowserConfig {
+fn default_cluster_key() -> String {
+    "zeroclaw:leader".into()
+}
+
+fn default_cluster_lease_secs() -> u64 {
+    15
+}
+
+impl Default for ClusterConfig {
     fn default() -> Self {
         Self {
             enabled: false,
-            allowed_domains: Vec::new(),
-            session_name: None,
-            backend: default_browser_backend(),
-            native_headless: default_true(),
-            native_webdriver_url: default_browser_webdriver_url(),
-            native_chrome_path: None,
-            computer_use: BrowserComputerUseConfig::default(),
+            backend: ClusterBackend::default(),
+            url: None,
+            key: default_cluster_key(),
+            instance_id: None,
+            lease_secs: default_cluster_lease_secs(),
         }
     }
 }
 
-// ── HTTP request tool ───────────────────────────────────────────
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_gateway_host(),
+            port: default_grpc_port(),
+            token: None,
+        }
+    }
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct HttpRequestConfig {
-    /// Enable `http_request` tool for API interactions
+// ── Plugins ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// Load plugins at channel startup (default: false)
     #[serde(default)]
     pub enabled: bool,
-    /// Allowed domains for HTTP requests (exact or subdomain match)
+    /// Plugin directory (default: `<workspace>/plugins`)
     #[serde(default)]
-    pub allowed_domains: Vec<String>,
-    /// Maximum response size in bytes (default: 1MB)
-    #[serde(default = "default_http_max_response_size")]
-    pub max_response_size: usize,
-    /// Request timeout in seconds (default: 30)
-    #[serde(default = "default_http_timeout_secs")]
-    pub timeout_secs: u64,
+    pub dir: Option<String>,
+    /// WASM fuel budget per plugin call; exhausting it aborts the call
+    #[serde(default = "default_plugin_fuel_per_call")]
+    pub fuel_per_call: u64,
+    /// Memory cap per plugin (WASM linear memory / Lua heap), in MiB
+    #[serde(default = "default_plugin_max_memory_mb")]
+    pub max_memory_mb: usize,
 }
 
-fn default_http_max_response_size() -> usize {
-    1_000_000 // 1MB
+fn default_plugin_fuel_per_call() -> u64 {
+    50_000_000
 }
 
-fn default_http_timeout_secs() -> u64 {
-    30
+fn default_plugin_max_memory_mb() -> usize {
+    64
 }
 
-// ── Memory ───────────────────────────────────────────────────
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            fuel_per_call: default_plugin_fuel_per_call(),
+            max_memory_mb: default_plugin_max_memory_mb(),
+        }
+    }
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MemoryConfig {
-    /// "sqlite" | "lucid" | "markdown" | "none" (`none` = explicit no-op memory)
-    pub backend: String,
-    /// Auto-save conversation context to memory
-    pub auto_save: bool,
-    /// Run memory/session hygiene (archiving + retention cleanup)
-    #[serde(default = "default_hygiene_enabled")]
-    pub hygiene_enabled: bool,
-    /// Archive daily/session files older than this many days
-    #[serde(default = "default_archive_after_days")]
-    pub archive_after_days: u32,
-    /// Purge archived files older than this many days
-    #[serde(default = "default_purge_after_days")]
-    pub purge_after_days: u32,
-    /// For sqlite backend: prune conversation rows older than this many days
-    #[serde(default = "default_conversation_retention_days")]
-    pub conversation_retention_days: u32,
-    /// Embedding provider: "none" | "openai" | "custom:URL"
-    #[serde(default = "default_embedding_provider")]
-    pub embedding_provider: String,
-    /// Embedding model name (e.g. "text-embedding-3-small")
-    #[serde(default = "default_embedding_model")]
-    pub embedding_model: String,
-    /// Embedding vector dimensions
-    #[serde(default = "default_embedding_dims")]
-    pub embedding_dimensions: usize,
-    /// Weight for vector similarity in hybrid search (0.0–1.0)
-    #[serde(default = "default_vector_weight")]
-    pub vector_weight: f64,
-    /// Weight for keyword BM25 in hybrid search (0.0–1.0)
-    #[serde(default = "default_keyword_weight")]
-    pub keyword_weight: f64,
-    /// Max embedding cache entries before LRU eviction
-    #[serde(default = "default_cache_size")]
-    pub embedding_cache_size: usize,
-    /// Max tokens per chunk for document splitting
-    #[serde(default = "default_chunk_size")]
-    pub chunk_max_tokens: usize,
+// ── Channel middleware ──────────────────────────────────────────
 
-    // ── Response Cache (saves tokens on repeated prompts) ──────
-    /// Enable LLM response caching to avoid paying for duplicate prompts
+/// Middleware chains applied to channel traffic. Chains are lists of
+/// middleware names run in order: built-ins are `logging`, `acl`,
+/// `rate_limit`, `flood`, `moderation` and `translation`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MiddlewareConfig {
+    /// Inbound chain for every channel
     #[serde(default)]
-    pub response_cache_enabled: bool,
-    /// TTL in minutes for cached responses (default: 60)
-    #[serde(default = "default_response_cache_ttl")]
-    pub response_cache_ttl_minutes: u32,
-    /// Max number of cached responses before LRU eviction (default: 5000)
-    #[serde(default = "default_response_cache_max")]
-    pub response_cache_max_entries: usize,
+    pub inbound: Vec<String>,
+    /// Outbound chain for every channel
+    #[serde(default)]
+    pub outbound: Vec<String>,
+    /// Per-channel overrides, keyed by channel name (e.g. `qq`, `discord`)
+    #[serde(default)]
+    pub channels: HashMap<String, MiddlewareChainConfig>,
+    #[serde(default)]
+    pub acl: AclMiddlewareConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitMiddlewareConfig,
+    #[serde(default)]
+    pub flood: FloodMiddlewareConfig,
+    #[serde(default)]
+    pub moderation: ModerationMiddlewareConfig,
+    #[serde(default)]
+    pub translation: TranslationMiddlewareConfig,
+}
 
-    // ── Memory Snapshot (soul backup to Markdown) ─────────────
-    /// Enable periodic export of core memories to MEMORY_SNAPSHOT.md
+/// Chain override for one channel. An unset direction keeps the global chain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MiddlewareChainConfig {
     #[serde(default)]
-    pub snapshot_enabled: bool,
-    /// Run snapshot during hygiene passes (heartbeat-driven)
+    pub inbound: Option<Vec<String>>,
     #[serde(default)]
-    pub snapshot_on_hygiene: bool,
-    /// Auto-hydrate from MEMORY_SNAPSHOT.md when brain.db is missing
-    #[serde(default = "default_true")]
-    pub auto_hydrate: bool,
+    pub outbound: Option<Vec<String>>,
 }
 
-fn default_embedding_provider() -> String {
-    "none".into()
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AclMiddlewareConfig {
+    /// Senders whose messages are dropped on every channel
+    #[serde(default)]
+    pub blocked_senders: Vec<String>,
+    /// Channels on which only these senders are accepted (channel → senders)
+    #[serde(default)]
+    pub allow_only: HashMap<String, Vec<String>>,
 }
-fn default_hygiene_enabled() -> bool {
-    true
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitMiddlewareConfig {
+    /// Messages accepted per sender within one window
+    #[serde(default = "default_middleware_rate_limit_max")]
+    pub max_messages: u32,
+    /// Window length in seconds
+    #[serde(default = "default_middleware_rate_limit_window_secs")]
+    pub window_secs: u64,
+    /// Reply sent when a sender is throttled; unset drops silently
+    #[serde(default)]
+    pub notice: Option<String>,
 }
-fn default_archive_after_days() -> u32 {
-    7
+
+fn default_middleware_rate_limit_max() -> u32 {
+    10
 }
-fn default_purge_after_days() -> u32 {
-    30
+
+fn default_middleware_rate_limit_window_secs() -> u64 {
+    60
 }
-fn default_conversation_retention_days() -> u32 {
-    30
+
+impl Default for RateLimitMiddlewareConfig {
+    fn default() -> Self {
+        Self {
+            max_messages: default_middleware_rate_limit_max(),
+            window_secs: default_middleware_rate_limit_window_secs(),
+            notice: None,
+        }
+    }
 }
-fn default_embedding_model() -> String {
-    "text-embedding-3-small".into()
+
+/// Escalating flood protection: excess messages are ignored, then warned
+/// about, then the sender is temporarily blocked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FloodMiddlewareConfig {
+    /// Messages accepted per sender within one window
+    #[serde(default = "default_flood_max_messages")]
+    pub max_messages: u32,
+    /// Window length in seconds
+    #[serde(default = "default_flood_window_secs")]
+    pub window_secs: u64,
+    /// Violations (messages over the limit) before the sender is warned
+    #[serde(default = "default_flood_warn_after")]
+    pub warn_after_violations: u32,
+    /// Violations before the sender is temporarily blocked
+    #[serde(default = "default_flood_block_after")]
+    pub block_after_violations: u32,
+    /// Block duration in seconds
+    #[serde(default = "default_flood_block_secs")]
+    pub block_secs: u64,
+    /// Seconds without a violation after which the count resets
+    #[serde(default = "default_flood_block_secs")]
+    pub violation_decay_secs: u64,
+    /// Warning sent to the sender
+    #[serde(default = "default_flood_warning")]
+    pub warning: String,
+    /// Message sent to the sender when blocked
+    #[serde(default = "default_flood_block_notice")]
+    pub block_notice: String,
+    /// Channel to notify operators on when someone is blocked
+    #[serde(default)]
+    pub notify_channel: Option<String>,
+    /// Recipient on `notify_channel` (chat id, user, room)
+    #[serde(default)]
+    pub notify_recipient: Option<String>,
 }
-fn default_embedding_dims() -> usize {
-    1536
+
+fn default_flood_max_messages() -> u32 {
+    8
 }
-fn default_vector_weight() -> f64 {
-    0.7
+
+fn default_flood_window_secs() -> u64 {
+    30
 }
-fn default_keyword_weight() -> f64 {
-    0.3
+
+fn default_flood_warn_after() -> u32 {
+    2
 }
-fn default_cache_size() -> usize {
-    10_000
+
+fn default_flood_block_after() -> u32 {
+    10
 }
-fn default_chunk_size() -> usize {
-    512
+
+fn default_flood_block_secs() -> u64 {
+    600
 }
-fn default_response_cache_ttl() -> u32 {
-    60
+
+fn default_flood_warning() -> String {
+    "⚠️ You're sending messages too fast — please slow down.".into()
 }
-fn default_response_cache_max() -> usize {
-    5_000
+
+fn default_flood_block_notice() -> String {
+    "🚫 Too many messages. You've been temporarily blocked.".into()
 }
 
-impl Default for MemoryConfig {
+impl Default for FloodMiddlewareConfig {
     fn default() -> Self {
         Self {
-            backend: "sqlite".into(),
-            auto_save: true,
-            hygiene_enabled: default_hygiene_enabled(),
-            archive_after_days: default_archive_after_days(),
-            purge_after_days: default_purge_after_days(),
-            conversation_retention_days: default_conversation_retention_days(),
-            embedding_provider: default_embedding_provider(),
-            embedding_model: default_embedding_model(),
-            embedding_dimensions: default_embedding_dims(),
-            vector_weight: default_vector_weight(),
-            keyword_weight: default_keyword_weight(),
-            embedding_cache_size: default_cache_size(),
-            chunk_max_tokens: default_chunk_size(),
-            response_cache_enabled: false,
-            response_cache_ttl_minutes: default_response_cache_ttl(),
-            response_cache_max_entries: default_response_cache_max(),
-            snapshot_enabled: false,
-            snapshot_on_hygiene: false,
-            auto_hydrate: true,
+            max_messages: default_flood_max_messages(),
+            window_secs: default_flood_window_secs(),
+            warn_after_violations: default_flood_warn_after(),
+            block_after_violations: default_flood_block_after(),
+            block_secs: default_flood_block_secs(),
+            violation_decay_secs: default_flood_block_secs(),
+            warning: default_flood_warning(),
+            block_notice: default_flood_block_notice(),
+            notify_channel: None,
+            notify_recipient: None,
         }
     }
 }
 
-// ── Observability ─────────────────────────────────────────────────
+/// What the moderation middleware does with matching content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Skip moderation
+    Off,
+    /// Log a warning and deliver unchanged
+    Flag,
+    /// Replace matches with the replacement text
+    #[default]
+    Redact,
+    /// Discard the message
+    Drop,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ObservabilityConfig {
-    /// "none" | "log" | "prometheus" | "otel"
-    pub backend: String,
-
-    /// OTLP endpoint (e.g. "http://localhost:4318"). Only used when backend = "otel".
+pub struct ModerationMiddlewareConfig {
+    /// Words matched case-insensitively (whole words for Latin script)
     #[serde(default)]
-    pub otel_endpoint: Option<String>,
+    pub words: Vec<String>,
+    /// Additional regex rules
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Action for matching content (default: redact)
+    #[serde(default)]
+    pub action: ModerationAction,
+    /// Per-channel action overrides, keyed by channel name
+    #[serde(default)]
+    pub channels: HashMap<String, ModerationAction>,
+    /// Text substituted for redacted content (default: "***")
+    #[serde(default = "default_moderation_replacement")]
+    pub replacement: String,
+    /// OpenAI-compatible moderation endpoint, consulted when no local rule
+    /// matches (e.g. `https://api.openai.com/v1/moderations`)
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// API key for `api_url`. Accepts `env:`/`file:`/`vault:`/`keyring:` references.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model requested from the moderation endpoint
+    #[serde(default = "default_moderation_model")]
+    pub api_model: String,
+}
 
-    /// Service name reported to the OTel collector. Defaults to "zeroclaw".
+fn default_moderation_replacement() -> String {
+    "***".into()
+}
+
+fn default_moderation_model() -> String {
+    "omni-moderation-latest".into()
+}
+
+impl Default for ModerationMiddlewareConfig {
+    fn default() -> Self {
+        Self {
+            words: Vec::new(),
+            patterns: Vec::new(),
+            action: ModerationAction::default(),
+            channels: HashMap::new(),
+            replacement: default_moderation_replacement(),
+            api_url: None,
+            api_key: None,
+            api_model: default_moderation_model(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationMiddlewareConfig {
+    /// Backend: "libretranslate" (default), "deepl" or "google"
+    #[serde(default = "default_translation_backend")]
+    pub backend: String,
+    /// Endpoint override (LibreTranslate: server base URL, default
+    /// `http://localhost:5000`)
     #[serde(default)]
-    pub otel_service_name: Option<String>,
+    pub api_url: Option<String>,
+    /// API key (required for DeepL and Google). Accepts
+    /// `env:`/`file:`/`vault:`/`keyring:` references.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Language the agent works in; inbound text is translated into it
+    /// (default: "en")
+    #[serde(default = "default_translation_working_language")]
+    pub working_language: String,
 }
 
-impl Default for ObservabilityConfig {
+fn default_translation_backend() -> String {
+    "libretranslate".into()
+}
+
+fn default_translation_working_language() -> String {
+    "en".into()
+}
+
+impl Default for TranslationMiddlewareConfig {
     fn default() -> Self {
         Self {
-            backend: "none".into(),
-            otel_endpoint: None,
-            otel_service_name: None,
+            backend: default_translation_backend(),
+            api_url: None,
+            api_key: None,
+            working_language: default_translation_working_language(),
         }
     }
 }
 
-// ── Autonomy / Security ──────────────────────────────────────────
+// ── Voice transcription ─────────────────────────────────────────
 
+/// Turn inbound voice notes and audio files into message text.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AutonomyConfig {
-    pub level: AutonomyLevel,
-    pub workspace_only: bool,
-    pub allowed_commands: Vec<String>,
-    pub forbidden_paths: Vec<String>,
-    pub max_actions_per_hour: u32,
-    pub max_cost_per_day_cents: u32,
+pub struct TranscriptionConfig {
+    /// Transcribe voice and audio attachments (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// "whisper_api" (OpenAI-compatible endpoint) or "whisper_cpp" (local binary)
+    #[serde(default = "default_transcription_backend")]
+    pub backend: String,
+    /// Transcription endpoint for `whisper_api`
+    #[serde(default = "default_transcription_api_url")]
+    pub api_url: String,
+    /// API key for `whisper_api`; supports `env:`/`file:`/`vault:`/`keyring:` references
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model for `whisper_api` (default: whisper-1)
+    #[serde(default = "default_transcription_model")]
+    pub model: String,
+    /// Spoken language hint (ISO-639-1); detected automatically when unset
+    #[serde(default)]
+    pub language: Option<String>,
+    /// whisper.cpp command-line binary (default: whisper-cli)
+    #[serde(default = "default_whisper_cpp_bin")]
+    pub whisper_cpp_bin: String,
+    /// ggml model file for `whisper_cpp`
+    #[serde(default)]
+    pub whisper_cpp_model: Option<String>,
+    /// ffmpeg binary used to convert audio to 16 kHz WAV for `whisper_cpp`
+    #[serde(default = "default_ffmpeg_bin")]
+    pub ffmpeg_bin: String,
+    /// Attachments larger than this are not transcribed (default: 25 MB)
+    #[serde(default = "default_transcription_max_bytes")]
+    pub max_bytes: usize,
+}
 
-    /// Require explicit approval for medium-risk shell commands.
-    #[serde(default = "default_true")]
-    pub require_approval_for_medium_risk: bool,
+fn default_transcription_backend() -> String {
+    "whisper_api".into()
+}
 
-    /// Block high-risk shell commands even if allowlisted.
-    #[serde(default = "default_true")]
-    pub block_high_risk_commands: bool,
+fn default_transcription_api_url() -> String {
+    "https://api.openai.com/v1/audio/transcriptions".into()
 }
 
-impl Default for AutonomyConfig {
+fn default_transcription_model() -> String {
+    "whisper-1".into()
+}
+
+fn default_whisper_cpp_bin() -> String {
+    "whisper-cli".into()
+}
+
+fn default_ffmpeg_bin() -> String {
+    "ffmpeg".into()
+}
+
+fn default_transcription_max_bytes() -> usize {
+    25 * 1024 * 1024
+}
+
+impl Default for TranscriptionConfig {
     fn default() -> Self {
         Self {
-            level: AutonomyLevel::Supervised,
-            workspace_only: true,
-            allowed_commands: vec![
-                "git".into(),
-                "npm".into(),
-                "cargo".into(),
-                "ls".into(),
-                "cat".into(),
-                "grep".into(),
-                "find".into(),
-                "echo".into(),
-                "pwd".into(),
-                "wc".into(),
-                "head".into(),
-                "tail".into(),
-            ],
-            forbidden_paths: vec![
-                "/etc".into(),
-                "/root".into(),
-                "/home".into(),
-                "/usr".into(),
-                "/bin".into(),
-                "/sbin".into(),
-                "/lib".into(),
-                "/opt".into(),
+            enabled: false,
+            backend: default_transcription_backend(),
+            api_url: default_transcription_api_url(),
+            api_key: None,
+            model: default_transcription_model(),
+            language: None,
+            whisper_cpp_bin: default_whisper_cpp_bin(),
+            whisper_cpp_model: None,
+            ffmpeg_bin: default_ffmpeg_bin(),
+            max_bytes: default_transcription_max_bytes(),
+        }
+    }
+}
+
+// ── Text-to-speech ──────────────────────────────────────────────
+
+/// When replies are sent as voice messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsMode {
+    /// Always reply with text
+    Off,
+    /// Reply with voice when the user sent a voice message
+    #[default]
+    VoiceReplies,
+    /// Reply with voice to every message
+    Always,
+}
+
+/// Speak replies on channels that accept audio uploads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsConfig {
+    /// Enable spoken replies (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// "openai" (OpenAI-compatible endpoint), "edge_tts" or "piper" (local binaries)
+    #[serde(default = "default_tts_backend")]
+    pub backend: String,
+    /// Default mode; users can override theirs with `/voice on|off|auto`
+    #[serde(default)]
+    pub mode: TtsMode,
+    /// Per-channel mode overrides, keyed by channel name
+    #[serde(default)]
+    pub channels: HashMap<String, TtsMode>,
+    /// Speech endpoint for `openai`
+    #[serde(default = "default_tts_api_url")]
+    pub api_url: String,
+    /// API key for `openai`; supports `env:`/`file:`/`vault:`/`keyring:` references
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model for `openai` (default: tts-1)
+    #[serde(default = "default_tts_model")]
+    pub model: String,
+    /// Voice name; defaults to "alloy" for `openai` and "en-US-AriaNeural" for `edge_tts`
+    #[serde(default)]
+    pub voice: Option<String>,
+    /// edge-tts binary (default: edge-tts)
+    #[serde(default = "default_edge_tts_bin")]
+    pub edge_tts_bin: String,
+    /// piper binary (default: piper)
+    #[serde(default = "default_piper_bin")]
+    pub piper_bin: String,
+    /// ONNX voice model for `piper`
+    #[serde(default)]
+    pub piper_model: Option<String>,
+    /// Replies longer than this are sent as text (default: 1500 characters)
+    #[serde(default = "default_tts_max_chars")]
+    pub max_chars: usize,
+}
+
+fn default_tts_backend() -> String {
+    "openai".into()
+}
+
+fn default_tts_api_url() -> String {
+    "https://api.openai.com/v1/audio/speech".into()
+}
+
+fn default_tts_model() -> String {
+    "tts-1".into()
+}
+
+fn default_edge_tts_bin() -> String {
+    "edge-tts".into()
+}
+
+fn default_piper_bin() -> String {
+    "piper".into()
+}
+
+fn default_tts_max_chars() -> usize {
+    1500
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: default_tts_backend(),
+            mode: TtsMode::default(),
+            channels: HashMap::new(),
+            api_url: default_tts_api_url(),
+            api_key: None,
+            model: default_tts_model(),
+            voice: None,
+            edge_tts_bin: default_edge_tts_bin(),
+            piper_bin: default_piper_bin(),
+            piper_model: None,
+            max_chars: default_tts_max_chars(),
+        }
+    }
+}
+
+// ── Image understanding ─────────────────────────────────────────
+
+/// Describe inbound images with a vision-capable model so the agent can
+/// reason about them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisionConfig {
+    /// Describe image attachments (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Provider for image requests; the main provider when unset
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// API key for `provider`; supports `env:`/`file:`/`vault:`/`keyring:` references
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Vision model; `default_model` when unset
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Instruction sent with each image
+    #[serde(default = "default_vision_prompt")]
+    pub prompt: String,
+    /// Images larger than this are not described (default: 10 MB)
+    #[serde(default = "default_vision_max_bytes")]
+    pub max_bytes: usize,
+}
+
+fn default_vision_prompt() -> String {
+    "Describe this image in detail, including any visible text, so someone who cannot see it can answer questions about it.".into()
+}
+
+fn default_vision_max_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+impl Default for VisionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: None,
+            api_key: None,
+            model: None,
+            prompt: default_vision_prompt(),
+            max_bytes: default_vision_max_bytes(),
+        }
+    }
+}
+
+// ── Image generation ────────────────────────────────────────────
+
+/// Text-to-image for the `image_generate` tool and the `/imagine` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageGenerationConfig {
+    /// Enable image generation (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// "openai" (DALL·E), "stability" (Stability AI) or "stable_diffusion" (AUTOMATIC1111 API)
+    #[serde(default = "default_image_generation_backend")]
+    pub backend: String,
+    /// Endpoint override; each backend has a sensible default
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// API key; supports `env:`/`file:`/`vault:`/`keyring:` references
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model (`dall-e-3`, `core`, ...) or Stable Diffusion checkpoint
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Image size as WIDTHxHEIGHT (default: 1024x1024)
+    #[serde(default = "default_image_generation_size")]
+    pub size: String,
+}
+
+fn default_image_generation_backend() -> String {
+    "openai".into()
+}
+
+fn default_image_generation_size() -> String {
+    "1024x1024".into()
+}
+
+impl Default for ImageGenerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: default_image_generation_backend(),
+            api_url: None,
+            api_key: None,
+            model: None,
+            size: default_image_generation_size(),
+        }
+    }
+}
+
+// ── Knowledge base ──────────────────────────────────────────────
+
+/// Document ingestion and retrieval for answers (`[knowledge]`).
+/// Embeddings come from the `[memory]` embedding provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Enable the knowledge base (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Files or directories to ingest; relative paths are under the workspace
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Vector store: "sqlite" (default) or "qdrant"
+    #[serde(default = "default_knowledge_store")]
+    pub store: String,
+    /// Qdrant REST endpoint
+    #[serde(default = "default_knowledge_qdrant_url")]
+    pub qdrant_url: String,
+    /// Qdrant collection name
+    #[serde(default = "default_knowledge_qdrant_collection")]
+    pub qdrant_collection: String,
+    /// Qdrant API key; supports `env:`/`file:`/`vault:`/`keyring:` references
+    #[serde(default)]
+    pub qdrant_api_key: Option<String>,
+    /// Approximate tokens per chunk (default: 512)
+    #[serde(default = "default_knowledge_chunk_tokens")]
+    pub chunk_tokens: usize,
+    /// Chunks retrieved per message (default: 4)
+    #[serde(default = "default_knowledge_top_k")]
+    pub top_k: usize,
+    /// Minimum cosine similarity for a chunk to be used (default: 0.3)
+    #[serde(default = "default_knowledge_min_score")]
+    pub min_score: f64,
+}
+
+fn default_knowledge_store() -> String {
+    "sqlite".into()
+}
+
+fn default_knowledge_qdrant_url() -> String {
+    "http://127.0.0.1:6333".into()
+}
+
+fn default_knowledge_qdrant_collection() -> String {
+    "zeroclaw_knowledge".into()
+}
+
+fn default_knowledge_chunk_tokens() -> usize {
+    512
+}
+
+fn default_knowledge_top_k() -> usize {
+    4
+}
+
+fn default_knowledge_min_score() -> f64 {
+    0.3
+}
+
+impl Default for KnowledgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            paths: Vec::new(),
+            store: default_knowledge_store(),
+            qdrant_url: default_knowledge_qdrant_url(),
+            qdrant_collection: default_knowledge_qdrant_collection(),
+            qdrant_api_key: None,
+            chunk_tokens: default_knowledge_chunk_tokens(),
+            top_k: default_knowledge_top_k(),
+            min_score: default_knowledge_min_score(),
+        }
+    }
+}
+
+// ── Web search ──────────────────────────────────────────────────
+
+/// Web search for the `web_search` tool and the `/search` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSearchConfig {
+    /// Enable web search for this deployment (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// "searxng" (self-hosted), "brave" or "bing"
+    #[serde(default = "default_web_search_backend")]
+    pub backend: String,
+    /// Endpoint; required for SearXNG (instance base URL), optional override otherwise
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// API key; supports `env:`/`file:`/`vault:`/`keyring:` references
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Maximum results returned per search (default: 5)
+    #[serde(default = "default_web_search_max_results")]
+    pub max_results: usize,
+}
+
+fn default_web_search_backend() -> String {
+    "searxng".into()
+}
+
+fn default_web_search_max_results() -> usize {
+    5
+}
+
+impl Default for WebSearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: default_web_search_backend(),
+            api_url: None,
+            api_key: None,
+            max_results: default_web_search_max_results(),
+        }
+    }
+}
+
+// ── Feeds ───────────────────────────────────────────────────────
+
+/// RSS/Atom feed watcher (`[feeds]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedsConfig {
+    /// Enable the feed watcher (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Default poll interval in seconds (default: 900, minimum: 60)
+    #[serde(default = "default_feeds_interval_secs")]
+    pub interval_secs: u64,
+    /// Default message template; placeholders: {feed}, {title}, {link},
+    /// {summary}, {published}
+    #[serde(default = "default_feeds_template")]
+    pub template: String,
+    #[serde(default)]
+    pub subscriptions: Vec<FeedSubscriptionConfig>,
+}
+
+/// One watched feed and where its new entries are posted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedSubscriptionConfig {
+    /// Unique name; keys the persisted seen-entry state
+    pub name: String,
+    pub url: String,
+    /// Channel to post to (e.g. "telegram")
+    pub channel: String,
+    /// Recipient on that channel (chat id, channel id, ...)
+    pub recipient: String,
+    /// Poll interval override in seconds
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// Template override
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Post new entries as a periodic summary instead of one by one
+    #[serde(default)]
+    pub digest: Option<DigestConfig>,
+}
+
+fn default_feeds_interval_secs() -> u64 {
+    900
+}
+
+fn default_feeds_template() -> String {
+    "📰 {feed}: {title}\n{link}".into()
+}
+
+impl Default for FeedsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_feeds_interval_secs(),
+            template: default_feeds_template(),
+            subscriptions: Vec::new(),
+        }
+    }
+}
+
+// ── Ingress ─────────────────────────────────────────────────────
+
+/// Inbound HTTP receiver for third-party integrations (`[ingress]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngressConfig {
+    /// Enable the ingress receiver (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Bind address (default: 127.0.0.1)
+    #[serde(default = "default_ingress_host")]
+    pub host: String,
+    /// Bind port (default: 8090)
+    #[serde(default = "default_ingress_port")]
+    pub port: u16,
+    /// GitHub webhooks at `/ingress/github`
+    #[serde(default)]
+    pub github: Option<ForgeWebhookConfig>,
+    /// GitLab webhooks at `/ingress/gitlab`
+    #[serde(default)]
+    pub gitlab: Option<ForgeWebhookConfig>,
+    /// Prometheus Alertmanager webhooks at `/ingress/alertmanager`
+    #[serde(default)]
+    pub alertmanager: Option<AlertmanagerConfig>,
+    /// Topic notifications at `/notify/{topic}`
+    #[serde(default)]
+    pub notify: Option<NotifyConfig>,
+}
+
+/// Webhook settings for one forge (GitHub or GitLab).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeWebhookConfig {
+    /// Webhook secret (GitHub HMAC key / GitLab token); supports secret refs
+    pub secret: String,
+    #[serde(default)]
+    pub targets: Vec<IngressTargetConfig>,
+    /// Message template overrides by event kind ("push", "pull_request",
+    /// "issue", "ci"); placeholders: {repo}, {actor}, {action}, {title},
+    /// {url}, {branch}, {status}, {commits}
+    #[serde(default)]
+    pub templates: HashMap<String, String>,
+}
+
+/// Alertmanager receiver. Targets route by severity (`events`) and alert
+/// name (`sources`); `/silence` works in the chats they post to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertmanagerConfig {
+    /// Bearer token Alertmanager sends (`http_config.authorization`);
+    /// supports secret refs
+    pub token: String,
+    /// Alertmanager base URL; when set, `/silence` also creates silences there
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// Post resolved notifications too (default: true)
+    #[serde(default = "default_true")]
+    pub send_resolved: bool,
+    #[serde(default)]
+    pub targets: Vec<IngressTargetConfig>,
+}
+
+/// Topic pub/sub: publishers POST to `/notify/{topic}`, chats subscribe
+/// with `/subscribe <topic>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    /// Bearer token publishers send; supports secret refs
+    pub token: String,
+    /// Topics that may be used; empty allows any name
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+/// Where matching ingress events are delivered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngressTargetConfig {
+    /// Channel to post to (e.g. "slack")
+    pub channel: String,
+    /// Recipient on that channel
+    pub recipient: String,
+    /// Event kinds to deliver; empty means all
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Sources (repositories, ...) to deliver; `*` suffix matches a prefix,
+    /// empty means all
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Post matching events as a periodic summary instead of one by one
+    #[serde(default)]
+    pub digest: Option<DigestConfig>,
+}
+
+/// Digest mode of a notification route: its messages are buffered and
+/// posted as one summary every `interval_mins`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestConfig {
+    /// Minutes between summaries (default: 60)
+    #[serde(default = "default_digest_interval_mins")]
+    pub interval_mins: u64,
+    /// Summary template; placeholders: {source}, {count}, {interval},
+    /// {items}
+    #[serde(default = "default_digest_template")]
+    pub template: String,
+    /// Most notifications listed in one summary; the rest are only counted
+    /// (default: 20)
+    #[serde(default = "default_digest_max_items")]
+    pub max_items: usize,
+}
+
+fn default_digest_interval_mins() -> u64 {
+    60
+}
+
+fn default_digest_template() -> String {
+    "🗞 {source}: {count} updates in the last {interval}\n\n{items}".into()
+}
+
+fn default_digest_max_items() -> usize {
+    20
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            interval_mins: default_digest_interval_mins(),
+            template: default_digest_template(),
+            max_items: default_digest_max_items(),
+        }
+    }
+}
+
+fn default_ingress_host() -> String {
+    "127.0.0.1".into()
+}
+
+fn default_ingress_port() -> u16 {
+    8090
+}
+
+impl Default for IngressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_ingress_host(),
+            port: default_ingress_port(),
+            github: None,
+            gitlab: None,
+            alertmanager: None,
+            notify: None,
+        }
+    }
+}
+
+// ── Hooks ───────────────────────────────────────────────────────
+
+/// Outbound delivery of lifecycle events (`[hooks]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Endpoints that receive lifecycle events as JSON POSTs
+    #[serde(default)]
+    pub webhooks: Vec<EventWebhookConfig>,
+}
+
+/// One endpoint of `[[hooks.webhooks]]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventWebhookConfig {
+    pub url: String,
+    /// HMAC key for `X-Zeroclaw-Timestamp`/`X-Zeroclaw-Signature`; supports
+    /// secret refs. Requests are unsigned without one
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events to send (`message_sent`, `channel_disconnected`, ...); empty
+    /// sends all
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+// ── Monitor ─────────────────────────────────────────────────────
+
+/// Uptime monitor (`[monitor]`). Per-check fields override the defaults here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorConfig {
+    /// Enable the uptime monitor (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Default probe interval in seconds (default: 60, minimum: 5)
+    #[serde(default = "default_monitor_interval_secs")]
+    pub interval_secs: u64,
+    /// Default probe timeout in seconds (default: 10)
+    #[serde(default = "default_monitor_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Consecutive failures before a check is reported down (default: 3)
+    #[serde(default = "default_monitor_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Consecutive successes before a down check is reported up (default: 1)
+    #[serde(default = "default_monitor_recovery_threshold")]
+    pub recovery_threshold: u32,
+    #[serde(default)]
+    pub alerts: Vec<MonitorAlertConfig>,
+    #[serde(default)]
+    pub checks: Vec<MonitorCheckConfig>,
+}
+
+/// One monitored endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorCheckConfig {
+    /// Unique name shown in alerts
+    pub name: String,
+    /// "http", "tcp" or "ping"
+    pub kind: String,
+    /// URL (http), `host:port` (tcp) or host (ping)
+    pub target: String,
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub failure_threshold: Option<u32>,
+    /// Required HTTP status; any 2xx when unset
+    #[serde(default)]
+    pub expected_status: Option<u16>,
+}
+
+/// Where monitor state changes are sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorAlertConfig {
+    /// Channel to post to (e.g. "telegram")
+    pub channel: String,
+    /// Recipient on that channel
+    pub recipient: String,
+    /// Check names to alert about; empty means all
+    #[serde(default)]
+    pub checks: Vec<String>,
+    /// Alert during quiet hours too (see `[channels_config.quiet_hours]`)
+    #[serde(default)]
+    pub urgent: bool,
+}
+
+fn default_monitor_interval_secs() -> u64 {
+    60
+}
+
+fn default_monitor_timeout_secs() -> u64 {
+    10
+}
+
+fn default_monitor_failure_threshold() -> u32 {
+    3
+}
+
+fn default_monitor_recovery_threshold() -> u32 {
+    1
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_monitor_interval_secs(),
+            timeout_secs: default_monitor_timeout_secs(),
+            failure_threshold: default_monitor_failure_threshold(),
+            recovery_threshold: default_monitor_recovery_threshold(),
+            alerts: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+}
+
+// ── Logging ─────────────────────────────────────────────────────
+
+/// Log output (`[logging]`). `RUST_LOG`, when set, replaces `level` and
+/// `modules`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// "text" (default) or "json" (one JSON object per line)
+    #[serde(default = "default_log_format")]
+    pub format: String,
+    /// Default level (default: "info")
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// Per-module levels keyed by tracing target, e.g.
+    /// `"zeroclaw::channels::qq" = "debug"`
+    #[serde(default)]
+    pub modules: HashMap<String, String>,
+    /// Write logs to this file instead of stderr
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// Rotate the log file once it exceeds this size (default: 50)
+    #[serde(default = "default_log_max_file_mb")]
+    pub max_file_mb: u64,
+    /// Rotated files to keep as `<file>.1` … `<file>.N` (default: 5)
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,
+    /// Mask tokens and secrets in log lines (default: true)
+    #[serde(default = "default_true")]
+    pub redact: bool,
+    /// Extra regexes whose matches are masked; a first capture group, if
+    /// any, is kept (e.g. `(session=)\\S+` keeps the key)
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+}
+
+fn default_log_format() -> String {
+    "text".into()
+}
+
+fn default_log_level() -> String {
+    "info".into()
+}
+
+fn default_log_max_file_mb() -> u64 {
+    50
+}
+
+fn default_log_max_files() -> usize {
+    5
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: default_log_format(),
+            level: default_log_level(),
+            modules: HashMap::new(),
+            file: None,
+            max_file_mb: default_log_max_file_mb(),
+            max_files: default_log_max_files(),
+            redact: true,
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+// ── Ops notifications ───────────────────────────────────────────
+
+/// Where internal failures are reported (`[ops]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpsConfig {
+    /// Send failure summaries to the ops target (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Channel name (or `tag:<tag>`) of the ops target
+    #[serde(default)]
+    pub channel: String,
+    /// Chat on that channel to notify
+    #[serde(default)]
+    pub recipient: String,
+    /// Summaries are sent at most once per interval (default: 300)
+    #[serde(default = "default_ops_interval_secs")]
+    pub interval_secs: u64,
+    /// Send failures per channel and interval before they are reported
+    /// (default: 3)
+    #[serde(default = "default_ops_send_failure_threshold")]
+    pub send_failure_threshold: u32,
+    /// Listener reconnects per channel and interval that count as a storm
+    /// (default: 5)
+    #[serde(default = "default_ops_reconnect_threshold")]
+    pub reconnect_threshold: u32,
+}
+
+fn default_ops_interval_secs() -> u64 {
+    300
+}
+
+fn default_ops_send_failure_threshold() -> u32 {
+    3
+}
+
+fn default_ops_reconnect_threshold() -> u32 {
+    5
+}
+
+impl Default for OpsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            channel: String::new(),
+            recipient: String::new(),
+            interval_secs: default_ops_interval_secs(),
+            send_failure_threshold: default_ops_send_failure_threshold(),
+            reconnect_threshold: default_ops_reconnect_threshold(),
+        }
+    }
+}
+
+// ── Analytics ───────────────────────────────────────────────────
+
+/// Usage analytics (`[analytics]`): channel activity aggregated into
+/// `<workspace>/state/analytics.db`, served at `GET /admin/analytics` and
+/// summarized weekly to the `[ops]` target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Aggregate channel activity (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Send a weekly digest to the `[ops]` channel (default: true)
+    #[serde(default = "default_true")]
+    pub weekly_digest: bool,
+    /// Day the digest goes out, e.g. "mon" or "friday" (default: "mon")
+    #[serde(default = "default_analytics_digest_weekday")]
+    pub digest_weekday: String,
+    /// Hour of that day, UTC, the digest goes out (default: 9)
+    #[serde(default = "default_analytics_digest_hour")]
+    pub digest_hour: u32,
+    /// Days of aggregates kept (default: 90)
+    #[serde(default = "default_analytics_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_analytics_digest_weekday() -> String {
+    "mon".into()
+}
+
+fn default_analytics_digest_hour() -> u32 {
+    9
+}
+
+fn default_analytics_retention_days() -> u32 {
+    90
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weekly_digest: true,
+            digest_weekday: default_analytics_digest_weekday(),
+            digest_hour: default_analytics_digest_hour(),
+            retention_days: default_analytics_retention_days(),
+        }
+    }
+}
+
+// ── File uploads ────────────────────────────────────────────────
+
+/// Fallback for files a channel cannot take (`[uploads]`): they are put in
+/// an object store and sent as a link.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadsConfig {
+    /// S3-compatible bucket for oversized files; without one they are
+    /// refused
+    #[serde(default)]
+    pub s3: Option<S3StorageConfig>,
+    /// How long links to uploaded files stay valid, at most 7 days
+    /// (default: 604800)
+    #[serde(default = "default_upload_link_expiry_secs")]
+    pub link_expiry_secs: u64,
+}
+
+fn default_upload_link_expiry_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+impl Default for UploadsConfig {
+    fn default() -> Self {
+        Self {
+            s3: None,
+            link_expiry_secs: default_upload_link_expiry_secs(),
+        }
+    }
+}
+
+/// An S3 bucket (AWS or a compatible store such as MinIO or R2).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3StorageConfig {
+    /// Service endpoint, e.g. `https://s3.eu-west-1.amazonaws.com` or
+    /// `http://localhost:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    /// Signing region (default: us-east-1)
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    pub access_key_id: String,
+    /// Secret key; may be an `env:`/`file:`/`vault:`/`keyring:` reference
+    pub secret_access_key: String,
+    /// Address objects as `endpoint/bucket/key` instead of
+    /// `bucket.endpoint/key` (needed by most self-hosted stores)
+    #[serde(default)]
+    pub path_style: bool,
+    /// Prefix for every object key, e.g. `zeroclaw/`
+    #[serde(default)]
+    pub prefix: String,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".into()
+}
+
+// ── Media store ─────────────────────────────────────────────────
+
+/// Where attachments and generated media are kept (`[media_store]`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaBackendKind {
+    /// Files under `local_dir`, served by the gateway
+    #[default]
+    Local,
+    /// An S3-compatible bucket (`[media_store.s3]`)
+    S3,
+}
+
+/// Persistent copies of inbound attachments and generated outputs, stored
+/// under content-addressed keys so other subsystems can link to them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaStoreConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: MediaBackendKind,
+    /// Directory of the local backend (default: `{workspace}/media`)
+    #[serde(default)]
+    pub local_dir: Option<PathBuf>,
+    /// Base URL the gateway is reachable at, for signed links to local
+    /// media, e.g. `https://bot.example.com`
+    #[serde(default)]
+    pub public_base_url: Option<String>,
+    /// Bucket of the S3 backend
+    #[serde(default)]
+    pub s3: Option<S3StorageConfig>,
+    /// How long signed media URLs stay valid (default: 86400)
+    #[serde(default = "default_media_url_expiry_secs")]
+    pub url_expiry_secs: u64,
+    /// Keep a copy of every attachment on inbound messages that pass the block
+    /// list, middleware and quotas (default: true)
+    #[serde(default = "default_true")]
+    pub persist_inbound: bool,
+    /// Keep a copy of generated images (default: true)
+    #[serde(default = "default_true")]
+    pub persist_generated: bool,
+}
+
+fn default_media_url_expiry_secs() -> u64 {
+    24 * 3600
+}
+
+impl Default for MediaStoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: MediaBackendKind::default(),
+            local_dir: None,
+            public_base_url: None,
+            s3: None,
+            url_expiry_secs: default_media_url_expiry_secs(),
+            persist_inbound: true,
+            persist_generated: true,
+        }
+    }
+}
+
+/// SQLite-backed key-value store in `{workspace}/state/kv.db`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KvConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Largest value a key may hold, in bytes (default: 65536)
+    #[serde(default = "default_kv_max_value_bytes")]
+    pub max_value_bytes: usize,
+}
+
+fn default_kv_max_value_bytes() -> usize {
+    64 * 1024
+}
+
+impl Default for KvConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_value_bytes: default_kv_max_value_bytes(),
+        }
+    }
+}
+
+/// User preferences (`/set language|timezone|notifications|verbosity`),
+/// stored in the `[kv]` store. A user's own setting wins over the defaults
+/// of their channel, which win over `defaults`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreferencesConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub defaults: PreferenceSettings,
+    /// Defaults by channel name, e.g. `[preferences.channels.telegram]`
+    #[serde(default)]
+    pub channels: HashMap<String, PreferenceSettings>,
+}
+
+impl Default for PreferencesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            defaults: PreferenceSettings::default(),
+            channels: HashMap::new(),
+        }
+    }
+}
+
+/// One layer of preferences; unset fields fall through to the next layer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreferenceSettings {
+    /// Language replies are written in, e.g. "en" or "pt-BR"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// IANA timezone, e.g. "Europe/Berlin"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Whether the user receives proactive notifications
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<ReplyVerbosity>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplyVerbosity {
+    Brief,
+    #[default]
+    Normal,
+    Detailed,
+}
+
+/// Reminders set with `/remind` (`[reminders]`), kept in
+/// `<workspace>/state/reminders.db` so they survive restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemindersConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// How often due reminders are checked, in seconds (default: 15, minimum: 5)
+    #[serde(default = "default_reminders_poll_secs")]
+    pub poll_secs: u64,
+    /// Pending reminders one user may have (default: 25)
+    #[serde(default = "default_reminders_max_per_user")]
+    pub max_per_user: usize,
+    /// Delivery attempts before a reminder is dropped (default: 10)
+    #[serde(default = "default_reminders_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_reminders_poll_secs() -> u64 {
+    15
+}
+
+fn default_reminders_max_per_user() -> usize {
+    25
+}
+
+fn default_reminders_max_attempts() -> u32 {
+    10
+}
+
+impl Default for RemindersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_secs: default_reminders_poll_secs(),
+            max_per_user: default_reminders_max_per_user(),
+            max_attempts: default_reminders_max_attempts(),
+        }
+    }
+}
+
+/// Per-group overrides (`/group prefix|language|commands|persona`), stored
+/// in the `[kv]` store. Group admins, as reported by the platform, may
+/// change them; so may the operators in `admins`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Operators allowed to change any group, as `<channel>:<user id>`
+    /// (e.g. "telegram:123456789")
+    #[serde(default)]
+    pub admins: Vec<String>,
+    /// Longest persona prompt, in characters (default: 2000)
+    #[serde(default = "default_groups_max_persona_chars")]
+    pub max_persona_chars: usize,
+}
+
+fn default_groups_max_persona_chars() -> usize {
+    2000
+}
+
+impl Default for GroupsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            admins: Vec::new(),
+            max_persona_chars: default_groups_max_persona_chars(),
+        }
+    }
+}
+
+/// Named agent personas (`[personas]`): system prompts added to the agent's
+/// own. A user's `/persona` choice wins over the persona of their channel,
+/// which wins over `default`. Prompts may use `{user}`, `{user_id}` and
+/// `{channel}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonasConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Persona used when neither the user nor the channel picks one
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Personas by name, e.g. `[personas.prompts.pirate]`
+    #[serde(default)]
+    pub prompts: HashMap<String, PersonaConfig>,
+    /// Persona by channel instance name, e.g. `telegram = "pirate"`
+    #[serde(default)]
+    pub channels: HashMap<String, String>,
+    /// Let users switch personas with `/persona <name>` (default: true)
+    #[serde(default = "default_true")]
+    pub user_selectable: bool,
+}
+
+impl Default for PersonasConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default: None,
+            prompts: HashMap::new(),
+            channels: HashMap::new(),
+            user_selectable: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonaConfig {
+    pub prompt: String,
+    /// Shown by `/persona`
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+// ── Composio (managed tool surface) ─────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposioConfig {
+    /// Enable Composio integration for 1000+ OAuth tools
+    #[serde(default)]
+    pub enabled: bool,
+    /// Composio API key (stored encrypted when secrets.encrypt = true)
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Default entity ID for multi-user setups
+    #[serde(default = "default_entity_id")]
+    pub entity_id: String,
+}
+
+fn default_entity_id() -> String {
+    "default".into()
+}
+
+impl Default for ComposioConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key: None,
+            entity_id: default_entity_id(),
+        }
+    }
+}
+
+// ── Secrets (encrypted credential store) ────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    /// Enable encryption for API keys and tokens in config.toml
+    #[serde(default = "default_true")]
+    pub encrypt: bool,
+    /// Seconds between re-reads of externally sourced secrets (`env:`, `file:`,
+    /// `vault:`, `keyring:` references) so rotated values are picked up without a restart.
+    /// A literal secret that starts with one of those prefixes must be written as
+    /// `inline:<value>`
+    #[serde(default = "default_secret_refresh_secs")]
+    pub refresh_secs: u64,
+    /// Directory holding `file:` secrets (default: `~/.zeroclaw/secrets`)
+    #[serde(default)]
+    pub file_dir: Option<String>,
+    /// OS keyring service name used for `keyring:` references
+    #[serde(default = "default_keyring_service")]
+    pub keyring_service: String,
+    /// HashiCorp Vault KV v2 backend for `vault:` references
+    #[serde(default)]
+    pub vault: Option<VaultSecretsConfig>,
+}
+
+fn default_secret_refresh_secs() -> u64 {
+    300
+}
+
+fn default_keyring_service() -> String {
+    "zeroclaw".into()
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            encrypt: true,
+            refresh_secs: default_secret_refresh_secs(),
+            file_dir: None,
+            keyring_service: default_keyring_service(),
+            vault: None,
+        }
+    }
+}
+
+// ── OAuth ───────────────────────────────────────────────────────
+
+/// An OAuth2 client for user-delegated channel tokens (`[oauth.<name>]`).
+/// `provider` fills in the endpoints of a known service; others set the
+/// URLs themselves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthClientConfig {
+    /// Endpoint preset: "slack", "mastodon" or "microsoft" (Teams)
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Mastodon instance URL (e.g. `https://mastodon.social`)
+    #[serde(default)]
+    pub instance: Option<String>,
+    /// Microsoft Entra tenant (default: "common")
+    #[serde(default)]
+    pub tenant: Option<String>,
+    pub client_id: String,
+    /// Client secret; supports secret refs. Public clients leave it unset
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub auth_url: Option<String>,
+    #[serde(default)]
+    pub token_url: Option<String>,
+    /// Device authorization endpoint, for `zeroclaw oauth login --device`
+    #[serde(default)]
+    pub device_auth_url: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Port of the local redirect listener; register
+    /// `http://127.0.0.1:<port>/callback` with the provider (default: 8765)
+    #[serde(default = "default_oauth_redirect_port")]
+    pub redirect_port: u16,
+}
+
+fn default_oauth_redirect_port() -> u16 {
+    8765
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultSecretsConfig {
+    /// Vault server address (e.g. "https://vault.internal:8200")
+    pub address: String,
+    /// Vault token. Falls back to the `VAULT_TOKEN` environment variable when unset.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// KV v2 mount point
+    #[serde(default = "default_vault_mount")]
+    pub mount: String,
+}
+
+fn default_vault_mount() -> String {
+    "secret".into()
+}
+
+// ── Browser (friendly-service browsing only) ───────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserComputerUseConfig {
+    /// Sidecar endpoint for computer-use actions (OS-level mouse/keyboard/screenshot)
+    #[serde(default = "default_browser_computer_use_endpoint")]
+    pub endpoint: String,
+    /// Optional bearer token for computer-use sidecar
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Per-action request timeout in milliseconds
+    #[serde(default = "default_browser_computer_use_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Allow remote/public endpoint for computer-use sidecar (default: false)
+    #[serde(default)]
+    pub allow_remote_endpoint: bool,
+    /// Optional window title/process allowlist forwarded to sidecar policy
+    #[serde(default)]
+    pub window_allowlist: Vec<String>,
+    /// Optional X-axis boundary for coordinate-based actions
+    #[serde(default)]
+    pub max_coordinate_x: Option<i64>,
+    /// Optional Y-axis boundary for coordinate-based actions
+    #[serde(default)]
+    pub max_coordinate_y: Option<i64>,
+}
+
+fn default_browser_computer_use_endpoint() -> String {
+    "http://127.0.0.1:8787/v1/actions".into()
+}
+
+fn default_browser_computer_use_timeout_ms() -> u64 {
+    15_000
+}
+
+impl Default for BrowserComputerUseConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_browser_computer_use_endpoint(),
+            api_key: None,
+            timeout_ms: default_browser_computer_use_timeout_ms(),
+            allow_remote_endpoint: false,
+            window_allowlist: Vec::new(),
+            max_coordinate_x: None,
+            max_coordinate_y: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserConfig {
+    /// Enable `browser_open` tool (opens URLs in Brave without scraping)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Allowed domains for `browser_open` (exact or subdomain match)
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Browser session name (for agent-browser automation)
+    #[serde(default)]
+    pub session_name: Option<String>,
+    /// Browser automation backend: "agent_browser" | "rust_native" | "computer_use" | "auto"
+    #[serde(default = "default_browser_backend")]
+    pub backend: String,
+    /// Headless mode for rust-native backend
+    #[serde(default = "default_true")]
+    pub native_headless: bool,
+    /// WebDriver endpoint URL for rust-native backend (e.g. http://127.0.0.1:9515)
+    #[serde(default = "default_browser_webdriver_url")]
+    pub native_webdriver_url: String,
+    /// Optional Chrome/Chromium executable path for rust-native backend
+    #[serde(default)]
+    pub native_chrome_path: Option<String>,
+    /// Computer-use sidecar configuration
+    #[serde(default)]
+    pub computer_use: BrowserComputerUseConfig,
+}
+
+fn default_browser_backend() -> String {
+    "agent_browser".into()
+}
+
+fn default_browser_webdriver_url() -> String {
+    "http://127.0.0.1:9515".into()
+}
+
+impl Default for BrowserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_domains: Vec::new(),
+            session_name: None,
+            backend: default_browser_backend(),
+            native_headless: default_true(),
+            native_webdriver_url: default_browser_webdriver_url(),
+            native_chrome_path: None,
+            computer_use: BrowserComputerUseConfig::default(),
+        }
+    }
+}
+
+// ── HTTP request tool ───────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HttpRequestConfig {
+    /// Enable `http_request` tool for API interactions
+    #[serde(default)]
+    pub enabled: bool,
+    /// Allowed domains for HTTP requests (exact or subdomain match)
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Maximum response size in bytes (default: 1MB)
+    #[serde(default = "default_http_max_response_size")]
+    pub max_response_size: usize,
+    /// Request timeout in seconds (default: 30)
+    #[serde(default = "default_http_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_http_max_response_size() -> usize {
+    1_000_000 // 1MB
+}
+
+fn default_http_timeout_secs() -> u64 {
+    30
+}
+
+// ── Memory ───────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct MemoryConfig {
+    /// "sqlite" | "lucid" | "markdown" | "postgres" | "none" (`none` = explicit no-op memory)
+    pub backend: String,
+    /// Auto-save conversation context to memory
+    pub auto_save: bool,
+    /// Run memory/session hygiene (archiving + retention cleanup)
+    #[serde(default = "default_hygiene_enabled")]
+    pub hygiene_enabled: bool,
+    /// Archive daily/session files older than this many days
+    #[serde(default = "default_archive_after_days")]
+    pub archive_after_days: u32,
+    /// Purge archived files older than this many days
+    #[serde(default = "default_purge_after_days")]
+    pub purge_after_days: u32,
+    /// For sqlite backend: prune conversation rows older than this many days
+    #[serde(default = "default_conversation_retention_days")]
+    pub conversation_retention_days: u32,
+    /// Embedding provider: "none" | "openai" | "custom:URL"
+    #[serde(default = "default_embedding_provider")]
+    pub embedding_provider: String,
+    /// Embedding model name (e.g. "text-embedding-3-small")
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+    /// Embedding vector dimensions
+    #[serde(default = "default_embedding_dims")]
+    pub embedding_dimensions: usize,
+    /// Weight for vector similarity in hybrid search (0.0–1.0)
+    #[serde(default = "default_vector_weight")]
+    pub vector_weight: f64,
+    /// Weight for keyword BM25 in hybrid search (0.0–1.0)
+    #[serde(default = "default_keyword_weight")]
+    pub keyword_weight: f64,
+    /// Max embedding cache entries before LRU eviction
+    #[serde(default = "default_cache_size")]
+    pub embedding_cache_size: usize,
+    /// Max tokens per chunk for document splitting
+    #[serde(default = "default_chunk_size")]
+    pub chunk_max_tokens: usize,
+
+    // ── Response Cache (saves tokens on repeated prompts) ──────
+    /// Enable LLM response caching to avoid paying for duplicate prompts
+    #[serde(default)]
+    pub response_cache_enabled: bool,
+    /// TTL in minutes for cached responses (default: 60)
+    #[serde(default = "default_response_cache_ttl")]
+    pub response_cache_ttl_minutes: u32,
+    /// Max number of cached responses before LRU eviction (default: 5000)
+    #[serde(default = "default_response_cache_max")]
+    pub response_cache_max_entries: usize,
+
+    // ── Memory Snapshot (soul backup to Markdown) ─────────────
+    /// Enable periodic export of core memories to MEMORY_SNAPSHOT.md
+    #[serde(default)]
+    pub snapshot_enabled: bool,
+    /// Run snapshot during hygiene passes (heartbeat-driven)
+    #[serde(default)]
+    pub snapshot_on_hygiene: bool,
+    /// Auto-hydrate from MEMORY_SNAPSHOT.md when brain.db is missing
+    #[serde(default = "default_true")]
+    pub auto_hydrate: bool,
+
+    // ── Postgres (shared memory, `--features postgres`) ─────────
+    /// Connection URL for backend = "postgres", e.g.
+    /// `postgres://zeroclaw:secret@db:5432/zeroclaw`. Encrypted on save.
+    #[serde(default)]
+    pub postgres_url: Option<String>,
+}
+
+fn default_embedding_provider() -> String {
+    "none".into()
+}
+fn default_hygiene_enabled() -> bool {
+    true
+}
+fn default_archive_after_days() -> u32 {
+    7
+}
+fn default_purge_after_days() -> u32 {
+    30
+}
+fn default_conversation_retention_days() -> u32 {
+    30
+}
+fn default_embedding_model() -> String {
+    "text-embedding-3-small".into()
+}
+fn default_embedding_dims() -> usize {
+    1536
+}
+fn default_vector_weight() -> f64 {
+    0.7
+}
+fn default_keyword_weight() -> f64 {
+    0.3
+}
+fn default_cache_size() -> usize {
+    10_000
+}
+fn default_chunk_size() -> usize {
+    512
+}
+fn default_response_cache_ttl() -> u32 {
+    60
+}
+fn default_response_cache_max() -> usize {
+    5_000
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            backend: "sqlite".into(),
+            auto_save: true,
+            hygiene_enabled: default_hygiene_enabled(),
+            archive_after_days: default_archive_after_days(),
+            purge_after_days: default_purge_after_days(),
+            conversation_retention_days: default_conversation_retention_days(),
+            embedding_provider: default_embedding_provider(),
+            embedding_model: default_embedding_model(),
+            embedding_dimensions: default_embedding_dims(),
+            vector_weight: default_vector_weight(),
+            keyword_weight: default_keyword_weight(),
+            embedding_cache_size: default_cache_size(),
+            chunk_max_tokens: default_chunk_size(),
+            response_cache_enabled: false,
+            response_cache_ttl_minutes: default_response_cache_ttl(),
+            response_cache_max_entries: default_response_cache_max(),
+            snapshot_enabled: false,
+            snapshot_on_hygiene: false,
+            auto_hydrate: true,
+            postgres_url: None,
+        }
+    }
+}
+
+// ── Observability ─────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservabilityConfig {
+    /// "none" | "log" | "prometheus" | "otel"
+    pub backend: String,
+
+    /// OTLP endpoint (e.g. "http://localhost:4318"). Only used when backend = "otel".
+    #[serde(default)]
+    pub otel_endpoint: Option<String>,
+
+    /// Service name reported to the OTel collector. Defaults to "zeroclaw".
+    #[serde(default)]
+    pub otel_service_name: Option<String>,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            backend: "none".into(),
+            otel_endpoint: None,
+            otel_service_name: None,
+        }
+    }
+}
+
+// ── Autonomy / Security ──────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutonomyConfig {
+    pub level: AutonomyLevel,
+    pub workspace_only: bool,
+    pub allowed_commands: Vec<String>,
+    pub forbidden_paths: Vec<String>,
+    pub max_actions_per_hour: u32,
+    pub max_cost_per_day_cents: u32,
+
+    /// Require explicit approval for medium-risk shell commands.
+    #[serde(default = "default_true")]
+    pub require_approval_for_medium_risk: bool,
+
+    /// Block high-risk shell commands even if allowlisted.
+    #[serde(default = "default_true")]
+    pub block_high_risk_commands: bool,
+}
+
+impl Default for AutonomyConfig {
+    fn default() -> Self {
+        Self {
+            level: AutonomyLevel::Supervised,
+            workspace_only: true,
+            allowed_commands: vec![
+                "git".into(),
+                "npm".into(),
+                "cargo".into(),
+                "ls".into(),
+                "cat".into(),
+                "grep".into(),
+                "find".into(),
+                "echo".into(),
+                "pwd".into(),
+                "wc".into(),
+                "head".into(),
+                "tail".into(),
+            ],
+            forbidden_paths: vec![
+                "/etc".into(),
+                "/root".into(),
+                "/home".into(),
+                "/usr".into(),
+                "/bin".into(),
+                "/sbin".into(),
+                "/lib".into(),
+                "/opt".into(),
                 "/boot".into(),
                 "/dev".into(),
                 "/proc".into(),
@@ -931,390 +2905,1100 @@ impl Default for AutonomyConfig {
     }
 }
 
-// ── Runtime ──────────────────────────────────────────────────────
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RuntimeConfig {
-    /// Runtime kind (`native` | `docker`).
-    #[serde(default = "default_runtime_kind")]
-    pub kind: String,
-
-    /// Docker runtime settings (used when `kind = "docker"`).
+// ── Runtime ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeConfig {
+    /// Runtime kind (`native` | `docker`).
+    #[serde(default = "default_runtime_kind")]
+    pub kind: String,
+
+    /// Docker runtime settings (used when `kind = "docker"`).
+    #[serde(default)]
+    pub docker: DockerRuntimeConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerRuntimeConfig {
+    /// Runtime image used to execute shell commands.
+    #[serde(default = "default_docker_image")]
+    pub image: String,
+
+    /// Docker network mode (`none`, `bridge`, etc.).
+    #[serde(default = "default_docker_network")]
+    pub network: String,
+
+    /// Optional memory limit in MB (`None` = no explicit limit).
+    #[serde(default = "default_docker_memory_limit_mb")]
+    pub memory_limit_mb: Option<u64>,
+
+    /// Optional CPU limit (`None` = no explicit limit).
+    #[serde(default = "default_docker_cpu_limit")]
+    pub cpu_limit: Option<f64>,
+
+    /// Mount root filesystem as read-only.
+    #[serde(default = "default_true")]
+    pub read_only_rootfs: bool,
+
+    /// Mount configured workspace into `/workspace`.
+    #[serde(default = "default_true")]
+    pub mount_workspace: bool,
+
+    /// Optional workspace root allowlist for Docker mount validation.
+    #[serde(default)]
+    pub allowed_workspace_roots: Vec<String>,
+}
+
+fn default_runtime_kind() -> String {
+    "native".into()
+}
+
+fn default_docker_image() -> String {
+    "alpine:3.20".into()
+}
+
+fn default_docker_network() -> String {
+    "none".into()
+}
+
+fn default_docker_memory_limit_mb() -> Option<u64> {
+    Some(512)
+}
+
+fn default_docker_cpu_limit() -> Option<f64> {
+    Some(1.0)
+}
+
+impl Default for DockerRuntimeConfig {
+    fn default() -> Self {
+        Self {
+            image: default_docker_image(),
+            network: default_docker_network(),
+            memory_limit_mb: default_docker_memory_limit_mb(),
+            cpu_limit: default_docker_cpu_limit(),
+            read_only_rootfs: true,
+            mount_workspace: true,
+            allowed_workspace_roots: Vec::new(),
+        }
+    }
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            kind: default_runtime_kind(),
+            docker: DockerRuntimeConfig::default(),
+        }
+    }
+}
+
+// ── Reliability / supervision ────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReliabilityConfig {
+    /// Retries per provider before failing over.
+    #[serde(default = "default_provider_retries")]
+    pub provider_retries: u32,
+    /// Base backoff (ms) for provider retry delay.
+    #[serde(default = "default_provider_backoff_ms")]
+    pub provider_backoff_ms: u64,
+    /// Fallback provider chain (e.g. `["anthropic", "openai"]`).
+    #[serde(default)]
+    pub fallback_providers: Vec<String>,
+    /// Additional API keys for round-robin rotation on rate-limit (429) errors.
+    /// The primary `api_key` is always tried first; these are extras.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Per-model fallback chains. When a model fails, try these alternatives in order.
+    /// Example: `{ "claude-opus-4-20250514" = ["claude-sonnet-4-20250514", "gpt-4o"] }`
+    #[serde(default)]
+    pub model_fallbacks: std::collections::HashMap<String, Vec<String>>,
+    /// Initial backoff for channel/daemon restarts.
+    #[serde(default = "default_channel_backoff_secs")]
+    pub channel_initial_backoff_secs: u64,
+    /// Max backoff for channel/daemon restarts.
+    #[serde(default = "default_channel_backoff_max_secs")]
+    pub channel_max_backoff_secs: u64,
+    /// Scheduler polling cadence in seconds.
+    #[serde(default = "default_scheduler_poll_secs")]
+    pub scheduler_poll_secs: u64,
+    /// Max retries for cron job execution attempts.
+    #[serde(default = "default_scheduler_retries")]
+    pub scheduler_retries: u32,
+    /// Fail fast on providers and channel send APIs that keep failing.
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+fn default_provider_retries() -> u32 {
+    2
+}
+
+fn default_provider_backoff_ms() -> u64 {
+    500
+}
+
+fn default_channel_backoff_secs() -> u64 {
+    2
+}
+
+fn default_channel_backoff_max_secs() -> u64 {
+    60
+}
+
+fn default_scheduler_poll_secs() -> u64 {
+    15
+}
+
+fn default_scheduler_retries() -> u32 {
+    2
+}
+
+impl Default for ReliabilityConfig {
+    fn default() -> Self {
+        Self {
+            provider_retries: default_provider_retries(),
+            provider_backoff_ms: default_provider_backoff_ms(),
+            fallback_providers: Vec::new(),
+            api_keys: Vec::new(),
+            model_fallbacks: std::collections::HashMap::new(),
+            channel_initial_backoff_secs: default_channel_backoff_secs(),
+            channel_max_backoff_secs: default_channel_backoff_max_secs(),
+            scheduler_poll_secs: default_scheduler_poll_secs(),
+            scheduler_retries: default_scheduler_retries(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+        }
+    }
+}
+
+/// Circuit breakers around LLM providers and channel send APIs: after
+/// `failure_threshold` consecutive failures a dependency is skipped for
+/// `open_secs`, then `half_open_probes` calls test whether it recovered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Consecutive failures that open the circuit (default: 5)
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds an open circuit fails calls before probing (default: 30)
+    #[serde(default = "default_circuit_open_secs")]
+    pub open_secs: u64,
+    /// Calls let through at once while probing (default: 1)
+    #[serde(default = "default_circuit_half_open_probes")]
+    pub half_open_probes: u32,
+}
+
+fn default_circuit_failure_threshold() -> u32 {
+    5
+}
+
+fn default_circuit_open_secs() -> u64 {
+    30
+}
+
+fn default_circuit_half_open_probes() -> u32 {
+    1
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_threshold: default_circuit_failure_threshold(),
+            open_secs: default_circuit_open_secs(),
+            half_open_probes: default_circuit_half_open_probes(),
+        }
+    }
+}
+
+// ── Scheduler ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// Enable the built-in scheduler loop.
+    #[serde(default = "default_scheduler_enabled")]
+    pub enabled: bool,
+    /// Maximum number of persisted scheduled tasks.
+    #[serde(default = "default_scheduler_max_tasks")]
+    pub max_tasks: usize,
+    /// Maximum tasks executed per scheduler polling cycle.
+    #[serde(default = "default_scheduler_max_concurrent")]
+    pub max_concurrent: usize,
+}
+
+fn default_scheduler_enabled() -> bool {
+    true
+}
+
+fn default_scheduler_max_tasks() -> usize {
+    64
+}
+
+fn default_scheduler_max_concurrent() -> usize {
+    4
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_scheduler_enabled(),
+            max_tasks: default_scheduler_max_tasks(),
+            max_concurrent: default_scheduler_max_concurrent(),
+        }
+    }
+}
+
+// ── Model routing ────────────────────────────────────────────────
+
+/// Route a task hint to a specific provider + model.
+///
+/// ```toml
+/// [[model_routes]]
+/// hint = "reasoning"
+/// provider = "openrouter"
+/// model = "anthropic/claude-opus-4-20250514"
+///
+/// [[model_routes]]
+/// hint = "fast"
+/// provider = "groq"
+/// model = "llama-3.3-70b-versatile"
+/// ```
+///
+/// Usage: pass `hint:reasoning` as the model parameter to route the request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRouteConfig {
+    /// Task hint name (e.g. "reasoning", "fast", "code", "summarize")
+    pub hint: String,
+    /// Provider to route to (must match a known provider name)
+    pub provider: String,
+    /// Model to use with that provider
+    pub model: String,
+    /// Optional API key override for this route's provider
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+// ── Heartbeat ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 30,
+        }
+    }
+}
+
+// ── Cron ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_max_run_history")]
+    pub max_run_history: u32,
+}
+
+fn default_max_run_history() -> u32 {
+    50
+}
+
+impl Default for CronConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_run_history: default_max_run_history(),
+        }
+    }
+}
+
+// ── Tunnel ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunnelConfig {
+    /// "none", "cloudflare", "tailscale", "ngrok", "custom"
+    pub provider: String,
+
+    #[serde(default)]
+    pub cloudflare: Option<CloudflareTunnelConfig>,
+
+    #[serde(default)]
+    pub tailscale: Option<TailscaleTunnelConfig>,
+
+    #[serde(default)]
+    pub ngrok: Option<NgrokTunnelConfig>,
+
+    #[serde(default)]
+    pub custom: Option<CustomTunnelConfig>,
+}
+
+impl Default for TunnelConfig {
+    fn default() -> Self {
+        Self {
+            provider: "none".into(),
+            cloudflare: None,
+            tailscale: None,
+            ngrok: None,
+            custom: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudflareTunnelConfig {
+    /// Cloudflare Tunnel token (from Zero Trust dashboard)
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailscaleTunnelConfig {
+    /// Use Tailscale Funnel (public internet) vs Serve (tailnet only)
+    #[serde(default)]
+    pub funnel: bool,
+    /// Optional hostname override
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NgrokTunnelConfig {
+    /// ngrok auth token
+    pub auth_token: String,
+    /// Optional custom domain
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTunnelConfig {
+    /// Command template to start the tunnel. Use {port} and {host} placeholders.
+    /// Example: "bore local {port} --to bore.pub"
+    pub start_command: String,
+    /// Optional URL to check tunnel health
+    pub health_url: Option<String>,
+    /// Optional regex to extract public URL from command stdout
+    pub url_pattern: Option<String>,
+}
+
+// ── Channels ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelsConfig {
+    pub cli: bool,
+    pub telegram: Option<TelegramConfig>,
+    pub discord: Option<DiscordConfig>,
+    pub slack: Option<SlackConfig>,
+    pub webhook: Option<WebhookConfig>,
+    pub imessage: Option<IMessageConfig>,
+    pub matrix: Option<MatrixConfig>,
+    pub signal: Option<SignalConfig>,
+    pub whatsapp: Option<WhatsAppConfig>,
+    pub email: Option<crate::channels::email_channel::EmailConfig>,
+    pub irc: Option<IrcConfig>,
+    pub lark: Option<LarkConfig>,
+    pub dingtalk: Option<DingTalkConfig>,
+    pub qq: Option<QQConfig>,
+    pub nostr: Option<NostrConfig>,
+    pub local_socket: Option<LocalSocketConfig>,
+    /// Per-channel inbound buffering between the listener and the dispatcher
+    #[serde(default)]
+    pub inbound_queue: InboundQueueConfig,
+    /// Conversation history kept per chat, with idle expiry
+    #[serde(default)]
+    pub sessions: SessionConfig,
+    /// Extra named instances of channel types, e.g. a second QQ bot
+    #[serde(default)]
+    pub accounts: Vec<ChannelAccountConfig>,
+    /// Tags per channel name; `tag:<tag>` then addresses every tagged
+    /// channel in delivery targets, broadcasts and middleware overrides
+    #[serde(default)]
+    pub tags: HashMap<String, ChannelTagsConfig>,
+    /// Retry failed replies on the user's linked accounts
+    #[serde(default)]
+    pub failover: FailoverConfig,
+    /// Network timeouts and the per-message processing deadline
+    #[serde(default)]
+    pub timeouts: NetworkTimeoutsConfig,
+    /// Connection pooling, keep-alive, HTTP version and CA certificates of
+    /// channel HTTP clients
+    #[serde(default)]
+    pub http: HttpClientsConfig,
+    /// Stream agent replies into the chat while they are generated
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    /// Worker pool that processes inbound messages
+    #[serde(default)]
+    pub dispatch: DispatchConfig,
+    /// Operational commands processed ahead of chat
+    #[serde(default)]
+    pub priority: PriorityConfig,
+    /// Times proactive notifications wait for instead of going out
+    #[serde(default)]
+    pub quiet_hours: QuietHoursConfig,
+    /// Confirmed broadcasts to tagged chats with `/announce`
+    #[serde(default)]
+    pub announcements: AnnouncementsConfig,
+    /// Log replies instead of sending them
+    #[serde(default)]
+    pub dry_run: DryRunConfig,
+    /// Channels that answer a copy of another channel's traffic without
+    /// delivering, to compare a persona or model against it
+    #[serde(default)]
+    pub shadows: Vec<ShadowChannelConfig>,
+}
+
+impl Default for ChannelsConfig {
+    fn default() -> Self {
+        Self {
+            cli: true,
+            telegram: None,
+            discord: None,
+            slack: None,
+            webhook: None,
+            imessage: None,
+            matrix: None,
+            signal: None,
+            whatsapp: None,
+            email: None,
+            irc: None,
+            lark: None,
+            dingtalk: None,
+            qq: None,
+            nostr: None,
+            local_socket: None,
+            inbound_queue: InboundQueueConfig::default(),
+            sessions: SessionConfig::default(),
+            accounts: Vec::new(),
+            tags: HashMap::new(),
+            failover: FailoverConfig::default(),
+            timeouts: NetworkTimeoutsConfig::default(),
+            http: HttpClientsConfig::default(),
+            streaming: StreamingConfig::default(),
+            dispatch: DispatchConfig::default(),
+            priority: PriorityConfig::default(),
+            quiet_hours: QuietHoursConfig::default(),
+            announcements: AnnouncementsConfig::default(),
+            dry_run: DryRunConfig::default(),
+            shadows: Vec::new(),
+        }
+    }
+}
+
+/// Tags of one channel (`[channels_config.tags.<channel>]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelTagsConfig {
     #[serde(default)]
-    pub docker: DockerRuntimeConfig,
+    pub tags: Vec<String>,
+    /// Recipient used when a `tag:` target leaves the recipient empty
+    #[serde(default)]
+    pub default_recipient: Option<String>,
 }
 
+/// Reply failover across linked identities (`[channels_config.failover]`).
+/// Needs `[identity_linking]`: the secondary routes are the other accounts
+/// linked to the sender's profile.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DockerRuntimeConfig {
-    /// Runtime image used to execute shell commands.
-    #[serde(default = "default_docker_image")]
-    pub image: String,
-
-    /// Docker network mode (`none`, `bridge`, etc.).
-    #[serde(default = "default_docker_network")]
-    pub network: String,
-
-    /// Optional memory limit in MB (`None` = no explicit limit).
-    #[serde(default = "default_docker_memory_limit_mb")]
-    pub memory_limit_mb: Option<u64>,
-
-    /// Optional CPU limit (`None` = no explicit limit).
-    #[serde(default = "default_docker_cpu_limit")]
-    pub cpu_limit: Option<f64>,
-
-    /// Mount root filesystem as read-only.
-    #[serde(default = "default_true")]
-    pub read_only_rootfs: bool,
-
-    /// Mount configured workspace into `/workspace`.
-    #[serde(default = "default_true")]
-    pub mount_workspace: bool,
-
-    /// Optional workspace root allowlist for Docker mount validation.
+pub struct FailoverConfig {
+    /// Retry failed replies on a linked secondary channel (default: false)
     #[serde(default)]
-    pub allowed_workspace_roots: Vec<String>,
+    pub enabled: bool,
+    /// Consecutive send failures after which a channel is skipped and
+    /// replies go straight to a secondary (default: 3)
+    #[serde(default = "default_failover_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long a tripped channel is skipped before it is tried again
+    /// (default: 60)
+    #[serde(default = "default_failover_retry_after_secs")]
+    pub retry_after_secs: u64,
+    /// Secondary channels in priority order; empty allows every linked
+    /// channel
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Recipient templates per channel, `{sender}` being the linked
+    /// sender id (e.g. `qq = "user:{sender}"`); default is the sender id
+    #[serde(default)]
+    pub recipients: HashMap<String, String>,
 }
 
-fn default_runtime_kind() -> String {
-    "native".into()
+fn default_failover_failure_threshold() -> u32 {
+    3
 }
 
-fn default_docker_image() -> String {
-    "alpine:3.20".into()
+fn default_failover_retry_after_secs() -> u64 {
+    60
 }
 
-fn default_docker_network() -> String {
-    "none".into()
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: default_failover_failure_threshold(),
+            retry_after_secs: default_failover_retry_after_secs(),
+            channels: Vec::new(),
+            recipients: HashMap::new(),
+        }
+    }
 }
 
-fn default_docker_memory_limit_mb() -> Option<u64> {
-    Some(512)
+/// An additional named channel instance (`[[channels_config.accounts]]`).
+/// The name replaces the type name everywhere a channel is addressed: in
+/// `ChannelMessage.channel`, middleware rules and delivery targets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelAccountConfig {
+    /// Unique instance name (e.g. "qq-sandbox")
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: ChannelAccountKind,
 }
 
-fn default_docker_cpu_limit() -> Option<f64> {
-    Some(1.0)
+/// Channel type and settings of an account, tagged by `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelAccountKind {
+    Telegram(TelegramConfig),
+    Discord(DiscordConfig),
+    Slack(SlackConfig),
+    Qq(QQConfig),
 }
 
-impl Default for DockerRuntimeConfig {
-    fn default() -> Self {
-        Self {
-            image: default_docker_image(),
-            network: default_docker_network(),
-            memory_limit_mb: default_docker_memory_limit_mb(),
-            cpu_limit: default_docker_cpu_limit(),
-            read_only_rootfs: true,
-            mount_workspace: true,
-            allowed_workspace_roots: Vec::new(),
+impl ChannelAccountKind {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Telegram(_) => "telegram",
+            Self::Discord(_) => "discord",
+            Self::Slack(_) => "slack",
+            Self::Qq(_) => "qq",
         }
     }
 }
 
-impl Default for RuntimeConfig {
-    fn default() -> Self {
-        Self {
-            kind: default_runtime_kind(),
-            docker: DockerRuntimeConfig::default(),
+/// What to do when a channel's inbound queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InboundOverflowPolicy {
+    /// Discard the oldest buffered message to make room
+    DropOldest,
+    /// Discard the incoming message
+    DropNew,
+    /// Wait for space (the channel's read loop pauses)
+    #[default]
+    Block,
+}
+
+impl InboundOverflowPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DropOldest => "drop_oldest",
+            Self::DropNew => "drop_new",
+            Self::Block => "block",
         }
     }
 }
 
-// ── Reliability / supervision ────────────────────────────────────
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReliabilityConfig {
-    /// Retries per provider before failing over.
-    #[serde(default = "default_provider_retries")]
-    pub provider_retries: u32,
-    /// Base backoff (ms) for provider retry delay.
-    #[serde(default = "default_provider_backoff_ms")]
-    pub provider_backoff_ms: u64,
-    /// Fallback provider chain (e.g. `["anthropic", "openai"]`).
-    #[serde(default)]
-    pub fallback_providers: Vec<String>,
-    /// Additional API keys for round-robin rotation on rate-limit (429) errors.
-    /// The primary `api_key` is always tried first; these are extras.
-    #[serde(default)]
-    pub api_keys: Vec<String>,
-    /// Per-model fallback chains. When a model fails, try these alternatives in order.
-    /// Example: `{ "claude-opus-4-20250514" = ["claude-sonnet-4-20250514", "gpt-4o"] }`
+pub struct InboundQueueConfig {
+    /// Messages buffered per channel before the overflow policy applies
+    #[serde(default = "default_inbound_queue_capacity")]
+    pub capacity: usize,
+    /// "drop_oldest" | "drop_new" | "block"
     #[serde(default)]
-    pub model_fallbacks: std::collections::HashMap<String, Vec<String>>,
-    /// Initial backoff for channel/daemon restarts.
-    #[serde(default = "default_channel_backoff_secs")]
-    pub channel_initial_backoff_secs: u64,
-    /// Max backoff for channel/daemon restarts.
-    #[serde(default = "default_channel_backoff_max_secs")]
-    pub channel_max_backoff_secs: u64,
-    /// Scheduler polling cadence in seconds.
-    #[serde(default = "default_scheduler_poll_secs")]
-    pub scheduler_poll_secs: u64,
-    /// Max retries for cron job execution attempts.
-    #[serde(default = "default_scheduler_retries")]
-    pub scheduler_retries: u32,
+    pub overflow: InboundOverflowPolicy,
 }
 
-fn default_provider_retries() -> u32 {
-    2
+fn default_inbound_queue_capacity() -> usize {
+    256
 }
 
-fn default_provider_backoff_ms() -> u64 {
-    500
+impl Default for InboundQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_inbound_queue_capacity(),
+            overflow: InboundOverflowPolicy::default(),
+        }
+    }
 }
 
-fn default_channel_backoff_secs() -> u64 {
-    2
+/// Timeouts of channel network calls (`[channels_config.timeouts]`), so a
+/// hung connection fails instead of stalling a listener or sender.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkTimeoutsConfig {
+    /// TCP/TLS connect timeout of HTTP requests in seconds (default: 10)
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_secs: u64,
+    /// Whole-request timeout of HTTP calls in seconds, long polls excepted
+    /// (default: 30)
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_secs: u64,
+    /// Timeout of WebSocket gateway handshakes in seconds (default: 15)
+    #[serde(default = "default_gateway_connect_timeout_secs")]
+    pub gateway_connect_secs: u64,
+    /// Longest an inbound message may take end to end, from transcription
+    /// to the last reply, in seconds; 0 disables the deadline (default: 300)
+    #[serde(default = "default_message_deadline_secs")]
+    pub message_deadline_secs: u64,
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
 }
 
-fn default_channel_backoff_max_secs() -> u64 {
-    60
+fn default_request_timeout_secs() -> u64 {
+    30
 }
 
-fn default_scheduler_poll_secs() -> u64 {
+fn default_gateway_connect_timeout_secs() -> u64 {
     15
 }
 
-fn default_scheduler_retries() -> u32 {
-    2
+fn default_message_deadline_secs() -> u64 {
+    300
 }
 
-impl Default for ReliabilityConfig {
+impl Default for NetworkTimeoutsConfig {
     fn default() -> Self {
         Self {
-            provider_retries: default_provider_retries(),
-            provider_backoff_ms: default_provider_backoff_ms(),
-            fallback_providers: Vec::new(),
-            api_keys: Vec::new(),
-            model_fallbacks: std::collections::HashMap::new(),
-            channel_initial_backoff_secs: default_channel_backoff_secs(),
-            channel_max_backoff_secs: default_channel_backoff_max_secs(),
-            scheduler_poll_secs: default_scheduler_poll_secs(),
-            scheduler_retries: default_scheduler_retries(),
+            connect_secs: default_connect_timeout_secs(),
+            request_secs: default_request_timeout_secs(),
+            gateway_connect_secs: default_gateway_connect_timeout_secs(),
+            message_deadline_secs: default_message_deadline_secs(),
         }
     }
 }
 
-// ── Scheduler ────────────────────────────────────────────────────
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SchedulerConfig {
-    /// Enable the built-in scheduler loop.
-    #[serde(default = "default_scheduler_enabled")]
-    pub enabled: bool,
-    /// Maximum number of persisted scheduled tasks.
-    #[serde(default = "default_scheduler_max_tasks")]
-    pub max_tasks: usize,
-    /// Maximum tasks executed per scheduler polling cycle.
-    #[serde(default = "default_scheduler_max_concurrent")]
-    pub max_concurrent: usize,
+/// HTTP version channel clients speak.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpVersionPreference {
+    /// HTTP/1.1 only
+    #[default]
+    Http1,
+    /// HTTP/2 where the server offers it via ALPN, HTTP/1.1 otherwise
+    Auto,
+    /// HTTP/2 only, without negotiation
+    Http2,
 }
 
-fn default_scheduler_enabled() -> bool {
-    true
+/// Connection settings of channel HTTP clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    /// Idle connections kept open per host (default: unlimited)
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Seconds an idle pooled connection is kept (default: 90)
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// Interval of TCP keep-alive probes in seconds; 0 disables them
+    /// (default: 60)
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+    /// Disable Nagle's algorithm (default: true)
+    #[serde(default = "default_true")]
+    pub tcp_nodelay: bool,
+    /// "http1" | "auto" | "http2"
+    #[serde(default)]
+    pub http_version: HttpVersionPreference,
+    /// Interval of HTTP/2 PING frames in seconds, sent on idle connections
+    /// too; 0 disables them (default: 0)
+    #[serde(default)]
+    pub http2_keepalive_secs: u64,
+    /// PEM files of extra root certificates, e.g. a corporate proxy's CA
+    #[serde(default)]
+    pub ca_certs: Vec<PathBuf>,
+    /// Key pinning, root store and protocol version restrictions, applied
+    /// to gateway WebSockets too
+    #[serde(default)]
+    pub tls: ChannelTlsConfig,
 }
 
-fn default_scheduler_max_tasks() -> usize {
-    64
+fn default_pool_idle_timeout_secs() -> u64 {
+    90
 }
 
-fn default_scheduler_max_concurrent() -> usize {
-    4
+fn default_tcp_keepalive_secs() -> u64 {
+    60
 }
 
-impl Default for SchedulerConfig {
+impl Default for HttpClientConfig {
     fn default() -> Self {
         Self {
-            enabled: default_scheduler_enabled(),
-            max_tasks: default_scheduler_max_tasks(),
-            max_concurrent: default_scheduler_max_concurrent(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            tcp_nodelay: true,
+            http_version: HttpVersionPreference::default(),
+            http2_keepalive_secs: 0,
+            ca_certs: Vec::new(),
+            tls: ChannelTlsConfig::default(),
         }
     }
 }
 
-// ── Model routing ────────────────────────────────────────────────
+/// Oldest TLS version channel connections accept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TlsMinVersion {
+    #[default]
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+/// TLS restrictions of a channel's connections. With none set, servers are
+/// verified against the built-in roots plus `ca_certs`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelTlsConfig {
+    /// Base64 SHA-256 hashes of accepted server public keys (SPKI); the
+    /// verified chain must contain one of them
+    #[serde(default)]
+    pub pinned_spki_sha256: Vec<String>,
+    /// PEM files of the only trusted roots, replacing the built-in ones
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+    /// "1.2" | "1.3" (default: "1.2")
+    #[serde(default)]
+    pub min_version: TlsMinVersion,
+}
 
-/// Route a task hint to a specific provider + model.
-///
-/// ```toml
-/// [[model_routes]]
-/// hint = "reasoning"
-/// provider = "openrouter"
-/// model = "anthropic/claude-opus-4-20250514"
-///
-/// [[model_routes]]
-/// hint = "fast"
-/// provider = "groq"
-/// model = "llama-3.3-70b-versatile"
-/// ```
-///
-/// Usage: pass `hint:reasoning` as the model parameter to route the request.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ModelRouteConfig {
-    /// Task hint name (e.g. "reasoning", "fast", "code", "summarize")
-    pub hint: String,
-    /// Provider to route to (must match a known provider name)
-    pub provider: String,
-    /// Model to use with that provider
-    pub model: String,
-    /// Optional API key override for this route's provider
+impl ChannelTlsConfig {
+    /// Whether anything beyond default verification is configured.
+    pub fn is_restricted(&self) -> bool {
+        !self.pinned_spki_sha256.is_empty()
+            || !self.roots.is_empty()
+            || self.min_version != TlsMinVersion::Tls12
+    }
+}
+
+/// HTTP client settings of all channels (`[channels_config.http]`), with
+/// per-channel replacements under `[channels_config.http.channels.<type>]`,
+/// e.g. `telegram` or `qq`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpClientsConfig {
+    #[serde(flatten)]
+    pub default: HttpClientConfig,
     #[serde(default)]
-    pub api_key: Option<String>,
+    pub channels: HashMap<String, HttpClientConfig>,
+}
+
+impl HttpClientsConfig {
+    /// The settings of the `channel` type's client.
+    pub fn for_channel(&self, channel: &str) -> &HttpClientConfig {
+        self.channels.get(channel).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Start a fresh session after this many idle seconds (default: 1800)
+    #[serde(default = "default_session_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// Earlier messages included in each prompt (default: 20, 0 disables history)
+    #[serde(default = "default_session_max_history")]
+    pub max_history: usize,
 }
 
-// ── Heartbeat ────────────────────────────────────────────────────
+fn default_session_idle_timeout_secs() -> u64 {
+    1800
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HeartbeatConfig {
-    pub enabled: bool,
-    pub interval_minutes: u32,
+fn default_session_max_history() -> usize {
+    20
 }
 
-impl Default for HeartbeatConfig {
+impl Default for SessionConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
-            interval_minutes: 30,
+            idle_timeout_secs: default_session_idle_timeout_secs(),
+            max_history: default_session_max_history(),
         }
     }
 }
 
-// ── Cron ────────────────────────────────────────────────────────
-
+/// Streaming of agent replies (`[channels_config.streaming]`). On channels
+/// that edit messages the reply grows in one message; elsewhere it is sent
+/// a paragraph at a time.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CronConfig {
-    #[serde(default = "default_true")]
+pub struct StreamingConfig {
+    #[serde(default)]
     pub enabled: bool,
-    #[serde(default = "default_max_run_history")]
-    pub max_run_history: u32,
+    /// Edit the message after about this many new tokens (default: 20)
+    #[serde(default = "default_streaming_edit_every_tokens")]
+    pub edit_every_tokens: usize,
+    /// Minimum milliseconds between two edits of a message, to stay under
+    /// rate limits (default: 1000)
+    #[serde(default = "default_streaming_min_edit_interval_ms")]
+    pub min_edit_interval_ms: u64,
 }
 
-fn default_max_run_history() -> u32 {
-    50
+fn default_streaming_edit_every_tokens() -> usize {
+    20
 }
 
-impl Default for CronConfig {
+fn default_streaming_min_edit_interval_ms() -> u64 {
+    1000
+}
+
+impl Default for StreamingConfig {
     fn default() -> Self {
         Self {
-            enabled: true,
-            max_run_history: default_max_run_history(),
+            enabled: false,
+            edit_every_tokens: default_streaming_edit_every_tokens(),
+            min_edit_interval_ms: default_streaming_min_edit_interval_ms(),
         }
     }
 }
 
-// ── Tunnel ──────────────────────────────────────────────────────
-
+/// Processing of inbound messages (`[channels_config.dispatch]`). Messages
+/// of one conversation are handled in order; different conversations run
+/// in parallel on a pool of workers.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TunnelConfig {
-    /// "none", "cloudflare", "tailscale", "ngrok", "custom"
-    pub provider: String,
-
+pub struct DispatchConfig {
+    /// Messages processed at once across all channels; 0 sizes the pool by
+    /// the number of channels (default: 0)
     #[serde(default)]
-    pub cloudflare: Option<CloudflareTunnelConfig>,
-
+    pub workers: usize,
+    /// Messages processed at once per channel, so one busy channel can't
+    /// take every worker; 0 for no limit (default: 0)
     #[serde(default)]
-    pub tailscale: Option<TailscaleTunnelConfig>,
-
+    pub max_per_channel: usize,
+    /// Seconds the model and its tools may take to answer one message.
+    /// Generous for on-device models, which are slower than cloud APIs
+    /// (default: 300)
+    #[serde(default = "default_dispatch_llm_timeout_secs")]
+    pub llm_timeout_secs: u64,
+    /// Replacements of `[channels_config.timeouts] message_deadline_secs`
+    /// by channel name; 0 disables the deadline
     #[serde(default)]
-    pub ngrok: Option<NgrokTunnelConfig>,
+    pub message_deadline_secs: HashMap<String, u64>,
+}
 
-    #[serde(default)]
-    pub custom: Option<CustomTunnelConfig>,
+fn default_dispatch_llm_timeout_secs() -> u64 {
+    300
 }
 
-impl Default for TunnelConfig {
+impl Default for DispatchConfig {
     fn default() -> Self {
         Self {
-            provider: "none".into(),
-            cloudflare: None,
-            tailscale: None,
-            ngrok: None,
-            custom: None,
+            workers: 0,
+            max_per_channel: 0,
+            llm_timeout_secs: default_dispatch_llm_timeout_secs(),
+            message_deadline_secs: HashMap::new(),
         }
     }
 }
 
+/// Urgent inbound messages (`[channels_config.priority]`): alert and admin
+/// commands are taken from a channel's queue before chat, don't wait behind
+/// the sender's earlier messages and may use workers chat never gets.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CloudflareTunnelConfig {
-    /// Cloudflare Tunnel token (from Zero Trust dashboard)
-    pub token: String,
+pub struct PriorityConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Commands that are urgent whoever sends them (default: alert
+    /// silences, subscriptions and `/group`)
+    #[serde(default = "default_priority_commands")]
+    pub commands: Vec<String>,
+    /// Operators whose commands are all urgent, as `<channel>:<user id>`
+    #[serde(default)]
+    pub admins: Vec<String>,
+    /// Workers kept for urgent messages, on top of
+    /// `[channels_config.dispatch] workers` (default: 2)
+    #[serde(default = "default_priority_reserved_workers")]
+    pub reserved_workers: usize,
+}
+
+fn default_priority_commands() -> Vec<String> {
+    [
+        "/silence",
+        "/unsilence",
+        "/subscribe",
+        "/unsubscribe",
+        "/subscriptions",
+        "/group",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_priority_reserved_workers() -> usize {
+    2
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            commands: default_priority_commands(),
+            admins: Vec::new(),
+            reserved_workers: default_priority_reserved_workers(),
+        }
+    }
 }
 
+/// Quiet hours (`[channels_config.quiet_hours]`): during a rule's window,
+/// proactive notifications (feeds, alerts, digests, topic notifications) to
+/// its chats are held and sent when the window ends. Urgent notifications
+/// and replies to the user's own messages always go out.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TailscaleTunnelConfig {
-    /// Use Tailscale Funnel (public internet) vs Serve (tailnet only)
+pub struct QuietHoursConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
     #[serde(default)]
-    pub funnel: bool,
-    /// Optional hostname override
-    pub hostname: Option<String>,
+    pub rules: Vec<QuietHoursRule>,
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: Vec::new(),
+        }
+    }
 }
 
+/// One quiet window, e.g. `start = "22:00"`, `end = "07:00"`. A rule for a
+/// recipient wins over one for its whole channel.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NgrokTunnelConfig {
-    /// ngrok auth token
-    pub auth_token: String,
-    /// Optional custom domain
-    pub domain: Option<String>,
+pub struct QuietHoursRule {
+    /// Channel the rule applies to (e.g. "telegram")
+    pub channel: String,
+    /// Chat the rule applies to; unset covers the whole channel
+    #[serde(default)]
+    pub recipient: Option<String>,
+    /// Local start time, `HH:MM`
+    pub start: String,
+    /// Local end time, `HH:MM`; before `start` for windows past midnight
+    pub end: String,
+    /// IANA timezone the times are in (default: UTC)
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
+/// `/announce <tag> <text>` (`[channels_config.announcements]`): an admin
+/// drafts a broadcast to every chat tagged `<tag>`, checks the preview and
+/// confirms it before anything is sent.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CustomTunnelConfig {
-    /// Command template to start the tunnel. Use {port} and {host} placeholders.
-    /// Example: "bore local {port} --to bore.pub"
-    pub start_command: String,
-    /// Optional URL to check tunnel health
-    pub health_url: Option<String>,
-    /// Optional regex to extract public URL from command stdout
-    pub url_pattern: Option<String>,
+pub struct AnnouncementsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Operators who may announce, as `<channel>:<user id>`, besides those
+    /// with the admin role in `[security.roles]`; with neither, `/announce`
+    /// is off
+    #[serde(default)]
+    pub admins: Vec<String>,
+    /// Seconds a draft waits for confirmation (default: 300)
+    #[serde(default = "default_announcements_confirm_secs")]
+    pub confirm_secs: u64,
+    /// Chats sent to at once (default: 8)
+    #[serde(default = "default_announcements_concurrency")]
+    pub concurrency: usize,
+    /// Chats between progress reports (default: 25)
+    #[serde(default = "default_announcements_progress_every")]
+    pub progress_every: usize,
+}
+
+fn default_announcements_confirm_secs() -> u64 {
+    300
 }
 
-// ── Channels ─────────────────────────────────────────────────────
+fn default_announcements_concurrency() -> usize {
+    8
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChannelsConfig {
-    pub cli: bool,
-    pub telegram: Option<TelegramConfig>,
-    pub discord: Option<DiscordConfig>,
-    pub slack: Option<SlackConfig>,
-    pub webhook: Option<WebhookConfig>,
-    pub imessage: Option<IMessageConfig>,
-    pub matrix: Option<MatrixConfig>,
-    pub signal: Option<SignalConfig>,
-    pub whatsapp: Option<WhatsAppConfig>,
-    pub email: Option<crate::channels::email_channel::EmailConfig>,
-    pub irc: Option<IrcConfig>,
-    pub lark: Option<LarkConfig>,
-    pub dingtalk: Option<DingTalkConfig>,
-    pub qq: Option<QQConfig>,
+fn default_announcements_progress_every() -> usize {
+    25
 }
 
-impl Default for ChannelsConfig {
+impl Default for AnnouncementsConfig {
     fn default() -> Self {
         Self {
-            cli: true,
-            telegram: None,
-            discord: None,
-            slack: None,
-            webhook: None,
-            imessage: None,
-            matrix: None,
-            signal: None,
-            whatsapp: None,
-            email: None,
-            irc: None,
-            lark: None,
-            dingtalk: None,
-            qq: None,
+            enabled: true,
+            admins: Vec::new(),
+            confirm_secs: default_announcements_confirm_secs(),
+            concurrency: default_announcements_concurrency(),
+            progress_every: default_announcements_progress_every(),
         }
     }
 }
 
+/// Dry run (`[channels_config.dry_run]`, or `zeroclaw channel start
+/// --dry-run`): channels listen and messages go through the whole pipeline,
+/// but everything a channel would send is logged instead of reaching the
+/// platform.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Replacements of `enabled` by channel name
+    #[serde(default)]
+    pub channels: HashMap<String, bool>,
+}
+
+impl DryRunConfig {
+    /// Whether sends on `channel` are logged instead of delivered.
+    pub fn applies_to(&self, channel: &str) -> bool {
+        self.channels.get(channel).copied().unwrap_or(self.enabled)
+    }
+}
+
+/// A shadow channel (`[[channels_config.shadows]]`): it receives a copy of
+/// every message `source` accepts and answers it through the full pipeline,
+/// but its replies are captured for comparison (`GET /admin/shadows`)
+/// instead of delivered. Commands are not mirrored, tools with side effects
+/// are disabled, and shadow turns are neither auto-saved to memory nor
+/// counted in quotas and analytics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowChannelConfig {
+    /// Name of the shadow; its sessions are kept under it
+    pub name: String,
+    /// Channel whose inbound traffic is mirrored (e.g. "telegram")
+    pub source: String,
+    /// Model answering on the shadow instead of the default one
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Persona answering on the shadow, like `[personas.channels]`
+    #[serde(default)]
+    pub persona: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
+    /// Bot token; may be an `env:`/`file:`/`vault:`/`keyring:` reference
     pub bot_token: String,
     pub allowed_users: Vec<String>,
 }
 
+/// Transport compression of a gateway WebSocket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GatewayCompression {
+    /// Plain JSON text frames
+    #[default]
+    None,
+    /// One zlib stream over the whole connection (`compress=zlib-stream`)
+    Zlib,
+    /// One zstd stream over the whole connection (`compress=zstd-stream`)
+    Zstd,
+}
+
+impl GatewayCompression {
+    /// Value of the gateway URL's `compress` query parameter.
+    pub fn query_param(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Zlib => Some("zlib-stream"),
+            Self::Zstd => Some("zstd-stream"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscordConfig {
+    /// Bot token; may be an `env:`/`file:`/`vault:`/`keyring:` reference
     pub bot_token: String,
     pub guild_id: Option<String>,
     #[serde(default)]
@@ -1327,21 +4011,44 @@ pub struct DiscordConfig {
     /// Other messages in the guild are silently ignored.
     #[serde(default)]
     pub mention_only: bool,
+    /// Send the first gateway heartbeat after a random fraction of the
+    /// interval from Hello, as the gateway docs recommend. Disable for a
+    /// full-interval first beat.
+    #[serde(default = "default_true")]
+    pub heartbeat_jitter: bool,
+    /// Gateway transport compression: "none" (default), "zlib" or "zstd".
+    #[serde(default)]
+    pub compression: GatewayCompression,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlackConfig {
+    /// Bot token; may be an `env:`/`file:`/`vault:`/`keyring:` reference
     pub bot_token: String,
     pub app_token: Option<String>,
     pub channel_id: Option<String>,
     #[serde(default)]
     pub allowed_users: Vec<String>,
+    /// `[oauth.<name>]` client whose sign-in provides the token instead of
+    /// `bot_token`; it is refreshed automatically
+    #[serde(default)]
+    pub oauth: Option<String>,
+    /// Signing secret of the Slack app. When set, messages arrive through
+    /// the events API on the gateway's `POST /slack/events`, verified with
+    /// `X-Slack-Signature`, instead of polling `conversations.history`
+    #[serde(default)]
+    pub signing_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhookConfig {
     pub port: u16,
     pub secret: Option<String>,
+    /// Require requests signed with this secret: `X-Zeroclaw-Timestamp`
+    /// and `X-Zeroclaw-Signature: v1=<hex HMAC-SHA256 of "<timestamp>.<body>">`,
+    /// at most 5 minutes old and not seen before
+    #[serde(default)]
+    pub signing_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1360,7 +4067,14 @@ pub struct MatrixConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignalConfig {
     /// Base URL for the signal-cli HTTP daemon (e.g. "http://127.0.0.1:8686").
+    /// Not needed when `rpc_socket` is set.
+    #[serde(default)]
     pub http_url: String,
+    /// Address of the JSON-RPC socket of `signal-cli daemon --tcp`
+    /// (e.g. "127.0.0.1:7583"). When set, messages are received through a
+    /// `subscribeReceive` subscription on it instead of the HTTP daemon.
+    #[serde(default)]
+    pub rpc_socket: Option<String>,
     /// E.164 phone number of the signal-cli account (e.g. "+1234567890").
     pub account: String,
     /// Optional group ID to filter messages.
@@ -1395,6 +4109,17 @@ pub struct WhatsAppConfig {
     /// Allowed phone numbers (E.164 format: +1234567890) or "*" for all
     #[serde(default)]
     pub allowed_numbers: Vec<String>,
+    /// Approved message template used to reach a user more than 24 hours
+    /// after they last wrote; its body's `{{1}}` parameter gets the text
+    #[serde(default)]
+    pub session_template: Option<String>,
+    /// Language code of `session_template` (default: "en_US")
+    #[serde(default = "default_whatsapp_template_language")]
+    pub template_language: String,
+}
+
+fn default_whatsapp_template_language() -> String {
+    "en_US".into()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1485,6 +4210,52 @@ pub struct SecurityConfig {
     /// Audit logging configuration
     #[serde(default)]
     pub audit: AuditConfig,
+
+    /// Operator roles for admin commands in chat
+    #[serde(default)]
+    pub roles: RolesConfig,
+
+    /// Encryption at rest of stored messages and memory
+    #[serde(default)]
+    pub encryption: StorageEncryptionConfig,
+}
+
+/// Who may run admin commands in chat (`/reload`, `/mute`, `/block`,
+/// `/announce`, `/audit`), as `<channel>:<user id>` (e.g.
+/// "telegram:123456789"). Each role may do everything the roles below it
+/// may. Invocations are recorded to the audit log.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RolesConfig {
+    /// Everything, including `/reload`
+    #[serde(default)]
+    pub owners: Vec<String>,
+    /// Muting channels, announcements and reading the audit log
+    #[serde(default)]
+    pub admins: Vec<String>,
+    /// Blocking and unblocking users
+    #[serde(default)]
+    pub moderators: Vec<String>,
+}
+
+/// AES-256-GCM encryption of the memory store (`memory/brain.db`, which
+/// also keeps auto-saved messages) and the response cache, so a leaked
+/// database file doesn't expose conversations. Keys are 32 bytes, hex or
+/// base64 encoded, inline or a secret reference (`env:`, `file:`, `vault:`,
+/// `keyring:`). To rotate, set a new `key` and move the old one to
+/// `previous_keys`; memories are re-encrypted under the new key when the
+/// store opens (stale cached responses are dropped), after which the old key
+/// can be dropped. Memory snapshots
+/// (`MEMORY_SNAPSHOT.md`) are still written in plaintext.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageEncryptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Key new values are encrypted with
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Earlier keys, only used to read values written before a rotation
+    #[serde(default)]
+    pub previous_keys: Vec<String>,
 }
 
 /// Sandbox configuration for OS-level isolation
@@ -1634,6 +4405,18 @@ pub struct DingTalkConfig {
     pub allowed_users: Vec<String>,
 }
 
+/// Which deployment of a platform's bot API a channel talks to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiEnvironment {
+    #[default]
+    Production,
+    /// The platform's sandbox, for bots under development
+    Sandbox,
+    /// A compatible API at `api_base`
+    Custom,
+}
+
 /// QQ Official Bot configuration (Tencent QQ Bot SDK)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QQConfig {
@@ -1644,6 +4427,52 @@ pub struct QQConfig {
     /// Allowed user IDs. Empty = deny all, "*" = allow all
     #[serde(default)]
     pub allowed_users: Vec<String>,
+    /// Send the first gateway heartbeat after a random fraction of the
+    /// interval from Hello, as the gateway docs recommend. Disable for a
+    /// full-interval first beat.
+    #[serde(default = "default_true")]
+    pub heartbeat_jitter: bool,
+    /// "production" | "sandbox" | "custom" (default: "production"); can be
+    /// switched on a running bot through the admin API
+    #[serde(default)]
+    pub environment: ApiEnvironment,
+    /// API base URL replacing the environment's, e.g. an internal relay;
+    /// required for "custom"
+    #[serde(default)]
+    pub api_base: Option<String>,
+    /// Gateway WebSocket URL replacing the one the API hands out
+    #[serde(default)]
+    pub gateway_url: Option<String>,
+    /// Receive events by HTTP callback on the gateway's `POST /qq`, signed
+    /// with an Ed25519 key derived from `app_secret`, instead of over the
+    /// WebSocket gateway
+    #[serde(default)]
+    pub callback: bool,
+}
+
+/// Nostr direct messages (NIP-04 and NIP-17); needs a build with
+/// `--features channel-nostr`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NostrConfig {
+    /// Bot private key, `nsec1...` or hex; a secret reference keeps it out
+    /// of the config
+    pub private_key: String,
+    /// Relay WebSocket URLs, e.g. "wss://relay.damus.io"
+    pub relays: Vec<String>,
+    /// Allowed sender public keys (`npub1...` or hex). Empty = deny all,
+    /// "*" = allow all
+    #[serde(default)]
+    pub allowed_pubkeys: Vec<String>,
+}
+
+/// Newline-delimited JSON over a Unix socket or Windows named pipe, for
+/// scripts on the same host
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalSocketConfig {
+    /// Socket path (default: `~/.zeroclaw/zeroclaw.sock`, or
+    /// `\\.\pipe\zeroclaw` on Windows)
+    #[serde(default)]
+    pub path: Option<String>,
 }
 
 // ── Config impl ──────────────────────────────────────────────────
@@ -1675,12 +4504,42 @@ impl Default for Config {
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
             gateway: GatewayConfig::default(),
+            admin: AdminConfig::default(),
+            grpc: GrpcConfig::default(),
+            bus: BusConfig::default(),
+            cluster: ClusterConfig::default(),
+            plugins: PluginsConfig::default(),
+            middleware: MiddlewareConfig::default(),
+            transcription: TranscriptionConfig::default(),
+            tts: TtsConfig::default(),
+            vision: VisionConfig::default(),
+            image_generation: ImageGenerationConfig::default(),
+            knowledge: KnowledgeConfig::default(),
+            web_search: WebSearchConfig::default(),
+            feeds: FeedsConfig::default(),
+            ingress: IngressConfig::default(),
+            hooks: HooksConfig::default(),
+            monitor: MonitorConfig::default(),
+            logging: LoggingConfig::default(),
+            ops: OpsConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            uploads: UploadsConfig::default(),
+            media_store: MediaStoreConfig::default(),
+            kv: KvConfig::default(),
+            preferences: PreferencesConfig::default(),
+            reminders: RemindersConfig::default(),
+            groups: GroupsConfig::default(),
+            personas: PersonasConfig::default(),
             composio: ComposioConfig::default(),
             secrets: SecretsConfig::default(),
+            security: SecurityConfig::default(),
+            oauth: HashMap::new(),
             browser: BrowserConfig::default(),
             http_request: HttpRequestConfig::default(),
             identity: IdentityConfig::default(),
+            identity_linking: IdentityLinkingConfig::default(),
             cost: CostConfig::default(),
+            quotas: QuotaConfig::default(),
             peripherals: PeripheralsConfig::default(),
             agents: HashMap::new(),
             hardware: HardwareConfig::default(),
@@ -1755,16 +4614,27 @@ fn encrypt_optional_secret(
     Ok(())
 }
 
+/// Config and workspace directories, following `ZEROCLAW_WORKSPACE`.
+fn resolve_config_and_workspace_dirs() -> Result<(PathBuf, PathBuf)> {
+    match std::env::var("ZEROCLAW_WORKSPACE") {
+        Ok(custom_workspace) if !custom_workspace.is_empty() => {
+            let workspace = PathBuf::from(custom_workspace);
+            Ok((resolve_config_dir_for_workspace(&workspace), workspace))
+        }
+        _ => default_config_and_workspace_dirs(),
+    }
+}
+
 impl Config {
+    /// Path of the config file [`Config::load_or_init`] reads.
+    pub fn default_path() -> Result<PathBuf> {
+        let (zeroclaw_dir, _) = resolve_config_and_workspace_dirs()?;
+        Ok(zeroclaw_dir.join("config.toml"))
+    }
+
     pub fn load_or_init() -> Result<Self> {
         // Resolve workspace first so config loading can follow ZEROCLAW_WORKSPACE.
-        let (zeroclaw_dir, workspace_dir) = match std::env::var("ZEROCLAW_WORKSPACE") {
-            Ok(custom_workspace) if !custom_workspace.is_empty() => {
-                let workspace = PathBuf::from(custom_workspace);
-                (resolve_config_dir_for_workspace(&workspace), workspace)
-            }
-            _ => default_config_and_workspace_dirs()?,
-        };
+        let (zeroclaw_dir, workspace_dir) = resolve_config_and_workspace_dirs()?;
 
         let config_path = zeroclaw_dir.join("config.toml");
 
@@ -1809,11 +4679,18 @@ impl Config {
                 &mut config.browser.computer_use.api_key,
                 "config.browser.computer_use.api_key",
             )?;
+            decrypt_optional_secret(
+                &store,
+                &mut config.memory.postgres_url,
+                "config.memory.postgres_url",
+            )?;
 
             for agent in config.agents.values_mut() {
                 decrypt_optional_secret(&store, &mut agent.api_key, "config.agents.*.api_key")?;
             }
             config.apply_env_overrides();
+            super::validate::ensure_valid(&config, &contents)
+                .with_context(|| format!("Failed to load {}", config_path.display()))?;
             Ok(config)
         } else {
             let mut config = Config::default();
@@ -1835,8 +4712,11 @@ impl Config {
 
     /// Apply environment variable overrides to config
     pub fn apply_env_overrides(&mut self) {
-        // API Key: ZEROCLAW_API_KEY or API_KEY (generic)
-        if let Ok(key) = std::env::var("ZEROCLAW_API_KEY").or_else(|_| std::env::var("API_KEY")) {
+        // API Key: ZEROCLAW_API_KEY, ZEROCLAW_LLM_API_KEY or API_KEY (generic)
+        if let Ok(key) = std::env::var("ZEROCLAW_API_KEY")
+            .or_else(|_| std::env::var("ZEROCLAW_LLM_API_KEY"))
+            .or_else(|_| std::env::var("API_KEY"))
+        {
             if !key.is_empty() {
                 self.api_key = Some(key);
             }
@@ -1914,6 +4794,96 @@ impl Config {
                 }
             }
         }
+
+        self.apply_env_channels();
+    }
+
+    /// Channel credentials from the environment, so a container can run a
+    /// single channel without a config file. A channel missing from the
+    /// file is added with its defaults. `ZEROCLAW_ALLOWED_USERS`
+    /// (comma-separated, `*` for everyone) replaces the allowlist of every
+    /// channel set this way; without it they answer no one.
+    fn apply_env_channels(&mut self) {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let allowed_users: Option<Vec<String>> = var("ZEROCLAW_ALLOWED_USERS").map(|users| {
+            users
+                .split(',')
+                .map(str::trim)
+                .filter(|user| !user.is_empty())
+                .map(str::to_string)
+                .collect()
+        });
+        let channels = &mut self.channels_config;
+
+        if let (Some(app_id), Some(app_secret)) =
+            (var("ZEROCLAW_QQ_APP_ID"), var("ZEROCLAW_QQ_SECRET"))
+        {
+            let qq = channels.qq.get_or_insert_with(|| QQConfig {
+                app_id: String::new(),
+                app_secret: String::new(),
+                allowed_users: Vec::new(),
+                heartbeat_jitter: true,
+                environment: ApiEnvironment::default(),
+                api_base: None,
+                gateway_url: None,
+                callback: false,
+            });
+            qq.app_id = app_id;
+            qq.app_secret = app_secret;
+            if let Some(users) = &allowed_users {
+                qq.allowed_users.clone_from(users);
+            }
+        }
+
+        if let Some(bot_token) = var("ZEROCLAW_TELEGRAM_BOT_TOKEN") {
+            let telegram = channels.telegram.get_or_insert_with(|| TelegramConfig {
+                bot_token: String::new(),
+                allowed_users: Vec::new(),
+            });
+            telegram.bot_token = bot_token;
+            if let Some(users) = &allowed_users {
+                telegram.allowed_users.clone_from(users);
+            }
+        }
+
+        if let Some(bot_token) = var("ZEROCLAW_DISCORD_BOT_TOKEN") {
+            let discord = channels.discord.get_or_insert_with(|| DiscordConfig {
+                bot_token: String::new(),
+                guild_id: None,
+                allowed_users: Vec::new(),
+                listen_to_bots: false,
+                mention_only: false,
+                heartbeat_jitter: true,
+                compression: GatewayCompression::default(),
+            });
+            discord.bot_token = bot_token;
+            if let Some(users) = &allowed_users {
+                discord.allowed_users.clone_from(users);
+            }
+        }
+
+        if let Some(bot_token) = var("ZEROCLAW_SLACK_BOT_TOKEN") {
+            let slack = channels.slack.get_or_insert_with(|| SlackConfig {
+                bot_token: String::new(),
+                app_token: None,
+                channel_id: None,
+                allowed_users: Vec::new(),
+                oauth: None,
+                signing_secret: None,
+            });
+            slack.bot_token = bot_token;
+            if let Some(app_token) = var("ZEROCLAW_SLACK_APP_TOKEN") {
+                slack.app_token = Some(app_token);
+            }
+            if let Some(users) = &allowed_users {
+                slack.allowed_users.clone_from(users);
+            }
+        }
     }
 
     pub fn save(&self) -> Result<()> {
@@ -1937,6 +4907,11 @@ impl Config {
             &mut config_to_save.browser.computer_use.api_key,
             "config.browser.computer_use.api_key",
         )?;
+        encrypt_optional_secret(
+            &store,
+            &mut config_to_save.memory.postgres_url,
+            "config.memory.postgres_url",
+        )?;
 
         for agent in config_to_save.agents.values_mut() {
             encrypt_optional_secret(&store, &mut agent.api_key, "config.agents.*.api_key")?;
@@ -2188,17 +5163,63 @@ default_temperature = 0.7
                 lark: None,
                 dingtalk: None,
                 qq: None,
+                nostr: None,
+                local_socket: None,
+                inbound_queue: InboundQueueConfig::default(),
+                sessions: SessionConfig::default(),
+                accounts: Vec::new(),
+                tags: HashMap::new(),
+                failover: FailoverConfig::default(),
+                timeouts: NetworkTimeoutsConfig::default(),
+                http: HttpClientsConfig::default(),
+                streaming: StreamingConfig::default(),
+                dispatch: DispatchConfig::default(),
+                priority: PriorityConfig::default(),
+                quiet_hours: QuietHoursConfig::default(),
+                announcements: AnnouncementsConfig::default(),
+                dry_run: DryRunConfig::default(),
+                shadows: Vec::new(),
             },
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
             gateway: GatewayConfig::default(),
+            admin: AdminConfig::default(),
+            grpc: GrpcConfig::default(),
+            bus: BusConfig::default(),
+            cluster: ClusterConfig::default(),
+            plugins: PluginsConfig::default(),
+            middleware: MiddlewareConfig::default(),
+            transcription: TranscriptionConfig::default(),
+            tts: TtsConfig::default(),
+            vision: VisionConfig::default(),
+            image_generation: ImageGenerationConfig::default(),
+            knowledge: KnowledgeConfig::default(),
+            web_search: WebSearchConfig::default(),
+            feeds: FeedsConfig::default(),
+            ingress: IngressConfig::default(),
+            hooks: HooksConfig::default(),
+            monitor: MonitorConfig::default(),
+            logging: LoggingConfig::default(),
+            ops: OpsConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            uploads: UploadsConfig::default(),
+            media_store: MediaStoreConfig::default(),
+            kv: KvConfig::default(),
+            preferences: PreferencesConfig::default(),
+            reminders: RemindersConfig::default(),
+            groups: GroupsConfig::default(),
+            personas: PersonasConfig::default(),
             composio: ComposioConfig::default(),
             secrets: SecretsConfig::default(),
+            security: SecurityConfig::default(),
+            oauth: HashMap::new(),
             browser: BrowserConfig::default(),
             http_request: HttpRequestConfig::default(),
             agent: AgentConfig::default(),
             identity: IdentityConfig::default(),
+            identity_linking: IdentityLinkingConfig::default(),
             cost: CostConfig::default(),
+            quotas: QuotaConfig::default(),
             peripherals: PeripheralsConfig::default(),
             agents: HashMap::new(),
             hardware: HardwareConfig::default(),
@@ -2301,13 +5322,43 @@ tool_dispatcher = "xml"
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
             gateway: GatewayConfig::default(),
+            admin: AdminConfig::default(),
+            grpc: GrpcConfig::default(),
+            bus: BusConfig::default(),
+            cluster: ClusterConfig::default(),
+            plugins: PluginsConfig::default(),
+            middleware: MiddlewareConfig::default(),
+            transcription: TranscriptionConfig::default(),
+            tts: TtsConfig::default(),
+            vision: VisionConfig::default(),
+            image_generation: ImageGenerationConfig::default(),
+            knowledge: KnowledgeConfig::default(),
+            web_search: WebSearchConfig::default(),
+            feeds: FeedsConfig::default(),
+            ingress: IngressConfig::default(),
+            hooks: HooksConfig::default(),
+            monitor: MonitorConfig::default(),
+            logging: LoggingConfig::default(),
+            ops: OpsConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            uploads: UploadsConfig::default(),
+            media_store: MediaStoreConfig::default(),
+            kv: KvConfig::default(),
+            preferences: PreferencesConfig::default(),
+            reminders: RemindersConfig::default(),
+            groups: GroupsConfig::default(),
+            personas: PersonasConfig::default(),
             composio: ComposioConfig::default(),
             secrets: SecretsConfig::default(),
+            security: SecurityConfig::default(),
+            oauth: HashMap::new(),
             browser: BrowserConfig::default(),
             http_request: HttpRequestConfig::default(),
             agent: AgentConfig::default(),
             identity: IdentityConfig::default(),
+            identity_linking: IdentityLinkingConfig::default(),
             cost: CostConfig::default(),
+            quotas: QuotaConfig::default(),
             peripherals: PeripheralsConfig::default(),
             agents: HashMap::new(),
             hardware: HardwareConfig::default(),
@@ -2447,6 +5498,8 @@ tool_dispatcher = "xml"
             allowed_users: vec![],
             listen_to_bots: false,
             mention_only: false,
+            heartbeat_jitter: true,
+            compression: GatewayCompression::None,
         };
         let json = serde_json::to_string(&dc).unwrap();
         let parsed: DiscordConfig = serde_json::from_str(&json).unwrap();
@@ -2462,6 +5515,8 @@ tool_dispatcher = "xml"
             allowed_users: vec![],
             listen_to_bots: false,
             mention_only: false,
+            heartbeat_jitter: true,
+            compression: GatewayCompression::None,
         };
         let json = serde_json::to_string(&dc).unwrap();
         let parsed: DiscordConfig = serde_json::from_str(&json).unwrap();
@@ -2540,6 +5595,7 @@ tool_dispatcher = "xml"
             allowed_from: vec!["+1111111111".into()],
             ignore_attachments: true,
             ignore_stories: false,
+            rpc_socket: None,
         };
         let json = serde_json::to_string(&sc).unwrap();
         let parsed: SignalConfig = serde_json::from_str(&json).unwrap();
@@ -2560,6 +5616,7 @@ tool_dispatcher = "xml"
             allowed_from: vec!["*".into()],
             ignore_attachments: false,
             ignore_stories: true,
+            rpc_socket: None,
         };
         let toml_str = toml::to_string(&sc).unwrap();
         let parsed: SignalConfig = toml::from_str(&toml_str).unwrap();
@@ -2577,6 +5634,7 @@ tool_dispatcher = "xml"
         assert!(parsed.allowed_from.is_empty());
         assert!(!parsed.ignore_attachments);
         assert!(!parsed.ignore_stories);
+        assert!(parsed.rpc_socket.is_none());
     }
 
     #[test]
@@ -2603,6 +5661,22 @@ tool_dispatcher = "xml"
             lark: None,
             dingtalk: None,
             qq: None,
+            nostr: None,
+            local_socket: None,
+            inbound_queue: InboundQueueConfig::default(),
+            sessions: SessionConfig::default(),
+            accounts: Vec::new(),
+            tags: HashMap::new(),
+            failover: FailoverConfig::default(),
+            timeouts: NetworkTimeoutsConfig::default(),
+            http: HttpClientsConfig::default(),
+            streaming: StreamingConfig::default(),
+            dispatch: DispatchConfig::default(),
+            priority: PriorityConfig::default(),
+            quiet_hours: QuietHoursConfig::default(),
+            announcements: AnnouncementsConfig::default(),
+            dry_run: DryRunConfig::default(),
+            shadows: Vec::new(),
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -2697,6 +5771,8 @@ channel_id = "C123"
             verify_token: "my-verify-token".into(),
             app_secret: None,
             allowed_numbers: vec!["+1234567890".into(), "+9876543210".into()],
+            session_template: None,
+            template_language: "en_US".into(),
         };
         let json = serde_json::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = serde_json::from_str(&json).unwrap();
@@ -2714,6 +5790,8 @@ channel_id = "C123"
             verify_token: "verify".into(),
             app_secret: Some("secret123".into()),
             allowed_numbers: vec!["+1".into()],
+            session_template: None,
+            template_language: "en_US".into(),
         };
         let toml_str = toml::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = toml::from_str(&toml_str).unwrap();
@@ -2736,6 +5814,8 @@ channel_id = "C123"
             verify_token: "ver".into(),
             app_secret: None,
             allowed_numbers: vec!["*".into()],
+            session_template: None,
+            template_language: "en_US".into(),
         };
         let toml_str = toml::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = toml::from_str(&toml_str).unwrap();
@@ -2759,12 +5839,30 @@ channel_id = "C123"
                 verify_token: "ver".into(),
                 app_secret: None,
                 allowed_numbers: vec!["+1".into()],
+                session_template: None,
+                template_language: "en_US".into(),
             }),
             email: None,
             irc: None,
             lark: None,
             dingtalk: None,
             qq: None,
+            nostr: None,
+            local_socket: None,
+            inbound_queue: InboundQueueConfig::default(),
+            sessions: SessionConfig::default(),
+            accounts: Vec::new(),
+            tags: HashMap::new(),
+            failover: FailoverConfig::default(),
+            timeouts: NetworkTimeoutsConfig::default(),
+            http: HttpClientsConfig::default(),
+            streaming: StreamingConfig::default(),
+            dispatch: DispatchConfig::default(),
+            priority: PriorityConfig::default(),
+            quiet_hours: QuietHoursConfig::default(),
+            announcements: AnnouncementsConfig::default(),
+            dry_run: DryRunConfig::default(),
+            shadows: Vec::new(),
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -2949,7 +6047,10 @@ enabled = true
 
     #[test]
     fn secrets_config_serde_roundtrip() {
-        let s = SecretsConfig { encrypt: false };
+        let s = SecretsConfig {
+            encrypt: false,
+            ..SecretsConfig::default()
+        };
         let toml_str = toml::to_string(&s).unwrap();
         let parsed: SecretsConfig = toml::from_str(&toml_str).unwrap();
         assert!(!parsed.encrypt);
@@ -3241,6 +6342,55 @@ default_model = "legacy-model"
         let _ = fs::remove_dir_all(temp_home);
     }
 
+    #[test]
+    fn env_configures_a_channel_without_a_config_file() {
+        let _env_guard = env_override_test_guard();
+        let mut config = Config::default();
+        assert!(config.channels_config.qq.is_none());
+
+        std::env::set_var("ZEROCLAW_QQ_APP_ID", "102000");
+        std::env::set_var("ZEROCLAW_QQ_SECRET", "qq-secret");
+        std::env::set_var("ZEROCLAW_LLM_API_KEY", "sk-llm");
+        std::env::set_var("ZEROCLAW_ALLOWED_USERS", "alice, bob");
+        config.apply_env_overrides();
+
+        let qq = config.channels_config.qq.as_ref().unwrap();
+        assert_eq!(qq.app_id, "102000");
+        assert_eq!(qq.app_secret, "qq-secret");
+        assert_eq!(qq.allowed_users, ["alice", "bob"]);
+        assert!(qq.heartbeat_jitter);
+        assert_eq!(config.api_key.as_deref(), Some("sk-llm"));
+        assert!(crate::config::validate::check(&config).is_empty());
+
+        for name in [
+            "ZEROCLAW_QQ_APP_ID",
+            "ZEROCLAW_QQ_SECRET",
+            "ZEROCLAW_LLM_API_KEY",
+            "ZEROCLAW_ALLOWED_USERS",
+        ] {
+            std::env::remove_var(name);
+        }
+    }
+
+    #[test]
+    fn env_channel_keeps_file_settings() {
+        let _env_guard = env_override_test_guard();
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(TelegramConfig {
+            bot_token: "from-file".into(),
+            allowed_users: vec!["carol".into()],
+        });
+
+        std::env::set_var("ZEROCLAW_TELEGRAM_BOT_TOKEN", "from-env");
+        config.apply_env_overrides();
+
+        let telegram = config.channels_config.telegram.as_ref().unwrap();
+        assert_eq!(telegram.bot_token, "from-env");
+        assert_eq!(telegram.allowed_users, ["carol"]);
+
+        std::env::remove_var("ZEROCLAW_TELEGRAM_BOT_TOKEN");
+    }
+
     #[test]
     fn env_override_empty_values_ignored() {
         let _env_guard = env_override_test_guard();
@@ -3450,6 +6600,59 @@ default_model = "legacy-model"
         assert!(!parsed.use_feishu);
     }
 
+    #[test]
+    fn channel_accounts_deserialize_by_type() {
+        let toml_str = r#"
+cli = true
+
+[[accounts]]
+name = "qq-sandbox"
+type = "qq"
+app_id = "123"
+app_secret = "secret"
+allowed_users = ["*"]
+
+[[accounts]]
+name = "discord-ops"
+type = "discord"
+bot_token = "token"
+"#;
+        let parsed: ChannelsConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(parsed.accounts.len(), 2);
+        assert_eq!(parsed.accounts[0].name, "qq-sandbox");
+        assert!(matches!(
+            &parsed.accounts[0].kind,
+            ChannelAccountKind::Qq(qq) if qq.app_id == "123"
+        ));
+        assert_eq!(parsed.accounts[1].kind.type_name(), "discord");
+    }
+
+    #[test]
+    fn channel_http_settings_override_defaults() {
+        let toml_str = r#"
+cli = true
+
+[http]
+tcp_nodelay = false
+ca_certs = ["/etc/ssl/corp-proxy.pem"]
+
+[http.channels.qq]
+http_version = "http2"
+http2_keepalive_secs = 30
+"#;
+        let parsed: ChannelsConfig = toml::from_str(toml_str).unwrap();
+        assert!(!parsed.http.default.tcp_nodelay);
+        assert_eq!(parsed.http.default.ca_certs.len(), 1);
+        let qq = parsed.http.for_channel("qq");
+        assert_eq!(qq.http_version, HttpVersionPreference::Http2);
+        assert_eq!(qq.http2_keepalive_secs, 30);
+        assert!(qq.tcp_nodelay);
+        assert_eq!(
+            parsed.http.for_channel("telegram").http_version,
+            HttpVersionPreference::Http1
+        );
+    }
+
     #[test]
     fn lark_config_defaults_to_lark_endpoint() {
         let json = r#"{"app_id":"cli_123","app_secret":"secret"}"#;
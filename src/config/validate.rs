@@ -0,0 +1,479 @@
+//! Config validation (`zeroclaw validate-config`).
+//!
+//! Deserializing only checks types. [`check`] catches what would otherwise
+//! fail (or silently misbehave) once channels are running: missing required
+//! values, options that exclude each other, and malformed URLs, IDs and
+//! times. Keys the schema doesn't know are reported as warnings, since they
+//! are ignored (usually a typo). Every issue names its key and, when read
+//! from a file, the line it is on.
+
+use super::Config;
+use anyhow::{bail, Context, Result};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The config can't be used as it is
+    Error,
+    /// Likely a mistake, but the config still loads
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    /// Dotted key path, e.g. `channels_config.quiet_hours.rules[0].start`
+    pub key: String,
+    /// 1-based line of the key in the file, when found
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key: key.into(),
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(key, message)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{severity}: line {line}: {}: {}", self.key, self.message),
+            None => write!(f, "{severity}: {}: {}", self.key, self.message),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    issues: Vec<ConfigIssue>,
+}
+
+impl Checker {
+    fn error(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ConfigIssue::error(key, message));
+    }
+
+    fn required(&mut self, key: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.error(key, "is required");
+        }
+    }
+
+    fn http_url(&mut self, key: &str, value: &str) {
+        match reqwest::Url::parse(value.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => self.error(
+                key,
+                format!("must be an http(s) URL, not {}://", url.scheme()),
+            ),
+            Err(e) => self.error(key, format!("{value:?} is not a valid URL ({e})")),
+        }
+    }
+
+    fn time_of_day(&mut self, key: &str, value: &str) {
+        if chrono::NaiveTime::parse_from_str(value.trim(), "%H:%M").is_err() {
+            self.error(key, format!("{value:?} is not a time (expected HH:MM)"));
+        }
+    }
+}
+
+/// Problems in a loaded config, without line numbers.
+#[allow(clippy::too_many_lines)]
+pub fn check(config: &Config) -> Vec<ConfigIssue> {
+    let mut c = Checker::default();
+
+    if !(0.0..=2.0).contains(&config.default_temperature) {
+        c.error("default_temperature", "must be between 0.0 and 2.0");
+    }
+    if let Some(url) = &config.api_url {
+        c.http_url("api_url", url);
+    }
+    for (i, route) in config.model_routes.iter().enumerate() {
+        c.required(&format!("model_routes[{i}].hint"), &route.hint);
+        c.required(&format!("model_routes[{i}].provider"), &route.provider);
+        c.required(&format!("model_routes[{i}].model"), &route.model);
+    }
+
+    let channels = &config.channels_config;
+    if let Some(telegram) = &channels.telegram {
+        c.required("channels_config.telegram.bot_token", &telegram.bot_token);
+    }
+    if let Some(discord) = &channels.discord {
+        c.required("channels_config.discord.bot_token", &discord.bot_token);
+        if let Some(guild) = &discord.guild_id {
+            if guild.is_empty() || !guild.bytes().all(|b| b.is_ascii_digit()) {
+                c.error(
+                    "channels_config.discord.guild_id",
+                    format!("{guild:?} is not a Discord ID (digits only)"),
+                );
+            }
+        }
+    }
+    if let Some(slack) = &channels.slack {
+        if slack.bot_token.trim().is_empty() && slack.oauth.is_none() {
+            c.error(
+                "channels_config.slack.bot_token",
+                "is required unless `oauth` names a client",
+            );
+        }
+        if let Some(id) = &slack.channel_id {
+            let valid = id.starts_with(['C', 'G', 'D'])
+                && id.len() > 1
+                && id
+                    .bytes()
+                    .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+            if !valid {
+                c.error(
+                    "channels_config.slack.channel_id",
+                    format!("{id:?} is not a Slack channel ID (e.g. C0123ABCD)"),
+                );
+            }
+        }
+    }
+    if let Some(matrix) = &channels.matrix {
+        c.http_url("channels_config.matrix.homeserver", &matrix.homeserver);
+        c.required("channels_config.matrix.access_token", &matrix.access_token);
+        let room = &matrix.room_id;
+        if !(room.starts_with(['!', '#']) && room.contains(':')) {
+            c.error(
+                "channels_config.matrix.room_id",
+                format!("{room:?} is not a room ID or alias (e.g. !abc:example.org)"),
+            );
+        }
+    }
+    if let Some(signal) = &channels.signal {
+        if signal.http_url.trim().is_empty() && signal.rpc_socket.is_none() {
+            c.error(
+                "channels_config.signal.http_url",
+                "is required unless `rpc_socket` is set",
+            );
+        } else if !signal.http_url.trim().is_empty() {
+            c.http_url("channels_config.signal.http_url", &signal.http_url);
+        }
+        let digits = signal.account.strip_prefix('+').unwrap_or_default();
+        if !(7..=15).contains(&digits.len()) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            c.error(
+                "channels_config.signal.account",
+                format!(
+                    "{:?} is not an E.164 phone number (e.g. +1234567890)",
+                    signal.account
+                ),
+            );
+        }
+    }
+    for (i, rule) in channels.quiet_hours.rules.iter().enumerate() {
+        let key = format!("channels_config.quiet_hours.rules[{i}]");
+        c.time_of_day(&format!("{key}.start"), &rule.start);
+        c.time_of_day(&format!("{key}.end"), &rule.end);
+        if let Some(timezone) = &rule.timezone {
+            if Tz::from_str(timezone).is_err() {
+                c.error(
+                    format!("{key}.timezone"),
+                    format!("{timezone:?} is not an IANA timezone"),
+                );
+            }
+        }
+    }
+    let mut shadows = HashMap::new();
+    for (i, shadow) in channels.shadows.iter().enumerate() {
+        let key = format!("channels_config.shadows[{i}]");
+        c.required(&format!("{key}.name"), &shadow.name);
+        c.required(&format!("{key}.source"), &shadow.source);
+        if shadow.name == shadow.source {
+            c.error(format!("{key}.name"), "must differ from `source`");
+        }
+        if let Some(first) = shadows.insert(shadow.name.as_str(), i) {
+            c.error(
+                format!("{key}.name"),
+                format!("{:?} is also used by shadows[{first}]", shadow.name),
+            );
+        }
+    }
+
+    let admin = &config.admin;
+    if admin.username.is_some() != admin.password.is_some() {
+        let missing = if admin.username.is_some() {
+            "admin.password"
+        } else {
+            "admin.username"
+        };
+        c.error(missing, "basic auth needs both `username` and `password`");
+    }
+
+    if config.memory.backend == "postgres" && config.memory.postgres_url.is_none() {
+        c.error(
+            "memory.postgres_url",
+            "is required with backend = \"postgres\"",
+        );
+    }
+    for (i, hook) in config.hooks.webhooks.iter().enumerate() {
+        c.http_url(&format!("hooks.webhooks[{i}].url"), &hook.url);
+    }
+
+    let analytics = &config.analytics;
+    if analytics.digest_weekday.parse::<chrono::Weekday>().is_err() {
+        c.error(
+            "analytics.digest_weekday",
+            format!("{:?} is not a weekday", analytics.digest_weekday),
+        );
+    }
+    if analytics.digest_hour > 23 {
+        c.error("analytics.digest_hour", "must be between 0 and 23");
+    }
+
+    c.issues
+}
+
+/// Problems in the config file `contents`, with line numbers.
+pub fn validate_str(contents: &str) -> Vec<ConfigIssue> {
+    validate_with(contents, |_| {})
+}
+
+fn validate_with(contents: &str, prepare: impl FnOnce(&mut Config)) -> Vec<ConfigIssue> {
+    let mut config: Config = match toml::from_str(contents) {
+        Ok(config) => config,
+        Err(e) => return vec![parse_issue(contents, &e)],
+    };
+    let mut issues = unknown_keys(contents, &config);
+    prepare(&mut config);
+    issues.extend(check(&config));
+    locate(contents, &mut issues);
+    issues
+}
+
+fn parse_issue(contents: &str, error: &toml::de::Error) -> ConfigIssue {
+    let line = error
+        .span()
+        .map(|span| contents[..span.start].matches('\n').count() + 1);
+    ConfigIssue {
+        line,
+        ..ConfigIssue::error("config", error.message().trim())
+    }
+}
+
+/// Keys of `contents` that don't survive a round trip through [`Config`].
+fn unknown_keys(contents: &str, config: &Config) -> Vec<ConfigIssue> {
+    let (Ok(raw), Ok(toml::Value::Table(known))) = (
+        toml::from_str::<toml::Table>(contents),
+        toml::Value::try_from(config),
+    ) else {
+        return Vec::new();
+    };
+    let mut issues = Vec::new();
+    compare_tables(&raw, &known, "", &mut issues);
+    issues
+}
+
+fn compare_tables(raw: &toml::Table, known: &toml::Table, path: &str, out: &mut Vec<ConfigIssue>) {
+    for (key, value) in raw {
+        let path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+        match (value, known.get(key)) {
+            (_, None) => out.push(ConfigIssue::warning(
+                path,
+                "is not a known setting and is ignored",
+            )),
+            (toml::Value::Table(raw), Some(toml::Value::Table(known))) => {
+                compare_tables(raw, known, &path, out);
+            }
+            (toml::Value::Array(raw), Some(toml::Value::Array(known))) => {
+                for (i, (raw, known)) in raw.iter().zip(known).enumerate() {
+                    if let (toml::Value::Table(raw), toml::Value::Table(known)) = (raw, known) {
+                        compare_tables(raw, known, &format!("{path}[{i}]"), out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fill in the line of each issue's key, or of the closest table above it.
+fn locate(contents: &str, issues: &mut [ConfigIssue]) {
+    let lines = key_lines(contents);
+    for issue in issues.iter_mut().filter(|issue| issue.line.is_none()) {
+        let mut key = issue.key.as_str();
+        issue.line = loop {
+            if let Some(line) = lines.get(key) {
+                break Some(*line);
+            }
+            match key.rfind(['.', '[']) {
+                Some(end) => key = &key[..end],
+                None => break None,
+            }
+        };
+    }
+}
+
+/// Line of every table header and plain `key = value` in `contents`, by
+/// dotted path.
+fn key_lines(contents: &str) -> HashMap<String, usize> {
+    let mut lines = HashMap::new();
+    let mut array_counts: HashMap<String, usize> = HashMap::new();
+    let mut table = String::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        let number = index + 1;
+        if let Some(name) = line.strip_prefix("[[").and_then(|l| l.split("]]").next()) {
+            let name = name.trim();
+            let count = array_counts.entry(name.to_string()).or_default();
+            table = format!("{name}[{count}]");
+            *count += 1;
+            lines.entry(name.to_string()).or_insert(number);
+            lines.insert(table.clone(), number);
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.split(']').next()) {
+            table = name.trim().to_string();
+            lines.insert(table.clone(), number);
+        } else if let Some((key, _)) = line.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            if key.is_empty() || key.starts_with('#') {
+                continue;
+            }
+            let path = if table.is_empty() {
+                key.to_string()
+            } else {
+                format!("{table}.{key}")
+            };
+            lines.entry(path).or_insert(number);
+        }
+    }
+    lines
+}
+
+/// Fail with every error [`check`] finds in `config`, read from `contents`.
+pub fn ensure_valid(config: &Config, contents: &str) -> Result<()> {
+    let mut errors: Vec<ConfigIssue> = check(config)
+        .into_iter()
+        .filter(ConfigIssue::is_error)
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    locate(contents, &mut errors);
+    let list: Vec<String> = errors.iter().map(|e| format!("  {e}")).collect();
+    bail!(
+        "Invalid config:\n{}\nFix the file, then check it with `zeroclaw validate-config`",
+        list.join("\n")
+    )
+}
+
+/// `zeroclaw validate-config`: print the issues of the file at `path`.
+pub fn run(path: &Path) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let issues = validate_with(&contents, Config::apply_env_overrides);
+    for issue in &issues {
+        println!("{issue}");
+    }
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+    if errors > 0 {
+        bail!("{} has {errors} error(s)", path.display());
+    }
+    println!(
+        "✅ {} is valid{}",
+        path.display(),
+        if issues.is_empty() {
+            ""
+        } else {
+            " (with warnings)"
+        }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(check(&Config::default()), Vec::new());
+        let contents = toml::to_string_pretty(&Config::default()).unwrap();
+        assert_eq!(validate_str(&contents), Vec::new());
+    }
+
+    #[test]
+    fn reports_format_errors_at_their_line() {
+        let contents = r#"
+default_temperature = 0.7
+
+[channels_config]
+cli = true
+
+[channels_config.signal]
+http_url = "localhost:8686"
+account = "12345"
+
+[[channels_config.quiet_hours.rules]]
+channel = "telegram"
+start = "22:00"
+end = "7am"
+"#;
+        let issues = validate_str(contents);
+        let found: Vec<(&str, Option<usize>)> = issues
+            .iter()
+            .map(|issue| (issue.key.as_str(), issue.line))
+            .collect();
+        assert!(found.contains(&("channels_config.signal.http_url", Some(8))));
+        assert!(found.contains(&("channels_config.signal.account", Some(9))));
+        assert!(found.contains(&("channels_config.quiet_hours.rules[0].end", Some(14))));
+        assert!(issues.iter().all(ConfigIssue::is_error));
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings() {
+        let contents = "default_temperature = 0.7\n\n[memory]\nbackend = \"sqlite\"\nauto_save = true\nhygeine_enabled = false\n";
+        let issues = validate_str(contents);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[0].key, "memory.hygeine_enabled");
+        assert_eq!(issues[0].line, Some(6));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_line() {
+        let issues = validate_str(
+            "default_temperature = 0.7\n\n[memory]\nbackend = \"sqlite\"\nauto_save = \"yes\"\n",
+        );
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_error());
+        assert_eq!(issues[0].line, Some(5));
+    }
+
+    #[test]
+    fn half_configured_basic_auth_is_rejected() {
+        let mut config = Config::default();
+        config.admin.username = Some("ops".into());
+        let err = ensure_valid(&config, "[admin]\nusername = \"ops\"\n").unwrap_err();
+        assert!(err.to_string().contains("admin.password"));
+        assert!(err.to_string().contains("line 1"));
+    }
+}
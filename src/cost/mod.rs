@@ -1,5 +1,7 @@
+pub mod quota;
 pub mod tracker;
 pub mod types;
 
+pub use quota::{QuotaCheck, QuotaLedger};
 pub use tracker::CostTracker;
 pub use types::{BudgetCheck, CostRecord, CostSummary, ModelStats, TokenUsage, UsagePeriod};
@@ -0,0 +1,416 @@
+//! Per-user and per-channel usage accounting with daily quotas.
+//!
+//! Every answered channel message adds one message, its token counts and
+//! its priced cost to two daily rows: one for the user and one for the
+//! channel. Before a message reaches the LLM, both rows are checked against
+//! `[quotas]`. Days roll over at midnight UTC.
+//!
+//! Usage lives in `<workspace>/state/usage.db`, so totals survive restarts.
+
+use super::types::TokenUsage;
+use crate::config::schema::{ModelPricing, QuotaConfig, QuotaLimits};
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::path::Path;
+
+/// Which row a quota applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaScope {
+    User,
+    Channel,
+}
+
+impl QuotaScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Channel => "channel",
+        }
+    }
+}
+
+/// Which limit was hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    Messages,
+    Tokens,
+    Cost,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuotaCheck {
+    Allowed,
+    Exceeded { scope: QuotaScope, kind: QuotaKind },
+}
+
+impl fmt::Display for QuotaCheck {
+    /// Human-readable name of the exhausted limit, for the `{limit}` placeholder.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self::Exceeded { scope, kind } = self else {
+            return f.write_str("no limit");
+        };
+        let owner = match scope {
+            QuotaScope::User => "your",
+            QuotaScope::Channel => "this channel's",
+        };
+        let limit = match kind {
+            QuotaKind::Messages => "daily message limit",
+            QuotaKind::Tokens => "daily token limit",
+            QuotaKind::Cost => "daily spending limit",
+        };
+        write!(f, "{owner} {limit}")
+    }
+}
+
+/// One day's usage for a user or channel.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub key: String,
+    pub messages: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn exceeds(&self, limits: &QuotaLimits) -> Option<QuotaKind> {
+        if limits.messages.is_some_and(|max| self.messages >= max) {
+            return Some(QuotaKind::Messages);
+        }
+        let tokens = self.input_tokens.saturating_add(self.output_tokens);
+        if limits.tokens.is_some_and(|max| tokens >= max) {
+            return Some(QuotaKind::Tokens);
+        }
+        if limits.cost_usd.is_some_and(|max| self.cost_usd >= max) {
+            return Some(QuotaKind::Cost);
+        }
+        None
+    }
+}
+
+/// Usage for one day, split by user and by channel, highest spend first.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub day: NaiveDate,
+    pub users: Vec<UsageTotals>,
+    pub channels: Vec<UsageTotals>,
+}
+
+pub struct QuotaLedger {
+    conn: Mutex<Connection>,
+    config: QuotaConfig,
+    prices: HashMap<String, ModelPricing>,
+}
+
+impl QuotaLedger {
+    /// Open (or create) the ledger under `<workspace>/state/`.
+    pub fn open(
+        workspace_dir: &Path,
+        config: &QuotaConfig,
+        prices: &HashMap<String, ModelPricing>,
+    ) -> Result<Self> {
+        let dir = workspace_dir.join("state");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create state directory: {}", dir.display()))?;
+        let db_path = dir.join("usage.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open usage DB: {}", db_path.display()))?;
+        Self::with_connection(conn, config, prices)
+    }
+
+    fn with_connection(
+        conn: Connection,
+        config: &QuotaConfig,
+        prices: &HashMap<String, ModelPricing>,
+    ) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage_daily (
+                day           TEXT NOT NULL,
+                scope         TEXT NOT NULL,
+                key           TEXT NOT NULL,
+                messages      INTEGER NOT NULL DEFAULT 0,
+                input_tokens  INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                cost_usd      REAL NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, scope, key)
+            );",
+        )
+        .context("Failed to initialize usage schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            config: config.clone(),
+            prices: prices.clone(),
+        })
+    }
+
+    /// The reply to send when `check` is exceeded.
+    pub fn exceeded_message(&self, check: &QuotaCheck) -> String {
+        self.config
+            .exceeded_message
+            .replace("{limit}", &check.to_string())
+    }
+
+    fn is_exempt(&self, channel: &str, sender: &str) -> bool {
+        let qualified = format!("{channel}:{sender}");
+        self.config
+            .exempt
+            .iter()
+            .any(|entry| entry == sender || *entry == qualified)
+    }
+
+    /// Whether `user` (a profile id or `channel:sender`) may send another
+    /// message on `channel` today.
+    pub fn check(&self, user: &str, channel: &str, sender: &str) -> Result<QuotaCheck> {
+        if self.is_exempt(channel, sender) {
+            return Ok(QuotaCheck::Allowed);
+        }
+        let day = today();
+        let conn = self.conn.lock();
+        for (scope, key, limits) in [
+            (QuotaScope::User, user, &self.config.per_user),
+            (QuotaScope::Channel, channel, &self.config.per_channel),
+        ] {
+            let totals = load(&conn, day, scope, key)?;
+            if let Some(kind) = totals.exceeds(limits) {
+                return Ok(QuotaCheck::Exceeded { scope, kind });
+            }
+        }
+        Ok(QuotaCheck::Allowed)
+    }
+
+    /// Record one answered message. Returns the priced usage.
+    pub fn record(
+        &self,
+        user: &str,
+        channel: &str,
+        model: &str,
+        input_tokens: u64,
+        output_tokens: u64,
+    ) -> Result<TokenUsage> {
+        let usage = priced_usage(&self.prices, model, input_tokens, output_tokens);
+        let day = today().to_string();
+        let conn = self.conn.lock();
+        for (scope, key) in [(QuotaScope::User, user), (QuotaScope::Channel, channel)] {
+            conn.execute(
+                "INSERT INTO usage_daily (day, scope, key, messages, input_tokens, output_tokens, cost_usd)
+                 VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6)
+                 ON CONFLICT (day, scope, key) DO UPDATE SET
+                    messages = messages + 1,
+                    input_tokens = input_tokens + excluded.input_tokens,
+                    output_tokens = output_tokens + excluded.output_tokens,
+                    cost_usd = cost_usd + excluded.cost_usd",
+                params![
+                    day,
+                    scope.as_str(),
+                    key,
+                    i64::try_from(usage.input_tokens).unwrap_or(i64::MAX),
+                    i64::try_from(usage.output_tokens).unwrap_or(i64::MAX),
+                    usage.cost_usd
+                ],
+            )?;
+        }
+        Ok(usage)
+    }
+
+    /// Delete `user`'s usage history. Channel totals are kept; they don't
+    /// name anyone. Returns the number of daily records deleted.
+    pub fn forget(&self, user: &str) -> Result<usize> {
+        let removed = self.conn.lock().execute(
+            "DELETE FROM usage_daily WHERE scope = ?1 AND key = ?2",
+            params![QuotaScope::User.as_str(), user],
+        )?;
+        Ok(removed)
+    }
+
+    /// Totals for `day` (today when `None`).
+    pub fn report(&self, day: Option<NaiveDate>) -> Result<UsageReport> {
+        let day = day.unwrap_or_else(today);
+        let conn = self.conn.lock();
+        Ok(UsageReport {
+            day,
+            users: load_all(&conn, day, QuotaScope::User)?,
+            channels: load_all(&conn, day, QuotaScope::Channel)?,
+        })
+    }
+}
+
+/// Price a reply at `prices` (`[cost.prices]`). Prices are keyed
+/// `provider/model`; accept either form. Unpriced models cost nothing.
+pub fn priced_usage<S: BuildHasher>(
+    prices: &HashMap<String, ModelPricing, S>,
+    model: &str,
+    input_tokens: u64,
+    output_tokens: u64,
+) -> TokenUsage {
+    let pricing = prices.get(model).or_else(|| {
+        prices
+            .iter()
+            .find(|(key, _)| key.rsplit_once('/').is_some_and(|(_, name)| name == model))
+            .map(|(_, pricing)| pricing)
+    });
+    TokenUsage::new(
+        model,
+        input_tokens,
+        output_tokens,
+        pricing.map_or(0.0, |p| p.input),
+        pricing.map_or(0.0, |p| p.output),
+    )
+}
+
+/// Rough token count for providers that don't report usage (~4 chars/token).
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+fn row_to_totals(row: &rusqlite::Row<'_>) -> rusqlite::Result<UsageTotals> {
+    Ok(UsageTotals {
+        key: row.get(0)?,
+        messages: u64::try_from(row.get::<_, i64>(1)?).unwrap_or(0),
+        input_tokens: u64::try_from(row.get::<_, i64>(2)?).unwrap_or(0),
+        output_tokens: u64::try_from(row.get::<_, i64>(3)?).unwrap_or(0),
+        cost_usd: row.get(4)?,
+    })
+}
+
+fn load(conn: &Connection, day: NaiveDate, scope: QuotaScope, key: &str) -> Result<UsageTotals> {
+    let totals = conn
+        .query_row(
+            "SELECT key, messages, input_tokens, output_tokens, cost_usd FROM usage_daily
+             WHERE day = ?1 AND scope = ?2 AND key = ?3",
+            params![day.to_string(), scope.as_str(), key],
+            row_to_totals,
+        )
+        .optional()?;
+    Ok(totals.unwrap_or_else(|| UsageTotals {
+        key: key.to_string(),
+        ..UsageTotals::default()
+    }))
+}
+
+fn load_all(conn: &Connection, day: NaiveDate, scope: QuotaScope) -> Result<Vec<UsageTotals>> {
+    let mut stmt = conn.prepare(
+        "SELECT key, messages, input_tokens, output_tokens, cost_usd FROM usage_daily
+         WHERE day = ?1 AND scope = ?2 ORDER BY cost_usd DESC, messages DESC, key",
+    )?;
+    let rows = stmt.query_map(params![day.to_string(), scope.as_str()], row_to_totals)?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger(config: &QuotaConfig) -> QuotaLedger {
+        let mut prices = HashMap::new();
+        prices.insert(
+            "openai/gpt-4o".to_string(),
+            ModelPricing {
+                input: 5.0,
+                output: 15.0,
+            },
+        );
+        QuotaLedger::with_connection(Connection::open_in_memory().unwrap(), config, &prices)
+            .unwrap()
+    }
+
+    #[test]
+    fn user_message_quota_is_enforced_per_user() {
+        let config = QuotaConfig {
+            enabled: true,
+            per_user: QuotaLimits {
+                messages: Some(2),
+                ..QuotaLimits::default()
+            },
+            ..QuotaConfig::default()
+        };
+        let ledger = ledger(&config);
+        for _ in 0..2 {
+            assert_eq!(
+                ledger.check("qq:alice", "qq", "alice").unwrap(),
+                QuotaCheck::Allowed
+            );
+            ledger.record("qq:alice", "qq", "gpt-4o", 10, 10).unwrap();
+        }
+        let check = ledger.check("qq:alice", "qq", "alice").unwrap();
+        assert_eq!(
+            check,
+            QuotaCheck::Exceeded {
+                scope: QuotaScope::User,
+                kind: QuotaKind::Messages
+            }
+        );
+        assert!(ledger
+            .exceeded_message(&check)
+            .contains("your daily message limit"));
+        assert_eq!(
+            ledger.check("qq:bob", "qq", "bob").unwrap(),
+            QuotaCheck::Allowed
+        );
+    }
+
+    #[test]
+    fn channel_cost_quota_and_exemptions() {
+        let config = QuotaConfig {
+            enabled: true,
+            per_channel: QuotaLimits {
+                cost_usd: Some(0.01),
+                ..QuotaLimits::default()
+            },
+            exempt: vec!["discord:admin".into()],
+            ..QuotaConfig::default()
+        };
+        let ledger = ledger(&config);
+        let usage = ledger
+            .record("discord:alice", "discord", "gpt-4o", 1_000, 1_000)
+            .unwrap();
+        assert!((usage.cost_usd - 0.02).abs() < 1e-9);
+
+        assert_eq!(
+            ledger.check("discord:bob", "discord", "bob").unwrap(),
+            QuotaCheck::Exceeded {
+                scope: QuotaScope::Channel,
+                kind: QuotaKind::Cost
+            }
+        );
+        assert_eq!(
+            ledger.check("discord:admin", "discord", "admin").unwrap(),
+            QuotaCheck::Allowed
+        );
+    }
+
+    #[test]
+    fn report_lists_users_and_channels() {
+        let ledger = ledger(&QuotaConfig::default());
+        ledger
+            .record("qq:alice", "qq", "unpriced", 100, 50)
+            .unwrap();
+        ledger
+            .record("qq:alice", "qq", "unpriced", 100, 50)
+            .unwrap();
+        ledger.record("qq:bob", "qq", "unpriced", 10, 5).unwrap();
+
+        let report = ledger.report(None).unwrap();
+        assert_eq!(report.users.len(), 2);
+        let alice = report.users.iter().find(|u| u.key == "qq:alice").unwrap();
+        assert_eq!((alice.messages, alice.input_tokens), (2, 200));
+        assert_eq!(report.channels[0].key, "qq");
+        assert_eq!(report.channels[0].messages, 3);
+    }
+
+    #[test]
+    fn estimate_tokens_rounds_up() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+}
@@ -1,12 +1,15 @@
 use crate::config::Config;
 use anyhow::Result;
 
+mod natural;
 mod schedule;
 mod store;
 mod types;
 
 pub mod scheduler;
 
+#[allow(unused_imports)]
+pub use natural::{parse_schedule, parse_schedule_prefix};
 #[allow(unused_imports)]
 pub use schedule::{
     next_run_for_schedule, normalize_expression, schedule_cron_expression, validate_schedule,
@@ -0,0 +1,572 @@
+//! Natural-language times: "in 30m", "tomorrow 9am", "every monday at 8:30",
+//! "every 2 hours", "半小时后", "明天下午3点半", "每天晚上8点".
+//!
+//! Clock times are read in the given timezone (normally the user's
+//! preference). One-off times become [`Schedule::At`], daily and weekly times
+//! a [`Schedule::Cron`] carrying the timezone, and intervals a
+//! [`Schedule::Every`]. A clock time without a day means its next
+//! occurrence; a day without a clock time means 09:00 (20:00 for "tonight").
+
+use super::Schedule;
+use anyhow::{bail, Result};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Day {
+    /// Days from today
+    Offset(i64),
+    /// The next such weekday after today
+    Next(Weekday),
+    Date(NaiveDate),
+    Daily,
+    Weekdays,
+    Weekly(Weekday),
+}
+
+/// Part of the day a clock time is in, from "pm" or "下午".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Period {
+    Morning,
+    Noon,
+    Afternoon,
+    Evening,
+}
+
+const UNITS: &[(&[&str], i64)] = &[
+    (&["seconds", "second", "secs", "sec", "s", "秒钟", "秒"], 1),
+    (&["minutes", "minute", "mins", "min", "m", "分钟", "分"], 60),
+    (&["hours", "hour", "hrs", "hr", "h", "小时", "钟头"], 3600),
+    (&["days", "day", "d", "天"], 86_400),
+    (&["weeks", "week", "w", "星期", "周"], 604_800),
+];
+
+const WEEKDAYS: &[(&[&str], Weekday)] = &[
+    (&["monday", "mon"], Weekday::Mon),
+    (&["tuesday", "tues", "tue"], Weekday::Tue),
+    (&["wednesday", "wed"], Weekday::Wed),
+    (&["thursday", "thurs", "thu"], Weekday::Thu),
+    (&["friday", "fri"], Weekday::Fri),
+    (&["saturday", "sat"], Weekday::Sat),
+    (&["sunday", "sun"], Weekday::Sun),
+];
+
+struct Scanner<'a> {
+    rest: &'a str,
+}
+
+impl Scanner<'_> {
+    fn skip_spaces(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    /// Consume `literal`, ASCII case-insensitively. Literals ending in a
+    /// letter may not be followed by another one, so "m" does not match
+    /// "months" while "1h30m" still splits.
+    fn eat(&mut self, literal: &str) -> bool {
+        let Some(head) = self.rest.get(..literal.len()) else {
+            return false;
+        };
+        let tail = &self.rest[literal.len()..];
+        let word = literal.ends_with(|c: char| c.is_ascii_alphabetic());
+        if !head.eq_ignore_ascii_case(literal)
+            || (word && tail.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            return false;
+        }
+        self.rest = tail;
+        true
+    }
+
+    fn eat_any(&mut self, literals: &[&str]) -> bool {
+        literals.iter().any(|literal| self.eat(literal))
+    }
+
+    /// An ASCII or Chinese number.
+    fn number(&mut self) -> Option<u32> {
+        let digits = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let (value, len) = if digits > 0 {
+            (self.rest[..digits].parse().ok()?, digits)
+        } else {
+            chinese_number(self.rest)?
+        };
+        self.rest = &self.rest[len..];
+        Some(value)
+    }
+
+    /// Run `parse`, restoring the position when it fails.
+    fn attempt<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let saved = self.rest;
+        let result = parse(self);
+        if result.is_none() {
+            self.rest = saved;
+        }
+        result
+    }
+}
+
+fn chinese_digit(c: char) -> Option<u32> {
+    Some(match c {
+        '零' | '〇' => 0,
+        '一' => 1,
+        '二' | '两' => 2,
+        '三' => 3,
+        '四' => 4,
+        '五' => 5,
+        '六' => 6,
+        '七' => 7,
+        '八' => 8,
+        '九' => 9,
+        _ => return None,
+    })
+}
+
+/// Chinese numerals up to 99 (`八`, `十二`, `二十五`), with their byte length.
+fn chinese_number(s: &str) -> Option<(u32, usize)> {
+    let mut chars = s.chars();
+    let first = chars.next()?;
+    let (tens, mut len) = match (first, chinese_digit(first)) {
+        ('十', _) => (1, first.len_utf8()),
+        (_, Some(digit)) if s[first.len_utf8()..].starts_with('十') => {
+            chars.next();
+            (digit, first.len_utf8() + '十'.len_utf8())
+        }
+        (_, Some(digit)) => return Some((digit, first.len_utf8())),
+        _ => return None,
+    };
+    let mut value = tens * 10;
+    if let Some(unit) = chars.next().and_then(chinese_digit) {
+        value += unit;
+        len += '一'.len_utf8();
+    }
+    Some((value, len))
+}
+
+fn unit(s: &mut Scanner) -> Option<i64> {
+    UNITS
+        .iter()
+        .find(|(names, _)| s.eat_any(names))
+        .map(|(_, secs)| *secs)
+}
+
+/// One `<amount> <unit>`, in seconds.
+fn duration_part(s: &mut Scanner) -> Option<i64> {
+    s.attempt(|s| {
+        s.skip_spaces();
+        if s.eat("半") {
+            s.eat("个");
+            return unit(s).map(|secs| secs / 2);
+        }
+        let amount = if s.eat_any(&["an", "a"]) {
+            1
+        } else {
+            i64::from(s.number()?)
+        };
+        s.skip_spaces();
+        s.eat("个");
+        amount.checked_mul(unit(s)?)
+    })
+}
+
+/// `1h30m`, `2 hours and 15 minutes`, `一个小时`, in seconds.
+fn duration(s: &mut Scanner) -> Option<i64> {
+    let mut total = duration_part(s)?;
+    while let Some(part) = s.attempt(|s| {
+        s.skip_spaces();
+        s.eat_any(&["and", ","]);
+        duration_part(s)
+    }) {
+        total = total.checked_add(part)?;
+    }
+    Some(total)
+}
+
+/// `in 30m` or `30分钟后`, in seconds.
+fn relative(s: &mut Scanner) -> Option<i64> {
+    s.attempt(|s| {
+        s.skip_spaces();
+        if !s.eat_any(&["in", "after"]) {
+            return None;
+        }
+        duration(s)
+    })
+    .or_else(|| {
+        s.attempt(|s| {
+            s.skip_spaces();
+            s.eat("过");
+            let secs = duration(s)?;
+            s.skip_spaces();
+            s.eat_any(&["以后", "之后", "后"]).then_some(secs)
+        })
+    })
+}
+
+/// `every 2 hours`, `every hour` or `每30分钟`, in seconds.
+fn interval(s: &mut Scanner) -> Option<i64> {
+    s.attempt(|s| {
+        s.skip_spaces();
+        if !s.eat("every") && !s.eat("每") {
+            return None;
+        }
+        s.eat("隔");
+        s.skip_spaces();
+        s.eat("个");
+        duration(s).or_else(|| unit(s))
+    })
+}
+
+fn weekday(s: &mut Scanner) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(names, _)| s.eat_any(names))
+        .map(|(_, day)| *day)
+}
+
+/// `周一`, `星期天`, `礼拜五`.
+fn chinese_weekday(s: &mut Scanner) -> Option<Weekday> {
+    s.attempt(|s| {
+        if !s.eat_any(&["星期", "礼拜", "周"]) {
+            return None;
+        }
+        let day = s.rest.chars().next()?;
+        let weekday = match day {
+            '一' => Weekday::Mon,
+            '二' => Weekday::Tue,
+            '三' => Weekday::Wed,
+            '四' => Weekday::Thu,
+            '五' => Weekday::Fri,
+            '六' => Weekday::Sat,
+            '日' | '天' | '七' => Weekday::Sun,
+            _ => return None,
+        };
+        s.rest = &s.rest[day.len_utf8()..];
+        Some(weekday)
+    })
+}
+
+fn day(s: &mut Scanner) -> Option<(Day, Option<Period>)> {
+    s.attempt(|s| {
+        s.skip_spaces();
+        let day = if s.eat_any(&["today", "今天", "今日"]) {
+            Day::Offset(0)
+        } else if s.eat_any(&["tonight", "今晚"]) {
+            return Some((Day::Offset(0), Some(Period::Evening)));
+        } else if s.eat("明晚") {
+            return Some((Day::Offset(1), Some(Period::Evening)));
+        } else if s.eat_any(&["tomorrow", "明天", "明日"]) {
+            Day::Offset(1)
+        } else if s.eat("后天") {
+            Day::Offset(2)
+        } else if s.eat_any(&["daily", "everyday", "每天", "每日"]) {
+            Day::Daily
+        } else if s.eat_any(&["weekdays", "每个工作日", "工作日"]) {
+            Day::Weekdays
+        } else if s.eat("every") {
+            s.skip_spaces();
+            if s.eat("day") {
+                Day::Daily
+            } else if s.eat("weekday") {
+                Day::Weekdays
+            } else {
+                Day::Weekly(weekday(s)?)
+            }
+        } else if s.eat("每") {
+            Day::Weekly(chinese_weekday(s)?)
+        } else if let Some(date) = s
+            .rest
+            .get(..10)
+            .and_then(|head| NaiveDate::parse_from_str(head, "%Y-%m-%d").ok())
+        {
+            s.rest = &s.rest[10..];
+            Day::Date(date)
+        } else {
+            s.eat("on");
+            s.skip_spaces();
+            s.eat_any(&["next", "下"]);
+            s.skip_spaces();
+            Day::Next(weekday(s).or_else(|| chinese_weekday(s))?)
+        };
+        Some((day, None))
+    })
+}
+
+fn chinese_period(s: &mut Scanner) -> Option<Period> {
+    if s.eat_any(&["凌晨", "早上", "早晨", "清晨", "上午"]) {
+        Some(Period::Morning)
+    } else if s.eat("中午") {
+        Some(Period::Noon)
+    } else if s.eat("下午") {
+        Some(Period::Afternoon)
+    } else if s.eat_any(&["晚上", "傍晚", "夜里", "晚"]) {
+        Some(Period::Evening)
+    } else {
+        None
+    }
+}
+
+/// A clock time as `(hour, minute, period)`. Bare numbers only count after
+/// "at", so "5 things" is not read as five o'clock.
+fn clock(s: &mut Scanner) -> Option<(u32, u32, Option<Period>)> {
+    s.attempt(|s| {
+        s.skip_spaces();
+        let at = s.eat_any(&["at", "@"]);
+        s.skip_spaces();
+        if s.eat("noon") {
+            return Some((12, 0, None));
+        }
+        if s.eat("midnight") {
+            return Some((0, 0, None));
+        }
+        let period = chinese_period(s);
+        let hour = s.number()?;
+        if s.eat_any(&["点钟", "点", "时"]) {
+            let minute = if s.eat("半") {
+                30
+            } else if s.eat("一刻") {
+                15
+            } else if s.eat("三刻") {
+                45
+            } else {
+                s.attempt(|s| {
+                    let minute = s.number()?;
+                    s.eat("分");
+                    Some(minute)
+                })
+                .unwrap_or(0)
+            };
+            return Some((hour, minute, period));
+        }
+        let minute = s.attempt(|s| if s.eat(":") { s.number() } else { None });
+        let meridiem = s.attempt(|s| {
+            s.skip_spaces();
+            if s.eat_any(&["am", "a.m."]) {
+                Some(Period::Morning)
+            } else if s.eat_any(&["pm", "p.m."]) {
+                Some(Period::Afternoon)
+            } else {
+                None
+            }
+        });
+        if !at && minute.is_none() && meridiem.is_none() && period.is_none() {
+            return None;
+        }
+        Some((hour, minute.unwrap_or(0), meridiem.or(period)))
+    })
+}
+
+fn to_time(hour: u32, minute: u32, period: Option<Period>) -> Option<NaiveTime> {
+    let hour = match period {
+        Some(Period::Morning | Period::Evening) if hour == 12 => 0,
+        Some(Period::Noon) if (1..=5).contains(&hour) => hour + 12,
+        Some(Period::Afternoon | Period::Evening) if hour < 12 => hour + 12,
+        _ => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn local_to_utc(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        // Skipped by a DST change: use the same time an hour later
+        .or_else(|| {
+            tz.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+}
+
+fn cron_weekday(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Mon",
+        Weekday::Tue => "Tue",
+        Weekday::Wed => "Wed",
+        Weekday::Thu => "Thu",
+        Weekday::Fri => "Fri",
+        Weekday::Sat => "Sat",
+        Weekday::Sun => "Sun",
+    }
+}
+
+/// Parse a time expression at the start of `input`, returning its schedule
+/// and the rest of the input. One-off times may be in the past; adding the
+/// job rejects them.
+pub fn parse_schedule_prefix(input: &str, now: DateTime<Utc>, tz: Tz) -> Option<(Schedule, &str)> {
+    let mut s = Scanner { rest: input };
+    if let Some(secs) = relative(&mut s) {
+        let at = now.checked_add_signed(Duration::try_seconds(secs)?)?;
+        return Some((Schedule::At { at }, s.rest));
+    }
+
+    // The day and the clock time may come in either order
+    let (mut day_part, mut clock_part, mut period) = (None, None, None);
+    for _ in 0..2 {
+        if day_part.is_none() {
+            if let Some((found, hint)) = day(&mut s) {
+                day_part = Some(found);
+                period = hint;
+                continue;
+            }
+        }
+        if clock_part.is_none() {
+            if let Some(found) = clock(&mut s) {
+                clock_part = Some(found);
+                continue;
+            }
+        }
+        break;
+    }
+    if day_part.is_none() && clock_part.is_none() {
+        let every_ms = u64::try_from(interval(&mut s)?).ok()?.checked_mul(1000)?;
+        return Some((Schedule::Every { every_ms }, s.rest));
+    }
+
+    let time = match clock_part {
+        Some((hour, minute, hint)) => to_time(hour, minute, hint.or(period))?,
+        None if period == Some(Period::Evening) => NaiveTime::from_hms_opt(20, 0, 0)?,
+        None => NaiveTime::from_hms_opt(9, 0, 0)?,
+    };
+    let weekdays = match day_part {
+        Some(Day::Daily) => Some("*"),
+        Some(Day::Weekdays) => Some("Mon-Fri"),
+        Some(Day::Weekly(day)) => Some(cron_weekday(day)),
+        _ => None,
+    };
+    if let Some(weekdays) = weekdays {
+        let schedule = Schedule::Cron {
+            expr: format!("{} {} * * {weekdays}", time.minute(), time.hour()),
+            tz: Some(tz.name().to_string()),
+        };
+        return Some((schedule, s.rest));
+    }
+
+    let today = now.with_timezone(&tz).date_naive();
+    let date = match day_part {
+        Some(Day::Offset(days)) => today + Duration::days(days),
+        Some(Day::Next(day)) => {
+            let ahead =
+                (7 + day.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+            today + Duration::days(i64::from(if ahead == 0 { 7 } else { ahead }))
+        }
+        Some(Day::Date(date)) => date,
+        _ => today,
+    };
+    let mut at = local_to_utc(tz, date.and_time(time))?;
+    if day_part.is_none() && at <= now {
+        at = local_to_utc(tz, (date + Duration::days(1)).and_time(time))?;
+    }
+    Some((Schedule::At { at }, s.rest))
+}
+
+/// Parse a whole time expression.
+pub fn parse_schedule(input: &str, now: DateTime<Utc>, tz: Tz) -> Result<Schedule> {
+    match parse_schedule_prefix(input, now, tz) {
+        Some((schedule, rest)) if rest.trim().is_empty() => Ok(schedule),
+        _ => bail!(
+            "Could not understand the time {input:?}; try \"in 30m\", \"tomorrow 9am\" or \"every monday at 8:30\""
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monday 2026-01-05 10:00 in Shanghai.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 5, 2, 0, 0).unwrap()
+    }
+
+    fn at(input: &str) -> DateTime<Tz> {
+        match parse_schedule(input, now(), Tz::Asia__Shanghai).unwrap() {
+            Schedule::At { at } => at.with_timezone(&Tz::Asia__Shanghai),
+            other => panic!("{input}: expected a one-off time, got {other:?}"),
+        }
+    }
+
+    fn local(day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        Tz::Asia__Shanghai
+            .with_ymd_and_hms(2026, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn relative_times() {
+        assert_eq!(at("in 30m"), local(5, 10, 30));
+        assert_eq!(at("in 1h30m"), local(5, 11, 30));
+        assert_eq!(at("in 2 hours and 15 minutes"), local(5, 12, 15));
+        assert_eq!(at("in an hour"), local(5, 11, 0));
+        assert_eq!(at("半小时后"), local(5, 10, 30));
+        assert_eq!(at("两个小时后"), local(5, 12, 0));
+        assert_eq!(at("3天后"), local(8, 10, 0));
+    }
+
+    #[test]
+    fn clock_times_and_days() {
+        assert_eq!(at("tomorrow 9am"), local(6, 9, 0));
+        assert_eq!(at("9:15pm tomorrow"), local(6, 21, 15));
+        assert_eq!(at("at 17:30"), local(5, 17, 30));
+        assert_eq!(at("today at 12pm"), local(5, 12, 0));
+        // Already past today, so tomorrow
+        assert_eq!(at("8am"), local(6, 8, 0));
+        assert_eq!(at("tonight"), local(5, 20, 0));
+        assert_eq!(at("friday at noon"), local(9, 12, 0));
+        assert_eq!(at("next monday"), local(12, 9, 0));
+        assert_eq!(
+            at("2026-02-01 08:00"),
+            Tz::Asia__Shanghai
+                .with_ymd_and_hms(2026, 2, 1, 8, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(at("明天下午3点半"), local(6, 15, 30));
+        assert_eq!(at("今晚8点"), local(5, 20, 0));
+        assert_eq!(at("周三早上7点15分"), local(7, 7, 15));
+    }
+
+    #[test]
+    fn recurring_times_keep_the_timezone() {
+        let cron = |expr: &str| Schedule::Cron {
+            expr: expr.to_string(),
+            tz: Some("Asia/Shanghai".to_string()),
+        };
+        let parse = |input| parse_schedule(input, now(), Tz::Asia__Shanghai).unwrap();
+        assert_eq!(parse("每天晚上8点"), cron("0 20 * * *"));
+        assert_eq!(parse("every day at 7:45am"), cron("45 7 * * *"));
+        assert_eq!(parse("every weekday at 9"), cron("0 9 * * Mon-Fri"));
+        assert_eq!(parse("每周一上午10点"), cron("0 10 * * Mon"));
+        assert_eq!(
+            parse("every 2 hours"),
+            Schedule::Every {
+                every_ms: 7_200_000
+            }
+        );
+        assert_eq!(
+            parse("每30分钟"),
+            Schedule::Every {
+                every_ms: 1_800_000
+            }
+        );
+    }
+
+    #[test]
+    fn prefix_parsing_leaves_the_rest() {
+        let (schedule, rest) =
+            parse_schedule_prefix("in 30m to restart the build", now(), Tz::UTC).unwrap();
+        assert_eq!(
+            schedule,
+            Schedule::At {
+                at: now() + Duration::minutes(30)
+            }
+        );
+        assert_eq!(rest, " to restart the build");
+        let (_, rest) = parse_schedule_prefix("明天早上9点开会", now(), Tz::UTC).unwrap();
+        assert_eq!(rest, "开会");
+        assert!(parse_schedule_prefix("5 things to do", now(), Tz::UTC).is_none());
+        assert!(parse_schedule("in 5 months", now(), Tz::UTC).is_err());
+    }
+}
@@ -231,7 +231,10 @@ async fn deliver_if_configured(config: &Config, job: &CronJob, output: &str) ->
                 .telegram
                 .as_ref()
                 .ok_or_else(|| anyhow::anyhow!("telegram channel not configured"))?;
-            let channel = TelegramChannel::new(tg.bot_token.clone(), tg.allowed_users.clone());
+            let channel = TelegramChannel::with_secret(
+                crate::channels::resolve_channel_secret(config, &tg.bot_token)?,
+                tg.allowed_users.clone(),
+            );
             channel.send(output, target).await?;
         }
         "discord" => {
@@ -240,8 +243,8 @@ async fn deliver_if_configured(config: &Config, job: &CronJob, output: &str) ->
                 .discord
                 .as_ref()
                 .ok_or_else(|| anyhow::anyhow!("discord channel not configured"))?;
-            let channel = DiscordChannel::new(
-                dc.bot_token.clone(),
+            let channel = DiscordChannel::with_secret(
+                crate::channels::resolve_channel_secret(config, &dc.bot_token)?,
                 dc.guild_id.clone(),
                 dc.allowed_users.clone(),
                 dc.listen_to_bots,
@@ -255,8 +258,8 @@ async fn deliver_if_configured(config: &Config, job: &CronJob, output: &str) ->
                 .slack
                 .as_ref()
                 .ok_or_else(|| anyhow::anyhow!("slack channel not configured"))?;
-            let channel = SlackChannel::new(
-                sl.bot_token.clone(),
+            let channel = SlackChannel::with_secret(
+                crate::channels::resolve_channel_secret(config, &sl.bot_token)?,
                 sl.channel_id.clone(),
                 sl.allowed_users.clone(),
             );
@@ -0,0 +1,257 @@
+//! systemd supervision (`zeroclaw daemon --systemd`).
+//!
+//! Speaks the `sd_notify` protocol over `$NOTIFY_SOCKET`: `READY=1` once the
+//! gateway answers `GET /health`, `WATCHDOG=1` only while it keeps
+//! answering, and `STOPPING=1` on shutdown. A daemon whose gateway has hung
+//! stops petting the watchdog, so systemd restarts it even though the
+//! process is still alive. Meant for units with `Type=notify` and
+//! `WatchdogSec=`, as written by `zeroclaw service install`.
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsString;
+use tokio::time::Duration;
+
+/// How often the gateway is probed when systemd set no watchdog.
+const DEFAULT_PROBE_SECONDS: u64 = 10;
+/// How often the gateway is probed until it first answers.
+const STARTUP_PROBE_SECONDS: u64 = 1;
+/// Upper bound on one `/health` probe.
+const PROBE_TIMEOUT_SECONDS: u64 = 5;
+
+/// Sends state changes to the service manager.
+#[derive(Clone)]
+pub struct Notifier {
+    socket: OsString,
+}
+
+impl Notifier {
+    /// The notifier systemd handed this process, if any.
+    pub fn from_env() -> Option<Self> {
+        std::env::var_os("NOTIFY_SOCKET")
+            .filter(|socket| !socket.is_empty())
+            .map(|socket| Self { socket })
+    }
+
+    /// Send newline-separated `KEY=VALUE` assignments, e.g. `READY=1`.
+    #[cfg(unix)]
+    pub fn notify(&self, state: &str) -> Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::UnixDatagram;
+
+        let socket = UnixDatagram::unbound()?;
+        let path = self.socket.as_bytes();
+        if let Some(name) = path.strip_prefix(b"@") {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            {
+                #[cfg(target_os = "android")]
+                use std::os::android::net::SocketAddrExt;
+                #[cfg(target_os = "linux")]
+                use std::os::linux::net::SocketAddrExt;
+
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &addr)?;
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            {
+                let _ = name;
+                bail!("Abstract notify sockets are only supported on Linux");
+            }
+        } else {
+            socket.send_to(state.as_bytes(), std::path::Path::new(&self.socket))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn notify(&self, _state: &str) -> Result<()> {
+        bail!("sd_notify is only supported on Unix")
+    }
+
+    /// Like [`Self::notify`], for messages whose loss only costs accuracy.
+    fn send(&self, state: &str) {
+        if let Err(e) = self.notify(state) {
+            tracing::warn!("sd_notify {state:?} failed: {e}");
+        }
+    }
+}
+
+/// The watchdog interval systemd expects pings within, from the values of
+/// `WATCHDOG_USEC` and `WATCHDOG_PID`. `None` when the watchdog is off or
+/// meant for another process.
+fn watchdog_timeout(usec: Option<&str>, pid: Option<&str>) -> Option<Duration> {
+    if let Some(pid) = pid {
+        if pid.trim().parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    let usec = usec?.trim().parse::<u64>().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+fn watchdog_from_env() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok();
+    let pid = std::env::var("WATCHDOG_PID").ok();
+    watchdog_timeout(usec.as_deref(), pid.as_deref())
+}
+
+/// Where to reach the gateway bound to `host:port` from this machine.
+fn health_url(host: &str, port: u16) -> String {
+    let host = match host.trim_start_matches('[').trim_end_matches(']') {
+        "0.0.0.0" | "" => "127.0.0.1".to_string(),
+        "::" => "[::1]".to_string(),
+        other if other.contains(':') => format!("[{other}]"),
+        other => other.to_string(),
+    };
+    format!("http://{host}:{port}/health")
+}
+
+/// Probes the gateway the way a client would.
+struct GatewayProbe {
+    client: reqwest::Client,
+    /// `None` for a random port, which only the gateway itself knows
+    url: Option<String>,
+}
+
+impl GatewayProbe {
+    fn new(host: &str, port: u16) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROBE_TIMEOUT_SECONDS))
+            .build()
+            .context("Failed to build gateway health probe")?;
+        Ok(Self {
+            client,
+            url: (port != 0).then(|| health_url(host, port)),
+        })
+    }
+
+    /// Why the gateway is not serving, or `None` when it is.
+    async fn check(&self) -> Option<String> {
+        let snapshot = crate::health::snapshot_json();
+        let component = &snapshot["components"]["gateway"];
+        if component["status"] != "ok" {
+            let error = component["last_error"].as_str().unwrap_or("not started");
+            return Some(format!("gateway is down: {error}"));
+        }
+        let url = self.url.as_deref()?;
+        match self.client.get(url).send().await {
+            Ok(resp) if resp.status().is_success() => None,
+            Ok(resp) => Some(format!("gateway health check returned {}", resp.status())),
+            Err(e) => Some(format!("gateway health check failed: {e}")),
+        }
+    }
+}
+
+/// Check that the daemon runs under systemd before starting anything.
+pub fn notifier() -> Result<Notifier> {
+    let Some(notifier) = Notifier::from_env() else {
+        bail!(
+            "--systemd needs NOTIFY_SOCKET; run the daemon from a unit with Type=notify \
+             (see `zeroclaw service install`)"
+        );
+    };
+    Ok(notifier)
+}
+
+/// Report readiness and pet the watchdog for as long as the gateway on
+/// `host:port` stays healthy.
+pub async fn supervise(notifier: Notifier, host: String, port: u16) -> Result<()> {
+    let probe = GatewayProbe::new(&host, port)?;
+    if probe.url.is_none() {
+        tracing::warn!(
+            "Gateway on a random port; systemd watchdog follows the gateway component status only"
+        );
+    }
+    let watchdog = watchdog_from_env();
+    let period = watchdog.map_or(Duration::from_secs(DEFAULT_PROBE_SECONDS), |timeout| {
+        timeout / 2
+    });
+    if let Some(timeout) = watchdog {
+        tracing::info!("systemd watchdog enabled ({}s)", timeout.as_secs());
+    }
+
+    let mut ready = false;
+    let mut failing = false;
+    loop {
+        match probe.check().await {
+            Some(problem) => {
+                if !failing {
+                    tracing::warn!("Withholding systemd watchdog ping: {problem}");
+                }
+                failing = true;
+                notifier.send(&format!("STATUS={problem}"));
+            }
+            None => {
+                if !ready {
+                    notifier.send(&format!("READY=1\nSTATUS=Gateway serving on {host}:{port}"));
+                } else if failing {
+                    notifier.send("STATUS=Gateway recovered");
+                }
+                ready = true;
+                failing = false;
+                if watchdog.is_some() {
+                    notifier.send("WATCHDOG=1");
+                }
+            }
+        }
+        // Poll quickly until ready so startup isn't held up by the watchdog period
+        let wait = if ready {
+            period
+        } else {
+            Duration::from_secs(STARTUP_PROBE_SECONDS)
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Tell systemd the daemon is shutting down.
+pub fn stopping(notifier: &Notifier) {
+    notifier.send("STOPPING=1");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_timeout_honours_pid_and_zero() {
+        let own = std::process::id().to_string();
+        assert_eq!(
+            watchdog_timeout(Some("30000000"), None),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            watchdog_timeout(Some("30000000"), Some(&own)),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(watchdog_timeout(Some("30000000"), Some("1")), None);
+        assert_eq!(watchdog_timeout(Some("0"), None), None);
+        assert_eq!(watchdog_timeout(None, None), None);
+    }
+
+    #[test]
+    fn health_url_reaches_wildcard_binds_over_loopback() {
+        assert_eq!(health_url("0.0.0.0", 3000), "http://127.0.0.1:3000/health");
+        assert_eq!(health_url("::", 3000), "http://[::1]:3000/health");
+        assert_eq!(
+            health_url("127.0.0.1", 8080),
+            "http://127.0.0.1:8080/health"
+        );
+        assert_eq!(health_url("fe80::1", 80), "http://[fe80::1]:80/health");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn notify_sends_datagram_to_socket() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("notify.sock");
+        let listener = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+        let notifier = Notifier {
+            socket: path.into_os_string(),
+        };
+
+        notifier.notify("READY=1").unwrap();
+        let mut buf = [0u8; 64];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+}
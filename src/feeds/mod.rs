@@ -0,0 +1,288 @@
+//! RSS/Atom feed watcher that posts new entries to channels.
+//!
+//! Each `[[feeds.subscriptions]]` entry is polled on its own interval. New
+//! entries are rendered through the subscription's template and sent to its
+//! channel target as one batch, oldest first and paced by the channel's rate
+//! limits (see [`crate::channels::batch`]), or into a periodic summary when
+//! the subscription has a `digest` (see [`crate::channels::digest`]). Seen entry ids are persisted in
+//! `<workspace>/feeds/seen.db`, so restarts don't re-post anything; the first
+//! poll of a new subscription only records what is already there.
+
+mod parse;
+
+pub use parse::{parse_feed, Feed, FeedEntry};
+
+use crate::channels::control::ChannelControl;
+use crate::config::schema::{FeedSubscriptionConfig, FeedsConfig};
+use crate::util::truncate_with_ellipsis;
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+const FEED_FETCH_TIMEOUT_SECS: u64 = 30;
+/// Longest summary substituted into templates.
+const SUMMARY_MAX_CHARS: usize = 300;
+/// Entries posted per poll at most; the rest are marked seen.
+const MAX_POSTS_PER_POLL: usize = 10;
+
+/// Persisted ids of entries already handled, per subscription.
+pub struct FeedStore {
+    conn: Mutex<Connection>,
+}
+
+impl FeedStore {
+    /// Open (or create) the store under `<workspace>/feeds/`.
+    pub fn open(workspace_dir: &Path) -> Result<Self> {
+        let dir = workspace_dir.join("feeds");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create feeds directory: {}", dir.display()))?;
+        let db_path = dir.join("seen.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open feed DB: {}", db_path.display()))?;
+        Self::with_connection(conn)
+    }
+
+    /// In-memory store for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS seen (
+                feed     TEXT NOT NULL,
+                entry_id TEXT NOT NULL,
+                seen_at  TEXT NOT NULL,
+                PRIMARY KEY (feed, entry_id)
+            );",
+        )
+        .context("Failed to initialize feed schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Whether anything was ever recorded for `feed`.
+    fn knows_feed(&self, feed: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        Ok(conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM seen WHERE feed = ?1)",
+            params![feed],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Record `entry_id`; returns `false` when it was already seen.
+    fn mark_seen(&self, feed: &str, entry_id: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO seen (feed, entry_id, seen_at) VALUES (?1, ?2, ?3)",
+            params![feed, entry_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(inserted > 0)
+    }
+}
+
+/// Render an entry through a template. Placeholders: `{feed}`, `{title}`,
+/// `{link}`, `{summary}`, `{published}`.
+pub fn render_entry(template: &str, feed_title: &str, entry: &FeedEntry) -> String {
+    template
+        .replace("{feed}", feed_title)
+        .replace("{title}", &entry.title)
+        .replace("{link}", &entry.link)
+        .replace(
+            "{summary}",
+            &truncate_with_ellipsis(&entry.summary, SUMMARY_MAX_CHARS),
+        )
+        .replace("{published}", &entry.published)
+        .trim()
+        .to_string()
+}
+
+/// Fetch a subscription and return the entries not seen before, oldest first.
+/// On the first poll of a subscription every entry is recorded and nothing
+/// is returned.
+pub async fn poll_subscription(
+    client: &reqwest::Client,
+    subscription: &FeedSubscriptionConfig,
+    store: &FeedStore,
+) -> Result<(Feed, Vec<FeedEntry>)> {
+    let response = client
+        .get(&subscription.url)
+        .timeout(Duration::from_secs(FEED_FETCH_TIMEOUT_SECS))
+        .send()
+        .await?
+        .error_for_status()?;
+    let body = response.text().await?;
+    let feed = parse_feed(&body).with_context(|| format!("Invalid feed {}", subscription.url))?;
+
+    let seeding = !store.knows_feed(&subscription.name)?;
+    let mut fresh = Vec::new();
+    // Feeds list newest first; walk oldest first so posts keep their order.
+    for entry in feed.entries.iter().rev() {
+        if store.mark_seen(&subscription.name, &entry.id)? && !seeding {
+            fresh.push(entry.clone());
+        }
+    }
+    if fresh.len() > MAX_POSTS_PER_POLL {
+        fresh.drain(..fresh.len() - MAX_POSTS_PER_POLL);
+    }
+    Ok((feed, fresh))
+}
+
+/// Poll every subscription forever, sending new entries through `control`.
+pub async fn run_feed_watcher(
+    config: FeedsConfig,
+    workspace_dir: &Path,
+    control: Arc<ChannelControl>,
+) -> Result<()> {
+    let store = Arc::new(FeedStore::open(workspace_dir)?);
+    let client = reqwest::Client::new();
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for subscription in config.subscriptions {
+        let store = Arc::clone(&store);
+        let client = client.clone();
+        let control = Arc::clone(&control);
+        let template = subscription
+            .template
+            .clone()
+            .unwrap_or_else(|| config.template.clone());
+        let interval = subscription
+            .interval_secs
+            .unwrap_or(config.interval_secs)
+            .max(60);
+        tasks.spawn(async move {
+            let component = format!("feed:{}", subscription.name);
+            let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+            loop {
+                ticker.tick().await;
+                match poll_subscription(&client, &subscription, &store).await {
+                    Ok((feed, entries)) => {
+                        crate::health::mark_component_ok(&component);
+                        let feed_title = if feed.title.is_empty() {
+                            subscription.name.as_str()
+                        } else {
+                            feed.title.as_str()
+                        };
+                        if let Some(digest) = &subscription.digest {
+                            for entry in &entries {
+                                control.digests().queue(
+                                    &subscription.channel,
+                                    &subscription.recipient,
+                                    feed_title,
+                                    digest,
+                                    &render_entry(&template, feed_title, entry),
+                                );
+                            }
+                            continue;
+                        }
+                        if entries.is_empty() {
+                            continue;
+                        }
+                        let count = entries.len();
+                        let messages = entries
+                            .iter()
+                            .map(|entry| render_entry(&template, feed_title, entry))
+                            .collect();
+                        if let Err(e) = control
+                            .send_batch(&subscription.channel, &subscription.recipient, messages)
+                            .await
+                        {
+                            tracing::warn!(
+                                "Feed {}: failed to post {count} new entries: {e}",
+                                subscription.name
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Feed {} poll failed: {e}", subscription.name);
+                        crate::health::mark_component_error(&component, e.to_string());
+                    }
+                }
+            }
+        });
+    }
+
+    while tasks.join_next().await.is_some() {}
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rss(guids: &[&str]) -> String {
+        let items = guids
+            .iter()
+            .map(|g| format!("<item><title>Post {g}</title><guid>{g}</guid></item>"))
+            .collect::<Vec<_>>()
+            .concat();
+        format!("<rss><channel><title>Blog</title>{items}</channel></rss>")
+    }
+
+    #[tokio::test]
+    async fn first_poll_seeds_and_later_polls_return_new_entries_oldest_first() {
+        use axum::{extract::State, routing::get, Router};
+
+        let body = Arc::new(Mutex::new(rss(&["2", "1"])));
+        let app = Router::new()
+            .route(
+                "/feed.xml",
+                get(|State(body): State<Arc<Mutex<String>>>| async move { body.lock().clone() }),
+            )
+            .with_state(Arc::clone(&body));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let subscription = FeedSubscriptionConfig {
+            name: "blog".into(),
+            url: format!("http://{addr}/feed.xml"),
+            channel: "telegram".into(),
+            recipient: "42".into(),
+            interval_secs: None,
+            template: None,
+            digest: None,
+        };
+        let store = FeedStore::in_memory().unwrap();
+        let client = reqwest::Client::new();
+
+        let (_, entries) = poll_subscription(&client, &subscription, &store)
+            .await
+            .unwrap();
+        assert!(entries.is_empty());
+
+        *body.lock() = rss(&["4", "3", "2", "1"]);
+        let (feed, entries) = poll_subscription(&client, &subscription, &store)
+            .await
+            .unwrap();
+        let ids: Vec<_> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["3", "4"]);
+        assert_eq!(feed.title, "Blog");
+
+        let (_, entries) = poll_subscription(&client, &subscription, &store)
+            .await
+            .unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn render_entry_fills_placeholders() {
+        let entry = FeedEntry {
+            id: "1".into(),
+            title: "Hello".into(),
+            link: "https://example.com/1".into(),
+            summary: "World".into(),
+            published: String::new(),
+        };
+        assert_eq!(
+            render_entry("📰 {feed}: {title}\n{summary}\n{link}", "Blog", &entry),
+            "📰 Blog: Hello\nWorld\nhttps://example.com/1"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Minimal RSS 2.0 / Atom parser: just the fields the watcher posts.
+
+use anyhow::{bail, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// One item of an RSS channel or entry of an Atom feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedEntry {
+    /// `<guid>` / `<id>`, falling back to the link, then the title
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    /// Plain-text summary with markup stripped
+    pub summary: String,
+    /// Publication date as given by the feed
+    pub published: String,
+}
+
+/// Parsed feed: its title and entries in document order (usually newest first).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Feed {
+    pub title: String,
+    pub entries: Vec<FeedEntry>,
+}
+
+pub fn parse_feed(xml: &str) -> Result<Feed> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut feed = Feed::default();
+    let mut seen_root = false;
+    let mut entry: Option<FeedEntry> = None;
+    let mut path: Vec<String> = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = local_name(e.name().as_ref());
+                if matches!(name.as_str(), "rss" | "feed" | "RDF") {
+                    seen_root = true;
+                }
+                if matches!(name.as_str(), "item" | "entry") {
+                    entry = Some(FeedEntry::default());
+                }
+                if let (Some(entry), "link") = (entry.as_mut(), name.as_str()) {
+                    set_atom_link(entry, &e);
+                }
+                path.push(name);
+                text.clear();
+            }
+            Event::Empty(e) => {
+                if let (Some(entry), "link") =
+                    (entry.as_mut(), local_name(e.name().as_ref()).as_str())
+                {
+                    set_atom_link(entry, &e);
+                }
+            }
+            Event::Text(t) => text.push_str(&t.unescape()?),
+            Event::CData(t) => text.push_str(&String::from_utf8_lossy(&t.into_inner())),
+            Event::End(_) => {
+                let name = path.pop().unwrap_or_default();
+                let value = text.trim().to_string();
+                text.clear();
+                match (entry.as_mut(), name.as_str()) {
+                    (Some(_), "item" | "entry") => {
+                        let mut done = entry.take().unwrap_or_default();
+                        if done.id.is_empty() {
+                            done.id = if done.link.is_empty() {
+                                done.title.clone()
+                            } else {
+                                done.link.clone()
+                            };
+                        }
+                        if !done.id.is_empty() {
+                            feed.entries.push(done);
+                        }
+                    }
+                    (Some(entry), "title") => entry.title = value,
+                    (Some(entry), "guid" | "id") => entry.id = value,
+                    (Some(entry), "link") if !value.is_empty() => entry.link = value,
+                    (Some(entry), "description" | "summary" | "content")
+                        if entry.summary.is_empty() =>
+                    {
+                        entry.summary = strip_markup(&value);
+                    }
+                    (Some(entry), "pubDate" | "published" | "updated" | "date")
+                        if entry.published.is_empty() =>
+                    {
+                        entry.published = value;
+                    }
+                    (None, "title") if feed.title.is_empty() => feed.title = value,
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !seen_root {
+        bail!("Not an RSS or Atom document");
+    }
+    Ok(feed)
+}
+
+/// Element name without its namespace prefix (`dc:date` → `date`).
+fn local_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    name.rsplit(':').next().unwrap_or_default().to_string()
+}
+
+/// Atom links carry the URL in `href`; prefer `rel="alternate"` (or no rel).
+fn set_atom_link(entry: &mut FeedEntry, element: &quick_xml::events::BytesStart<'_>) {
+    let mut href = None;
+    let mut alternate = true;
+    for attr in element.attributes().flatten() {
+        match attr.key.as_ref() {
+            b"href" => href = attr.unescape_value().ok().map(|v| v.to_string()),
+            b"rel" => alternate = attr.value.as_ref() == b"alternate",
+            _ => {}
+        }
+    }
+    if let Some(href) = href {
+        if alternate || entry.link.is_empty() {
+            entry.link = href;
+        }
+    }
+}
+
+/// Drop HTML tags and collapse whitespace.
+fn strip_markup(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rss_items() {
+        let feed = parse_feed(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <title>Rust Blog</title>
+              <item>
+                <title>Rust 1.90</title>
+                <link>https://blog.rust-lang.org/1.90</link>
+                <guid>rust-1.90</guid>
+                <description><![CDATA[<p>New <b>release</b></p>]]></description>
+                <pubDate>Thu, 18 Sep 2025 00:00:00 +0000</pubDate>
+              </item>
+              <item><title>No guid</title><link>https://example.com/a</link></item>
+            </channel></rss>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title, "Rust Blog");
+        assert_eq!(feed.entries.len(), 2);
+        assert_eq!(feed.entries[0].id, "rust-1.90");
+        assert_eq!(feed.entries[0].summary, "New release");
+        assert_eq!(feed.entries[0].published, "Thu, 18 Sep 2025 00:00:00 +0000");
+        assert_eq!(feed.entries[1].id, "https://example.com/a");
+    }
+
+    #[test]
+    fn parses_atom_entries() {
+        let feed = parse_feed(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Releases</title>
+              <entry>
+                <id>tag:github.com,2008:v1.0</id>
+                <title>v1.0 &amp; more</title>
+                <link rel="replies" href="https://example.com/comments"/>
+                <link rel="alternate" href="https://example.com/v1.0"/>
+                <updated>2025-01-01T00:00:00Z</updated>
+                <summary>First stable</summary>
+              </entry>
+            </feed>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title, "Releases");
+        let entry = &feed.entries[0];
+        assert_eq!(entry.title, "v1.0 & more");
+        assert_eq!(entry.link, "https://example.com/v1.0");
+        assert_eq!(entry.summary, "First stable");
+        assert_eq!(entry.published, "2025-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn rejects_non_feeds() {
+        assert!(parse_feed("<html><body>nope</body></html>").is_err());
+    }
+}
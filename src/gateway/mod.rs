@@ -7,19 +7,20 @@
 //! - Request timeouts (30s) to prevent slow-loris attacks
 //! - Header sanitization (handled by axum/hyper)
 
-use crate::channels::{Channel, WhatsAppChannel};
+use crate::channels::{Channel, QQChannel, QQEnvironment, SlackChannel, WhatsAppChannel};
 use crate::config::Config;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::providers::{self, Provider};
 use crate::runtime;
 use crate::security::pairing::{constant_time_eq, is_public_bind, PairingGuard};
+use crate::security::webhook_security::{self, SignedWebhookVerifier};
 use crate::security::SecurityPolicy;
 use crate::tools;
 use crate::util::truncate_with_ellipsis;
 use anyhow::Result;
 use axum::{
     body::Bytes,
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Json},
     routing::{get, post},
@@ -183,12 +184,20 @@ pub struct AppState {
     pub auto_save: bool,
     /// SHA-256 hash of `X-Webhook-Secret` (hex-encoded), never plaintext.
     pub webhook_secret_hash: Option<Arc<str>>,
+    /// Verifies `X-Zeroclaw-Signature` when the webhook has a `signing_secret`
+    pub webhook_signature: Option<Arc<SignedWebhookVerifier>>,
     pub pairing: Arc<PairingGuard>,
     pub rate_limiter: Arc<GatewayRateLimiter>,
     pub idempotency_store: Arc<IdempotencyStore>,
     pub whatsapp: Option<Arc<WhatsAppChannel>>,
     /// `WhatsApp` app secret for webhook signature verification (`X-Hub-Signature-256`)
     pub whatsapp_app_secret: Option<Arc<str>>,
+    /// QQ bot in callback mode, answering `POST /qq`
+    pub qq: Option<Arc<QQChannel>>,
+    /// Slack app receiving the events API on `POST /slack/events`
+    pub slack: Option<Arc<SlackChannel>>,
+    /// Verifies `X-Slack-Signature` with the Slack app's signing secret
+    pub slack_signature: Option<Arc<SignedWebhookVerifier>>,
 }
 
 /// Run the HTTP gateway using axum with proper HTTP/1.1 compliance.
@@ -265,15 +274,33 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
             })
         });
 
+    // Signing secret for timestamped HMAC signatures of webhook requests
+    let webhook_signature: Option<Arc<SignedWebhookVerifier>> = config
+        .channels_config
+        .webhook
+        .as_ref()
+        .and_then(|webhook| webhook.signing_secret.as_deref())
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| {
+            Arc::new(SignedWebhookVerifier::new(
+                secret,
+                Duration::from_secs(webhook_security::DEFAULT_REPLAY_WINDOW_SECS),
+            ))
+        });
+
     // WhatsApp channel (if configured)
     let whatsapp_channel: Option<Arc<WhatsAppChannel>> =
         config.channels_config.whatsapp.as_ref().map(|wa| {
-            Arc::new(WhatsAppChannel::new(
-                wa.access_token.clone(),
-                wa.phone_number_id.clone(),
-                wa.verify_token.clone(),
-                wa.allowed_numbers.clone(),
-            ))
+            Arc::new(
+                WhatsAppChannel::new(
+                    wa.access_token.clone(),
+                    wa.phone_number_id.clone(),
+                    wa.verify_token.clone(),
+                    wa.allowed_numbers.clone(),
+                )
+                .with_session_template(wa.session_template.clone(), wa.template_language.clone()),
+            )
         });
 
     // WhatsApp app secret for webhook signature verification
@@ -295,6 +322,59 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         })
         .map(Arc::from);
 
+    // QQ bot in callback mode: events are POSTed to /qq
+    let qq_channel: Option<Arc<QQChannel>> =
+        match config.channels_config.qq.as_ref().filter(|qq| qq.callback) {
+            Some(qq) => Some(Arc::new(
+                QQChannel::with_secret(
+                    qq.app_id.clone(),
+                    crate::channels::resolve_channel_secret(&config, &qq.app_secret)?,
+                    qq.allowed_users.clone(),
+                )
+                .with_environment(QQEnvironment::from_config(qq)?)
+                .with_callback(true),
+            )),
+            None => None,
+        };
+
+    // Slack events API: requests are signed with the app's signing secret
+    let (slack_channel, slack_signature) = match config
+        .channels_config
+        .slack
+        .as_ref()
+        .and_then(|sl| Some((sl, sl.signing_secret.as_deref()?)))
+    {
+        Some((sl, raw_secret)) => {
+            let secret = crate::channels::resolve_channel_secret(&config, raw_secret)?
+                .get()
+                .await?;
+            let channel = SlackChannel::with_secret(
+                crate::channels::resolve_channel_secret(&config, &sl.bot_token)?,
+                sl.channel_id.clone(),
+                sl.allowed_users.clone(),
+            )
+            .with_oauth(crate::channels::slack_oauth(&config, sl)?)
+            .with_events_api(true);
+            (
+                Some(Arc::new(channel)),
+                Some(Arc::new(SignedWebhookVerifier::slack(
+                    secret.trim(),
+                    Duration::from_secs(webhook_security::DEFAULT_REPLAY_WINDOW_SECS),
+                ))),
+            )
+        }
+        None => (None, None),
+    };
+
+    // Media store, unless the channels in this process already set it up
+    if crate::media_store::global().is_none() {
+        crate::media_store::install(crate::media_store::MediaStore::from_config(
+            &config.media_store,
+            &config.workspace_dir,
+            |raw| crate::channels::resolve_channel_secret(&config, raw),
+        )?);
+    }
+
     // ── Pairing guard ──────────────────────────────────────
     let pairing = Arc::new(PairingGuard::new(
         config.gateway.require_pairing,
@@ -336,7 +416,16 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         println!("  GET  /whatsapp  — Meta webhook verification");
         println!("  POST /whatsapp  — WhatsApp message webhook");
     }
+    if qq_channel.is_some() {
+        println!("  POST /qq        — QQ bot event callback");
+    }
+    if slack_channel.is_some() {
+        println!("  POST /slack/events — Slack events API");
+    }
     println!("  GET  /health    — health check");
+    if crate::media_store::global().is_some() {
+        println!("  GET  /media/*   — signed links to stored media");
+    }
     if let Some(code) = pairing.pairing_code() {
         println!();
         println!("  🔐 PAIRING REQUIRED — use this one-time code:");
@@ -361,11 +450,15 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         mem,
         auto_save: config.memory.auto_save,
         webhook_secret_hash,
+        webhook_signature,
         pairing,
         rate_limiter,
         idempotency_store,
         whatsapp: whatsapp_channel,
         whatsapp_app_secret,
+        qq: qq_channel,
+        slack: slack_channel,
+        slack_signature,
     };
 
     // Build router with middleware
@@ -375,6 +468,9 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         .route("/webhook", post(handle_webhook))
         .route("/whatsapp", get(handle_whatsapp_verify))
         .route("/whatsapp", post(handle_whatsapp_message))
+        .route("/qq", post(handle_qq_callback))
+        .route("/slack/events", post(handle_slack_events))
+        .route("/media/{*key}", get(handle_media))
         .with_state(state)
         .layer(RequestBodyLimitLayer::new(MAX_BODY_SIZE))
         .layer(TimeoutLayer::with_status_code(
@@ -398,6 +494,7 @@ async fn handle_health(State(state): State<AppState>) -> impl IntoResponse {
         "status": "ok",
         "paired": state.pairing.is_paired(),
         "runtime": crate::health::snapshot_json(),
+        "circuits": crate::circuit::snapshot(),
     });
     Json(body)
 }
@@ -457,7 +554,7 @@ pub struct WebhookBody {
 async fn handle_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
-    body: Result<Json<WebhookBody>, axum::extract::rejection::JsonRejection>,
+    body: Bytes,
 ) -> impl IntoResponse {
     let client_key = client_key_from_headers(&headers);
     if !state.rate_limiter.allow_webhook(&client_key) {
@@ -503,8 +600,17 @@ async fn handle_webhook(
         }
     }
 
+    // ── Signed requests (optional): HMAC, replay window, replayed signatures ──
+    if let Some(ref verifier) = state.webhook_signature {
+        if let Err(e) = verifier.verify(&headers, &body) {
+            tracing::warn!("Webhook: rejected request — {e}");
+            let err = serde_json::json!({"error": format!("Unauthorized — {e}")});
+            return (StatusCode::UNAUTHORIZED, Json(err));
+        }
+    }
+
     // ── Parse body ──
-    let Json(webhook_body) = match body {
+    let webhook_body = match serde_json::from_slice::<WebhookBody>(&body) {
         Ok(b) => b,
         Err(e) => {
             tracing::warn!("Webhook JSON parse error: {e}");
@@ -600,31 +706,42 @@ async fn handle_whatsapp_verify(
     (StatusCode::FORBIDDEN, "Forbidden".to_string())
 }
 
+/// Signed media link query params
+#[derive(serde::Deserialize)]
+pub struct MediaQuery {
+    pub expires: u64,
+    pub sig: String,
+}
+
+/// GET /media/{key} — stored media behind a link signed by the media store
+async fn handle_media(
+    Path(key): Path<String>,
+    Query(params): Query<MediaQuery>,
+) -> impl IntoResponse {
+    let Some(store) = crate::media_store::global() else {
+        return (StatusCode::NOT_FOUND, "Media store not configured").into_response();
+    };
+    if !store.verify_url(&key, params.expires, &params.sig) {
+        return (StatusCode::FORBIDDEN, "Invalid or expired link").into_response();
+    }
+    match store.load(&key).await {
+        Ok(bytes) => (
+            [(header::CONTENT_TYPE, crate::media_store::mime_for_key(&key))],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::debug!("Media {key} unavailable: {e}");
+            (StatusCode::NOT_FOUND, "Not found").into_response()
+        }
+    }
+}
+
 /// Verify `WhatsApp` webhook signature (`X-Hub-Signature-256`).
 /// Returns true if the signature is valid, false otherwise.
 /// See: <https://developers.facebook.com/docs/graph-api/webhooks/getting-started#verification-requests>
 pub fn verify_whatsapp_signature(app_secret: &str, body: &[u8], signature_header: &str) -> bool {
-    use hmac::{Hmac, Mac};
-    use sha2::Sha256;
-
-    // Signature format: "sha256=<hex_signature>"
-    let Some(hex_sig) = signature_header.strip_prefix("sha256=") else {
-        return false;
-    };
-
-    // Decode hex signature
-    let Ok(expected) = hex::decode(hex_sig) else {
-        return false;
-    };
-
-    // Compute HMAC-SHA256
-    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes()) else {
-        return false;
-    };
-    mac.update(body);
-
-    // Constant-time comparison
-    mac.verify_slice(&expected).is_ok()
+    webhook_security::verify_hmac_sha256(app_secret, body, signature_header)
 }
 
 /// POST /whatsapp — incoming message webhook
@@ -671,6 +788,9 @@ async fn handle_whatsapp_message(
         );
     };
 
+    // Delivery and read receipts of tracked messages
+    wa.record_statuses(&payload);
+
     // Parse messages from the webhook payload
     let messages = wa.parse_webhook_payload(&payload);
 
@@ -680,7 +800,22 @@ async fn handle_whatsapp_message(
     }
 
     // Process each message
-    for msg in &messages {
+    for mut msg in messages {
+        if !msg.attachments.is_empty() {
+            if let Some(store) = crate::media_store::global().filter(|s| s.persist_inbound()) {
+                store.persist_attachments(wa.as_ref(), &mut msg).await;
+            }
+        }
+        if msg.content.trim().is_empty() {
+            // Media without a caption; there is no text to answer
+            tracing::info!(
+                "WhatsApp media from {} ({} attachment(s))",
+                msg.sender,
+                msg.attachments.len()
+            );
+            continue;
+        }
+
         tracing::info!(
             "WhatsApp message from {}: {}",
             msg.sender,
@@ -689,7 +824,7 @@ async fn handle_whatsapp_message(
 
         // Auto-save to memory
         if state.auto_save {
-            let key = whatsapp_memory_key(msg);
+            let key = whatsapp_memory_key(&msg);
             let _ = state
                 .mem
                 .store(&key, &msg.content, MemoryCategory::Conversation, None)
@@ -724,6 +859,149 @@ async fn handle_whatsapp_message(
     (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
 }
 
+/// Answer a message that arrived by callback on `channel`, the way
+/// `/whatsapp` does: remember it, ask the model, send the reply.
+async fn answer_callback_message(
+    state: AppState,
+    channel: Arc<dyn Channel>,
+    msg: crate::channels::traits::ChannelMessage,
+) {
+    tracing::info!(
+        "{} message from {}: {}",
+        channel.name(),
+        msg.sender,
+        truncate_with_ellipsis(&msg.content, 50)
+    );
+
+    if state.auto_save {
+        let key = format!("{}_{}_{}", channel.name(), msg.sender, msg.id);
+        let _ = state
+            .mem
+            .store(&key, &msg.content, MemoryCategory::Conversation, None)
+            .await;
+    }
+
+    let reply = match state
+        .provider
+        .simple_chat(&msg.content, &state.model, state.temperature)
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!("LLM error for {} message: {e:#}", channel.name());
+            "Sorry, I couldn't process your message right now.".to_string()
+        }
+    };
+    if let Err(e) = channel.send(&reply, &msg.reply_target).await {
+        tracing::error!("Failed to send {} reply: {e}", channel.name());
+    }
+}
+
+/// POST /qq — QQ bot events in callback mode (`X-Signature-Ed25519`)
+async fn handle_qq_callback(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(qq) = state.qq.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "QQ callback mode not configured"})),
+        );
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+    };
+
+    match qq
+        .handle_callback(
+            header("X-Signature-Timestamp"),
+            header("X-Signature-Ed25519"),
+            &body,
+        )
+        .await
+    {
+        Ok((reply, message)) => {
+            // Answered in the background: QQ redelivers callbacks that
+            // aren't acknowledged promptly
+            if let Some(msg) = message.filter(|msg| !msg.content.trim().is_empty()) {
+                tokio::spawn(answer_callback_message(state, qq, msg));
+            }
+            (StatusCode::OK, Json(reply))
+        }
+        Err(e) => {
+            tracing::warn!("QQ callback: rejected request — {e}");
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({"error": "Invalid signature"})),
+            )
+        }
+    }
+}
+
+/// POST /slack/events — Slack events API (`X-Slack-Signature`)
+async fn handle_slack_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let (Some(slack), Some(verifier)) = (state.slack.clone(), state.slack_signature.clone()) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Slack events API not configured"})),
+        );
+    };
+
+    if let Err(e) = verifier.verify(&headers, &body) {
+        tracing::warn!("Slack events: rejected request — {e}");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": "Invalid signature"})),
+        );
+    }
+
+    let Ok(payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid JSON payload"})),
+        );
+    };
+
+    match payload.get("type").and_then(|t| t.as_str()) {
+        Some("url_verification") => {
+            let challenge = payload.get("challenge").cloned().unwrap_or_default();
+            return (
+                StatusCode::OK,
+                Json(serde_json::json!({"challenge": challenge})),
+            );
+        }
+        Some("event_callback") => {}
+        _ => return (StatusCode::OK, Json(serde_json::json!({"status": "ok"}))),
+    }
+
+    // Slack retries events it didn't see acknowledged within 3 seconds,
+    // with a fresh signature
+    if let Some(event_id) = payload.get("event_id").and_then(|id| id.as_str()) {
+        if !state
+            .idempotency_store
+            .record_if_new(&format!("slack:{event_id}"))
+        {
+            return (
+                StatusCode::OK,
+                Json(serde_json::json!({"status": "duplicate"})),
+            );
+        }
+    }
+
+    if let Some(msg) = slack.parse_event_callback(&payload) {
+        tokio::spawn(answer_callback_message(state, slack, msg));
+    }
+    (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -847,11 +1125,9 @@ mod tests {
     fn whatsapp_memory_key_includes_sender_and_message_id() {
         let msg = ChannelMessage {
             id: "wamid-123".into(),
-            sender: "+1234567890".into(),
-            reply_target: "+1234567890".into(),
-            content: "hello".into(),
-            channel: "whatsapp".into(),
             timestamp: 1,
+            received_at: 1,
+            ..ChannelMessage::test("whatsapp", "+1234567890", "hello")
         };
 
         let key = whatsapp_memory_key(&msg);
@@ -1000,27 +1276,27 @@ mod tests {
             mem: memory,
             auto_save: false,
             webhook_secret_hash: None,
+            webhook_signature: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            qq: None,
+            slack: None,
+            slack_signature: None,
         };
 
         let mut headers = HeaderMap::new();
         headers.insert("X-Idempotency-Key", HeaderValue::from_static("abc-123"));
 
-        let body = Ok(Json(WebhookBody {
-            message: "hello".into(),
-        }));
+        let body = Bytes::from_static(br#"{"message":"hello"}"#);
         let first = handle_webhook(State(state.clone()), headers.clone(), body)
             .await
             .into_response();
         assert_eq!(first.status(), StatusCode::OK);
 
-        let body = Ok(Json(WebhookBody {
-            message: "hello".into(),
-        }));
+        let body = Bytes::from_static(br#"{"message":"hello"}"#);
         let second = handle_webhook(State(state), headers, body)
             .await
             .into_response();
@@ -1048,26 +1324,26 @@ mod tests {
             mem: memory,
             auto_save: true,
             webhook_secret_hash: None,
+            webhook_signature: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            qq: None,
+            slack: None,
+            slack_signature: None,
         };
 
         let headers = HeaderMap::new();
 
-        let body1 = Ok(Json(WebhookBody {
-            message: "hello one".into(),
-        }));
+        let body1 = Bytes::from_static(br#"{"message":"hello one"}"#);
         let first = handle_webhook(State(state.clone()), headers.clone(), body1)
             .await
             .into_response();
         assert_eq!(first.status(), StatusCode::OK);
 
-        let body2 = Ok(Json(WebhookBody {
-            message: "hello two".into(),
-        }));
+        let body2 = Bytes::from_static(br#"{"message":"hello two"}"#);
         let second = handle_webhook(State(state), headers, body2)
             .await
             .into_response();
@@ -1105,19 +1381,21 @@ mod tests {
             mem: memory,
             auto_save: false,
             webhook_secret_hash: Some(Arc::from(hash_webhook_secret("super-secret"))),
+            webhook_signature: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            qq: None,
+            slack: None,
+            slack_signature: None,
         };
 
         let response = handle_webhook(
             State(state),
             HeaderMap::new(),
-            Ok(Json(WebhookBody {
-                message: "hello".into(),
-            })),
+            Bytes::from_static(br#"{"message":"hello"}"#),
         )
         .await
         .into_response();
@@ -1139,11 +1417,15 @@ mod tests {
             mem: memory,
             auto_save: false,
             webhook_secret_hash: Some(Arc::from(hash_webhook_secret("super-secret"))),
+            webhook_signature: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            qq: None,
+            slack: None,
+            slack_signature: None,
         };
 
         let mut headers = HeaderMap::new();
@@ -1152,9 +1434,7 @@ mod tests {
         let response = handle_webhook(
             State(state),
             headers,
-            Ok(Json(WebhookBody {
-                message: "hello".into(),
-            })),
+            Bytes::from_static(br#"{"message":"hello"}"#),
         )
         .await
         .into_response();
@@ -1176,11 +1456,15 @@ mod tests {
             mem: memory,
             auto_save: false,
             webhook_secret_hash: Some(Arc::from(hash_webhook_secret("super-secret"))),
+            webhook_signature: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
             whatsapp: None,
             whatsapp_app_secret: None,
+            qq: None,
+            slack: None,
+            slack_signature: None,
         };
 
         let mut headers = HeaderMap::new();
@@ -1189,9 +1473,7 @@ mod tests {
         let response = handle_webhook(
             State(state),
             headers,
-            Ok(Json(WebhookBody {
-                message: "hello".into(),
-            })),
+            Bytes::from_static(br#"{"message":"hello"}"#),
         )
         .await
         .into_response();
@@ -1200,6 +1482,117 @@ mod tests {
         assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
     }
 
+    #[tokio::test]
+    async fn webhook_signature_rejects_unsigned_and_replayed_requests() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
+
+        let state = AppState {
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: memory,
+            auto_save: false,
+            webhook_secret_hash: None,
+            webhook_signature: Some(Arc::new(SignedWebhookVerifier::new(
+                "signing-secret",
+                Duration::from_secs(300),
+            ))),
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
+            whatsapp: None,
+            whatsapp_app_secret: None,
+            qq: None,
+            slack: None,
+            slack_signature: None,
+        };
+
+        let body = Bytes::from_static(br#"{"message":"hello"}"#);
+        let unsigned = handle_webhook(State(state.clone()), HeaderMap::new(), body.clone())
+            .await
+            .into_response();
+        assert_eq!(unsigned.status(), StatusCode::UNAUTHORIZED);
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in webhook_security::signature_headers("signing-secret", &body) {
+            headers.insert(name, HeaderValue::from_str(&value).unwrap());
+        }
+        let signed = handle_webhook(State(state.clone()), headers.clone(), body.clone())
+            .await
+            .into_response();
+        assert_eq!(signed.status(), StatusCode::OK);
+
+        let replayed = handle_webhook(State(state), headers, body)
+            .await
+            .into_response();
+        assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn slack_events_require_the_slack_signature() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
+
+        let state = AppState {
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: memory,
+            auto_save: false,
+            webhook_secret_hash: None,
+            webhook_signature: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300))),
+            whatsapp: None,
+            whatsapp_app_secret: None,
+            qq: None,
+            slack: Some(Arc::new(SlackChannel::new(
+                "xoxb-fake".into(),
+                None,
+                vec!["*".into()],
+            ))),
+            slack_signature: Some(Arc::new(SignedWebhookVerifier::slack(
+                "slack-signing-secret",
+                Duration::from_secs(300),
+            ))),
+        };
+
+        let body = Bytes::from_static(br#"{"type":"url_verification","challenge":"c4ll3nge"}"#);
+        let unsigned = handle_slack_events(State(state.clone()), HeaderMap::new(), body.clone())
+            .await
+            .into_response();
+        assert_eq!(unsigned.status(), StatusCode::UNAUTHORIZED);
+
+        let now = chrono::Utc::now().timestamp();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            webhook_security::SLACK_TIMESTAMP_HEADER,
+            HeaderValue::from(now),
+        );
+        headers.insert(
+            webhook_security::SLACK_SIGNATURE_HEADER,
+            HeaderValue::from_str(&webhook_security::TimestampedScheme::Slack.sign(
+                "slack-signing-secret",
+                now,
+                &body,
+            ))
+            .unwrap(),
+        );
+        let signed = handle_slack_events(State(state), headers, body)
+            .await
+            .into_response();
+        assert_eq!(signed.status(), StatusCode::OK);
+        let reply = signed.into_body().collect().await.unwrap().to_bytes();
+        let reply: serde_json::Value = serde_json::from_slice(&reply).unwrap();
+        assert_eq!(reply["challenge"], "c4ll3nge");
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+    }
+
     // ══════════════════════════════════════════════════════════
     // WhatsApp Signature Verification Tests (CWE-345 Prevention)
     // ══════════════════════════════════════════════════════════
@@ -0,0 +1,390 @@
+//! Per-group overrides, changed by the group's admins with `/group`.
+//!
+//! - `/group` — show this group's settings
+//! - `/group prefix <prefix>` — extra command prefix, e.g. `!` for `!remind`
+//! - `/group language <language>` — reply language in this group
+//! - `/group commands <command> ...` — only these commands work here
+//! - `/group persona <text>` — extra system prompt for the agent
+//! - `/group <name> default` — remove the override
+//!
+//! Overrides belong to one chat: a Telegram group, a QQ guild channel or
+//! group, keyed by channel and [`ChannelMessage::conversation`]. They are
+//! stored in the `groups` namespace of the [`crate::kv`] store. Anyone in the
+//! group can see them; changing them takes a group admin as reported by
+//! [`Channel::is_group_admin`], or an operator listed in `[groups] admins`.
+
+use crate::channels::traits::{Channel, ChannelMessage};
+use crate::config::GroupsConfig;
+use crate::kv::KvNamespace;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+const MAX_PREFIX_LEN: usize = 3;
+
+const USAGE: &str = "Usage: /group prefix|language|commands|persona <value>, \
+                     or /group <name> default";
+
+/// One group's overrides; unset fields leave the defaults alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupSettings {
+    /// Prefix accepted in place of `/`, e.g. "!"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Commands (without `/`) that may be used; `None` allows all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_commands: Option<Vec<String>>,
+    /// Extra system prompt for conversations in the group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persona: Option<String>,
+}
+
+impl GroupSettings {
+    /// `content` with the group's prefix turned into `/`, or `None` when it
+    /// isn't a command with that prefix.
+    pub fn normalize_command(&self, content: &str) -> Option<String> {
+        let prefix = self.prefix.as_deref().filter(|prefix| *prefix != "/")?;
+        let command = content.trim_start().strip_prefix(prefix)?;
+        command
+            .starts_with(|c: char| c.is_alphabetic())
+            .then(|| format!("/{command}"))
+    }
+
+    /// The name of the command in `content` when this group disabled it.
+    /// `/group` itself is always allowed.
+    pub fn blocked_command<'a>(&self, content: &'a str) -> Option<&'a str> {
+        let allowed = self.allowed_commands.as_ref()?;
+        let name = content
+            .trim_start()
+            .strip_prefix('/')?
+            .split_whitespace()
+            .next()?
+            .split('@')
+            .next()?;
+        let allowed = name.is_empty()
+            || name == "group"
+            || allowed
+                .iter()
+                .any(|command| command.eq_ignore_ascii_case(name));
+        (!allowed).then_some(name)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "👥 Group settings:\n\
+             • prefix: {}\n\
+             • language: {}\n\
+             • commands: {}\n\
+             • persona: {}\n\
+             Admins change them with /group <name> <value>, or /group <name> default.",
+            self.prefix.as_deref().unwrap_or("/"),
+            self.language.as_deref().unwrap_or("default"),
+            self.allowed_commands
+                .as_ref()
+                .map_or_else(|| "all".to_string(), |commands| commands.join(", ")),
+            self.persona.as_deref().unwrap_or("none"),
+        )
+    }
+}
+
+/// Stored group overrides.
+pub struct GroupStore {
+    kv: KvNamespace,
+    config: GroupsConfig,
+}
+
+impl GroupStore {
+    pub fn new(kv: KvNamespace, config: GroupsConfig) -> Self {
+        Self { kv, config }
+    }
+
+    fn key(channel: &str, conversation: &str) -> String {
+        format!("{channel}:{conversation}")
+    }
+
+    /// The overrides of the chat `conversation` on `channel`.
+    pub fn settings(&self, channel: &str, conversation: &str) -> Result<GroupSettings> {
+        match self.kv.get(&Self::key(channel, conversation))? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(GroupSettings::default()),
+        }
+    }
+
+    /// Overrides for `msg`'s chat; direct messages have none, and a store
+    /// failure falls back to none.
+    pub fn for_message(&self, channel: &dyn Channel, msg: &ChannelMessage) -> GroupSettings {
+        if is_direct(channel, msg) {
+            return GroupSettings::default();
+        }
+        self.settings(&msg.channel, msg.conversation())
+            .unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Failed to load group settings of {}: {e}",
+                    msg.conversation()
+                );
+                GroupSettings::default()
+            })
+    }
+
+    fn save(&self, channel: &str, conversation: &str, settings: &GroupSettings) -> Result<()> {
+        let key = Self::key(channel, conversation);
+        if *settings == GroupSettings::default() {
+            self.kv.delete(&key)?;
+        } else {
+            self.kv.set(&key, &serde_json::to_string(settings)?, None)?;
+        }
+        Ok(())
+    }
+
+    /// Change one override of a chat; `"default"` removes it.
+    pub fn set(&self, channel: &str, conversation: &str, name: &str, value: &str) -> Result<()> {
+        let mut settings = self.settings(channel, conversation)?;
+        self.apply(&mut settings, &name.to_ascii_lowercase(), value.trim())?;
+        self.save(channel, conversation, &settings)
+    }
+
+    fn apply(&self, settings: &mut GroupSettings, name: &str, value: &str) -> Result<()> {
+        let clear = value.eq_ignore_ascii_case("default");
+        match name {
+            "prefix" => {
+                settings.prefix = if clear {
+                    None
+                } else if value.is_empty()
+                    || value.chars().count() > MAX_PREFIX_LEN
+                    || value
+                        .chars()
+                        .any(|c| c.is_alphanumeric() || c.is_whitespace())
+                {
+                    bail!("Prefixes are 1 to {MAX_PREFIX_LEN} symbols, like ! or .");
+                } else {
+                    Some(value.to_string())
+                };
+            }
+            "language" | "lang" => {
+                settings.language = if clear {
+                    None
+                } else {
+                    Some(crate::preferences::parse_language(value)?)
+                };
+            }
+            "commands" => {
+                settings.allowed_commands = if clear || value.eq_ignore_ascii_case("all") {
+                    None
+                } else {
+                    let commands = value
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|command| !command.is_empty())
+                        .map(|command| command.trim_start_matches('/').to_ascii_lowercase())
+                        .collect::<Vec<_>>();
+                    if commands.iter().any(|command| {
+                        command.is_empty()
+                            || !command
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    }) {
+                        bail!("List commands by name, like: /group commands remind set");
+                    }
+                    Some(commands)
+                };
+            }
+            "persona" | "prompt" => {
+                settings.persona = if clear {
+                    None
+                } else if value.chars().count() > self.config.max_persona_chars {
+                    bail!(
+                        "Personas are limited to {} characters",
+                        self.config.max_persona_chars
+                    );
+                } else {
+                    Some(value.to_string())
+                };
+            }
+            _ => bail!("Unknown group setting {name:?}; {USAGE}"),
+        }
+        Ok(())
+    }
+
+    /// Whether `msg`'s author may change the group's overrides.
+    async fn may_edit(&self, channel: &dyn Channel, msg: &ChannelMessage) -> bool {
+        let operator = format!("{}:{}", msg.channel, msg.author_id());
+        if self.config.admins.contains(&operator) {
+            return true;
+        }
+        channel.is_group_admin(msg).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to check group admin {operator}: {e}");
+            false
+        })
+    }
+
+    /// Answer `/group`. Returns `None` for any other message.
+    pub async fn handle_command(
+        &self,
+        channel: &dyn Channel,
+        msg: &ChannelMessage,
+    ) -> Option<String> {
+        let mut words = msg.content.split_whitespace();
+        let command = words.next()?.split('@').next()?;
+        if command != "/group" {
+            return None;
+        }
+        if is_direct(channel, msg) {
+            return Some("Group settings only apply in groups.".to_string());
+        }
+        let conversation = msg.conversation();
+        let name = words.next();
+        let value = words.collect::<Vec<_>>().join(" ");
+        let reply = match name {
+            None => match self.settings(&msg.channel, conversation) {
+                Ok(settings) => settings.describe(),
+                Err(e) => format!("⚠️ {e}"),
+            },
+            Some(_) if value.is_empty() => USAGE.to_string(),
+            Some(name) => {
+                if !self.may_edit(channel, msg).await {
+                    return Some("⚠️ Only group admins can change group settings".to_string());
+                }
+                match self
+                    .set(&msg.channel, conversation, name, &value)
+                    .and_then(|()| self.settings(&msg.channel, conversation))
+                {
+                    Ok(settings) => format!("✅ Saved.\n{}", settings.describe()),
+                    Err(e) => format!("⚠️ {e}"),
+                }
+            }
+        };
+        Some(reply)
+    }
+}
+
+fn is_direct(channel: &dyn Channel, msg: &ChannelMessage) -> bool {
+    channel.conversation_key(msg).starts_with("dm:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv::KvStore;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    /// Reports `admin` as the only group admin.
+    struct AdminChannel;
+
+    #[async_trait]
+    impl Channel for AdminChannel {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        async fn send(&self, _message: &str, _recipient: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn is_group_admin(&self, msg: &ChannelMessage) -> anyhow::Result<bool> {
+            Ok(msg.sender == "admin")
+        }
+    }
+
+    fn store() -> GroupStore {
+        let kv = Arc::new(KvStore::in_memory().unwrap());
+        GroupStore::new(
+            kv.namespace("groups"),
+            GroupsConfig {
+                admins: vec!["test:operator".into()],
+                ..GroupsConfig::default()
+            },
+        )
+    }
+
+    fn message(sender: &str, chat: &str, content: &str) -> ChannelMessage {
+        ChannelMessage {
+            reply_target: chat.into(),
+            ..ChannelMessage::test("test", sender, content)
+        }
+    }
+
+    #[tokio::test]
+    async fn only_admins_and_operators_change_settings() {
+        let store = store();
+        let reply = store
+            .handle_command(&AdminChannel, &message("bob", "g1", "/group prefix !"))
+            .await
+            .unwrap();
+        assert!(reply.contains("Only group admins"), "{reply}");
+
+        let reply = store
+            .handle_command(&AdminChannel, &message("admin", "g1", "/group prefix !"))
+            .await
+            .unwrap();
+        assert!(reply.contains("prefix: !"), "{reply}");
+        let reply = store
+            .handle_command(
+                &AdminChannel,
+                &message(
+                    "operator",
+                    "g1",
+                    "/group@zeroclaw_bot persona Talk like a pirate",
+                ),
+            )
+            .await
+            .unwrap();
+        assert!(reply.contains("persona: Talk like a pirate"), "{reply}");
+        assert!(store
+            .handle_command(&AdminChannel, &message("admin", "g1", "/group prefix abc"))
+            .await
+            .unwrap()
+            .starts_with("⚠️"));
+
+        // Other groups and direct messages are unaffected
+        assert_eq!(
+            store.settings("test", "g2").unwrap(),
+            GroupSettings::default()
+        );
+        assert_eq!(
+            store
+                .handle_command(&AdminChannel, &message("admin", "admin", "/group"))
+                .await
+                .unwrap(),
+            "Group settings only apply in groups."
+        );
+        assert!(store
+            .handle_command(&AdminChannel, &message("bob", "g1", "/groups"))
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn prefix_and_allowed_commands_apply_to_messages() {
+        let store = store();
+        store.set("test", "g1", "prefix", "!").unwrap();
+        store.set("test", "g1", "commands", "/remind, set").unwrap();
+        let settings = store.for_message(&AdminChannel, &message("bob", "g1", "hi"));
+
+        assert_eq!(
+            settings
+                .normalize_command("!remind in 5m to stretch")
+                .as_deref(),
+            Some("/remind in 5m to stretch")
+        );
+        assert_eq!(settings.normalize_command("! not a command"), None);
+        assert_eq!(settings.blocked_command("/remind list"), None);
+        assert_eq!(settings.blocked_command("/new"), Some("new"));
+        assert_eq!(settings.blocked_command("/group"), None);
+        assert_eq!(settings.blocked_command("hello"), None);
+
+        store.set("test", "g1", "commands", "all").unwrap();
+        store.set("test", "g1", "prefix", "default").unwrap();
+        assert_eq!(
+            store.settings("test", "g1").unwrap(),
+            GroupSettings::default()
+        );
+    }
+}
@@ -69,13 +69,23 @@ pub fn mark_component_ok(component: &str) {
 
 #[allow(clippy::needless_pass_by_value)]
 pub fn mark_component_error(component: &str, error: impl ToString) {
-    let err = error.to_string();
+    let err = crate::security::redact::redact(&error.to_string());
     upsert_component(component, move |entry| {
         entry.status = "error".into();
         entry.last_error = Some(err);
     });
 }
 
+/// The component stopped for good and will not be restarted.
+#[allow(clippy::needless_pass_by_value)]
+pub fn mark_component_failed(component: &str, error: impl ToString) {
+    let err = crate::security::redact::redact(&error.to_string());
+    upsert_component(component, move |entry| {
+        entry.status = "failed".into();
+        entry.last_error = Some(err);
+    });
+}
+
 pub fn bump_component_restart(component: &str) {
     upsert_component(component, |entry| {
         entry.restart_count = entry.restart_count.saturating_add(1);
@@ -0,0 +1,359 @@
+//! Cross-channel user identity linking.
+//!
+//! Every channel account (`channel` + sender id) belongs to exactly one
+//! profile, created on first sight. A user links a second account to their
+//! profile with a one-time code:
+//!
+//! 1. `/link` on account A replies with a short code
+//! 2. `/link <code>` on account B, before the code expires, moves B onto A's
+//!    profile
+//!
+//! `/unlink` detaches the current account onto a fresh profile and `/whoami`
+//! lists the accounts linked to the current one. Conversation memory, ACL
+//! checks and quotas key off the profile id, so they follow the person across
+//! platforms.
+//!
+//! Profiles live in `<workspace>/users/identities.db`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Characters used in link codes; no 0/O or 1/I/L to keep codes easy to type.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 8;
+
+/// One channel account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedAccount {
+    pub channel: String,
+    pub sender: String,
+}
+
+pub struct IdentityStore {
+    conn: Mutex<Connection>,
+    code_ttl: Duration,
+}
+
+impl IdentityStore {
+    /// Open (or create) the store under `<workspace>/users/`.
+    pub fn open(workspace_dir: &Path, code_ttl_secs: u64) -> Result<Self> {
+        let dir = workspace_dir.join("users");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create users directory: {}", dir.display()))?;
+        let db_path = dir.join("identities.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open identity DB: {}", db_path.display()))?;
+        Self::with_connection(conn, code_ttl_secs)
+    }
+
+    /// In-memory store for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory(code_ttl_secs: u64) -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, code_ttl_secs)
+    }
+
+    fn with_connection(conn: Connection, code_ttl_secs: u64) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS accounts (
+                channel    TEXT NOT NULL,
+                sender     TEXT NOT NULL,
+                profile_id TEXT NOT NULL,
+                linked_at  TEXT NOT NULL,
+                PRIMARY KEY (channel, sender)
+            );
+            CREATE INDEX IF NOT EXISTS idx_accounts_profile ON accounts(profile_id);
+
+            CREATE TABLE IF NOT EXISTS link_codes (
+                code       TEXT PRIMARY KEY,
+                profile_id TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );",
+        )
+        .context("Failed to initialize identity schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            code_ttl: Duration::seconds(i64::try_from(code_ttl_secs).unwrap_or(i64::MAX)),
+        })
+    }
+
+    /// Profile id for an account, creating a profile on first sight.
+    pub fn profile_for(&self, channel: &str, sender: &str) -> Result<String> {
+        let conn = self.conn.lock();
+        profile_for(&conn, channel, sender)
+    }
+
+    /// Profile id of an account already seen, without creating one.
+    pub fn find_profile(&self, channel: &str, sender: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        find_profile(&conn, channel, sender)
+    }
+
+    /// All accounts on the same profile as this one (including itself).
+    pub fn linked_accounts(&self, channel: &str, sender: &str) -> Result<Vec<LinkedAccount>> {
+        let conn = self.conn.lock();
+        let profile = profile_for(&conn, channel, sender)?;
+        let mut stmt = conn.prepare(
+            "SELECT channel, sender FROM accounts WHERE profile_id = ?1 ORDER BY channel, sender",
+        )?;
+        let rows = stmt.query_map(params![profile], |row| {
+            Ok(LinkedAccount {
+                channel: row.get(0)?,
+                sender: row.get(1)?,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    /// Issue a one-time code that links another account to this one's profile.
+    pub fn issue_code(&self, channel: &str, sender: &str) -> Result<String> {
+        let conn = self.conn.lock();
+        let profile = profile_for(&conn, channel, sender)?;
+        let now = Utc::now();
+        conn.execute(
+            "DELETE FROM link_codes WHERE expires_at <= ?1 OR profile_id = ?2",
+            params![now.to_rfc3339(), profile],
+        )?;
+        let code = generate_code();
+        conn.execute(
+            "INSERT INTO link_codes (code, profile_id, expires_at) VALUES (?1, ?2, ?3)",
+            params![code, profile, (now + self.code_ttl).to_rfc3339()],
+        )?;
+        Ok(code)
+    }
+
+    /// Redeem a code from another account, moving this account onto the
+    /// code's profile. Returns the profile id.
+    pub fn redeem_code(&self, code: &str, channel: &str, sender: &str) -> Result<String> {
+        let code = code.trim().to_ascii_uppercase();
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let row: Option<(String, String)> = tx
+            .query_row(
+                "SELECT profile_id, expires_at FROM link_codes WHERE code = ?1",
+                params![code],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((profile, expires_at)) = row else {
+            anyhow::bail!("unknown or already used link code");
+        };
+        tx.execute("DELETE FROM link_codes WHERE code = ?1", params![code])?;
+        let expires_at = DateTime::parse_from_rfc3339(&expires_at)?.with_timezone(&Utc);
+        if expires_at <= Utc::now() {
+            tx.commit()?;
+            anyhow::bail!("link code expired");
+        }
+        tx.execute(
+            "INSERT INTO accounts (channel, sender, profile_id, linked_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (channel, sender) DO UPDATE SET profile_id = excluded.profile_id,
+                                                          linked_at = excluded.linked_at",
+            params![channel, sender, profile, Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(profile)
+    }
+
+    /// Detach an account onto a fresh profile of its own.
+    pub fn unlink(&self, channel: &str, sender: &str) -> Result<String> {
+        let conn = self.conn.lock();
+        let profile = new_profile_id();
+        conn.execute(
+            "INSERT INTO accounts (channel, sender, profile_id, linked_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (channel, sender) DO UPDATE SET profile_id = excluded.profile_id,
+                                                          linked_at = excluded.linked_at",
+            params![channel, sender, profile, Utc::now().to_rfc3339()],
+        )?;
+        Ok(profile)
+    }
+
+    /// Delete the profile of an account, with every account linked to it and
+    /// its pending link codes. Returns the profile id and the accounts it
+    /// had, or `None` when the account was never seen.
+    pub fn forget(
+        &self,
+        channel: &str,
+        sender: &str,
+    ) -> Result<Option<(String, Vec<LinkedAccount>)>> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let profile: Option<String> = tx
+            .query_row(
+                "SELECT profile_id FROM accounts WHERE channel = ?1 AND sender = ?2",
+                params![channel, sender],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(profile) = profile else {
+            return Ok(None);
+        };
+        let accounts = {
+            let mut stmt = tx.prepare(
+                "SELECT channel, sender FROM accounts WHERE profile_id = ?1 ORDER BY channel, sender",
+            )?;
+            let rows = stmt.query_map(params![profile], |row| {
+                Ok(LinkedAccount {
+                    channel: row.get(0)?,
+                    sender: row.get(1)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        tx.execute(
+            "DELETE FROM accounts WHERE profile_id = ?1",
+            params![profile],
+        )?;
+        tx.execute(
+            "DELETE FROM link_codes WHERE profile_id = ?1",
+            params![profile],
+        )?;
+        tx.commit()?;
+        Ok(Some((profile, accounts)))
+    }
+
+    /// Answer `/link`, `/link <code>`, `/unlink` and `/whoami`. Returns `None`
+    /// for any other message.
+    pub fn handle_command(&self, channel: &str, sender: &str, content: &str) -> Option<String> {
+        let mut parts = content.split_whitespace();
+        let command = parts.next()?.split('@').next()?;
+        let arg = parts.next();
+        let reply = match (command, arg) {
+            ("/link", None) => self.issue_code(channel, sender).map(|code| {
+                format!(
+                    "🔗 Send `/link {code}` from your other account within {} minutes to link it to this one.",
+                    self.code_ttl.num_minutes().max(1)
+                )
+            }),
+            ("/link", Some(code)) => self
+                .redeem_code(code, channel, sender)
+                .map(|_| "✅ Accounts linked.".to_string()),
+            ("/unlink", _) => self
+                .unlink(channel, sender)
+                .map(|_| "This account is no longer linked to any other.".to_string()),
+            ("/whoami", _) => self.linked_accounts(channel, sender).map(|accounts| {
+                let list: Vec<_> = accounts
+                    .iter()
+                    .map(|a| format!("- {}: {}", a.channel, a.sender))
+                    .collect();
+                format!("Linked accounts:\n{}", list.join("\n"))
+            }),
+            _ => return None,
+        };
+        Some(reply.unwrap_or_else(|e| format!("⚠️ {e}")))
+    }
+}
+
+fn new_profile_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn generate_code() -> String {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| char::from(CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())]))
+        .collect()
+}
+
+fn find_profile(conn: &Connection, channel: &str, sender: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT profile_id FROM accounts WHERE channel = ?1 AND sender = ?2",
+        params![channel, sender],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+fn profile_for(conn: &Connection, channel: &str, sender: &str) -> Result<String> {
+    if let Some(profile) = find_profile(conn, channel, sender)? {
+        return Ok(profile);
+    }
+    let profile = new_profile_id();
+    conn.execute(
+        "INSERT INTO accounts (channel, sender, profile_id, linked_at) VALUES (?1, ?2, ?3, ?4)",
+        params![channel, sender, profile, Utc::now().to_rfc3339()],
+    )?;
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(ttl_secs: u64) -> IdentityStore {
+        IdentityStore::in_memory(ttl_secs).unwrap()
+    }
+
+    #[test]
+    fn code_flow_links_accounts_to_one_profile() {
+        let store = store(600);
+        let qq = store.profile_for("qq", "10001").unwrap();
+        let discord = store.profile_for("discord", "alice#1").unwrap();
+        assert_ne!(qq, discord);
+
+        let code = store.issue_code("qq", "10001").unwrap();
+        assert_eq!(code.len(), CODE_LENGTH);
+        let linked = store
+            .redeem_code(&code.to_lowercase(), "discord", "alice#1")
+            .unwrap();
+        assert_eq!(linked, qq);
+        assert_eq!(store.profile_for("discord", "alice#1").unwrap(), qq);
+        assert_eq!(
+            store.linked_accounts("discord", "alice#1").unwrap().len(),
+            2
+        );
+
+        // Codes are single-use.
+        assert!(store.redeem_code(&code, "telegram", "42").is_err());
+    }
+
+    #[test]
+    fn expired_codes_are_rejected() {
+        let store = store(0);
+        let code = store.issue_code("qq", "10001").unwrap();
+        let err = store.redeem_code(&code, "discord", "alice#1").unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
+    #[test]
+    fn unlink_detaches_only_the_current_account() {
+        let store = store(600);
+        let code = store.issue_code("qq", "10001").unwrap();
+        store.redeem_code(&code, "discord", "alice#1").unwrap();
+        let qq = store.profile_for("qq", "10001").unwrap();
+
+        let fresh = store.unlink("discord", "alice#1").unwrap();
+        assert_ne!(fresh, qq);
+        assert_eq!(store.profile_for("qq", "10001").unwrap(), qq);
+        assert_eq!(store.linked_accounts("qq", "10001").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn commands_drive_the_flow() {
+        let store = store(600);
+        let reply = store.handle_command("qq", "10001", "/link").unwrap();
+        let code = reply
+            .split('`')
+            .nth(1)
+            .and_then(|cmd| cmd.strip_prefix("/link "))
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            store
+                .handle_command("telegram", "42", &format!("/link {code}"))
+                .unwrap(),
+            "✅ Accounts linked."
+        );
+        let whoami = store.handle_command("telegram", "42", "/whoami").unwrap();
+        assert!(whoami.contains("qq: 10001") && whoami.contains("telegram: 42"));
+        assert!(store.handle_command("qq", "10001", "hello").is_none());
+        assert!(store
+            .handle_command("qq", "10001", "/link BADCODE")
+            .unwrap()
+            .starts_with("⚠️"));
+    }
+}
@@ -3,6 +3,11 @@
 //! AIEOS (AI Entity Object Specification) is a standardization framework for
 //! portable AI identity. This module handles loading and converting AIEOS v1.1
 //! JSON to ZeroClaw's system prompt format.
+//!
+//! User-side identity (linking one person's accounts across channels) lives
+//! in [`linking`].
+
+pub mod linking;
 
 use crate::config::IdentityConfig;
 use anyhow::{Context, Result};
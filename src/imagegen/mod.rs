@@ -0,0 +1,139 @@
+mod openai;
+mod stability;
+mod stable_diffusion;
+
+pub use openai::OpenAiImageGenerator;
+pub use stability::StabilityImageGenerator;
+pub use stable_diffusion::StableDiffusionGenerator;
+
+use crate::config::schema::ImageGenerationConfig;
+use crate::security::ManagedSecret;
+use anyhow::{bail, Result};
+use std::sync::Arc;
+
+const IMAGE_GENERATION_TIMEOUT_SECS: u64 = 180;
+
+// ── Generator trait ──────────────────────────────────────────────
+
+/// An encoded image produced by a generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
+impl GeneratedImage {
+    pub fn png(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            mime_type: "image/png".into(),
+        }
+    }
+
+    /// File name with an extension matching the content type.
+    pub fn file_name(&self, stem: &str) -> String {
+        let ext = match self.mime_type.as_str() {
+            "image/jpeg" => "jpg",
+            "image/webp" => "webp",
+            _ => "png",
+        };
+        format!("{stem}.{ext}")
+    }
+}
+
+/// Text-to-image backend for the `image_generate` tool and `/imagine`.
+#[async_trait::async_trait]
+pub trait ImageGenerator: Send + Sync {
+    /// Backend name (e.g. "openai", "stability", "stable_diffusion")
+    fn name(&self) -> &str;
+
+    async fn generate(&self, prompt: &str) -> Result<GeneratedImage>;
+}
+
+/// Parse a `WIDTHxHEIGHT` size.
+fn parse_size(size: &str) -> Result<(u32, u32)> {
+    let parsed = size
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+    match parsed {
+        Some((w, h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => bail!("Invalid image size \"{size}\" (expected WIDTHxHEIGHT)"),
+    }
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    use base64::Engine;
+    Ok(base64::engine::general_purpose::STANDARD.decode(data.trim())?)
+}
+
+// ── Factory ──────────────────────────────────────────────────────
+
+/// Create an image generator from config. Returns `None` when image
+/// generation is disabled. `api_key` is the resolved `[image_generation] api_key`.
+pub fn create_image_generator(
+    config: &ImageGenerationConfig,
+    api_key: Option<Arc<ManagedSecret>>,
+) -> Result<Option<Arc<dyn ImageGenerator>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let url = |default: &str| config.api_url.clone().unwrap_or_else(|| default.into());
+    let model = |default: &str| config.model.clone().unwrap_or_else(|| default.into());
+    let generator: Arc<dyn ImageGenerator> = match config.backend.as_str() {
+        "openai" => Arc::new(OpenAiImageGenerator::new(
+            url("https://api.openai.com/v1/images/generations"),
+            model("dall-e-3"),
+            config.size.clone(),
+            api_key,
+        )),
+        "stability" => Arc::new(StabilityImageGenerator::new(
+            url("https://api.stability.ai/v2beta/stable-image/generate"),
+            model("core"),
+            parse_size(&config.size)?,
+            api_key,
+        )),
+        "stable_diffusion" => Arc::new(StableDiffusionGenerator::new(
+            url("http://127.0.0.1:7860"),
+            config.model.clone(),
+            parse_size(&config.size)?,
+            api_key,
+        )),
+        other => bail!("Unknown image generation backend: {other}"),
+    };
+    Ok(Some(generator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_respects_enabled_and_backend() {
+        let mut config = ImageGenerationConfig::default();
+        assert!(create_image_generator(&config, None).unwrap().is_none());
+
+        config.enabled = true;
+        for backend in ["openai", "stability", "stable_diffusion"] {
+            config.backend = backend.into();
+            let generator = create_image_generator(&config, None).unwrap().unwrap();
+            assert_eq!(generator.name(), backend);
+        }
+
+        config.size = "big".into();
+        assert!(create_image_generator(&config, None).is_err());
+        config.backend = "nope".into();
+        assert!(create_image_generator(&config, None).is_err());
+    }
+
+    #[test]
+    fn sizes_and_file_names() {
+        assert_eq!(parse_size("1024x768").unwrap(), (1024, 768));
+        assert!(parse_size("0x10").is_err());
+        let image = GeneratedImage {
+            bytes: vec![],
+            mime_type: "image/jpeg".into(),
+        };
+        assert_eq!(image.file_name("cat"), "cat.jpg");
+        assert_eq!(GeneratedImage::png(vec![]).file_name("cat"), "cat.png");
+    }
+}
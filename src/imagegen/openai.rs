@@ -0,0 +1,102 @@
+use super::{decode_base64, GeneratedImage, ImageGenerator, IMAGE_GENERATION_TIMEOUT_SECS};
+use crate::security::ManagedSecret;
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// OpenAI Images API (DALL·E, gpt-image-1) or a compatible endpoint.
+pub struct OpenAiImageGenerator {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    size: String,
+    api_key: Option<Arc<ManagedSecret>>,
+}
+
+impl OpenAiImageGenerator {
+    pub fn new(
+        url: String,
+        model: String,
+        size: String,
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            model,
+            size,
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ImageGenerator for OpenAiImageGenerator {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<GeneratedImage> {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "prompt": prompt,
+            "size": self.size,
+            "n": 1,
+        });
+        // gpt-image models always return base64 and reject the parameter.
+        if self.model.starts_with("dall-e") {
+            body["response_format"] = "b64_json".into();
+        }
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(Duration::from_secs(IMAGE_GENERATION_TIMEOUT_SECS))
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(crate::providers::api_error("OpenAI Images", response).await);
+        }
+        let body: serde_json::Value = response.json().await?;
+        let data = body
+            .pointer("/data/0/b64_json")
+            .and_then(serde_json::Value::as_str)
+            .context("OpenAI Images response has no image")?;
+        Ok(GeneratedImage::png(decode_base64(data)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn decodes_base64_image() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/v1/images/generations",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                let ok = body["prompt"] == "a red fox" && body["response_format"] == "b64_json";
+                Json(serde_json::json!({
+                    "data": [{ "b64_json": if ok { "iVBORw==" } else { "" } }]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let generator = OpenAiImageGenerator::new(
+            format!("http://{addr}/v1/images/generations"),
+            "dall-e-3".into(),
+            "1024x1024".into(),
+            None,
+        );
+        let image = generator.generate("a red fox").await.unwrap();
+        assert_eq!(image.bytes, b"\x89PNG");
+        assert_eq!(image.mime_type, "image/png");
+    }
+}
@@ -0,0 +1,93 @@
+use super::{GeneratedImage, ImageGenerator, IMAGE_GENERATION_TIMEOUT_SECS};
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use reqwest::multipart::Form;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Stability AI hosted Stable Diffusion (`/v2beta/stable-image/generate/{model}`).
+pub struct StabilityImageGenerator {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    aspect_ratio: &'static str,
+    api_key: Option<Arc<ManagedSecret>>,
+}
+
+/// Closest aspect ratio the API accepts; it does not take pixel sizes.
+fn aspect_ratio((width, height): (u32, u32)) -> &'static str {
+    const RATIOS: [(&str, f64); 9] = [
+        ("21:9", 21.0 / 9.0),
+        ("16:9", 16.0 / 9.0),
+        ("3:2", 1.5),
+        ("5:4", 1.25),
+        ("1:1", 1.0),
+        ("4:5", 0.8),
+        ("2:3", 2.0 / 3.0),
+        ("9:16", 9.0 / 16.0),
+        ("9:21", 9.0 / 21.0),
+    ];
+    let target = f64::from(width) / f64::from(height);
+    RATIOS
+        .iter()
+        .min_by(|a, b| (a.1 - target).abs().total_cmp(&(b.1 - target).abs()))
+        .map_or("1:1", |(name, _)| name)
+}
+
+impl StabilityImageGenerator {
+    pub fn new(
+        base_url: String,
+        model: String,
+        size: (u32, u32),
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model,
+            aspect_ratio: aspect_ratio(size),
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ImageGenerator for StabilityImageGenerator {
+    fn name(&self) -> &str {
+        "stability"
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<GeneratedImage> {
+        let form = Form::new()
+            .text("prompt", prompt.to_string())
+            .text("aspect_ratio", self.aspect_ratio)
+            .text("output_format", "png");
+        let mut request = self
+            .client
+            .post(format!("{}/{}", self.base_url, self.model))
+            .timeout(Duration::from_secs(IMAGE_GENERATION_TIMEOUT_SECS))
+            .header("Accept", "image/*")
+            .multipart(form);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(crate::providers::api_error("Stability", response).await);
+        }
+        Ok(GeneratedImage::png(response.bytes().await?.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_map_to_nearest_aspect_ratio() {
+        assert_eq!(aspect_ratio((1024, 1024)), "1:1");
+        assert_eq!(aspect_ratio((1792, 1024)), "16:9");
+        assert_eq!(aspect_ratio((1024, 1792)), "9:16");
+        assert_eq!(aspect_ratio((1200, 800)), "3:2");
+    }
+}
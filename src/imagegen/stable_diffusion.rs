@@ -0,0 +1,69 @@
+use super::{decode_base64, GeneratedImage, ImageGenerator, IMAGE_GENERATION_TIMEOUT_SECS};
+use crate::security::ManagedSecret;
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Self-hosted Stable Diffusion through the AUTOMATIC1111 web UI API
+/// (`/sdapi/v1/txt2img`), also served by Forge and SD.Next.
+pub struct StableDiffusionGenerator {
+    client: reqwest::Client,
+    base_url: String,
+    checkpoint: Option<String>,
+    size: (u32, u32),
+    api_key: Option<Arc<ManagedSecret>>,
+}
+
+impl StableDiffusionGenerator {
+    pub fn new(
+        base_url: String,
+        checkpoint: Option<String>,
+        size: (u32, u32),
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            checkpoint,
+            size,
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ImageGenerator for StableDiffusionGenerator {
+    fn name(&self) -> &str {
+        "stable_diffusion"
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<GeneratedImage> {
+        let mut body = serde_json::json!({
+            "prompt": prompt,
+            "width": self.size.0,
+            "height": self.size.1,
+            "steps": 25,
+        });
+        if let Some(checkpoint) = &self.checkpoint {
+            body["override_settings"] = serde_json::json!({ "sd_model_checkpoint": checkpoint });
+        }
+        let mut request = self
+            .client
+            .post(format!("{}/sdapi/v1/txt2img", self.base_url))
+            .timeout(Duration::from_secs(IMAGE_GENERATION_TIMEOUT_SECS))
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(crate::providers::api_error("Stable Diffusion", response).await);
+        }
+        let body: serde_json::Value = response.json().await?;
+        let data = body
+            .pointer("/images/0")
+            .and_then(serde_json::Value::as_str)
+            .context("Stable Diffusion response has no image")?;
+        Ok(GeneratedImage::png(decode_base64(data)?))
+    }
+}
@@ -0,0 +1,555 @@
+//! Prometheus Alertmanager webhooks and the `/silence` bot commands.
+//!
+//! Silences are kept in-process and checked before alerts are posted. When
+//! `api_url` is configured, `/silence` also creates a matching silence in
+//! Alertmanager so other receivers stay quiet too.
+
+use super::{error_response, filter_matches, IngressState};
+use crate::channels::control::ChannelControl;
+use crate::channels::priority::Priority;
+use crate::config::schema::{AlertmanagerConfig, IngressTargetConfig};
+use crate::security::pairing::constant_time_eq;
+use crate::security::ManagedSecret;
+use anyhow::{bail, Context, Result};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+/// Body of an Alertmanager webhook (version 4).
+#[derive(Debug, Deserialize)]
+pub struct AlertmanagerPayload {
+    #[serde(default)]
+    pub alerts: Vec<Alert>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Alert {
+    /// "firing" or "resolved"
+    pub status: String,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+impl Alert {
+    pub fn name(&self) -> &str {
+        self.labels
+            .get("alertname")
+            .map_or("unknown", String::as_str)
+    }
+
+    pub fn severity(&self) -> &str {
+        self.labels.get("severity").map_or("none", String::as_str)
+    }
+
+    fn is_firing(&self) -> bool {
+        self.status == "firing"
+    }
+
+    fn line(&self) -> String {
+        let mut line = format!("• [{}] {}", self.severity(), self.name());
+        if let Some(instance) = self.labels.get("instance") {
+            let _ = write!(line, " ({instance})");
+        }
+        let summary = self
+            .annotations
+            .get("summary")
+            .or_else(|| self.annotations.get("description"));
+        if let Some(summary) = summary {
+            let _ = write!(line, ": {summary}");
+        }
+        line
+    }
+}
+
+/// Format a batch of alerts, firing before resolved.
+pub fn render_alerts(alerts: &[&Alert]) -> String {
+    let (firing, resolved): (Vec<&Alert>, Vec<&Alert>) =
+        alerts.iter().copied().partition(|alert| alert.is_firing());
+    let mut message = String::new();
+    if !firing.is_empty() {
+        let _ = writeln!(message, "🔥 FIRING ({})", firing.len());
+        for alert in &firing {
+            let _ = writeln!(message, "{}", alert.line());
+        }
+    }
+    if !resolved.is_empty() {
+        let _ = writeln!(message, "✅ RESOLVED ({})", resolved.len());
+        for alert in &resolved {
+            let _ = writeln!(message, "{}", alert.line());
+        }
+    }
+    if !firing.is_empty() {
+        message.push_str("Mute with /silence <alertname> <duration>");
+    }
+    message.trim_end().to_string()
+}
+
+/// Firing critical alerts are urgent: quiet hours and digests don't hold
+/// them.
+fn alerts_priority(alerts: &[&Alert]) -> Priority {
+    if alerts
+        .iter()
+        .any(|alert| alert.is_firing() && alert.severity() == "critical")
+    {
+        Priority::Urgent
+    } else {
+        Priority::Normal
+    }
+}
+
+struct Silence {
+    until: DateTime<Utc>,
+    by: String,
+    upstream_id: Option<String>,
+}
+
+/// Active silences by alert name, shared by the receiver and the bot commands.
+static SILENCES: LazyLock<Mutex<HashMap<String, Silence>>> = LazyLock::new(Default::default);
+
+/// Whether alerts named `alertname` are currently silenced.
+pub fn is_silenced(alertname: &str) -> bool {
+    let now = Utc::now();
+    let mut silences = SILENCES.lock();
+    silences.retain(|_, silence| silence.until > now);
+    silences.contains_key(alertname)
+}
+
+/// Parse durations like `90s`, `30m`, `2h`, `1d` or `1h30m`.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in input.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => return None,
+        };
+        let value: u64 = number.parse().ok()?;
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        number.clear();
+    }
+    (number.is_empty() && total > 0).then(|| Duration::from_secs(total))
+}
+
+/// Verifies, filters and routes Alertmanager webhooks.
+pub struct AlertmanagerReceiver {
+    token: Arc<ManagedSecret>,
+    targets: Vec<IngressTargetConfig>,
+    send_resolved: bool,
+}
+
+impl AlertmanagerReceiver {
+    pub fn new(
+        token: Arc<ManagedSecret>,
+        targets: Vec<IngressTargetConfig>,
+        send_resolved: bool,
+    ) -> Self {
+        Self {
+            token,
+            targets,
+            send_resolved,
+        }
+    }
+
+    pub fn from_config(
+        config: &AlertmanagerConfig,
+        resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            resolve(&config.token)?,
+            config.targets.clone(),
+            config.send_resolved,
+        ))
+    }
+
+    /// Post each target the alerts matching its severity (`events`) and
+    /// alert name (`sources`) filters. Returns how many targets were sent to.
+    async fn deliver(&self, control: &ChannelControl, alerts: &[Alert]) -> usize {
+        let mut delivered = 0;
+        for target in &self.targets {
+            let matching: Vec<&Alert> = alerts
+                .iter()
+                .filter(|alert| self.send_resolved || alert.is_firing())
+                .filter(|alert| !is_silenced(alert.name()))
+                .filter(|alert| {
+                    filter_matches(&target.events, alert.severity())
+                        && filter_matches(&target.sources, alert.name())
+                })
+                .collect();
+            if matching.is_empty() {
+                continue;
+            }
+            match control
+                .send_or_digest(
+                    &target.channel,
+                    &target.recipient,
+                    "Alertmanager",
+                    target.digest.as_ref(),
+                    &render_alerts(&matching),
+                    alerts_priority(&matching),
+                )
+                .await
+            {
+                Ok(()) => delivered += 1,
+                Err(e) => tracing::warn!(
+                    "Ingress: failed to deliver alerts to {}: {e}",
+                    target.channel
+                ),
+            }
+        }
+        delivered
+    }
+}
+
+/// POST /ingress/alertmanager
+pub(super) async fn handle_alertmanager(
+    State(state): State<IngressState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(receiver) = state.alertmanager.clone() else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            "Alertmanager webhooks are not configured",
+        );
+    };
+    let expected = match receiver.token.get().await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Ingress: failed to resolve Alertmanager token: {e}");
+            return error_response(StatusCode::SERVICE_UNAVAILABLE, "Webhook token unavailable");
+        }
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if provided.is_empty() || !constant_time_eq(provided, &expected) {
+        tracing::warn!("Ingress: rejected Alertmanager webhook with invalid token");
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid token");
+    }
+    let Ok(payload) = serde_json::from_slice::<AlertmanagerPayload>(&body) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid Alertmanager payload");
+    };
+    let delivered = receiver.deliver(&state.control, &payload.alerts).await;
+    Json(serde_json::json!({ "status": "delivered", "targets": delivered })).into_response()
+}
+
+/// Handles `/silence` and `/unsilence` in the chats alerts are posted to.
+pub struct AlertSilencer {
+    client: reqwest::Client,
+    api_url: Option<String>,
+    targets: Vec<IngressTargetConfig>,
+}
+
+impl AlertSilencer {
+    pub fn from_config(config: &AlertmanagerConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url: config
+                .api_url
+                .as_deref()
+                .map(|url| url.trim_end_matches('/').to_string()),
+            targets: config.targets.clone(),
+        }
+    }
+
+    /// Answer a silence command, or `None` when `content` isn't one or the
+    /// chat doesn't receive alerts.
+    pub async fn handle_command(
+        &self,
+        channel: &str,
+        reply_target: &str,
+        sender: &str,
+        content: &str,
+    ) -> Option<String> {
+        let mut parts = content.split_whitespace();
+        let command = parts.next()?.split('@').next()?;
+        if !matches!(command, "/silence" | "/unsilence") {
+            return None;
+        }
+        if !self
+            .targets
+            .iter()
+            .any(|t| t.channel == channel && t.recipient == reply_target)
+        {
+            return None;
+        }
+        let args: Vec<&str> = parts.collect();
+        let reply = match (command, args.as_slice()) {
+            ("/silence", []) => list_silences(),
+            ("/silence", [alert, duration]) => match parse_duration(duration) {
+                Some(duration) => self.silence(alert, duration, sender).await,
+                None => format!("⚠️ Invalid duration '{duration}' (examples: 30m, 2h, 1d)"),
+            },
+            ("/unsilence", [alert]) => self.unsilence(alert).await,
+            _ => "Usage: /silence <alertname> <duration> | /silence | /unsilence <alertname>"
+                .to_string(),
+        };
+        Some(reply)
+    }
+
+    async fn silence(&self, alert: &str, duration: Duration, sender: &str) -> String {
+        let now = Utc::now();
+        let Ok(until) = chrono::Duration::from_std(duration).map(|d| now + d) else {
+            return "⚠️ Duration is too long".to_string();
+        };
+        let mut upstream_id = None;
+        let mut note = String::new();
+        if let Some(api_url) = &self.api_url {
+            match self
+                .create_upstream(api_url, alert, now, until, sender)
+                .await
+            {
+                Ok(id) => upstream_id = Some(id),
+                Err(e) => {
+                    tracing::warn!("Failed to create Alertmanager silence for {alert}: {e}");
+                    note = " (Alertmanager silence failed; muted here only)".to_string();
+                }
+            }
+        }
+        SILENCES.lock().insert(
+            alert.to_string(),
+            Silence {
+                until,
+                by: sender.to_string(),
+                upstream_id,
+            },
+        );
+        format!(
+            "🔕 Silenced {alert} until {}{note}",
+            until.format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+
+    async fn unsilence(&self, alert: &str) -> String {
+        let Some(silence) = SILENCES.lock().remove(alert) else {
+            return format!("{alert} is not silenced");
+        };
+        if let (Some(api_url), Some(id)) = (&self.api_url, &silence.upstream_id) {
+            let result = self
+                .client
+                .delete(format!("{api_url}/api/v2/silence/{id}"))
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            if let Err(e) = result {
+                tracing::warn!("Failed to expire Alertmanager silence {id}: {e}");
+            }
+        }
+        format!("🔔 {alert} is no longer silenced")
+    }
+
+    async fn create_upstream(
+        &self,
+        api_url: &str,
+        alert: &str,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        sender: &str,
+    ) -> Result<String> {
+        let resp = self
+            .client
+            .post(format!("{api_url}/api/v2/silences"))
+            .json(&serde_json::json!({
+                "matchers": [{ "name": "alertname", "value": alert, "isRegex": false, "isEqual": true }],
+                "startsAt": starts_at.to_rfc3339(),
+                "endsAt": ends_at.to_rfc3339(),
+                "createdBy": sender,
+                "comment": "Silenced from chat",
+            }))
+            .send()
+            .await
+            .context("Alertmanager request failed")?;
+        let status = resp.status();
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        if !status.is_success() {
+            bail!("Alertmanager returned {status}: {body}");
+        }
+        Ok(body["silenceID"].as_str().unwrap_or_default().to_string())
+    }
+}
+
+fn list_silences() -> String {
+    let now = Utc::now();
+    let mut silences = SILENCES.lock();
+    silences.retain(|_, silence| silence.until > now);
+    if silences.is_empty() {
+        return "No active silences.".to_string();
+    }
+    let mut names: Vec<_> = silences.iter().collect();
+    names.sort_by_key(|(name, _)| name.as_str());
+    let mut reply = String::from("🔕 Active silences:");
+    for (name, silence) in names {
+        let _ = write!(
+            reply,
+            "\n• {name} until {} (by {})",
+            silence.until.format("%Y-%m-%d %H:%M UTC"),
+            silence.by
+        );
+    }
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(status: &str, name: &str, severity: &str) -> Alert {
+        Alert {
+            status: status.into(),
+            labels: HashMap::from([
+                ("alertname".to_string(), name.to_string()),
+                ("severity".to_string(), severity.to_string()),
+                ("instance".to_string(), "api-1".to_string()),
+            ]),
+            annotations: HashMap::from([("summary".to_string(), "p99 above 2s".to_string())]),
+        }
+    }
+
+    fn silencer() -> AlertSilencer {
+        AlertSilencer::from_config(&AlertmanagerConfig {
+            token: "t".into(),
+            api_url: None,
+            send_resolved: true,
+            targets: vec![IngressTargetConfig {
+                channel: "slack".into(),
+                recipient: "C-ops".into(),
+                events: vec![],
+                sources: vec![],
+                digest: None,
+            }],
+        })
+    }
+
+    #[test]
+    fn durations_parse() {
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86_400)));
+        assert_eq!(parse_duration("90"), None);
+        assert_eq!(parse_duration("2x"), None);
+        assert_eq!(parse_duration("0m"), None);
+    }
+
+    #[test]
+    fn alerts_render_firing_then_resolved() {
+        let firing = alert("firing", "HighLatency", "critical");
+        let resolved = alert("resolved", "DiskFull", "warning");
+        let text = render_alerts(&[&resolved, &firing]);
+        assert_eq!(
+            text,
+            "🔥 FIRING (1)\n• [critical] HighLatency (api-1): p99 above 2s\n\
+             ✅ RESOLVED (1)\n• [warning] DiskFull (api-1): p99 above 2s\n\
+             Mute with /silence <alertname> <duration>"
+        );
+        assert!(!render_alerts(&[&resolved]).contains("/silence"));
+    }
+
+    #[tokio::test]
+    async fn silence_commands_only_work_in_alert_chats() {
+        let silencer = silencer();
+        assert!(silencer
+            .handle_command("slack", "C-random", "u1", "/silence TestSilenceA 1h")
+            .await
+            .is_none());
+        assert!(silencer
+            .handle_command("slack", "C-ops", "u1", "hello")
+            .await
+            .is_none());
+
+        let reply = silencer
+            .handle_command("slack", "C-ops", "u1", "/silence TestSilenceA 1h")
+            .await
+            .unwrap();
+        assert!(reply.starts_with("🔕 Silenced TestSilenceA"));
+        assert!(is_silenced("TestSilenceA"));
+        assert!(list_silences().contains("TestSilenceA"));
+
+        silencer
+            .handle_command("slack", "C-ops", "u1", "/unsilence TestSilenceA")
+            .await
+            .unwrap();
+        assert!(!is_silenced("TestSilenceA"));
+    }
+
+    #[tokio::test]
+    async fn delivery_routes_by_severity_and_skips_silenced() {
+        use crate::channels::manager::ChannelManager;
+        use crate::channels::traits::{Channel, ChannelMessage};
+        use async_trait::async_trait;
+
+        #[derive(Default)]
+        struct RecordingChannel {
+            sent: Mutex<Vec<String>>,
+        }
+
+        #[async_trait]
+        impl Channel for RecordingChannel {
+            fn name(&self) -> &str {
+                "slack"
+            }
+
+            async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+                self.sent.lock().push(format!("{recipient}:{message}"));
+                Ok(())
+            }
+
+            async fn listen(
+                &self,
+                _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+            ) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let channel = Arc::new(RecordingChannel::default());
+        let channels: Vec<Arc<dyn Channel>> = vec![channel.clone()];
+        let control = ChannelControl::new();
+        control.attach(ChannelManager::from_channels(&channels));
+        let target = |recipient: &str, severity: &str| IngressTargetConfig {
+            channel: "slack".into(),
+            recipient: recipient.into(),
+            events: vec![severity.into()],
+            sources: vec![],
+            digest: None,
+        };
+        let receiver = AlertmanagerReceiver::new(
+            Arc::new(ManagedSecret::fixed("t")),
+            vec![target("pager", "critical"), target("ops", "warning")],
+            false,
+        );
+
+        silencer()
+            .handle_command("slack", "C-ops", "u1", "/silence TestSilenceB 1h")
+            .await;
+        let alerts = vec![
+            alert("firing", "TestPageMe", "critical"),
+            alert("firing", "TestSilenceB", "warning"),
+            alert("resolved", "TestResolved", "warning"),
+        ];
+        assert_eq!(receiver.deliver(&control, &alerts).await, 1);
+        let sent = channel.sent.lock();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].starts_with("pager:🔥 FIRING (1)\n• [critical] TestPageMe"));
+    }
+}
@@ -0,0 +1,560 @@
+//! GitHub and GitLab webhooks: push, pull/merge request, issue and CI events.
+
+use super::{deliver_to_targets, error_response, IngressState};
+use crate::config::schema::{ForgeWebhookConfig, IngressTargetConfig};
+use crate::security::pairing::constant_time_eq;
+use crate::security::webhook_security::{verify_hmac_sha256, ReplayGuard};
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long GitHub delivery ids are remembered; a delivery seen again
+/// within this window is a replay and isn't routed.
+const DELIVERY_MEMORY_SECS: u64 = 24 * 60 * 60;
+
+/// A forge event reduced to what notifications show.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForgeEvent {
+    /// "push", "pull_request", "issue" or "ci"
+    pub kind: &'static str,
+    /// `owner/name` or GitLab `group/project`
+    pub repo: String,
+    pub actor: String,
+    pub action: String,
+    pub title: String,
+    pub url: String,
+    pub branch: String,
+    /// CI result ("success", "failure", ...)
+    pub status: String,
+    pub commits: usize,
+}
+
+fn default_template(kind: &str) -> &'static str {
+    match kind {
+        "push" => "📦 [{repo}] {actor} pushed {commits} commit(s) to {branch}: {title}\n{url}",
+        "pull_request" => "🔀 [{repo}] {actor} {action} pull request: {title}\n{url}",
+        "issue" => "🐛 [{repo}] {actor} {action} issue: {title}\n{url}",
+        _ => "⚙️ [{repo}] {title} {status} on {branch}\n{url}",
+    }
+}
+
+/// Verifies, renders and routes the webhooks of one forge.
+pub struct ForgeReceiver {
+    secret: Arc<ManagedSecret>,
+    targets: Vec<IngressTargetConfig>,
+    templates: HashMap<String, String>,
+    deliveries: ReplayGuard,
+}
+
+impl ForgeReceiver {
+    pub fn new(
+        secret: Arc<ManagedSecret>,
+        targets: Vec<IngressTargetConfig>,
+        templates: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            secret,
+            targets,
+            templates,
+            deliveries: ReplayGuard::new(Duration::from_secs(DELIVERY_MEMORY_SECS)),
+        }
+    }
+
+    pub fn from_config(
+        config: &ForgeWebhookConfig,
+        resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Self> {
+        Ok(Self::new(
+            resolve(&config.secret)?,
+            config.targets.clone(),
+            config.templates.clone(),
+        ))
+    }
+
+    /// Render an event through its template. Placeholders: `{repo}`,
+    /// `{actor}`, `{action}`, `{title}`, `{url}`, `{branch}`, `{status}`,
+    /// `{commits}`.
+    pub fn render(&self, event: &ForgeEvent) -> String {
+        let template = self
+            .templates
+            .get(event.kind)
+            .map_or_else(|| default_template(event.kind), String::as_str);
+        template
+            .replace("{repo}", &event.repo)
+            .replace("{actor}", &event.actor)
+            .replace("{action}", &event.action)
+            .replace("{title}", &event.title)
+            .replace("{url}", &event.url)
+            .replace("{branch}", &event.branch)
+            .replace("{status}", &event.status)
+            .replace("{commits}", &event.commits.to_string())
+            .trim()
+            .to_string()
+    }
+
+    async fn dispatch(&self, state: &IngressState, event: Option<ForgeEvent>) -> Response {
+        let Some(event) = event else {
+            return (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({ "status": "ignored" })),
+            )
+                .into_response();
+        };
+        let message = self.render(&event);
+        let delivered = deliver_to_targets(
+            &state.control,
+            &self.targets,
+            event.kind,
+            &event.repo,
+            &message,
+        )
+        .await;
+        Json(serde_json::json!({ "status": "delivered", "targets": delivered })).into_response()
+    }
+}
+
+fn str_at<'a>(payload: &'a Value, pointer: &str) -> &'a str {
+    payload
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .unwrap_or("")
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or("").trim().to_string()
+}
+
+/// Normalize a GitHub webhook. `None` for events that aren't notified
+/// (pings, intermediate CI states, ...).
+pub fn github_event(event: &str, payload: &Value) -> Option<ForgeEvent> {
+    let repo = str_at(payload, "/repository/full_name").to_string();
+    let actor = str_at(payload, "/sender/login").to_string();
+    match event {
+        "push" => {
+            let commits = payload["commits"].as_array().map_or(0, Vec::len);
+            if commits == 0 {
+                return None;
+            }
+            Some(ForgeEvent {
+                kind: "push",
+                repo,
+                actor,
+                action: "pushed".into(),
+                title: first_line(str_at(payload, "/head_commit/message")),
+                url: str_at(payload, "/compare").to_string(),
+                branch: str_at(payload, "/ref")
+                    .trim_start_matches("refs/heads/")
+                    .into(),
+                commits,
+                ..ForgeEvent::default()
+            })
+        }
+        "pull_request" => {
+            let mut action = str_at(payload, "/action").to_string();
+            if !matches!(action.as_str(), "opened" | "closed" | "reopened") {
+                return None;
+            }
+            if action == "closed" && payload["pull_request"]["merged"].as_bool() == Some(true) {
+                action = "merged".into();
+            }
+            Some(ForgeEvent {
+                kind: "pull_request",
+                repo,
+                actor,
+                action,
+                title: str_at(payload, "/pull_request/title").to_string(),
+                url: str_at(payload, "/pull_request/html_url").to_string(),
+                branch: str_at(payload, "/pull_request/head/ref").to_string(),
+                ..ForgeEvent::default()
+            })
+        }
+        "issues" => {
+            let action = str_at(payload, "/action").to_string();
+            if !matches!(action.as_str(), "opened" | "closed" | "reopened") {
+                return None;
+            }
+            Some(ForgeEvent {
+                kind: "issue",
+                repo,
+                actor,
+                action,
+                title: str_at(payload, "/issue/title").to_string(),
+                url: str_at(payload, "/issue/html_url").to_string(),
+                ..ForgeEvent::default()
+            })
+        }
+        "workflow_run" if str_at(payload, "/action") == "completed" => Some(ForgeEvent {
+            kind: "ci",
+            repo,
+            actor,
+            action: "completed".into(),
+            title: str_at(payload, "/workflow_run/name").to_string(),
+            url: str_at(payload, "/workflow_run/html_url").to_string(),
+            branch: str_at(payload, "/workflow_run/head_branch").to_string(),
+            status: str_at(payload, "/workflow_run/conclusion").to_string(),
+            ..ForgeEvent::default()
+        }),
+        _ => None,
+    }
+}
+
+/// Normalize a GitLab webhook by its `object_kind`.
+pub fn gitlab_event(payload: &Value) -> Option<ForgeEvent> {
+    let repo = str_at(payload, "/project/path_with_namespace").to_string();
+    let actor = match str_at(payload, "/user_name") {
+        "" => str_at(payload, "/user/username").to_string(),
+        name => name.to_string(),
+    };
+    match str_at(payload, "/object_kind") {
+        "push" => {
+            let commits = payload["total_commits_count"]
+                .as_u64()
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(0);
+            if commits == 0 {
+                return None;
+            }
+            let title = payload["commits"]
+                .as_array()
+                .and_then(|c| c.last())
+                .map(|c| first_line(c["message"].as_str().unwrap_or("")))
+                .unwrap_or_default();
+            Some(ForgeEvent {
+                kind: "push",
+                repo,
+                actor,
+                action: "pushed".into(),
+                title,
+                url: str_at(payload, "/project/web_url").to_string(),
+                branch: str_at(payload, "/ref")
+                    .trim_start_matches("refs/heads/")
+                    .into(),
+                commits,
+                ..ForgeEvent::default()
+            })
+        }
+        kind @ ("merge_request" | "issue") => {
+            let action = match str_at(payload, "/object_attributes/action") {
+                "open" => "opened",
+                "close" => "closed",
+                "reopen" => "reopened",
+                "merge" => "merged",
+                _ => return None,
+            };
+            Some(ForgeEvent {
+                kind: if kind == "issue" {
+                    "issue"
+                } else {
+                    "pull_request"
+                },
+                repo,
+                actor,
+                action: action.into(),
+                title: str_at(payload, "/object_attributes/title").to_string(),
+                url: str_at(payload, "/object_attributes/url").to_string(),
+                branch: str_at(payload, "/object_attributes/source_branch").to_string(),
+                ..ForgeEvent::default()
+            })
+        }
+        "pipeline" => {
+            let status = str_at(payload, "/object_attributes/status");
+            if !matches!(status, "success" | "failed" | "canceled") {
+                return None;
+            }
+            let id = payload["object_attributes"]["id"].as_u64().unwrap_or(0);
+            Some(ForgeEvent {
+                kind: "ci",
+                repo,
+                actor,
+                action: "completed".into(),
+                title: format!("Pipeline #{id}"),
+                url: format!("{}/-/pipelines/{id}", str_at(payload, "/project/web_url")),
+                branch: str_at(payload, "/object_attributes/ref").to_string(),
+                status: status.to_string(),
+                ..ForgeEvent::default()
+            })
+        }
+        _ => None,
+    }
+}
+
+async fn resolve_secret(receiver: &ForgeReceiver) -> Result<String, Response> {
+    receiver.secret.get().await.map_err(|e| {
+        tracing::error!("Ingress: failed to resolve webhook secret: {e}");
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Webhook secret unavailable",
+        )
+    })
+}
+
+/// POST /ingress/github
+pub(super) async fn handle_github(
+    State(state): State<IngressState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(receiver) = state.github.clone() else {
+        return error_response(StatusCode::NOT_FOUND, "GitHub webhooks are not configured");
+    };
+    let secret = match resolve_secret(&receiver).await {
+        Ok(secret) => secret,
+        Err(resp) => return resp,
+    };
+    let signature = headers
+        .get("X-Hub-Signature-256")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !verify_hmac_sha256(&secret, &body, signature) {
+        tracing::warn!("Ingress: rejected GitHub webhook with invalid signature");
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid signature");
+    }
+    if let Some(delivery) = headers
+        .get("X-GitHub-Delivery")
+        .and_then(|v| v.to_str().ok())
+    {
+        if receiver.deliveries.check(delivery).is_err() {
+            tracing::warn!("Ingress: ignored replayed GitHub delivery {delivery}");
+            return Json(serde_json::json!({ "status": "duplicate" })).into_response();
+        }
+    }
+    let Ok(payload) = serde_json::from_slice::<Value>(&body) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid JSON payload");
+    };
+    let event = headers
+        .get("X-GitHub-Event")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    receiver
+        .dispatch(&state, github_event(event, &payload))
+        .await
+}
+
+/// POST /ingress/gitlab
+pub(super) async fn handle_gitlab(
+    State(state): State<IngressState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(receiver) = state.gitlab.clone() else {
+        return error_response(StatusCode::NOT_FOUND, "GitLab webhooks are not configured");
+    };
+    let secret = match resolve_secret(&receiver).await {
+        Ok(secret) => secret,
+        Err(resp) => return resp,
+    };
+    let token = headers
+        .get("X-Gitlab-Token")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if token.is_empty() || !constant_time_eq(token, &secret) {
+        tracing::warn!("Ingress: rejected GitLab webhook with invalid token");
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid token");
+    }
+    let Ok(payload) = serde_json::from_slice::<Value>(&body) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid JSON payload");
+    };
+    receiver.dispatch(&state, gitlab_event(&payload)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::control::ChannelControl;
+    use crate::channels::manager::ChannelManager;
+    use crate::channels::traits::{Channel, ChannelMessage};
+    use async_trait::async_trait;
+    use axum::http::HeaderValue;
+    use http_body_util::BodyExt;
+    use serde_json::json;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: parking_lot::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        async fn send(&self, message: &str, recipient: &str) -> anyhow::Result<()> {
+            self.sent.lock().push(format!("{recipient}:{message}"));
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn state(targets: Vec<IngressTargetConfig>) -> (IngressState, Arc<RecordingChannel>) {
+        let channel = Arc::new(RecordingChannel::default());
+        let channels: Vec<Arc<dyn Channel>> = vec![channel.clone()];
+        let control = Arc::new(ChannelControl::new());
+        control.attach(ChannelManager::from_channels(&channels));
+        let receiver = Arc::new(ForgeReceiver::new(
+            Arc::new(ManagedSecret::fixed("s3cret")),
+            targets,
+            HashMap::new(),
+        ));
+        let state = IngressState {
+            control,
+            github: Some(Arc::clone(&receiver)),
+            gitlab: Some(receiver),
+            alertmanager: None,
+            notify: None,
+        };
+        (state, channel)
+    }
+
+    fn target(events: &[&str], sources: &[&str]) -> IngressTargetConfig {
+        IngressTargetConfig {
+            channel: "test".into(),
+            recipient: "ops".into(),
+            events: events.iter().map(|s| (*s).to_string()).collect(),
+            sources: sources.iter().map(|s| (*s).to_string()).collect(),
+            digest: None,
+        }
+    }
+
+    fn github_push() -> Value {
+        json!({
+            "ref": "refs/heads/main",
+            "compare": "https://github.com/org/app/compare/a...b",
+            "repository": { "full_name": "org/app" },
+            "sender": { "login": "alice" },
+            "commits": [{}, {}],
+            "head_commit": { "message": "Fix login\n\nDetails" }
+        })
+    }
+
+    #[test]
+    fn github_events_are_normalized_and_rendered() {
+        let event = github_event("push", &github_push()).unwrap();
+        assert_eq!(event.branch, "main");
+        assert_eq!(event.commits, 2);
+        let (state, _) = state(vec![]);
+        assert_eq!(
+            state.github.unwrap().render(&event),
+            "📦 [org/app] alice pushed 2 commit(s) to main: Fix login\nhttps://github.com/org/app/compare/a...b"
+        );
+
+        let merged = github_event(
+            "pull_request",
+            &json!({
+                "action": "closed",
+                "pull_request": { "merged": true, "title": "Add X", "html_url": "u" },
+                "repository": { "full_name": "org/app" },
+                "sender": { "login": "bob" }
+            }),
+        )
+        .unwrap();
+        assert_eq!(merged.action, "merged");
+        assert!(github_event("pull_request", &json!({ "action": "labeled" })).is_none());
+        assert!(github_event("ping", &json!({})).is_none());
+    }
+
+    #[test]
+    fn gitlab_pipeline_is_normalized() {
+        let event = gitlab_event(&json!({
+            "object_kind": "pipeline",
+            "object_attributes": { "id": 7, "status": "failed", "ref": "main" },
+            "project": { "path_with_namespace": "grp/app", "web_url": "https://gl/grp/app" },
+            "user": { "username": "carol" }
+        }))
+        .unwrap();
+        assert_eq!(event.kind, "ci");
+        assert_eq!(event.url, "https://gl/grp/app/-/pipelines/7");
+        assert_eq!(event.actor, "carol");
+        assert!(gitlab_event(&json!({
+            "object_kind": "pipeline",
+            "object_attributes": { "status": "running" }
+        }))
+        .is_none());
+    }
+
+    #[tokio::test]
+    async fn github_webhook_requires_signature_and_routes_by_filters() {
+        let (state, channel) = state(vec![
+            target(&["push"], &["org/*"]),
+            target(&["issue"], &[]),
+            target(&[], &["other/*"]),
+        ]);
+        let body = serde_json::to_vec(&github_push()).unwrap();
+        let signature = crate::security::webhook_security::sign_hmac_sha256("s3cret", &body);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-GitHub-Event", HeaderValue::from_static("push"));
+        headers.insert(
+            "X-GitHub-Delivery",
+            HeaderValue::from_static("72d3162e-cc78-11e3-81ab-4c9367dc0958"),
+        );
+        let resp = handle_github(
+            State(state.clone()),
+            headers.clone(),
+            Bytes::from(body.clone()),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        headers.insert(
+            "X-Hub-Signature-256",
+            HeaderValue::from_str(&signature).unwrap(),
+        );
+        let resp = handle_github(
+            State(state.clone()),
+            headers.clone(),
+            Bytes::from(body.clone()),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The same delivery again is a replay
+        let resp = handle_github(State(state), headers, Bytes::from(body)).await;
+        let resp_body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&resp_body).contains("duplicate"));
+        let sent = channel.sent.lock();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].starts_with("ops:📦 [org/app]"));
+    }
+
+    #[tokio::test]
+    async fn gitlab_webhook_checks_token() {
+        let (state, channel) = state(vec![target(&[], &[])]);
+        let body = Bytes::from(
+            serde_json::to_vec(&json!({
+                "object_kind": "issue",
+                "user": { "username": "dan" },
+                "project": { "path_with_namespace": "grp/app" },
+                "object_attributes": { "action": "open", "title": "Crash", "url": "u" }
+            }))
+            .unwrap(),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Gitlab-Token", HeaderValue::from_static("wrong"));
+        let resp = handle_gitlab(State(state.clone()), headers.clone(), body.clone()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        headers.insert("X-Gitlab-Token", HeaderValue::from_static("s3cret"));
+        let resp = handle_gitlab(State(state), headers, body).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            channel.sent.lock().as_slice(),
+            ["ops:🐛 [grp/app] dan opened issue: Crash\nu"]
+        );
+    }
+}
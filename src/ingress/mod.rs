@@ -0,0 +1,180 @@
+//! Inbound HTTP receiver for third-party integrations.
+//!
+//! A small axum server that runs next to the channel runtime and turns
+//! events from other systems into channel messages:
+//!
+//! - `POST /ingress/github` — GitHub webhooks (`X-Hub-Signature-256` verified,
+//!   replayed deliveries ignored)
+//! - `POST /ingress/gitlab` — GitLab webhooks (`X-Gitlab-Token` verified)
+//! - `POST /ingress/alertmanager` — Prometheus Alertmanager webhooks (bearer token)
+//! - `POST /notify/{topic}` — topic notifications for subscribed chats (bearer token)
+//!
+//! Every endpoint authenticates its own requests, so unlike the admin API
+//! the receiver may be bound to a public address.
+
+pub mod alertmanager;
+pub mod forge;
+pub mod notify;
+
+use crate::channels::control::ChannelControl;
+use crate::channels::priority::Priority;
+use crate::config::schema::{IngressConfig, IngressTargetConfig};
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+
+/// Forge payloads (push events with many commits) can be large.
+const INGRESS_MAX_BODY_SIZE: usize = 1_048_576;
+const INGRESS_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Clone)]
+pub struct IngressState {
+    pub control: Arc<ChannelControl>,
+    pub github: Option<Arc<forge::ForgeReceiver>>,
+    pub gitlab: Option<Arc<forge::ForgeReceiver>>,
+    pub alertmanager: Option<Arc<alertmanager::AlertmanagerReceiver>>,
+    pub notify: Option<Arc<notify::NotifyReceiver>>,
+}
+
+pub fn ingress_router(state: IngressState) -> Router {
+    Router::new()
+        .route("/ingress/github", post(forge::handle_github))
+        .route("/ingress/gitlab", post(forge::handle_gitlab))
+        .route(
+            "/ingress/alertmanager",
+            post(alertmanager::handle_alertmanager),
+        )
+        .route("/notify/{topic}", post(notify::handle_notify))
+        .with_state(state)
+        .layer(RequestBodyLimitLayer::new(INGRESS_MAX_BODY_SIZE))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            Duration::from_secs(INGRESS_REQUEST_TIMEOUT_SECS),
+        ))
+}
+
+/// Serve the ingress receiver until the listener fails. `resolve` turns a
+/// configured credential into a managed secret.
+pub async fn run_ingress_server(
+    config: &IngressConfig,
+    workspace_dir: &Path,
+    control: Arc<ChannelControl>,
+    resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+) -> Result<()> {
+    let github = config
+        .github
+        .as_ref()
+        .map(|c| forge::ForgeReceiver::from_config(c, &resolve).map(Arc::new))
+        .transpose()?;
+    let gitlab = config
+        .gitlab
+        .as_ref()
+        .map(|c| forge::ForgeReceiver::from_config(c, &resolve).map(Arc::new))
+        .transpose()?;
+    let alertmanager = config
+        .alertmanager
+        .as_ref()
+        .map(|c| alertmanager::AlertmanagerReceiver::from_config(c, &resolve).map(Arc::new))
+        .transpose()?;
+    let notify = config
+        .notify
+        .as_ref()
+        .map(|c| -> Result<_> {
+            let subscriptions = notify::TopicSubscriptions::open(workspace_dir, c.topics.clone())?;
+            Ok(Arc::new(notify::NotifyReceiver::new(
+                resolve(&c.token)?,
+                subscriptions,
+            )))
+        })
+        .transpose()?;
+
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("  📥 Ingress:    http://{}", listener.local_addr()?);
+
+    let state = IngressState {
+        control,
+        github,
+        gitlab,
+        alertmanager,
+        notify,
+    };
+    axum::serve(listener, ingress_router(state)).await?;
+    Ok(())
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "error": message.into() }))).into_response()
+}
+
+/// Whether `value` matches a target filter: empty filters match everything,
+/// `*` suffixes match prefixes (`org/*`).
+fn filter_matches(filter: &[String], value: &str) -> bool {
+    filter.is_empty()
+        || filter
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => value.starts_with(prefix),
+                None => pattern == value,
+            })
+}
+
+/// Send `message` to every target whose filters match, or add it to the
+/// target's digest. Returns how many targets it was delivered to.
+async fn deliver_to_targets(
+    control: &ChannelControl,
+    targets: &[IngressTargetConfig],
+    event: &str,
+    source: &str,
+    message: &str,
+) -> usize {
+    let mut delivered = 0;
+    for target in targets {
+        if !filter_matches(&target.events, event) || !filter_matches(&target.sources, source) {
+            continue;
+        }
+        match control
+            .send_or_digest(
+                &target.channel,
+                &target.recipient,
+                source,
+                target.digest.as_ref(),
+                message,
+                Priority::Normal,
+            )
+            .await
+        {
+            Ok(()) => delivered += 1,
+            Err(e) => tracing::warn!(
+                "Ingress: failed to deliver {event} to {}: {e}",
+                target.channel
+            ),
+        }
+    }
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_match_exact_and_prefix() {
+        assert!(filter_matches(&[], "anything"));
+        let filter = vec!["org/*".to_string(), "push".to_string()];
+        assert!(filter_matches(&filter, "org/repo"));
+        assert!(filter_matches(&filter, "push"));
+        assert!(!filter_matches(&filter, "other/repo"));
+    }
+}
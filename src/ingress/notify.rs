@@ -0,0 +1,369 @@
+//! Topic-based notifications: `POST /notify/{topic}` fans a message out to
+//! every chat subscribed to the topic with `/subscribe <topic>`. A JSON body
+//! with `"urgent": true` goes out during quiet hours too.
+//!
+//! Subscriptions live in `<workspace>/notify/subscriptions.db`, shared by the
+//! ingress receiver and the channel runtime.
+
+use super::{error_response, IngressState};
+use crate::channels::control::ChannelControl;
+use crate::channels::priority::Priority;
+use crate::security::pairing::constant_time_eq;
+use crate::security::ManagedSecret;
+use anyhow::{Context, Result};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection};
+use std::sync::Arc;
+
+const MAX_TOPIC_LEN: usize = 64;
+
+/// Topic names are short lowercase identifiers (`deploys`, `ci.main`).
+pub fn valid_topic(topic: &str) -> bool {
+    !topic.is_empty()
+        && topic.len() <= MAX_TOPIC_LEN
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Persisted topic subscriptions, keyed by chat (`channel` + `recipient`).
+pub struct TopicSubscriptions {
+    conn: Mutex<Connection>,
+    /// Topics that may be used; empty allows any valid name
+    allowed: Vec<String>,
+}
+
+impl TopicSubscriptions {
+    /// Open (or create) the store under `<workspace>/notify/`.
+    pub fn open(workspace_dir: &std::path::Path, allowed: Vec<String>) -> Result<Self> {
+        let dir = workspace_dir.join("notify");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create notify directory: {}", dir.display()))?;
+        let db_path = dir.join("subscriptions.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open subscriptions DB: {}", db_path.display()))?;
+        Self::with_connection(conn, allowed)
+    }
+
+    /// In-memory store for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory(allowed: Vec<String>) -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, allowed)
+    }
+
+    fn with_connection(conn: Connection, allowed: Vec<String>) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS subscriptions (
+                topic      TEXT NOT NULL,
+                channel    TEXT NOT NULL,
+                recipient  TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (topic, channel, recipient)
+             );",
+        )
+        .context("Failed to initialize subscriptions schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            allowed,
+        })
+    }
+
+    pub fn is_allowed(&self, topic: &str) -> bool {
+        valid_topic(topic) && (self.allowed.is_empty() || self.allowed.iter().any(|t| t == topic))
+    }
+
+    /// Subscribe a chat; returns `false` when it already was.
+    pub fn subscribe(&self, topic: &str, channel: &str, recipient: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO subscriptions (topic, channel, recipient, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![topic, channel, recipient, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Unsubscribe a chat; returns `false` when it wasn't subscribed.
+    pub fn unsubscribe(&self, topic: &str, channel: &str, recipient: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let removed = conn.execute(
+            "DELETE FROM subscriptions WHERE topic = ?1 AND channel = ?2 AND recipient = ?3",
+            params![topic, channel, recipient],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// `(channel, recipient)` pairs subscribed to `topic`.
+    pub fn subscribers(&self, topic: &str) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT channel, recipient FROM subscriptions WHERE topic = ?1
+             ORDER BY channel, recipient",
+        )?;
+        let rows = stmt
+            .query_map(params![topic], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Topics a chat is subscribed to.
+    pub fn topics_for(&self, channel: &str, recipient: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT topic FROM subscriptions WHERE channel = ?1 AND recipient = ?2
+             ORDER BY topic",
+        )?;
+        let rows = stmt
+            .query_map(params![channel, recipient], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(rows)
+    }
+
+    /// Answer `/subscribe`, `/unsubscribe` or `/subscriptions` from a chat,
+    /// or `None` for any other message.
+    pub fn handle_command(&self, channel: &str, recipient: &str, content: &str) -> Option<String> {
+        let mut parts = content.split_whitespace();
+        let command = parts.next()?.split('@').next()?;
+        if !matches!(command, "/subscribe" | "/unsubscribe" | "/subscriptions") {
+            return None;
+        }
+        let topic = parts.next();
+        let result = match (command, topic) {
+            ("/subscriptions", _) => self.topics_for(channel, recipient).map(|topics| {
+                if topics.is_empty() {
+                    "This chat has no topic subscriptions.".to_string()
+                } else {
+                    format!("📬 Subscribed topics: {}", topics.join(", "))
+                }
+            }),
+            (_, None) => Ok(format!("Usage: {command} <topic>")),
+            (_, Some(topic)) if !self.is_allowed(topic) => {
+                Ok(format!("⚠️ Unknown topic '{topic}'"))
+            }
+            ("/subscribe", Some(topic)) => self.subscribe(topic, channel, recipient).map(|added| {
+                if added {
+                    format!("📬 Subscribed to {topic}")
+                } else {
+                    format!("Already subscribed to {topic}")
+                }
+            }),
+            (_, Some(topic)) => self.unsubscribe(topic, channel, recipient).map(|removed| {
+                if removed {
+                    format!("📭 Unsubscribed from {topic}")
+                } else {
+                    format!("Not subscribed to {topic}")
+                }
+            }),
+        };
+        Some(result.unwrap_or_else(|e| {
+            tracing::warn!("Topic subscription command failed: {e}");
+            "⚠️ Failed to update subscriptions".to_string()
+        }))
+    }
+}
+
+/// Authenticated publisher side of the topic subscriptions.
+pub struct NotifyReceiver {
+    token: Arc<ManagedSecret>,
+    subscriptions: TopicSubscriptions,
+}
+
+impl NotifyReceiver {
+    pub fn new(token: Arc<ManagedSecret>, subscriptions: TopicSubscriptions) -> Self {
+        Self {
+            token,
+            subscriptions,
+        }
+    }
+
+    /// Send `message` to every subscriber of `topic`; returns how many got it.
+    pub async fn publish(
+        &self,
+        control: &ChannelControl,
+        topic: &str,
+        message: &str,
+        priority: Priority,
+    ) -> usize {
+        let subscribers = match self.subscriptions.subscribers(topic) {
+            Ok(subscribers) => subscribers,
+            Err(e) => {
+                tracing::warn!("Notify: failed to load subscribers of {topic}: {e}");
+                return 0;
+            }
+        };
+        let mut delivered = 0;
+        for (channel, recipient) in subscribers {
+            match control
+                .send_with_priority(&channel, &recipient, message, priority)
+                .await
+            {
+                Ok(()) => delivered += 1,
+                Err(e) => tracing::warn!("Notify: failed to deliver {topic} to {channel}: {e}"),
+            }
+        }
+        delivered
+    }
+}
+
+/// Message text from a JSON body (`{"message": ..., "title": ...}`) or the
+/// raw body when it isn't JSON.
+fn notification_text(topic: &str, headers: &HeaderMap, body: &[u8]) -> Option<String> {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let (title, message) = if is_json {
+        let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+        (
+            value["title"].as_str().map(str::to_string),
+            value["message"].as_str()?.to_string(),
+        )
+    } else {
+        (None, String::from_utf8(body.to_vec()).ok()?)
+    };
+    let message = message.trim();
+    if message.is_empty() {
+        return None;
+    }
+    Some(match title {
+        Some(title) => format!("📣 [{topic}] {title}\n{message}"),
+        None => format!("📣 [{topic}] {message}"),
+    })
+}
+
+/// Urgent when a JSON body has `"urgent": true`; quiet hours don't hold
+/// urgent notifications.
+fn notification_priority(headers: &HeaderMap, body: &[u8]) -> Priority {
+    let urgent = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"))
+        && serde_json::from_slice::<serde_json::Value>(body)
+            .is_ok_and(|value| value["urgent"].as_bool() == Some(true));
+    if urgent {
+        Priority::Urgent
+    } else {
+        Priority::Normal
+    }
+}
+
+/// POST /notify/{topic}
+pub(super) async fn handle_notify(
+    State(state): State<IngressState>,
+    Path(topic): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(receiver) = state.notify.clone() else {
+        return error_response(StatusCode::NOT_FOUND, "Notifications are not configured");
+    };
+    let expected = match receiver.token.get().await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Ingress: failed to resolve notify token: {e}");
+            return error_response(StatusCode::SERVICE_UNAVAILABLE, "Notify token unavailable");
+        }
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if provided.is_empty() || !constant_time_eq(provided, &expected) {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid token");
+    }
+    if !receiver.subscriptions.is_allowed(&topic) {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown topic '{topic}'"));
+    }
+    let Some(message) = notification_text(&topic, &headers, &body) else {
+        return error_response(StatusCode::BAD_REQUEST, "Missing message");
+    };
+    let priority = notification_priority(&headers, &body);
+    let delivered = receiver
+        .publish(&state.control, &topic, &message, priority)
+        .await;
+    Json(serde_json::json!({ "status": "delivered", "subscribers": delivered })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn topic_names_are_validated() {
+        assert!(valid_topic("deploys"));
+        assert!(valid_topic("ci.main-branch_2"));
+        assert!(!valid_topic("Deploys"));
+        assert!(!valid_topic("a b"));
+        assert!(!valid_topic(""));
+    }
+
+    #[test]
+    fn commands_manage_chat_subscriptions() {
+        let subs = TopicSubscriptions::in_memory(vec!["deploys".into(), "ci".into()]).unwrap();
+        assert_eq!(subs.handle_command("tg", "42", "hello"), None);
+        assert_eq!(
+            subs.handle_command("tg", "42", "/subscribe deploys")
+                .unwrap(),
+            "📬 Subscribed to deploys"
+        );
+        assert_eq!(
+            subs.handle_command("tg", "42", "/subscribe deploys")
+                .unwrap(),
+            "Already subscribed to deploys"
+        );
+        assert_eq!(
+            subs.handle_command("tg", "42", "/subscribe other").unwrap(),
+            "⚠️ Unknown topic 'other'"
+        );
+        subs.subscribe("ci", "tg", "42").unwrap();
+        assert_eq!(
+            subs.handle_command("tg", "42", "/subscriptions").unwrap(),
+            "📬 Subscribed topics: ci, deploys"
+        );
+        assert_eq!(
+            subs.subscribers("deploys").unwrap(),
+            vec![("tg".to_string(), "42".to_string())]
+        );
+        assert_eq!(
+            subs.handle_command("tg", "42", "/unsubscribe@bot deploys")
+                .unwrap(),
+            "📭 Unsubscribed from deploys"
+        );
+        assert!(subs.subscribers("deploys").unwrap().is_empty());
+    }
+
+    #[test]
+    fn notification_text_accepts_json_and_plain_bodies() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            notification_text("ci", &headers, b"build passed").unwrap(),
+            "📣 [ci] build passed"
+        );
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        assert_eq!(
+            notification_text("ci", &headers, br#"{"title":"Build","message":"passed"}"#).unwrap(),
+            "📣 [ci] Build\npassed"
+        );
+        assert!(notification_text("ci", &headers, br#"{"title":"x"}"#).is_none());
+        assert_eq!(
+            notification_priority(&headers, br#"{"message":"down","urgent":true}"#),
+            Priority::Urgent
+        );
+        assert_eq!(
+            notification_priority(&headers, br#"{"message":"passed"}"#),
+            Priority::Normal
+        );
+    }
+}
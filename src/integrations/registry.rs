@@ -109,9 +109,15 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
         },
         IntegrationEntry {
             name: "Nostr",
-            description: "Decentralized DMs (NIP-04)",
+            description: "Decentralized DMs (NIP-04, NIP-17)",
             category: IntegrationCategory::Chat,
-            status_fn: |_| IntegrationStatus::ComingSoon,
+            status_fn: |c| {
+                if c.channels_config.nostr.is_some() {
+                    IntegrationStatus::Active
+                } else {
+                    IntegrationStatus::Available
+                }
+            },
         },
         IntegrationEntry {
             name: "WebChat",
@@ -895,11 +901,22 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn nostr_available_when_not_configured() {
+        let config = Config::default();
+        let entries = all_integrations();
+        let ns = entries.iter().find(|e| e.name == "Nostr").unwrap();
+        assert!(matches!(
+            (ns.status_fn)(&config),
+            IntegrationStatus::Available
+        ));
+    }
+
     #[test]
     fn coming_soon_integrations_stay_coming_soon() {
         let config = Config::default();
         let entries = all_integrations();
-        for name in ["Nostr", "Spotify", "Home Assistant"] {
+        for name in ["Spotify", "Home Assistant"] {
             let entry = entries.iter().find(|e| e.name == name).unwrap();
             assert!(
                 matches!((entry.status_fn)(&config), IntegrationStatus::ComingSoon),
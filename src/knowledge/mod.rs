@@ -0,0 +1,426 @@
+//! Team knowledge base: document ingestion and retrieval for answers.
+//!
+//! Markdown, text and (with the `rag-pdf` feature) PDF files under the
+//! configured paths are chunked, embedded and stored in a [`VectorStore`].
+//! Each channel message retrieves the closest chunks, which are handed to
+//! the model as numbered sources; replies that cite `[n]` get a sources
+//! footer listing the files they came from.
+//!
+//! Ingestion is incremental: a file is only re-embedded when its content
+//! hash changes, and files that disappeared are dropped from the store.
+
+mod qdrant;
+mod sqlite;
+
+pub use qdrant::QdrantVectorStore;
+pub use sqlite::SqliteVectorStore;
+
+use crate::config::schema::KnowledgeConfig;
+use crate::memory::chunker;
+use crate::memory::embeddings::EmbeddingProvider;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Texts embedded per provider request during ingestion.
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// A stored piece of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnowledgeChunk {
+    /// Path of the source document, relative to the workspace when possible
+    pub source: String,
+    /// Nearest markdown heading above the chunk
+    pub heading: Option<String>,
+    pub content: String,
+}
+
+/// A chunk returned by a search, with its cosine similarity to the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredChunk {
+    pub chunk: KnowledgeChunk,
+    pub score: f32,
+}
+
+/// Storage for embedded chunks, keyed by source document.
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Backend name (e.g. "sqlite", "qdrant")
+    fn name(&self) -> &str;
+
+    /// Content hash recorded for `source` at its last ingestion.
+    async fn fingerprint(&self, source: &str) -> Result<Option<String>>;
+
+    /// Replace every chunk of `source` with `chunks` and record `fingerprint`.
+    async fn replace(
+        &self,
+        source: &str,
+        fingerprint: &str,
+        chunks: Vec<(KnowledgeChunk, Vec<f32>)>,
+    ) -> Result<()>;
+
+    /// Drop a source and all of its chunks.
+    async fn remove(&self, source: &str) -> Result<()>;
+
+    /// All ingested sources.
+    async fn sources(&self) -> Result<Vec<String>>;
+
+    /// The `limit` chunks closest to `embedding`, best first.
+    async fn search(&self, embedding: &[f32], limit: usize) -> Result<Vec<ScoredChunk>>;
+}
+
+/// Outcome of one ingestion pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IngestReport {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub chunks: usize,
+}
+
+pub struct KnowledgeBase {
+    store: Arc<dyn VectorStore>,
+    embedder: Arc<dyn EmbeddingProvider>,
+    roots: Vec<PathBuf>,
+    workspace_dir: PathBuf,
+    chunk_tokens: usize,
+    top_k: usize,
+    min_score: f32,
+}
+
+impl KnowledgeBase {
+    pub fn new(
+        config: &KnowledgeConfig,
+        store: Arc<dyn VectorStore>,
+        embedder: Arc<dyn EmbeddingProvider>,
+        workspace_dir: &Path,
+    ) -> Self {
+        let roots = config
+            .paths
+            .iter()
+            .map(|p| {
+                let expanded = PathBuf::from(shellexpand::tilde(p).as_ref());
+                if expanded.is_absolute() {
+                    expanded
+                } else {
+                    workspace_dir.join(expanded)
+                }
+            })
+            .collect();
+        #[allow(clippy::cast_possible_truncation)]
+        let min_score = config.min_score as f32;
+        Self {
+            store,
+            embedder,
+            roots,
+            workspace_dir: workspace_dir.to_path_buf(),
+            chunk_tokens: config.chunk_tokens.max(64),
+            top_k: config.top_k,
+            min_score,
+        }
+    }
+
+    pub fn store_name(&self) -> &str {
+        self.store.name()
+    }
+
+    /// Bring the store in line with the files on disk.
+    pub async fn ingest(&self) -> Result<IngestReport> {
+        let mut files = Vec::new();
+        for root in &self.roots {
+            if root.is_file() {
+                files.push(root.clone());
+            } else {
+                collect_documents(root, &mut files);
+            }
+        }
+
+        let mut report = IngestReport::default();
+        let mut seen = BTreeSet::new();
+        for path in files {
+            let source = path
+                .strip_prefix(&self.workspace_dir)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            let Some(text) = read_document(&path) else {
+                continue;
+            };
+            seen.insert(source.clone());
+
+            let fingerprint = content_hash(&text);
+            if self.store.fingerprint(&source).await?.as_deref() == Some(fingerprint.as_str()) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let chunks = chunker::chunk_markdown(&text, self.chunk_tokens);
+            let mut embedded = Vec::with_capacity(chunks.len());
+            for batch in chunks.chunks(EMBED_BATCH_SIZE) {
+                let texts: Vec<&str> = batch.iter().map(|c| c.content.as_str()).collect();
+                let vectors = self
+                    .embedder
+                    .embed(&texts)
+                    .await
+                    .with_context(|| format!("Failed to embed {source}"))?;
+                if vectors.len() != batch.len() {
+                    bail!(
+                        "Embedding provider returned {} vectors for {} chunks of {source}",
+                        vectors.len(),
+                        batch.len()
+                    );
+                }
+                for (chunk, vector) in batch.iter().zip(vectors) {
+                    embedded.push((
+                        KnowledgeChunk {
+                            source: source.clone(),
+                            heading: chunk.heading.clone(),
+                            content: chunk.content.clone(),
+                        },
+                        vector,
+                    ));
+                }
+            }
+            report.chunks += embedded.len();
+            self.store.replace(&source, &fingerprint, embedded).await?;
+            report.indexed += 1;
+        }
+
+        for source in self.store.sources().await? {
+            if !seen.contains(&source) {
+                self.store.remove(&source).await?;
+                report.removed += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Chunks relevant to `query`, best first, above the score threshold.
+    pub async fn retrieve(&self, query: &str) -> Result<Vec<ScoredChunk>> {
+        if self.top_k == 0 || query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let embedding = self.embedder.embed_one(query).await?;
+        let mut results = self.store.search(&embedding, self.top_k).await?;
+        results.retain(|r| r.score >= self.min_score);
+        Ok(results)
+    }
+}
+
+/// Numbered source block prepended to the user's message.
+pub fn context_block(results: &[ScoredChunk]) -> String {
+    if results.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("[Knowledge base — cite the sources you use as [1], [2], ...]\n");
+    for (i, result) in results.iter().enumerate() {
+        let _ = writeln!(
+            block,
+            "[{}] {}\n{}",
+            i + 1,
+            source_label(&result.chunk),
+            result.chunk.content.trim()
+        );
+    }
+    block.push('\n');
+    block
+}
+
+/// Append a sources footer for every `[n]` the reply cites.
+pub fn append_citations(reply: &str, results: &[ScoredChunk]) -> String {
+    let cited: Vec<String> = results
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| reply.contains(&format!("[{}]", i + 1)))
+        .map(|(i, r)| format!("[{}] {}", i + 1, source_label(&r.chunk)))
+        .collect();
+    if cited.is_empty() {
+        return reply.to_string();
+    }
+    format!("{reply}\n\nSources:\n{}", cited.join("\n"))
+}
+
+fn source_label(chunk: &KnowledgeChunk) -> String {
+    match &chunk.heading {
+        Some(heading) => format!(
+            "{} § {}",
+            chunk.source,
+            heading.trim_start_matches('#').trim()
+        ),
+        None => chunk.source.clone(),
+    }
+}
+
+fn content_hash(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+fn is_document(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("md" | "markdown" | "txt") => true,
+        Some("pdf") => cfg!(feature = "rag-pdf"),
+        _ => false,
+    }
+}
+
+fn collect_documents(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        tracing::warn!("Knowledge path {} is not readable", dir.display());
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_documents(&path, out);
+        } else if path.is_file() && is_document(&path) {
+            out.push(path);
+        }
+    }
+}
+
+/// Text of a document, or `None` when it is empty or unreadable.
+fn read_document(path: &Path) -> Option<String> {
+    let text = if path.extension().and_then(|e| e.to_str()) == Some("pdf") {
+        #[cfg(feature = "rag-pdf")]
+        {
+            let bytes = std::fs::read(path).ok()?;
+            pdf_extract::extract_text_from_mem(&bytes).ok()?
+        }
+        #[cfg(not(feature = "rag-pdf"))]
+        {
+            return None;
+        }
+    } else {
+        std::fs::read_to_string(path).ok()?
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
+
+// ── Factory ──────────────────────────────────────────────────────
+
+/// Build the knowledge base from config. Returns `None` when disabled.
+/// `qdrant_api_key` is the resolved `[knowledge] qdrant_api_key`.
+pub fn create_knowledge_base(
+    config: &KnowledgeConfig,
+    embedder: Arc<dyn EmbeddingProvider>,
+    workspace_dir: &Path,
+    qdrant_api_key: Option<Arc<crate::security::ManagedSecret>>,
+) -> Result<Option<KnowledgeBase>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    if embedder.dimensions() == 0 {
+        bail!("[knowledge] needs an embedding provider; set [memory] embedding_provider");
+    }
+    let store: Arc<dyn VectorStore> = match config.store.as_str() {
+        "sqlite" => Arc::new(SqliteVectorStore::open(workspace_dir)?),
+        "qdrant" => Arc::new(QdrantVectorStore::new(
+            &config.qdrant_url,
+            &config.qdrant_collection,
+            embedder.dimensions(),
+            qdrant_api_key,
+        )),
+        other => bail!("Unknown knowledge store: {other}"),
+    };
+    Ok(Some(KnowledgeBase::new(
+        config,
+        store,
+        embedder,
+        workspace_dir,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Two-dimensional embedding: how much a text talks about cats vs. dogs.
+    struct PetEmbedding;
+
+    #[async_trait]
+    impl EmbeddingProvider for PetEmbedding {
+        fn name(&self) -> &str {
+            "pets"
+        }
+
+        fn dimensions(&self) -> usize {
+            2
+        }
+
+        async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|t| {
+                    let t = t.to_lowercase();
+                    vec![
+                        t.matches("cat").count() as f32,
+                        t.matches("dog").count() as f32,
+                    ]
+                })
+                .collect())
+        }
+    }
+
+    fn knowledge_base(tmp: &TempDir) -> KnowledgeBase {
+        let config = KnowledgeConfig {
+            enabled: true,
+            paths: vec!["docs".into()],
+            min_score: 0.5,
+            ..KnowledgeConfig::default()
+        };
+        create_knowledge_base(&config, Arc::new(PetEmbedding), tmp.path(), None)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn ingest_is_incremental_and_retrieval_cites_sources() {
+        let tmp = TempDir::new().unwrap();
+        let docs = tmp.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join("cats.md"), "# Cats\nA cat naps. Cats purr.").unwrap();
+        std::fs::write(docs.join("dogs.txt"), "A dog barks at another dog.").unwrap();
+        std::fs::write(docs.join("image.png"), "not a document").unwrap();
+
+        let kb = knowledge_base(&tmp);
+        let report = kb.ingest().await.unwrap();
+        assert_eq!((report.indexed, report.unchanged), (2, 0));
+        assert_eq!(kb.ingest().await.unwrap().unchanged, 2);
+
+        let results = kb.retrieve("tell me about my cat").await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.source, "docs/cats.md");
+
+        let block = context_block(&results);
+        assert!(block.contains("[1] docs/cats.md § Cats"));
+        let reply = append_citations("Cats purr [1].", &results);
+        assert!(reply.ends_with("Sources:\n[1] docs/cats.md § Cats"));
+        assert_eq!(append_citations("No idea.", &results), "No idea.");
+
+        std::fs::remove_file(docs.join("dogs.txt")).unwrap();
+        assert_eq!(kb.ingest().await.unwrap().removed, 1);
+    }
+
+    #[test]
+    fn factory_requires_embeddings_and_known_store() {
+        let tmp = TempDir::new().unwrap();
+        let mut config = KnowledgeConfig::default();
+        let noop = Arc::new(crate::memory::embeddings::NoopEmbedding);
+        assert!(
+            create_knowledge_base(&config, noop.clone(), tmp.path(), None)
+                .unwrap()
+                .is_none()
+        );
+
+        config.enabled = true;
+        assert!(create_knowledge_base(&config, noop, tmp.path(), None).is_err());
+        config.store = "nope".into();
+        assert!(create_knowledge_base(&config, Arc::new(PetEmbedding), tmp.path(), None).is_err());
+    }
+}
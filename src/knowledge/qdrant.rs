@@ -0,0 +1,222 @@
+use super::{KnowledgeChunk, ScoredChunk, VectorStore};
+use crate::security::ManagedSecret;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+/// Chunks stored as points in a Qdrant collection over its REST API. The
+/// collection is created on first use; fingerprints live in point payloads.
+pub struct QdrantVectorStore {
+    client: reqwest::Client,
+    base_url: String,
+    collection: String,
+    dimensions: usize,
+    api_key: Option<Arc<ManagedSecret>>,
+    ready: OnceCell<()>,
+}
+
+impl QdrantVectorStore {
+    pub fn new(
+        base_url: &str,
+        collection: &str,
+        dimensions: usize,
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            dimensions,
+            api_key,
+            ready: OnceCell::new(),
+        }
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        let url = format!("{}/collections/{}{path}", self.base_url, self.collection);
+        let mut req = self.client.request(method, &url);
+        if let Some(key) = &self.api_key {
+            req = req.header("api-key", key.get().await?);
+        }
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let resp = req
+            .send()
+            .await
+            .with_context(|| format!("Qdrant request to {url} failed"))?;
+        let status = resp.status();
+        let payload: Value = resp.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            bail!("Qdrant returned {status}: {payload}");
+        }
+        Ok(payload)
+    }
+
+    async fn ensure_collection(&self) -> Result<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                let url = format!("{}/collections/{}", self.base_url, self.collection);
+                let mut req = self.client.get(&url);
+                if let Some(key) = &self.api_key {
+                    req = req.header("api-key", key.get().await?);
+                }
+                if req.send().await?.status().is_success() {
+                    return Ok(());
+                }
+                self.request(
+                    reqwest::Method::PUT,
+                    "",
+                    Some(json!({
+                        "vectors": { "size": self.dimensions, "distance": "Cosine" }
+                    })),
+                )
+                .await
+                .map(|_| ())
+            })
+            .await
+            .copied()
+    }
+
+    fn source_filter(source: &str) -> Value {
+        json!({ "must": [{ "key": "source", "match": { "value": source } }] })
+    }
+
+    /// Scroll through every point, returning their payloads.
+    async fn scroll_payloads(&self) -> Result<Vec<Value>> {
+        let mut payloads = Vec::new();
+        let mut offset = Value::Null;
+        loop {
+            let mut body = json!({ "limit": 256, "with_payload": true, "with_vector": false });
+            if !offset.is_null() {
+                body["offset"] = offset.clone();
+            }
+            let resp = self
+                .request(reqwest::Method::POST, "/points/scroll", Some(body))
+                .await?;
+            if let Some(points) = resp["result"]["points"].as_array() {
+                payloads.extend(points.iter().map(|p| p["payload"].clone()));
+            }
+            offset = resp["result"]["next_page_offset"].clone();
+            if offset.is_null() {
+                return Ok(payloads);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl VectorStore for QdrantVectorStore {
+    fn name(&self) -> &str {
+        "qdrant"
+    }
+
+    async fn fingerprint(&self, source: &str) -> Result<Option<String>> {
+        self.ensure_collection().await?;
+        let resp = self
+            .request(
+                reqwest::Method::POST,
+                "/points/scroll",
+                Some(json!({
+                    "filter": Self::source_filter(source),
+                    "limit": 1,
+                    "with_payload": ["fingerprint"],
+                    "with_vector": false
+                })),
+            )
+            .await?;
+        Ok(resp["result"]["points"][0]["payload"]["fingerprint"]
+            .as_str()
+            .map(str::to_string))
+    }
+
+    async fn replace(
+        &self,
+        source: &str,
+        fingerprint: &str,
+        chunks: Vec<(KnowledgeChunk, Vec<f32>)>,
+    ) -> Result<()> {
+        self.remove(source).await?;
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<Value> = chunks
+            .into_iter()
+            .map(|(chunk, embedding)| {
+                json!({
+                    "id": uuid::Uuid::new_v4().to_string(),
+                    "vector": embedding,
+                    "payload": {
+                        "source": chunk.source,
+                        "heading": chunk.heading,
+                        "content": chunk.content,
+                        "fingerprint": fingerprint,
+                    }
+                })
+            })
+            .collect();
+        self.request(
+            reqwest::Method::PUT,
+            "/points?wait=true",
+            Some(json!({ "points": points })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, source: &str) -> Result<()> {
+        self.ensure_collection().await?;
+        self.request(
+            reqwest::Method::POST,
+            "/points/delete?wait=true",
+            Some(json!({ "filter": Self::source_filter(source) })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn sources(&self) -> Result<Vec<String>> {
+        self.ensure_collection().await?;
+        let mut sources: Vec<String> = self
+            .scroll_payloads()
+            .await?
+            .iter()
+            .filter_map(|p| p["source"].as_str().map(str::to_string))
+            .collect();
+        sources.sort();
+        sources.dedup();
+        Ok(sources)
+    }
+
+    async fn search(&self, embedding: &[f32], limit: usize) -> Result<Vec<ScoredChunk>> {
+        self.ensure_collection().await?;
+        let resp = self
+            .request(
+                reqwest::Method::POST,
+                "/points/search",
+                Some(json!({ "vector": embedding, "limit": limit, "with_payload": true })),
+            )
+            .await?;
+        let hits = resp["result"].as_array().cloned().unwrap_or_default();
+        #[allow(clippy::cast_possible_truncation)]
+        let results = hits
+            .iter()
+            .map(|hit| ScoredChunk {
+                chunk: KnowledgeChunk {
+                    source: hit["payload"]["source"].as_str().unwrap_or("").to_string(),
+                    heading: hit["payload"]["heading"].as_str().map(str::to_string),
+                    content: hit["payload"]["content"].as_str().unwrap_or("").to_string(),
+                },
+                score: hit["score"].as_f64().unwrap_or(0.0) as f32,
+            })
+            .collect();
+        Ok(results)
+    }
+}
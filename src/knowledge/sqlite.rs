@@ -0,0 +1,131 @@
+use super::{KnowledgeChunk, ScoredChunk, VectorStore};
+use crate::memory::vector;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Chunks and embeddings in `<workspace>/knowledge/knowledge.db`, searched
+/// by brute-force cosine similarity. Fine for team-sized document sets;
+/// use the Qdrant store for larger corpora.
+pub struct SqliteVectorStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteVectorStore {
+    pub fn open(workspace_dir: &Path) -> Result<Self> {
+        let dir = workspace_dir.join("knowledge");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create knowledge directory: {}", dir.display()))?;
+        let db_path = dir.join("knowledge.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open knowledge DB: {}", db_path.display()))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS sources (
+                source      TEXT PRIMARY KEY,
+                fingerprint TEXT NOT NULL,
+                indexed_at  TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS chunks (
+                source    TEXT NOT NULL,
+                idx       INTEGER NOT NULL,
+                heading   TEXT,
+                content   TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                PRIMARY KEY (source, idx)
+             );",
+        )
+        .context("Failed to initialize knowledge schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+#[async_trait]
+impl VectorStore for SqliteVectorStore {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn fingerprint(&self, source: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        Ok(conn
+            .query_row(
+                "SELECT fingerprint FROM sources WHERE source = ?1",
+                params![source],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    async fn replace(
+        &self,
+        source: &str,
+        fingerprint: &str,
+        chunks: Vec<(KnowledgeChunk, Vec<f32>)>,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM chunks WHERE source = ?1", params![source])?;
+        for (idx, (chunk, embedding)) in chunks.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO chunks (source, idx, heading, content, embedding)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    source,
+                    i64::try_from(idx)?,
+                    chunk.heading,
+                    chunk.content,
+                    vector::vec_to_bytes(embedding)
+                ],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO sources (source, fingerprint, indexed_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(source) DO UPDATE SET fingerprint = ?2, indexed_at = ?3",
+            params![source, fingerprint, chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn remove(&self, source: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute("DELETE FROM chunks WHERE source = ?1", params![source])?;
+        conn.execute("DELETE FROM sources WHERE source = ?1", params![source])?;
+        Ok(())
+    }
+
+    async fn sources(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("SELECT source FROM sources ORDER BY source")?;
+        let sources = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(sources)
+    }
+
+    async fn search(&self, embedding: &[f32], limit: usize) -> Result<Vec<ScoredChunk>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("SELECT source, heading, content, embedding FROM chunks")?;
+        let mut scored = stmt
+            .query_map([], |row| {
+                let blob: Vec<u8> = row.get(3)?;
+                Ok(ScoredChunk {
+                    chunk: KnowledgeChunk {
+                        source: row.get(0)?,
+                        heading: row.get(1)?,
+                        content: row.get(2)?,
+                    },
+                    score: vector::cosine_similarity(embedding, &vector::bytes_to_vec(&blob)),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+}
@@ -0,0 +1,302 @@
+//! Namespaced key-value store for small bits of handler and plugin state.
+//!
+//! Values are strings (JSON by convention) stored under a namespace and key,
+//! optionally with a time to live. Expired entries are invisible to reads and
+//! are deleted when the store opens or [`KvStore::purge_expired`] runs.
+//! Plugins get a [`KvNamespace`] of their own (`lua:<script>`,
+//! `wasm:<plugin>`), so they cannot read or clobber each other's state.
+//!
+//! The store lives in `<workspace>/state/kv.db`; [`install`] makes it the
+//! process-wide instance returned by [`global`].
+
+use crate::config::KvConfig;
+use anyhow::{bail, Context, Result};
+use parking_lot::{Mutex, RwLock};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest key, in bytes.
+pub const MAX_KEY_LEN: usize = 256;
+
+pub struct KvStore {
+    conn: Mutex<Connection>,
+    max_value_bytes: usize,
+}
+
+impl KvStore {
+    /// Open the store configured by `[kv]`, or `None` when it is disabled.
+    pub fn from_config(config: &KvConfig, workspace_dir: &Path) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        Self::open(workspace_dir, config.max_value_bytes).map(Some)
+    }
+
+    /// Open (or create) the store under `<workspace>/state/`.
+    pub fn open(workspace_dir: &Path, max_value_bytes: usize) -> Result<Self> {
+        let dir = workspace_dir.join("state");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create state directory: {}", dir.display()))?;
+        let db_path = dir.join("kv.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open KV DB: {}", db_path.display()))?;
+        Self::with_connection(conn, max_value_bytes)
+    }
+
+    /// In-memory store for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Result<Self> {
+        Self::with_connection(
+            Connection::open_in_memory()?,
+            KvConfig::default().max_value_bytes,
+        )
+    }
+
+    fn with_connection(conn: Connection, max_value_bytes: usize) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS kv (
+                namespace  TEXT NOT NULL,
+                key        TEXT NOT NULL,
+                value      TEXT NOT NULL,
+                expires_at INTEGER,
+                PRIMARY KEY (namespace, key)
+             );
+             CREATE INDEX IF NOT EXISTS idx_kv_expires ON kv(expires_at)
+                WHERE expires_at IS NOT NULL;",
+        )
+        .context("Failed to initialize KV schema")?;
+        let store = Self {
+            conn: Mutex::new(conn),
+            max_value_bytes,
+        };
+        store.purge_expired()?;
+        Ok(store)
+    }
+
+    /// A handle confined to `namespace`.
+    pub fn namespace(self: &Arc<Self>, namespace: impl Into<String>) -> KvNamespace {
+        KvNamespace {
+            store: Arc::clone(self),
+            namespace: namespace.into(),
+        }
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT value FROM kv
+             WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+            params![namespace, key, unix_now()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(Into::into)
+    }
+
+    /// Store `value`, replacing any previous one. Without a `ttl` the entry
+    /// never expires.
+    pub fn set(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        validate_key(key)?;
+        if value.len() > self.max_value_bytes {
+            bail!(
+                "value of {} bytes exceeds the {}-byte limit",
+                value.len(),
+                self.max_value_bytes
+            );
+        }
+        let expires_at = ttl
+            .map(|ttl| unix_now().saturating_add(i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX)));
+        self.conn.lock().execute(
+            "INSERT INTO kv (namespace, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (namespace, key)
+             DO UPDATE SET value = excluded.value, expires_at = excluded.expires_at",
+            params![namespace, key, value, expires_at],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a key. Returns `false` if it did not exist (or had expired).
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        let deleted = self.conn.lock().execute(
+            "DELETE FROM kv
+             WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+            params![namespace, key, unix_now()],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Add `by` to the integer under `key` (missing counts as 0) and return
+    /// the new value. An existing expiry is kept.
+    pub fn incr(&self, namespace: &str, key: &str, by: i64) -> Result<i64> {
+        validate_key(key)?;
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let current: Option<(String, Option<i64>)> = tx
+            .query_row(
+                "SELECT value, expires_at FROM kv
+                 WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+                params![namespace, key, unix_now()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let (value, expires_at) = match current {
+            Some((value, expires_at)) => {
+                let value: i64 = value
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("{key} does not hold an integer"))?;
+                (value, expires_at)
+            }
+            None => (0, None),
+        };
+        let value = value
+            .checked_add(by)
+            .with_context(|| format!("{key} would overflow"))?;
+        tx.execute(
+            "INSERT INTO kv (namespace, key, value, expires_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (namespace, key)
+             DO UPDATE SET value = excluded.value, expires_at = excluded.expires_at",
+            params![namespace, key, value.to_string(), expires_at],
+        )?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    /// Live keys of `namespace` starting with `prefix`, in order.
+    pub fn keys(&self, namespace: &str, prefix: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT key FROM kv
+             WHERE namespace = ?1 AND substr(key, 1, length(?2)) = ?2
+               AND (expires_at IS NULL OR expires_at > ?3)
+             ORDER BY key",
+        )?;
+        let rows = stmt.query_map(params![namespace, prefix, unix_now()], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    /// Delete expired entries. Returns how many were removed.
+    pub fn purge_expired(&self) -> Result<usize> {
+        let purged = self.conn.lock().execute(
+            "DELETE FROM kv WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+            params![unix_now()],
+        )?;
+        Ok(purged)
+    }
+}
+
+/// [`KvStore`] operations confined to one namespace.
+#[derive(Clone)]
+pub struct KvNamespace {
+    store: Arc<KvStore>,
+    namespace: String,
+}
+
+impl KvNamespace {
+    pub fn name(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        self.store.get(&self.namespace, key)
+    }
+
+    pub fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()> {
+        self.store.set(&self.namespace, key, value, ttl)
+    }
+
+    pub fn delete(&self, key: &str) -> Result<bool> {
+        self.store.delete(&self.namespace, key)
+    }
+
+    pub fn incr(&self, key: &str, by: i64) -> Result<i64> {
+        self.store.incr(&self.namespace, key, by)
+    }
+
+    pub fn keys(&self, prefix: &str) -> Result<Vec<String>> {
+        self.store.keys(&self.namespace, prefix)
+    }
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        bail!("keys must be 1 to {MAX_KEY_LEN} bytes long");
+    }
+    Ok(())
+}
+
+fn unix_now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+static STORE: RwLock<Option<Arc<KvStore>>> = RwLock::new(None);
+
+/// Make `store` the process-wide KV store (`None` disables it).
+pub fn install(store: Option<KvStore>) {
+    *STORE.write() = store.map(Arc::new);
+}
+
+pub fn global() -> Option<Arc<KvStore>> {
+    STORE.read().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_are_isolated() {
+        let store = Arc::new(KvStore::in_memory().unwrap());
+        let a = store.namespace("lua:a");
+        let b = store.namespace("lua:b");
+        a.set("greeting", "hello", None).unwrap();
+        b.set("greeting", "hi", None).unwrap();
+        a.set("other", "x", None).unwrap();
+
+        assert_eq!(a.get("greeting").unwrap().as_deref(), Some("hello"));
+        assert_eq!(b.get("greeting").unwrap().as_deref(), Some("hi"));
+        assert_eq!(a.keys("gr").unwrap(), vec!["greeting".to_string()]);
+        assert!(a.delete("greeting").unwrap());
+        assert!(!a.delete("greeting").unwrap());
+        assert_eq!(b.get("greeting").unwrap().as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn expired_entries_are_invisible_and_purged() {
+        let store = Arc::new(KvStore::in_memory().unwrap());
+        let ns = store.namespace("test");
+        ns.set("gone", "1", Some(Duration::ZERO)).unwrap();
+        ns.set("kept", "1", Some(Duration::from_secs(3600)))
+            .unwrap();
+
+        assert_eq!(ns.get("gone").unwrap(), None);
+        assert_eq!(ns.keys("").unwrap(), vec!["kept".to_string()]);
+        assert_eq!(store.purge_expired().unwrap(), 1);
+        // An expired counter starts over
+        assert_eq!(ns.incr("gone", 5).unwrap(), 5);
+    }
+
+    #[test]
+    fn counters_and_limits() {
+        let store = Arc::new(KvStore::in_memory().unwrap());
+        let ns = store.namespace("test");
+        assert_eq!(ns.incr("hits", 1).unwrap(), 1);
+        assert_eq!(ns.incr("hits", 2).unwrap(), 3);
+        ns.set("name", "alice", None).unwrap();
+        assert!(ns.incr("name", 1).is_err());
+
+        assert!(ns.set("", "x", None).is_err());
+        let too_big = "x".repeat(KvConfig::default().max_value_bytes + 1);
+        assert!(ns.set("big", &too_big, None).is_err());
+    }
+}
@@ -39,32 +39,55 @@ use clap::Subcommand;
 use serde::{Deserialize, Serialize};
 
 pub mod agent;
+pub mod analytics;
 pub mod channels;
+pub mod circuit;
+pub mod cluster;
 pub mod config;
 pub mod cost;
 pub mod cron;
 pub mod daemon;
 pub mod doctor;
+pub mod feeds;
 pub mod gateway;
+pub mod groups;
 pub mod hardware;
 pub mod health;
 pub mod heartbeat;
 pub mod identity;
+pub mod imagegen;
+pub mod ingress;
 pub mod integrations;
+pub mod knowledge;
+pub mod kv;
+pub mod logging;
+pub mod media_store;
 pub mod memory;
 pub mod migration;
+pub mod monitor;
+pub mod oauth;
 pub mod observability;
 pub mod onboard;
+pub mod ops;
 pub mod peripherals;
+pub mod personas;
+pub mod plugins;
+pub mod preferences;
 pub mod providers;
 pub mod rag;
+pub mod reminders;
 pub mod runtime;
 pub mod security;
 pub mod service;
 pub mod skills;
+pub mod state;
 pub mod tools;
+pub mod transcription;
+pub mod tts;
 pub mod tunnel;
 pub mod util;
+pub mod vision;
+pub mod websearch;
 
 pub use config::Config;
 
@@ -89,7 +112,14 @@ pub enum ChannelCommands {
     /// List all configured channels
     List,
     /// Start all configured channels (handled in main.rs for async)
-    Start,
+    Start {
+        /// Use stdin/stdout as the only channel: JSON lines in, JSON lines out
+        #[arg(long)]
+        pipe: bool,
+        /// Log replies instead of sending them (see `[channels_config.dry_run]`)
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Run health checks for configured channels (handled in main.rs for async)
     Doctor,
     /// Add a new channel configuration
@@ -141,6 +171,26 @@ pub enum MigrateCommands {
         #[arg(long)]
         dry_run: bool,
     },
+    /// Apply pending schema migrations to the Postgres memory store
+    Schema {
+        /// List pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Copy every memory from one backend to another (e.g. sqlite to postgres)
+    Copy {
+        /// Backend to read from (sqlite, lucid, markdown, postgres)
+        #[arg(long)]
+        from: String,
+
+        /// Backend to write to (sqlite, lucid, markdown, postgres)
+        #[arg(long)]
+        to: String,
+
+        /// Count what would be copied without writing any data
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 /// Cron subcommands
@@ -251,3 +301,42 @@ pub enum PeripheralCommands {
     /// Flash ZeroClaw firmware to Nucleo-F401RE (builds + probe-rs run)
     FlashNucleo,
 }
+
+/// State snapshot subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum StateCommands {
+    /// Write schedules, preferences, identity links, ACLs and memory to a snapshot
+    Export {
+        /// Snapshot file to write (e.g. state.tar.zst)
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Restore a snapshot, keeping replaced files as <name>.bak
+    Import {
+        /// Snapshot file written by `zeroclaw state export`
+        archive: std::path::PathBuf,
+        /// List what would be restored without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// OAuth sign-in subcommands
+#[derive(Subcommand, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OAuthCommands {
+    /// Sign in to a configured [oauth.<name>] client
+    Login {
+        /// Client name from [oauth.<name>]
+        name: String,
+        /// Sign in with a code on another device instead of a local browser redirect
+        #[arg(long)]
+        device: bool,
+    },
+    /// Show which clients are signed in and when their tokens expire
+    Status,
+    /// Forget the stored tokens of a client
+    Logout {
+        /// Client name from [oauth.<name>]
+        name: String,
+    },
+}
@@ -0,0 +1,155 @@
+//! Process-wide `tracing` setup from `[logging]`.
+//!
+//! Chooses text or JSON output, builds the level filter from the default
+//! level plus per-module overrides (unless `RUST_LOG` is set), masks
+//! secrets in every formatted line (see [`crate::security::redact`]), and
+//! writes to stderr or a size-rotated file. The filter stays reloadable so
+//! levels can be raised at runtime (see [`overrides`]).
+
+pub mod overrides;
+mod rotate;
+
+pub use rotate::RotatingFile;
+
+use crate::config::LoggingConfig;
+use crate::security::redact::Redactor;
+use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
+use std::io::{self, Write};
+use std::sync::Arc;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+#[derive(Clone)]
+enum Sink {
+    Stderr,
+    File(Arc<Mutex<RotatingFile>>),
+}
+
+/// `MakeWriter` handing out one redacting writer per event.
+#[derive(Clone)]
+struct LogWriter {
+    sink: Sink,
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl Write for LogWriter {
+    // The fmt layer writes each formatted event with a single `write_all`,
+    // so `buf` is a whole line and can be redacted as one.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let redacted;
+        let bytes = match &self.redactor {
+            Some(redactor) => {
+                redacted = redactor.redact(&String::from_utf8_lossy(buf));
+                redacted.as_bytes()
+            }
+            None => buf,
+        };
+        match &self.sink {
+            Sink::Stderr => io::stderr().write_all(bytes)?,
+            Sink::File(file) => file.lock().write_all(bytes)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &self.sink {
+            Sink::Stderr => io::stderr().flush(),
+            Sink::File(file) => file.lock().flush(),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogWriter {
+    type Writer = LogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Filter directives for `config`: the default level followed by each
+/// module override, sorted for a stable result.
+pub fn filter_directives(config: &LoggingConfig) -> String {
+    let mut modules: Vec<_> = config.modules.iter().collect();
+    modules.sort();
+    let mut directives = vec![config.level.clone()];
+    directives.extend(modules.into_iter().map(|(m, l)| format!("{m}={l}")));
+    directives.join(",")
+}
+
+/// Install the global subscriber described by `config`.
+pub fn init(config: &LoggingConfig) -> Result<()> {
+    let base = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directives| EnvFilter::try_new(directives).is_ok())
+        .unwrap_or_else(|| filter_directives(config));
+    let filter = EnvFilter::try_new(&base).context("Invalid [logging] level or modules")?;
+    let redactor = if config.redact {
+        Some(Arc::new(Redactor::new(&config.redact_patterns)?))
+    } else {
+        None
+    };
+    let sink = match &config.file {
+        Some(path) => Sink::File(Arc::new(Mutex::new(
+            RotatingFile::open(
+                path,
+                config.max_file_mb.saturating_mul(1024 * 1024),
+                config.max_files,
+            )
+            .with_context(|| format!("Failed to open log file {}", path.display()))?,
+        ))),
+        None => Sink::Stderr,
+    };
+    let ansi = matches!(sink, Sink::Stderr) && config.format == "text";
+    let (filter, handle) = reload::Layer::new(filter);
+    let fmt = tracing_subscriber::fmt::layer()
+        .with_ansi(ansi)
+        .with_writer(LogWriter { sink, redactor });
+
+    let fmt = match config.format.as_str() {
+        "json" => fmt.json().with_current_span(false).boxed(),
+        "text" => fmt.boxed(),
+        other => bail!("Unknown logging.format '{other}' (expected text or json)"),
+    };
+    let subscriber = Registry::default().with(filter).with(fmt);
+    tracing::subscriber::set_global_default(subscriber)
+        .context("Failed to install the global log subscriber")?;
+    overrides::install(base, handle);
+    Ok(())
+}
+
+/// Default stderr logging for the current thread, used before the config
+/// is loaded.
+pub fn bootstrap() -> tracing::subscriber::DefaultGuard {
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .finish();
+    tracing::subscriber::set_default(subscriber)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn directives_combine_level_and_modules() {
+        let config = LoggingConfig {
+            level: "warn".into(),
+            modules: HashMap::from([
+                ("zeroclaw::channels::qq".into(), "debug".into()),
+                ("hyper".into(), "error".into()),
+            ]),
+            ..LoggingConfig::default()
+        };
+        assert_eq!(
+            filter_directives(&config),
+            "warn,hyper=error,zeroclaw::channels::qq=debug"
+        );
+        assert!(EnvFilter::try_new(filter_directives(&config)).is_ok());
+    }
+}
@@ -0,0 +1,235 @@
+//! Log levels changed at runtime (`/debug`, `/admin/logging`).
+//!
+//! An override is one `target=level` filter directive laid over the ones
+//! the subscriber started with (`[logging]` or `RUST_LOG`) for a limited
+//! time, so an operator can turn on e.g. raw gateway frames of one channel
+//! for ten minutes without a restart. Expired overrides drop out on their
+//! own; nothing is written back to config.toml.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Level an override sets when not told: everything, raw gateway frames
+/// included.
+pub const DEFAULT_OVERRIDE_LEVEL: &str = "trace";
+
+/// How long an override lasts when not told.
+pub const DEFAULT_OVERRIDE_MINUTES: u64 = 10;
+
+/// Longest an override may last.
+pub const MAX_OVERRIDE_MINUTES: u64 = 24 * 60;
+
+pub(super) type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// A level in force for one target until `expires_at`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LevelOverride {
+    pub target: String,
+    pub level: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+struct Filters {
+    /// Directives the subscriber was installed with
+    base: String,
+    handle: FilterHandle,
+    overrides: Mutex<BTreeMap<String, LevelOverride>>,
+}
+
+static FILTERS: OnceLock<Filters> = OnceLock::new();
+
+/// Remember the installed filter so overrides can replace it.
+pub(super) fn install(base: String, handle: FilterHandle) {
+    let _ = FILTERS.set(Filters {
+        base,
+        handle,
+        overrides: Mutex::new(BTreeMap::new()),
+    });
+}
+
+fn filters() -> Result<&'static Filters> {
+    FILTERS
+        .get()
+        .context("Log levels can't be changed: the log subscriber is not reloadable")
+}
+
+/// The tracing target for `name`: a channel name (`qq`, `discord`) maps to
+/// its module, anything with a `::` is taken as a target already.
+pub fn log_target(name: &str) -> Result<String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+    if !valid {
+        bail!("Invalid log target '{name}'");
+    }
+    Ok(match name {
+        "zeroclaw" => name.to_string(),
+        _ if name.contains("::") => name.to_string(),
+        "email" => "zeroclaw::channels::email_channel".to_string(),
+        _ => format!("zeroclaw::channels::{name}"),
+    })
+}
+
+/// Log `target` at `level` for `duration`, replacing any earlier override
+/// of the same target.
+pub fn set(target: &str, level: &str, duration: Duration) -> Result<LevelOverride> {
+    let level = LevelFilter::from_str(level)
+        .map_err(|_| anyhow::anyhow!("Unknown log level '{level}'"))?
+        .to_string()
+        .to_ascii_lowercase();
+    let filters = filters()?;
+    let entry = LevelOverride {
+        target: target.to_string(),
+        level,
+        expires_at: Utc::now()
+            + chrono::Duration::from_std(duration).context("Override lasts too long")?,
+    };
+    {
+        let mut overrides = filters.overrides.lock();
+        overrides.insert(entry.target.clone(), entry.clone());
+        apply(filters, &overrides)?;
+    }
+    tracing::info!(
+        "Log level of {} set to {} until {}",
+        entry.target,
+        entry.level,
+        entry.expires_at.format("%H:%M:%S")
+    );
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        runtime.spawn(async move {
+            tokio::time::sleep(duration).await;
+            expire();
+        });
+    }
+    Ok(entry)
+}
+
+/// Drop the override of `target`, or all of them. Returns how many were
+/// dropped.
+pub fn clear(target: Option<&str>) -> Result<usize> {
+    let filters = filters()?;
+    let mut overrides = filters.overrides.lock();
+    let before = overrides.len();
+    match target {
+        Some(target) => {
+            overrides.remove(target);
+        }
+        None => overrides.clear(),
+    }
+    let cleared = before - overrides.len();
+    if cleared > 0 {
+        apply(filters, &overrides)?;
+    }
+    Ok(cleared)
+}
+
+/// Overrides in force, by target.
+pub fn active() -> Vec<LevelOverride> {
+    FILTERS
+        .get()
+        .map(|filters| filters.overrides.lock().values().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The directives the subscriber started with.
+pub fn base_directives() -> Option<&'static str> {
+    FILTERS.get().map(|filters| filters.base.as_str())
+}
+
+fn expire() {
+    let Some(filters) = FILTERS.get() else {
+        return;
+    };
+    let mut overrides = filters.overrides.lock();
+    let now = Utc::now();
+    let before = overrides.len();
+    overrides.retain(|_, entry| entry.expires_at > now);
+    if overrides.len() < before {
+        if let Err(e) = apply(filters, &overrides) {
+            tracing::warn!("Failed to restore log levels: {e:#}");
+        } else {
+            tracing::info!("Log level overrides expired; {} left", overrides.len());
+        }
+    }
+}
+
+fn apply(filters: &Filters, overrides: &BTreeMap<String, LevelOverride>) -> Result<()> {
+    let filter = EnvFilter::try_new(directives(&filters.base, overrides.values()))
+        .context("Invalid log level override")?;
+    filters
+        .handle
+        .reload(filter)
+        .context("Failed to reload the log filter")
+}
+
+/// `base` with a directive per override; base directives for a target that
+/// is overridden are left out so the override decides.
+fn directives<'a>(base: &str, overrides: impl Iterator<Item = &'a LevelOverride>) -> String {
+    let overrides: Vec<_> = overrides.collect();
+    let mut directives: Vec<String> = base
+        .split(',')
+        .filter(|directive| {
+            let target = directive.split('=').next().unwrap_or_default().trim();
+            !directive.trim().is_empty() && !overrides.iter().any(|o| o.target == target)
+        })
+        .map(str::to_string)
+        .collect();
+    directives.extend(
+        overrides
+            .iter()
+            .map(|o| format!("{}={}", o.target, o.level)),
+    );
+    directives.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(target: &str, level: &str) -> LevelOverride {
+        LevelOverride {
+            target: target.into(),
+            level: level.into(),
+            expires_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn overrides_replace_base_directives_of_their_target() {
+        let overrides = [
+            entry("zeroclaw::channels::qq", "trace"),
+            entry("hyper", "debug"),
+        ];
+        let combined = directives(
+            "info,zeroclaw::channels::qq=warn,zeroclaw::channels::qqbot=error",
+            overrides.iter(),
+        );
+        assert_eq!(
+            combined,
+            "info,zeroclaw::channels::qqbot=error,zeroclaw::channels::qq=trace,hyper=debug"
+        );
+        assert!(EnvFilter::try_new(combined).is_ok());
+    }
+
+    #[test]
+    fn channel_names_map_to_their_module() {
+        assert_eq!(log_target("qq").unwrap(), "zeroclaw::channels::qq");
+        assert_eq!(
+            log_target("email").unwrap(),
+            "zeroclaw::channels::email_channel"
+        );
+        assert_eq!(log_target("hyper::proto").unwrap(), "hyper::proto");
+        assert_eq!(log_target("zeroclaw").unwrap(), "zeroclaw");
+        assert!(log_target("qq=trace,info").is_err());
+        assert!(log_target("").is_err());
+    }
+}
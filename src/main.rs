@@ -35,42 +35,67 @@
 use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 use tracing::info;
-use tracing_subscriber::{fmt, EnvFilter};
 
 mod agent;
+mod analytics;
 mod channels;
+mod circuit;
+mod cluster;
 mod rag {
     pub use zeroclaw::rag::*;
 }
 mod config;
+// Only the quota ledger is used by the binary; the tracker API is library-facing.
+#[allow(unused_imports)]
+mod cost;
 mod cron;
 mod daemon;
 mod doctor;
+mod feeds;
 mod gateway;
+mod groups;
 mod hardware;
 mod health;
 mod heartbeat;
 mod identity;
+mod imagegen;
+mod ingress;
 mod integrations;
+mod knowledge;
+mod kv;
+mod logging;
+mod media_store;
 mod memory;
 mod migration;
+mod monitor;
+mod oauth;
 mod observability;
 mod onboard;
+mod ops;
 mod peripherals;
+mod personas;
+mod plugins;
+mod preferences;
 mod providers;
+mod reminders;
 mod runtime;
 mod security;
 mod service;
 mod skillforge;
 mod skills;
+mod state;
 mod tools;
+mod transcription;
+mod tts;
 mod tunnel;
 mod util;
+mod vision;
+mod websearch;
 
-use config::Config;
+use config::{Config, LoggingConfig};
 
 // Re-export so binary's hardware/peripherals modules can use crate::HardwareCommands etc.
-pub use zeroclaw::{HardwareCommands, PeripheralCommands};
+pub use zeroclaw::{HardwareCommands, OAuthCommands, PeripheralCommands, StateCommands};
 
 /// `ZeroClaw` - Zero overhead. Zero compromise. 100% Rust.
 #[derive(Parser, Debug)]
@@ -165,6 +190,10 @@ enum Commands {
         /// Host to bind to; defaults to config gateway.host
         #[arg(long)]
         host: Option<String>,
+
+        /// Report readiness and gateway liveness to systemd (Type=notify, WatchdogSec=)
+        #[arg(long)]
+        systemd: bool,
     },
 
     /// Manage OS service lifecycle (launchd/systemd user service)
@@ -176,6 +205,14 @@ enum Commands {
     /// Run diagnostics for daemon/scheduler/channel freshness
     Doctor,
 
+    /// Check the config file and report every problem with its key and line
+    #[command(alias = "check")]
+    ValidateConfig {
+        /// Config file to check (default: the one `zeroclaw` loads)
+        #[arg(long)]
+        path: Option<std::path::PathBuf>,
+    },
+
     /// Show system status (full details)
     Status,
 
@@ -215,6 +252,12 @@ enum Commands {
         migrate_command: MigrateCommands,
     },
 
+    /// Export or import operational state, for moving a deployment between hosts
+    State {
+        #[command(subcommand)]
+        state_command: zeroclaw::StateCommands,
+    },
+
     /// Discover and introspect USB hardware
     Hardware {
         #[command(subcommand)]
@@ -226,6 +269,12 @@ enum Commands {
         #[command(subcommand)]
         peripheral_command: zeroclaw::PeripheralCommands,
     },
+
+    /// Sign in to OAuth clients used by channels (Slack, Mastodon, Teams)
+    Oauth {
+        #[command(subcommand)]
+        oauth_command: zeroclaw::OAuthCommands,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -240,6 +289,26 @@ enum MigrateCommands {
         #[arg(long)]
         dry_run: bool,
     },
+    /// Apply pending schema migrations to the Postgres memory store
+    Schema {
+        /// List pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Copy every memory from one backend to another (e.g. sqlite to postgres)
+    Copy {
+        /// Backend to read from (sqlite, lucid, markdown, postgres)
+        #[arg(long)]
+        from: String,
+
+        /// Backend to write to (sqlite, lucid, markdown, postgres)
+        #[arg(long)]
+        to: String,
+
+        /// Count what would be copied without writing any data
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -313,7 +382,14 @@ enum ChannelCommands {
     /// List configured channels
     List,
     /// Start all configured channels (Telegram, Discord, Slack)
-    Start,
+    Start {
+        /// Use stdin/stdout as the only channel: JSON lines in, JSON lines out
+        #[arg(long)]
+        pipe: bool,
+        /// Log replies instead of sending them (see `[channels_config.dry_run]`)
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Run health checks for configured channels
     Doctor,
     /// Add a new channel
@@ -372,15 +448,6 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Initialize logging - respects RUST_LOG env var, defaults to INFO
-    let subscriber = fmt::Subscriber::builder()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-
     // Onboard runs quick setup by default, or the interactive wizard with --interactive.
     // The onboard wizard uses reqwest::blocking internally, which creates its own
     // Tokio runtime. To avoid "Cannot drop a runtime in a context where blocking is
@@ -393,6 +460,7 @@ async fn main() -> Result<()> {
         memory,
     } = &cli.command
     {
+        logging::init(&LoggingConfig::default())?;
         let interactive = *interactive;
         let channels_only = *channels_only;
         let api_key = api_key.clone();
@@ -418,17 +486,37 @@ async fn main() -> Result<()> {
         .await??;
         // Auto-start channels if user said yes during wizard
         if std::env::var("ZEROCLAW_AUTOSTART_CHANNELS").as_deref() == Ok("1") {
+            security::at_rest::install(security::at_rest::from_config(&config).await?);
             channels::start_channels(config).await?;
         }
         return Ok(());
     }
 
-    // All other commands need config loaded first
-    let mut config = Config::load_or_init()?;
-    config.apply_env_overrides();
+    // Checks the file itself, so it must not fail on loading it
+    if let Commands::ValidateConfig { path } = &cli.command {
+        let path = match path {
+            Some(path) => path.clone(),
+            None => Config::default_path()?,
+        };
+        return config::validate::run(&path);
+    }
+
+    // All other commands need config loaded first; log to stderr until the
+    // [logging] section is known.
+    let mut config = {
+        let _bootstrap = logging::bootstrap();
+        let mut config = Config::load_or_init()?;
+        config.apply_env_overrides();
+        config
+    };
+    if let Some(api_key) = config.api_key.as_deref() {
+        security::redact::register_secret(api_key);
+    }
+    logging::init(&config.logging)?;
+    security::at_rest::install(security::at_rest::from_config(&config).await?);
 
     match cli.command {
-        Commands::Onboard { .. } => unreachable!(),
+        Commands::Onboard { .. } | Commands::ValidateConfig { .. } => unreachable!(),
 
         Commands::Agent {
             message,
@@ -451,7 +539,11 @@ async fn main() -> Result<()> {
             gateway::run_gateway(&host, port, config).await
         }
 
-        Commands::Daemon { port, host } => {
+        Commands::Daemon {
+            port,
+            host,
+            systemd,
+        } => {
             let port = port.unwrap_or(config.gateway.port);
             let host = host.unwrap_or_else(|| config.gateway.host.clone());
             if port == 0 {
@@ -459,7 +551,7 @@ async fn main() -> Result<()> {
             } else {
                 info!("🧠 Starting ZeroClaw Daemon on {host}:{port}");
             }
-            daemon::run(config, host, port).await
+            daemon::run(config, host, port, systemd).await
         }
 
         Commands::Status => {
@@ -555,7 +647,16 @@ async fn main() -> Result<()> {
         Commands::Doctor => doctor::run(&config),
 
         Commands::Channel { channel_command } => match channel_command {
-            ChannelCommands::Start => channels::start_channels(config).await,
+            ChannelCommands::Start { pipe, dry_run } => {
+                if dry_run {
+                    config.channels_config.dry_run.enabled = true;
+                }
+                if pipe {
+                    channels::pipe::run(config).await
+                } else {
+                    channels::start_channels(config).await
+                }
+            }
             ChannelCommands::Doctor => channels::doctor_channels(config).await,
             other => channels::handle_command(other, &config),
         },
@@ -572,6 +673,8 @@ async fn main() -> Result<()> {
             migration::handle_command(migrate_command, &config).await
         }
 
+        Commands::State { state_command } => state::handle_command(state_command, &config),
+
         Commands::Hardware { hardware_command } => {
             hardware::handle_command(hardware_command.clone(), &config)
         }
@@ -579,6 +682,8 @@ async fn main() -> Result<()> {
         Commands::Peripheral { peripheral_command } => {
             peripherals::handle_command(peripheral_command.clone(), &config)
         }
+
+        Commands::Oauth { oauth_command } => oauth::handle_command(oauth_command, &config).await,
     }
 }
 
@@ -0,0 +1,164 @@
+//! Media kept in a local directory and linked through the gateway.
+
+use super::MediaBackend;
+use crate::security::pairing::constant_time_eq;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// File in the media directory holding the key links are signed with.
+const URL_KEY_FILE: &str = ".url_key";
+
+pub struct LocalBackend {
+    dir: PathBuf,
+    /// Where the gateway is reachable, without a trailing slash
+    public_base_url: Option<String>,
+    url_key: Vec<u8>,
+}
+
+impl LocalBackend {
+    /// Use `dir`, creating it and its URL signing key on first use.
+    pub fn new(dir: PathBuf, public_base_url: Option<String>) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let url_key = load_or_create_url_key(&dir.join(URL_KEY_FILE))?;
+        Ok(Self {
+            dir,
+            public_base_url: public_base_url.map(|url| url.trim_end_matches('/').to_string()),
+            url_key,
+        })
+    }
+
+    /// Path of `key`, refusing keys that would leave the directory.
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let valid = !key.is_empty()
+            && key.split('/').all(|part| {
+                !part.is_empty()
+                    && !part.starts_with('.')
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+            });
+        if !valid {
+            bail!("Invalid media key: {key}");
+        }
+        Ok(self.dir.join(key))
+    }
+
+    fn signature(&self, key: &str, expires: u64) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.url_key).expect("HMAC accepts any key length");
+        mac.update(format!("{key}\n{expires}").as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+fn load_or_create_url_key(path: &Path) -> Result<Vec<u8>> {
+    if let Ok(existing) = std::fs::read_to_string(path) {
+        if let Ok(key) = hex::decode(existing.trim()) {
+            if !key.is_empty() {
+                return Ok(key);
+            }
+        }
+    }
+    use rand::RngCore;
+    let mut key = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    std::fs::write(path, hex::encode(&key))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(key)
+}
+
+#[async_trait]
+impl MediaBackend for LocalBackend {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(tokio::fs::try_exists(self.path(key)?).await?)
+    }
+
+    async fn put(&self, key: &str, _mime: &str, bytes: Vec<u8>) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Write then rename, so readers never see a partial file
+        let tmp = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
+        tokio::fs::write(&tmp, bytes)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path(key)?;
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    async fn signed_url(&self, key: &str, expires_secs: u64) -> Result<String> {
+        self.path(key)?;
+        let Some(base) = &self.public_base_url else {
+            bail!("Set media_store.public_base_url to link to local media");
+        };
+        let expires = crate::channels::traits::unix_now() + expires_secs;
+        Ok(format!(
+            "{base}/media/{key}?expires={expires}&sig={}",
+            self.signature(key, expires)
+        ))
+    }
+
+    fn verify_signed_url(&self, key: &str, expires: u64, signature: &str) -> bool {
+        expires >= crate::channels::traits::unix_now()
+            && self.path(key).is_ok()
+            && constant_time_eq(&self.signature(key, expires), signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn links_verify_until_they_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend =
+            LocalBackend::new(dir.path().to_path_buf(), Some("http://gw".into())).unwrap();
+        let key = "sha256/ab/abc.png";
+
+        let url = backend.signed_url(key, 60).await.unwrap();
+        let query = url.split_once('?').unwrap().1;
+        let (expires, sig) = query.split_once("&sig=").unwrap();
+        let expires: u64 = expires.trim_start_matches("expires=").parse().unwrap();
+        assert!(backend.verify_signed_url(key, expires, sig));
+        assert!(!backend.verify_signed_url("sha256/ab/other.png", expires, sig));
+        assert!(!backend.verify_signed_url(key, expires + 1, sig));
+        let stale = 1;
+        assert!(!backend.verify_signed_url(key, stale, &backend.signature(key, stale)));
+
+        // The signing key survives a restart
+        let reopened = LocalBackend::new(dir.path().to_path_buf(), None).unwrap();
+        assert!(reopened.verify_signed_url(key, expires, sig));
+
+        assert!(backend
+            .put("../escape.png", "image/png", vec![])
+            .await
+            .is_err());
+        assert!(backend.get("sha256/../../etc/passwd").await.is_err());
+    }
+}
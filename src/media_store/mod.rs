@@ -0,0 +1,277 @@
+//! Persistent media: inbound attachments and generated outputs.
+//!
+//! Everything is stored under a content-addressed key
+//! (`sha256/ab/abcdef….png`), so the same bytes are kept once and a key
+//! never changes meaning. Other subsystems (RAG, the audit log) record the
+//! key and ask [`MediaStore::url`] for a time-limited link when they need
+//! one. Two backends exist: a local directory whose links are served and
+//! verified by the gateway's `GET /media/{key}`, and an S3-compatible
+//! bucket with presigned links.
+
+pub mod local;
+pub mod s3;
+
+use crate::channels::traits::{Channel, ChannelMessage};
+use crate::config::{MediaBackendKind, MediaStoreConfig};
+use crate::security::ManagedSecret;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Arc;
+
+pub use local::LocalBackend;
+pub use s3::S3Client;
+
+/// Storage a [`MediaStore`] keeps its objects in.
+#[async_trait]
+pub trait MediaBackend: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn exists(&self, key: &str) -> Result<bool>;
+
+    async fn put(&self, key: &str, mime: &str, bytes: Vec<u8>) -> Result<()>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// A link to `key` that works for `expires_secs`.
+    async fn signed_url(&self, key: &str, expires_secs: u64) -> Result<String>;
+
+    /// Check a link made by [`MediaBackend::signed_url`]. Only backends
+    /// whose links are served by the gateway verify anything.
+    fn verify_signed_url(&self, _key: &str, _expires: u64, _signature: &str) -> bool {
+        false
+    }
+}
+
+/// File extensions of the content types media is commonly stored as.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("audio/ogg", "ogg"),
+    ("audio/mpeg", "mp3"),
+    ("audio/wav", "wav"),
+    ("audio/mp4", "m4a"),
+    ("video/mp4", "mp4"),
+    ("application/pdf", "pdf"),
+    ("text/plain", "txt"),
+];
+
+/// Extension for a content type; `bin` when unknown.
+pub fn extension_for(mime: &str) -> &'static str {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    EXTENSIONS
+        .iter()
+        .find(|(m, _)| m.eq_ignore_ascii_case(essence))
+        .map_or("bin", |(_, ext)| ext)
+}
+
+/// Content type of a stored key, from its extension.
+pub fn mime_for_key(key: &str) -> &'static str {
+    let ext = key.rsplit_once('.').map_or("", |(_, ext)| ext);
+    EXTENSIONS
+        .iter()
+        .find(|(_, e)| *e == ext)
+        .map_or("application/octet-stream", |(mime, _)| mime)
+}
+
+/// Content-addressed key of `bytes`: `sha256/{first two hex}/{hex}.{ext}`.
+pub fn content_key(bytes: &[u8], mime: &str) -> String {
+    key_for_hash(&hex::encode(Sha256::digest(bytes)), mime)
+}
+
+fn key_for_hash(sha256: &str, mime: &str) -> String {
+    format!("sha256/{}/{sha256}.{}", &sha256[..2], extension_for(mime))
+}
+
+/// A stored object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredMedia {
+    pub key: String,
+    pub sha256: String,
+    pub size: u64,
+    pub mime: String,
+}
+
+pub struct MediaStore {
+    backend: Arc<dyn MediaBackend>,
+    url_expiry_secs: u64,
+    persist_inbound: bool,
+    persist_generated: bool,
+}
+
+impl MediaStore {
+    pub fn new(backend: Arc<dyn MediaBackend>) -> Self {
+        let defaults = MediaStoreConfig::default();
+        Self {
+            backend,
+            url_expiry_secs: defaults.url_expiry_secs,
+            persist_inbound: defaults.persist_inbound,
+            persist_generated: defaults.persist_generated,
+        }
+    }
+
+    /// `None` unless `[media_store]` is enabled.
+    pub fn from_config(
+        config: &MediaStoreConfig,
+        workspace_dir: &Path,
+        secret: impl FnOnce(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let backend: Arc<dyn MediaBackend> = match config.backend {
+            MediaBackendKind::Local => {
+                let dir = config
+                    .local_dir
+                    .clone()
+                    .unwrap_or_else(|| workspace_dir.join("media"));
+                Arc::new(LocalBackend::new(dir, config.public_base_url.clone())?)
+            }
+            MediaBackendKind::S3 => {
+                let Some(s3) = &config.s3 else {
+                    bail!("media_store.backend = \"s3\" needs a [media_store.s3] section");
+                };
+                Arc::new(S3Client::new(s3, secret(&s3.secret_access_key)?)?)
+            }
+        };
+        Ok(Some(Self {
+            backend,
+            url_expiry_secs: config.url_expiry_secs.max(1),
+            persist_inbound: config.persist_inbound,
+            persist_generated: config.persist_generated,
+        }))
+    }
+
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
+    pub fn persist_inbound(&self) -> bool {
+        self.persist_inbound
+    }
+
+    pub fn persist_generated(&self) -> bool {
+        self.persist_generated
+    }
+
+    /// Store `bytes` under their content key. Content already stored is not
+    /// uploaded again.
+    pub async fn store(&self, bytes: Vec<u8>, mime: &str) -> Result<StoredMedia> {
+        let sha256 = hex::encode(Sha256::digest(&bytes));
+        let key = key_for_hash(&sha256, mime);
+        let size = bytes.len() as u64;
+        if !self.backend.exists(&key).await? {
+            self.backend
+                .put(&key, mime, bytes)
+                .await
+                .with_context(|| format!("Failed to store {key}"))?;
+        }
+        Ok(StoredMedia {
+            key,
+            sha256,
+            size,
+            mime: mime.to_string(),
+        })
+    }
+
+    pub async fn load(&self, key: &str) -> Result<Vec<u8>> {
+        self.backend.get(key).await
+    }
+
+    /// A time-limited link to a stored key.
+    pub async fn url(&self, key: &str) -> Result<String> {
+        self.backend.signed_url(key, self.url_expiry_secs).await
+    }
+
+    pub fn verify_url(&self, key: &str, expires: u64, signature: &str) -> bool {
+        self.backend.verify_signed_url(key, expires, signature)
+    }
+
+    /// Download and store every attachment of `msg` not stored yet,
+    /// recording the keys in [`MediaAttachment::stored_key`]. Failures are
+    /// logged and leave the attachment as it was.
+    ///
+    /// [`MediaAttachment::stored_key`]: crate::channels::traits::MediaAttachment::stored_key
+    pub async fn persist_attachments(&self, channel: &dyn Channel, msg: &mut ChannelMessage) {
+        for attachment in &mut msg.attachments {
+            if attachment.stored_key.is_some() {
+                continue;
+            }
+            let bytes = match channel.download_attachment(attachment).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("Failed to download attachment on {}: {e}", msg.channel);
+                    continue;
+                }
+            };
+            let mime = attachment
+                .mime_type
+                .clone()
+                .or_else(|| {
+                    attachment
+                        .file_name
+                        .as_deref()
+                        .map(|name| mime_for_key(name).to_string())
+                })
+                .unwrap_or_else(|| "application/octet-stream".into());
+            match self.store(bytes, &mime).await {
+                Ok(stored) => attachment.stored_key = Some(stored.key),
+                Err(e) => tracing::warn!("Failed to persist attachment on {}: {e}", msg.channel),
+            }
+        }
+    }
+}
+
+static STORE: RwLock<Option<Arc<MediaStore>>> = RwLock::new(None);
+
+/// Make `store` the process-wide media store (`None` disables it).
+pub fn install(store: Option<MediaStore>) {
+    *STORE.write() = store.map(Arc::new);
+}
+
+pub fn global() -> Option<Arc<MediaStore>> {
+    STORE.read().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_content_addressed() {
+        let key = content_key(b"hello", "image/png");
+        assert_eq!(
+            key,
+            "sha256/2c/2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.png"
+        );
+        assert_eq!(mime_for_key(&key), "image/png");
+        assert_eq!(extension_for("audio/ogg; codecs=opus"), "ogg");
+        assert_eq!(extension_for("application/x-unknown"), "bin");
+        assert_eq!(mime_for_key("sha256/ab/ab.bin"), "application/octet-stream");
+    }
+
+    #[tokio::test]
+    async fn identical_content_is_stored_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = LocalBackend::new(
+            dir.path().join("media"),
+            Some("https://bot.example.com/".into()),
+        )
+        .unwrap();
+        let store = MediaStore::new(Arc::new(backend));
+
+        let first = store.store(b"image".to_vec(), "image/jpeg").await.unwrap();
+        let second = store.store(b"image".to_vec(), "image/jpeg").await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.size, 5);
+        assert!(first.key.ends_with(&format!("{}.jpg", first.sha256)));
+        assert_eq!(store.load(&first.key).await.unwrap(), b"image");
+
+        let url = store.url(&first.key).await.unwrap();
+        assert!(url.starts_with(&format!("https://bot.example.com/media/{}?", first.key)));
+    }
+}
@@ -5,6 +5,8 @@ pub mod hygiene;
 pub mod lucid;
 pub mod markdown;
 pub mod none;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 pub mod response_cache;
 pub mod snapshot;
 pub mod sqlite;
@@ -19,6 +21,8 @@ pub use backend::{
 pub use lucid::LucidMemory;
 pub use markdown::MarkdownMemory;
 pub use none::NoneMemory;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresMemory;
 pub use response_cache::ResponseCache;
 pub use sqlite::SqliteMemory;
 pub use traits::Memory;
@@ -32,6 +36,7 @@ use std::sync::Arc;
 fn create_memory_with_sqlite_builder<F>(
     backend_name: &str,
     workspace_dir: &Path,
+    postgres_url: Option<&str>,
     mut sqlite_builder: F,
     unknown_context: &str,
 ) -> anyhow::Result<Box<dyn Memory>>
@@ -45,6 +50,7 @@ where
             Ok(Box::new(LucidMemory::new(workspace_dir, local)))
         }
         MemoryBackendKind::Markdown => Ok(Box::new(MarkdownMemory::new(workspace_dir))),
+        MemoryBackendKind::Postgres => create_postgres_memory(postgres_url),
         MemoryBackendKind::None => Ok(Box::new(NoneMemory::new())),
         MemoryBackendKind::Unknown => {
             tracing::warn!(
@@ -55,6 +61,21 @@ where
     }
 }
 
+/// Postgres is shared storage, so a misconfigured one is an error rather
+/// than a silent fallback to memory only this instance can see.
+#[cfg(feature = "postgres")]
+fn create_postgres_memory(url: Option<&str>) -> anyhow::Result<Box<dyn Memory>> {
+    match url.map(str::trim).filter(|url| !url.is_empty()) {
+        Some(url) => Ok(Box::new(PostgresMemory::new(url))),
+        None => anyhow::bail!("memory backend 'postgres' needs [memory] postgres_url"),
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+fn create_postgres_memory(_url: Option<&str>) -> anyhow::Result<Box<dyn Memory>> {
+    anyhow::bail!("memory backend 'postgres' needs a build with --features postgres")
+}
+
 /// Factory: create the right memory backend from config
 pub fn create_memory(
     config: &MemoryConfig,
@@ -122,6 +143,7 @@ pub fn create_memory(
     create_memory_with_sqlite_builder(
         &config.backend,
         workspace_dir,
+        config.postgres_url.as_deref(),
         || build_sqlite_memory(config, workspace_dir, api_key),
         "",
     )
@@ -130,16 +152,18 @@ pub fn create_memory(
 pub fn create_memory_for_migration(
     backend: &str,
     workspace_dir: &Path,
+    postgres_url: Option<&str>,
 ) -> anyhow::Result<Box<dyn Memory>> {
     if matches!(classify_memory_backend(backend), MemoryBackendKind::None) {
         anyhow::bail!(
-            "memory backend 'none' disables persistence; choose sqlite, lucid, or markdown before migration"
+            "memory backend 'none' disables persistence; choose sqlite, lucid, markdown, or postgres before migration"
         );
     }
 
     create_memory_with_sqlite_builder(
         backend,
         workspace_dir,
+        postgres_url,
         || SqliteMemory::new(workspace_dir),
         " during migration",
     )
@@ -231,17 +255,27 @@ mod tests {
         assert_eq!(mem.name(), "markdown");
     }
 
+    #[test]
+    fn factory_postgres_without_url_is_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let cfg = MemoryConfig {
+            backend: "postgres".into(),
+            ..MemoryConfig::default()
+        };
+        assert!(create_memory(&cfg, tmp.path(), None).is_err());
+    }
+
     #[test]
     fn migration_factory_lucid() {
         let tmp = TempDir::new().unwrap();
-        let mem = create_memory_for_migration("lucid", tmp.path()).unwrap();
+        let mem = create_memory_for_migration("lucid", tmp.path(), None).unwrap();
         assert_eq!(mem.name(), "lucid");
     }
 
     #[test]
     fn migration_factory_none_is_rejected() {
         let tmp = TempDir::new().unwrap();
-        let error = create_memory_for_migration("none", tmp.path())
+        let error = create_memory_for_migration("none", tmp.path(), None)
             .err()
             .expect("backend=none should be rejected for migration");
         assert!(error.to_string().contains("disables persistence"));
@@ -0,0 +1,453 @@
+//! PostgreSQL-backed memory (`--features postgres`), for several instances
+//! sharing one memory store.
+//!
+//! The schema is versioned: [`MIGRATIONS`] is applied in order by
+//! `zeroclaw migrate schema` and recorded in `zeroclaw_migrations`. The
+//! backend itself never changes the schema; it refuses to start against a
+//! database that is behind, so upgrades stay an explicit operator step.
+
+use super::traits::{Memory, MemoryCategory, MemoryEntry};
+use crate::security::at_rest::{self, AtRestCipher};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio_postgres::{Client, NoTls, Row};
+use uuid::Uuid;
+
+/// One schema step. Versions only grow; a released step is never edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create memories",
+        sql: "CREATE TABLE memories (
+                id          TEXT PRIMARY KEY,
+                key         TEXT NOT NULL UNIQUE,
+                content     TEXT NOT NULL,
+                category    TEXT NOT NULL DEFAULT 'core',
+                session_id  TEXT,
+                created_at  TIMESTAMPTZ NOT NULL,
+                updated_at  TIMESTAMPTZ NOT NULL
+              );
+              CREATE INDEX idx_memories_category ON memories(category);
+              CREATE INDEX idx_memories_session ON memories(session_id);",
+    },
+    Migration {
+        version: 2,
+        name: "keyword search",
+        sql: "ALTER TABLE memories ADD COLUMN search tsvector
+                GENERATED ALWAYS AS (to_tsvector('simple', key || ' ' || content)) STORED;
+              CREATE INDEX idx_memories_search ON memories USING GIN (search);",
+    },
+];
+
+/// Serializes concurrent `migrate schema` runs against the same database.
+const MIGRATION_LOCK: i64 = 0x7a63_6c61_7731;
+
+const COLUMNS: &str = "id, key, content, category, created_at, session_id";
+
+/// Steps of [`MIGRATIONS`] not in `applied`, in order.
+fn pending(applied: &[i64]) -> Vec<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect()
+}
+
+async fn connect(url: &str) -> Result<Client> {
+    let (client, connection) = tokio_postgres::connect(url, NoTls)
+        .await
+        .context("Failed to connect to Postgres")?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            tracing::warn!("Postgres memory connection closed: {e}");
+        }
+    });
+    Ok(client)
+}
+
+async fn applied_versions(client: &Client) -> Result<Vec<i64>> {
+    let exists: bool = client
+        .query_one("SELECT to_regclass('zeroclaw_migrations') IS NOT NULL", &[])
+        .await?
+        .get(0);
+    if !exists {
+        return Ok(Vec::new());
+    }
+    let rows = client
+        .query("SELECT version FROM zeroclaw_migrations", &[])
+        .await?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Schema steps the database at `url` still needs.
+pub async fn pending_migrations(url: &str) -> Result<Vec<&'static Migration>> {
+    let client = connect(url).await?;
+    Ok(pending(&applied_versions(&client).await?))
+}
+
+/// Apply every pending schema step, each in its own transaction, and return
+/// the ones applied.
+pub async fn apply_migrations(url: &str) -> Result<Vec<&'static Migration>> {
+    let client = connect(url).await?;
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK])
+        .await?;
+    let result = apply_pending(&client).await;
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK])
+        .await?;
+    result
+}
+
+async fn apply_pending(client: &Client) -> Result<Vec<&'static Migration>> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS zeroclaw_migrations (
+                version     BIGINT PRIMARY KEY,
+                name        TEXT NOT NULL,
+                applied_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+             )",
+        )
+        .await?;
+    let todo = pending(&applied_versions(client).await?);
+    for migration in &todo {
+        client.batch_execute("BEGIN").await?;
+        let applied = async {
+            client.batch_execute(migration.sql).await?;
+            client
+                .execute(
+                    "INSERT INTO zeroclaw_migrations (version, name) VALUES ($1, $2)",
+                    &[&migration.version, &migration.name],
+                )
+                .await
+        }
+        .await;
+        if let Err(e) = applied {
+            let _ = client.batch_execute("ROLLBACK").await;
+            return Err(e).with_context(|| {
+                format!(
+                    "Postgres migration {} ({}) failed",
+                    migration.version, migration.name
+                )
+            });
+        }
+        client.batch_execute("COMMIT").await?;
+    }
+    Ok(todo)
+}
+
+/// A `to_tsquery` expression matching any word of `query`, each quoted so
+/// user text can't inject query operators.
+fn any_word_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|w| format!("'{}'", w.replace('\\', "\\\\").replace('\'', "''")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" | "))
+}
+
+fn str_to_category(s: &str) -> MemoryCategory {
+    match s {
+        "core" => MemoryCategory::Core,
+        "daily" => MemoryCategory::Daily,
+        "conversation" => MemoryCategory::Conversation,
+        other => MemoryCategory::Custom(other.to_string()),
+    }
+}
+
+fn row_to_entry(row: &Row) -> MemoryEntry {
+    MemoryEntry {
+        id: row.get(0),
+        key: row.get(1),
+        content: row.get(2),
+        category: str_to_category(row.get(3)),
+        timestamp: row.get::<_, DateTime<Utc>>(4).to_rfc3339(),
+        session_id: row.get(5),
+        score: None,
+    }
+}
+
+pub struct PostgresMemory {
+    url: String,
+    client: tokio::sync::Mutex<Option<Arc<Client>>>,
+    cipher: Option<Arc<AtRestCipher>>,
+}
+
+impl PostgresMemory {
+    /// Memory at `url`. Connects on first use, so this never blocks.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: tokio::sync::Mutex::new(None),
+            cipher: at_rest::global(),
+        }
+    }
+
+    /// The shared connection, reconnecting when it dropped.
+    async fn client(&self) -> Result<Arc<Client>> {
+        let mut slot = self.client.lock().await;
+        if let Some(client) = slot.as_ref().filter(|c| !c.is_closed()) {
+            return Ok(Arc::clone(client));
+        }
+        let client = connect(&self.url).await?;
+        let behind = pending(&applied_versions(&client).await?);
+        if !behind.is_empty() {
+            bail!(
+                "Postgres memory schema is {} migration(s) behind; run `zeroclaw migrate schema`",
+                behind.len()
+            );
+        }
+        let client = Arc::new(client);
+        *slot = Some(Arc::clone(&client));
+        Ok(client)
+    }
+
+    /// `content` as it is stored.
+    fn seal(&self, content: &str) -> Result<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.seal(content),
+            None => Ok(content.to_string()),
+        }
+    }
+
+    /// `entry` with its stored content decrypted, or `None` (logged) when it
+    /// can't be.
+    fn unseal(&self, mut entry: MemoryEntry) -> Option<MemoryEntry> {
+        let Some(cipher) = &self.cipher else {
+            return Some(entry);
+        };
+        match cipher.open(&entry.content) {
+            Ok(content) => {
+                entry.content = content;
+                Some(entry)
+            }
+            Err(e) => {
+                tracing::warn!("Skipping memory {}: {e}", entry.key);
+                None
+            }
+        }
+    }
+
+    /// Keyword search over encrypted content: decrypt every memory and rank
+    /// by the number of query words it contains.
+    async fn scan_recall(
+        &self,
+        query: &str,
+        limit: usize,
+        session_id: Option<&str>,
+    ) -> Result<Vec<MemoryEntry>> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut scored: Vec<MemoryEntry> = self
+            .list(None, session_id)
+            .await?
+            .into_iter()
+            .filter_map(|mut entry| {
+                let haystack = format!("{} {}", entry.key, entry.content).to_lowercase();
+                let hits = words
+                    .iter()
+                    .filter(|w| haystack.contains(w.as_str()))
+                    .count();
+                entry.score = Some(hits as f64);
+                (hits > 0).then_some(entry)
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        scored.truncate(limit);
+        Ok(scored)
+    }
+}
+
+#[async_trait]
+impl Memory for PostgresMemory {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+        session_id: Option<&str>,
+    ) -> Result<()> {
+        self.restore(&MemoryEntry {
+            id: Uuid::new_v4().to_string(),
+            key: key.to_string(),
+            content: content.to_string(),
+            category,
+            timestamp: Utc::now().to_rfc3339(),
+            session_id: session_id.map(str::to_string),
+            score: None,
+        })
+        .await
+    }
+
+    async fn restore(&self, entry: &MemoryEntry) -> Result<()> {
+        let created_at = DateTime::parse_from_rfc3339(&entry.timestamp)
+            .map_or_else(|_| Utc::now(), |t| t.with_timezone(&Utc));
+        let content = self.seal(&entry.content)?;
+        let category = entry.category.to_string();
+        self.client()
+            .await?
+            .execute(
+                "INSERT INTO memories (id, key, content, category, session_id, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, now())
+                 ON CONFLICT (key) DO UPDATE SET
+                    content = excluded.content,
+                    category = excluded.category,
+                    session_id = excluded.session_id,
+                    updated_at = excluded.updated_at",
+                &[
+                    &entry.id,
+                    &entry.key,
+                    &content,
+                    &category,
+                    &entry.session_id,
+                    &created_at,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: usize,
+        session_id: Option<&str>,
+    ) -> Result<Vec<MemoryEntry>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let Some(tsquery) = any_word_query(query) else {
+            return Ok(Vec::new());
+        };
+        if self.cipher.is_some() {
+            return self.scan_recall(query, limit, session_id).await;
+        }
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let rows = self
+            .client()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {COLUMNS}, ts_rank(search, q)::float8 AS rank
+                     FROM memories, to_tsquery('simple', $1) AS q
+                     WHERE search @@ q AND ($2::text IS NULL OR session_id = $2)
+                     ORDER BY rank DESC, updated_at DESC
+                     LIMIT $3"
+                ),
+                &[&tsquery, &session_id, &limit],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| MemoryEntry {
+                score: Some(row.get(6)),
+                ..row_to_entry(row)
+            })
+            .collect())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<MemoryEntry>> {
+        let row = self
+            .client()
+            .await?
+            .query_opt(
+                &format!("SELECT {COLUMNS} FROM memories WHERE key = $1"),
+                &[&key],
+            )
+            .await?;
+        Ok(row.and_then(|row| self.unseal(row_to_entry(&row))))
+    }
+
+    async fn list(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> Result<Vec<MemoryEntry>> {
+        let category = category.map(MemoryCategory::to_string);
+        let rows = self
+            .client()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {COLUMNS} FROM memories
+                     WHERE ($1::text IS NULL OR category = $1)
+                       AND ($2::text IS NULL OR session_id = $2)
+                     ORDER BY updated_at DESC"
+                ),
+                &[&category, &session_id],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| self.unseal(row_to_entry(row)))
+            .collect())
+    }
+
+    async fn forget(&self, key: &str) -> Result<bool> {
+        let deleted = self
+            .client()
+            .await?
+            .execute("DELETE FROM memories WHERE key = $1", &[&key])
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    async fn count(&self) -> Result<usize> {
+        let count: i64 = self
+            .client()
+            .await?
+            .query_one("SELECT COUNT(*) FROM memories", &[])
+            .await?
+            .get(0);
+        Ok(usize::try_from(count).unwrap_or(0))
+    }
+
+    async fn health_check(&self) -> bool {
+        match self.client().await {
+            Ok(client) => client.simple_query("SELECT 1").await.is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_ordered_and_pending_skips_applied() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+        assert_eq!(pending(&[]).len(), MIGRATIONS.len());
+        let rest: Vec<i64> = pending(&[1]).iter().map(|m| m.version).collect();
+        assert_eq!(rest, vec![2]);
+        assert!(pending(&[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn any_word_query_quotes_every_word() {
+        assert_eq!(
+            any_word_query("rust  async").as_deref(),
+            Some("'rust' | 'async'")
+        );
+        assert_eq!(
+            any_word_query("it's a&b").as_deref(),
+            Some("'it''s' | 'a&b'")
+        );
+        assert_eq!(any_word_query("   "), None);
+    }
+}
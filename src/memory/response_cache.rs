@@ -4,13 +4,18 @@
 //! `(model, system_prompt_hash, user_prompt)`. Entries expire after a
 //! configurable TTL (default: 1 hour). The cache is optional and disabled by
 //! default — users opt in via `[memory] response_cache_enabled = true`.
+//! Channels look up the first message of each session in it; `/nocache`
+//! skips the lookup.
 
+use crate::security::at_rest::{self, AtRestCipher};
 use anyhow::Result;
 use chrono::{Duration, Local};
 use parking_lot::Mutex;
 use rusqlite::{params, Connection};
 use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 /// Response cache backed by a dedicated SQLite database.
 ///
@@ -22,6 +27,22 @@ pub struct ResponseCache {
     db_path: PathBuf,
     ttl_minutes: i64,
     max_entries: usize,
+    /// Lookups that found nothing since the cache was opened
+    misses: AtomicU64,
+    /// Seals `response` when `[security.encryption]` is on
+    cipher: Option<Arc<AtRestCipher>>,
+}
+
+/// `prompt` lowercased, with runs of whitespace collapsed and trailing
+/// punctuation dropped, so trivially different phrasings share a key.
+pub fn normalize_prompt(prompt: &str) -> String {
+    prompt
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .trim_end_matches(['?', '!', '.', ' '])
+        .to_string()
 }
 
 impl ResponseCache {
@@ -53,11 +74,24 @@ impl ResponseCache {
             CREATE INDEX IF NOT EXISTS idx_rc_created ON response_cache(created_at);",
         )?;
 
+        let cipher = at_rest::global();
+        if let Some(cipher) = &cipher {
+            // Cached answers are cheap to recompute: drop the ones not sealed
+            // under the current key rather than re-encrypting them
+            conn.execute_batch("PRAGMA secure_delete = ON;")?;
+            conn.execute(
+                "DELETE FROM response_cache WHERE response NOT LIKE ?1",
+                params![cipher.current_pattern()],
+            )?;
+        }
+
         Ok(Self {
             conn: Mutex::new(conn),
             db_path,
             ttl_minutes: i64::from(ttl_minutes),
             max_entries,
+            misses: AtomicU64::new(0),
+            cipher,
         })
     }
 
@@ -87,7 +121,13 @@ impl ResponseCache {
              WHERE prompt_hash = ?1 AND created_at > ?2",
         )?;
 
-        let result: Option<String> = stmt.query_row(params![key, cutoff], |row| row.get(0)).ok();
+        let result: Option<String> = stmt
+            .query_row(params![key, cutoff], |row| row.get::<_, String>(0))
+            .ok()
+            .and_then(|response| match &self.cipher {
+                Some(cipher) => cipher.open(&response).ok(),
+                None => Some(response),
+            });
 
         if result.is_some() {
             // Bump hit count and accessed_at
@@ -98,6 +138,8 @@ impl ResponseCache {
                  WHERE prompt_hash = ?2",
                 params![now_str, key],
             )?;
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
         }
 
         Ok(result)
@@ -108,6 +150,10 @@ impl ResponseCache {
         let conn = self.conn.lock();
 
         let now = Local::now().to_rfc3339();
+        let response = match &self.cipher {
+            Some(cipher) => cipher.seal(response)?,
+            None => response.to_string(),
+        };
 
         conn.execute(
             "INSERT OR REPLACE INTO response_cache
@@ -161,6 +207,11 @@ impl ResponseCache {
         Ok((count as usize, hits as u64, tokens_saved as u64))
     }
 
+    /// Lookups that missed since the cache was opened.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
     /// Wipe the entire cache (useful for `zeroclaw cache clear`).
     pub fn clear(&self) -> Result<usize> {
         let conn = self.conn.lock();
@@ -312,6 +363,18 @@ mod tests {
         assert_eq!(count, 0);
     }
 
+    #[test]
+    fn misses_counted_and_prompts_normalized() {
+        let (_tmp, cache) = temp_cache(60);
+        let key = ResponseCache::cache_key("gpt-4", None, &normalize_prompt("Opening hours?"));
+        assert!(cache.get(&key).unwrap().is_none());
+        cache.put(&key, "gpt-4", "9 to 5", 3).unwrap();
+
+        let same = ResponseCache::cache_key("gpt-4", None, &normalize_prompt("  opening\nHOURS "));
+        assert_eq!(cache.get(&same).unwrap().as_deref(), Some("9 to 5"));
+        assert_eq!(cache.misses(), 1);
+    }
+
     #[test]
     fn stats_empty_cache() {
         let (_tmp, cache) = temp_cache(60);
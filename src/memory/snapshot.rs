@@ -5,7 +5,11 @@
 //!
 //! **Auto-Hydration**: if `brain.db` is missing but `MEMORY_SNAPSHOT.md` exists,
 //! re-indexes all entries back into a fresh SQLite database.
+//!
+//! With encryption at rest on, the snapshot holds memories sealed with the
+//! current key, just like `brain.db`, and hydration restores them sealed.
 
+use crate::security::at_rest::AtRestCipher;
 use anyhow::Result;
 use chrono::Local;
 use rusqlite::{params, Connection};
@@ -25,6 +29,10 @@ const SNAPSHOT_HEADER: &str = "# 🧠 ZeroClaw Memory Snapshot\n\n\
 ///
 /// Returns the number of entries exported.
 pub fn export_snapshot(workspace_dir: &Path) -> Result<usize> {
+    export_snapshot_with(workspace_dir, crate::security::at_rest::global().as_deref())
+}
+
+fn export_snapshot_with(workspace_dir: &Path, cipher: Option<&AtRestCipher>) -> Result<usize> {
     let db_path = workspace_dir.join("memory").join("brain.db");
     if !db_path.exists() {
         tracing::debug!("snapshot export skipped: brain.db does not exist");
@@ -54,6 +62,23 @@ pub fn export_snapshot(workspace_dir: &Path) -> Result<usize> {
         .filter_map(|r| r.ok())
         .collect();
 
+    // Encrypted memories stay encrypted: rows not yet sealed with the current
+    // key are sealed with it, and rows no configured key opens are left out
+    let rows: Vec<_> = match cipher {
+        Some(cipher) => rows
+            .into_iter()
+            .filter_map(|(key, content, category, created_at, updated_at)| {
+                let content = if cipher.needs_reseal(&content) {
+                    cipher.seal(&cipher.open(&content).ok()?).ok()?
+                } else {
+                    content
+                };
+                Some((key, content, category, created_at, updated_at))
+            })
+            .collect(),
+        None => rows,
+    };
+
     if rows.is_empty() {
         tracing::debug!("snapshot export: no core memories to export");
         return Ok(0);
@@ -426,6 +451,63 @@ Rule 3: Protect the user.
         assert_eq!(identity, "I am a test agent");
     }
 
+    #[test]
+    fn export_keeps_encrypted_memories_sealed() {
+        const OLD_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        const KEY: &str = "202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f";
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path();
+        let db_dir = workspace.join("memory");
+        fs::create_dir_all(&db_dir).unwrap();
+        let conn = Connection::open(db_dir.join("brain.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE memories (
+                id TEXT PRIMARY KEY,
+                key TEXT NOT NULL UNIQUE,
+                content TEXT NOT NULL,
+                category TEXT NOT NULL DEFAULT 'core',
+                embedding BLOB,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+             );",
+        )
+        .unwrap();
+        let old = AtRestCipher::new(OLD_KEY, &[]).unwrap();
+        let cipher = AtRestCipher::new(KEY, &[OLD_KEY.to_string()]).unwrap();
+        let now = Local::now().to_rfc3339();
+        for (id, key, content) in [
+            ("id1", "identity", cipher.seal("I am a test agent").unwrap()),
+            ("id2", "preference", old.seal("User likes Rust").unwrap()),
+            ("id3", "legacy", "Written before encryption".to_string()),
+        ] {
+            conn.execute(
+                "INSERT INTO memories (id, key, content, category, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, 'core', ?4, ?4)",
+                params![id, key, content, now],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        assert_eq!(export_snapshot_with(workspace, Some(&cipher)).unwrap(), 3);
+        let snapshot = fs::read_to_string(workspace.join(SNAPSHOT_FILENAME)).unwrap();
+        assert!(!snapshot.contains("I am a test agent"));
+        assert!(!snapshot.contains("User likes Rust"));
+        assert!(!snapshot.contains("Written before encryption"));
+
+        let entries = parse_snapshot(&snapshot);
+        assert_eq!(entries.len(), 3);
+        for (_, content) in &entries {
+            assert!(!cipher.needs_reseal(content));
+        }
+        let preference = &entries
+            .iter()
+            .find(|(key, _)| key == "preference")
+            .unwrap()
+            .1;
+        assert_eq!(cipher.open(preference).unwrap(), "User likes Rust");
+    }
+
     #[test]
     fn should_hydrate_only_when_needed() {
         let tmp = TempDir::new().unwrap();
@@ -1,6 +1,7 @@
 use super::embeddings::EmbeddingProvider;
 use super::traits::{Memory, MemoryCategory, MemoryEntry};
 use super::vector;
+use crate::security::at_rest::{self, AtRestCipher};
 use async_trait::async_trait;
 use chrono::Local;
 use parking_lot::Mutex;
@@ -17,6 +18,9 @@ use uuid::Uuid;
 /// - **Hybrid Merge**: weighted fusion of vector + keyword results
 /// - **Embedding Cache**: LRU-evicted cache to avoid redundant API calls
 /// - **Safe Reindex**: temp DB → seed → sync → atomic swap → rollback
+/// - **Encryption at rest**: with `[security.encryption]` on, `content` is
+///   stored sealed and keyword search decrypts and scans instead of using
+///   FTS5
 pub struct SqliteMemory {
     conn: Mutex<Connection>,
     db_path: PathBuf,
@@ -24,6 +28,7 @@ pub struct SqliteMemory {
     vector_weight: f32,
     keyword_weight: f32,
     cache_max: usize,
+    cipher: Option<Arc<AtRestCipher>>,
 }
 
 impl SqliteMemory {
@@ -43,6 +48,24 @@ impl SqliteMemory {
         vector_weight: f32,
         keyword_weight: f32,
         cache_max: usize,
+    ) -> anyhow::Result<Self> {
+        Self::open(
+            workspace_dir,
+            embedder,
+            vector_weight,
+            keyword_weight,
+            cache_max,
+            at_rest::global(),
+        )
+    }
+
+    fn open(
+        workspace_dir: &Path,
+        embedder: Arc<dyn EmbeddingProvider>,
+        vector_weight: f32,
+        keyword_weight: f32,
+        cache_max: usize,
+        cipher: Option<Arc<AtRestCipher>>,
     ) -> anyhow::Result<Self> {
         let db_path = workspace_dir.join("memory").join("brain.db");
 
@@ -68,6 +91,16 @@ impl SqliteMemory {
 
         Self::init_schema(&conn)?;
 
+        if let Some(cipher) = &cipher {
+            // Overwrite deleted plaintext instead of leaving it in free pages
+            conn.execute_batch("PRAGMA secure_delete = ON;")?;
+            let resealed = Self::reseal(&conn, cipher)?;
+            if resealed > 0 {
+                conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+                tracing::info!("Encrypted {resealed} memories with key {}", cipher.key_id());
+            }
+        }
+
         Ok(Self {
             conn: Mutex::new(conn),
             db_path,
@@ -75,9 +108,65 @@ impl SqliteMemory {
             vector_weight,
             keyword_weight,
             cache_max,
+            cipher,
         })
     }
 
+    /// Seal every memory that is plaintext or sealed with a previous key
+    /// under the current key. Rows that can't be opened are left alone.
+    fn reseal(conn: &Connection, cipher: &AtRestCipher) -> anyhow::Result<usize> {
+        let stale: Vec<(String, String)> = {
+            let mut stmt =
+                conn.prepare("SELECT id, content FROM memories WHERE content NOT LIKE ?1")?;
+            let rows = stmt.query_map(params![cipher.current_pattern()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let mut resealed = 0;
+        for (id, content) in stale {
+            let plaintext = match cipher.open(&content) {
+                Ok(plaintext) => plaintext,
+                Err(e) => {
+                    tracing::warn!("Cannot re-encrypt memory {id}: {e}");
+                    continue;
+                }
+            };
+            conn.execute(
+                "UPDATE memories SET content = ?1 WHERE id = ?2",
+                params![cipher.seal(&plaintext)?, id],
+            )?;
+            resealed += 1;
+        }
+        Ok(resealed)
+    }
+
+    /// `content` as it is stored.
+    fn seal(&self, content: &str) -> anyhow::Result<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.seal(content),
+            None => Ok(content.to_string()),
+        }
+    }
+
+    /// `entry` with its stored content decrypted, or `None` (logged) when it
+    /// can't be.
+    fn unseal(&self, mut entry: MemoryEntry) -> Option<MemoryEntry> {
+        let Some(cipher) = &self.cipher else {
+            return Some(entry);
+        };
+        match cipher.open(&entry.content) {
+            Ok(content) => {
+                entry.content = content;
+                Some(entry)
+            }
+            Err(e) => {
+                tracing::warn!("Skipping memory {}: {e}", entry.key);
+                None
+            }
+        }
+    }
+
     /// Initialize all tables: memories, FTS5, `embedding_cache`
     fn init_schema(conn: &Connection) -> anyhow::Result<()> {
         conn.execute_batch(
@@ -237,6 +326,7 @@ impl SqliteMemory {
         conn: &Connection,
         query: &str,
         limit: usize,
+        session_id: Option<&str>,
     ) -> anyhow::Result<Vec<(String, f32)>> {
         // Escape FTS5 special chars and build query
         let fts_query: String = query
@@ -252,7 +342,7 @@ impl SqliteMemory {
         let sql = "SELECT m.id, bm25(memories_fts) as score
                    FROM memories_fts f
                    JOIN memories m ON m.rowid = f.rowid
-                   WHERE memories_fts MATCH ?1
+                   WHERE memories_fts MATCH ?1 AND (?3 IS NULL OR m.session_id = ?3)
                    ORDER BY score
                    LIMIT ?2";
 
@@ -260,7 +350,7 @@ impl SqliteMemory {
         #[allow(clippy::cast_possible_wrap)]
         let limit_i64 = limit as i64;
 
-        let rows = stmt.query_map(params![fts_query, limit_i64], |row| {
+        let rows = stmt.query_map(params![fts_query, limit_i64, session_id], |row| {
             let id: String = row.get(0)?;
             let score: f64 = row.get(1)?;
             // BM25 returns negative scores (lower = better), negate for ranking
@@ -275,16 +365,65 @@ impl SqliteMemory {
         Ok(results)
     }
 
+    /// Keyword search over encrypted content: decrypt every memory and rank
+    /// by the number of query words it contains.
+    fn scan_search(
+        conn: &Connection,
+        cipher: &AtRestCipher,
+        query: &str,
+        limit: usize,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<(String, f32)>> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT id, key, content FROM memories WHERE ?1 IS NULL OR session_id = ?1")?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        let mut scored: Vec<(String, f32)> = Vec::new();
+        for row in rows {
+            let (id, key, content) = row?;
+            let Ok(content) = cipher.open(&content) else {
+                continue;
+            };
+            let haystack = format!("{} {}", key.to_lowercase(), content.to_lowercase());
+            let hits = words
+                .iter()
+                .filter(|w| haystack.contains(w.as_str()))
+                .count();
+            if hits > 0 {
+                #[allow(clippy::cast_precision_loss)]
+                scored.push((id, hits as f32));
+            }
+        }
+
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
     /// Vector similarity search: scan embeddings and compute cosine similarity
     fn vector_search(
         conn: &Connection,
         query_embedding: &[f32],
         limit: usize,
+        session_id: Option<&str>,
     ) -> anyhow::Result<Vec<(String, f32)>> {
-        let mut stmt =
-            conn.prepare("SELECT id, embedding FROM memories WHERE embedding IS NOT NULL")?;
+        let mut stmt = conn.prepare(
+            "SELECT id, embedding FROM memories
+             WHERE embedding IS NOT NULL AND (?1 IS NULL OR session_id = ?1)",
+        )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![session_id], |row| {
             let id: String = row.get(0)?;
             let blob: Vec<u8> = row.get(1)?;
             Ok((id, blob))
@@ -333,7 +472,14 @@ impl SqliteMemory {
 
         let mut count = 0;
         for (id, content) in &entries {
-            if let Ok(Some(emb)) = self.get_or_compute_embedding(content).await {
+            let content = match &self.cipher {
+                Some(cipher) => match cipher.open(content) {
+                    Ok(content) => content,
+                    Err(_) => continue,
+                },
+                None => content.clone(),
+            };
+            if let Ok(Some(emb)) = self.get_or_compute_embedding(&content).await {
                 let bytes = vector::vec_to_bytes(&emb);
                 let conn = self.conn.lock();
                 conn.execute(
@@ -366,6 +512,7 @@ impl Memory for SqliteMemory {
             .get_or_compute_embedding(content)
             .await?
             .map(|emb| vector::vec_to_bytes(&emb));
+        let content = self.seal(content)?;
 
         let conn = self.conn.lock();
         let now = Local::now().to_rfc3339();
@@ -387,6 +534,41 @@ impl Memory for SqliteMemory {
         Ok(())
     }
 
+    async fn restore(&self, entry: &MemoryEntry) -> anyhow::Result<()> {
+        let embedding_bytes = self
+            .get_or_compute_embedding(&entry.content)
+            .await?
+            .map(|emb| vector::vec_to_bytes(&emb));
+        let content = self.seal(&entry.content)?;
+
+        let conn = self.conn.lock();
+        let now = Local::now().to_rfc3339();
+        let cat = Self::category_to_str(&entry.category);
+
+        conn.execute(
+            "INSERT INTO memories (id, key, content, category, embedding, created_at, updated_at, session_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(key) DO UPDATE SET
+                content = excluded.content,
+                category = excluded.category,
+                embedding = excluded.embedding,
+                updated_at = excluded.updated_at,
+                session_id = excluded.session_id",
+            params![
+                entry.id,
+                entry.key,
+                content,
+                cat,
+                embedding_bytes,
+                entry.timestamp,
+                now,
+                entry.session_id
+            ],
+        )?;
+
+        Ok(())
+    }
+
     async fn recall(
         &self,
         query: &str,
@@ -402,12 +584,17 @@ impl Memory for SqliteMemory {
 
         let conn = self.conn.lock();
 
-        // FTS5 BM25 keyword search
-        let keyword_results = Self::fts5_search(&conn, query, limit * 2).unwrap_or_default();
+        // FTS5 BM25 keyword search; the FTS index only holds ciphertext when
+        // content is encrypted
+        let keyword_results = match &self.cipher {
+            Some(cipher) => Self::scan_search(&conn, cipher, query, limit * 2, session_id),
+            None => Self::fts5_search(&conn, query, limit * 2, session_id),
+        }
+        .unwrap_or_default();
 
         // Vector similarity search (if embeddings available)
         let vector_results = if let Some(ref qe) = query_embedding {
-            Self::vector_search(&conn, qe, limit * 2).unwrap_or_default()
+            Self::vector_search(&conn, qe, limit * 2, session_id).unwrap_or_default()
         } else {
             Vec::new()
         };
@@ -451,18 +638,13 @@ impl Memory for SqliteMemory {
                     score: Some(f64::from(scored.final_score)),
                 })
             }) {
-                // Filter by session_id if requested
-                if let Some(sid) = session_id {
-                    if entry.session_id.as_deref() != Some(sid) {
-                        continue;
-                    }
-                }
-                results.push(entry);
+                results.extend(self.unseal(entry));
             }
         }
 
-        // If hybrid returned nothing, fall back to LIKE search
-        if results.is_empty() {
+        // If hybrid returned nothing, fall back to LIKE search (which can't
+        // see into encrypted content)
+        if results.is_empty() && self.cipher.is_none() {
             let keywords: Vec<String> =
                 query.split_whitespace().map(|w| format!("%{w}%")).collect();
             if !keywords.is_empty() {
@@ -476,10 +658,11 @@ impl Memory for SqliteMemory {
                 let where_clause = conditions.join(" OR ");
                 let sql = format!(
                     "SELECT id, key, content, category, created_at, session_id FROM memories
-                     WHERE {where_clause}
+                     WHERE ({where_clause}) AND (?{session} IS NULL OR session_id = ?{session})
                      ORDER BY updated_at DESC
                      LIMIT ?{}",
-                    keywords.len() * 2 + 1
+                    keywords.len() * 2 + 1,
+                    session = keywords.len() * 2 + 2
                 );
                 let mut stmt = conn.prepare(&sql)?;
                 let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -489,6 +672,7 @@ impl Memory for SqliteMemory {
                 }
                 #[allow(clippy::cast_possible_wrap)]
                 param_values.push(Box::new(limit as i64));
+                param_values.push(Box::new(session_id.map(str::to_string)));
                 let params_ref: Vec<&dyn rusqlite::types::ToSql> =
                     param_values.iter().map(AsRef::as_ref).collect();
                 let rows = stmt.query_map(params_ref.as_slice(), |row| {
@@ -503,13 +687,7 @@ impl Memory for SqliteMemory {
                     })
                 })?;
                 for row in rows {
-                    let entry = row?;
-                    if let Some(sid) = session_id {
-                        if entry.session_id.as_deref() != Some(sid) {
-                            continue;
-                        }
-                    }
-                    results.push(entry);
+                    results.push(row?);
                 }
             }
         }
@@ -538,7 +716,7 @@ impl Memory for SqliteMemory {
         })?;
 
         match rows.next() {
-            Some(Ok(entry)) => Ok(Some(entry)),
+            Some(Ok(entry)) => Ok(self.unseal(entry)),
             _ => Ok(None),
         }
     }
@@ -578,7 +756,7 @@ impl Memory for SqliteMemory {
                         continue;
                     }
                 }
-                results.push(entry);
+                results.extend(self.unseal(entry));
             }
         } else {
             let mut stmt = conn.prepare(
@@ -593,7 +771,7 @@ impl Memory for SqliteMemory {
                         continue;
                     }
                 }
-                results.push(entry);
+                results.extend(self.unseal(entry));
             }
         }
 
@@ -1231,6 +1409,26 @@ mod tests {
         assert_eq!(entry.content, content);
     }
 
+    #[tokio::test]
+    async fn restore_keeps_id_and_timestamp() {
+        let (_tmp, mem) = temp_sqlite();
+        let entry = MemoryEntry {
+            id: "copied-1".into(),
+            key: "lang".into(),
+            content: "prefers Rust".into(),
+            category: MemoryCategory::Core,
+            timestamp: "2026-01-02T03:04:05+00:00".into(),
+            session_id: Some("sess-a".into()),
+            score: None,
+        };
+        mem.restore(&entry).await.unwrap();
+        let restored = mem.get("lang").await.unwrap().unwrap();
+        assert_eq!(restored.id, "copied-1");
+        assert_eq!(restored.timestamp, entry.timestamp);
+        assert_eq!(restored.content, "prefers Rust");
+        assert_eq!(restored.session_id.as_deref(), Some("sess-a"));
+    }
+
     // ── Edge cases: recall ───────────────────────────────────────
 
     #[tokio::test]
@@ -1530,6 +1728,33 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[tokio::test]
+    async fn session_recall_is_not_crowded_out_by_other_sessions() {
+        let (_tmp, mem) = temp_sqlite();
+        for i in 0..20 {
+            mem.store(
+                &format!("b{i}"),
+                "workshop workshop workshop",
+                MemoryCategory::Conversation,
+                Some("sess-b"),
+            )
+            .await
+            .unwrap();
+        }
+        mem.store(
+            "a1",
+            "booked a workshop",
+            MemoryCategory::Conversation,
+            Some("sess-a"),
+        )
+        .await
+        .unwrap();
+
+        let results = mem.recall("workshop", 2, Some("sess-a")).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].key, "a1");
+    }
+
     #[tokio::test]
     async fn list_with_session_filter() {
         let (_tmp, mem) = temp_sqlite();
@@ -1583,4 +1808,84 @@ mod tests {
             assert_eq!(results[0].session_id.as_deref(), Some("sess-x"));
         }
     }
+
+    // ── Encryption at rest ────────────────────────────────────────
+
+    #[tokio::test]
+    async fn encrypts_content_on_disk_and_reseals_after_rotation() {
+        const OLD_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        const NEW_KEY: &str = "202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f";
+        let tmp = TempDir::new().unwrap();
+        let open = |cipher: AtRestCipher| {
+            SqliteMemory::open(
+                tmp.path(),
+                Arc::new(super::super::embeddings::NoopEmbedding),
+                0.7,
+                0.3,
+                1000,
+                Some(Arc::new(cipher)),
+            )
+            .unwrap()
+        };
+        let stored = |mem: &SqliteMemory| -> String {
+            mem.conn
+                .lock()
+                .query_row("SELECT content FROM memories WHERE key = 'k1'", [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+
+        let plain = SqliteMemory::new(tmp.path()).unwrap();
+        plain
+            .store(
+                "k0",
+                "written before encryption",
+                MemoryCategory::Core,
+                None,
+            )
+            .await
+            .unwrap();
+        drop(plain);
+
+        let old = AtRestCipher::new(OLD_KEY, &[]).unwrap();
+        let old_id = old.key_id().to_string();
+        let mem = open(old);
+        mem.store(
+            "k1",
+            "my passport number is X123",
+            MemoryCategory::Core,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(stored(&mem).starts_with(&format!("aes1:{old_id}:")));
+        assert!(!stored(&mem).contains("passport"));
+        assert_eq!(
+            mem.get("k1").await.unwrap().unwrap().content,
+            "my passport number is X123"
+        );
+        let results = mem.recall("passport", 5, None).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].key, "k1");
+        assert_eq!(
+            mem.get("k0").await.unwrap().unwrap().content,
+            "written before encryption"
+        );
+        drop(mem);
+
+        let rotated = AtRestCipher::new(NEW_KEY, &[OLD_KEY.to_string()]).unwrap();
+        let new_id = rotated.key_id().to_string();
+        let mem = open(rotated);
+        assert!(stored(&mem).starts_with(&format!("aes1:{new_id}:")));
+        assert_eq!(mem.list(None, None).await.unwrap().len(), 2);
+        drop(mem);
+
+        // The old key is no longer needed
+        let mem = open(AtRestCipher::new(NEW_KEY, &[]).unwrap());
+        assert_eq!(
+            mem.get("k0").await.unwrap().unwrap().content,
+            "written before encryption"
+        );
+    }
 }
@@ -53,6 +53,19 @@ pub trait Memory: Send + Sync {
         session_id: Option<&str>,
     ) -> anyhow::Result<()>;
 
+    /// Write an entry read from another backend, keeping its id and
+    /// timestamp where the backend stores them. Used when copying between
+    /// backends.
+    async fn restore(&self, entry: &MemoryEntry) -> anyhow::Result<()> {
+        self.store(
+            &entry.key,
+            &entry.content,
+            entry.category.clone(),
+            entry.session_id.as_deref(),
+        )
+        .await
+    }
+
     /// Recall memories matching a query (keyword search), optionally scoped to a session
     async fn recall(
         &self,
@@ -28,9 +28,98 @@ pub async fn handle_command(command: crate::MigrateCommands, config: &Config) ->
         crate::MigrateCommands::Openclaw { source, dry_run } => {
             migrate_openclaw_memory(config, source, dry_run).await
         }
+        crate::MigrateCommands::Schema { dry_run } => {
+            migrate_postgres_schema(config, dry_run).await
+        }
+        crate::MigrateCommands::Copy { from, to, dry_run } => {
+            copy_memory(config, &from, &to, dry_run).await
+        }
     }
 }
 
+#[cfg(feature = "postgres")]
+async fn migrate_postgres_schema(config: &Config, dry_run: bool) -> Result<()> {
+    use crate::memory::postgres;
+
+    let url = config
+        .memory
+        .postgres_url
+        .as_deref()
+        .context("Set [memory] postgres_url to migrate the Postgres schema")?;
+
+    let migrations = if dry_run {
+        postgres::pending_migrations(url).await?
+    } else {
+        postgres::apply_migrations(url).await?
+    };
+    if migrations.is_empty() {
+        println!("✅ Postgres memory schema is up to date");
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("🔎 Dry run: {} pending migration(s)", migrations.len());
+    } else {
+        println!("✅ Applied {} migration(s)", migrations.len());
+    }
+    for migration in migrations {
+        println!("  {:>3}  {}", migration.version, migration.name);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "postgres"))]
+#[allow(clippy::unused_async)]
+async fn migrate_postgres_schema(_config: &Config, _dry_run: bool) -> Result<()> {
+    bail!("`zeroclaw migrate schema` needs a build with --features postgres")
+}
+
+async fn copy_memory(config: &Config, from: &str, to: &str, dry_run: bool) -> Result<()> {
+    for backend in [from, to] {
+        if memory::classify_memory_backend(backend) == memory::MemoryBackendKind::Unknown {
+            bail!(
+                "Unknown memory backend '{backend}'; choose sqlite, lucid, markdown, or postgres"
+            );
+        }
+    }
+    let source_profile = memory::memory_backend_profile(from);
+    let target_profile = memory::memory_backend_profile(to);
+    // sqlite and lucid both keep their memories in memory/brain.db
+    if source_profile.key == target_profile.key
+        || (source_profile.sqlite_based && target_profile.sqlite_based)
+    {
+        bail!("'{from}' and '{to}' share the same storage; nothing to copy");
+    }
+
+    let postgres_url = config.memory.postgres_url.as_deref();
+    let source = memory::create_memory_for_migration(from, &config.workspace_dir, postgres_url)?;
+    let entries = source.list(None, None).await?;
+
+    if dry_run {
+        println!("🔎 Dry run: memory copy preview");
+        println!("  From: {from}");
+        println!("  To:   {to}");
+        println!("  Entries: {}", entries.len());
+        println!();
+        println!("Run without --dry-run to copy these entries.");
+        return Ok(());
+    }
+
+    let target = memory::create_memory_for_migration(to, &config.workspace_dir, postgres_url)?;
+    for entry in &entries {
+        target
+            .restore(entry)
+            .await
+            .with_context(|| format!("Failed to copy memory '{}'", entry.key))?;
+    }
+
+    println!("✅ Copied {} memories from {from} to {to}", entries.len());
+    if to != config.memory.backend {
+        println!("  Set [memory] backend = \"{to}\" to start using them.");
+    }
+    Ok(())
+}
+
 async fn migrate_openclaw_memory(
     config: &Config,
     source_workspace: Option<PathBuf>,
@@ -114,7 +203,11 @@ async fn migrate_openclaw_memory(
 }
 
 fn target_memory_backend(config: &Config) -> Result<Box<dyn Memory>> {
-    memory::create_memory_for_migration(&config.memory.backend, &config.workspace_dir)
+    memory::create_memory_for_migration(
+        &config.memory.backend,
+        &config.workspace_dir,
+        config.memory.postgres_url.as_deref(),
+    )
 }
 
 fn collect_source_entries(
@@ -545,6 +638,38 @@ mod tests {
         assert_eq!(target_mem.count().await.unwrap(), 0);
     }
 
+    #[tokio::test]
+    async fn copy_moves_markdown_memories_into_sqlite() {
+        let workspace = TempDir::new().unwrap();
+        let config = test_config(workspace.path());
+        let markdown = memory::MarkdownMemory::new(workspace.path());
+        markdown
+            .store("lang", "prefers Rust", MemoryCategory::Core, None)
+            .await
+            .unwrap();
+
+        copy_memory(&config, "markdown", "sqlite", false)
+            .await
+            .unwrap();
+
+        let sqlite = SqliteMemory::new(workspace.path()).unwrap();
+        let all = sqlite.list(None, None).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].content.contains("prefers Rust"));
+    }
+
+    #[tokio::test]
+    async fn copy_rejects_backends_sharing_storage() {
+        let workspace = TempDir::new().unwrap();
+        let config = test_config(workspace.path());
+        assert!(copy_memory(&config, "sqlite", "lucid", false)
+            .await
+            .is_err());
+        assert!(copy_memory(&config, "sqlite", "redis", false)
+            .await
+            .is_err());
+    }
+
     #[test]
     fn migration_target_rejects_none_backend() {
         let target = TempDir::new().unwrap();
@@ -0,0 +1,98 @@
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+
+/// What a check probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckKind {
+    /// GET the URL; passes on the expected status (any 2xx by default)
+    Http,
+    /// Open a TCP connection to `host:port`
+    Tcp,
+    /// One ICMP echo via the system `ping` binary
+    Ping,
+}
+
+impl CheckKind {
+    pub fn parse(kind: &str) -> Result<Self> {
+        match kind {
+            "http" => Ok(Self::Http),
+            "tcp" => Ok(Self::Tcp),
+            "ping" => Ok(Self::Ping),
+            other => bail!("Unknown monitor check kind '{other}' (expected http, tcp or ping)"),
+        }
+    }
+}
+
+/// Run one probe; `Err` carries the failure reason shown in alerts.
+pub async fn probe(
+    client: &reqwest::Client,
+    kind: CheckKind,
+    target: &str,
+    timeout: Duration,
+    expected_status: Option<u16>,
+) -> Result<()> {
+    match kind {
+        CheckKind::Http => {
+            let resp = client
+                .get(target)
+                .timeout(timeout)
+                .send()
+                .await
+                .context("request failed")?;
+            let status = resp.status();
+            let ok = match expected_status {
+                Some(expected) => status.as_u16() == expected,
+                None => status.is_success(),
+            };
+            if !ok {
+                bail!("HTTP {status}");
+            }
+            Ok(())
+        }
+        CheckKind::Tcp => {
+            tokio::time::timeout(timeout, tokio::net::TcpStream::connect(target))
+                .await
+                .map_err(|_| anyhow::anyhow!("connect timed out"))?
+                .context("connect failed")?;
+            Ok(())
+        }
+        CheckKind::Ping => {
+            let wait = timeout.as_secs().max(1).to_string();
+            let output = tokio::process::Command::new("ping")
+                .args(["-c", "1", "-W", wait.as_str(), target])
+                .output()
+                .await
+                .context("failed to run ping")?;
+            if !output.status.success() {
+                bail!("no reply");
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tcp_probe_detects_open_and_closed_ports() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let client = reqwest::Client::new();
+        let timeout = Duration::from_secs(2);
+        assert!(probe(&client, CheckKind::Tcp, &addr, timeout, None)
+            .await
+            .is_ok());
+        drop(listener);
+        assert!(probe(&client, CheckKind::Tcp, &addr, timeout, None)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn kinds_parse() {
+        assert_eq!(CheckKind::parse("http").unwrap(), CheckKind::Http);
+        assert!(CheckKind::parse("dns").is_err());
+    }
+}
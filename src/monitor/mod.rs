@@ -0,0 +1,252 @@
+//! Uptime monitor: HTTP, TCP and ping checks that alert through channels.
+//!
+//! Each `[[monitor.checks]]` entry is probed on its own interval. A check
+//! goes down after `failure_threshold` consecutive failures and back up
+//! after `recovery_threshold` consecutive successes; only those state
+//! changes are sent to the `[[monitor.alerts]]` targets.
+
+mod checks;
+
+pub use checks::{probe, CheckKind};
+
+use crate::channels::control::ChannelControl;
+use crate::channels::priority::Priority;
+use crate::config::schema::{MonitorAlertConfig, MonitorCheckConfig, MonitorConfig};
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// No verdict yet
+    Unknown,
+    Up,
+    Down,
+}
+
+/// A state change worth alerting about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transition {
+    Down { error: String },
+    Recovered { downtime: Duration },
+}
+
+/// Debounced up/down state of one check.
+#[derive(Debug)]
+pub struct CheckState {
+    status: CheckStatus,
+    failures: u32,
+    successes: u32,
+    down_since: Option<Instant>,
+    failure_threshold: u32,
+    recovery_threshold: u32,
+}
+
+impl CheckState {
+    pub fn new(failure_threshold: u32, recovery_threshold: u32) -> Self {
+        Self {
+            status: CheckStatus::Unknown,
+            failures: 0,
+            successes: 0,
+            down_since: None,
+            failure_threshold: failure_threshold.max(1),
+            recovery_threshold: recovery_threshold.max(1),
+        }
+    }
+
+    pub fn status(&self) -> CheckStatus {
+        self.status
+    }
+
+    /// Feed one probe result; returns the transition it caused, if any.
+    /// Coming up from `Unknown` is not alerted.
+    pub fn record(&mut self, result: Result<(), String>) -> Option<Transition> {
+        match result {
+            Ok(()) => {
+                self.failures = 0;
+                self.successes = self.successes.saturating_add(1);
+                match self.status {
+                    CheckStatus::Unknown => {
+                        self.status = CheckStatus::Up;
+                        None
+                    }
+                    CheckStatus::Down if self.successes >= self.recovery_threshold => {
+                        self.status = CheckStatus::Up;
+                        let downtime = self.down_since.take().map(|t| t.elapsed());
+                        Some(Transition::Recovered {
+                            downtime: downtime.unwrap_or_default(),
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            Err(error) => {
+                self.successes = 0;
+                self.failures = self.failures.saturating_add(1);
+                if self.status != CheckStatus::Down && self.failures >= self.failure_threshold {
+                    self.status = CheckStatus::Down;
+                    self.down_since = Some(Instant::now());
+                    Some(Transition::Down { error })
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// `1h 5m`-style rendering of a downtime.
+fn format_downtime(downtime: Duration) -> String {
+    let secs = downtime.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s}s"),
+        (h, m, _) => format!("{h}h {m}m"),
+    }
+}
+
+/// Alert text for a transition of check `name` probing `target`.
+pub fn render_transition(name: &str, target: &str, transition: &Transition) -> String {
+    match transition {
+        Transition::Down { error } => format!("🔴 {name} is DOWN ({target}): {error}"),
+        Transition::Recovered { downtime } => format!(
+            "🟢 {name} is back UP ({target}) after {}",
+            format_downtime(*downtime)
+        ),
+    }
+}
+
+async fn send_alert(
+    control: &ChannelControl,
+    alerts: &[MonitorAlertConfig],
+    check: &str,
+    text: &str,
+) {
+    for alert in alerts {
+        if !alert.checks.is_empty() && !alert.checks.iter().any(|c| c == check) {
+            continue;
+        }
+        let priority = if alert.urgent {
+            Priority::Urgent
+        } else {
+            Priority::Normal
+        };
+        if let Err(e) = control
+            .send_with_priority(&alert.channel, &alert.recipient, text, priority)
+            .await
+        {
+            tracing::warn!(
+                "Monitor: failed to alert {} about {check}: {e}",
+                alert.channel
+            );
+        }
+    }
+}
+
+/// Run every configured check until the process stops.
+pub async fn run_monitor(config: MonitorConfig, control: Arc<ChannelControl>) -> Result<()> {
+    let kinds = config
+        .checks
+        .iter()
+        .map(|check| CheckKind::parse(&check.kind))
+        .collect::<Result<Vec<_>>>()?;
+    let client = reqwest::Client::new();
+    let alerts = Arc::new(config.alerts);
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (check, kind) in config.checks.into_iter().zip(kinds) {
+        let client = client.clone();
+        let control = Arc::clone(&control);
+        let alerts = Arc::clone(&alerts);
+        let interval = check.interval_secs.unwrap_or(config.interval_secs).max(5);
+        let timeout = Duration::from_secs(check.timeout_secs.unwrap_or(config.timeout_secs));
+        let mut state = CheckState::new(
+            check.failure_threshold.unwrap_or(config.failure_threshold),
+            config.recovery_threshold,
+        );
+        tasks.spawn(async move {
+            let MonitorCheckConfig {
+                name,
+                target,
+                expected_status,
+                ..
+            } = check;
+            let component = format!("monitor:{name}");
+            let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+            loop {
+                ticker.tick().await;
+                let result = probe(&client, kind, &target, timeout, expected_status)
+                    .await
+                    .map_err(|e| format!("{e:#}"));
+                match &result {
+                    Ok(()) => crate::health::mark_component_ok(&component),
+                    Err(e) => crate::health::mark_component_error(&component, e.clone()),
+                }
+                if let Some(transition) = state.record(result) {
+                    tracing::info!("Monitor {name}: {transition:?}");
+                    let text = render_transition(&name, &target, &transition);
+                    send_alert(&control, &alerts, &name, &text).await;
+                }
+            }
+        });
+    }
+
+    while tasks.join_next().await.is_some() {}
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_debounces_failures_and_recoveries() {
+        let mut state = CheckState::new(2, 2);
+        assert_eq!(state.record(Ok(())), None);
+        assert_eq!(state.status(), CheckStatus::Up);
+
+        assert_eq!(state.record(Err("timeout".into())), None);
+        assert_eq!(
+            state.record(Err("timeout".into())),
+            Some(Transition::Down {
+                error: "timeout".into()
+            })
+        );
+        assert_eq!(state.record(Err("timeout".into())), None);
+
+        assert_eq!(state.record(Ok(())), None);
+        assert!(matches!(
+            state.record(Ok(())),
+            Some(Transition::Recovered { .. })
+        ));
+        assert_eq!(state.status(), CheckStatus::Up);
+    }
+
+    #[test]
+    fn first_failures_alert_from_unknown() {
+        let mut state = CheckState::new(1, 1);
+        assert!(matches!(
+            state.record(Err("refused".into())),
+            Some(Transition::Down { .. })
+        ));
+    }
+
+    #[test]
+    fn transitions_render() {
+        let down = Transition::Down {
+            error: "HTTP 503".into(),
+        };
+        assert_eq!(
+            render_transition("api", "https://api", &down),
+            "🔴 api is DOWN (https://api): HTTP 503"
+        );
+        let up = Transition::Recovered {
+            downtime: Duration::from_secs(3725),
+        };
+        assert_eq!(
+            render_transition("api", "https://api", &up),
+            "🟢 api is back UP (https://api) after 1h 2m"
+        );
+        assert_eq!(format_downtime(Duration::from_secs(75)), "1m 15s");
+    }
+}
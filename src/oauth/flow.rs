@@ -0,0 +1,259 @@
+//! Interactive sign-in: authorization code with PKCE through a local
+//! redirect listener, or the device authorization grant (RFC 8628).
+
+use super::{OAuthClient, OAuthError, TokenSet};
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// How long the redirect listener waits for the browser.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Poll interval when the server doesn't give one.
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+
+fn base64url(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// A PKCE code verifier and its S256 challenge (RFC 7636).
+fn pkce_pair() -> (String, String) {
+    let verifier = base64url(&rand::random::<[u8; 32]>());
+    let challenge = base64url(&Sha256::digest(verifier.as_bytes()));
+    (verifier, challenge)
+}
+
+impl OAuthClient {
+    /// Where the browser is sent to sign in.
+    pub fn authorize_url(&self, state: &str, challenge: &str) -> Result<reqwest::Url> {
+        if self.endpoints.auth_url.is_empty() {
+            bail!("{}: no authorization URL; use --device", self.name);
+        }
+        let scope = self.scope();
+        let redirect_uri = self.redirect_uri();
+        let mut params = vec![
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("state", state),
+            ("code_challenge", challenge),
+            ("code_challenge_method", "S256"),
+        ];
+        if !scope.is_empty() {
+            params.push(("scope", scope.as_str()));
+        }
+        reqwest::Url::parse_with_params(&self.endpoints.auth_url, &params)
+            .with_context(|| format!("Invalid auth_url of {}", self.name))
+    }
+}
+
+/// The `code` of a redirect request (`GET /callback?code=..&state=..`).
+fn parse_callback(request: &str, expected_state: &str) -> Result<String> {
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("GET "))
+        .and_then(|rest| rest.split(' ').next())
+        .context("Not a redirect request")?;
+    let url = reqwest::Url::parse(&format!("http://127.0.0.1{target}"))?;
+    if url.path() != "/callback" {
+        bail!("Unexpected request to {}", url.path());
+    }
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    if let Some(code) = param("error") {
+        return Err(OAuthError {
+            code,
+            description: param("error_description").unwrap_or_default(),
+        }
+        .into());
+    }
+    if param("state").as_deref() != Some(expected_state) {
+        bail!("Redirect state does not match; sign in again");
+    }
+    param("code").context("Redirect has no code")
+}
+
+async fn wait_for_callback(listener: &TcpListener, state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = vec![0u8; 8192];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        // Browsers also ask for /favicon.ico; only the callback counts.
+        if !request.starts_with("GET /callback") {
+            let _ = stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .await;
+            continue;
+        }
+        let result = parse_callback(&request, state);
+        let page = match &result {
+            Ok(_) => "Signed in to ZeroClaw. You can close this window.".to_string(),
+            Err(e) => format!("Sign-in failed: {e}"),
+        };
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{page}",
+            page.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        return result;
+    }
+}
+
+/// Sign in through the browser: print the authorization URL, take the
+/// redirect on `127.0.0.1:<redirect_port>/callback`, and exchange its code.
+pub async fn login_with_browser(client: &OAuthClient) -> Result<TokenSet> {
+    let (verifier, challenge) = pkce_pair();
+    let state = base64url(&rand::random::<[u8; 16]>());
+    let url = client.authorize_url(&state, &challenge)?;
+    let listener = TcpListener::bind(("127.0.0.1", client.redirect_port))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to listen on port {} for the redirect; set redirect_port",
+                client.redirect_port
+            )
+        })?;
+
+    println!("Open this URL to sign in to {}:", client.name);
+    println!();
+    println!("  {url}");
+    println!();
+    println!("Waiting for the redirect to {} ...", client.redirect_uri());
+
+    let code = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_callback(&listener, &state))
+        .await
+        .context("Timed out waiting for the sign-in redirect")??;
+    client.exchange_code(&code, &verifier).await
+}
+
+/// Device authorization response (RFC 8628 §3.2).
+#[derive(Debug, Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    #[serde(alias = "verification_url")]
+    verification_uri: String,
+    expires_in: u64,
+    interval: Option<u64>,
+}
+
+/// Sign in on another device: print the verification URL and user code,
+/// then poll the token endpoint until the user approves.
+pub async fn login_with_device_code(client: &OAuthClient) -> Result<TokenSet> {
+    let Some(device_auth_url) = &client.endpoints.device_auth_url else {
+        bail!(
+            "{} does not support device sign-in; run `zeroclaw oauth login {}` without --device",
+            client.name,
+            client.name
+        );
+    };
+    let mut form = vec![("client_id", client.client_id.clone())];
+    let scope = client.scope();
+    if !scope.is_empty() {
+        form.push(("scope", scope));
+    }
+    let device: DeviceAuthorization = client
+        .http
+        .post(device_auth_url)
+        .form(&form)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Invalid device authorization response")?;
+
+    println!(
+        "Open {} and enter the code {} to sign in to {}.",
+        device.verification_uri, device.user_code, client.name
+    );
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(device.expires_in);
+    let mut interval = device.interval.unwrap_or(DEFAULT_POLL_INTERVAL_SECS);
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if tokio::time::Instant::now() >= deadline {
+            bail!("The device code expired before sign-in finished");
+        }
+        let result = client
+            .token_request(vec![
+                (
+                    "grant_type",
+                    "urn:ietf:params:oauth:grant-type:device_code".into(),
+                ),
+                ("device_code", device.device_code.clone()),
+            ])
+            .await;
+        match result {
+            Ok(tokens) => return Ok(tokens),
+            Err(e) => {
+                let code = e.downcast_ref::<OAuthError>().map(|e| e.code.clone());
+                match code.as_deref() {
+                    Some("authorization_pending") => {}
+                    Some("slow_down") => interval += 5,
+                    _ => return Err(e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::OAuthClientConfig;
+
+    #[test]
+    fn authorize_url_carries_pkce_and_redirect() {
+        let client = OAuthClient::from_config(
+            "slack",
+            &OAuthClientConfig {
+                provider: Some("slack".into()),
+                instance: None,
+                tenant: None,
+                client_id: "123.456".into(),
+                client_secret: None,
+                auth_url: None,
+                token_url: None,
+                device_auth_url: None,
+                scopes: vec!["chat:write".into(), "channels:history".into()],
+                redirect_port: 8765,
+            },
+            |_| unreachable!(),
+        )
+        .unwrap();
+        let (verifier, challenge) = pkce_pair();
+        assert_eq!(verifier.len(), 43);
+        let url = client.authorize_url("st4te", &challenge).unwrap();
+        let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(url.host_str(), Some("slack.com"));
+        assert_eq!(query["redirect_uri"], "http://127.0.0.1:8765/callback");
+        assert_eq!(query["code_challenge"], challenge);
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["scope"], "chat:write,channels:history");
+        assert_eq!(query["state"], "st4te");
+    }
+
+    #[test]
+    fn parses_redirect_requests() {
+        let request = "GET /callback?code=abc%2F1&state=st4te HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n";
+        assert_eq!(parse_callback(request, "st4te").unwrap(), "abc/1");
+        assert!(parse_callback(request, "other").is_err());
+
+        let denied = "GET /callback?error=access_denied&error_description=nope HTTP/1.1\r\n\r\n";
+        let err = parse_callback(denied, "st4te").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<OAuthError>().unwrap().code,
+            "access_denied"
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! OAuth2 for channels that act with user-delegated tokens (Slack user
+//! tokens, Mastodon, Microsoft Teams).
+//!
+//! An operator signs a configured client (`[oauth.<name>]`) in once with
+//! `zeroclaw oauth login <name>`: in a browser through a local redirect
+//! listener (authorization code with PKCE), or with `--device` on a
+//! headless host (device authorization grant). The tokens are kept in a
+//! [`TokenStore`], encrypted. Channels then get access tokens from an
+//! [`OAuthTokenSource`] behind a [`TokenCache`], which refreshes them with
+//! the refresh token before they expire and saves rotated refresh tokens
+//! back to the store.
+
+pub mod flow;
+pub mod store;
+
+pub use store::TokenStore;
+
+use crate::channels::http::token::{IssuedToken, TokenCache, TokenSource};
+use crate::channels::traits::unix_now;
+use crate::config::schema::OAuthClientConfig;
+use crate::config::Config;
+use crate::security::ManagedSecret;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Stored access tokens this close to expiry are refreshed before use.
+const REFRESH_MARGIN_SECS: u64 = 300;
+
+/// How long a token without an expiry is used before the store is read
+/// again (picking up a new sign-in).
+const NON_EXPIRING_RECHECK_SECS: u64 = 24 * 60 * 60;
+
+/// Tokens of a signed-in client.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenSet {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Unix seconds; `None` for tokens that don't expire
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+impl TokenSet {
+    fn expires_within(&self, now: u64, secs: u64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now + secs)
+    }
+}
+
+/// An error response of a token endpoint (RFC 6749 §5.2).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{code}: {description}")]
+pub struct OAuthError {
+    pub code: String,
+    pub description: String,
+}
+
+/// Endpoints of an authorization server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthEndpoints {
+    pub auth_url: String,
+    pub token_url: String,
+    pub device_auth_url: Option<String>,
+    /// Slack separates scopes with commas, everyone else with spaces
+    pub scope_separator: &'static str,
+}
+
+impl OAuthEndpoints {
+    /// The preset of `config.provider`, with URLs set in `config` taking
+    /// precedence.
+    pub fn from_config(config: &OAuthClientConfig) -> Result<Self> {
+        let mut endpoints = match config.provider.as_deref() {
+            Some("slack") => Self {
+                auth_url: "https://slack.com/oauth/v2/authorize".into(),
+                token_url: "https://slack.com/api/oauth.v2.access".into(),
+                device_auth_url: None,
+                scope_separator: ",",
+            },
+            Some("mastodon") => {
+                let instance = config
+                    .instance
+                    .as_deref()
+                    .context("Mastodon OAuth clients need `instance`")?
+                    .trim_end_matches('/');
+                Self {
+                    auth_url: format!("{instance}/oauth/authorize"),
+                    token_url: format!("{instance}/oauth/token"),
+                    device_auth_url: None,
+                    scope_separator: " ",
+                }
+            }
+            Some("microsoft") => {
+                let base = format!(
+                    "https://login.microsoftonline.com/{}/oauth2/v2.0",
+                    config.tenant.as_deref().unwrap_or("common")
+                );
+                Self {
+                    auth_url: format!("{base}/authorize"),
+                    token_url: format!("{base}/token"),
+                    device_auth_url: Some(format!("{base}/devicecode")),
+                    scope_separator: " ",
+                }
+            }
+            Some(other) => bail!("Unknown OAuth provider '{other}'"),
+            None => Self {
+                auth_url: String::new(),
+                token_url: String::new(),
+                device_auth_url: None,
+                scope_separator: " ",
+            },
+        };
+        if let Some(url) = &config.auth_url {
+            endpoints.auth_url.clone_from(url);
+        }
+        if let Some(url) = &config.token_url {
+            endpoints.token_url.clone_from(url);
+        }
+        if config.device_auth_url.is_some() {
+            endpoints
+                .device_auth_url
+                .clone_from(&config.device_auth_url);
+        }
+        if endpoints.token_url.is_empty() {
+            bail!("OAuth client needs `provider` or `token_url`");
+        }
+        Ok(endpoints)
+    }
+}
+
+/// Token endpoint response (RFC 6749 §5.1), or an error in the same body.
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+    scope: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// A configured OAuth2 client.
+pub struct OAuthClient {
+    name: String,
+    endpoints: OAuthEndpoints,
+    client_id: String,
+    client_secret: Option<Arc<ManagedSecret>>,
+    scopes: Vec<String>,
+    redirect_port: u16,
+    http: reqwest::Client,
+}
+
+impl OAuthClient {
+    /// `resolve` turns the configured client secret reference into a
+    /// managed secret.
+    pub fn from_config(
+        name: &str,
+        config: &OAuthClientConfig,
+        resolve: impl Fn(&str) -> Result<Arc<ManagedSecret>>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            endpoints: OAuthEndpoints::from_config(config)
+                .with_context(|| format!("Invalid [oauth.{name}]"))?,
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.as_deref().map(resolve).transpose()?,
+            scopes: config.scopes.clone(),
+            redirect_port: config.redirect_port,
+            http: crate::channels::net::http_client(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn redirect_uri(&self) -> String {
+        format!("http://127.0.0.1:{}/callback", self.redirect_port)
+    }
+
+    fn scope(&self) -> String {
+        self.scopes.join(self.endpoints.scope_separator)
+    }
+
+    /// Exchange an authorization code from the redirect.
+    pub async fn exchange_code(&self, code: &str, verifier: &str) -> Result<TokenSet> {
+        self.token_request(vec![
+            ("grant_type", "authorization_code".into()),
+            ("code", code.into()),
+            ("redirect_uri", self.redirect_uri()),
+            ("code_verifier", verifier.into()),
+        ])
+        .await
+    }
+
+    /// New tokens for `tokens`' refresh token. Servers that don't rotate
+    /// refresh tokens keep the old one.
+    pub async fn refresh(&self, tokens: &TokenSet) -> Result<TokenSet> {
+        let refresh_token = tokens
+            .refresh_token
+            .clone()
+            .with_context(|| format!("{}: no refresh token; sign in again", self.name))?;
+        let mut fresh = self
+            .token_request(vec![
+                ("grant_type", "refresh_token".into()),
+                ("refresh_token", refresh_token.clone()),
+            ])
+            .await?;
+        fresh.refresh_token.get_or_insert(refresh_token);
+        Ok(fresh)
+    }
+
+    async fn token_request(&self, mut form: Vec<(&'static str, String)>) -> Result<TokenSet> {
+        form.push(("client_id", self.client_id.clone()));
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret.get().await?));
+        }
+        let response: TokenResponse = self
+            .http
+            .post(&self.endpoints.token_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await?
+            .json()
+            .await
+            .context("Invalid token endpoint response")?;
+        parse_token_response(response, unix_now())
+    }
+}
+
+fn parse_token_response(response: TokenResponse, now: u64) -> Result<TokenSet> {
+    if let Some(code) = response.error {
+        return Err(OAuthError {
+            code,
+            description: response.error_description.unwrap_or_default(),
+        }
+        .into());
+    }
+    let access_token = response
+        .access_token
+        .context("Token endpoint returned no access token")?;
+    crate::security::redact::register_secret(&access_token);
+    if let Some(refresh_token) = &response.refresh_token {
+        crate::security::redact::register_secret(refresh_token);
+    }
+    Ok(TokenSet {
+        access_token,
+        refresh_token: response.refresh_token,
+        expires_at: response.expires_in.map(|secs| now + secs),
+        scope: response.scope,
+    })
+}
+
+/// Access tokens of a signed-in client, for a channel's [`TokenCache`].
+/// Tokens close to expiry are refreshed and saved back to the store.
+pub struct OAuthTokenSource {
+    client: OAuthClient,
+    store: TokenStore,
+}
+
+impl OAuthTokenSource {
+    pub fn new(client: OAuthClient, store: TokenStore) -> Self {
+        Self { client, store }
+    }
+
+    /// The source of `[oauth.<name>]`, with tokens in the store of `config`.
+    pub fn from_config(config: &Config, name: &str) -> Result<Self> {
+        Ok(Self::new(
+            client_from_config(config, name)?,
+            store_for(config),
+        ))
+    }
+}
+
+#[async_trait]
+impl TokenSource for OAuthTokenSource {
+    async fn fetch(&self) -> Result<IssuedToken> {
+        let name = self.client.name();
+        let mut tokens = self.store.load(name)?.with_context(|| {
+            format!("Not signed in to OAuth client '{name}'; run `zeroclaw oauth login {name}`")
+        })?;
+        let now = unix_now();
+        if tokens.expires_within(now, REFRESH_MARGIN_SECS) {
+            tokens = self.client.refresh(&tokens).await?;
+            self.store.save(name, &tokens)?;
+        }
+        Ok(IssuedToken {
+            lifetime_secs: tokens
+                .expires_at
+                .map_or(NON_EXPIRING_RECHECK_SECS, |at| at.saturating_sub(now)),
+            token: tokens.access_token,
+        })
+    }
+}
+
+/// Refreshed access tokens of `[oauth.<name>]`, for a channel to use.
+pub fn token_cache(config: &Config, name: &str) -> Result<Arc<TokenCache<OAuthTokenSource>>> {
+    Ok(Arc::new(TokenCache::new(OAuthTokenSource::from_config(
+        config, name,
+    )?)))
+}
+
+fn zeroclaw_dir(config: &Config) -> PathBuf {
+    config
+        .config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), std::path::Path::to_path_buf)
+}
+
+fn store_for(config: &Config) -> TokenStore {
+    TokenStore::new(&zeroclaw_dir(config), config.secrets.encrypt)
+}
+
+fn client_from_config(config: &Config, name: &str) -> Result<OAuthClient> {
+    let client_config = config
+        .oauth
+        .get(name)
+        .with_context(|| format!("No [oauth.{name}] client is configured"))?;
+    OAuthClient::from_config(name, client_config, |raw| {
+        crate::channels::resolve_channel_secret(config, raw)
+    })
+}
+
+pub async fn handle_command(command: crate::OAuthCommands, config: &Config) -> Result<()> {
+    let store = store_for(config);
+    match command {
+        crate::OAuthCommands::Login { name, device } => {
+            let client = client_from_config(config, &name)?;
+            let tokens = if device {
+                flow::login_with_device_code(&client).await?
+            } else {
+                flow::login_with_browser(&client).await?
+            };
+            store.save(&name, &tokens)?;
+            println!("✅ Signed in to {name}.");
+            Ok(())
+        }
+        crate::OAuthCommands::Status => {
+            let mut names: Vec<&String> = config.oauth.keys().collect();
+            if names.is_empty() {
+                println!("No [oauth.<name>] clients are configured.");
+            }
+            names.sort();
+            let now = unix_now();
+            for name in names {
+                let status = match store.load(name)? {
+                    None => "not signed in".to_string(),
+                    Some(tokens) => match tokens.expires_at {
+                        None => "signed in".to_string(),
+                        Some(at) if at > now => {
+                            format!("signed in, token valid {}m", (at - now) / 60)
+                        }
+                        Some(_) if tokens.refresh_token.is_some() => {
+                            "signed in, token expired (refreshed on next use)".to_string()
+                        }
+                        Some(_) => "token expired; sign in again".to_string(),
+                    },
+                };
+                println!("  {name}: {status}");
+            }
+            Ok(())
+        }
+        crate::OAuthCommands::Logout { name } => {
+            if store.remove(&name)? {
+                println!("Signed out of {name}.");
+            } else {
+                println!("{name} was not signed in.");
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_config(provider: Option<&str>) -> OAuthClientConfig {
+        OAuthClientConfig {
+            provider: provider.map(str::to_string),
+            instance: Some("https://mastodon.example/".into()),
+            tenant: None,
+            client_id: "client".into(),
+            client_secret: None,
+            auth_url: None,
+            token_url: None,
+            device_auth_url: None,
+            scopes: vec!["read".into(), "write".into()],
+            redirect_port: 8765,
+        }
+    }
+
+    #[test]
+    fn presets_fill_in_endpoints() {
+        let mastodon = OAuthEndpoints::from_config(&client_config(Some("mastodon"))).unwrap();
+        assert_eq!(mastodon.token_url, "https://mastodon.example/oauth/token");
+        let teams = OAuthEndpoints::from_config(&client_config(Some("microsoft"))).unwrap();
+        assert_eq!(
+            teams.device_auth_url.as_deref(),
+            Some("https://login.microsoftonline.com/common/oauth2/v2.0/devicecode")
+        );
+        let slack = OAuthEndpoints::from_config(&client_config(Some("slack"))).unwrap();
+        assert_eq!(slack.scope_separator, ",");
+
+        assert!(OAuthEndpoints::from_config(&client_config(None)).is_err());
+        assert!(OAuthEndpoints::from_config(&client_config(Some("myspace"))).is_err());
+        let mut custom = client_config(None);
+        custom.token_url = Some("https://auth.example/token".into());
+        assert_eq!(
+            OAuthEndpoints::from_config(&custom).unwrap().token_url,
+            "https://auth.example/token"
+        );
+    }
+
+    #[test]
+    fn token_responses_become_token_sets_or_errors() {
+        let ok: TokenResponse = serde_json::from_str(
+            r#"{"access_token":"at","refresh_token":"rt","expires_in":3600,"token_type":"Bearer"}"#,
+        )
+        .unwrap();
+        let tokens = parse_token_response(ok, 1_000).unwrap();
+        assert_eq!(tokens.expires_at, Some(4_600));
+        assert!(!tokens.expires_within(4_000, REFRESH_MARGIN_SECS));
+        assert!(tokens.expires_within(4_400, REFRESH_MARGIN_SECS));
+
+        let pending: TokenResponse =
+            serde_json::from_str(r#"{"error":"authorization_pending"}"#).unwrap();
+        let err = parse_token_response(pending, 1_000).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<OAuthError>().unwrap().code,
+            "authorization_pending"
+        );
+    }
+
+    #[tokio::test]
+    async fn token_source_requires_a_sign_in() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let client = OAuthClient::from_config(
+            "mastodon",
+            &client_config(Some("mastodon")),
+            |_| unreachable!(),
+        )
+        .unwrap();
+        let store = TokenStore::new(tmp.path(), false);
+        let source = OAuthTokenSource::new(client, TokenStore::new(tmp.path(), false));
+        let err = source.fetch().await.unwrap_err().to_string();
+        assert!(err.contains("zeroclaw oauth login mastodon"));
+
+        // Mastodon tokens don't expire
+        store
+            .save(
+                "mastodon",
+                &TokenSet {
+                    access_token: "at".into(),
+                    ..TokenSet::default()
+                },
+            )
+            .unwrap();
+        let issued = source.fetch().await.unwrap();
+        assert_eq!(issued.token, "at");
+        assert_eq!(issued.lifetime_secs, NON_EXPIRING_RECHECK_SECS);
+    }
+}
@@ -0,0 +1,107 @@
+//! Signed-in OAuth tokens on disk.
+
+use super::TokenSet;
+use crate::security::SecretStore;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Tokens of each OAuth client in `<zeroclaw dir>/oauth/<name>.json`,
+/// encrypted with the secret store key unless `[secrets] encrypt` is off.
+pub struct TokenStore {
+    dir: PathBuf,
+    secrets: SecretStore,
+}
+
+impl TokenStore {
+    pub fn new(zeroclaw_dir: &Path, encrypt: bool) -> Self {
+        Self {
+            dir: zeroclaw_dir.join("oauth"),
+            secrets: SecretStore::new(zeroclaw_dir, encrypt),
+        }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("Invalid OAuth client name '{name}'");
+        }
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+
+    /// The tokens of `name`, or `None` if it hasn't signed in.
+    pub fn load(&self, name: &str) -> Result<Option<TokenSet>> {
+        let path = self.path(name)?;
+        let stored = match std::fs::read_to_string(&path) {
+            Ok(stored) => stored,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let json = self
+            .secrets
+            .decrypt(stored.trim())
+            .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+        let tokens: TokenSet = serde_json::from_str(&json)
+            .with_context(|| format!("Malformed token file {}", path.display()))?;
+        crate::security::redact::register_secret(&tokens.access_token);
+        Ok(Some(tokens))
+    }
+
+    /// Replace the tokens of `name`. The file is written next to its final
+    /// path and renamed over it, readable by the owner only.
+    pub fn save(&self, name: &str, tokens: &TokenSet) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let stored = self.secrets.encrypt(&serde_json::to_string(tokens)?)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, stored)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Forget the tokens of `name`. Returns `false` if there were none.
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        match std::fs::remove_file(self.path(name)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn tokens_are_stored_encrypted() {
+        let tmp = TempDir::new().unwrap();
+        let store = TokenStore::new(tmp.path(), true);
+        let tokens = TokenSet {
+            access_token: "xoxe.xoxp-access".into(),
+            refresh_token: Some("xoxe-1-refresh".into()),
+            expires_at: Some(1_700_043_200),
+            scope: Some("chat:write".into()),
+        };
+
+        assert!(store.load("slack").unwrap().is_none());
+        store.save("slack", &tokens).unwrap();
+        let raw = std::fs::read_to_string(tmp.path().join("oauth/slack.json")).unwrap();
+        assert!(raw.starts_with("enc2:"));
+        assert!(!raw.contains("refresh"));
+        assert_eq!(store.load("slack").unwrap(), Some(tokens));
+
+        assert!(store.save("../escape", &TokenSet::default()).is_err());
+        assert!(store.remove("slack").unwrap());
+        assert!(!store.remove("slack").unwrap());
+    }
+}
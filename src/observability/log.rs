@@ -70,9 +70,15 @@ impl Observer for LogObserver {
             ObserverEvent::ChannelMessage { channel, direction } => {
                 info!(channel = %channel, direction = %direction, "channel.message");
             }
+            ObserverEvent::ChannelMessageDropped { channel, reason } => {
+                info!(channel = %channel, reason = %reason, "channel.message_dropped");
+            }
             ObserverEvent::HeartbeatTick => {
                 info!("heartbeat.tick");
             }
+            ObserverEvent::GatewayFrameMalformed { channel } => {
+                info!(channel = %channel, "gateway.frame_malformed");
+            }
             ObserverEvent::Error { component, message } => {
                 info!(component = %component, error = %message, "error");
             }
@@ -94,6 +100,10 @@ impl Observer for LogObserver {
             ObserverMetric::QueueDepth(d) => {
                 info!(depth = d, "metric.queue_depth");
             }
+            ObserverMetric::GatewayHeartbeatInterval { channel, interval } => {
+                let ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+                info!(channel = %channel, interval_ms = ms, "metric.gateway_heartbeat_interval");
+            }
         }
     }
 
@@ -154,7 +164,14 @@ mod tests {
             channel: "telegram".into(),
             direction: "outbound".into(),
         });
+        obs.record_event(&ObserverEvent::ChannelMessageDropped {
+            channel: "qq".into(),
+            reason: "drop_oldest".into(),
+        });
         obs.record_event(&ObserverEvent::HeartbeatTick);
+        obs.record_event(&ObserverEvent::GatewayFrameMalformed {
+            channel: "discord".into(),
+        });
         obs.record_event(&ObserverEvent::Error {
             component: "provider".into(),
             message: "timeout".into(),
@@ -169,5 +186,9 @@ mod tests {
         obs.record_metric(&ObserverMetric::TokensUsed(u64::MAX));
         obs.record_metric(&ObserverMetric::ActiveSessions(1));
         obs.record_metric(&ObserverMetric::QueueDepth(999));
+        obs.record_metric(&ObserverMetric::GatewayHeartbeatInterval {
+            channel: "qq".into(),
+            interval: Duration::from_millis(41_250),
+        });
     }
 }
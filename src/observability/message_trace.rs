@@ -0,0 +1,125 @@
+//! Per-message OpenTelemetry traces.
+//!
+//! Each inbound channel message gets a root `channel.message` span; the
+//! stages of its journey (`middleware.inbound`, `agent.turn`,
+//! `channel.send`) run as child spans, and the `llm.call` / `tool.call`
+//! spans the OTel observer records during the turn nest under them. Spans
+//! go to the global tracer provider, so they are exported over OTLP when the
+//! `otel` observability backend is active and cost nothing otherwise.
+
+use crate::channels::traits::ChannelMessage;
+use opentelemetry::context::FutureExt as _;
+use opentelemetry::trace::{SpanBuilder, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use std::fmt::Display;
+use std::future::Future;
+
+const TRACER_NAME: &str = "zeroclaw";
+
+/// Root span context for one inbound message.
+pub fn message_context(msg: &ChannelMessage) -> Context {
+    let span = global::tracer(TRACER_NAME).build(
+        SpanBuilder::from_name("channel.message")
+            .with_kind(SpanKind::Server)
+            .with_attributes(vec![
+                KeyValue::new("channel", msg.channel.clone()),
+                KeyValue::new("sender", msg.sender.clone()),
+                KeyValue::new("message.id", msg.id.clone()),
+                KeyValue::new(
+                    "message.timestamp",
+                    i64::try_from(msg.timestamp).unwrap_or(i64::MAX),
+                ),
+                // Platform → process delivery delay
+                KeyValue::new(
+                    "message.delay_secs",
+                    i64::try_from(msg.received_at.saturating_sub(msg.timestamp))
+                        .unwrap_or(i64::MAX),
+                ),
+            ]),
+    );
+    Context::current_with_span(span)
+}
+
+/// Run `fut` inside the message trace rooted at `cx`, ending the root span
+/// when it completes.
+pub async fn traced_message<F: Future>(cx: Context, fut: F) -> F::Output {
+    let output = fut.with_context(cx.clone()).await;
+    cx.span().end();
+    output
+}
+
+fn child_context(name: &'static str, attributes: Vec<KeyValue>) -> Context {
+    let parent = Context::current();
+    let span = global::tracer(TRACER_NAME).build_with_context(
+        SpanBuilder::from_name(name)
+            .with_kind(SpanKind::Internal)
+            .with_attributes(attributes),
+        &parent,
+    );
+    parent.with_span(span)
+}
+
+/// Run `fut` as a child span of the current message trace.
+pub async fn in_span<F: Future>(
+    name: &'static str,
+    attributes: Vec<KeyValue>,
+    fut: F,
+) -> F::Output {
+    let cx = child_context(name, attributes);
+    let output = fut.with_context(cx.clone()).await;
+    cx.span().end();
+    output
+}
+
+/// [`in_span`] for fallible stages: an `Err` marks the span as failed.
+pub async fn in_span_result<T, E, F>(
+    name: &'static str,
+    attributes: Vec<KeyValue>,
+    fut: F,
+) -> Result<T, E>
+where
+    E: Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let cx = child_context(name, attributes);
+    let output = fut.with_context(cx.clone()).await;
+    let span = cx.span();
+    match &output {
+        Ok(_) => span.set_status(Status::Ok),
+        Err(e) => span.set_status(Status::error(e.to_string())),
+    }
+    span.end();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> ChannelMessage {
+        ChannelMessage {
+            id: "m1".into(),
+            timestamp: 1_700_000_000,
+            received_at: 1_700_000_000,
+            ..ChannelMessage::test("telegram", "alice", "hi")
+        }
+    }
+
+    #[tokio::test]
+    async fn stages_pass_outputs_through() {
+        let cx = message_context(&message());
+        let result = traced_message(cx, async {
+            let doubled = in_span("agent.turn", vec![], async { 21 * 2 }).await;
+            in_span_result::<_, String, _>("channel.send", vec![], async move { Ok(doubled) }).await
+        })
+        .await;
+        assert_eq!(result, Ok(42));
+    }
+
+    #[tokio::test]
+    async fn failed_stage_returns_error() {
+        let result: Result<(), String> =
+            in_span_result("channel.send", vec![], async { Err("boom".into()) }).await;
+        assert_eq!(result.unwrap_err(), "boom");
+    }
+}
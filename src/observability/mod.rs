@@ -1,4 +1,5 @@
 pub mod log;
+pub mod message_trace;
 pub mod multi;
 pub mod noop;
 pub mod otel;
@@ -20,12 +20,15 @@ pub struct OtelObserver {
     tool_calls: Counter<u64>,
     tool_duration: Histogram<f64>,
     channel_messages: Counter<u64>,
+    channel_messages_dropped: Counter<u64>,
     heartbeat_ticks: Counter<u64>,
+    gateway_malformed_frames: Counter<u64>,
     errors: Counter<u64>,
     request_latency: Histogram<f64>,
     tokens_used: Counter<u64>,
     active_sessions: Gauge<u64>,
     queue_depth: Gauge<u64>,
+    gateway_heartbeat_interval: Gauge<f64>,
 }
 
 impl OtelObserver {
@@ -118,11 +121,21 @@ impl OtelObserver {
             .with_description("Total channel messages")
             .build();
 
+        let channel_messages_dropped = meter
+            .u64_counter("zeroclaw.channel.messages_dropped")
+            .with_description("Inbound channel messages discarded by queue overflow policy")
+            .build();
+
         let heartbeat_ticks = meter
             .u64_counter("zeroclaw.heartbeat.ticks")
             .with_description("Total heartbeat ticks")
             .build();
 
+        let gateway_malformed_frames = meter
+            .u64_counter("zeroclaw.gateway.malformed_frames")
+            .with_description("Gateway frames and payloads skipped because of their shape")
+            .build();
+
         let errors = meter
             .u64_counter("zeroclaw.errors")
             .with_description("Total errors by component")
@@ -149,6 +162,12 @@ impl OtelObserver {
             .with_description("Current message queue depth")
             .build();
 
+        let gateway_heartbeat_interval = meter
+            .f64_gauge("zeroclaw.gateway.heartbeat_interval")
+            .with_description("Heartbeat interval requested by the gateway, in seconds")
+            .with_unit("s")
+            .build();
+
         Ok(Self {
             tracer_provider,
             meter_provider: meter_provider_clone,
@@ -159,12 +178,15 @@ impl OtelObserver {
             tool_calls,
             tool_duration,
             channel_messages,
+            channel_messages_dropped,
             heartbeat_ticks,
+            gateway_malformed_frames,
             errors,
             request_latency,
             tokens_used,
             active_sessions,
             queue_depth,
+            gateway_heartbeat_interval,
         })
     }
 }
@@ -299,9 +321,22 @@ impl Observer for OtelObserver {
                     ],
                 );
             }
+            ObserverEvent::ChannelMessageDropped { channel, reason } => {
+                self.channel_messages_dropped.add(
+                    1,
+                    &[
+                        KeyValue::new("channel", channel.clone()),
+                        KeyValue::new("reason", reason.clone()),
+                    ],
+                );
+            }
             ObserverEvent::HeartbeatTick => {
                 self.heartbeat_ticks.add(1, &[]);
             }
+            ObserverEvent::GatewayFrameMalformed { channel } => {
+                self.gateway_malformed_frames
+                    .add(1, &[KeyValue::new("channel", channel.clone())]);
+            }
             ObserverEvent::Error { component, message } => {
                 // Create an error span for visibility in trace backends
                 let mut span = tracer.build(
@@ -335,6 +370,12 @@ impl Observer for OtelObserver {
             ObserverMetric::QueueDepth(d) => {
                 self.queue_depth.record(*d as u64, &[]);
             }
+            ObserverMetric::GatewayHeartbeatInterval { channel, interval } => {
+                self.gateway_heartbeat_interval.record(
+                    interval.as_secs_f64(),
+                    &[KeyValue::new("channel", channel.clone())],
+                );
+            }
         }
     }
 
@@ -423,7 +464,14 @@ mod tests {
             channel: "telegram".into(),
             direction: "inbound".into(),
         });
+        obs.record_event(&ObserverEvent::ChannelMessageDropped {
+            channel: "qq".into(),
+            reason: "drop_new".into(),
+        });
         obs.record_event(&ObserverEvent::HeartbeatTick);
+        obs.record_event(&ObserverEvent::GatewayFrameMalformed {
+            channel: "discord".into(),
+        });
         obs.record_event(&ObserverEvent::Error {
             component: "provider".into(),
             message: "timeout".into(),
@@ -438,6 +486,10 @@ mod tests {
         obs.record_metric(&ObserverMetric::TokensUsed(0));
         obs.record_metric(&ObserverMetric::ActiveSessions(3));
         obs.record_metric(&ObserverMetric::QueueDepth(42));
+        obs.record_metric(&ObserverMetric::GatewayHeartbeatInterval {
+            channel: "qq".into(),
+            interval: Duration::from_millis(41_250),
+        });
     }
 
     #[test]
@@ -44,7 +44,16 @@ pub enum ObserverEvent {
         channel: String,
         direction: String,
     },
+    /// An inbound channel message was discarded by the queue overflow policy.
+    ChannelMessageDropped {
+        channel: String,
+        reason: String,
+    },
     HeartbeatTick,
+    /// A gateway frame or event payload was skipped because of its shape.
+    GatewayFrameMalformed {
+        channel: String,
+    },
     Error {
         component: String,
         message: String,
@@ -58,6 +67,11 @@ pub enum ObserverMetric {
     TokensUsed(u64),
     ActiveSessions(u64),
     QueueDepth(u64),
+    /// Heartbeat interval a gateway asked for in its last Hello.
+    GatewayHeartbeatInterval {
+        channel: String,
+        interval: Duration,
+    },
 }
 
 /// Core observability trait — implement for any backend
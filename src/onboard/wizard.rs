@@ -1,8 +1,12 @@
-use crate::config::schema::{DingTalkConfig, IrcConfig, QQConfig, WhatsAppConfig};
+use crate::config::schema::{
+    AnnouncementsConfig, ApiEnvironment, DingTalkConfig, DispatchConfig, DryRunConfig,
+    FailoverConfig, HttpClientsConfig, InboundQueueConfig, IrcConfig, NetworkTimeoutsConfig,
+    PriorityConfig, QQConfig, QuietHoursConfig, SessionConfig, StreamingConfig, WhatsAppConfig,
+};
 use crate::config::{
     AutonomyConfig, BrowserConfig, ChannelsConfig, ComposioConfig, Config, DiscordConfig,
-    HeartbeatConfig, IMessageConfig, MatrixConfig, MemoryConfig, ObservabilityConfig,
-    RuntimeConfig, SecretsConfig, SlackConfig, TelegramConfig, WebhookConfig,
+    GatewayCompression, HeartbeatConfig, IMessageConfig, MatrixConfig, MemoryConfig,
+    ObservabilityConfig, RuntimeConfig, SecretsConfig, SlackConfig, TelegramConfig, WebhookConfig,
 };
 use crate::hardware::{self, HardwareConfig};
 use crate::memory::{
@@ -123,12 +127,42 @@ pub fn run_wizard() -> Result<Config> {
         memory: memory_config, // User-selected memory backend
         tunnel: tunnel_config,
         gateway: crate::config::GatewayConfig::default(),
+        admin: crate::config::AdminConfig::default(),
+        grpc: crate::config::GrpcConfig::default(),
+        bus: crate::config::BusConfig::default(),
+        cluster: crate::config::ClusterConfig::default(),
+        plugins: crate::config::PluginsConfig::default(),
+        middleware: crate::config::MiddlewareConfig::default(),
+        transcription: crate::config::TranscriptionConfig::default(),
+        tts: crate::config::TtsConfig::default(),
+        vision: crate::config::VisionConfig::default(),
+        image_generation: crate::config::ImageGenerationConfig::default(),
+        knowledge: crate::config::KnowledgeConfig::default(),
+        web_search: crate::config::WebSearchConfig::default(),
+        feeds: crate::config::FeedsConfig::default(),
+        ingress: crate::config::IngressConfig::default(),
+        hooks: crate::config::HooksConfig::default(),
+        monitor: crate::config::MonitorConfig::default(),
+        logging: crate::config::LoggingConfig::default(),
+        ops: crate::config::OpsConfig::default(),
+        analytics: crate::config::AnalyticsConfig::default(),
+        uploads: crate::config::UploadsConfig::default(),
+        media_store: crate::config::MediaStoreConfig::default(),
+        kv: crate::config::KvConfig::default(),
+        preferences: crate::config::PreferencesConfig::default(),
+        reminders: crate::config::RemindersConfig::default(),
+        groups: crate::config::GroupsConfig::default(),
+        personas: crate::config::PersonasConfig::default(),
         composio: composio_config,
         secrets: secrets_config,
+        security: crate::config::SecurityConfig::default(),
+        oauth: std::collections::HashMap::new(),
         browser: BrowserConfig::default(),
         http_request: crate::config::HttpRequestConfig::default(),
         identity: crate::config::IdentityConfig::default(),
+        identity_linking: crate::config::IdentityLinkingConfig::default(),
         cost: crate::config::CostConfig::default(),
+        quotas: crate::config::QuotaConfig::default(),
         peripherals: crate::config::PeripheralsConfig::default(),
         agents: std::collections::HashMap::new(),
         hardware: hardware_config,
@@ -159,7 +193,9 @@ pub fn run_wizard() -> Result<Config> {
         || config.channels_config.matrix.is_some()
         || config.channels_config.email.is_some()
         || config.channels_config.dingtalk.is_some()
-        || config.channels_config.qq.is_some();
+        || config.channels_config.qq.is_some()
+        || config.channels_config.nostr.is_some()
+        || config.channels_config.local_socket.is_some();
 
     if has_channels && config.api_key.is_some() {
         let launch: bool = Confirm::new()
@@ -217,7 +253,9 @@ pub fn run_channels_repair_wizard() -> Result<Config> {
         || config.channels_config.matrix.is_some()
         || config.channels_config.email.is_some()
         || config.channels_config.dingtalk.is_some()
-        || config.channels_config.qq.is_some();
+        || config.channels_config.qq.is_some()
+        || config.channels_config.nostr.is_some()
+        || config.channels_config.local_socket.is_some();
 
     if has_channels && config.api_key.is_some() {
         let launch: bool = Confirm::new()
@@ -282,6 +320,7 @@ fn memory_config_defaults_for_backend(backend: &str) -> MemoryConfig {
         snapshot_enabled: false,
         snapshot_on_hygiene: false,
         auto_hydrate: true,
+        postgres_url: None,
     }
 }
 
@@ -339,12 +378,42 @@ pub fn run_quick_setup(
         memory: memory_config,
         tunnel: crate::config::TunnelConfig::default(),
         gateway: crate::config::GatewayConfig::default(),
+        admin: crate::config::AdminConfig::default(),
+        grpc: crate::config::GrpcConfig::default(),
+        bus: crate::config::BusConfig::default(),
+        cluster: crate::config::ClusterConfig::default(),
+        plugins: crate::config::PluginsConfig::default(),
+        middleware: crate::config::MiddlewareConfig::default(),
+        transcription: crate::config::TranscriptionConfig::default(),
+        tts: crate::config::TtsConfig::default(),
+        vision: crate::config::VisionConfig::default(),
+        image_generation: crate::config::ImageGenerationConfig::default(),
+        knowledge: crate::config::KnowledgeConfig::default(),
+        web_search: crate::config::WebSearchConfig::default(),
+        feeds: crate::config::FeedsConfig::default(),
+        ingress: crate::config::IngressConfig::default(),
+        hooks: crate::config::HooksConfig::default(),
+        monitor: crate::config::MonitorConfig::default(),
+        logging: crate::config::LoggingConfig::default(),
+        ops: crate::config::OpsConfig::default(),
+        analytics: crate::config::AnalyticsConfig::default(),
+        uploads: crate::config::UploadsConfig::default(),
+        media_store: crate::config::MediaStoreConfig::default(),
+        kv: crate::config::KvConfig::default(),
+        preferences: crate::config::PreferencesConfig::default(),
+        reminders: crate::config::RemindersConfig::default(),
+        groups: crate::config::GroupsConfig::default(),
+        personas: crate::config::PersonasConfig::default(),
         composio: ComposioConfig::default(),
         secrets: SecretsConfig::default(),
+        security: crate::config::SecurityConfig::default(),
+        oauth: std::collections::HashMap::new(),
         browser: BrowserConfig::default(),
         http_request: crate::config::HttpRequestConfig::default(),
         identity: crate::config::IdentityConfig::default(),
+        identity_linking: crate::config::IdentityLinkingConfig::default(),
         cost: crate::config::CostConfig::default(),
+        quotas: crate::config::QuotaConfig::default(),
         peripherals: crate::config::PeripheralsConfig::default(),
         agents: std::collections::HashMap::new(),
         hardware: crate::config::HardwareConfig::default(),
@@ -2063,7 +2132,10 @@ fn setup_tool_mode() -> Result<(ComposioConfig, SecretsConfig)> {
         .default(true)
         .interact()?;
 
-    let secrets_config = SecretsConfig { encrypt };
+    let secrets_config = SecretsConfig {
+        encrypt,
+        ..SecretsConfig::default()
+    };
 
     if encrypt {
         println!(
@@ -2430,6 +2502,22 @@ fn setup_channels() -> Result<ChannelsConfig> {
         lark: None,
         dingtalk: None,
         qq: None,
+        nostr: None,
+        local_socket: None,
+        inbound_queue: InboundQueueConfig::default(),
+        sessions: SessionConfig::default(),
+        accounts: Vec::new(),
+        tags: std::collections::HashMap::new(),
+        failover: FailoverConfig::default(),
+        timeouts: NetworkTimeoutsConfig::default(),
+        http: HttpClientsConfig::default(),
+        streaming: StreamingConfig::default(),
+        dispatch: DispatchConfig::default(),
+        priority: PriorityConfig::default(),
+        quiet_hours: QuietHoursConfig::default(),
+        announcements: AnnouncementsConfig::default(),
+        dry_run: DryRunConfig::default(),
+        shadows: Vec::new(),
     };
 
     loop {
@@ -2715,6 +2803,8 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     allowed_users,
                     listen_to_bots: false,
                     mention_only: false,
+                    heartbeat_jitter: true,
+                    compression: GatewayCompression::None,
                 });
             }
             2 => {
@@ -2840,6 +2930,8 @@ fn setup_channels() -> Result<ChannelsConfig> {
                         Some(channel)
                     },
                     allowed_users,
+                    oauth: None,
+                    signing_secret: None,
                 });
             }
             3 => {
@@ -3058,6 +3150,8 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     verify_token: verify_token.trim().to_string(),
                     app_secret: None, // Can be set via ZEROCLAW_WHATSAPP_APP_SECRET env var
                     allowed_numbers,
+                    session_template: None,
+                    template_language: "en_US".into(),
                 });
             }
             6 => {
@@ -3225,6 +3319,7 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     } else {
                         Some(secret)
                     },
+                    signing_secret: None,
                 });
                 println!(
                     "  {} Webhook on port {}",
@@ -3376,6 +3471,11 @@ fn setup_channels() -> Result<ChannelsConfig> {
                     app_id,
                     app_secret,
                     allowed_users,
+                    heartbeat_jitter: true,
+                    environment: ApiEnvironment::default(),
+                    api_base: None,
+                    gateway_url: None,
+                    callback: false,
                 });
             }
             _ => break, // Done
@@ -3870,7 +3970,9 @@ fn print_summary(config: &Config) {
         || config.channels_config.matrix.is_some()
         || config.channels_config.email.is_some()
         || config.channels_config.dingtalk.is_some()
-        || config.channels_config.qq.is_some();
+        || config.channels_config.qq.is_some()
+        || config.channels_config.nostr.is_some()
+        || config.channels_config.local_socket.is_some();
 
     println!();
     println!(
@@ -0,0 +1,210 @@
+//! Operator notifications about internal failures.
+//!
+//! Components report trouble with [`report`]: failed sends, listener
+//! reconnects, token refresh failures and panics. When `[ops]` is enabled a
+//! background task drains the reports every `interval_secs`, keeps what is
+//! worth an operator's attention (repeated send failures, reconnect storms,
+//! every token failure and panic) and delivers one summary to the ops
+//! channel — so at most one message per interval, however noisy things get.
+
+use crate::channels::control::ChannelControl;
+use crate::channels::manager::BroadcastTarget;
+use crate::config::schema::OpsConfig;
+use crate::security::redact::redact;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Once};
+use std::time::Duration;
+
+/// Reports buffered between flushes; the oldest are dropped beyond this.
+const MAX_PENDING_REPORTS: usize = 1000;
+
+/// Longest error detail kept per summary line.
+const MAX_DETAIL_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OpsEventKind {
+    SendFailure,
+    Reconnect,
+    TokenRefresh,
+    Panic,
+}
+
+impl OpsEventKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::SendFailure => "send failures",
+            Self::Reconnect => "reconnects",
+            Self::TokenRefresh => "token refresh failures",
+            Self::Panic => "panics",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpsEvent {
+    pub kind: OpsEventKind,
+    /// Channel or component the event came from
+    pub source: String,
+    pub detail: String,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PENDING: LazyLock<Mutex<VecDeque<OpsEvent>>> = LazyLock::new(|| Mutex::new(VecDeque::new()));
+static PANIC_HOOK: Once = Once::new();
+
+/// Report an event for the next ops summary. A no-op unless the ops
+/// notifier is running.
+#[allow(clippy::needless_pass_by_value)]
+pub fn report(kind: OpsEventKind, source: &str, detail: impl ToString) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut pending = PENDING.lock();
+    if pending.len() >= MAX_PENDING_REPORTS {
+        pending.pop_front();
+    }
+    pending.push_back(OpsEvent {
+        kind,
+        source: source.to_string(),
+        detail: redact(&detail.to_string()),
+    });
+}
+
+fn take_pending() -> Vec<OpsEvent> {
+    PENDING.lock().drain(..).collect()
+}
+
+/// Summary of one interval's events, or `None` if nothing crossed its
+/// threshold.
+pub fn summarize(events: &[OpsEvent], config: &OpsConfig) -> Option<String> {
+    let mut groups: BTreeMap<(OpsEventKind, &str), (u32, &str)> = BTreeMap::new();
+    for event in events {
+        let entry = groups
+            .entry((event.kind, event.source.as_str()))
+            .or_insert((0, ""));
+        entry.0 += 1;
+        entry.1 = &event.detail;
+    }
+
+    let mut lines = Vec::new();
+    for ((kind, source), (count, last)) in groups {
+        let threshold = match kind {
+            OpsEventKind::SendFailure => config.send_failure_threshold,
+            OpsEventKind::Reconnect => config.reconnect_threshold,
+            OpsEventKind::TokenRefresh | OpsEventKind::Panic => 1,
+        };
+        if count < threshold.max(1) {
+            continue;
+        }
+        let last = crate::util::truncate_with_ellipsis(last, MAX_DETAIL_CHARS);
+        lines.push(format!(
+            "• {source}: {count} {} (last: {last})",
+            kind.label()
+        ));
+    }
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut text = format!("⚠️ Ops: problems in the last {}s", config.interval_secs);
+    for line in lines {
+        let _ = write!(text, "\n{line}");
+    }
+    Some(text)
+}
+
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let thread = std::thread::current();
+            report(
+                OpsEventKind::Panic,
+                thread.name().unwrap_or("unnamed thread"),
+                info,
+            );
+            previous(info);
+        }));
+    });
+}
+
+/// Deliver ops summaries until the process stops.
+pub async fn run_ops_notifier(config: OpsConfig, control: Arc<ChannelControl>) {
+    ENABLED.store(true, Ordering::Relaxed);
+    install_panic_hook();
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.interval_secs.max(10)));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let Some(summary) = summarize(&take_pending(), &config) else {
+            continue;
+        };
+        let Some(manager) = control.manager() else {
+            continue;
+        };
+        // Straight through the manager rather than `ChannelControl::send`:
+        // a failing ops channel must not feed its own failures back into the
+        // next summary, and mutes should not hold operator alerts.
+        let target = BroadcastTarget::new(&config.channel, &config.recipient);
+        for target in manager.expand(&target) {
+            if let Err(e) = manager
+                .send(&target.channel, &summary, &target.recipient)
+                .await
+            {
+                tracing::warn!("Failed to deliver ops summary to {}: {e}", target.channel);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: OpsEventKind, source: &str, detail: &str) -> OpsEvent {
+        OpsEvent {
+            kind,
+            source: source.into(),
+            detail: detail.into(),
+        }
+    }
+
+    fn config() -> OpsConfig {
+        OpsConfig {
+            enabled: true,
+            channel: "telegram".into(),
+            recipient: "42".into(),
+            interval_secs: 300,
+            send_failure_threshold: 3,
+            reconnect_threshold: 2,
+        }
+    }
+
+    #[test]
+    fn summary_keeps_only_events_over_threshold() {
+        let events = vec![
+            event(OpsEventKind::SendFailure, "qq", "500"),
+            event(OpsEventKind::SendFailure, "qq", "502"),
+            event(OpsEventKind::SendFailure, "qq", "503"),
+            event(OpsEventKind::SendFailure, "discord", "429"),
+            event(OpsEventKind::Reconnect, "discord", "reset"),
+            event(OpsEventKind::Panic, "tokio-runtime-worker", "boom"),
+        ];
+        assert_eq!(
+            summarize(&events, &config()).unwrap(),
+            "⚠️ Ops: problems in the last 300s\n\
+             • qq: 3 send failures (last: 503)\n\
+             • tokio-runtime-worker: 1 panics (last: boom)"
+        );
+    }
+
+    #[test]
+    fn quiet_interval_sends_nothing() {
+        let events = vec![event(OpsEventKind::Reconnect, "qq", "reset")];
+        assert!(summarize(&events, &config()).is_none());
+        assert!(summarize(&[], &config()).is_none());
+    }
+}
@@ -0,0 +1,236 @@
+//! Named agent personas from `[personas]`.
+//!
+//! - `/persona` — your current persona and the ones available
+//! - `/persona <name>` — switch persona
+//! - `/persona default` — back to the channel's persona
+//!
+//! The effective persona of a conversation is the user's choice (stored
+//! under their id in the `personas` namespace of the [`crate::kv`] store),
+//! else the persona configured for the channel instance, else
+//! `[personas] default`. Its prompt is rendered with [`PersonaVars`] and
+//! added to the agent's system prompt.
+
+use crate::config::{PersonaConfig, PersonasConfig};
+use crate::kv::KvNamespace;
+use anyhow::{bail, Result};
+
+/// Values interpolated into persona prompts.
+#[derive(Debug, Clone, Copy)]
+pub struct PersonaVars<'a> {
+    /// `{user}`: the display name, else the sender
+    pub user: &'a str,
+    /// `{user_id}`
+    pub user_id: &'a str,
+    /// `{channel}`: the channel instance name
+    pub channel: &'a str,
+}
+
+/// Replace `{user}`, `{user_id}` and `{channel}` in `prompt`. Other braces
+/// are left alone.
+pub fn render(prompt: &str, vars: &PersonaVars) -> String {
+    prompt
+        .replace("{user}", vars.user)
+        .replace("{user_id}", vars.user_id)
+        .replace("{channel}", vars.channel)
+}
+
+/// Configured personas plus users' choices.
+pub struct PersonaStore {
+    /// `None` when the KV store is off; users can't switch then
+    kv: Option<KvNamespace>,
+    config: PersonasConfig,
+}
+
+impl PersonaStore {
+    pub fn new(kv: Option<KvNamespace>, config: PersonasConfig) -> Self {
+        for (channel, name) in &config.channels {
+            if !config.prompts.contains_key(name) {
+                tracing::warn!("Channel {channel} uses unknown persona {name:?}");
+            }
+        }
+        Self { kv, config }
+    }
+
+    /// The persona `user` picked, if it still exists.
+    pub fn selected(&self, user: &str) -> Result<Option<String>> {
+        let Some(kv) = self.kv.as_ref().filter(|_| self.config.user_selectable) else {
+            return Ok(None);
+        };
+        Ok(kv
+            .get(user)?
+            .filter(|name| self.config.prompts.contains_key(name)))
+    }
+
+    /// Name and settings of the persona `user` gets on `channel`.
+    pub fn resolve(&self, channel: &str, user: &str) -> Option<(&str, &PersonaConfig)> {
+        let selected = self.selected(user).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load the persona of {user}: {e}");
+            None
+        });
+        let persona = [
+            selected.as_deref(),
+            self.config.channels.get(channel).map(String::as_str),
+            self.config.default.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|name| self.config.prompts.get_key_value(name))
+        .map(|(name, persona)| (name.as_str(), persona));
+        persona
+    }
+
+    /// The rendered prompt of the persona `vars.user_id` gets on
+    /// `vars.channel`, for the user stored under `user`.
+    pub fn prompt(&self, user: &str, vars: &PersonaVars) -> Option<String> {
+        self.resolve(vars.channel, user)
+            .map(|(_, persona)| render(&persona.prompt, vars))
+    }
+
+    /// Switch `user` to the persona `name`; `"default"` clears their choice.
+    pub fn select(&self, user: &str, name: &str) -> Result<()> {
+        let Some(kv) = &self.kv else {
+            bail!("Switching personas needs the [kv] store");
+        };
+        if !self.config.user_selectable {
+            bail!("Personas can't be switched here");
+        }
+        if name.eq_ignore_ascii_case("default") {
+            kv.delete(user)?;
+            return Ok(());
+        }
+        if !self.config.prompts.contains_key(name) {
+            bail!("Unknown persona {name:?}; /persona lists them");
+        }
+        kv.set(user, name, None)
+    }
+
+    /// Delete `user`'s persona choice. Returns `false` if they made none.
+    pub fn forget(&self, user: &str) -> Result<bool> {
+        match &self.kv {
+            Some(kv) => kv.delete(user),
+            None => Ok(false),
+        }
+    }
+
+    fn describe(&self, channel: &str, user: &str) -> String {
+        let current = self.resolve(channel, user).map_or("none", |(name, _)| name);
+        let mut names: Vec<&String> = self.config.prompts.keys().collect();
+        names.sort();
+        let available: Vec<String> = names
+            .into_iter()
+            .map(|name| match &self.config.prompts[name].description {
+                Some(description) => format!("• {name} — {description}"),
+                None => format!("• {name}"),
+            })
+            .collect();
+        if available.is_empty() {
+            return "No personas are configured.".to_string();
+        }
+        format!(
+            "🎭 Persona: {current}\n{}\nSwitch with /persona <name>, or /persona default.",
+            available.join("\n")
+        )
+    }
+
+    /// Answer `/persona`. Returns `None` for any other message.
+    pub fn handle_command(&self, channel: &str, user: &str, content: &str) -> Option<String> {
+        let mut words = content.split_whitespace();
+        let command = words.next()?.split('@').next()?;
+        if command != "/persona" {
+            return None;
+        }
+        let reply = match words.next() {
+            None => self.describe(channel, user),
+            Some(name) => match self.select(user, name) {
+                Ok(()) => match self.resolve(channel, user) {
+                    Some((current, _)) => format!("🎭 Persona is now {current}"),
+                    None => "🎭 Persona cleared".to_string(),
+                },
+                Err(e) => format!("⚠️ {e}"),
+            },
+        };
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv::KvStore;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn persona(prompt: &str) -> PersonaConfig {
+        PersonaConfig {
+            prompt: prompt.to_string(),
+            description: None,
+        }
+    }
+
+    fn store() -> PersonaStore {
+        let kv = Arc::new(KvStore::in_memory().unwrap());
+        PersonaStore::new(
+            Some(kv.namespace("personas")),
+            PersonasConfig {
+                default: Some("assistant".into()),
+                prompts: HashMap::from([
+                    ("assistant".to_string(), persona("Be helpful.")),
+                    (
+                        "pirate".to_string(),
+                        persona("Talk like a pirate to {user} on {channel}."),
+                    ),
+                    ("support".to_string(), persona("You are {channel} support.")),
+                ]),
+                channels: HashMap::from([("qq-support".to_string(), "support".to_string())]),
+                ..PersonasConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn user_choice_wins_over_channel_and_default() {
+        let store = store();
+        assert_eq!(store.resolve("telegram", "alice").unwrap().0, "assistant");
+        assert_eq!(store.resolve("qq-support", "alice").unwrap().0, "support");
+
+        assert_eq!(
+            store
+                .handle_command("qq-support", "alice", "/persona@zeroclaw_bot pirate")
+                .unwrap(),
+            "🎭 Persona is now pirate"
+        );
+        let vars = PersonaVars {
+            user: "Alice",
+            user_id: "42",
+            channel: "qq-support",
+        };
+        assert_eq!(
+            store.prompt("alice", &vars).as_deref(),
+            Some("Talk like a pirate to Alice on qq-support.")
+        );
+
+        store.select("alice", "default").unwrap();
+        assert_eq!(store.resolve("qq-support", "alice").unwrap().0, "support");
+        assert!(store
+            .handle_command("telegram", "alice", "/persona ninja")
+            .unwrap()
+            .contains("Unknown persona"));
+        assert!(store
+            .handle_command("telegram", "alice", "/persona")
+            .unwrap()
+            .contains("• pirate"));
+    }
+
+    #[test]
+    fn switching_can_be_disabled() {
+        let store = PersonaStore::new(
+            None,
+            PersonasConfig {
+                prompts: HashMap::from([("pirate".to_string(), persona("Arr."))]),
+                ..PersonasConfig::default()
+            },
+        );
+        assert!(store.select("alice", "pirate").is_err());
+        assert!(store.resolve("telegram", "alice").is_none());
+    }
+}
@@ -0,0 +1,575 @@
+//! Lua message hooks (mlua, Lua 5.4).
+//!
+//! Every `.lua` file in the plugins directory is a hook script. A script
+//! defines a global `on_message(msg)` that receives the inbound message as a
+//! table (`id`, `sender`, `reply_target`, `content`, `channel`, `timestamp`)
+//! and returns:
+//!
+//! - `nil` or `true` — pass the message through unchanged
+//! - `false` — drop it
+//! - a string — rewrite the content and continue
+//! - `{ reply = "..." }` — answer the sender instead of calling the agent
+//! - `{ content = "..." }` — same as returning a string
+//!
+//! Scripts can call back into the runtime through the `zeroclaw` table:
+//!
+//! - `zeroclaw.send(channel, recipient, text)` — send a message (queued and
+//!   delivered once `on_message` returns; muted channels hold it in the outbox)
+//! - `zeroclaw.log(level, text)` — `"debug"`, `"info"`, `"warn"` or `"error"`
+//! - `zeroclaw.kv_get(key)`, `zeroclaw.kv_set(key, value, ttl_secs?)`,
+//!   `zeroclaw.kv_delete(key)` and `zeroclaw.kv_incr(key, by?)` — the
+//!   script's own namespace (`lua:<script>`) of the [`crate::kv`] store, when
+//!   it is enabled
+//!
+//! Scripts run sandboxed: only the `string`, `table`, `math` and `utf8`
+//! libraries are loaded, so `os`, `io`, `package` and the file-loading base
+//! functions (`dofile`, `loadfile`) are unavailable.
+//!
+//! Scripts are hot-reloaded: each call checks the file's modification time and
+//! re-runs the script in a fresh Lua state when it changed. A script that fails
+//! to reload keeps running its previous version. Each call is capped at
+//! [`CALL_TIMEOUT`] and `[plugins] max_memory_mb` of Lua heap.
+
+use super::{FilterAction, Plugin};
+use crate::channels::control::ChannelControl;
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::PluginsConfig;
+use crate::kv::{KvNamespace, KvStore};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib, Table, Value, VmState};
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Wall-clock budget for a single `on_message` call.
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Instructions between timeout checks.
+const HOOK_INSTRUCTION_INTERVAL: u32 = 10_000;
+
+/// Message queued by `zeroclaw.send` during a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PendingSend {
+    channel: String,
+    recipient: String,
+    text: String,
+}
+
+/// One loaded version of a script.
+struct Script {
+    lua: Lua,
+    modified: Option<SystemTime>,
+    sends: Arc<Mutex<Vec<PendingSend>>>,
+}
+
+impl Script {
+    fn load(
+        name: &str,
+        path: &Path,
+        memory_limit: usize,
+        kv: Option<&KvNamespace>,
+    ) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        let lua = sandboxed_lua().map_err(lua_error)?;
+        lua.set_memory_limit(memory_limit).map_err(lua_error)?;
+        let sends = Arc::new(Mutex::new(Vec::new()));
+        install_host_api(&lua, name, &sends, kv).map_err(lua_error)?;
+        lua.load(&source)
+            .set_name(name)
+            .exec()
+            .map_err(lua_error)
+            .with_context(|| format!("failed to run {}", path.display()))?;
+
+        Ok(Self {
+            lua,
+            modified,
+            sends,
+        })
+    }
+
+    /// Run `on_message`, returning the verdict and any queued sends.
+    fn on_message(&self, msg: &ChannelMessage) -> Result<(FilterAction, Vec<PendingSend>)> {
+        let Some(handler) = self
+            .lua
+            .globals()
+            .get::<Option<Function>>("on_message")
+            .map_err(lua_error)?
+        else {
+            return Ok((FilterAction::Pass, Vec::new()));
+        };
+
+        let deadline = Instant::now() + CALL_TIMEOUT;
+        self.lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INSTRUCTION_INTERVAL),
+            move |_lua, _debug| {
+                if Instant::now() > deadline {
+                    Err(mlua::Error::runtime("on_message exceeded its time budget"))
+                } else {
+                    Ok(VmState::Continue)
+                }
+            },
+        );
+        let result = message_table(&self.lua, msg)
+            .and_then(|table| handler.call::<Value>(table))
+            .and_then(|value| parse_verdict(&value));
+        self.lua.remove_hook();
+
+        // Sends queued before a failure are discarded with the call.
+        let sends = std::mem::take(&mut *self.sends.lock());
+        Ok((result.map_err(lua_error)?, sends))
+    }
+}
+
+/// A Lua state without the libraries that reach the filesystem or processes.
+fn sandboxed_lua() -> mlua::Result<Lua> {
+    let lua = Lua::new_with(
+        StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8,
+        LuaOptions::default(),
+    )?;
+    let globals = lua.globals();
+    globals.set("dofile", Value::Nil)?;
+    globals.set("loadfile", Value::Nil)?;
+    Ok(lua)
+}
+
+fn lua_error(e: mlua::Error) -> anyhow::Error {
+    anyhow::anyhow!("{e}")
+}
+
+fn kv_error(e: anyhow::Error) -> mlua::Error {
+    mlua::Error::runtime(format!("{e:#}"))
+}
+
+fn install_host_api(
+    lua: &Lua,
+    name: &str,
+    sends: &Arc<Mutex<Vec<PendingSend>>>,
+    kv: Option<&KvNamespace>,
+) -> mlua::Result<()> {
+    let api = lua.create_table()?;
+
+    let queue = Arc::clone(sends);
+    api.set(
+        "send",
+        lua.create_function(
+            move |_, (channel, recipient, text): (String, String, String)| {
+                queue.lock().push(PendingSend {
+                    channel,
+                    recipient,
+                    text,
+                });
+                Ok(())
+            },
+        )?,
+    )?;
+
+    let script = name.to_string();
+    api.set(
+        "log",
+        lua.create_function(move |_, (level, text): (String, String)| {
+            match level.as_str() {
+                "debug" => tracing::debug!("[lua:{script}] {text}"),
+                "warn" => tracing::warn!("[lua:{script}] {text}"),
+                "error" => tracing::error!("[lua:{script}] {text}"),
+                _ => tracing::info!("[lua:{script}] {text}"),
+            }
+            Ok(())
+        })?,
+    )?;
+
+    if let Some(kv) = kv {
+        install_kv_api(lua, &api, kv)?;
+    }
+
+    lua.globals().set("zeroclaw", api)
+}
+
+fn install_kv_api(lua: &Lua, api: &Table, kv: &KvNamespace) -> mlua::Result<()> {
+    let store = kv.clone();
+    api.set(
+        "kv_get",
+        lua.create_function(move |_, key: String| store.get(&key).map_err(kv_error))?,
+    )?;
+    let store = kv.clone();
+    api.set(
+        "kv_set",
+        lua.create_function(
+            move |_, (key, value, ttl_secs): (String, String, Option<u64>)| {
+                let ttl = ttl_secs.filter(|secs| *secs > 0).map(Duration::from_secs);
+                store.set(&key, &value, ttl).map_err(kv_error)
+            },
+        )?,
+    )?;
+    let store = kv.clone();
+    api.set(
+        "kv_delete",
+        lua.create_function(move |_, key: String| store.delete(&key).map_err(kv_error))?,
+    )?;
+    let store = kv.clone();
+    api.set(
+        "kv_incr",
+        lua.create_function(move |_, (key, by): (String, Option<i64>)| {
+            store.incr(&key, by.unwrap_or(1)).map_err(kv_error)
+        })?,
+    )
+}
+
+fn message_table(lua: &Lua, msg: &ChannelMessage) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+    table.set("id", msg.id.as_str())?;
+    table.set("sender", msg.sender.as_str())?;
+    table.set("reply_target", msg.reply_target.as_str())?;
+    table.set("content", msg.content.as_str())?;
+    table.set("channel", msg.channel.as_str())?;
+    table.set("timestamp", msg.timestamp)?;
+    Ok(table)
+}
+
+fn parse_verdict(value: &Value) -> mlua::Result<FilterAction> {
+    match value {
+        Value::Nil | Value::Boolean(true) => Ok(FilterAction::Pass),
+        Value::Boolean(false) => Ok(FilterAction::Drop),
+        Value::String(s) => Ok(FilterAction::Rewrite(s.to_str()?.to_string())),
+        Value::Table(table) => {
+            if let Some(reply) = table.get::<Option<String>>("reply")? {
+                Ok(FilterAction::Reply(reply))
+            } else if let Some(content) = table.get::<Option<String>>("content")? {
+                Ok(FilterAction::Rewrite(content))
+            } else {
+                Ok(FilterAction::Pass)
+            }
+        }
+        other => Err(mlua::Error::runtime(format!(
+            "on_message returned unsupported {}",
+            other.type_name()
+        ))),
+    }
+}
+
+pub struct LuaPlugin {
+    name: String,
+    path: PathBuf,
+    memory_limit: usize,
+    script: Arc<Mutex<Script>>,
+    control: Arc<ChannelControl>,
+    kv: Option<KvNamespace>,
+}
+
+impl LuaPlugin {
+    /// Load the script at `path`; with `kv`, it gets the `zeroclaw.kv_*`
+    /// functions.
+    pub fn load(
+        path: &Path,
+        config: &PluginsConfig,
+        control: Arc<ChannelControl>,
+        kv: Option<&Arc<KvStore>>,
+    ) -> Result<Self> {
+        let name = path.file_stem().map_or_else(
+            || "script".to_string(),
+            |s| s.to_string_lossy().into_owned(),
+        );
+        let memory_limit = config.max_memory_mb.saturating_mul(1024 * 1024);
+        let kv = kv.map(|store| store.namespace(format!("lua:{name}")));
+        let script = Script::load(&name, path, memory_limit, kv.as_ref())?;
+        Ok(Self {
+            name,
+            path: path.to_path_buf(),
+            memory_limit,
+            script: Arc::new(Mutex::new(script)),
+            control,
+            kv,
+        })
+    }
+}
+
+/// Re-run the script if its file changed since it was loaded.
+fn reload_if_changed(
+    script: &mut Script,
+    name: &str,
+    path: &Path,
+    memory_limit: usize,
+    kv: Option<&KvNamespace>,
+) {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    if modified.is_none() || modified == script.modified {
+        return;
+    }
+    match Script::load(name, path, memory_limit, kv) {
+        Ok(fresh) => {
+            tracing::info!("Reloaded Lua hook {name} from {}", path.display());
+            *script = fresh;
+        }
+        Err(e) => {
+            tracing::warn!("Lua hook {name} failed to reload; keeping previous version: {e:#}");
+            // Don't retry until the file changes again.
+            script.modified = modified;
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for LuaPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn has_filter(&self) -> bool {
+        // A hot reload may add `on_message` later; scripts without it pass.
+        true
+    }
+
+    async fn filter(&self, msg: &ChannelMessage) -> Result<FilterAction> {
+        let script = Arc::clone(&self.script);
+        let name = self.name.clone();
+        let path = self.path.clone();
+        let memory_limit = self.memory_limit;
+        let kv = self.kv.clone();
+        let msg = msg.clone();
+
+        let (action, sends) = tokio::task::spawn_blocking(move || {
+            let mut script = script.lock();
+            reload_if_changed(&mut script, &name, &path, memory_limit, kv.as_ref());
+            script.on_message(&msg)
+        })
+        .await
+        .context("Lua hook panicked")??;
+
+        for send in sends {
+            if let Err(e) = self
+                .control
+                .send(&send.channel, &send.recipient, &send.text)
+                .await
+            {
+                tracing::warn!(
+                    "Lua hook {} failed to send to {}: {e}",
+                    self.name,
+                    send.channel
+                );
+            }
+        }
+        Ok(action)
+    }
+}
+
+/// Load every `.lua` script in `dir`, in file-name order.
+pub fn load_dir(
+    dir: &Path,
+    config: &PluginsConfig,
+    control: &Arc<ChannelControl>,
+) -> Vec<Arc<dyn Plugin>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+        .collect();
+    paths.sort();
+    let kv = crate::kv::global();
+
+    paths
+        .into_iter()
+        .filter_map(
+            |path| match LuaPlugin::load(&path, config, Arc::clone(control), kv.as_ref()) {
+                Ok(plugin) => {
+                    tracing::info!("Loaded Lua hook {} from {}", plugin.name(), path.display());
+                    Some(Arc::new(plugin) as Arc<dyn Plugin>)
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping Lua hook {}: {e:#}", path.display());
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::manager::ChannelManager;
+    use crate::channels::traits::Channel;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "qq"
+        }
+
+        async fn send(&self, message: &str, recipient: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .push((message.to_string(), recipient.to_string()));
+            Ok(())
+        }
+
+        async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn msg(content: &str) -> ChannelMessage {
+        ChannelMessage::test("qq", "alice", content)
+    }
+
+    fn load(dir: &Path, source: &str) -> (LuaPlugin, Arc<RecordingChannel>) {
+        let path = dir.join("hook.lua");
+        std::fs::write(&path, source).unwrap();
+        let channel = Arc::new(RecordingChannel::default());
+        let control = Arc::new(ChannelControl::new());
+        let channels: Vec<Arc<dyn Channel>> = vec![channel.clone()];
+        control.attach(ChannelManager::from_channels(&channels));
+        let plugin = LuaPlugin::load(&path, &PluginsConfig::default(), control, None).unwrap();
+        (plugin, channel)
+    }
+
+    #[tokio::test]
+    async fn on_message_can_pass_drop_rewrite_and_reply() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plugin, _) = load(
+            dir.path(),
+            r#"
+            function on_message(msg)
+              if msg.content:find("spam") then return false end
+              if msg.content == "/ping" then return { reply = "pong " .. msg.sender } end
+              if msg.content:find("colour") then return (msg.content:gsub("colour", "color")) end
+              return nil
+            end
+            "#,
+        );
+
+        assert_eq!(
+            plugin.filter(&msg("hello")).await.unwrap(),
+            FilterAction::Pass
+        );
+        assert_eq!(
+            plugin.filter(&msg("spam!")).await.unwrap(),
+            FilterAction::Drop
+        );
+        assert_eq!(
+            plugin.filter(&msg("colour")).await.unwrap(),
+            FilterAction::Rewrite("color".into())
+        );
+        assert_eq!(
+            plugin.filter(&msg("/ping")).await.unwrap(),
+            FilterAction::Reply("pong alice".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn send_callback_delivers_through_channels() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plugin, channel) = load(
+            dir.path(),
+            r#"
+            function on_message(msg)
+              zeroclaw.send("qq", "ops", "saw: " .. msg.content)
+            end
+            "#,
+        );
+
+        plugin.filter(&msg("hi")).await.unwrap();
+        assert_eq!(
+            channel.sent.lock().as_slice(),
+            [("saw: hi".to_string(), "ops".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn script_changes_are_hot_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plugin, _) = load(dir.path(), "function on_message(msg) return 'v1' end");
+        assert_eq!(
+            plugin.filter(&msg("x")).await.unwrap(),
+            FilterAction::Rewrite("v1".into())
+        );
+
+        // Force a distinct mtime even on coarse-grained filesystems.
+        let path = dir.path().join("hook.lua");
+        std::fs::write(&path, "function on_message(msg) return 'v2' end").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(
+            plugin.filter(&msg("x")).await.unwrap(),
+            FilterAction::Rewrite("v2".into())
+        );
+
+        // A broken edit keeps the previous version running.
+        std::fs::write(&path, "function on_message(").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later + Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(
+            plugin.filter(&msg("x")).await.unwrap(),
+            FilterAction::Rewrite("v2".into())
+        );
+    }
+
+    #[test]
+    fn kv_functions_use_the_script_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter.lua");
+        std::fs::write(
+            &path,
+            r#"
+            function on_message(msg)
+              zeroclaw.kv_set("last", msg.sender, 60)
+              return tostring(zeroclaw.kv_incr("seen"))
+            end
+            "#,
+        )
+        .unwrap();
+        let store = Arc::new(KvStore::in_memory().unwrap());
+        let kv = store.namespace("lua:counter");
+        let script = Script::load("counter", &path, 16 * 1024 * 1024, Some(&kv)).unwrap();
+
+        script.on_message(&msg("a")).unwrap();
+        let (action, _) = script.on_message(&msg("b")).unwrap();
+        assert_eq!(action, FilterAction::Rewrite("2".into()));
+        assert_eq!(kv.get("last").unwrap().as_deref(), Some("alice"));
+        assert_eq!(store.get("lua:other", "seen").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn scripts_cannot_reach_os_or_io() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plugin, _) = load(
+            dir.path(),
+            r#"
+            function on_message(msg)
+              return type(os) .. " " .. type(io) .. " " .. type(dofile) .. " " .. type(string.format)
+            end
+            "#,
+        );
+        assert_eq!(
+            plugin.filter(&msg("x")).await.unwrap(),
+            FilterAction::Rewrite("nil nil nil function".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn runaway_script_hits_time_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plugin, _) = load(dir.path(), "function on_message(msg) while true do end end");
+        let err = plugin.filter(&msg("x")).await.unwrap_err();
+        assert!(err.to_string().contains("time budget"), "{err}");
+    }
+}
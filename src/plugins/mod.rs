@@ -0,0 +1,373 @@
+//! Runtime-loaded plugins that extend message handling without recompiling.
+//!
+//! A [`Plugin`] can register any of three extension points:
+//!
+//! - **message filters** — inspect every inbound message and pass, drop or
+//!   rewrite it before it reaches the agent
+//! - **command handlers** — answer `/command args` messages directly
+//! - **tools** — extra tools exposed to the LLM alongside the built-in ones
+//!
+//! Plugins are collected into a [`PluginRegistry`] by [`load_plugins`]. The
+//! sandboxed WASM backend lives in [`wasm`] (`--features wasm-plugins`);
+//! lightweight Lua `on_message` hooks live in [`lua`] (`--features lua-hooks`).
+//! Both keep state across calls in their own namespace of [`crate::kv`].
+
+#[cfg(feature = "lua-hooks")]
+pub mod lua;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm;
+
+use crate::channels::control::ChannelControl;
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::PluginsConfig;
+use crate::tools::{Tool, ToolResult, ToolSpec};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Verdict of a message filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Let the message through unchanged.
+    Pass,
+    /// Discard the message; later filters and the agent never see it.
+    Drop,
+    /// Replace the message content and continue.
+    Rewrite(String),
+    /// Answer the sender with this text instead of calling the agent.
+    Reply(String),
+}
+
+/// Outcome of running an inbound message through every plugin.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum InboundOutcome {
+    /// Hand the (possibly rewritten) message to the agent.
+    Continue(ChannelMessage),
+    /// A filter dropped the message.
+    Dropped { plugin: String },
+    /// A command handler answered; send this reply instead of calling the agent.
+    Reply { plugin: String, reply: String },
+}
+
+#[async_trait]
+pub trait Plugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether [`Plugin::filter`] should run for inbound messages.
+    fn has_filter(&self) -> bool {
+        false
+    }
+
+    /// Command names (without the leading `/`) this plugin answers.
+    fn commands(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Tools this plugin exposes to the agent.
+    fn tools(&self) -> Vec<ToolSpec> {
+        Vec::new()
+    }
+
+    async fn filter(&self, _msg: &ChannelMessage) -> Result<FilterAction> {
+        Ok(FilterAction::Pass)
+    }
+
+    /// Answer `/command args`. `None` means "not handled", so the message
+    /// continues to the agent.
+    async fn handle_command(
+        &self,
+        _command: &str,
+        _args: &str,
+        _msg: &ChannelMessage,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    async fn call_tool(&self, tool: &str, _args: serde_json::Value) -> Result<ToolResult> {
+        anyhow::bail!("plugin {} does not provide tool {tool}", self.name())
+    }
+}
+
+/// Split `/command args` into `("command", "args")`. Strips a `@botname`
+/// suffix from the command as sent by Telegram-style clients.
+fn parse_command(content: &str) -> Option<(&str, &str)> {
+    let rest = content.trim_start().strip_prefix('/')?;
+    let (head, args) = rest
+        .split_once(char::is_whitespace)
+        .map_or((rest, ""), |(head, args)| (head, args.trim()));
+    let command = head.split('@').next().unwrap_or(head);
+    (!command.is_empty()).then_some((command, args))
+}
+
+/// Ordered set of loaded plugins. Filters run in load order; a failing plugin
+/// is logged and skipped rather than blocking message handling.
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Arc<dyn Plugin>>,
+}
+
+impl PluginRegistry {
+    pub fn new(plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        Self { plugins }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|p| p.name()).collect()
+    }
+
+    /// Run filters, then command handlers, over an inbound message.
+    pub async fn process_inbound(&self, mut msg: ChannelMessage) -> InboundOutcome {
+        for plugin in self.plugins.iter().filter(|p| p.has_filter()) {
+            match plugin.filter(&msg).await {
+                Ok(FilterAction::Pass) => {}
+                Ok(FilterAction::Drop) => {
+                    return InboundOutcome::Dropped {
+                        plugin: plugin.name().to_string(),
+                    };
+                }
+                Ok(FilterAction::Rewrite(content)) => msg.content = content,
+                Ok(FilterAction::Reply(reply)) => {
+                    return InboundOutcome::Reply {
+                        plugin: plugin.name().to_string(),
+                        reply,
+                    };
+                }
+                Err(e) => tracing::warn!("Plugin {} filter failed: {e}", plugin.name()),
+            }
+        }
+
+        if let Some((command, args)) = parse_command(&msg.content) {
+            for plugin in &self.plugins {
+                if !plugin.commands().iter().any(|c| c == command) {
+                    continue;
+                }
+                match plugin.handle_command(command, args, &msg).await {
+                    Ok(Some(reply)) => {
+                        return InboundOutcome::Reply {
+                            plugin: plugin.name().to_string(),
+                            reply,
+                        };
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::warn!("Plugin {} command /{command} failed: {e}", plugin.name());
+                    }
+                }
+            }
+        }
+
+        InboundOutcome::Continue(msg)
+    }
+
+    /// Agent tools backed by plugins.
+    pub fn tools(&self) -> Vec<Box<dyn Tool>> {
+        self.plugins
+            .iter()
+            .flat_map(|plugin| {
+                plugin.tools().into_iter().map(|spec| {
+                    Box::new(PluginTool {
+                        plugin: Arc::clone(plugin),
+                        spec,
+                    }) as Box<dyn Tool>
+                })
+            })
+            .collect()
+    }
+}
+
+/// Adapts one plugin-provided tool to the [`Tool`] trait.
+struct PluginTool {
+    plugin: Arc<dyn Plugin>,
+    spec: ToolSpec,
+}
+
+#[async_trait]
+impl Tool for PluginTool {
+    fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    fn description(&self) -> &str {
+        &self.spec.description
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.spec.parameters.clone()
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult> {
+        self.plugin.call_tool(&self.spec.name, args).await
+    }
+}
+
+/// Directory plugins are loaded from: `[plugins] dir`, or `<workspace>/plugins`.
+pub fn plugins_dir(config: &PluginsConfig, workspace_dir: &Path) -> PathBuf {
+    config.dir.as_ref().map_or_else(
+        || workspace_dir.join("plugins"),
+        |dir| PathBuf::from(shellexpand::tilde(dir).as_ref()),
+    )
+}
+
+/// Load every plugin in the configured directory. Plugins that fail to load
+/// are logged and skipped. `control` lets hooks send messages of their own.
+pub fn load_plugins(
+    config: &PluginsConfig,
+    workspace_dir: &Path,
+    control: &Arc<ChannelControl>,
+) -> PluginRegistry {
+    if !config.enabled {
+        return PluginRegistry::default();
+    }
+
+    let dir = plugins_dir(config, workspace_dir);
+    #[allow(unused_mut)]
+    let mut plugins: Vec<Arc<dyn Plugin>> = Vec::new();
+
+    #[cfg(feature = "wasm-plugins")]
+    plugins.extend(wasm::load_dir(&dir, config));
+
+    #[cfg(feature = "lua-hooks")]
+    plugins.extend(lua::load_dir(&dir, config, control));
+
+    #[cfg(not(any(feature = "wasm-plugins", feature = "lua-hooks")))]
+    {
+        let _ = control;
+        if dir.is_dir() {
+            tracing::warn!(
+                "[plugins] is enabled but this build has neither the `wasm-plugins` nor the `lua-hooks` feature; skipping {}",
+                dir.display()
+            );
+        }
+    }
+
+    PluginRegistry::new(plugins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPlugin {
+        drop_word: &'static str,
+    }
+
+    #[async_trait]
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn has_filter(&self) -> bool {
+            true
+        }
+
+        fn commands(&self) -> Vec<String> {
+            vec!["ping".into()]
+        }
+
+        fn tools(&self) -> Vec<ToolSpec> {
+            vec![ToolSpec {
+                name: "echo".into(),
+                description: "Echo arguments".into(),
+                parameters: serde_json::json!({"type": "object"}),
+            }]
+        }
+
+        async fn filter(&self, msg: &ChannelMessage) -> Result<FilterAction> {
+            if msg.content.contains(self.drop_word) {
+                Ok(FilterAction::Drop)
+            } else if msg.content.contains("colour") {
+                Ok(FilterAction::Rewrite(
+                    msg.content.replace("colour", "color"),
+                ))
+            } else {
+                Ok(FilterAction::Pass)
+            }
+        }
+
+        async fn handle_command(
+            &self,
+            command: &str,
+            args: &str,
+            _msg: &ChannelMessage,
+        ) -> Result<Option<String>> {
+            Ok((command == "ping").then(|| format!("pong {args}").trim().to_string()))
+        }
+
+        async fn call_tool(&self, _tool: &str, args: serde_json::Value) -> Result<ToolResult> {
+            Ok(ToolResult {
+                success: true,
+                output: args.to_string(),
+                error: None,
+            })
+        }
+    }
+
+    fn registry() -> PluginRegistry {
+        PluginRegistry::new(vec![Arc::new(TestPlugin { drop_word: "spam" })])
+    }
+
+    fn msg(content: &str) -> ChannelMessage {
+        ChannelMessage::test("qq", "alice", content)
+    }
+
+    #[test]
+    fn parse_command_splits_name_and_args() {
+        assert_eq!(parse_command("/ping"), Some(("ping", "")));
+        assert_eq!(parse_command("/ping@bot  a b "), Some(("ping", "a b")));
+        assert_eq!(parse_command("ping"), None);
+        assert_eq!(parse_command("/"), None);
+    }
+
+    #[tokio::test]
+    async fn filters_drop_and_rewrite() {
+        let registry = registry();
+        assert!(matches!(
+            registry.process_inbound(msg("buy spam now")).await,
+            InboundOutcome::Dropped { plugin } if plugin == "test"
+        ));
+        match registry.process_inbound(msg("favourite colour")).await {
+            InboundOutcome::Continue(m) => assert_eq!(m.content, "favourite color"),
+            other => panic!("unexpected outcome: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn registered_command_replies_directly() {
+        let outcome = registry().process_inbound(msg("/ping there")).await;
+        assert!(matches!(
+            outcome,
+            InboundOutcome::Reply { plugin, reply } if plugin == "test" && reply == "pong there"
+        ));
+        // Unknown commands fall through to the agent.
+        assert!(matches!(
+            registry().process_inbound(msg("/help")).await,
+            InboundOutcome::Continue(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn plugin_tools_are_exposed_as_agent_tools() {
+        let tools = registry().tools();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name(), "echo");
+        let result = tools[0].execute(serde_json::json!({"x": 1})).await.unwrap();
+        assert_eq!(result.output, r#"{"x":1}"#);
+    }
+
+    #[test]
+    fn disabled_config_loads_nothing() {
+        let registry = load_plugins(
+            &PluginsConfig::default(),
+            Path::new("/nonexistent"),
+            &Arc::new(ChannelControl::new()),
+        );
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,624 @@
+//! Sandboxed WASM plugin backend (wasmtime).
+//!
+//! Plugins are core WASM modules (`.wasm`, or `.wat` text) loaded from the
+//! plugins directory. They get no WASI: no filesystem, network or clock
+//! access. Every call runs with a fuel budget and a memory cap from
+//! `[plugins]` config, so a misbehaving plugin traps instead of hanging or
+//! exhausting the host.
+//!
+//! # Host ABI (v1)
+//!
+//! Data crosses the boundary as UTF-8 JSON in the plugin's linear memory.
+//! Functions returning data return an `i64` packing `(ptr << 32) | len`.
+//!
+//! Required exports:
+//! - `memory`
+//! - `zc_alloc(len: i32) -> i32` — buffer for the host to write input into
+//! - `zc_manifest() -> i64` — `{"name", "filter"?, "commands"?, "tools"?}`;
+//!   `tools` entries are `{"name", "description", "parameters"}`
+//!
+//! Optional exports (required when the manifest registers the capability):
+//! - `zc_filter(ptr, len) -> i64` — input: channel message
+//!   `{"id","sender","reply_target","content","channel","timestamp"}`;
+//!   output: `{"action":"pass"}`, `{"action":"drop"}`,
+//!   `{"action":"rewrite","content":"..."}` or `{"action":"reply","content":"..."}`
+//! - `zc_command(ptr, len) -> i64` — input: `{"command","args","message"}`;
+//!   output: `{"reply": "..." | null}`
+//! - `zc_tool(ptr, len) -> i64` — input: `{"tool","args"}`;
+//!   output: `{"success","output","error"}`
+//!
+//! Host imports (module `zeroclaw`):
+//! - `log(level: i32, ptr: i32, len: i32)` — 0=debug, 1=info, 2=warn, 3+=error
+//! - `kv_get(key_ptr, key_len) -> i64` — the value, copied into a `zc_alloc`
+//!   buffer, or -1 when the key is missing or the store is disabled
+//! - `kv_set(key_ptr, key_len, value_ptr, value_len, ttl_secs: i64) -> i32` —
+//!   0 on success, -1 on failure; a `ttl_secs` of 0 never expires
+//! - `kv_delete(key_ptr, key_len) -> i32` — 1 if removed, 0 if missing, -1 on
+//!   failure
+//!
+//! The `kv_*` functions work on the plugin's own namespace (`wasm:<name>`) of
+//! the [`crate::kv`] store.
+
+use super::{FilterAction, Plugin};
+use crate::channels::traits::ChannelMessage;
+use crate::config::schema::PluginsConfig;
+use crate::kv::{KvNamespace, KvStore};
+use crate::tools::{ToolResult, ToolSpec};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use wasmtime::{
+    Caller, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    TypedFunc,
+};
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    name: String,
+    #[serde(default)]
+    filter: bool,
+    #[serde(default)]
+    commands: Vec<String>,
+    #[serde(default)]
+    tools: Vec<ToolSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum FilterResponse {
+    Pass,
+    Drop,
+    Rewrite { content: String },
+    Reply { content: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandResponse {
+    #[serde(default)]
+    reply: Option<String>,
+}
+
+struct HostState {
+    plugin: String,
+    limits: StoreLimits,
+    kv: Option<KvNamespace>,
+}
+
+/// Copy `len` bytes at `ptr` out of the calling plugin's memory.
+fn guest_bytes(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(wasmtime::Extern::into_memory)?;
+    let (start, len) = (usize::try_from(ptr).ok()?, usize::try_from(len).ok()?);
+    memory
+        .data(&*caller)
+        .get(start..start.checked_add(len)?)
+        .map(<[u8]>::to_vec)
+}
+
+fn guest_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Option<String> {
+    String::from_utf8(guest_bytes(caller, ptr, len)?).ok()
+}
+
+/// Copy `bytes` into a `zc_alloc` buffer of the calling plugin, returning
+/// the packed `(ptr << 32) | len`.
+fn write_guest(caller: &mut Caller<'_, HostState>, bytes: &[u8]) -> wasmtime::Result<i64> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(wasmtime::Extern::into_memory)
+        .context("plugin does not export `memory`")?;
+    let alloc = caller
+        .get_export("zc_alloc")
+        .and_then(wasmtime::Extern::into_func)
+        .context("plugin does not export `zc_alloc`")?
+        .typed::<i32, i32>(&*caller)?;
+    let len = i32::try_from(bytes.len()).context("value too large")?;
+    let ptr = alloc.call(&mut *caller, len)?;
+    memory
+        .write(&mut *caller, usize::try_from(ptr)?, bytes)
+        .context("zc_alloc returned an out-of-bounds buffer")?;
+    #[allow(clippy::cast_sign_loss)]
+    Ok((i64::from(ptr as u32) << 32) | i64::from(len as u32))
+}
+
+struct WasmInstance {
+    store: Store<HostState>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    fuel_per_call: u64,
+}
+
+impl WasmInstance {
+    /// Call `export` with `input` serialized as JSON and parse its JSON output.
+    fn call_json<T: serde::de::DeserializeOwned>(
+        &mut self,
+        export: &str,
+        input: &serde_json::Value,
+    ) -> Result<T> {
+        let bytes = serde_json::to_vec(input)?;
+        let raw = self.call_raw(export, Some(&bytes))?;
+        serde_json::from_slice(&raw).with_context(|| format!("{export} returned invalid JSON"))
+    }
+
+    fn call_raw(&mut self, export: &str, input: Option<&[u8]>) -> Result<Vec<u8>> {
+        self.store.set_fuel(self.fuel_per_call)?;
+
+        let packed = if let Some(input) = input {
+            let len = i32::try_from(input.len()).context("plugin input too large")?;
+            let ptr = self.alloc.call(&mut self.store, len)?;
+            self.memory
+                .write(&mut self.store, usize::try_from(ptr)?, input)
+                .context("zc_alloc returned an out-of-bounds buffer")?;
+            self.instance
+                .get_typed_func::<(i32, i32), i64>(&mut self.store, export)?
+                .call(&mut self.store, (ptr, len))?
+        } else {
+            self.instance
+                .get_typed_func::<(), i64>(&mut self.store, export)?
+                .call(&mut self.store, ())?
+        };
+
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let (ptr, len) = ((packed >> 32) as u32 as usize, packed as u32 as usize);
+        let data = self.memory.data(&self.store);
+        let end = ptr
+            .checked_add(len)
+            .filter(|end| *end <= data.len())
+            .with_context(|| format!("{export} returned an out-of-bounds result"))?;
+        Ok(data[ptr..end].to_vec())
+    }
+}
+
+pub struct WasmPlugin {
+    manifest: Manifest,
+    instance: Arc<Mutex<WasmInstance>>,
+}
+
+impl WasmPlugin {
+    /// Load the module at `path`; with `kv`, its `kv_*` imports reach the
+    /// plugin's namespace.
+    pub fn load(
+        engine: &Engine,
+        path: &Path,
+        config: &PluginsConfig,
+        kv: Option<&Arc<KvStore>>,
+    ) -> Result<Self> {
+        let module = Module::from_file(engine, path)
+            .with_context(|| format!("failed to compile {}", path.display()))?;
+        let plugin_label = path.file_stem().map_or_else(
+            || "plugin".to_string(),
+            |s| s.to_string_lossy().into_owned(),
+        );
+
+        let mut linker = Linker::new(engine);
+        linker.func_wrap(
+            "zeroclaw",
+            "log",
+            |mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| {
+                let Some(bytes) = guest_bytes(&mut caller, ptr, len) else {
+                    return;
+                };
+                let text = String::from_utf8_lossy(&bytes);
+                let plugin = &caller.data().plugin;
+                match level {
+                    0 => tracing::debug!("[plugin:{plugin}] {text}"),
+                    1 => tracing::info!("[plugin:{plugin}] {text}"),
+                    2 => tracing::warn!("[plugin:{plugin}] {text}"),
+                    _ => tracing::error!("[plugin:{plugin}] {text}"),
+                }
+            },
+        )?;
+        linker.func_wrap(
+            "zeroclaw",
+            "kv_get",
+            |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> wasmtime::Result<i64> {
+                let (Some(kv), Some(key)) = (
+                    caller.data().kv.clone(),
+                    guest_string(&mut caller, ptr, len),
+                ) else {
+                    return Ok(-1);
+                };
+                match kv.get(&key) {
+                    Ok(Some(value)) => write_guest(&mut caller, value.as_bytes()),
+                    Ok(None) => Ok(-1),
+                    Err(e) => {
+                        tracing::warn!("[plugin:{}] kv_get failed: {e:#}", caller.data().plugin);
+                        Ok(-1)
+                    }
+                }
+            },
+        )?;
+        linker.func_wrap(
+            "zeroclaw",
+            "kv_set",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             value_ptr: i32,
+             value_len: i32,
+             ttl_secs: i64| {
+                let (Some(kv), Some(key), Some(value)) = (
+                    caller.data().kv.clone(),
+                    guest_string(&mut caller, key_ptr, key_len),
+                    guest_string(&mut caller, value_ptr, value_len),
+                ) else {
+                    return -1;
+                };
+                let ttl = u64::try_from(ttl_secs)
+                    .ok()
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs);
+                match kv.set(&key, &value, ttl) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        tracing::warn!("[plugin:{}] kv_set failed: {e:#}", caller.data().plugin);
+                        -1
+                    }
+                }
+            },
+        )?;
+        linker.func_wrap(
+            "zeroclaw",
+            "kv_delete",
+            |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+                let (Some(kv), Some(key)) = (
+                    caller.data().kv.clone(),
+                    guest_string(&mut caller, ptr, len),
+                ) else {
+                    return -1;
+                };
+                match kv.delete(&key) {
+                    Ok(deleted) => i32::from(deleted),
+                    Err(e) => {
+                        tracing::warn!("[plugin:{}] kv_delete failed: {e:#}", caller.data().plugin);
+                        -1
+                    }
+                }
+            },
+        )?;
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(config.max_memory_mb.saturating_mul(1024 * 1024))
+            .instances(1)
+            .build();
+        let mut store = Store::new(
+            engine,
+            HostState {
+                plugin: plugin_label,
+                limits,
+                kv: None,
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(config.fuel_per_call)?;
+
+        let instance = linker.instantiate(&mut store, &module)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("plugin does not export `memory`")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "zc_alloc")
+            .context("plugin does not export `zc_alloc`")?;
+
+        let mut wasm = WasmInstance {
+            store,
+            instance,
+            memory,
+            alloc,
+            fuel_per_call: config.fuel_per_call,
+        };
+        let manifest_bytes = wasm.call_raw("zc_manifest", None)?;
+        let manifest: Manifest =
+            serde_json::from_slice(&manifest_bytes).context("invalid plugin manifest")?;
+        wasm.store.data_mut().plugin.clone_from(&manifest.name);
+        wasm.store.data_mut().kv =
+            kv.map(|store| store.namespace(format!("wasm:{}", manifest.name)));
+
+        Ok(Self {
+            manifest,
+            instance: Arc::new(Mutex::new(wasm)),
+        })
+    }
+
+    /// Run a plugin call on the blocking pool; WASM execution is synchronous.
+    async fn call<T>(&self, export: &'static str, input: serde_json::Value) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+    {
+        let instance = Arc::clone(&self.instance);
+        tokio::task::spawn_blocking(move || instance.lock().call_json(export, &input))
+            .await
+            .context("plugin call panicked")?
+    }
+}
+
+#[async_trait]
+impl Plugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn has_filter(&self) -> bool {
+        self.manifest.filter
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.manifest.commands.clone()
+    }
+
+    fn tools(&self) -> Vec<ToolSpec> {
+        self.manifest.tools.clone()
+    }
+
+    async fn filter(&self, msg: &ChannelMessage) -> Result<FilterAction> {
+        let response: FilterResponse = self
+            .call(
+                "zc_filter",
+                serde_json::json!({
+                    "id": msg.id,
+                    "sender": msg.sender,
+                    "reply_target": msg.reply_target,
+                    "content": msg.content,
+                    "channel": msg.channel,
+                    "timestamp": msg.timestamp,
+                }),
+            )
+            .await?;
+        Ok(match response {
+            FilterResponse::Pass => FilterAction::Pass,
+            FilterResponse::Drop => FilterAction::Drop,
+            FilterResponse::Rewrite { content } => FilterAction::Rewrite(content),
+            FilterResponse::Reply { content } => FilterAction::Reply(content),
+        })
+    }
+
+    async fn handle_command(
+        &self,
+        command: &str,
+        args: &str,
+        msg: &ChannelMessage,
+    ) -> Result<Option<String>> {
+        let response: CommandResponse = self
+            .call(
+                "zc_command",
+                serde_json::json!({
+                    "command": command,
+                    "args": args,
+                    "message": {
+                        "id": msg.id,
+                        "sender": msg.sender,
+                        "reply_target": msg.reply_target,
+                        "content": msg.content,
+                        "channel": msg.channel,
+                        "timestamp": msg.timestamp,
+                    },
+                }),
+            )
+            .await?;
+        Ok(response.reply)
+    }
+
+    async fn call_tool(&self, tool: &str, args: serde_json::Value) -> Result<ToolResult> {
+        self.call("zc_tool", serde_json::json!({ "tool": tool, "args": args }))
+            .await
+    }
+}
+
+/// Load every `.wasm`/`.wat` module in `dir`, in file-name order.
+pub fn load_dir(dir: &Path, config: &PluginsConfig) -> Vec<Arc<dyn Plugin>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "wasm" || ext == "wat")
+        })
+        .collect();
+    paths.sort();
+    if paths.is_empty() {
+        return Vec::new();
+    }
+
+    let mut engine_config = wasmtime::Config::new();
+    engine_config.consume_fuel(true);
+    let engine = match Engine::new(&engine_config) {
+        Ok(engine) => engine,
+        Err(e) => {
+            tracing::error!("Failed to start WASM plugin engine: {e}");
+            return Vec::new();
+        }
+    };
+
+    let kv = crate::kv::global();
+
+    paths
+        .into_iter()
+        .filter_map(
+            |path| match WasmPlugin::load(&engine, &path, config, kv.as_ref()) {
+                Ok(plugin) => {
+                    tracing::info!(
+                        "Loaded WASM plugin {} from {}",
+                        plugin.name(),
+                        path.display()
+                    );
+                    Some(Arc::new(plugin) as Arc<dyn Plugin>)
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping WASM plugin {}: {e:#}", path.display());
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    /// Build a WAT module whose exports return fixed JSON documents.
+    fn fixed_output_module(outputs: &[(&str, &str)], extra: &str) -> String {
+        let mut wat = String::from(
+            "(module\n\
+             (import \"zeroclaw\" \"log\" (func $log (param i32 i32 i32)))\n\
+             (memory (export \"memory\") 1)\n\
+             (func (export \"zc_alloc\") (param i32) (result i32) i32.const 32768)\n",
+        );
+        let mut offset = 0usize;
+        for (export, json) in outputs {
+            let escaped = json.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = writeln!(wat, "(data (i32.const {offset}) \"{escaped}\")");
+            let packed = ((offset as u64) << 32) | json.len() as u64;
+            let params = if *export == "zc_manifest" {
+                ""
+            } else {
+                "(param i32 i32) "
+            };
+            let _ = writeln!(
+                wat,
+                "(func (export \"{export}\") {params}(result i64) i64.const {packed})"
+            );
+            offset += json.len().next_multiple_of(16);
+        }
+        wat.push_str(extra);
+        wat.push(')');
+        wat
+    }
+
+    fn config() -> PluginsConfig {
+        PluginsConfig {
+            enabled: true,
+            fuel_per_call: 100_000,
+            ..PluginsConfig::default()
+        }
+    }
+
+    fn msg(content: &str) -> ChannelMessage {
+        ChannelMessage::test("qq", "alice", content)
+    }
+
+    #[tokio::test]
+    async fn loads_manifest_and_calls_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        let wat = fixed_output_module(
+            &[
+                (
+                    "zc_manifest",
+                    r#"{"name":"demo","filter":true,"commands":["ping"],"tools":[{"name":"demo_tool","description":"Demo","parameters":{"type":"object"}}]}"#,
+                ),
+                ("zc_filter", r#"{"action":"rewrite","content":"filtered"}"#),
+                ("zc_command", r#"{"reply":"pong"}"#),
+                ("zc_tool", r#"{"success":true,"output":"ok","error":null}"#),
+            ],
+            "",
+        );
+        std::fs::write(dir.path().join("demo.wat"), wat).unwrap();
+
+        let plugins = load_dir(dir.path(), &config());
+        assert_eq!(plugins.len(), 1);
+        let plugin = &plugins[0];
+        assert_eq!(plugin.name(), "demo");
+        assert!(plugin.has_filter());
+        assert_eq!(plugin.commands(), vec!["ping".to_string()]);
+        assert_eq!(plugin.tools()[0].name, "demo_tool");
+
+        assert_eq!(
+            plugin.filter(&msg("hi")).await.unwrap(),
+            FilterAction::Rewrite("filtered".into())
+        );
+        assert_eq!(
+            plugin
+                .handle_command("ping", "", &msg("/ping"))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("pong")
+        );
+        let result = plugin
+            .call_tool("demo_tool", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "ok");
+    }
+
+    #[tokio::test]
+    async fn runaway_plugin_is_stopped_by_fuel_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let wat = fixed_output_module(
+            &[("zc_manifest", r#"{"name":"spin","filter":true}"#)],
+            "(func (export \"zc_filter\") (param i32 i32) (result i64) (loop $l (br $l)) i64.const 0)\n",
+        );
+        std::fs::write(dir.path().join("spin.wat"), wat).unwrap();
+
+        let plugins = load_dir(dir.path(), &config());
+        assert_eq!(plugins.len(), 1);
+        assert!(plugins[0].filter(&msg("hi")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn kv_imports_use_the_plugin_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = r#"{"name":"kv","commands":["remember"]}"#;
+        let value = r#"{"reply":"stored"}"#;
+        let escape = |s: &str| s.replace('"', "\\\"");
+        // `remember` stores a command response under "last" and answers
+        // with what `kv_get` returns for it
+        let wat = format!(
+            "(module\n\
+             (import \"zeroclaw\" \"kv_set\" (func $kv_set (param i32 i32 i32 i32 i64) (result i32)))\n\
+             (import \"zeroclaw\" \"kv_get\" (func $kv_get (param i32 i32) (result i64)))\n\
+             (memory (export \"memory\") 1)\n\
+             (func (export \"zc_alloc\") (param i32) (result i32) i32.const 32768)\n\
+             (data (i32.const 0) \"{}\")\n\
+             (data (i32.const 1024) \"last\")\n\
+             (data (i32.const 1040) \"{}\")\n\
+             (func (export \"zc_manifest\") (result i64) i64.const {})\n\
+             (func (export \"zc_command\") (param i32 i32) (result i64)\n\
+               (drop (call $kv_set (i32.const 1024) (i32.const 4) (i32.const 1040) (i32.const {}) (i64.const 0)))\n\
+               (call $kv_get (i32.const 1024) (i32.const 4))))",
+            escape(manifest),
+            escape(value),
+            manifest.len(),
+            value.len(),
+        );
+        let path = dir.path().join("kv.wat");
+        std::fs::write(&path, wat).unwrap();
+
+        let engine = {
+            let mut engine_config = wasmtime::Config::new();
+            engine_config.consume_fuel(true);
+            Engine::new(&engine_config).unwrap()
+        };
+        let store = Arc::new(KvStore::in_memory().unwrap());
+        let plugin = WasmPlugin::load(&engine, &path, &config(), Some(&store)).unwrap();
+        assert_eq!(
+            plugin
+                .handle_command("remember", "", &msg("/remember"))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("stored")
+        );
+        assert_eq!(
+            store.get("wasm:kv", "last").unwrap().as_deref(),
+            Some(value)
+        );
+    }
+
+    #[test]
+    fn invalid_modules_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("broken.wasm"), b"not wasm").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+        assert!(load_dir(dir.path(), &config()).is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+//! Per-user preferences set with `/set`.
+//!
+//! - `/set` — show the current preferences
+//! - `/set language <language>` — reply language, e.g. `en` or `pt-BR`
+//! - `/set timezone <IANA name>` — e.g. `Europe/Berlin`
+//! - `/set notifications on|off` — proactive notifications
+//! - `/set verbosity brief|normal|detailed` — reply length
+//! - `/set <name> default` — fall back to the channel's default again
+//!
+//! A user's settings are stored under their id (the linked profile when
+//! identity linking is on) in the `preferences` namespace of the
+//! [`crate::kv`] store. Unset fields resolve to
+//! `[preferences.channels.<channel>]`, then `[preferences.defaults]`. The
+//! agent is told the user's language, local time and preferred verbosity.
+
+use crate::config::{PreferenceSettings, PreferencesConfig, ReplyVerbosity};
+use crate::kv::KvNamespace;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+const MAX_LANGUAGE_LEN: usize = 35;
+
+const USAGE: &str =
+    "Usage: /set language|timezone|notifications|verbosity <value>, or /set <name> default";
+
+/// A user's effective preferences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserPreferences {
+    /// `None` leaves the reply language to the agent
+    pub language: Option<String>,
+    pub timezone: Tz,
+    pub notifications: bool,
+    pub verbosity: ReplyVerbosity,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            language: None,
+            timezone: Tz::UTC,
+            notifications: true,
+            verbosity: ReplyVerbosity::Normal,
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Combine layers, the first one setting a field winning.
+    fn from_layers(layers: &[&PreferenceSettings]) -> Self {
+        let timezone = layers
+            .iter()
+            .find_map(|l| l.timezone.as_deref())
+            .and_then(|timezone| {
+                Tz::from_str(timezone)
+                    .inspect_err(|_| {
+                        tracing::warn!("Ignoring invalid timezone preference {timezone:?}");
+                    })
+                    .ok()
+            })
+            .unwrap_or(Tz::UTC);
+        Self {
+            language: layers.iter().find_map(|l| l.language.clone()),
+            timezone,
+            notifications: layers.iter().find_map(|l| l.notifications).unwrap_or(true),
+            verbosity: layers.iter().find_map(|l| l.verbosity).unwrap_or_default(),
+        }
+    }
+
+    pub fn local_time(&self, at: DateTime<Utc>) -> DateTime<Tz> {
+        at.with_timezone(&self.timezone)
+    }
+
+    /// System prompt addendum describing the user's preferences at `now`.
+    pub fn agent_instructions(&self, now: DateTime<Utc>) -> String {
+        let mut parts = vec![format!(
+            "The user's local time is {} ({}).",
+            self.local_time(now).format("%A %Y-%m-%d %H:%M"),
+            self.timezone.name()
+        )];
+        if let Some(language) = &self.language {
+            parts.push(format!("Always reply in this language: {language}."));
+        }
+        match self.verbosity {
+            ReplyVerbosity::Brief => parts.push(
+                "Keep replies brief: a few sentences at most unless the user asks for more."
+                    .to_string(),
+            ),
+            ReplyVerbosity::Normal => {}
+            ReplyVerbosity::Detailed => {
+                parts.push("Give thorough, detailed replies.".to_string());
+            }
+        }
+        parts.join(" ")
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "⚙️ Your preferences:\n\
+             • language: {}\n\
+             • timezone: {}\n\
+             • notifications: {}\n\
+             • verbosity: {}\n\
+             Change one with /set <name> <value>, or /set <name> default.",
+            self.language.as_deref().unwrap_or("automatic"),
+            self.timezone.name(),
+            if self.notifications { "on" } else { "off" },
+            verbosity_name(self.verbosity),
+        )
+    }
+}
+
+fn verbosity_name(verbosity: ReplyVerbosity) -> &'static str {
+    match verbosity {
+        ReplyVerbosity::Brief => "brief",
+        ReplyVerbosity::Normal => "normal",
+        ReplyVerbosity::Detailed => "detailed",
+    }
+}
+
+/// Set the preference `name` in `settings`; `"default"` clears it.
+fn apply(settings: &mut PreferenceSettings, name: &str, value: &str) -> Result<()> {
+    let clear = value.eq_ignore_ascii_case("default");
+    match name {
+        "language" | "lang" => {
+            settings.language = if clear {
+                None
+            } else {
+                Some(parse_language(value)?)
+            };
+        }
+        "timezone" | "tz" => {
+            settings.timezone = if clear {
+                None
+            } else {
+                let timezone = Tz::from_str(value).map_err(|_| {
+                    anyhow!("Unknown timezone {value:?}; use an IANA name like Europe/Berlin")
+                })?;
+                Some(timezone.name().to_string())
+            };
+        }
+        "notifications" => {
+            settings.notifications = if clear {
+                None
+            } else {
+                Some(match value.to_ascii_lowercase().as_str() {
+                    "on" | "yes" | "true" => true,
+                    "off" | "no" | "false" => false,
+                    _ => bail!("Notifications are either on or off"),
+                })
+            };
+        }
+        "verbosity" => {
+            settings.verbosity = if clear {
+                None
+            } else {
+                Some(match value.to_ascii_lowercase().as_str() {
+                    "brief" => ReplyVerbosity::Brief,
+                    "normal" => ReplyVerbosity::Normal,
+                    "detailed" => ReplyVerbosity::Detailed,
+                    _ => bail!("Verbosity is brief, normal or detailed"),
+                })
+            };
+        }
+        _ => bail!("Unknown preference {name:?}; {USAGE}"),
+    }
+    Ok(())
+}
+
+/// Language codes (`pt-BR`) or names (`Brazilian Portuguese`).
+pub(crate) fn parse_language(value: &str) -> Result<String> {
+    let valid = value.len() <= MAX_LANGUAGE_LEN
+        && value
+            .chars()
+            .all(|c| c.is_alphabetic() || c == '-' || c == ' ');
+    if !valid {
+        bail!("Languages are a code like en or pt-BR, or a name like German");
+    }
+    Ok(value.to_string())
+}
+
+/// Stored preferences plus the configured defaults.
+pub struct PreferenceStore {
+    kv: KvNamespace,
+    config: PreferencesConfig,
+}
+
+impl PreferenceStore {
+    pub fn new(kv: KvNamespace, config: PreferencesConfig) -> Self {
+        Self { kv, config }
+    }
+
+    /// The settings `user` chose themselves.
+    pub fn settings(&self, user: &str) -> Result<PreferenceSettings> {
+        match self.kv.get(user)? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(PreferenceSettings::default()),
+        }
+    }
+
+    fn save(&self, user: &str, settings: &PreferenceSettings) -> Result<()> {
+        if *settings == PreferenceSettings::default() {
+            self.kv.delete(user)?;
+        } else {
+            self.kv.set(user, &serde_json::to_string(settings)?, None)?;
+        }
+        Ok(())
+    }
+
+    /// Effective preferences of `user` on `channel`. A store failure falls
+    /// back to the defaults.
+    pub fn resolve(&self, channel: &str, user: &str) -> UserPreferences {
+        let own = self.settings(user).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load preferences of {user}: {e}");
+            PreferenceSettings::default()
+        });
+        let layers: Vec<&PreferenceSettings> = [
+            Some(&own),
+            self.config.channels.get(channel),
+            Some(&self.config.defaults),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        UserPreferences::from_layers(&layers)
+    }
+
+    /// Change one of `user`'s settings; `"default"` clears it.
+    pub fn set(&self, user: &str, name: &str, value: &str) -> Result<()> {
+        let mut settings = self.settings(user)?;
+        apply(&mut settings, &name.to_ascii_lowercase(), value.trim())?;
+        self.save(user, &settings)
+    }
+
+    /// Delete everything `user` set. Returns `false` if they set nothing.
+    pub fn forget(&self, user: &str) -> Result<bool> {
+        self.kv.delete(user)
+    }
+
+    /// Answer `/set`. Returns `None` for any other message.
+    pub fn handle_command(&self, channel: &str, user: &str, content: &str) -> Option<String> {
+        let mut words = content.split_whitespace();
+        let command = words.next()?.split('@').next()?;
+        if command != "/set" {
+            return None;
+        }
+        let name = words.next();
+        let value = words.collect::<Vec<_>>().join(" ");
+        let reply = match name {
+            None => self.resolve(channel, user).describe(),
+            Some(_) if value.is_empty() => USAGE.to_string(),
+            Some(name) => match self.set(user, name, &value) {
+                Ok(()) => format!("✅ Saved.\n{}", self.resolve(channel, user).describe()),
+                Err(e) => format!("⚠️ {e}"),
+            },
+        };
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kv::KvStore;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn store() -> PreferenceStore {
+        let kv = Arc::new(KvStore::in_memory().unwrap());
+        PreferenceStore::new(
+            kv.namespace("preferences"),
+            PreferencesConfig {
+                enabled: true,
+                defaults: PreferenceSettings {
+                    language: Some("en".into()),
+                    ..PreferenceSettings::default()
+                },
+                channels: HashMap::from([(
+                    "dingtalk".to_string(),
+                    PreferenceSettings {
+                        language: Some("zh-CN".into()),
+                        timezone: Some("Asia/Shanghai".into()),
+                        ..PreferenceSettings::default()
+                    },
+                )]),
+            },
+        )
+    }
+
+    #[test]
+    fn own_settings_win_over_channel_and_global_defaults() {
+        let store = store();
+        let prefs = store.resolve("dingtalk", "alice");
+        assert_eq!(prefs.language.as_deref(), Some("zh-CN"));
+        assert_eq!(prefs.timezone, Tz::Asia__Shanghai);
+        assert_eq!(
+            store.resolve("telegram", "alice").language.as_deref(),
+            Some("en")
+        );
+
+        store.set("alice", "language", "German").unwrap();
+        store.set("alice", "verbosity", "brief").unwrap();
+        let prefs = store.resolve("dingtalk", "alice");
+        assert_eq!(prefs.language.as_deref(), Some("German"));
+        assert_eq!(prefs.verbosity, ReplyVerbosity::Brief);
+        assert_eq!(prefs.timezone, Tz::Asia__Shanghai);
+
+        store.set("alice", "language", "default").unwrap();
+        assert_eq!(
+            store.resolve("dingtalk", "alice").language.as_deref(),
+            Some("zh-CN")
+        );
+    }
+
+    #[test]
+    fn set_command_validates_and_reports() {
+        let store = store();
+        let reply = store
+            .handle_command(
+                "telegram",
+                "bob",
+                "/set@zeroclaw_bot timezone Europe/Berlin",
+            )
+            .unwrap();
+        assert!(reply.contains("timezone: Europe/Berlin"), "{reply}");
+        assert!(store
+            .handle_command("telegram", "bob", "/set timezone Mars/Olympus")
+            .unwrap()
+            .contains("Unknown timezone"));
+        assert!(store
+            .handle_command("telegram", "bob", "/set notifications maybe")
+            .unwrap()
+            .starts_with("⚠️"));
+        assert!(store
+            .handle_command("telegram", "bob", "/set verbosity")
+            .unwrap()
+            .starts_with("Usage"));
+        assert!(store
+            .handle_command("telegram", "bob", "/set")
+            .unwrap()
+            .contains("notifications: on"));
+        assert!(store
+            .handle_command("telegram", "bob", "/settings")
+            .is_none());
+        assert_eq!(
+            store.settings("bob").unwrap().timezone.as_deref(),
+            Some("Europe/Berlin")
+        );
+    }
+
+    #[test]
+    fn agent_instructions_use_local_time() {
+        let prefs = UserPreferences {
+            language: Some("fr".into()),
+            timezone: Tz::Europe__Paris,
+            notifications: true,
+            verbosity: ReplyVerbosity::Detailed,
+        };
+        let now = DateTime::parse_from_rfc3339("2026-01-05T08:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            prefs.agent_instructions(now),
+            "The user's local time is Monday 2026-01-05 09:30 (Europe/Paris). \
+             Always reply in this language: fr. Give thorough, detailed replies."
+        );
+    }
+}
@@ -399,6 +399,55 @@ impl Provider for AnthropicProvider {
         Ok(Self::parse_native_response(native_response))
     }
 
+    async fn describe_image(
+        &self,
+        image: &[u8],
+        mime_type: &str,
+        prompt: &str,
+        model: &str,
+    ) -> anyhow::Result<String> {
+        use base64::Engine;
+
+        let credential = self.credential.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Anthropic credentials not set. Set ANTHROPIC_API_KEY or ANTHROPIC_OAUTH_TOKEN (setup-token)."
+            )
+        })?;
+
+        let body = serde_json::json!({
+            "model": model,
+            "max_tokens": 1024,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "media_type": mime_type,
+                            "data": base64::engine::general_purpose::STANDARD.encode(image),
+                        }
+                    },
+                    { "type": "text", "text": prompt }
+                ]
+            }]
+        });
+        let request = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(&body);
+
+        let response = self.apply_auth(request, credential).send().await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("Anthropic", response).await);
+        }
+
+        let chat_response: ChatResponse = response.json().await?;
+        Self::parse_text_response(chat_response)
+    }
+
     fn supports_native_tools(&self) -> bool {
         true
     }
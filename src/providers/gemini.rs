@@ -321,6 +321,52 @@ impl Provider for GeminiProvider {
             .and_then(|p| p.text)
             .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))
     }
+
+    async fn describe_image(
+        &self,
+        image: &[u8],
+        mime_type: &str,
+        prompt: &str,
+        model: &str,
+    ) -> anyhow::Result<String> {
+        use base64::Engine;
+
+        let auth = self
+            .auth
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Gemini API key not found. Set GEMINI_API_KEY."))?;
+
+        let body = serde_json::json!({
+            "contents": [{
+                "role": "user",
+                "parts": [
+                    {
+                        "inline_data": {
+                            "mime_type": mime_type,
+                            "data": base64::engine::general_purpose::STANDARD.encode(image),
+                        }
+                    },
+                    { "text": prompt }
+                ]
+            }]
+        });
+        let url = Self::build_generate_content_url(model, auth);
+        let mut request = self.client.post(&url).json(&body);
+        if let GeminiAuth::OAuthToken(token) = auth {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("Gemini", response).await);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        body.pointer("/candidates/0/content/parts/0/text")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("No response from Gemini"))
+    }
 }
 
 #[cfg(test)]
@@ -126,7 +126,7 @@ pub fn scrub_secret_patterns(input: &str) -> String {
 
 /// Sanitize API error text by scrubbing secrets and truncating length.
 pub fn sanitize_api_error(input: &str) -> String {
-    let scrubbed = scrub_secret_patterns(input);
+    let scrubbed = crate::security::redact::redact(input);
 
     if scrubbed.chars().count() <= MAX_API_ERROR_CHARS {
         return scrubbed;
@@ -151,6 +151,42 @@ pub async fn api_error(provider: &str, response: reqwest::Response) -> anyhow::E
     anyhow::anyhow!("{provider} API error ({status}): {sanitized}")
 }
 
+/// Chat Completions request body asking `model` about one inline image, in
+/// the OpenAI `image_url` data-URL format most vision APIs accept.
+pub(crate) fn openai_image_request(
+    image: &[u8],
+    mime_type: &str,
+    prompt: &str,
+    model: &str,
+) -> serde_json::Value {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(image);
+    serde_json::json!({
+        "model": model,
+        "messages": [{
+            "role": "user",
+            "content": [
+                { "type": "text", "text": prompt },
+                {
+                    "type": "image_url",
+                    "image_url": { "url": format!("data:{mime_type};base64,{encoded}") }
+                }
+            ]
+        }]
+    })
+}
+
+/// Text of the first choice of a Chat Completions response.
+pub(crate) fn openai_response_text(
+    provider: &str,
+    body: &serde_json::Value,
+) -> anyhow::Result<String> {
+    body.pointer("/choices/0/message/content")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("No response from {provider}"))
+}
+
 /// Resolve API key for a provider from config and environment variables.
 ///
 /// Resolution order:
@@ -461,13 +497,15 @@ pub fn create_resilient_provider(
         }
     }
 
+    crate::circuit::configure(&reliability.circuit_breaker);
     let reliable = ReliableProvider::new(
         providers,
         reliability.provider_retries,
         reliability.provider_backoff_ms,
     )
     .with_api_keys(reliability.api_keys.clone())
-    .with_model_fallbacks(reliability.model_fallbacks.clone());
+    .with_model_fallbacks(reliability.model_fallbacks.clone())
+    .with_circuit_breakers();
 
     Ok(Box::new(reliable))
 }
@@ -549,6 +587,19 @@ pub fn create_routed_provider(
 mod tests {
     use super::*;
 
+    #[test]
+    fn openai_image_request_inlines_a_data_url() {
+        let body = openai_image_request(b"abc", "image/png", "what is it?", "gpt-4o");
+        assert_eq!(body["model"], "gpt-4o");
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[0]["text"], "what is it?");
+        assert_eq!(content[1]["image_url"]["url"], "data:image/png;base64,YWJj");
+
+        let response = serde_json::json!({ "choices": [{ "message": { "content": "a cat" } }] });
+        assert_eq!(openai_response_text("test", &response).unwrap(), "a cat");
+        assert!(openai_response_text("test", &serde_json::json!({})).is_err());
+    }
+
     #[test]
     fn resolve_provider_credential_prefers_explicit_argument() {
         let resolved = resolve_provider_credential("openrouter", Some("  explicit-key  "));
@@ -923,6 +974,7 @@ mod tests {
             channel_max_backoff_secs: 60,
             scheduler_poll_secs: 15,
             scheduler_retries: 2,
+            circuit_breaker: crate::config::CircuitBreakerConfig::default(),
         };
 
         let provider = create_resilient_provider(
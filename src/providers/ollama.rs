@@ -368,6 +368,48 @@ impl Provider for OllamaProvider {
         Ok(content)
     }
 
+    async fn describe_image(
+        &self,
+        image: &[u8],
+        _mime_type: &str,
+        prompt: &str,
+        model: &str,
+    ) -> anyhow::Result<String> {
+        use base64::Engine;
+
+        let (normalized_model, should_auth) = self.resolve_request_details(model)?;
+        let body = serde_json::json!({
+            "model": normalized_model,
+            "stream": false,
+            "messages": [{
+                "role": "user",
+                "content": prompt,
+                "images": [base64::engine::general_purpose::STANDARD.encode(image)],
+            }]
+        });
+
+        let mut request = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body);
+        if should_auth {
+            if let Some(key) = self.api_key.as_ref() {
+                request = request.bearer_auth(key);
+            }
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("Ollama", response).await);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        body.pointer("/message/content")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("No response from Ollama"))
+    }
+
     fn supports_native_tools(&self) -> bool {
         // Return false since loop_.rs uses XML-style tool parsing via system prompt
         // The model may return native tool_calls but we convert them to JSON format
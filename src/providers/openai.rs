@@ -319,6 +319,34 @@ impl Provider for OpenAiProvider {
         Ok(Self::parse_native_response(message))
     }
 
+    async fn describe_image(
+        &self,
+        image: &[u8],
+        mime_type: &str,
+        prompt: &str,
+        model: &str,
+    ) -> anyhow::Result<String> {
+        let credential = self.credential.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("OpenAI API key not set. Set OPENAI_API_KEY or edit config.toml.")
+        })?;
+
+        let response = self
+            .client
+            .post("https://api.openai.com/v1/chat/completions")
+            .header("Authorization", format!("Bearer {credential}"))
+            .json(&super::openai_image_request(
+                image, mime_type, prompt, model,
+            ))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(super::api_error("OpenAI", response).await);
+        }
+
+        super::openai_response_text("OpenAI", &response.json().await?)
+    }
+
     fn supports_native_tools(&self) -> bool {
         true
     }
@@ -1,5 +1,6 @@
 use super::traits::{ChatMessage, StreamChunk, StreamOptions, StreamResult};
 use super::Provider;
+use crate::circuit::CircuitPermit;
 use async_trait::async_trait;
 use futures_util::{stream, StreamExt};
 use std::collections::HashMap;
@@ -80,6 +81,8 @@ pub struct ReliableProvider {
     key_index: AtomicUsize,
     /// Per-model fallback chains: model_name → [fallback_model_1, fallback_model_2, ...]
     model_fallbacks: HashMap<String, Vec<String>>,
+    /// Skip providers whose circuit breaker is open.
+    circuit_breakers: bool,
 }
 
 impl ReliableProvider {
@@ -95,6 +98,7 @@ impl ReliableProvider {
             api_keys: Vec::new(),
             key_index: AtomicUsize::new(0),
             model_fallbacks: HashMap::new(),
+            circuit_breakers: false,
         }
     }
 
@@ -110,6 +114,23 @@ impl ReliableProvider {
         self
     }
 
+    /// Guard each provider with its circuit breaker (`provider:<name>`).
+    pub fn with_circuit_breakers(mut self) -> Self {
+        self.circuit_breakers = true;
+        self
+    }
+
+    /// Permission to call `provider_name`, or an error while its circuit is
+    /// open.
+    fn circuit_permit(&self, provider_name: &str) -> anyhow::Result<Option<CircuitPermit>> {
+        if !self.circuit_breakers {
+            return Ok(None);
+        }
+        crate::circuit::breaker(&format!("provider:{provider_name}"))
+            .try_acquire()
+            .map(Some)
+    }
+
     /// Build the list of models to try: [original, fallback1, fallback2, ...]
     fn model_chain<'a>(&'a self, model: &'a str) -> Vec<&'a str> {
         let mut chain = vec![model];
@@ -119,6 +140,19 @@ impl ReliableProvider {
         chain
     }
 
+    fn streaming_provider(&self, options: StreamOptions) -> Option<&(String, Box<dyn Provider>)> {
+        self.providers
+            .iter()
+            .find(|(_, provider)| options.enabled && provider.supports_streaming())
+    }
+
+    fn streaming_model(&self, model: &str) -> String {
+        match self.model_chain(model).first() {
+            Some(m) => m.to_string(),
+            None => model.to_string(),
+        }
+    }
+
     /// Advance to the next API key and return it, or None if no extra keys configured.
     fn rotate_key(&self) -> Option<&str> {
         if self.api_keys.is_empty() {
@@ -151,6 +185,44 @@ impl Provider for ReliableProvider {
         Ok(())
     }
 
+    /// Image requests are large and rarely transient failures, so they fail
+    /// over across providers without retries.
+    async fn describe_image(
+        &self,
+        image: &[u8],
+        mime_type: &str,
+        prompt: &str,
+        model: &str,
+    ) -> anyhow::Result<String> {
+        let mut failures = Vec::new();
+        for (provider_name, provider) in &self.providers {
+            let permit = match self.circuit_permit(provider_name) {
+                Ok(permit) => permit,
+                Err(e) => {
+                    failures.push(format!("{provider_name}: {e}"));
+                    continue;
+                }
+            };
+            match provider
+                .describe_image(image, mime_type, prompt, model)
+                .await
+            {
+                Ok(description) => {
+                    report_to_circuit(permit, true);
+                    return Ok(description);
+                }
+                Err(e) => {
+                    report_to_circuit(permit, is_non_retryable(&e));
+                    failures.push(format!("{provider_name}: {e}"));
+                }
+            }
+        }
+        anyhow::bail!(
+            "All providers failed to describe the image:\n{}",
+            failures.join("\n")
+        )
+    }
+
     async fn chat_with_system(
         &self,
         system_prompt: Option<&str>,
@@ -166,11 +238,19 @@ impl Provider for ReliableProvider {
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
+                    let permit = match self.circuit_permit(provider_name) {
+                        Ok(permit) => permit,
+                        Err(e) => {
+                            failures.push(format!("{provider_name}/{current_model}: {e}"));
+                            break;
+                        }
+                    };
                     match provider
                         .chat_with_system(system_prompt, message, current_model, temperature)
                         .await
                     {
                         Ok(resp) => {
+                            report_to_circuit(permit, true);
                             if attempt > 0 || *current_model != model {
                                 tracing::info!(
                                     provider = provider_name,
@@ -185,6 +265,7 @@ impl Provider for ReliableProvider {
                         Err(e) => {
                             let non_retryable = is_non_retryable(&e);
                             let rate_limited = is_rate_limited(&e);
+                            report_to_circuit(permit, non_retryable);
 
                             let failure_reason = if rate_limited {
                                 "rate_limited"
@@ -271,11 +352,19 @@ impl Provider for ReliableProvider {
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
+                    let permit = match self.circuit_permit(provider_name) {
+                        Ok(permit) => permit,
+                        Err(e) => {
+                            failures.push(format!("{provider_name}/{current_model}: {e}"));
+                            break;
+                        }
+                    };
                     match provider
                         .chat_with_history(messages, current_model, temperature)
                         .await
                     {
                         Ok(resp) => {
+                            report_to_circuit(permit, true);
                             if attempt > 0 || *current_model != model {
                                 tracing::info!(
                                     provider = provider_name,
@@ -290,6 +379,7 @@ impl Provider for ReliableProvider {
                         Err(e) => {
                             let non_retryable = is_non_retryable(&e);
                             let rate_limited = is_rate_limited(&e);
+                            report_to_circuit(permit, non_retryable);
 
                             let failure_reason = if rate_limited {
                                 "rate_limited"
@@ -365,69 +455,90 @@ impl Provider for ReliableProvider {
         temperature: f64,
         options: StreamOptions,
     ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
-        // Try each provider/model combination for streaming
         // For streaming, we use the first provider that supports it and has streaming enabled
-        for (provider_name, provider) in &self.providers {
-            if !provider.supports_streaming() || !options.enabled {
-                continue;
-            }
+        let Some((provider_name, provider)) = self.streaming_provider(options) else {
+            return no_streaming_provider();
+        };
+        // Try the first model in the chain; the caller can retry the entire request if needed
+        let current_model = self.streaming_model(model);
+        let stream = provider.stream_chat_with_system(
+            system_prompt,
+            message,
+            &current_model,
+            temperature,
+            options,
+        );
+        log_stream_errors(provider_name.clone(), current_model, stream)
+    }
 
-            // Clone provider data for the stream
-            let provider_clone = provider_name.clone();
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let Some((provider_name, provider)) = self.streaming_provider(options) else {
+            return no_streaming_provider();
+        };
+        let current_model = self.streaming_model(model);
+        let stream =
+            provider.stream_chat_with_history(messages, &current_model, temperature, options);
+        log_stream_errors(provider_name.clone(), current_model, stream)
+    }
+}
 
-            // Try the first model in the chain for streaming
-            let current_model = match self.model_chain(model).first() {
-                Some(m) => m.to_string(),
-                None => model.to_string(),
-            };
+/// Report a call's outcome to its provider's circuit breaker. Client errors
+/// mean the provider is up, so they don't count against it.
+fn report_to_circuit(permit: Option<CircuitPermit>, provider_up: bool) {
+    match permit {
+        Some(permit) if provider_up => permit.success(),
+        Some(permit) => permit.failure(),
+        None => {}
+    }
+}
 
-            // For streaming, we attempt once and propagate errors
-            // The caller can retry the entire request if needed
-            let stream = provider.stream_chat_with_system(
-                system_prompt,
-                message,
-                &current_model,
-                temperature,
-                options,
-            );
-
-            // Use a channel to bridge the stream with logging
-            let (tx, rx) = tokio::sync::mpsc::channel::<StreamResult<StreamChunk>>(100);
-
-            tokio::spawn(async move {
-                let mut stream = stream;
-                while let Some(chunk) = stream.next().await {
-                    if let Err(ref e) = chunk {
-                        tracing::warn!(
-                            provider = provider_clone,
-                            model = current_model,
-                            "Streaming error: {e}"
-                        );
-                    }
-                    if tx.send(chunk).await.is_err() {
-                        break; // Receiver dropped
-                    }
-                }
-            });
+/// Forward `stream`, logging the errors in it.
+fn log_stream_errors(
+    provider_name: String,
+    model: String,
+    mut stream: stream::BoxStream<'static, StreamResult<StreamChunk>>,
+) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+    // Use a channel to bridge the stream with logging
+    let (tx, rx) = tokio::sync::mpsc::channel::<StreamResult<StreamChunk>>(100);
+
+    tokio::spawn(async move {
+        while let Some(chunk) = stream.next().await {
+            if let Err(ref e) = chunk {
+                tracing::warn!(
+                    provider = provider_name,
+                    model = model,
+                    "Streaming error: {e}"
+                );
+            }
+            if tx.send(chunk).await.is_err() {
+                break; // Receiver dropped
+            }
+        }
+    });
 
-            // Convert channel receiver to stream
-            return stream::unfold(rx, |mut rx| async move {
-                match rx.recv().await {
-                    Some(chunk) => Some((chunk, rx)),
-                    None => None,
-                }
-            })
-            .boxed();
+    // Convert channel receiver to stream
+    stream::unfold(rx, |mut rx| async move {
+        match rx.recv().await {
+            Some(chunk) => Some((chunk, rx)),
+            None => None,
         }
+    })
+    .boxed()
+}
 
-        // No streaming support available
-        stream::once(async move {
-            Err(super::traits::StreamError::Provider(
-                "No provider supports streaming".to_string(),
-            ))
-        })
-        .boxed()
-    }
+fn no_streaming_provider() -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+    stream::once(async move {
+        Err(super::traits::StreamError::Provider(
+            "No provider supports streaming".to_string(),
+        ))
+    })
+    .boxed()
 }
 
 #[cfg(test)]
@@ -580,6 +691,47 @@ mod tests {
         assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
     }
 
+    #[tokio::test]
+    async fn open_circuit_skips_failing_provider() {
+        let primary = format!("primary-{}", uuid::Uuid::new_v4());
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    primary.clone(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: usize::MAX,
+                        response: "never",
+                        error: "503 Service Unavailable",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::new(AtomicUsize::new(0)),
+                        fail_until_attempt: 0,
+                        response: "from fallback",
+                        error: "fallback down",
+                    }),
+                ),
+            ],
+            0,
+            1,
+        )
+        .with_circuit_breakers();
+
+        for _ in 0..6 {
+            let result = provider.simple_chat("hello", "test", 0.0).await.unwrap();
+            assert_eq!(result, "from fallback");
+        }
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 5);
+        assert_eq!(
+            crate::circuit::breaker(&format!("provider:{primary}")).state(),
+            crate::circuit::CircuitState::Open
+        );
+    }
+
     #[tokio::test]
     async fn returns_aggregated_error_when_all_providers_fail() {
         let provider = ReliableProvider::new(
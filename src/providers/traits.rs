@@ -351,6 +351,18 @@ pub trait Provider: Send + Sync {
         self.capabilities().native_tool_calling
     }
 
+    /// Describe an image so it can be used in a text conversation. `prompt`
+    /// says what to focus on. Providers without image input return an error.
+    async fn describe_image(
+        &self,
+        _image: &[u8],
+        _mime_type: &str,
+        _prompt: &str,
+        _model: &str,
+    ) -> anyhow::Result<String> {
+        anyhow::bail!("This provider does not support image input")
+    }
+
     /// Warm up the HTTP connection pool (TLS handshake, DNS, HTTP/2 setup).
     /// Default implementation is a no-op; providers with HTTP clients should override.
     async fn warmup(&self) -> anyhow::Result<()> {
@@ -0,0 +1,611 @@
+//! Reminders set from chat with `/remind`.
+//!
+//! - `/remind me in 30m to restart the build`
+//! - `/remind tomorrow 9am to call the bank`, `/remind every monday at 8:30 to ...`
+//! - `/reminders` (or `/remind list`) — your pending reminders
+//! - `/remind cancel <id>`
+//!
+//! Times are read with [`crate::cron::parse_schedule_prefix`] in the user's
+//! timezone preference. Reminders are stored in
+//! `<workspace>/state/reminders.db` and delivered back to the chat they were
+//! set in by [`run_reminder_loop`]. A reminder stays stored until it has been
+//! sent, so one that fell due while the bot was down goes out late rather
+//! than not at all; failed sends are retried with backoff, up to
+//! `[reminders] max_attempts` times.
+
+use crate::channels::control::ChannelControl;
+use crate::channels::priority::Priority;
+use crate::config::RemindersConfig;
+use crate::cron::{next_run_for_schedule, parse_schedule_prefix, validate_schedule, Schedule};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::Tz;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const MAX_TEXT_LEN: usize = 1000;
+const MIN_POLL_SECS: u64 = 5;
+/// Reminders sent this much after their time mention when they were due.
+const LATE_AFTER_SECS: i64 = 120;
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+const USAGE: &str = "Usage: /remind me in 30m to restart the build, \
+                     /remind tomorrow 9am to ..., /reminders, or /remind cancel <id>";
+
+/// A pending reminder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub id: i64,
+    pub channel: String,
+    pub recipient: String,
+    /// Who set it; only they can list or cancel it
+    pub user: String,
+    pub text: String,
+    pub schedule: Schedule,
+    /// The timezone the time was given in
+    pub timezone: Tz,
+    pub next_at: DateTime<Utc>,
+    /// Failed delivery attempts of the current occurrence
+    pub attempts: u32,
+}
+
+impl Reminder {
+    fn is_recurring(&self) -> bool {
+        !matches!(self.schedule, Schedule::At { .. })
+    }
+
+    fn local_time(&self) -> String {
+        self.next_at
+            .with_timezone(&self.timezone)
+            .format("%a %Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    fn describe(&self) -> String {
+        let repeats = match &self.schedule {
+            Schedule::At { .. } => String::new(),
+            Schedule::Cron { expr, .. } => format!(", repeats {expr}"),
+            Schedule::Every { every_ms } => format!(", repeats every {}", interval(*every_ms)),
+        };
+        format!(
+            "#{} {} ({}{repeats}): {}",
+            self.id,
+            self.local_time(),
+            self.timezone.name(),
+            self.text
+        )
+    }
+
+    /// The message sent when the reminder is due.
+    fn message(&self, now: DateTime<Utc>) -> String {
+        if (now - self.next_at).num_seconds() > LATE_AFTER_SECS {
+            format!("⏰ Reminder (due {}): {}", self.local_time(), self.text)
+        } else {
+            format!("⏰ Reminder: {}", self.text)
+        }
+    }
+}
+
+fn interval(every_ms: u64) -> String {
+    let secs = every_ms / 1000;
+    match secs {
+        s if s >= 86_400 && s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s >= 3600 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+/// Persisted reminders.
+pub struct ReminderStore {
+    conn: Mutex<Connection>,
+    config: RemindersConfig,
+}
+
+impl ReminderStore {
+    /// Open (or create) the store under `<workspace>/state/`.
+    pub fn open(workspace_dir: &Path, config: RemindersConfig) -> Result<Self> {
+        let dir = workspace_dir.join("state");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create state directory: {}", dir.display()))?;
+        let db_path = dir.join("reminders.db");
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open reminders DB: {}", db_path.display()))?;
+        Self::with_connection(conn, config)
+    }
+
+    /// In-memory store for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory(config: RemindersConfig) -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, config)
+    }
+
+    fn with_connection(conn: Connection, config: RemindersConfig) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS reminders (
+                id         INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel    TEXT NOT NULL,
+                recipient  TEXT NOT NULL,
+                user       TEXT NOT NULL,
+                text       TEXT NOT NULL,
+                schedule   TEXT NOT NULL,
+                timezone   TEXT NOT NULL,
+                next_at    INTEGER NOT NULL,
+                attempts   INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_reminders_next_at ON reminders(next_at);
+             CREATE INDEX IF NOT EXISTS idx_reminders_user ON reminders(user);",
+        )
+        .context("Failed to initialize reminders schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            config,
+        })
+    }
+
+    /// Store a reminder for `user`, delivered to `recipient` on `channel`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add(
+        &self,
+        channel: &str,
+        recipient: &str,
+        user: &str,
+        text: &str,
+        schedule: Schedule,
+        timezone: Tz,
+        now: DateTime<Utc>,
+    ) -> Result<Reminder> {
+        if text.chars().count() > MAX_TEXT_LEN {
+            bail!("Reminders are limited to {MAX_TEXT_LEN} characters");
+        }
+        validate_schedule(&schedule, now)?;
+        let next_at = next_run_for_schedule(&schedule, now)?;
+        let conn = self.conn.lock();
+        let pending: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM reminders WHERE user = ?1",
+            params![user],
+            |row| row.get(0),
+        )?;
+        let pending = usize::try_from(pending).unwrap_or(usize::MAX);
+        if pending >= self.config.max_per_user {
+            bail!(
+                "You already have {pending} reminders; cancel one with /remind cancel <id> first"
+            );
+        }
+        conn.execute(
+            "INSERT INTO reminders
+                (channel, recipient, user, text, schedule, timezone, next_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                channel,
+                recipient,
+                user,
+                text,
+                serde_json::to_string(&schedule)?,
+                timezone.name(),
+                next_at.timestamp(),
+                now.to_rfc3339(),
+            ],
+        )?;
+        Ok(Reminder {
+            id: conn.last_insert_rowid(),
+            channel: channel.to_string(),
+            recipient: recipient.to_string(),
+            user: user.to_string(),
+            text: text.to_string(),
+            schedule,
+            timezone,
+            next_at,
+            attempts: 0,
+        })
+    }
+
+    fn query(&self, filter: &str, param: impl rusqlite::ToSql) -> Result<Vec<Reminder>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, channel, recipient, user, text, schedule, timezone, next_at, attempts
+             FROM reminders WHERE {filter} ORDER BY next_at, id"
+        ))?;
+        let rows = stmt
+            .query_map(params![param], map_reminder_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// `user`'s reminders, soonest first.
+    pub fn pending(&self, user: &str) -> Result<Vec<Reminder>> {
+        self.query("user = ?1", user)
+    }
+
+    /// Reminders due at `now`, oldest first.
+    pub fn due(&self, now: DateTime<Utc>) -> Result<Vec<Reminder>> {
+        self.query("next_at <= ?1", now.timestamp())
+    }
+
+    /// Delete one of `user`'s reminders. Returns `false` if they have none
+    /// with that id.
+    pub fn cancel(&self, user: &str, id: i64) -> Result<bool> {
+        let removed = self.conn.lock().execute(
+            "DELETE FROM reminders WHERE id = ?1 AND user = ?2",
+            params![id, user],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Delete all of `user`'s reminders. Returns how many there were.
+    pub fn forget(&self, user: &str) -> Result<usize> {
+        let removed = self
+            .conn
+            .lock()
+            .execute("DELETE FROM reminders WHERE user = ?1", params![user])?;
+        Ok(removed)
+    }
+
+    /// Record a delivery: one-off reminders are removed, recurring ones move
+    /// to their next occurrence after `now`.
+    pub fn delivered(&self, reminder: &Reminder, now: DateTime<Utc>) -> Result<()> {
+        let conn = self.conn.lock();
+        if reminder.is_recurring() {
+            let next_at = next_run_for_schedule(&reminder.schedule, now)?;
+            conn.execute(
+                "UPDATE reminders SET next_at = ?1, attempts = 0 WHERE id = ?2",
+                params![next_at.timestamp(), reminder.id],
+            )?;
+        } else {
+            conn.execute("DELETE FROM reminders WHERE id = ?1", params![reminder.id])?;
+        }
+        Ok(())
+    }
+
+    /// Record a failed delivery and schedule a retry. Returns `true` when
+    /// the attempts are used up and the occurrence was given up on.
+    pub fn failed(&self, reminder: &Reminder, now: DateTime<Utc>) -> Result<bool> {
+        let attempts = reminder.attempts + 1;
+        if attempts >= self.config.max_attempts {
+            self.delivered(reminder, now)?;
+            return Ok(true);
+        }
+        let delay = (30_i64 << attempts.min(16)).min(MAX_RETRY_DELAY_SECS);
+        self.conn.lock().execute(
+            "UPDATE reminders SET next_at = ?1, attempts = ?2 WHERE id = ?3",
+            params![
+                (now + Duration::seconds(delay)).timestamp(),
+                attempts,
+                reminder.id
+            ],
+        )?;
+        Ok(false)
+    }
+
+    /// Answer `/remind` and `/reminders` from `user` in the chat `recipient`
+    /// on `channel`, reading times in `timezone`. Returns `None` for any
+    /// other message.
+    pub fn handle_command(
+        &self,
+        channel: &str,
+        recipient: &str,
+        user: &str,
+        timezone: Tz,
+        content: &str,
+    ) -> Option<String> {
+        let content = content.trim();
+        let (command, rest) = content
+            .split_once(char::is_whitespace)
+            .unwrap_or((content, ""));
+        let rest = rest.trim();
+        let reply = match command.split('@').next()? {
+            "/reminders" => self.list(user),
+            "/remind" if rest.is_empty() => Ok(USAGE.to_string()),
+            "/remind" if rest.eq_ignore_ascii_case("list") => self.list(user),
+            "/remind" => match strip_word(rest, "cancel") {
+                Some(id) => self.cancel_command(user, id),
+                None => self.create_command(channel, recipient, user, timezone, rest),
+            },
+            _ => return None,
+        };
+        Some(reply.unwrap_or_else(|e| {
+            tracing::warn!("Reminder command failed: {e}");
+            "⚠️ Failed to update reminders".to_string()
+        }))
+    }
+
+    fn list(&self, user: &str) -> Result<String> {
+        let reminders = self.pending(user)?;
+        if reminders.is_empty() {
+            return Ok("You have no reminders.".to_string());
+        }
+        let lines: Vec<String> = reminders.iter().map(Reminder::describe).collect();
+        Ok(format!("⏰ Your reminders:\n{}", lines.join("\n")))
+    }
+
+    fn cancel_command(&self, user: &str, id: &str) -> Result<String> {
+        let Ok(id) = id.trim().trim_start_matches('#').parse::<i64>() else {
+            return Ok("Usage: /remind cancel <id>; /reminders lists the ids".to_string());
+        };
+        Ok(if self.cancel(user, id)? {
+            format!("🗑️ Cancelled reminder #{id}")
+        } else {
+            format!("You have no reminder #{id}")
+        })
+    }
+
+    fn create_command(
+        &self,
+        channel: &str,
+        recipient: &str,
+        user: &str,
+        timezone: Tz,
+        request: &str,
+    ) -> Result<String> {
+        let request = strip_word(request, "me").unwrap_or(request);
+        let now = Utc::now();
+        let Some((schedule, text)) = parse_schedule_prefix(request, now, timezone) else {
+            return Ok(format!("⚠️ I couldn't find a time in that. {USAGE}"));
+        };
+        let text = text.trim();
+        let text = ["to", "that", "about"]
+            .iter()
+            .find_map(|word| strip_word(text, word))
+            .unwrap_or(text)
+            .trim();
+        if text.is_empty() {
+            return Ok(format!("⚠️ What should I remind you of? {USAGE}"));
+        }
+        match self.add(channel, recipient, user, text, schedule, timezone, now) {
+            Ok(reminder) => Ok(format!("⏰ Reminder set: {}", reminder.describe())),
+            Err(e) => Ok(format!("⚠️ {e}")),
+        }
+    }
+}
+
+fn sql_conversion_error(err: anyhow::Error) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(err.into())
+}
+
+fn map_reminder_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Reminder> {
+    let id: i64 = row.get(0)?;
+    let schedule = serde_json::from_str(&row.get::<_, String>(5)?)
+        .with_context(|| format!("Invalid schedule of reminder {id}"))
+        .map_err(sql_conversion_error)?;
+    let next_at = Utc
+        .timestamp_opt(row.get(7)?, 0)
+        .single()
+        .with_context(|| format!("Invalid time of reminder {id}"))
+        .map_err(sql_conversion_error)?;
+    Ok(Reminder {
+        id,
+        channel: row.get(1)?,
+        recipient: row.get(2)?,
+        user: row.get(3)?,
+        text: row.get(4)?,
+        schedule,
+        timezone: Tz::from_str(&row.get::<_, String>(6)?).unwrap_or(Tz::UTC),
+        next_at,
+        attempts: row.get(8)?,
+    })
+}
+
+/// `text` after a leading `word` (ASCII case-insensitive) and whitespace.
+fn strip_word<'a>(text: &'a str, word: &str) -> Option<&'a str> {
+    let head = text.get(..word.len())?;
+    let rest = &text[word.len()..];
+    (head.eq_ignore_ascii_case(word) && rest.starts_with(char::is_whitespace))
+        .then(|| rest.trim_start())
+}
+
+/// Send due reminders through `control`; returns how many went out.
+pub async fn deliver_due(store: &ReminderStore, control: &ChannelControl) -> Result<usize> {
+    let now = Utc::now();
+    let mut sent = 0;
+    for reminder in store.due(now)? {
+        // The user asked for this time, so quiet hours don't hold it
+        match control
+            .send_with_priority(
+                &reminder.channel,
+                &reminder.recipient,
+                &reminder.message(now),
+                Priority::Urgent,
+            )
+            .await
+        {
+            Ok(()) => {
+                store.delivered(&reminder, now)?;
+                sent += 1;
+            }
+            Err(e) => {
+                if store.failed(&reminder, now)? {
+                    tracing::error!(
+                        "Giving up on reminder {} for {} on {} after {} attempts: {e}",
+                        reminder.id,
+                        reminder.user,
+                        reminder.channel,
+                        reminder.attempts + 1
+                    );
+                } else {
+                    tracing::warn!(
+                        "Reminder {} could not be sent on {}, retrying: {e}",
+                        reminder.id,
+                        reminder.channel
+                    );
+                }
+            }
+        }
+    }
+    Ok(sent)
+}
+
+/// Deliver reminders as they fall due, forever.
+pub async fn run_reminder_loop(
+    config: RemindersConfig,
+    workspace_dir: &Path,
+    control: Arc<ChannelControl>,
+) -> Result<()> {
+    let poll_secs = config.poll_secs.max(MIN_POLL_SECS);
+    let store = ReminderStore::open(workspace_dir, config)?;
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(poll_secs));
+    loop {
+        ticker.tick().await;
+        match deliver_due(&store, &control).await {
+            Ok(_) => crate::health::mark_component_ok("reminders"),
+            Err(e) => {
+                tracing::warn!("Reminder delivery failed: {e}");
+                crate::health::mark_component_error("reminders", e.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ReminderStore {
+        ReminderStore::in_memory(RemindersConfig {
+            max_per_user: 2,
+            max_attempts: 2,
+            ..RemindersConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn commands_create_list_and_cancel_own_reminders() {
+        let store = store();
+        let tz = Tz::Europe__Berlin;
+        let reply = store
+            .handle_command(
+                "telegram",
+                "chat-1",
+                "alice",
+                tz,
+                "/remind@zeroclaw_bot me in 30m to restart the build",
+            )
+            .unwrap();
+        assert!(reply.starts_with("⏰ Reminder set: #1"), "{reply}");
+        assert!(
+            reply.ends_with("(Europe/Berlin): restart the build"),
+            "{reply}"
+        );
+
+        let reply = store
+            .handle_command(
+                "telegram",
+                "chat-1",
+                "alice",
+                tz,
+                "/remind every day at 9 stretch",
+            )
+            .unwrap();
+        assert!(reply.contains("repeats 0 9 * * *"), "{reply}");
+        assert!(store
+            .handle_command("telegram", "chat-1", "alice", tz, "/remind in 1h to eat")
+            .unwrap()
+            .contains("already have 2 reminders"));
+        assert!(store
+            .handle_command("telegram", "chat-1", "alice", tz, "/remind me to eat")
+            .unwrap()
+            .contains("couldn't find a time"));
+
+        let list = store
+            .handle_command("telegram", "chat-1", "alice", tz, "/reminders")
+            .unwrap();
+        assert!(
+            list.contains("restart the build") && list.contains("stretch"),
+            "{list}"
+        );
+        assert_eq!(
+            store
+                .handle_command("telegram", "chat-1", "bob", tz, "/remind cancel 1")
+                .unwrap(),
+            "You have no reminder #1"
+        );
+        assert_eq!(
+            store
+                .handle_command("telegram", "chat-1", "alice", tz, "/remind cancel #1")
+                .unwrap(),
+            "🗑️ Cancelled reminder #1"
+        );
+        assert_eq!(store.pending("alice").unwrap().len(), 1);
+        assert!(store
+            .handle_command("telegram", "chat-1", "alice", tz, "/reminder")
+            .is_none());
+    }
+
+    #[test]
+    fn delivery_removes_one_offs_and_reschedules_recurring() {
+        let store = store();
+        let now = Utc::now();
+        let once = store
+            .add(
+                "telegram",
+                "chat-1",
+                "alice",
+                "stand up",
+                Schedule::At {
+                    at: now + Duration::minutes(1),
+                },
+                Tz::UTC,
+                now,
+            )
+            .unwrap();
+        let hourly = store
+            .add(
+                "discord",
+                "chan-2",
+                "alice",
+                "drink water",
+                Schedule::Every {
+                    every_ms: 3_600_000,
+                },
+                Tz::UTC,
+                now,
+            )
+            .unwrap();
+        assert!(store.due(now).unwrap().is_empty());
+
+        // Both are due after a restart two hours later
+        let later = now + Duration::hours(2);
+        let due = store.due(later).unwrap();
+        assert_eq!(due.len(), 2);
+        assert!(due[0].message(later).starts_with("⏰ Reminder (due "));
+        store.delivered(&once, later).unwrap();
+        store.delivered(&hourly, later).unwrap();
+        let pending = store.pending("alice").unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            pending[0].next_at.timestamp(),
+            (later + Duration::hours(1)).timestamp()
+        );
+    }
+
+    #[test]
+    fn failed_deliveries_retry_then_give_up() {
+        let store = store();
+        let now = Utc::now();
+        let reminder = store
+            .add(
+                "telegram",
+                "chat-1",
+                "alice",
+                "call the bank",
+                Schedule::At {
+                    at: now + Duration::seconds(5),
+                },
+                Tz::UTC,
+                now,
+            )
+            .unwrap();
+        let due_at = now + Duration::seconds(10);
+        assert!(!store.failed(&reminder, due_at).unwrap());
+        let retry = &store.pending("alice").unwrap()[0];
+        assert_eq!(retry.attempts, 1);
+        assert!(retry.next_at > due_at);
+        assert!(store.failed(retry, retry.next_at).unwrap());
+        assert!(store.pending("alice").unwrap().is_empty());
+    }
+}
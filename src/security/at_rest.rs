@@ -0,0 +1,230 @@
+//! Encryption at rest for stored messages and memory (`[security.encryption]`).
+//!
+//! Values are sealed with AES-256-GCM as
+//! `aes1:<key id>:<base64(nonce ‖ ciphertext ‖ tag)>`, where the key id is
+//! the first 8 hex digits of the key's SHA-256. The current key seals; it and
+//! the previous keys open, so a key can be rotated without losing data: the
+//! memory store re-seals every row not matching
+//! [`AtRestCipher::current_pattern`] when it opens. Values without the prefix are plaintext
+//! written before encryption was turned on and are returned as-is.
+
+use crate::config::Config;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{AeadCore, Aes256Gcm, Nonce};
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use parking_lot::RwLock;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const PREFIX: &str = "aes1:";
+
+/// AES-GCM nonce length in bytes.
+const NONCE_LEN: usize = 12;
+
+const KEY_LEN: usize = 32;
+
+struct SealingKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl SealingKey {
+    /// A 32-byte key, hex (64 digits) or base64 encoded.
+    fn parse(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let bytes =
+            if encoded.len() == KEY_LEN * 2 && encoded.bytes().all(|b| b.is_ascii_hexdigit()) {
+                hex::decode(encoded)?
+            } else {
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .context("Encryption keys are 32 bytes, hex or base64 encoded")?
+            };
+        if bytes.len() != KEY_LEN {
+            bail!(
+                "Encryption keys are {KEY_LEN} bytes, got {}; generate one with `openssl rand -hex 32`",
+                bytes.len()
+            );
+        }
+        let id = hex::encode(&Sha256::digest(&bytes)[..4]);
+        let cipher = Aes256Gcm::new_from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid encryption key: {e}"))?;
+        Ok(Self { id, cipher })
+    }
+}
+
+/// Seals and opens stored values.
+pub struct AtRestCipher {
+    current: SealingKey,
+    previous: Vec<SealingKey>,
+    /// `aes1:<current key id>:`
+    current_prefix: String,
+}
+
+impl AtRestCipher {
+    /// `key` seals; `previous` keys only open values sealed before a
+    /// rotation.
+    pub fn new(key: &str, previous: &[String]) -> Result<Self> {
+        let current = SealingKey::parse(key).context("Invalid [security.encryption] key")?;
+        let previous = previous
+            .iter()
+            .map(|key| SealingKey::parse(key))
+            .collect::<Result<Vec<_>>>()
+            .context("Invalid [security.encryption] previous_keys entry")?;
+        Ok(Self {
+            current_prefix: format!("{PREFIX}{}:", current.id),
+            current,
+            previous,
+        })
+    }
+
+    /// Id of the key values are sealed with.
+    pub fn key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    /// `plaintext` sealed under the current key.
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Encryption failed: {e}"))?;
+        let mut blob = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ciphertext);
+        Ok(format!(
+            "{}{}",
+            self.current_prefix,
+            base64::engine::general_purpose::STANDARD.encode(blob)
+        ))
+    }
+
+    /// The plaintext of a stored value. Plaintext values are returned as-is;
+    /// values sealed with a key that is no longer configured are an error.
+    pub fn open(&self, stored: &str) -> Result<String> {
+        let Some(sealed) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let (id, encoded) = sealed
+            .split_once(':')
+            .context("Malformed encrypted value")?;
+        let key = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == id)
+            .with_context(|| format!("Value is encrypted with unknown key {id}"))?;
+        let blob = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .context("Malformed encrypted value")?;
+        if blob.len() < NONCE_LEN {
+            bail!("Malformed encrypted value");
+        }
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+        let plaintext = key
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Decryption failed: wrong key or tampered value"))?;
+        String::from_utf8(plaintext).context("Decrypted value is not valid UTF-8")
+    }
+
+    /// Whether a stored value is plaintext or sealed with a previous key.
+    pub fn needs_reseal(&self, stored: &str) -> bool {
+        !stored.starts_with(&self.current_prefix)
+    }
+
+    /// SQL `LIKE` pattern matching values sealed with the current key.
+    pub fn current_pattern(&self) -> String {
+        format!("{}%", self.current_prefix)
+    }
+}
+
+/// The cipher `[security.encryption]` asks for, resolving its keys through
+/// the secret providers, or `None` when encryption is off.
+pub async fn from_config(config: &Config) -> Result<Option<AtRestCipher>> {
+    let encryption = &config.security.encryption;
+    if !encryption.enabled {
+        return Ok(None);
+    }
+    let Some(raw) = encryption.key.as_deref() else {
+        bail!("[security.encryption] is enabled but no key is set");
+    };
+    let zeroclaw_dir = config
+        .config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), std::path::Path::to_path_buf);
+    let resolve = |raw: &str| super::resolve_secret(raw, &config.secrets, &zeroclaw_dir);
+    let key = resolve(raw)?.get().await?;
+    super::redact::register_secret(&key);
+    let mut previous = Vec::with_capacity(encryption.previous_keys.len());
+    for raw in &encryption.previous_keys {
+        let key = resolve(raw)?.get().await?;
+        super::redact::register_secret(&key);
+        previous.push(key);
+    }
+    AtRestCipher::new(&key, &previous).map(Some)
+}
+
+static CIPHER: RwLock<Option<Arc<AtRestCipher>>> = RwLock::new(None);
+
+/// Make `cipher` the process-wide cipher of the stores (`None` turns
+/// encryption off for stores opened from now on).
+pub fn install(cipher: Option<AtRestCipher>) {
+    *CIPHER.write() = cipher.map(Arc::new);
+}
+
+pub fn global() -> Option<Arc<AtRestCipher>> {
+    CIPHER.read().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const KEY_B: &str = "ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8=";
+
+    #[test]
+    fn seals_opens_and_rotates() {
+        let old = AtRestCipher::new(KEY_A, &[]).unwrap();
+        let sealed = old.seal("my order number is 4411").unwrap();
+        assert!(sealed.starts_with(&format!("aes1:{}:", old.key_id())));
+        assert!(!sealed.contains("order number"));
+        assert_ne!(sealed, old.seal("my order number is 4411").unwrap());
+        assert_eq!(old.open(&sealed).unwrap(), "my order number is 4411");
+        assert_eq!(
+            old.open("written in plaintext").unwrap(),
+            "written in plaintext"
+        );
+
+        let rotated = AtRestCipher::new(KEY_B, &[KEY_A.to_string()]).unwrap();
+        assert!(rotated.needs_reseal(&sealed));
+        assert_eq!(rotated.open(&sealed).unwrap(), "my order number is 4411");
+        let resealed = rotated.seal("my order number is 4411").unwrap();
+        assert!(!rotated.needs_reseal(&resealed));
+
+        let without_old = AtRestCipher::new(KEY_B, &[]).unwrap();
+        assert!(without_old
+            .open(&sealed)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown key"));
+        let flip = resealed.len() - 8;
+        let mut tampered = resealed.clone();
+        let replacement = if &resealed[flip..=flip] == "A" {
+            "B"
+        } else {
+            "A"
+        };
+        tampered.replace_range(flip..=flip, replacement);
+        assert!(rotated.open(&tampered).is_err());
+    }
+
+    #[test]
+    fn rejects_short_keys() {
+        assert!(AtRestCipher::new("abcd", &[]).is_err());
+        assert!(AtRestCipher::new(&"ab".repeat(16), &[]).is_err());
+    }
+}
@@ -3,15 +3,16 @@
 use crate::config::AuditConfig;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Audit event types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AuditEventType {
     CommandExecution,
@@ -21,6 +22,7 @@ pub enum AuditEventType {
     AuthFailure,
     PolicyViolation,
     SecurityEvent,
+    AdminCommand,
 }
 
 /// Actor information (who performed the action)
@@ -162,6 +164,20 @@ pub struct CommandExecutionLog<'a> {
     pub duration_ms: u64,
 }
 
+/// An admin command run from chat, for audit logging.
+#[derive(Debug, Clone)]
+pub struct AdminCommandLog<'a> {
+    pub channel: &'a str,
+    pub user_id: &'a str,
+    pub username: Option<&'a str>,
+    pub command: &'a str,
+    /// Role the operator holds, if any
+    pub role: Option<&'a str>,
+    pub allowed: bool,
+    /// Why the command failed, when it was allowed but failed
+    pub error: Option<&'a str>,
+}
+
 impl AuditLogger {
     /// Create a new audit logger
     pub fn new(config: AuditConfig, zeroclaw_dir: PathBuf) -> Result<Self> {
@@ -210,6 +226,49 @@ impl AuditLogger {
         self.log(&event)
     }
 
+    /// Log an admin command run from chat, allowed or not.
+    pub fn log_admin_command(&self, entry: AdminCommandLog<'_>) -> Result<()> {
+        let event = AuditEvent::new(AuditEventType::AdminCommand)
+            .with_actor(
+                entry.channel.to_string(),
+                Some(entry.user_id.to_string()),
+                entry.username.map(str::to_string),
+            )
+            .with_action(
+                entry.command.to_string(),
+                entry.role.unwrap_or("none").to_string(),
+                entry.allowed,
+                entry.allowed,
+            )
+            .with_result(
+                entry.allowed && entry.error.is_none(),
+                None,
+                0,
+                entry.error.map(str::to_string),
+            );
+
+        self.log(&event)
+    }
+
+    /// The last `limit` events of `event_type` in the current log file,
+    /// oldest first.
+    pub fn recent(&self, event_type: &AuditEventType, limit: usize) -> Result<Vec<AuditEvent>> {
+        let content = match std::fs::read_to_string(&self.log_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut events: Vec<AuditEvent> = content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<AuditEvent>(line).ok())
+            .filter(|event| event.event_type == *event_type)
+            .take(limit)
+            .collect();
+        events.reverse();
+        Ok(events)
+    }
+
     /// Backward-compatible helper to log a command execution event.
     #[allow(clippy::too_many_arguments)]
     pub fn log_command(
@@ -258,6 +317,27 @@ impl AuditLogger {
     }
 }
 
+static LOGGER: RwLock<Option<Arc<AuditLogger>>> = RwLock::new(None);
+
+/// Make `logger` the process-wide audit logger (`None` disables it).
+pub fn install(logger: Option<AuditLogger>) {
+    *LOGGER.write() = logger.map(Arc::new);
+}
+
+pub fn global() -> Option<Arc<AuditLogger>> {
+    LOGGER.read().clone()
+}
+
+/// Record an admin command with the process-wide audit logger, if any.
+/// Failures to write are logged, never returned.
+pub fn record_admin_command(entry: AdminCommandLog<'_>) {
+    if let Some(logger) = global() {
+        if let Err(e) = logger.log_admin_command(entry) {
+            tracing::warn!("Failed to write the audit log: {e}");
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,4 +412,35 @@ mod tests {
         assert!(!tmp.path().join("audit.log").exists());
         Ok(())
     }
+
+    #[test]
+    fn admin_commands_are_read_back_newest_last() -> Result<()> {
+        let tmp = TempDir::new()?;
+        let logger = AuditLogger::new(AuditConfig::default(), tmp.path().to_path_buf())?;
+        logger.log_command("cli", "ls", "low", false, true, true, 3)?;
+        for (command, allowed) in [("/mute slack", true), ("/reload", false), ("/audit", true)] {
+            logger.log_admin_command(AdminCommandLog {
+                channel: "telegram",
+                user_id: "42",
+                username: Some("alice"),
+                command,
+                role: Some("admin"),
+                allowed,
+                error: None,
+            })?;
+        }
+
+        let events = logger.recent(&AuditEventType::AdminCommand, 2)?;
+        let commands: Vec<_> = events
+            .iter()
+            .filter_map(|event| event.action.as_ref()?.command.clone())
+            .collect();
+        assert_eq!(commands, ["/reload", "/audit"]);
+        assert!(!events[0].action.as_ref().unwrap().allowed);
+        assert_eq!(
+            events[1].actor.as_ref().unwrap().user_id.as_deref(),
+            Some("42")
+        );
+        Ok(())
+    }
 }
@@ -1,3 +1,4 @@
+pub mod at_rest;
 pub mod audit;
 #[cfg(feature = "sandbox-bubblewrap")]
 pub mod bubblewrap;
@@ -9,8 +10,12 @@ pub mod firejail;
 pub mod landlock;
 pub mod pairing;
 pub mod policy;
+pub mod redact;
+pub mod roles;
+pub mod secret_provider;
 pub mod secrets;
 pub mod traits;
+pub mod webhook_security;
 
 #[allow(unused_imports)]
 pub use audit::{AuditEvent, AuditEventType, AuditLogger};
@@ -20,6 +25,8 @@ pub use detect::create_sandbox;
 pub use pairing::PairingGuard;
 pub use policy::{AutonomyLevel, SecurityPolicy};
 #[allow(unused_imports)]
+pub use secret_provider::{resolve_secret, ManagedSecret, SecretProvider};
+#[allow(unused_imports)]
 pub use secrets::SecretStore;
 #[allow(unused_imports)]
 pub use traits::{NoopSandbox, Sandbox};
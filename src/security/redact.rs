@@ -0,0 +1,162 @@
+//! Masking of secrets in log lines and error messages.
+//!
+//! Two kinds of secrets are masked: values the process knows are secret
+//! (resolved channel secrets, API keys, fetched access tokens — registered
+//! with [`register_secret`]) and anything shaped like a credential
+//! (`Authorization` headers, `token=…` pairs, well-known key prefixes).
+//! [`redact`] is applied to every log line, to recorded channel and health
+//! errors, and to error text replied to users.
+
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Registered values shorter than this are ignored; masking them would
+/// mangle ordinary text.
+const MIN_SECRET_LEN: usize = 8;
+
+static KNOWN_SECRETS: LazyLock<RwLock<BTreeSet<String>>> =
+    LazyLock::new(|| RwLock::new(BTreeSet::new()));
+
+static CREDENTIAL_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    vec![
+        // Authorization headers: `Authorization: Bearer x`, `Bot x`, `QQBot x`
+        Regex::new(r"(?i)(authorization[\x22']?\s*[:=]\s*[\x22']?)[a-z]+\s+[^\s\x22',}]+")
+            .unwrap(),
+        // Credentials in query strings and key=value / "key": "value" pairs
+        Regex::new(
+            r#"(?i)((?:access_token|refresh_token|client_secret|clientsecret|app_secret|api_key|password|token)[\x22']?\s*[:=]\s*[\x22']?)[^\s\x22'&,}]{6,}"#,
+        )
+        .unwrap(),
+    ]
+});
+
+/// Mask `value` wherever it appears from now on.
+pub fn register_secret(value: &str) {
+    let value = value.trim();
+    if value.len() < MIN_SECRET_LEN {
+        return;
+    }
+    if !KNOWN_SECRETS.read().contains(value) {
+        KNOWN_SECRETS.write().insert(value.to_string());
+    }
+}
+
+fn apply_pattern(pattern: &Regex, text: String) -> String {
+    let replacement = if pattern.captures_len() > 1 {
+        "${1}[REDACTED]"
+    } else {
+        REDACTED
+    };
+    match pattern.replace_all(&text, replacement) {
+        std::borrow::Cow::Borrowed(_) => text,
+        std::borrow::Cow::Owned(replaced) => replaced,
+    }
+}
+
+/// `text` with registered secrets and credential-shaped substrings masked.
+pub fn redact(text: &str) -> String {
+    let mut out = text.to_string();
+    {
+        let known = KNOWN_SECRETS.read();
+        // Longest first, so a secret containing another is masked whole.
+        let mut known: Vec<_> = known.iter().collect();
+        known.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        for secret in known {
+            if out.contains(secret.as_str()) {
+                out = out.replace(secret.as_str(), REDACTED);
+            }
+        }
+    }
+    for pattern in CREDENTIAL_PATTERNS.iter() {
+        out = apply_pattern(pattern, out);
+    }
+    crate::providers::scrub_secret_patterns(&out)
+}
+
+/// [`redact`] of an error's full display chain.
+pub fn redact_error(error: &impl Display) -> String {
+    redact(&format!("{error:#}"))
+}
+
+/// [`redact`] plus deployment-specific patterns.
+pub struct Redactor {
+    extra: Vec<Regex>,
+}
+
+impl Redactor {
+    /// A first capture group in an `extra` pattern, if any, is kept.
+    pub fn new(extra: &[String]) -> Result<Self> {
+        let extra = extra
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).with_context(|| format!("Invalid redaction pattern: {pattern}"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { extra })
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        self.extra
+            .iter()
+            .fold(redact(text), |out, pattern| apply_pattern(pattern, out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_headers_and_token_pairs() {
+        assert_eq!(
+            redact("GET /gateway?access_token=abcdef123456&x=1"),
+            "GET /gateway?access_token=[REDACTED]&x=1"
+        );
+        assert_eq!(
+            redact("Authorization: QQBot abc.def.ghi sent"),
+            "Authorization: [REDACTED] sent"
+        );
+        assert_eq!(
+            redact(r#"{"client_secret":"s3cr3t-value","id":7}"#),
+            r#"{"client_secret":"[REDACTED]","id":7}"#
+        );
+        assert_eq!(redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn masks_registered_secrets_anywhere() {
+        register_secret("123456:AAE-telegram-bot-token");
+        register_secret("short");
+        assert_eq!(
+            redact("error sending request for url (https://api.telegram.org/bot123456:AAE-telegram-bot-token/sendMessage)"),
+            "error sending request for url (https://api.telegram.org/bot[REDACTED]/sendMessage)"
+        );
+        assert_eq!(redact("a short word"), "a short word");
+    }
+
+    #[test]
+    fn redactor_applies_extra_patterns() {
+        let redactor = Redactor::new(&["acct-\\d+".into(), "(session=)\\S+".into()]).unwrap();
+        assert_eq!(
+            redactor.redact("user acct-42 session=xyz joined"),
+            "user [REDACTED] session=[REDACTED] joined"
+        );
+        assert!(Redactor::new(&["(".into()]).is_err());
+    }
+
+    #[test]
+    fn error_chains_are_redacted() {
+        let error = anyhow::anyhow!("body: {{\"access_token\":\"tok-0123456789\"}}")
+            .context("QQ token request failed");
+        assert_eq!(
+            redact_error(&error),
+            "QQ token request failed: body: {\"access_token\":\"[REDACTED]\"}"
+        );
+    }
+}
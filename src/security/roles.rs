@@ -0,0 +1,102 @@
+//! Operator roles for admin commands (`[security.roles]`).
+//!
+//! Operators are named `<channel>:<user id>`. Roles are ordered: an owner
+//! may run every admin command, an admin everything but `/reload`, a
+//! moderator only `/block` and `/unblock`. [`required_role`] maps a chat
+//! command to the role it needs.
+
+use crate::config::RolesConfig;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Moderator,
+    Admin,
+    Owner,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Moderator => "moderator",
+            Self::Admin => "admin",
+            Self::Owner => "owner",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+static ROLES: LazyLock<RwLock<RolesConfig>> = LazyLock::new(|| RwLock::new(RolesConfig::default()));
+
+/// Grant the roles of `config` from now on.
+pub fn configure(config: &RolesConfig) {
+    *ROLES.write() = config.clone();
+}
+
+/// Whether anyone holds a role.
+pub fn configured() -> bool {
+    let roles = ROLES.read();
+    !(roles.owners.is_empty() && roles.admins.is_empty() && roles.moderators.is_empty())
+}
+
+/// The highest role of `operator` (`<channel>:<user id>`).
+pub fn role_of(operator: &str) -> Option<Role> {
+    role_in(&ROLES.read(), operator)
+}
+
+fn role_in(config: &RolesConfig, operator: &str) -> Option<Role> {
+    [
+        (Role::Owner, &config.owners),
+        (Role::Admin, &config.admins),
+        (Role::Moderator, &config.moderators),
+    ]
+    .into_iter()
+    .find(|(_, operators)| operators.iter().any(|o| o == operator))
+    .map(|(role, _)| role)
+}
+
+/// Whether `operator` holds `role` or a higher one.
+pub fn has_role(operator: &str, role: Role) -> bool {
+    role_of(operator).is_some_and(|held| held >= role)
+}
+
+/// The role an admin command (`/mute`, without arguments) needs, or `None`
+/// when it isn't one.
+pub fn required_role(command: &str) -> Option<Role> {
+    match command {
+        "/reload" => Some(Role::Owner),
+        "/mute" | "/unmute" | "/announce" | "/audit" | "/debug" => Some(Role::Admin),
+        "/block" | "/unblock" => Some(Role::Moderator),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highest_role_wins_and_covers_lower_ones() {
+        let config = RolesConfig {
+            owners: vec!["telegram:1".into()],
+            admins: vec!["telegram:1".into(), "slack:U2".into()],
+            moderators: vec!["discord:3".into()],
+        };
+        assert_eq!(role_in(&config, "telegram:1"), Some(Role::Owner));
+        assert_eq!(role_in(&config, "slack:U2"), Some(Role::Admin));
+        assert_eq!(role_in(&config, "telegram:U2"), None);
+
+        let moderator = role_in(&config, "discord:3").unwrap();
+        assert!(moderator >= required_role("/block").unwrap());
+        assert!(moderator < required_role("/mute").unwrap());
+        assert_eq!(required_role("/help"), None);
+    }
+}
@@ -0,0 +1,586 @@
+// Pluggable secret sources for channel credentials.
+//
+// Channel secrets in config.toml are plain strings by default. A value may
+// instead be a reference that is resolved at runtime:
+//
+//   env:QQ_APP_SECRET            → environment variable
+//   file:qq_app_secret           → file under `secrets.file_dir` (plaintext or `enc2:`)
+//   vault:zeroclaw/qq#app_secret → HashiCorp Vault KV v2 (`<path>#<field>`)
+//   keyring:qq_app_secret        → OS keyring (`secret-tool` / `security`)
+//   inline:env:not-a-reference   → the literal text after `inline:`
+//
+// Any value starting with one of those prefixes is read as a reference, so a
+// literal secret that happens to begin with `env:` etc. must be written with
+// the `inline:` prefix.
+//
+// Resolved values are cached for `secrets.refresh_secs` and re-read after that,
+// so rotating a secret at the source takes effect without a restart. A failed
+// re-read keeps serving the last good value and backs off before trying again.
+
+use super::SecretStore;
+use crate::config::schema::{SecretsConfig, VaultSecretsConfig};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// A backend that can look up a secret value by key.
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Backend name (e.g. "env", "vault")
+    fn name(&self) -> &str;
+
+    /// Fetch the current value of `key`.
+    async fn fetch(&self, key: &str) -> Result<String>;
+}
+
+// ── Static ───────────────────────────────────────────────────────
+
+/// Inline secret taken verbatim from config. Never changes.
+pub struct StaticSecretProvider {
+    value: String,
+}
+
+impl StaticSecretProvider {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for StaticSecretProvider {
+    fn name(&self) -> &str {
+        "inline"
+    }
+
+    async fn fetch(&self, _key: &str) -> Result<String> {
+        Ok(self.value.clone())
+    }
+}
+
+// ── Environment ──────────────────────────────────────────────────
+
+pub struct EnvSecretProvider;
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    async fn fetch(&self, key: &str) -> Result<String> {
+        match std::env::var(key) {
+            Ok(value) if !value.is_empty() => Ok(value),
+            _ => bail!("Environment variable {key} is not set"),
+        }
+    }
+}
+
+// ── File ─────────────────────────────────────────────────────────
+
+/// One secret per file. Contents may be plaintext or `enc2:` ciphertext
+/// produced by [`SecretStore`].
+pub struct FileSecretProvider {
+    dir: PathBuf,
+    store: SecretStore,
+}
+
+impl FileSecretProvider {
+    pub fn new(dir: PathBuf, store: SecretStore) -> Self {
+        Self { dir, store }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for FileSecretProvider {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn fetch(&self, key: &str) -> Result<String> {
+        if key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.') {
+            bail!("Invalid file secret name: {key:?}");
+        }
+        let path = self.dir.join(key);
+        let raw = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+        self.store.decrypt(raw.trim())
+    }
+}
+
+// ── HashiCorp Vault (KV v2) ──────────────────────────────────────
+
+pub struct VaultSecretProvider {
+    address: String,
+    token: Option<String>,
+    mount: String,
+    client: reqwest::Client,
+}
+
+impl VaultSecretProvider {
+    pub fn new(config: &VaultSecretsConfig) -> Self {
+        Self {
+            address: config.address.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+            mount: config.mount.trim_matches('/').to_string(),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+        }
+    }
+
+    fn token(&self) -> Result<String> {
+        self.token
+            .clone()
+            .filter(|t| !t.is_empty())
+            .or_else(|| std::env::var("VAULT_TOKEN").ok().filter(|t| !t.is_empty()))
+            .context("Vault token missing: set secrets.vault.token or VAULT_TOKEN")
+    }
+}
+
+/// Split a `path#field` Vault reference. The field defaults to `value`.
+fn split_vault_key(key: &str) -> (&str, &str) {
+    match key.split_once('#') {
+        Some((path, field)) if !field.is_empty() => (path.trim_matches('/'), field),
+        Some((path, _)) => (path.trim_matches('/'), "value"),
+        None => (key.trim_matches('/'), "value"),
+    }
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    async fn fetch(&self, key: &str) -> Result<String> {
+        let (path, field) = split_vault_key(key);
+        let url = format!("{}/v1/{}/data/{path}", self.address, self.mount);
+        let resp = self
+            .client
+            .get(&url)
+            .header("X-Vault-Token", self.token()?)
+            .send()
+            .await
+            .context("Vault request failed")?;
+
+        if !resp.status().is_success() {
+            bail!("Vault returned {} for secret path {path}", resp.status());
+        }
+
+        let body: serde_json::Value = resp.json().await?;
+        body.pointer(&format!("/data/data/{field}"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .with_context(|| format!("Vault secret {path} has no string field {field:?}"))
+    }
+}
+
+// ── OS keyring ───────────────────────────────────────────────────
+
+/// Reads secrets from the platform keyring via its CLI
+/// (`secret-tool` on Linux, `security` on macOS).
+pub struct KeyringSecretProvider {
+    service: String,
+}
+
+impl KeyringSecretProvider {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for KeyringSecretProvider {
+    fn name(&self) -> &str {
+        "keyring"
+    }
+
+    async fn fetch(&self, key: &str) -> Result<String> {
+        let mut cmd = if cfg!(target_os = "macos") {
+            let mut c = tokio::process::Command::new("security");
+            c.args([
+                "find-generic-password",
+                "-s",
+                &self.service,
+                "-a",
+                key,
+                "-w",
+            ]);
+            c
+        } else if cfg!(target_os = "linux") {
+            let mut c = tokio::process::Command::new("secret-tool");
+            c.args(["lookup", "service", &self.service, "account", key]);
+            c
+        } else {
+            bail!("OS keyring secrets are only supported on Linux and macOS");
+        };
+
+        let output = cmd.output().await.context("Failed to run keyring helper")?;
+        if !output.status.success() {
+            bail!("Keyring has no entry for {}/{key}", self.service);
+        }
+        let value = String::from_utf8(output.stdout)
+            .context("Keyring secret is not valid UTF-8")?
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        if value.is_empty() {
+            bail!("Keyring entry {}/{key} is empty", self.service);
+        }
+        Ok(value)
+    }
+}
+
+// ── Managed (cached + rotating) secret ───────────────────────────
+
+/// First wait after a failed backend read; doubles per consecutive failure.
+const RETRY_BACKOFF_BASE: Duration = Duration::from_secs(5);
+/// Longest wait between backend reads while the backend keeps failing.
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+#[derive(Default)]
+struct SecretCache {
+    /// Last good value and when it was read.
+    value: Option<(String, Instant)>,
+    /// Consecutive failed reads since the last good one.
+    failures: u32,
+    /// No backend read before this instant while failing.
+    retry_at: Option<Instant>,
+}
+
+/// A resolved secret reference with caching and periodic re-reads.
+///
+/// When a refresh fails but a previous value is cached, the stale value is
+/// returned and a warning logged, so a transient backend outage does not take
+/// a working channel down. Failed reads back off exponentially, so a down
+/// backend is not hit on every call.
+pub struct ManagedSecret {
+    provider: Arc<dyn SecretProvider>,
+    key: String,
+    refresh: Option<Duration>,
+    cache: RwLock<SecretCache>,
+}
+
+impl ManagedSecret {
+    pub fn new(
+        provider: Arc<dyn SecretProvider>,
+        key: impl Into<String>,
+        refresh: Duration,
+    ) -> Self {
+        Self {
+            provider,
+            key: key.into(),
+            refresh: Some(refresh),
+            cache: RwLock::new(SecretCache::default()),
+        }
+    }
+
+    /// A fixed inline value that is never re-read.
+    pub fn fixed(value: impl Into<String>) -> Self {
+        Self {
+            provider: Arc::new(StaticSecretProvider::new(value)),
+            key: String::new(),
+            refresh: None,
+            cache: RwLock::new(SecretCache::default()),
+        }
+    }
+
+    /// Backend this secret is sourced from.
+    pub fn source(&self) -> &str {
+        self.provider.name()
+    }
+
+    /// Current secret value, re-read from the backend when the cache is stale.
+    pub async fn get(&self) -> Result<String> {
+        {
+            let cache = self.cache.read().await;
+            let wait = cache.retry_at.map_or(Duration::ZERO, |at| {
+                at.saturating_duration_since(Instant::now())
+            });
+            if let Some((ref value, fetched_at)) = cache.value {
+                let fresh = self.refresh.is_none_or(|ttl| fetched_at.elapsed() < ttl);
+                if fresh || !wait.is_zero() {
+                    return Ok(value.clone());
+                }
+            } else if !wait.is_zero() {
+                bail!(
+                    "Secret backend {} is failing; next retry in {}s",
+                    self.provider.name(),
+                    wait.as_secs()
+                );
+            }
+        }
+
+        match self.provider.fetch(&self.key).await {
+            Ok(value) => {
+                super::redact::register_secret(&value);
+                *self.cache.write().await = SecretCache {
+                    value: Some((value.clone(), Instant::now())),
+                    failures: 0,
+                    retry_at: None,
+                };
+                Ok(value)
+            }
+            Err(e) => {
+                let mut cache = self.cache.write().await;
+                let backoff = RETRY_BACKOFF_BASE
+                    .saturating_mul(1 << cache.failures.min(6))
+                    .min(RETRY_BACKOFF_MAX);
+                cache.failures = cache.failures.saturating_add(1);
+                cache.retry_at = Some(Instant::now() + backoff);
+                if let Some((ref stale, _)) = cache.value {
+                    tracing::warn!(
+                        "Secret refresh via {} failed, keeping previous value and retrying in {}s: {e}",
+                        self.provider.name(),
+                        backoff.as_secs()
+                    );
+                    return Ok(stale.clone());
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Drop the cached value so the next `get` re-reads the backend.
+    pub async fn invalidate(&self) {
+        *self.cache.write().await = SecretCache::default();
+    }
+}
+
+// ── Factory ──────────────────────────────────────────────────────
+
+/// Resolve a config secret value into a [`ManagedSecret`].
+///
+/// Values without a recognised `env:`/`file:`/`vault:`/`keyring:` prefix are
+/// treated as inline secrets. A value with one of those prefixes is always a
+/// reference; prefix a literal secret that starts that way with `inline:`.
+pub fn resolve_secret(
+    raw: &str,
+    config: &SecretsConfig,
+    zeroclaw_dir: &Path,
+) -> Result<ManagedSecret> {
+    let refresh = Duration::from_secs(config.refresh_secs.max(1));
+    let Some((scheme, key)) = raw.split_once(':') else {
+        return Ok(ManagedSecret::fixed(raw));
+    };
+
+    let provider: Arc<dyn SecretProvider> = match scheme {
+        "inline" => return Ok(ManagedSecret::fixed(key)),
+        "env" => Arc::new(EnvSecretProvider),
+        "file" => {
+            let dir = config.file_dir.as_deref().map_or_else(
+                || zeroclaw_dir.join("secrets"),
+                |d| {
+                    let expanded = PathBuf::from(shellexpand::tilde(d).as_ref());
+                    if expanded.is_absolute() {
+                        expanded
+                    } else {
+                        zeroclaw_dir.join(expanded)
+                    }
+                },
+            );
+            Arc::new(FileSecretProvider::new(
+                dir,
+                SecretStore::new(zeroclaw_dir, config.encrypt),
+            ))
+        }
+        "vault" => {
+            let vault = config
+                .vault
+                .as_ref()
+                .context("Secret uses a vault: reference but [secrets.vault] is not configured")?;
+            Arc::new(VaultSecretProvider::new(vault))
+        }
+        "keyring" => Arc::new(KeyringSecretProvider::new(config.keyring_service.clone())),
+        _ => return Ok(ManagedSecret::fixed(raw)),
+    };
+
+    if key.is_empty() {
+        bail!("Empty secret reference: {scheme}:");
+    }
+
+    Ok(ManagedSecret::new(provider, key, refresh))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingProvider {
+        calls: AtomicUsize,
+        fail_after: usize,
+    }
+
+    #[async_trait]
+    impl SecretProvider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn fetch(&self, _key: &str) -> Result<String> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            if n >= self.fail_after {
+                bail!("backend down");
+            }
+            Ok(format!("v{n}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn inline_value_is_returned_verbatim() {
+        let secret =
+            resolve_secret("plain-secret", &SecretsConfig::default(), Path::new("/tmp")).unwrap();
+        assert_eq!(secret.source(), "inline");
+        assert_eq!(secret.get().await.unwrap(), "plain-secret");
+    }
+
+    #[tokio::test]
+    async fn inline_prefix_keeps_reference_like_values_literal() {
+        let secret = resolve_secret(
+            "inline:env:not-a-variable",
+            &SecretsConfig::default(),
+            Path::new("/tmp"),
+        )
+        .unwrap();
+        assert_eq!(secret.source(), "inline");
+        assert_eq!(secret.get().await.unwrap(), "env:not-a-variable");
+    }
+
+    #[tokio::test]
+    async fn unknown_scheme_is_treated_as_inline() {
+        let secret =
+            resolve_secret("abc:def", &SecretsConfig::default(), Path::new("/tmp")).unwrap();
+        assert_eq!(secret.get().await.unwrap(), "abc:def");
+    }
+
+    #[test]
+    fn vault_reference_without_config_errors() {
+        let result = resolve_secret("vault:qq#secret", &SecretsConfig::default(), Path::new("/"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn empty_reference_key_errors() {
+        assert!(resolve_secret("env:", &SecretsConfig::default(), Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn vault_key_splits_path_and_field() {
+        assert_eq!(split_vault_key("zeroclaw/qq#app"), ("zeroclaw/qq", "app"));
+        assert_eq!(split_vault_key("/zeroclaw/qq/"), ("zeroclaw/qq", "value"));
+        assert_eq!(split_vault_key("qq#"), ("qq", "value"));
+    }
+
+    #[tokio::test]
+    async fn env_provider_reads_variable() {
+        std::env::set_var("ZEROCLAW_TEST_SECRET_PROVIDER_ENV", "from-env");
+        let secret = resolve_secret(
+            "env:ZEROCLAW_TEST_SECRET_PROVIDER_ENV",
+            &SecretsConfig::default(),
+            Path::new("/tmp"),
+        )
+        .unwrap();
+        assert_eq!(secret.source(), "env");
+        assert_eq!(secret.get().await.unwrap(), "from-env");
+        std::env::remove_var("ZEROCLAW_TEST_SECRET_PROVIDER_ENV");
+    }
+
+    #[tokio::test]
+    async fn file_provider_reads_plain_and_encrypted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let secrets_dir = tmp.path().join("secrets");
+        std::fs::create_dir_all(&secrets_dir).unwrap();
+        std::fs::write(secrets_dir.join("plain"), "hunter2\n").unwrap();
+        let store = SecretStore::new(tmp.path(), true);
+        std::fs::write(
+            secrets_dir.join("sealed"),
+            store.encrypt("sealed-value").unwrap(),
+        )
+        .unwrap();
+
+        let config = SecretsConfig::default();
+        let plain = resolve_secret("file:plain", &config, tmp.path()).unwrap();
+        let sealed = resolve_secret("file:sealed", &config, tmp.path()).unwrap();
+        assert_eq!(plain.get().await.unwrap(), "hunter2");
+        assert_eq!(sealed.get().await.unwrap(), "sealed-value");
+    }
+
+    #[tokio::test]
+    async fn file_provider_rejects_path_traversal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider =
+            FileSecretProvider::new(tmp.path().into(), SecretStore::new(tmp.path(), false));
+        assert!(provider.fetch("../config.toml").await.is_err());
+        assert!(provider.fetch(".secret_key").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn managed_secret_caches_until_refresh_interval() {
+        let provider = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            fail_after: usize::MAX,
+        });
+        let secret = ManagedSecret::new(provider.clone(), "k", Duration::from_secs(3600));
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+
+        secret.invalidate().await;
+        assert_eq!(secret.get().await.unwrap(), "v1");
+    }
+
+    #[tokio::test]
+    async fn managed_secret_picks_up_rotation() {
+        let provider = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            fail_after: usize::MAX,
+        });
+        let secret = ManagedSecret::new(provider, "k", Duration::ZERO);
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(secret.get().await.unwrap(), "v1");
+    }
+
+    #[tokio::test]
+    async fn managed_secret_keeps_stale_value_on_refresh_failure() {
+        let provider = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            fail_after: 1,
+        });
+        let secret = ManagedSecret::new(provider, "k", Duration::ZERO);
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(secret.get().await.unwrap(), "v0");
+    }
+
+    #[tokio::test]
+    async fn managed_secret_backs_off_after_refresh_failure() {
+        let provider = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            fail_after: 1,
+        });
+        let secret = ManagedSecret::new(provider.clone(), "k", Duration::ZERO);
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(secret.get().await.unwrap(), "v0");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn managed_secret_errors_without_cached_value() {
+        let provider = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            fail_after: 0,
+        });
+        let secret = ManagedSecret::new(provider, "k", Duration::ZERO);
+        assert!(secret.get().await.is_err());
+    }
+}
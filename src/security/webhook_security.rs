@@ -0,0 +1,442 @@
+//! Signing and verification of webhooks, shared by the endpoints that
+//! receive them and by the outbound event webhooks.
+//!
+//! - HMAC-SHA256 over the body (`sha256=<hex>`): GitHub and `WhatsApp`.
+//! - Timestamped HMAC-SHA256 ([`TimestampedScheme`]): zeroclaw's own
+//!   webhooks sign `<timestamp>.<body>` as `v1=<hex>`, the Slack events API
+//!   signs `v0:<timestamp>:<body>` as `v0=<hex>`. Requests whose timestamp
+//!   is outside the replay window are refused.
+//! - Ed25519 over `<timestamp><body>`: QQ callbacks, with the key derived
+//!   from the bot secret ([`Ed25519Signer`]).
+//!
+//! Signatures are compared in constant time. [`ReplayGuard`] remembers the
+//! delivery ids or signatures seen within the window, so a captured request
+//! can't be sent again while its timestamp is still fresh.
+
+use axum::http::HeaderMap;
+use ed25519_dalek::{Signature, Signer, SigningKey};
+use hmac::{Hmac, Mac};
+use parking_lot::Mutex;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Unix timestamp of a signed zeroclaw webhook.
+pub const TIMESTAMP_HEADER: &str = "X-Zeroclaw-Timestamp";
+
+/// `v1=<hex>` signature of a zeroclaw webhook.
+pub const SIGNATURE_HEADER: &str = "X-Zeroclaw-Signature";
+
+/// Unix timestamp of a Slack request.
+pub const SLACK_TIMESTAMP_HEADER: &str = "X-Slack-Request-Timestamp";
+
+/// `v0=<hex>` signature of a Slack request.
+pub const SLACK_SIGNATURE_HEADER: &str = "X-Slack-Signature";
+
+/// How far a signed request's timestamp may be from now, and how long its
+/// signature is remembered.
+pub const DEFAULT_REPLAY_WINDOW_SECS: u64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    #[error("missing signature")]
+    Missing,
+    #[error("malformed signature")]
+    Malformed,
+    #[error("signature does not match")]
+    Mismatch,
+    #[error("timestamp outside the replay window")]
+    Stale,
+    #[error("request was already received")]
+    Replayed,
+}
+
+fn keyed_mac(secret: &str) -> HmacSha256 {
+    HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length")
+}
+
+/// `sha256=<hex HMAC-SHA256 of body>`, the way GitHub and Meta sign webhooks.
+pub fn sign_hmac_sha256(secret: &str, body: &[u8]) -> String {
+    let mut mac = keyed_mac(secret);
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Verify an `X-Hub-Signature-256`-style header (`sha256=<hex hmac>`) over `body`.
+pub fn verify_hmac_sha256(secret: &str, body: &[u8], signature_header: &str) -> bool {
+    let Some(expected) = signature_header
+        .strip_prefix("sha256=")
+        .and_then(|hex_sig| hex::decode(hex_sig).ok())
+    else {
+        return false;
+    };
+    let mut mac = keyed_mac(secret);
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Layout of a timestamped HMAC-SHA256 signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampedScheme {
+    /// zeroclaw's webhooks: `v1=<hex>` over `<timestamp>.<body>`
+    Zeroclaw,
+    /// Slack request signing: `v0=<hex>` over `v0:<timestamp>:<body>`
+    Slack,
+}
+
+impl TimestampedScheme {
+    /// Timestamp and signature header of a request.
+    fn headers(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Zeroclaw => (TIMESTAMP_HEADER, SIGNATURE_HEADER),
+            Self::Slack => (SLACK_TIMESTAMP_HEADER, SLACK_SIGNATURE_HEADER),
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Zeroclaw => "v1=",
+            Self::Slack => "v0=",
+        }
+    }
+
+    fn mac(self, secret: &str, timestamp: &str, body: &[u8]) -> HmacSha256 {
+        let mut mac = keyed_mac(secret);
+        match self {
+            Self::Zeroclaw => {
+                mac.update(timestamp.as_bytes());
+                mac.update(b".");
+            }
+            Self::Slack => {
+                mac.update(b"v0:");
+                mac.update(timestamp.as_bytes());
+                mac.update(b":");
+            }
+        }
+        mac.update(body);
+        mac
+    }
+
+    /// Signature of `body` sent at `timestamp` (Unix seconds).
+    pub fn sign(self, secret: &str, timestamp: i64, body: &[u8]) -> String {
+        let mac = self.mac(secret, &timestamp.to_string(), body);
+        format!(
+            "{}{}",
+            self.prefix(),
+            hex::encode(mac.finalize().into_bytes())
+        )
+    }
+
+    /// Check `signature` over `body` and that `timestamp` is within `window`
+    /// of `now` (both Unix seconds).
+    pub fn verify(
+        self,
+        secret: &str,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+        window: Duration,
+        now: i64,
+    ) -> Result<(), SignatureError> {
+        if timestamp.is_empty() || signature.is_empty() {
+            return Err(SignatureError::Missing);
+        }
+        let sent: i64 = timestamp.parse().map_err(|_| SignatureError::Malformed)?;
+        let expected = signature
+            .strip_prefix(self.prefix())
+            .and_then(|hex_sig| hex::decode(hex_sig).ok())
+            .ok_or(SignatureError::Malformed)?;
+        self.mac(secret, timestamp, body)
+            .verify_slice(&expected)
+            .map_err(|_| SignatureError::Mismatch)?;
+        if now.abs_diff(sent) > window.as_secs() {
+            return Err(SignatureError::Stale);
+        }
+        Ok(())
+    }
+}
+
+/// Headers that sign an outbound webhook carrying `body`.
+pub fn signature_headers(secret: &str, body: &[u8]) -> [(&'static str, String); 2] {
+    let now = chrono::Utc::now().timestamp();
+    [
+        (TIMESTAMP_HEADER, now.to_string()),
+        (
+            SIGNATURE_HEADER,
+            TimestampedScheme::Zeroclaw.sign(secret, now, body),
+        ),
+    ]
+}
+
+/// Remembers ids (delivery ids or signatures) seen within a window.
+pub struct ReplayGuard {
+    window: Duration,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl ReplayGuard {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `Ok` the first time `id` is seen within the window.
+    pub fn check(&self, id: &str) -> Result<(), SignatureError> {
+        let now = Instant::now();
+        let mut seen = self.seen.lock();
+        seen.retain(|_, at| now.duration_since(*at) < self.window);
+        if seen.contains_key(id) {
+            return Err(SignatureError::Replayed);
+        }
+        seen.insert(id.to_string(), now);
+        Ok(())
+    }
+}
+
+/// Verifies requests signed with [`signature_headers`] (or by Slack): the
+/// signature, the replay window and signatures already received.
+pub struct SignedWebhookVerifier {
+    scheme: TimestampedScheme,
+    secret: String,
+    window: Duration,
+    seen: ReplayGuard,
+}
+
+impl SignedWebhookVerifier {
+    pub fn new(secret: impl Into<String>, window: Duration) -> Self {
+        Self {
+            scheme: TimestampedScheme::Zeroclaw,
+            secret: secret.into(),
+            window,
+            seen: ReplayGuard::new(window),
+        }
+    }
+
+    /// Verifier of Slack events API requests signed with the app's signing
+    /// secret.
+    pub fn slack(secret: impl Into<String>, window: Duration) -> Self {
+        Self {
+            scheme: TimestampedScheme::Slack,
+            ..Self::new(secret, window)
+        }
+    }
+
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), SignatureError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+        };
+        let (timestamp_header, signature_header) = self.scheme.headers();
+        let signature = header(signature_header);
+        self.scheme.verify(
+            &self.secret,
+            header(timestamp_header),
+            signature,
+            body,
+            self.window,
+            chrono::Utc::now().timestamp(),
+        )?;
+        self.seen.check(signature)
+    }
+}
+
+/// Ed25519 key pair derived from a bot secret the way QQ callbacks derive
+/// it: the secret, repeated to 32 bytes, is the seed.
+pub struct Ed25519Signer {
+    key: SigningKey,
+}
+
+impl Ed25519Signer {
+    /// `None` for an empty secret.
+    pub fn from_secret(secret: &str) -> Option<Self> {
+        let seed: [u8; 32] = secret
+            .bytes()
+            .cycle()
+            .take(32)
+            .collect::<Vec<_>>()
+            .try_into()
+            .ok()?;
+        Some(Self {
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Hex signature of `message`.
+    pub fn sign(&self, message: &[u8]) -> String {
+        hex::encode(self.key.sign(message).to_bytes())
+    }
+
+    /// Check a hex signature of `<timestamp><body>` and that `timestamp` is
+    /// within `window` of `now` (both Unix seconds).
+    pub fn verify(
+        &self,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+        window: Duration,
+        now: i64,
+    ) -> Result<(), SignatureError> {
+        if timestamp.is_empty() || signature.is_empty() {
+            return Err(SignatureError::Missing);
+        }
+        let sent: i64 = timestamp.parse().map_err(|_| SignatureError::Malformed)?;
+        let signature = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or(SignatureError::Malformed)?;
+        let mut message = Vec::with_capacity(timestamp.len() + body.len());
+        message.extend_from_slice(timestamp.as_bytes());
+        message.extend_from_slice(body);
+        self.key
+            .verifying_key()
+            .verify_strict(&message, &signature)
+            .map_err(|_| SignatureError::Mismatch)?;
+        if now.abs_diff(sent) > window.as_secs() {
+            return Err(SignatureError::Stale);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn hmac_signature_roundtrip() {
+        let header = sign_hmac_sha256("secret", b"{}");
+        assert!(header.starts_with("sha256="));
+        assert!(verify_hmac_sha256("secret", b"{}", &header));
+        assert!(!verify_hmac_sha256("other", b"{}", &header));
+        assert!(!verify_hmac_sha256("secret", b"{ }", &header));
+        assert!(!verify_hmac_sha256("secret", b"{}", "sha1=abc"));
+        assert!(!verify_hmac_sha256("secret", b"{}", "sha256=zz"));
+    }
+
+    #[test]
+    fn timestamped_signatures_enforce_the_replay_window() {
+        let window = Duration::from_secs(DEFAULT_REPLAY_WINDOW_SECS);
+        let body = br#"{"type":"event_callback"}"#;
+        let sig = TimestampedScheme::Slack.sign("s3cret", 1_700_000_000, body);
+        assert!(sig.starts_with("v0="));
+        let verify = |ts: &str, sig: &str, now: i64| {
+            TimestampedScheme::Slack.verify("s3cret", ts, sig, body, window, now)
+        };
+        assert_eq!(verify("1700000000", &sig, 1_700_000_100), Ok(()));
+        assert_eq!(
+            verify("1700000000", &sig, 1_700_000_301),
+            Err(SignatureError::Stale)
+        );
+        assert_eq!(
+            verify("1700000001", &sig, 1_700_000_100),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify("soon", &sig, 1_700_000_100),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            verify("1700000000", "", 1_700_000_100),
+            Err(SignatureError::Missing)
+        );
+        // The schemes don't accept each other's signatures
+        let ours = TimestampedScheme::Zeroclaw.sign("s3cret", 1_700_000_000, body);
+        assert_eq!(
+            verify("1700000000", &ours, 1_700_000_100),
+            Err(SignatureError::Malformed)
+        );
+    }
+
+    #[test]
+    fn signed_webhooks_are_accepted_once() {
+        let body = br#"{"message":"hi"}"#;
+        let mut headers = HeaderMap::new();
+        for (name, value) in signature_headers("s3cret", body) {
+            headers.insert(name, HeaderValue::from_str(&value).unwrap());
+        }
+        let verifier =
+            SignedWebhookVerifier::new("s3cret", Duration::from_secs(DEFAULT_REPLAY_WINDOW_SECS));
+        assert_eq!(verifier.verify(&headers, body), Ok(()));
+        assert_eq!(
+            verifier.verify(&headers, body),
+            Err(SignatureError::Replayed)
+        );
+        assert_eq!(
+            verifier.verify(&HeaderMap::new(), body),
+            Err(SignatureError::Missing)
+        );
+        let other = SignedWebhookVerifier::new("other", Duration::from_secs(60));
+        assert_eq!(other.verify(&headers, body), Err(SignatureError::Mismatch));
+    }
+
+    #[test]
+    fn slack_requests_are_verified_from_their_headers() {
+        let body = br#"{"type":"event_callback"}"#;
+        let now = chrono::Utc::now().timestamp();
+        let mut headers = HeaderMap::new();
+        headers.insert(SLACK_TIMESTAMP_HEADER, HeaderValue::from(now));
+        headers.insert(
+            SLACK_SIGNATURE_HEADER,
+            HeaderValue::from_str(&TimestampedScheme::Slack.sign("s3cret", now, body)).unwrap(),
+        );
+        let verifier =
+            SignedWebhookVerifier::slack("s3cret", Duration::from_secs(DEFAULT_REPLAY_WINDOW_SECS));
+        assert_eq!(verifier.verify(&headers, body), Ok(()));
+        assert_eq!(
+            verifier.verify(&headers, body),
+            Err(SignatureError::Replayed)
+        );
+
+        // zeroclaw's own headers don't satisfy a Slack verifier
+        let mut ours = HeaderMap::new();
+        for (name, value) in signature_headers("s3cret", body) {
+            ours.insert(name, HeaderValue::from_str(&value).unwrap());
+        }
+        assert_eq!(verifier.verify(&ours, body), Err(SignatureError::Missing));
+    }
+
+    #[test]
+    fn ed25519_signatures_from_a_bot_secret() {
+        let window = Duration::from_secs(DEFAULT_REPLAY_WINDOW_SECS);
+        let body = br#"{"op":0}"#;
+        assert!(Ed25519Signer::from_secret("").is_none());
+        let signer = Ed25519Signer::from_secret("naOC0ocQE3shWLAfffVLB1rhYPG7").unwrap();
+        let signature = signer.sign(b"1725442341{\"op\":0}");
+        let verify = |signer: &Ed25519Signer, ts: &str, sig: &str, now: i64| {
+            signer.verify(ts, sig, body, window, now)
+        };
+        assert_eq!(
+            verify(&signer, "1725442341", &signature, 1_725_442_400),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&signer, "1725442342", &signature, 1_725_442_400),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify(&signer, "1725442341", &signature, 1_725_443_000),
+            Err(SignatureError::Stale)
+        );
+        assert_eq!(
+            verify(&signer, "1725442341", "abcd", 1_725_442_400),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            verify(&signer, "", &signature, 1_725_442_400),
+            Err(SignatureError::Missing)
+        );
+
+        let other = Ed25519Signer::from_secret("another bot secret").unwrap();
+        assert_eq!(
+            verify(&other, "1725442341", &signature, 1_725_442_400),
+            Err(SignatureError::Mismatch)
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Snapshots of a deployment's operational state (`zeroclaw state export`
+//! and `zeroclaw state import`), for moving it to another host.
+//!
+//! A snapshot is a zstd-compressed tar of the stores that hold state an
+//! operator would otherwise have to rebuild by hand:
+//!
+//! | Archive path              | Contents                                        |
+//! |---------------------------|-------------------------------------------------|
+//! | `memory/`, `MEMORY.md`    | agent memory                                    |
+//! | `cron/jobs.db`            | scheduled tasks                                 |
+//! | `state/reminders.db`      | pending reminders                               |
+//! | `state/kv.db`             | user preferences and plugin state               |
+//! | `state/usage.db`          | per-user and per-channel quota ledger           |
+//! | `state/analytics.db`      | usage history behind the ops digest             |
+//! | `users/identities.db`     | identity links between channel accounts         |
+//! | `notify/subscriptions.db` | topic subscriptions for `/notify`               |
+//! | `feeds/seen.db`           | feed entries already posted                     |
+//! | `knowledge/knowledge.db`  | ingested knowledge base documents               |
+//! | `config.toml`             | ACLs, allow lists, roles and paired tokens      |
+//!
+//! plus a `manifest.json`. SQLite databases are copied with `VACUUM INTO`,
+//! so exporting while channels run still yields consistent files. Caches
+//! are left out. Secrets in `config.toml` stay encrypted with this host's
+//! `.secret_key`, which is not exported: copy it separately or re-enter
+//! the secrets.
+//!
+//! Import replaces the files it restores, keeping each replaced file as
+//! `<name>.bak`. Stop the daemon first so nothing writes to them meanwhile.
+
+use crate::config::Config;
+use crate::util::ScratchDir;
+use anyhow::{bail, Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Snapshot layout version, bumped on incompatible changes.
+const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+
+/// Archive name of the config file.
+const CONFIG_ENTRY: &str = "config.toml";
+
+/// Workspace files and directories a snapshot carries.
+const WORKSPACE_ENTRIES: &[&str] = &[
+    "memory",
+    "MEMORY.md",
+    "cron/jobs.db",
+    "state/reminders.db",
+    "state/kv.db",
+    "state/usage.db",
+    "state/analytics.db",
+    "users/identities.db",
+    "notify/subscriptions.db",
+    "feeds/seen.db",
+    "knowledge/knowledge.db",
+];
+
+/// Files under the entries above that are caches or SQLite side files.
+const SKIPPED_FILES: &[&str] = &["response_cache.db"];
+const SKIPPED_SUFFIXES: &[&str] = &["-wal", "-shm", "-journal", ".bak"];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    zeroclaw_version: String,
+    created_at: String,
+    files: Vec<String>,
+}
+
+pub fn handle_command(command: crate::StateCommands, config: &Config) -> Result<()> {
+    match command {
+        crate::StateCommands::Export { out } => {
+            let files = export(config, &out)?;
+            println!("✅ Exported {} files to {}", files.len(), out.display());
+            for file in files {
+                println!("   {file}");
+            }
+            println!();
+            println!("Secrets stay encrypted with .secret_key, which is not included.");
+            Ok(())
+        }
+        crate::StateCommands::Import { archive, dry_run } => {
+            let files = import(config, &archive, dry_run)?;
+            if dry_run {
+                println!("🔎 Dry run: {} would restore", archive.display());
+            } else {
+                println!(
+                    "✅ Restored {} files from {}",
+                    files.len(),
+                    archive.display()
+                );
+            }
+            for file in files {
+                println!("   {file}");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write a snapshot of `config`'s deployment to `out`. Returns the archive
+/// paths written.
+pub fn export(config: &Config, out: &Path) -> Result<Vec<String>> {
+    let scratch = ScratchDir::create("zeroclaw-state")?;
+    let mut files = Vec::new();
+    for entry in WORKSPACE_ENTRIES {
+        collect(&config.workspace_dir, Path::new(entry), &mut files)?;
+    }
+    for name in &files {
+        stage(&config.workspace_dir.join(name), &scratch.path().join(name))?;
+    }
+    if config.config_path.is_file() {
+        fs::copy(&config.config_path, scratch.path().join(CONFIG_ENTRY))
+            .with_context(|| format!("Failed to read {}", config.config_path.display()))?;
+        files.push(CONFIG_ENTRY.to_string());
+    }
+    if files.is_empty() {
+        bail!("Nothing to export in {}", config.workspace_dir.display());
+    }
+
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        zeroclaw_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files: files.clone(),
+    };
+    fs::write(
+        scratch.path().join(MANIFEST),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+
+    // Written next to the target and renamed, so a failed export never
+    // leaves a truncated snapshot behind
+    let partial = out.with_extension("partial");
+    let result = write_archive(scratch.path(), &files, &partial)
+        .and_then(|()| fs::rename(&partial, out).map_err(Into::into));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result.with_context(|| format!("Failed to write {}", out.display()))?;
+    Ok(files)
+}
+
+/// Restore the snapshot at `archive` into `config`'s deployment, or only
+/// list what it would restore when `dry_run`. Returns the archive paths.
+pub fn import(config: &Config, archive: &Path, dry_run: bool) -> Result<Vec<String>> {
+    let scratch = ScratchDir::create("zeroclaw-state")?;
+    let manifest = unpack(archive, scratch.path())?;
+    if dry_run {
+        return Ok(manifest.files);
+    }
+    for name in &manifest.files {
+        let target = if name == CONFIG_ENTRY {
+            config.config_path.clone()
+        } else {
+            config.workspace_dir.join(name)
+        };
+        restore(&scratch.path().join(name), &target)?;
+    }
+    Ok(manifest.files)
+}
+
+/// Add the files at `rel` under `root` (recursing into directories) to
+/// `files`, as `/`-separated relative paths.
+fn collect(root: &Path, rel: &Path, files: &mut Vec<String>) -> Result<()> {
+    let path = root.join(rel);
+    if path.is_dir() {
+        let mut children: Vec<PathBuf> = fs::read_dir(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| rel.join(entry.file_name())))
+            .collect();
+        children.sort();
+        for child in children {
+            collect(root, &child, files)?;
+        }
+    } else if path.is_file() && !skipped(rel) {
+        let name: Vec<String> = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        files.push(name.join("/"));
+    }
+    Ok(())
+}
+
+fn skipped(rel: &Path) -> bool {
+    let name = rel
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    SKIPPED_FILES.contains(&name.as_str())
+        || SKIPPED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Copy `src` to `dest`; SQLite databases through `VACUUM INTO`, which
+/// reads a consistent snapshot even while another process writes.
+fn stage(src: &Path, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if src.extension().is_some_and(|ext| ext == "db") {
+        let conn = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open {}", src.display()))?;
+        conn.execute("VACUUM INTO ?1", [dest.to_string_lossy()])
+            .with_context(|| format!("Failed to copy {}", src.display()))?;
+    } else {
+        fs::copy(src, dest).with_context(|| format!("Failed to read {}", src.display()))?;
+    }
+    Ok(())
+}
+
+fn write_archive(dir: &Path, files: &[String], out: &Path) -> Result<()> {
+    let file = fs::File::create(out)?;
+    let encoder = zstd::stream::Encoder::new(file, 0)?;
+    let mut tar = tar::Builder::new(encoder);
+    tar.append_path_with_name(dir.join(MANIFEST), MANIFEST)?;
+    for name in files {
+        tar.append_path_with_name(dir.join(name), name)?;
+    }
+    tar.into_inner()?.finish()?.sync_all()?;
+    Ok(())
+}
+
+/// Whether `name` is a path a snapshot may contain: relative, without
+/// `..`, and under one of the known entries.
+fn allowed_entry(name: &str) -> bool {
+    let path = Path::new(name);
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return false;
+    }
+    name == MANIFEST
+        || name == CONFIG_ENTRY
+        || WORKSPACE_ENTRIES
+            .iter()
+            .any(|entry| name == *entry || name.starts_with(&format!("{entry}/")))
+}
+
+/// Extract `archive` into `dir` and return its manifest, after checking
+/// every entry against [`allowed_entry`] and the manifest.
+fn unpack(archive: &Path, dir: &Path) -> Result<Manifest> {
+    let file =
+        fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let decoder = zstd::stream::Decoder::new(file)?;
+    let mut tar = tar::Archive::new(decoder);
+    let mut unpacked = Vec::new();
+    for entry in tar.entries().context("Not a state snapshot")? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        if !allowed_entry(&name) {
+            bail!("Unexpected file in snapshot: {name}");
+        }
+        let dest = dir.join(&name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&dest)?;
+        unpacked.push(name);
+    }
+
+    let manifest: Manifest = serde_json::from_slice(
+        &fs::read(dir.join(MANIFEST)).context("Snapshot has no manifest.json")?,
+    )
+    .context("Invalid manifest.json")?;
+    if manifest.format != FORMAT_VERSION {
+        bail!(
+            "Snapshot format {} is not supported (expected {FORMAT_VERSION}); it was written by zeroclaw {}",
+            manifest.format,
+            manifest.zeroclaw_version
+        );
+    }
+    if let Some(missing) = manifest.files.iter().find(|f| !unpacked.contains(f)) {
+        bail!("Snapshot is missing {missing}");
+    }
+    Ok(manifest)
+}
+
+/// Move `src` to `target`, keeping a file already there as `<name>.bak`.
+fn restore(src: &Path, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if target.exists() {
+        let mut backup = target.as_os_str().to_owned();
+        backup.push(".bak");
+        fs::rename(target, &backup)
+            .with_context(|| format!("Failed to back up {}", target.display()))?;
+    }
+    // The scratch directory may be on another filesystem
+    fs::copy(src, target).with_context(|| format!("Failed to write {}", target.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config_in(dir: &Path) -> Config {
+        Config {
+            workspace_dir: dir.join("workspace"),
+            config_path: dir.join("config.toml"),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn snapshots_round_trip_between_deployments() {
+        let source = TempDir::new().unwrap();
+        let config = config_in(source.path());
+        let ws = &config.workspace_dir;
+        fs::create_dir_all(ws.join("memory")).unwrap();
+        fs::create_dir_all(ws.join("state")).unwrap();
+        let conn = Connection::open(ws.join("state/kv.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE kv (k TEXT, v TEXT); INSERT INTO kv VALUES ('lang', 'de');",
+        )
+        .unwrap();
+        drop(conn);
+        fs::write(ws.join("memory/2026-01-01.md"), "- met Bob").unwrap();
+        fs::write(ws.join("memory/response_cache.db"), "cache").unwrap();
+        fs::write(&config.config_path, "[security.roles]\nowners = [\"1\"]\n").unwrap();
+
+        let out = source.path().join("state.tar.zst");
+        let files = export(&config, &out).unwrap();
+        assert_eq!(
+            files,
+            vec!["memory/2026-01-01.md", "state/kv.db", "config.toml"]
+        );
+
+        let target = TempDir::new().unwrap();
+        let restored = config_in(target.path());
+        fs::write(&restored.config_path, "old").unwrap();
+        assert_eq!(import(&restored, &out, true).unwrap(), files);
+        assert!(!restored.workspace_dir.join("state/kv.db").exists());
+
+        import(&restored, &out, false).unwrap();
+        let conn = Connection::open(restored.workspace_dir.join("state/kv.db")).unwrap();
+        let lang: String = conn
+            .query_row("SELECT v FROM kv WHERE k = 'lang'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(lang, "de");
+        assert_eq!(
+            fs::read_to_string(restored.workspace_dir.join("memory/2026-01-01.md")).unwrap(),
+            "- met Bob"
+        );
+        assert!(fs::read_to_string(&restored.config_path)
+            .unwrap()
+            .contains("owners"));
+        let mut backup = restored.config_path.into_os_string();
+        backup.push(".bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "old");
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_snapshot_layout() {
+        assert!(allowed_entry("state/kv.db"));
+        assert!(allowed_entry("memory/notes/a.md"));
+        assert!(allowed_entry(CONFIG_ENTRY));
+        assert!(!allowed_entry("state/costs.jsonl"));
+        assert!(!allowed_entry("memory/../../etc/passwd"));
+        assert!(!allowed_entry("/etc/passwd"));
+        assert!(!allowed_entry("memoryx/a.md"));
+    }
+
+    #[test]
+    fn every_workspace_store_is_exported_or_skipped() {
+        let dir = TempDir::new().unwrap();
+        let config = config_in(dir.path());
+        let ws = &config.workspace_dir;
+        let prices = std::collections::HashMap::new();
+        crate::cron::list_jobs(&config).unwrap();
+        crate::reminders::ReminderStore::open(ws, crate::config::RemindersConfig::default())
+            .unwrap();
+        crate::kv::KvStore::open(ws, 1024).unwrap();
+        crate::cost::QuotaLedger::open(ws, &crate::config::QuotaConfig::default(), &prices)
+            .unwrap();
+        crate::analytics::Analytics::open(ws, &prices).unwrap();
+        crate::identity::linking::IdentityStore::open(ws, 600).unwrap();
+        crate::ingress::notify::TopicSubscriptions::open(ws, Vec::new()).unwrap();
+        crate::feeds::FeedStore::open(ws).unwrap();
+        crate::knowledge::SqliteVectorStore::open(ws).unwrap();
+        crate::memory::ResponseCache::new(ws, 60, 10).unwrap();
+
+        let mut stores = Vec::new();
+        collect(ws, Path::new(""), &mut stores).unwrap();
+        stores.retain(|file| file.ends_with(".db"));
+        assert_eq!(stores.len(), 9, "{stores:?}");
+        for store in stores {
+            assert!(allowed_entry(&store), "{store} is left out of snapshots");
+        }
+    }
+}
@@ -21,6 +21,10 @@ impl Tool for CronListTool {
         "cron_list"
     }
 
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         "List all scheduled cron jobs"
     }
@@ -35,6 +35,10 @@ impl Tool for CronRunsTool {
         "cron_runs"
     }
 
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         "List recent run history for a cron job"
     }
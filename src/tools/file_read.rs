@@ -23,6 +23,10 @@ impl Tool for FileReadTool {
         "file_read"
     }
 
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         "Read the contents of a file in the workspace"
     }
@@ -0,0 +1,156 @@
+use super::traits::{Tool, ToolResult};
+use crate::imagegen::ImageGenerator;
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Generate an image from a prompt and save it under `<workspace>/generated`.
+/// The reply marker it returns makes channels upload the file natively.
+pub struct ImageGenerateTool {
+    security: Arc<SecurityPolicy>,
+    generator: Arc<dyn ImageGenerator>,
+    output_dir: PathBuf,
+}
+
+impl ImageGenerateTool {
+    pub fn new(
+        security: Arc<SecurityPolicy>,
+        generator: Arc<dyn ImageGenerator>,
+        workspace_dir: &std::path::Path,
+    ) -> Self {
+        Self {
+            security,
+            generator,
+            output_dir: workspace_dir.join("generated"),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ImageGenerateTool {
+    fn name(&self) -> &str {
+        "image_generate"
+    }
+
+    fn description(&self) -> &str {
+        "Generate an image from a text description. Returns a marker to include in your reply so the image is sent to the user."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "prompt": {
+                    "type": "string",
+                    "description": "Detailed description of the image to generate"
+                }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        if !self.security.record_action() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Action blocked: rate limit exceeded".into()),
+            });
+        }
+
+        let prompt = args
+            .get("prompt")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'prompt' parameter"))?;
+
+        let image = match self.generator.generate(prompt).await {
+            Ok(image) => image,
+            Err(e) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!("Image generation failed: {e}")),
+                })
+            }
+        };
+
+        tokio::fs::create_dir_all(&self.output_dir).await?;
+        let path = self
+            .output_dir
+            .join(image.file_name(&uuid::Uuid::new_v4().to_string()));
+        tokio::fs::write(&path, &image.bytes).await?;
+
+        let mut output = format!(
+            "Image saved to {path}. Include [IMAGE:{path}] in your reply to show it to the user.",
+            path = path.display()
+        );
+        if let Some(store) = crate::media_store::global().filter(|store| store.persist_generated())
+        {
+            match store.store(image.bytes, &image.mime_type).await {
+                Ok(stored) => {
+                    let _ = write!(output, " Media key: {}.", stored.key);
+                }
+                Err(e) => tracing::warn!("Failed to store generated image: {e}"),
+            }
+        }
+
+        Ok(ToolResult {
+            success: true,
+            output,
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::imagegen::GeneratedImage;
+
+    struct FixedGenerator;
+
+    #[async_trait]
+    impl ImageGenerator for FixedGenerator {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn generate(&self, prompt: &str) -> anyhow::Result<GeneratedImage> {
+            if prompt == "fail" {
+                anyhow::bail!("content policy");
+            }
+            Ok(GeneratedImage::png(b"\x89PNG".to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn saves_image_and_returns_marker() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tool = ImageGenerateTool::new(
+            Arc::new(SecurityPolicy::default()),
+            Arc::new(FixedGenerator),
+            tmp.path(),
+        );
+
+        let result = tool.execute(json!({ "prompt": "a fox" })).await.unwrap();
+        assert!(result.success);
+        let path = result
+            .output
+            .split("[IMAGE:")
+            .nth(1)
+            .and_then(|rest| rest.split(']').next())
+            .unwrap();
+        assert!(path.ends_with(".png"));
+        assert_eq!(std::fs::read(path).unwrap(), b"\x89PNG");
+
+        let failed = tool.execute(json!({ "prompt": "fail" })).await.unwrap();
+        assert!(!failed.success);
+        assert!(failed.error.unwrap().contains("content policy"));
+        assert!(tool.execute(json!({})).await.is_err());
+    }
+}
@@ -124,6 +124,10 @@ impl Tool for ImageInfoTool {
         "image_info"
     }
 
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         "Read image file metadata (format, dimensions, size) and optionally return base64-encoded data."
     }
@@ -22,6 +22,10 @@ impl Tool for MemoryRecallTool {
         "memory_recall"
     }
 
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &str {
         "Search long-term memory for relevant facts, preferences, or context. Returns scored results ranked by relevance."
     }
@@ -15,6 +15,7 @@ pub mod hardware_board_info;
 pub mod hardware_memory_map;
 pub mod hardware_memory_read;
 pub mod http_request;
+pub mod image_generate;
 pub mod image_info;
 pub mod memory_forget;
 pub mod memory_recall;
@@ -25,6 +26,7 @@ pub mod schema;
 pub mod screenshot;
 pub mod shell;
 pub mod traits;
+pub mod web_search;
 
 pub use browser::{BrowserTool, ComputerUseConfig};
 pub use browser_open::BrowserOpenTool;
@@ -43,6 +45,7 @@ pub use hardware_board_info::HardwareBoardInfoTool;
 pub use hardware_memory_map::HardwareMemoryMapTool;
 pub use hardware_memory_read::HardwareMemoryReadTool;
 pub use http_request::HttpRequestTool;
+pub use image_generate::ImageGenerateTool;
 pub use image_info::ImageInfoTool;
 pub use memory_forget::MemoryForgetTool;
 pub use memory_recall::MemoryRecallTool;
@@ -55,6 +58,7 @@ pub use shell::ShellTool;
 pub use traits::Tool;
 #[allow(unused_imports)]
 pub use traits::{ToolResult, ToolSpec};
+pub use web_search::WebSearchTool;
 
 use crate::config::{Config, DelegateAgentConfig};
 use crate::memory::Memory;
@@ -191,6 +195,30 @@ pub fn all_tools_with_runtime(
     tools.push(Box::new(ScreenshotTool::new(security.clone())));
     tools.push(Box::new(ImageInfoTool::new(security.clone())));
 
+    if root_config.image_generation.enabled {
+        match image_generator(root_config) {
+            Ok(Some(generator)) => tools.push(Box::new(ImageGenerateTool::new(
+                security.clone(),
+                generator,
+                workspace_dir,
+            ))),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Image generation disabled: {e}"),
+        }
+    }
+
+    if root_config.web_search.enabled {
+        match search_backend(root_config) {
+            Ok(Some(backend)) => tools.push(Box::new(WebSearchTool::new(
+                security.clone(),
+                backend,
+                root_config.web_search.max_results,
+            ))),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Web search disabled: {e}"),
+        }
+    }
+
     if let Some(key) = composio_key {
         if !key.is_empty() {
             tools.push(Box::new(ComposioTool::new(key, composio_entity_id)));
@@ -216,6 +244,36 @@ pub fn all_tools_with_runtime(
     tools
 }
 
+/// Resolve an optional credential that may be an `env:`/`file:`/`vault:`/`keyring:`
+/// reference.
+fn resolve_tool_secret(
+    config: &Config,
+    raw: Option<&str>,
+) -> anyhow::Result<Option<Arc<crate::security::ManagedSecret>>> {
+    let zeroclaw_dir = config.config_path.parent().map_or_else(
+        || std::path::PathBuf::from("."),
+        std::path::Path::to_path_buf,
+    );
+    Ok(raw
+        .map(|raw| crate::security::resolve_secret(raw, &config.secrets, &zeroclaw_dir))
+        .transpose()?
+        .map(Arc::new))
+}
+
+fn image_generator(
+    config: &Config,
+) -> anyhow::Result<Option<Arc<dyn crate::imagegen::ImageGenerator>>> {
+    let api_key = resolve_tool_secret(config, config.image_generation.api_key.as_deref())?;
+    crate::imagegen::create_image_generator(&config.image_generation, api_key)
+}
+
+fn search_backend(
+    config: &Config,
+) -> anyhow::Result<Option<Arc<dyn crate::websearch::SearchBackend>>> {
+    let api_key = resolve_tool_secret(config, config.web_search.api_key.as_deref())?;
+    crate::websearch::create_search_backend(&config.web_search, api_key)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
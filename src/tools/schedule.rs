@@ -5,7 +5,9 @@ use crate::security::SecurityPolicy;
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde_json::json;
+use std::str::FromStr;
 use std::sync::Arc;
 
 /// Tool that lets the agent manage recurring and one-shot scheduled tasks.
@@ -51,6 +53,14 @@ impl Tool for ScheduleTool {
                     "type": "string",
                     "description": "Absolute RFC3339 time for one-shot tasks (e.g. '2030-01-01T00:00:00Z')."
                 },
+                "when": {
+                    "type": "string",
+                    "description": "Natural-language time, one-shot or recurring (e.g. 'tomorrow 9am', 'every monday at 8:30', '每天晚上8点')."
+                },
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA timezone 'when' is read in (e.g. 'Europe/Berlin'). Defaults to the configured default timezone preference, else UTC."
+                },
                 "command": {
                     "type": "string",
                     "description": "Shell command to execute. Required for create/add/once."
@@ -229,44 +239,51 @@ impl ScheduleTool {
         let expression = args.get("expression").and_then(|value| value.as_str());
         let delay = args.get("delay").and_then(|value| value.as_str());
         let run_at = args.get("run_at").and_then(|value| value.as_str());
+        let when = args.get("when").and_then(|value| value.as_str());
 
         match action {
             "add" => {
-                if expression.is_none() || delay.is_some() || run_at.is_some() {
+                if expression.is_none() || delay.is_some() || run_at.is_some() || when.is_some() {
                     return Ok(ToolResult {
                         success: false,
                         output: String::new(),
-                        error: Some("'add' requires 'expression' and forbids delay/run_at".into()),
+                        error: Some(
+                            "'add' requires 'expression' and forbids delay/run_at/when".into(),
+                        ),
                     });
                 }
             }
             "once" => {
-                if expression.is_some() || (delay.is_none() && run_at.is_none()) {
-                    return Ok(ToolResult {
-                        success: false,
-                        output: String::new(),
-                        error: Some("'once' requires exactly one of 'delay' or 'run_at'".into()),
-                    });
-                }
-                if delay.is_some() && run_at.is_some() {
+                let count = [delay.is_some(), run_at.is_some(), when.is_some()]
+                    .into_iter()
+                    .filter(|value| *value)
+                    .count();
+                if expression.is_some() || count != 1 {
                     return Ok(ToolResult {
                         success: false,
                         output: String::new(),
-                        error: Some("'once' supports either delay or run_at, not both".into()),
+                        error: Some(
+                            "'once' requires exactly one of 'delay', 'run_at' or 'when'".into(),
+                        ),
                     });
                 }
             }
             _ => {
-                let count = [expression.is_some(), delay.is_some(), run_at.is_some()]
-                    .into_iter()
-                    .filter(|value| *value)
-                    .count();
+                let count = [
+                    expression.is_some(),
+                    delay.is_some(),
+                    run_at.is_some(),
+                    when.is_some(),
+                ]
+                .into_iter()
+                .filter(|value| *value)
+                .count();
                 if count != 1 {
                     return Ok(ToolResult {
                         success: false,
                         output: String::new(),
                         error: Some(
-                            "Exactly one of 'expression', 'delay', or 'run_at' must be provided"
+                            "Exactly one of 'expression', 'delay', 'run_at' or 'when' must be provided"
                                 .into(),
                         ),
                     });
@@ -274,6 +291,34 @@ impl ScheduleTool {
             }
         }
 
+        if let Some(value) = when {
+            let timezone = self.timezone(args)?;
+            let schedule = cron::parse_schedule(value, Utc::now(), timezone)?;
+            if action == "once" && !matches!(schedule, cron::Schedule::At { .. }) {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some(format!(
+                        "'once' needs a one-off time, but {value:?} repeats"
+                    )),
+                });
+            }
+            let one_shot = matches!(schedule, cron::Schedule::At { .. });
+            let job = cron::add_shell_job(&self.config, None, schedule, command)?;
+            return Ok(ToolResult {
+                success: true,
+                output: format!(
+                    "Created {} job {} (when: {value}, timezone: {}, next: {}, cmd: {})",
+                    if one_shot { "one-shot" } else { "recurring" },
+                    job.id,
+                    timezone.name(),
+                    job.next_run.to_rfc3339(),
+                    job.command
+                ),
+                error: None,
+            });
+        }
+
         if let Some(value) = expression {
             let job = cron::add_job(&self.config, value, command)?;
             return Ok(ToolResult {
@@ -321,6 +366,23 @@ impl ScheduleTool {
         })
     }
 
+    /// The `timezone` argument, else the default timezone preference.
+    fn timezone(&self, args: &serde_json::Value) -> Result<Tz> {
+        match args.get("timezone").and_then(|value| value.as_str()) {
+            Some(name) => {
+                Tz::from_str(name).map_err(|_| anyhow::anyhow!("Unknown timezone '{name}'"))
+            }
+            None => Ok(self
+                .config
+                .preferences
+                .defaults
+                .timezone
+                .as_deref()
+                .and_then(|name| Tz::from_str(name).ok())
+                .unwrap_or(Tz::UTC)),
+        }
+    }
+
     fn handle_cancel(&self, id: &str) -> ToolResult {
         match cron::remove_job(&self.config, id) {
             Ok(()) => ToolResult {
@@ -477,6 +539,55 @@ mod tests {
         assert!(resume.success);
     }
 
+    #[tokio::test]
+    async fn natural_language_times_use_the_timezone() {
+        let (_tmp, config, security) = test_setup();
+        let tool = ScheduleTool::new(security, config);
+
+        let daily = tool
+            .execute(json!({
+                "action": "create",
+                "when": "每天晚上8点",
+                "timezone": "Asia/Shanghai",
+                "command": "echo evening"
+            }))
+            .await
+            .unwrap();
+        assert!(daily.success, "{:?}", daily.error);
+        assert!(daily.output.contains("Created recurring job"));
+        assert!(daily.output.contains("timezone: Asia/Shanghai"));
+
+        let once = tool
+            .execute(json!({
+                "action": "once",
+                "when": "in 2 hours",
+                "command": "echo later"
+            }))
+            .await
+            .unwrap();
+        assert!(once.success, "{:?}", once.error);
+        assert!(once.output.contains("Created one-shot job"));
+
+        let repeating = tool
+            .execute(json!({
+                "action": "once",
+                "when": "every monday at 9am",
+                "command": "echo weekly"
+            }))
+            .await
+            .unwrap();
+        assert!(!repeating.success);
+
+        let unparsable = tool
+            .execute(json!({
+                "action": "create",
+                "when": "whenever you like",
+                "command": "echo never"
+            }))
+            .await;
+        assert!(unparsable.is_err());
+    }
+
     #[tokio::test]
     async fn readonly_blocks_mutating_actions() {
         let tmp = TempDir::new().unwrap();
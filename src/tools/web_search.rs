@@ -0,0 +1,149 @@
+use super::traits::{Tool, ToolResult};
+use crate::security::SecurityPolicy;
+use crate::websearch::{summarize_results, SearchBackend};
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+/// Search the web through the configured backend and return titles, links
+/// and snippets.
+pub struct WebSearchTool {
+    security: Arc<SecurityPolicy>,
+    backend: Arc<dyn SearchBackend>,
+    max_results: usize,
+}
+
+impl WebSearchTool {
+    pub fn new(
+        security: Arc<SecurityPolicy>,
+        backend: Arc<dyn SearchBackend>,
+        max_results: usize,
+    ) -> Self {
+        Self {
+            security,
+            backend,
+            max_results: max_results.max(1),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    fn name(&self) -> &str {
+        "web_search"
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &str {
+        "Search the web. Returns result titles, links and snippets; cite the links you rely on in your reply."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Search query"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of results",
+                    "minimum": 1
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        if !self.security.record_action() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Action blocked: rate limit exceeded".into()),
+            });
+        }
+
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'query' parameter"))?;
+        let limit = args
+            .get("limit")
+            .and_then(serde_json::Value::as_u64)
+            .and_then(|v| usize::try_from(v).ok())
+            .map_or(self.max_results, |v| v.clamp(1, self.max_results));
+
+        match self.backend.search(query, limit).await {
+            Ok(results) => Ok(ToolResult {
+                success: true,
+                output: summarize_results(query, &results),
+                error: None,
+            }),
+            Err(e) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Web search failed: {e}")),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websearch::SearchResult;
+
+    struct FixedBackend;
+
+    #[async_trait]
+    impl SearchBackend for FixedBackend {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn search(&self, query: &str, limit: usize) -> anyhow::Result<Vec<SearchResult>> {
+            if query == "fail" {
+                anyhow::bail!("quota exhausted");
+            }
+            Ok((0..10)
+                .map(|i| SearchResult {
+                    title: format!("{query} {i}"),
+                    url: format!("https://example.com/{i}"),
+                    snippet: String::new(),
+                })
+                .take(limit)
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_links_capped_at_max_results() {
+        let tool = WebSearchTool::new(
+            Arc::new(SecurityPolicy::default()),
+            Arc::new(FixedBackend),
+            3,
+        );
+
+        let result = tool
+            .execute(json!({ "query": "rust", "limit": 50 }))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result
+            .output
+            .contains("3. rust 2\n   https://example.com/2"));
+        assert!(!result.output.contains("https://example.com/3"));
+
+        let failed = tool.execute(json!({ "query": "fail" })).await.unwrap();
+        assert!(!failed.success);
+        assert!(failed.error.unwrap().contains("quota exhausted"));
+        assert!(tool.execute(json!({})).await.is_err());
+    }
+}
@@ -0,0 +1,249 @@
+mod whisper_api;
+mod whisper_cpp;
+
+pub use whisper_api::WhisperApiTranscriber;
+pub use whisper_cpp::WhisperCppTranscriber;
+
+use crate::channels::traits::{Channel, ChannelMessage, MediaKind};
+use crate::config::schema::TranscriptionConfig;
+use crate::security::ManagedSecret;
+use anyhow::{bail, Result};
+use std::sync::Arc;
+
+// ── Transcriber trait ────────────────────────────────────────────
+
+/// Speech-to-text backend for inbound voice notes and audio files.
+#[async_trait::async_trait]
+pub trait Transcriber: Send + Sync {
+    /// Backend name (e.g. "whisper_api", "whisper_cpp")
+    fn name(&self) -> &str;
+
+    /// Transcribe encoded audio. `file_name` carries the container format
+    /// (e.g. `voice.ogg`), which some backends need to decode it.
+    async fn transcribe(&self, audio: &[u8], file_name: &str) -> Result<String>;
+}
+
+/// File name to hand a backend when the platform didn't provide one.
+pub fn audio_file_name(file_name: Option<&str>, mime_type: Option<&str>) -> String {
+    if let Some(name) = file_name.filter(|n| n.contains('.')) {
+        return name.to_string();
+    }
+    let ext = match mime_type.unwrap_or_default() {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "m4a",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/webm" => "webm",
+        "audio/flac" => "flac",
+        "audio/silk" => "silk",
+        _ => "ogg",
+    };
+    format!("audio.{ext}")
+}
+
+// ── Inbound pipeline ─────────────────────────────────────────────
+
+/// Turns voice and audio attachments of inbound messages into text.
+pub struct TranscriptionService {
+    transcriber: Arc<dyn Transcriber>,
+    max_bytes: usize,
+}
+
+impl TranscriptionService {
+    pub fn new(transcriber: Arc<dyn Transcriber>, max_bytes: usize) -> Self {
+        Self {
+            transcriber,
+            max_bytes,
+        }
+    }
+
+    /// Download and transcribe every voice/audio attachment of `msg`,
+    /// appending the text to its content (after any caption). Attachments
+    /// stay on the message. Returns `false` if any attachment failed.
+    pub async fn transcribe_message(
+        &self,
+        channel: &dyn Channel,
+        msg: &mut ChannelMessage,
+    ) -> bool {
+        let mut ok = true;
+        let mut transcripts = Vec::new();
+        for attachment in &msg.attachments {
+            if !matches!(attachment.kind, MediaKind::Voice | MediaKind::Audio) {
+                continue;
+            }
+            let audio = match channel.download_attachment(attachment).await {
+                Ok(audio) if audio.len() > self.max_bytes => {
+                    tracing::warn!(
+                        "Skipping {} byte audio on {} (limit {})",
+                        audio.len(),
+                        msg.channel,
+                        self.max_bytes
+                    );
+                    ok = false;
+                    continue;
+                }
+                Ok(audio) => audio,
+                Err(e) => {
+                    tracing::warn!("Failed to download audio on {}: {e}", msg.channel);
+                    ok = false;
+                    continue;
+                }
+            };
+            let file_name = audio_file_name(
+                attachment.file_name.as_deref(),
+                attachment.mime_type.as_deref(),
+            );
+            match self.transcriber.transcribe(&audio, &file_name).await {
+                Ok(text) if !text.is_empty() => transcripts.push(text),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "{} transcription failed on {}: {e}",
+                        self.transcriber.name(),
+                        msg.channel
+                    );
+                    ok = false;
+                }
+            }
+        }
+        if !transcripts.is_empty() {
+            let transcript = transcripts.join("\n");
+            msg.content = if msg.content.trim().is_empty() {
+                transcript
+            } else {
+                format!("{}\n\n{transcript}", msg.content)
+            };
+        }
+        ok
+    }
+}
+
+// ── Factory ──────────────────────────────────────────────────────
+
+/// Create a transcriber from config. Returns `None` when transcription is
+/// disabled. `api_key` is the resolved `[transcription] api_key`.
+pub fn create_transcriber(
+    config: &TranscriptionConfig,
+    api_key: Option<Arc<ManagedSecret>>,
+) -> Result<Option<Arc<dyn Transcriber>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    match config.backend.as_str() {
+        "whisper_api" => Ok(Some(Arc::new(WhisperApiTranscriber::new(
+            config.api_url.clone(),
+            config.model.clone(),
+            config.language.clone(),
+            api_key,
+        )))),
+        "whisper_cpp" => {
+            let Some(model) = config.whisper_cpp_model.clone() else {
+                bail!("transcription backend \"whisper_cpp\" requires whisper_cpp_model");
+            };
+            Ok(Some(Arc::new(WhisperCppTranscriber::new(
+                config.whisper_cpp_bin.clone(),
+                model,
+                config.ffmpeg_bin.clone(),
+                config.language.clone(),
+            ))))
+        }
+        other => bail!("Unknown transcription backend: {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_respects_enabled_and_backend() {
+        let mut config = TranscriptionConfig::default();
+        assert!(create_transcriber(&config, None).unwrap().is_none());
+
+        config.enabled = true;
+        let transcriber = create_transcriber(&config, None).unwrap().unwrap();
+        assert_eq!(transcriber.name(), "whisper_api");
+
+        config.backend = "whisper_cpp".into();
+        assert!(create_transcriber(&config, None).is_err());
+        config.whisper_cpp_model = Some("/models/ggml-base.bin".into());
+        let transcriber = create_transcriber(&config, None).unwrap().unwrap();
+        assert_eq!(transcriber.name(), "whisper_cpp");
+
+        config.backend = "nope".into();
+        assert!(create_transcriber(&config, None).is_err());
+    }
+
+    struct EchoTranscriber;
+
+    #[async_trait::async_trait]
+    impl Transcriber for EchoTranscriber {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        async fn transcribe(&self, audio: &[u8], file_name: &str) -> Result<String> {
+            Ok(format!("{} ({file_name})", String::from_utf8_lossy(audio)))
+        }
+    }
+
+    struct FixtureChannel;
+
+    #[async_trait::async_trait]
+    impl Channel for FixtureChannel {
+        fn name(&self) -> &str {
+            "fixture"
+        }
+
+        async fn send(&self, _message: &str, _recipient: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn download_attachment(
+            &self,
+            attachment: &crate::channels::traits::MediaAttachment,
+        ) -> Result<Vec<u8>> {
+            Ok(attachment.source.clone().into_bytes())
+        }
+    }
+
+    #[tokio::test]
+    async fn transcripts_follow_the_caption_and_keep_attachments() {
+        use crate::channels::traits::MediaAttachment;
+
+        let voice = |source: &str, kind| MediaAttachment {
+            kind,
+            source: source.into(),
+            mime_type: Some("audio/ogg".into()),
+            file_name: None,
+            stored_key: None,
+        };
+        let mut msg = ChannelMessage {
+            attachments: vec![
+                voice("hello", MediaKind::Voice),
+                voice("cat.png", MediaKind::Image),
+                voice("too long for the limit", MediaKind::Audio),
+            ],
+            ..ChannelMessage::test("fixture", "alice", "listen")
+        };
+
+        let service = TranscriptionService::new(Arc::new(EchoTranscriber), 10);
+        let ok = service.transcribe_message(&FixtureChannel, &mut msg).await;
+        assert!(!ok);
+        assert_eq!(msg.content, "listen\n\nhello (audio.ogg)");
+        assert_eq!(msg.attachments.len(), 3);
+    }
+
+    #[test]
+    fn audio_file_name_falls_back_to_mime_type() {
+        assert_eq!(audio_file_name(Some("memo.m4a"), None), "memo.m4a");
+        assert_eq!(audio_file_name(None, Some("audio/mpeg")), "audio.mp3");
+        assert_eq!(
+            audio_file_name(Some("voice"), Some("audio/ogg")),
+            "audio.ogg"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use super::Transcriber;
+use crate::security::ManagedSecret;
+use anyhow::{Context, Result};
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TRANSCRIPTION_TIMEOUT_SECS: u64 = 120;
+
+/// OpenAI-compatible `/audio/transcriptions` endpoint (OpenAI Whisper, Groq,
+/// a self-hosted faster-whisper server, ...).
+pub struct WhisperApiTranscriber {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    language: Option<String>,
+    api_key: Option<Arc<ManagedSecret>>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+impl WhisperApiTranscriber {
+    pub fn new(
+        url: String,
+        model: String,
+        language: Option<String>,
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            model,
+            language,
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transcriber for WhisperApiTranscriber {
+    fn name(&self) -> &str {
+        "whisper_api"
+    }
+
+    async fn transcribe(&self, audio: &[u8], file_name: &str) -> Result<String> {
+        let mut form = Form::new()
+            .part(
+                "file",
+                Part::bytes(audio.to_vec()).file_name(file_name.to_string()),
+            )
+            .text("model", self.model.clone());
+        if let Some(language) = &self.language {
+            form = form.text("language", language.clone());
+        }
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(Duration::from_secs(TRANSCRIPTION_TIMEOUT_SECS))
+            .multipart(form);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let response: TranscriptionResponse = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("invalid transcription response")?;
+        Ok(response.text.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn posts_multipart_audio_and_reads_text() {
+        use axum::{body::Bytes, http::HeaderMap, routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/v1/audio/transcriptions",
+            post(|headers: HeaderMap, body: Bytes| async move {
+                let body = String::from_utf8_lossy(&body);
+                let authorized = headers
+                    .get("authorization")
+                    .is_some_and(|v| v == "Bearer sk-test");
+                let ok = authorized
+                    && body.contains("filename=\"voice.ogg\"")
+                    && body.contains("OggS1234")
+                    && body.contains("whisper-1");
+                Json(
+                    serde_json::json!({ "text": if ok { " hello there " } else { "bad request" } }),
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let transcriber = WhisperApiTranscriber::new(
+            format!("http://{addr}/v1/audio/transcriptions"),
+            "whisper-1".into(),
+            Some("en".into()),
+            Some(Arc::new(ManagedSecret::fixed("sk-test"))),
+        );
+        let text = transcriber
+            .transcribe(b"OggS1234", "voice.ogg")
+            .await
+            .unwrap();
+        assert_eq!(text, "hello there");
+    }
+}
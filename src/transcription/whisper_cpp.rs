@@ -0,0 +1,128 @@
+use super::Transcriber;
+use crate::util::{run_tool, ScratchDir};
+use anyhow::Result;
+use std::path::Path;
+use std::time::Duration;
+
+const WHISPER_CPP_TIMEOUT_SECS: u64 = 300;
+
+/// Local transcription with the whisper.cpp command-line tool. Audio is first
+/// converted to the 16 kHz mono WAV whisper.cpp expects with ffmpeg.
+pub struct WhisperCppTranscriber {
+    binary: String,
+    model: String,
+    ffmpeg: String,
+    language: Option<String>,
+}
+
+impl WhisperCppTranscriber {
+    pub fn new(binary: String, model: String, ffmpeg: String, language: Option<String>) -> Self {
+        Self {
+            binary,
+            model,
+            ffmpeg,
+            language,
+        }
+    }
+
+    fn ffmpeg_args(input: &Path, output: &Path) -> Vec<String> {
+        vec![
+            "-nostdin".into(),
+            "-loglevel".into(),
+            "error".into(),
+            "-y".into(),
+            "-i".into(),
+            input.display().to_string(),
+            "-ar".into(),
+            "16000".into(),
+            "-ac".into(),
+            "1".into(),
+            "-c:a".into(),
+            "pcm_s16le".into(),
+            output.display().to_string(),
+        ]
+    }
+
+    fn whisper_args(&self, wav: &Path) -> Vec<String> {
+        let mut args = vec![
+            "-m".into(),
+            self.model.clone(),
+            "-f".into(),
+            wav.display().to_string(),
+            "--no-timestamps".into(),
+            "--no-prints".into(),
+        ];
+        if let Some(language) = &self.language {
+            args.push("-l".into());
+            args.push(language.clone());
+        }
+        args
+    }
+}
+
+#[async_trait::async_trait]
+impl Transcriber for WhisperCppTranscriber {
+    fn name(&self) -> &str {
+        "whisper_cpp"
+    }
+
+    async fn transcribe(&self, audio: &[u8], file_name: &str) -> Result<String> {
+        let scratch = ScratchDir::create("zeroclaw-stt")?;
+        // Keep only the extension; the platform-supplied name is untrusted.
+        let ext = Path::new(file_name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| e.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or("ogg");
+        let input = scratch.path().join(format!("input.{ext}"));
+        let wav = scratch.path().join("input.wav");
+        tokio::fs::write(&input, audio).await?;
+
+        let timeout = Duration::from_secs(WHISPER_CPP_TIMEOUT_SECS);
+        run_tool(
+            &self.ffmpeg,
+            &Self::ffmpeg_args(&input, &wav),
+            None,
+            timeout,
+        )
+        .await?;
+        let text = run_tool(&self.binary, &self.whisper_args(&wav), None, timeout).await?;
+        Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whisper_args_include_model_and_language() {
+        let transcriber = WhisperCppTranscriber::new(
+            "whisper-cli".into(),
+            "/models/ggml-base.bin".into(),
+            "ffmpeg".into(),
+            Some("de".into()),
+        );
+        let args = transcriber.whisper_args(Path::new("/tmp/x.wav"));
+        assert_eq!(
+            args[..4],
+            ["-m", "/models/ggml-base.bin", "-f", "/tmp/x.wav"]
+        );
+        assert!(args.ends_with(&["-l".to_string(), "de".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn missing_binary_is_an_error() {
+        let transcriber = WhisperCppTranscriber::new(
+            "whisper-cli".into(),
+            "/models/ggml-base.bin".into(),
+            "/nonexistent/ffmpeg".into(),
+            None,
+        );
+        let err = transcriber
+            .transcribe(b"OggS", "voice.ogg")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/ffmpeg"));
+    }
+}
@@ -0,0 +1,54 @@
+use super::{SpeechSynthesizer, SynthesizedAudio};
+use crate::util::{run_tool, ScratchDir};
+use anyhow::Result;
+use std::time::Duration;
+
+const EDGE_TTS_TIMEOUT_SECS: u64 = 60;
+
+/// Microsoft Edge online voices through the `edge-tts` command-line tool.
+/// Produces MP3.
+pub struct EdgeTtsSynthesizer {
+    binary: String,
+    voice: String,
+}
+
+impl EdgeTtsSynthesizer {
+    pub fn new(binary: String, voice: String) -> Self {
+        Self { binary, voice }
+    }
+}
+
+#[async_trait::async_trait]
+impl SpeechSynthesizer for EdgeTtsSynthesizer {
+    fn name(&self) -> &str {
+        "edge_tts"
+    }
+
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio> {
+        let scratch = ScratchDir::create("zeroclaw-tts")?;
+        let input = scratch.path().join("reply.txt");
+        let output = scratch.path().join("reply.mp3");
+        tokio::fs::write(&input, text).await?;
+
+        let args = vec![
+            "--voice".into(),
+            self.voice.clone(),
+            "--file".into(),
+            input.display().to_string(),
+            "--write-media".into(),
+            output.display().to_string(),
+        ];
+        run_tool(
+            &self.binary,
+            &args,
+            None,
+            Duration::from_secs(EDGE_TTS_TIMEOUT_SECS),
+        )
+        .await?;
+        Ok(SynthesizedAudio {
+            bytes: tokio::fs::read(&output).await?,
+            file_name: "reply.mp3".into(),
+            mime_type: "audio/mpeg".into(),
+        })
+    }
+}
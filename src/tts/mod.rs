@@ -0,0 +1,259 @@
+mod edge_tts;
+mod openai;
+mod piper;
+
+pub use edge_tts::EdgeTtsSynthesizer;
+pub use openai::OpenAiSynthesizer;
+pub use piper::PiperSynthesizer;
+
+use crate::config::schema::{TtsConfig, TtsMode};
+use crate::security::ManagedSecret;
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// ── Synthesizer trait ────────────────────────────────────────────
+
+/// Encoded speech ready to upload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SynthesizedAudio {
+    pub bytes: Vec<u8>,
+    /// File name carrying the container format (e.g. `reply.ogg`)
+    pub file_name: String,
+    pub mime_type: String,
+}
+
+/// Text-to-speech backend for spoken replies.
+#[async_trait::async_trait]
+pub trait SpeechSynthesizer: Send + Sync {
+    /// Backend name (e.g. "openai", "edge_tts", "piper")
+    fn name(&self) -> &str;
+
+    /// Render `text` as speech.
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio>;
+}
+
+/// Strip markdown markup that would otherwise be read aloud.
+pub fn speakable_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            continue;
+        }
+        let line = line.trim_start_matches(['#', '>']).trim();
+        let line = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .unwrap_or(line);
+        out.extend(line.chars().filter(|c| !matches!(c, '*' | '_' | '`' | '~')));
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+// ── Reply pipeline ───────────────────────────────────────────────
+
+/// Decides which replies are spoken and renders them.
+pub struct TtsService {
+    synthesizer: Arc<dyn SpeechSynthesizer>,
+    mode: TtsMode,
+    channel_modes: HashMap<String, TtsMode>,
+    max_chars: usize,
+    /// `/voice` choices, keyed by `channel:sender`
+    preferences: Mutex<HashMap<String, TtsMode>>,
+}
+
+impl TtsService {
+    pub fn new(synthesizer: Arc<dyn SpeechSynthesizer>, config: &TtsConfig) -> Self {
+        Self {
+            synthesizer,
+            mode: config.mode,
+            channel_modes: config.channels.clone(),
+            max_chars: config.max_chars,
+            preferences: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn mode_for(&self, channel: &str, sender: &str) -> TtsMode {
+        if let Some(mode) = self.preferences.lock().get(&format!("{channel}:{sender}")) {
+            return *mode;
+        }
+        self.channel_modes
+            .get(channel)
+            .copied()
+            .unwrap_or(self.mode)
+    }
+
+    /// Whether the reply to `sender` should be spoken. `voice_in` is true
+    /// when the message being answered was itself a voice message.
+    pub fn should_speak(&self, channel: &str, sender: &str, voice_in: bool, reply: &str) -> bool {
+        if reply.chars().count() > self.max_chars {
+            return false;
+        }
+        match self.mode_for(channel, sender) {
+            TtsMode::Off => false,
+            TtsMode::VoiceReplies => voice_in,
+            TtsMode::Always => true,
+        }
+    }
+
+    /// Answer `/voice on|off|auto`. Returns `None` for any other message.
+    pub fn handle_command(&self, channel: &str, sender: &str, content: &str) -> Option<String> {
+        let mut words = content.split_whitespace();
+        let command = words.next()?.split('@').next()?;
+        if command != "/voice" {
+            return None;
+        }
+        let key = format!("{channel}:{sender}");
+        let reply = match words.next() {
+            Some("on") => {
+                self.preferences.lock().insert(key, TtsMode::Always);
+                "🔊 I'll reply with voice messages."
+            }
+            Some("off") => {
+                self.preferences.lock().insert(key, TtsMode::Off);
+                "🔇 I'll reply with text."
+            }
+            Some("auto") => {
+                self.preferences.lock().insert(key, TtsMode::VoiceReplies);
+                "🎙️ I'll reply with voice when you send voice."
+            }
+            _ => "Usage: /voice on|off|auto",
+        };
+        Some(reply.to_string())
+    }
+
+    pub async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio> {
+        let text = speakable_text(text);
+        if text.is_empty() {
+            bail!("nothing to speak");
+        }
+        self.synthesizer.synthesize(&text).await
+    }
+}
+
+// ── Factory ──────────────────────────────────────────────────────
+
+/// Create a synthesizer from config. Returns `None` when TTS is disabled.
+/// `api_key` is the resolved `[tts] api_key`.
+pub fn create_synthesizer(
+    config: &TtsConfig,
+    api_key: Option<Arc<ManagedSecret>>,
+) -> Result<Option<Arc<dyn SpeechSynthesizer>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    match config.backend.as_str() {
+        "openai" => Ok(Some(Arc::new(OpenAiSynthesizer::new(
+            config.api_url.clone(),
+            config.model.clone(),
+            config.voice.clone().unwrap_or_else(|| "alloy".into()),
+            api_key,
+        )))),
+        "edge_tts" => Ok(Some(Arc::new(EdgeTtsSynthesizer::new(
+            config.edge_tts_bin.clone(),
+            config
+                .voice
+                .clone()
+                .unwrap_or_else(|| "en-US-AriaNeural".into()),
+        )))),
+        "piper" => {
+            let Some(model) = config.piper_model.clone() else {
+                bail!("tts backend \"piper\" requires piper_model");
+            };
+            Ok(Some(Arc::new(PiperSynthesizer::new(
+                config.piper_bin.clone(),
+                model,
+            ))))
+        }
+        other => bail!("Unknown tts backend: {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_respects_enabled_and_backend() {
+        let mut config = TtsConfig::default();
+        assert!(create_synthesizer(&config, None).unwrap().is_none());
+
+        config.enabled = true;
+        let synthesizer = create_synthesizer(&config, None).unwrap().unwrap();
+        assert_eq!(synthesizer.name(), "openai");
+
+        config.backend = "edge_tts".into();
+        let synthesizer = create_synthesizer(&config, None).unwrap().unwrap();
+        assert_eq!(synthesizer.name(), "edge_tts");
+
+        config.backend = "piper".into();
+        assert!(create_synthesizer(&config, None).is_err());
+        config.piper_model = Some("/voices/en_US-amy-medium.onnx".into());
+        let synthesizer = create_synthesizer(&config, None).unwrap().unwrap();
+        assert_eq!(synthesizer.name(), "piper");
+
+        config.backend = "nope".into();
+        assert!(create_synthesizer(&config, None).is_err());
+    }
+
+    struct SilentSynthesizer;
+
+    #[async_trait::async_trait]
+    impl SpeechSynthesizer for SilentSynthesizer {
+        fn name(&self) -> &str {
+            "silent"
+        }
+
+        async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio> {
+            Ok(SynthesizedAudio {
+                bytes: text.as_bytes().to_vec(),
+                file_name: "reply.ogg".into(),
+                mime_type: "audio/ogg".into(),
+            })
+        }
+    }
+
+    #[test]
+    fn mode_follows_user_then_channel_then_default() {
+        let config = TtsConfig {
+            channels: HashMap::from([("discord".to_string(), TtsMode::Off)]),
+            max_chars: 20,
+            ..TtsConfig::default()
+        };
+        let tts = TtsService::new(Arc::new(SilentSynthesizer), &config);
+
+        assert!(tts.should_speak("telegram", "alice", true, "hi"));
+        assert!(!tts.should_speak("telegram", "alice", false, "hi"));
+        assert!(!tts.should_speak("telegram", "alice", true, &"long ".repeat(10)));
+        assert!(!tts.should_speak("discord", "alice", true, "hi"));
+
+        assert!(tts
+            .handle_command("discord", "alice", "/voice on")
+            .unwrap()
+            .contains("voice"));
+        assert!(tts.should_speak("discord", "alice", false, "hi"));
+        assert!(!tts.should_speak("discord", "bob", false, "hi"));
+
+        tts.handle_command("telegram", "alice", "/voice@zeroclaw_bot off");
+        assert!(!tts.should_speak("telegram", "alice", true, "hi"));
+        assert!(tts
+            .handle_command("telegram", "alice", "/voice")
+            .unwrap()
+            .starts_with("Usage"));
+        assert!(tts.handle_command("telegram", "alice", "hello").is_none());
+    }
+
+    #[tokio::test]
+    async fn markdown_is_not_read_aloud() {
+        assert_eq!(
+            speakable_text("## Result\n\n- **bold** and `code`\n```rust\n```"),
+            "Result\n\nbold and code"
+        );
+        let tts = TtsService::new(Arc::new(SilentSynthesizer), &TtsConfig::default());
+        assert!(tts.synthesize("```\n```").await.is_err());
+        let audio = tts.synthesize("*hi*").await.unwrap();
+        assert_eq!(audio.bytes, b"hi");
+    }
+}
@@ -0,0 +1,103 @@
+use super::{SpeechSynthesizer, SynthesizedAudio};
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SPEECH_TIMEOUT_SECS: u64 = 60;
+
+/// OpenAI-compatible `/audio/speech` endpoint. Requests Opus in Ogg, which
+/// messaging apps play as a native voice note.
+pub struct OpenAiSynthesizer {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    voice: String,
+    api_key: Option<Arc<ManagedSecret>>,
+}
+
+impl OpenAiSynthesizer {
+    pub fn new(
+        url: String,
+        model: String,
+        voice: String,
+        api_key: Option<Arc<ManagedSecret>>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            model,
+            voice,
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SpeechSynthesizer for OpenAiSynthesizer {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio> {
+        let body = serde_json::json!({
+            "model": self.model,
+            "voice": self.voice,
+            "input": text,
+            "response_format": "opus",
+        });
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(Duration::from_secs(SPEECH_TIMEOUT_SECS))
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let bytes = request.send().await?.error_for_status()?.bytes().await?;
+        Ok(SynthesizedAudio {
+            bytes: bytes.to_vec(),
+            file_name: "reply.ogg".into(),
+            mime_type: "audio/ogg".into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn posts_text_and_returns_audio_bytes() {
+        use axum::{http::HeaderMap, routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/v1/audio/speech",
+            post(
+                |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                    let authorized = headers
+                        .get("authorization")
+                        .is_some_and(|v| v == "Bearer sk-test");
+                    if authorized && body["voice"] == "nova" && body["response_format"] == "opus" {
+                        format!("OggS:{}", body["input"].as_str().unwrap_or_default())
+                    } else {
+                        "bad request".to_string()
+                    }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let synthesizer = OpenAiSynthesizer::new(
+            format!("http://{addr}/v1/audio/speech"),
+            "tts-1".into(),
+            "nova".into(),
+            Some(Arc::new(ManagedSecret::fixed("sk-test"))),
+        );
+        let audio = synthesizer.synthesize("hello").await.unwrap();
+        assert_eq!(audio.bytes, b"OggS:hello");
+        assert_eq!(audio.file_name, "reply.ogg");
+    }
+}
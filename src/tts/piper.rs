@@ -0,0 +1,63 @@
+use super::{SpeechSynthesizer, SynthesizedAudio};
+use crate::util::{run_tool, ScratchDir};
+use anyhow::Result;
+use std::time::Duration;
+
+const PIPER_TIMEOUT_SECS: u64 = 120;
+
+/// Fully local speech with piper. Text is read from stdin; produces WAV.
+pub struct PiperSynthesizer {
+    binary: String,
+    model: String,
+}
+
+impl PiperSynthesizer {
+    pub fn new(binary: String, model: String) -> Self {
+        Self { binary, model }
+    }
+}
+
+#[async_trait::async_trait]
+impl SpeechSynthesizer for PiperSynthesizer {
+    fn name(&self) -> &str {
+        "piper"
+    }
+
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio> {
+        let scratch = ScratchDir::create("zeroclaw-tts")?;
+        let output = scratch.path().join("reply.wav");
+        let args = vec![
+            "--model".into(),
+            self.model.clone(),
+            "--output_file".into(),
+            output.display().to_string(),
+        ];
+        run_tool(
+            &self.binary,
+            &args,
+            Some(text.as_bytes()),
+            Duration::from_secs(PIPER_TIMEOUT_SECS),
+        )
+        .await?;
+        Ok(SynthesizedAudio {
+            bytes: tokio::fs::read(&output).await?,
+            file_name: "reply.wav".into(),
+            mime_type: "audio/wav".into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn missing_binary_is_an_error() {
+        let synthesizer = PiperSynthesizer::new(
+            "/nonexistent/piper".into(),
+            "/voices/en_US-amy-medium.onnx".into(),
+        );
+        let err = synthesizer.synthesize("hello").await.unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/piper"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Image understanding for inbound messages.
+//!
+//! Image attachments are sent to a vision-capable model through
+//! [`Provider::describe_image`] and the description is added to the message
+//! text, so the agent (which may be text-only) can reason about them.
+
+use crate::channels::traits::{Channel, ChannelMessage, MediaKind};
+use crate::providers::Provider;
+use std::sync::Arc;
+
+/// Content type to declare for an image when the platform didn't say.
+pub fn image_mime_type(mime_type: Option<&str>, bytes: &[u8]) -> String {
+    if let Some(mime) = mime_type.filter(|m| m.starts_with("image/")) {
+        return mime.to_string();
+    }
+    let sniffed = match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        _ => "image/jpeg",
+    };
+    sniffed.to_string()
+}
+
+pub struct VisionService {
+    provider: Arc<dyn Provider>,
+    model: String,
+    prompt: String,
+    max_bytes: usize,
+}
+
+impl VisionService {
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        model: String,
+        prompt: String,
+        max_bytes: usize,
+    ) -> Self {
+        Self {
+            provider,
+            model,
+            prompt,
+            max_bytes,
+        }
+    }
+
+    /// Download and describe every image attachment of `msg`, appending
+    /// `[Image: …]` blocks to its content. Attachments stay on the message.
+    /// Returns `false` if any image could not be described.
+    pub async fn describe_message(&self, channel: &dyn Channel, msg: &mut ChannelMessage) -> bool {
+        let mut ok = true;
+        let mut descriptions = Vec::new();
+        for attachment in &msg.attachments {
+            if attachment.kind != MediaKind::Image {
+                continue;
+            }
+            let image = match channel.download_attachment(attachment).await {
+                Ok(image) if image.len() > self.max_bytes => {
+                    tracing::warn!(
+                        "Skipping {} byte image on {} (limit {})",
+                        image.len(),
+                        msg.channel,
+                        self.max_bytes
+                    );
+                    ok = false;
+                    continue;
+                }
+                Ok(image) => image,
+                Err(e) => {
+                    tracing::warn!("Failed to download image on {}: {e}", msg.channel);
+                    ok = false;
+                    continue;
+                }
+            };
+            let mime_type = image_mime_type(attachment.mime_type.as_deref(), &image);
+            match self
+                .provider
+                .describe_image(&image, &mime_type, &self.prompt, &self.model)
+                .await
+            {
+                Ok(description) => descriptions.push(format!("[Image: {}]", description.trim())),
+                Err(e) => {
+                    tracing::warn!("Image description failed on {}: {e}", msg.channel);
+                    ok = false;
+                }
+            }
+        }
+        if !descriptions.is_empty() {
+            let descriptions = descriptions.join("\n");
+            msg.content = if msg.content.trim().is_empty() {
+                descriptions
+            } else {
+                format!("{}\n\n{descriptions}", msg.content)
+            };
+        }
+        ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::traits::MediaAttachment;
+    use anyhow::Result;
+
+    struct LabelProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for LabelProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            Ok(String::new())
+        }
+
+        async fn describe_image(
+            &self,
+            image: &[u8],
+            mime_type: &str,
+            _prompt: &str,
+            model: &str,
+        ) -> Result<String> {
+            if image == b"broken" {
+                anyhow::bail!("unreadable image");
+            }
+            Ok(format!("{model} saw {mime_type}"))
+        }
+    }
+
+    struct FixtureChannel;
+
+    #[async_trait::async_trait]
+    impl Channel for FixtureChannel {
+        fn name(&self) -> &str {
+            "fixture"
+        }
+
+        async fn send(&self, _message: &str, _recipient: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn download_attachment(&self, attachment: &MediaAttachment) -> Result<Vec<u8>> {
+            Ok(attachment.source.clone().into_bytes())
+        }
+    }
+
+    #[tokio::test]
+    async fn descriptions_follow_the_caption() {
+        let image = |source: &str, kind| MediaAttachment {
+            kind,
+            source: source.into(),
+            mime_type: None,
+            file_name: None,
+            stored_key: None,
+        };
+        let mut msg = ChannelMessage {
+            attachments: vec![
+                image("photo", MediaKind::Image),
+                image("voice", MediaKind::Voice),
+                image("broken", MediaKind::Image),
+            ],
+            ..ChannelMessage::test("fixture", "alice", "what is this?")
+        };
+
+        let service = VisionService::new(
+            Arc::new(LabelProvider),
+            "gpt-4o".into(),
+            "describe".into(),
+            1024,
+        );
+        let ok = service.describe_message(&FixtureChannel, &mut msg).await;
+        assert!(!ok);
+        assert_eq!(
+            msg.content,
+            "what is this?\n\n[Image: gpt-4o saw image/jpeg]"
+        );
+        assert_eq!(msg.attachments.len(), 3);
+    }
+
+    #[test]
+    fn mime_type_is_sniffed_when_missing() {
+        assert_eq!(image_mime_type(Some("image/webp"), b""), "image/webp");
+        assert_eq!(
+            image_mime_type(Some("application/octet-stream"), b"\x89PNG\r\n"),
+            "image/png"
+        );
+        assert_eq!(image_mime_type(None, b"GIF89a"), "image/gif");
+        assert_eq!(image_mime_type(None, b"\xff\xd8\xff"), "image/jpeg");
+    }
+}
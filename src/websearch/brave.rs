@@ -0,0 +1,60 @@
+use super::{SearchBackend, SearchResult, WEB_SEARCH_TIMEOUT_SECS};
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Brave Search API.
+pub struct BraveSearch {
+    client: reqwest::Client,
+    url: String,
+    api_key: Arc<ManagedSecret>,
+}
+
+impl BraveSearch {
+    pub fn new(url: String, api_key: Arc<ManagedSecret>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for BraveSearch {
+    fn name(&self) -> &str {
+        "brave"
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let count = limit.clamp(1, 20).to_string();
+        let response = self
+            .client
+            .get(&self.url)
+            .timeout(Duration::from_secs(WEB_SEARCH_TIMEOUT_SECS))
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", self.api_key.get().await?)
+            .query(&[("q", query), ("count", count.as_str())])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(crate::providers::api_error("Brave Search", response).await);
+        }
+        let body: serde_json::Value = response.json().await?;
+        Ok(body["web"]["results"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .take(limit)
+                    .map(|r| SearchResult {
+                        title: r["title"].as_str().unwrap_or_default().to_string(),
+                        url: r["url"].as_str().unwrap_or_default().to_string(),
+                        snippet: r["description"].as_str().unwrap_or_default().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,180 @@
+mod bing;
+mod brave;
+mod searxng;
+
+pub use bing::BingSearch;
+pub use brave::BraveSearch;
+pub use searxng::SearxngSearch;
+
+use crate::config::schema::WebSearchConfig;
+use crate::security::ManagedSecret;
+use crate::util::truncate_with_ellipsis;
+use anyhow::{bail, Result};
+use std::fmt::Write;
+use std::sync::Arc;
+
+const WEB_SEARCH_TIMEOUT_SECS: u64 = 15;
+/// Longest snippet kept per result in summaries.
+const SNIPPET_MAX_CHARS: usize = 200;
+
+// ── Backend trait ────────────────────────────────────────────────
+
+/// One search hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// Web search backend for the `web_search` tool and the `/search` command.
+#[async_trait::async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Backend name (e.g. "searxng", "brave", "bing")
+    fn name(&self) -> &str;
+
+    /// Up to `limit` results for `query`, best first.
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>>;
+}
+
+/// Numbered list of results with their links, for chat replies and tool output.
+pub fn summarize_results(query: &str, results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return format!("No results for \"{query}\".");
+    }
+    let mut out = format!("Results for \"{query}\":\n");
+    for (i, result) in results.iter().enumerate() {
+        let _ = write!(
+            out,
+            "\n{}. {}\n   {}",
+            i + 1,
+            result.title.trim(),
+            result.url
+        );
+        let snippet = result
+            .snippet
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !snippet.is_empty() {
+            let _ = write!(
+                out,
+                "\n   {}",
+                truncate_with_ellipsis(&snippet, SNIPPET_MAX_CHARS)
+            );
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+/// Query of a `/search` command, or `None` for any other message.
+pub fn search_command_query(content: &str) -> Option<&str> {
+    let content = content.trim_start();
+    let command = content.split_whitespace().next()?;
+    if command.split('@').next()? != "/search" {
+        return None;
+    }
+    Some(content[command.len()..].trim())
+}
+
+// ── Factory ──────────────────────────────────────────────────────
+
+/// Create a search backend from config. Returns `None` when web search is
+/// disabled. `api_key` is the resolved `[web_search] api_key`.
+pub fn create_search_backend(
+    config: &WebSearchConfig,
+    api_key: Option<Arc<ManagedSecret>>,
+) -> Result<Option<Arc<dyn SearchBackend>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let url = |default: &str| config.api_url.clone().unwrap_or_else(|| default.into());
+    let backend: Arc<dyn SearchBackend> = match config.backend.as_str() {
+        "searxng" => {
+            let Some(url) = config.api_url.clone() else {
+                bail!("[web_search] backend \"searxng\" requires api_url");
+            };
+            Arc::new(SearxngSearch::new(url, api_key))
+        }
+        "brave" => {
+            let Some(key) = api_key else {
+                bail!("[web_search] backend \"brave\" requires api_key");
+            };
+            Arc::new(BraveSearch::new(
+                url("https://api.search.brave.com/res/v1/web/search"),
+                key,
+            ))
+        }
+        "bing" => {
+            let Some(key) = api_key else {
+                bail!("[web_search] backend \"bing\" requires api_key");
+            };
+            Arc::new(BingSearch::new(
+                url("https://api.bing.microsoft.com/v7.0/search"),
+                key,
+            ))
+        }
+        other => bail!("Unknown web search backend: {other}"),
+    };
+    Ok(Some(backend))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret() -> Arc<ManagedSecret> {
+        Arc::new(ManagedSecret::fixed("key"))
+    }
+
+    #[test]
+    fn factory_respects_enabled_and_requirements() {
+        let mut config = WebSearchConfig::default();
+        assert!(create_search_backend(&config, None).unwrap().is_none());
+
+        config.enabled = true;
+        config.backend = "searxng".into();
+        assert!(create_search_backend(&config, None).is_err());
+        config.api_url = Some("http://127.0.0.1:8888".into());
+        let backend = create_search_backend(&config, None).unwrap().unwrap();
+        assert_eq!(backend.name(), "searxng");
+
+        for name in ["brave", "bing"] {
+            config.backend = name.into();
+            assert!(create_search_backend(&config, None).is_err());
+            let backend = create_search_backend(&config, Some(secret()))
+                .unwrap()
+                .unwrap();
+            assert_eq!(backend.name(), name);
+        }
+
+        config.backend = "nope".into();
+        assert!(create_search_backend(&config, None).is_err());
+    }
+
+    #[test]
+    fn summary_lists_titles_links_and_snippets() {
+        let results = vec![SearchResult {
+            title: "Rust".into(),
+            url: "https://www.rust-lang.org".into(),
+            snippet: "A language   empowering\neveryone.".into(),
+        }];
+        let summary = summarize_results("rust", &results);
+        assert!(summary.contains("1. Rust\n   https://www.rust-lang.org"));
+        assert!(summary.contains("A language empowering everyone."));
+        assert_eq!(summarize_results("x", &[]), "No results for \"x\".");
+    }
+
+    #[test]
+    fn search_command_parses_query() {
+        assert_eq!(
+            search_command_query("/search rust async"),
+            Some("rust async")
+        );
+        assert_eq!(search_command_query("/search@bot tokio"), Some("tokio"));
+        assert_eq!(search_command_query("/search"), Some(""));
+        assert_eq!(search_command_query("/searching"), None);
+        assert_eq!(search_command_query("what is rust"), None);
+    }
+}
@@ -0,0 +1,98 @@
+use super::{SearchBackend, SearchResult, WEB_SEARCH_TIMEOUT_SECS};
+use crate::security::ManagedSecret;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Self-hosted SearXNG instance via its JSON API (`format=json` must be
+/// enabled in the instance's `settings.yml`).
+pub struct SearxngSearch {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<Arc<ManagedSecret>>,
+}
+
+impl SearxngSearch {
+    pub fn new(base_url: String, api_key: Option<Arc<ManagedSecret>>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for SearxngSearch {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let mut request = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .timeout(Duration::from_secs(WEB_SEARCH_TIMEOUT_SECS))
+            .query(&[("q", query), ("format", "json")]);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key.get().await?);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(crate::providers::api_error("SearXNG", response).await);
+        }
+        let body: serde_json::Value = response.json().await?;
+        Ok(body["results"]
+            .as_array()
+            .map(|results| {
+                results
+                    .iter()
+                    .take(limit)
+                    .map(|r| SearchResult {
+                        title: r["title"].as_str().unwrap_or_default().to_string(),
+                        url: r["url"].as_str().unwrap_or_default().to_string(),
+                        snippet: r["content"].as_str().unwrap_or_default().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn parses_json_results() {
+        use axum::{extract::Query, routing::get, Json, Router};
+        use std::collections::HashMap;
+
+        let app = Router::new().route(
+            "/search",
+            get(|Query(q): Query<HashMap<String, String>>| async move {
+                assert_eq!(q["format"], "json");
+                Json(serde_json::json!({
+                    "results": [
+                        { "title": q["q"], "url": "https://a.example", "content": "first" },
+                        { "title": "B", "url": "https://b.example", "content": "second" }
+                    ]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let backend = SearxngSearch::new(format!("http://{addr}/"), None);
+        let results = backend.search("zeroclaw", 1).await.unwrap();
+        assert_eq!(
+            results,
+            vec![SearchResult {
+                title: "zeroclaw".into(),
+                url: "https://a.example".into(),
+                snippet: "first".into(),
+            }]
+        );
+    }
+}